@@ -51,6 +51,12 @@ fn main() {
                 omniscient,
                 new_game,
                 mute,
+                balance,
+                #[cfg(feature = "leaderboard")]
+                leaderboard,
+                presence,
+                voting,
+                script,
             },
         col_encode_choice,
     } = Args::parser().with_help_default().parse_env_or_exit();
@@ -72,6 +78,15 @@ fn main() {
         omniscient,
         new_game,
         mute,
+        balance,
+        audio: true,
+        share_seed: None,
+        export_file: None,
+        #[cfg(feature = "leaderboard")]
+        leaderboard,
+        presence,
+        voting,
+        script,
     });
     use ColEncodeChoice as C;
     match col_encode_choice {
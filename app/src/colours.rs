@@ -1,4 +1,6 @@
-use rgb_int::Rgb24;
+use chargrid::core::Tint;
+use rgb_int::{Rgb24, Rgba32};
+use serde::{Deserialize, Serialize};
 
 pub const FLOOR: Rgb24 = Rgb24::new(108, 0, 162);
 pub const FLOOR_BLOODY: Rgb24 = Rgb24::new(255, 0, 0);
@@ -18,17 +20,41 @@ pub const SHOP_BG: Rgb24 = Rgb24::hex(0xcc00cc);
 pub const SHOP_ITEM: Rgb24 = Rgb24::hex(0xffff00);
 pub const SHOP_GUN: Rgb24 = Rgb24::hex(0x00ff00);
 pub const SHOP_ORGAN_CLINIC: Rgb24 = Rgb24::hex(0x00ffff);
+pub const SHOP_ORGAN_TRADER: Rgb24 = Rgb24::hex(0xff8000);
+pub const SHOP_MERCHANT: Rgb24 = Rgb24::hex(0xff66cc);
+pub const STASH: Rgb24 = Rgb24::hex(0x8080ff);
+pub const BOUNTY_HUNTER: Rgb24 = Rgb24::hex(0xcc0033);
+pub const MERCENARY: Rgb24 = Rgb24::hex(0xff9999);
 pub const NORMAL_MODE: Rgb24 = Rgb24::new(0, 255, 255);
 pub const AIMING_MODE: Rgb24 = Rgb24::new(255, 0, 0);
+pub const HIDDEN_MODE: Rgb24 = Rgb24::new(128, 96, 255);
+pub const AIMING_OBSTRUCTION: Rgb24 = Rgb24::new(255, 255, 0);
 pub const HEALTH: Rgb24 = Rgb24::hex(0x800000);
 pub const OXYGEN: Rgb24 = Rgb24::hex(0x234790);
 pub const FOOD: Rgb24 = Rgb24::hex(0x997300);
 pub const POISON: Rgb24 = Rgb24::hex(0x336600);
+pub const ACID: Rgb24 = Rgb24::hex(0xccff00);
+pub const ELECTRIC: Rgb24 = Rgb24::hex(0x33ccff);
+pub const WATER: Rgb24 = Rgb24::hex(0x0a2a6b);
+pub const WATER_FOREGROUND: Rgb24 = Rgb24::hex(0x3d7eff);
+pub const BRIDGE: Rgb24 = Rgb24::hex(0x8c5a2b);
+pub const WINDOW: Rgb24 = Rgb24::hex(0x99e6ff);
+pub const OFFICES_WALL: Rgb24 = Rgb24::hex(0xb3b3cc);
+pub const OFFICES_FLOOR: Rgb24 = Rgb24::hex(0x4d4d80);
+pub const HOSPITAL_WALL: Rgb24 = Rgb24::hex(0xe6f2ff);
+pub const HOSPITAL_FLOOR: Rgb24 = Rgb24::hex(0x4d9999);
+pub const SEWERS_WALL: Rgb24 = Rgb24::hex(0x6b8e23);
+pub const SEWERS_FLOOR: Rgb24 = Rgb24::hex(0x3d5c3d);
+pub const LAB_WALL: Rgb24 = Rgb24::hex(0x00e6b8);
+pub const LAB_FLOOR: Rgb24 = Rgb24::hex(0x1a6680);
 pub const RADIATION: Rgb24 = Rgb24::hex(0x009973);
 pub const GLOWER: Rgb24 = Rgb24::hex(0x009973);
 pub const VENTER: Rgb24 = Rgb24::hex(0xcccccc);
 pub const POWER: Rgb24 = Rgb24::hex(0xff00ff);
 pub const SATIATION: Rgb24 = Rgb24::hex(0xff0000);
+pub const COLD: Rgb24 = Rgb24::hex(0x3399cc);
+pub const HOT: Rgb24 = Rgb24::hex(0xcc4400);
+pub const TEMPERATE: Rgb24 = Rgb24::hex(0x668066);
 pub const MONEY: Rgb24 = Rgb24::hex(0xffff66);
 pub const STIMPACK: Rgb24 = HEALTH.saturating_scalar_mul_div(2, 1);
 pub const ANTIDOTE: Rgb24 = POISON;
@@ -40,3 +66,148 @@ pub const ORGAN_CONTAINER: Rgb24 = Rgb24::hex(0x00e6e6);
 pub const PISTOL: Rgb24 = Rgb24::hex(0xbfbfbf);
 pub const SHOTGUN: Rgb24 = Rgb24::hex(0xb35900);
 pub const ROCKET_LAUNCHER: Rgb24 = Rgb24::hex(0x00e600);
+pub const SNIPER_RIFLE: Rgb24 = Rgb24::hex(0xcc99ff);
+pub const DRONE: Rgb24 = Rgb24::hex(0x66ccff);
+pub const TURRET: Rgb24 = Rgb24::hex(0x99ccff);
+pub const SCANNER: Rgb24 = Rgb24::hex(0x66ffcc);
+pub const GEIGER_COUNTER: Rgb24 = Rgb24::hex(0xffcc00);
+pub const SCHEMATICS: Rgb24 = Rgb24::hex(0x3399ff);
+pub const PLANK: Rgb24 = Rgb24::hex(0xa0703c);
+pub const ANESTHETIC: Rgb24 = Rgb24::hex(0xb399ff);
+pub const FIELD_SURGERY_KIT: Rgb24 = Rgb24::hex(0xff6680);
+pub const IMMUNOSUPPRESSANT: Rgb24 = Rgb24::hex(0x99e6b3);
+pub const BANDAGE: Rgb24 = Rgb24::hex(0xf2f2e6);
+pub const ANTIPSYCHOTIC: Rgb24 = Rgb24::hex(0xcc66ff);
+pub const EMP_GRENADE: Rgb24 = Rgb24::hex(0x3f7fff);
+pub const SMOKE_GRENADE: Rgb24 = Rgb24::hex(0xaaaaaa);
+pub const SMOKE: Rgb24 = Rgb24::hex(0x888888);
+pub const GAS_MASK: Rgb24 = Rgb24::hex(0x66cc66);
+pub const HAZARD_SUIT: Rgb24 = Rgb24::hex(0xffdd00);
+pub const SENTINEL: Rgb24 = Rgb24::hex(0xb3b3cc);
+pub const UNIDENTIFIED: Rgb24 = Rgb24::hex(0xbbbbbb);
+pub const GUARD: Rgb24 = Rgb24::hex(0x3366ff);
+pub const CIVILIAN: Rgb24 = Rgb24::hex(0xccbb99);
+pub const ALARM_PANEL: Rgb24 = Rgb24::hex(0xff3333);
+pub const GENERATOR: Rgb24 = Rgb24::hex(0xffaa00);
+pub const EXPLOSIVE_BARREL: Rgb24 = Rgb24::hex(0xff4400);
+pub const TERMINAL: Rgb24 = Rgb24::hex(0x33ff66);
+pub const READABLE: Rgb24 = Rgb24::hex(0xf2e6c9);
+pub const ABANDONED_CAR: Rgb24 = Rgb24::hex(0x8899aa);
+pub const VENDING_MACHINE: Rgb24 = Rgb24::hex(0x33ccff);
+pub const GRAFFITI: Rgb24 = Rgb24::hex(0xff33cc);
+pub const BLOODSTAIN: Rgb24 = Rgb24::hex(0x800000);
+pub const REMAINS: Rgb24 = Rgb24::hex(0xd9d2bf);
+pub const RAW_MEAT: Rgb24 = Rgb24::hex(0xcc3344);
+pub const COOKED_MEAT: Rgb24 = Rgb24::hex(0xb36b2d);
+pub const ROTTEN_MEAT: Rgb24 = Rgb24::hex(0x6b7a2e);
+pub const MARKET_REPORT: Rgb24 = Rgb24::hex(0x66cc66);
+
+/// Colour schemes for players who have trouble telling apart some of the colours above. Rather
+/// than replacing individual colours, a palette is applied to everything drawn, so colours which
+/// have been blended with light or faded out are adjusted too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+type Matrix = [[f32; 3]; 3];
+
+const RGB_TO_LMS: Matrix = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+const LMS_TO_RGB: Matrix = [
+    [0.08094445, -0.13050441, 0.11672107],
+    [-0.010248534, 0.05401933, -0.11361471],
+    [-0.00036529695, -0.004121615, 0.6935114],
+];
+
+// How each kind of colour blindness sees colours, in LMS colour space
+const PROTANOPIA: Matrix = [[0., 2.02344, -2.52581], [0., 1., 0.], [0., 0., 1.]];
+const DEUTERANOPIA: Matrix = [[1., 0., 0.], [0.494207, 0., 1.24827], [0., 0., 1.]];
+const TRITANOPIA: Matrix = [[1., 0., 0.], [0., 1., 0.], [-0.395913, 0.801109, 0.]];
+
+// Moves the information lost to colour blindness into channels which can still be seen
+const ERROR_SHIFT: Matrix = [[0., 0., 0.], [0.7, 1., 0.], [0.7, 0., 1.]];
+
+// Amount by which the high contrast palette pushes each channel away from the middle
+const HIGH_CONTRAST_FACTOR: f32 = 1.6;
+
+fn mul(m: &Matrix, v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+fn to_channel(c: f32) -> u8 {
+    c.round().clamp(0., 255.) as u8
+}
+
+// Daltonize a colour: work out how it appears with the given colour blindness, and shift the
+// difference into colours which are distinguishable with it
+fn daltonize(colour: Rgb24, simulation: &Matrix) -> Rgb24 {
+    let rgb = [colour.r as f32, colour.g as f32, colour.b as f32];
+    let simulated = mul(&LMS_TO_RGB, mul(simulation, mul(&RGB_TO_LMS, rgb)));
+    let error = [
+        rgb[0] - simulated[0],
+        rgb[1] - simulated[1],
+        rgb[2] - simulated[2],
+    ];
+    let shift = mul(&ERROR_SHIFT, error);
+    Rgb24::new(
+        to_channel(rgb[0] + shift[0]),
+        to_channel(rgb[1] + shift[1]),
+        to_channel(rgb[2] + shift[2]),
+    )
+}
+
+fn high_contrast(colour: Rgb24) -> Rgb24 {
+    let stretch = |c: u8| to_channel((c as f32 - 128.) * HIGH_CONTRAST_FACTOR + 128.);
+    Rgb24::new(stretch(colour.r), stretch(colour.g), stretch(colour.b))
+}
+
+impl Palette {
+    pub const ALL: [Self; 5] = [
+        Self::Standard,
+        Self::Deuteranopia,
+        Self::Protanopia,
+        Self::Tritanopia,
+        Self::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Protanopia => "Protanopia",
+            Self::Tritanopia => "Tritanopia",
+            Self::HighContrast => "High Contrast",
+        }
+    }
+
+    pub fn apply(self, colour: Rgb24) -> Rgb24 {
+        match self {
+            Self::Standard => colour,
+            Self::Deuteranopia => daltonize(colour, &DEUTERANOPIA),
+            Self::Protanopia => daltonize(colour, &PROTANOPIA),
+            Self::Tritanopia => daltonize(colour, &TRITANOPIA),
+            Self::HighContrast => high_contrast(colour),
+        }
+    }
+}
+
+impl Tint for Palette {
+    fn tint(&self, rgba32: Rgba32) -> Rgba32 {
+        let Rgba32 { r, g, b, a } = rgba32;
+        self.apply(Rgb24::new(r, g, b)).to_rgba32(a)
+    }
+}
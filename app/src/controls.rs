@@ -4,7 +4,7 @@ use maplit::btreemap;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AppInput {
     Direction(CardinalDirection),
     Wait,
@@ -16,7 +16,96 @@ pub enum AppInput {
     ApplyItem,
     UnequipItem,
     Reload,
+    Overwatch,
     ViewOrgans,
+    CharacterSheet,
+    ToggleDroneControl,
+    ToggleSprint,
+    OverviewMap,
+    ToggleMute,
+    VolumeUp,
+    VolumeDown,
+    ExportMap,
+    ToggleObjectives,
+    ScriptActions,
+}
+
+impl AppInput {
+    pub const ALL: [Self; 25] = [
+        Self::Direction(CardinalDirection::North),
+        Self::Direction(CardinalDirection::East),
+        Self::Direction(CardinalDirection::South),
+        Self::Direction(CardinalDirection::West),
+        Self::Wait,
+        Self::FireEquipped,
+        Self::FireBody,
+        Self::MessageLog,
+        Self::Get,
+        Self::DropItem,
+        Self::ApplyItem,
+        Self::UnequipItem,
+        Self::Reload,
+        Self::Overwatch,
+        Self::ViewOrgans,
+        Self::CharacterSheet,
+        Self::ToggleDroneControl,
+        Self::ToggleSprint,
+        Self::OverviewMap,
+        Self::ToggleMute,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::ExportMap,
+        Self::ToggleObjectives,
+        Self::ScriptActions,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Direction(CardinalDirection::North) => "Move North",
+            Self::Direction(CardinalDirection::East) => "Move East",
+            Self::Direction(CardinalDirection::South) => "Move South",
+            Self::Direction(CardinalDirection::West) => "Move West",
+            Self::Wait => "Wait",
+            Self::FireEquipped => "Fire Equipped Weapon",
+            Self::FireBody => "Fire Cybernetic Organ",
+            Self::MessageLog => "Message Log",
+            Self::Get => "Pick Up",
+            Self::DropItem => "Drop Item",
+            Self::ApplyItem => "Apply Item",
+            Self::UnequipItem => "Unequip Item",
+            Self::Reload => "Reload",
+            Self::Overwatch => "Overwatch",
+            Self::ViewOrgans => "View Organs",
+            Self::CharacterSheet => "Character Sheet",
+            Self::ToggleDroneControl => "Toggle Drone Control",
+            Self::ToggleSprint => "Toggle Sprint",
+            Self::OverviewMap => "Overview Map",
+            Self::ToggleMute => "Toggle Mute",
+            Self::VolumeUp => "Volume Up",
+            Self::VolumeDown => "Volume Down",
+            Self::ExportMap => "Export Map",
+            Self::ToggleObjectives => "Toggle Objectives",
+            Self::ScriptActions => "Script Actions",
+        }
+    }
+}
+
+/// A human-readable name for a key, for displaying bindings
+pub fn key_name(key: KeyboardInput) -> String {
+    match key {
+        KeyboardInput::Char(' ') => "space".to_string(),
+        KeyboardInput::Char(ch) => ch.to_string(),
+        KeyboardInput::Function(n) => format!("F{}", n),
+        KeyboardInput::Up => "↑".to_string(),
+        KeyboardInput::Down => "↓".to_string(),
+        KeyboardInput::Left => "←".to_string(),
+        KeyboardInput::Right => "→".to_string(),
+        KeyboardInput::Home => "home".to_string(),
+        KeyboardInput::End => "end".to_string(),
+        KeyboardInput::PageUp => "page up".to_string(),
+        KeyboardInput::PageDown => "page down".to_string(),
+        KeyboardInput::Delete => "delete".to_string(),
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -40,7 +129,19 @@ impl Default for Controls {
             KeyboardInput::Char('a') => AppInput::ApplyItem,
             KeyboardInput::Char('u') => AppInput::UnequipItem,
             KeyboardInput::Char('r') => AppInput::Reload,
+            KeyboardInput::Char('w') => AppInput::Overwatch,
             KeyboardInput::Char('o') => AppInput::ViewOrgans,
+            KeyboardInput::Char('@') => AppInput::CharacterSheet,
+            KeyboardInput::Char('x') => AppInput::ToggleDroneControl,
+            KeyboardInput::Char('s') => AppInput::ToggleSprint,
+            KeyboardInput::Char('v') => AppInput::OverviewMap,
+            KeyboardInput::Char('M') => AppInput::ToggleMute,
+            KeyboardInput::Char('+') => AppInput::VolumeUp,
+            KeyboardInput::Char('=') => AppInput::VolumeUp,
+            KeyboardInput::Char('-') => AppInput::VolumeDown,
+            KeyboardInput::Char('P') => AppInput::ExportMap,
+            KeyboardInput::Char('O') => AppInput::ToggleObjectives,
+            KeyboardInput::Char('z') => AppInput::ScriptActions,
         ];
         Self { keys }
     }
@@ -52,4 +153,25 @@ impl Controls {
             Input::Mouse(_) => None,
         }
     }
+
+    /// The keys bound to an action
+    pub fn keys_for(&self, app_input: AppInput) -> Vec<KeyboardInput> {
+        self.keys
+            .iter()
+            .filter(|&(_, &bound)| bound == app_input)
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    /// The action a key is bound to, if any
+    pub fn action_for(&self, key: KeyboardInput) -> Option<AppInput> {
+        self.keys.get(&key).cloned()
+    }
+
+    /// Make `key` the only key bound to an action. Any other action the key was bound to is
+    /// left without it.
+    pub fn bind(&mut self, key: KeyboardInput, app_input: AppInput) {
+        self.keys.retain(|_, &mut bound| bound != app_input);
+        self.keys.insert(key, app_input);
+    }
 }
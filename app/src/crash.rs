@@ -0,0 +1,41 @@
+use general_storage_static::StaticStorage;
+use std::cell::Cell;
+use std::panic;
+
+/// Storage key of the report written when the game crashes
+pub const CRASH_REPORT_KEY: &str = "crash-report.txt";
+
+thread_local! {
+    static RNG_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Remember the seed of the game being played so it can be included in crash reports
+pub fn set_rng_seed(rng_seed: u64) {
+    let _ = RNG_SEED.try_with(|seed| seed.set(Some(rng_seed)));
+}
+
+/// Install a panic hook which writes a report of the game's most recent state to storage before
+/// running the existing panic hook. The storage used by the rest of the app may be borrowed when
+/// the panic happens, so `open_storage` is used to open it separately. It shouldn't panic, so
+/// returns `None` if storage can't be opened.
+pub fn install_crash_report_hook<F>(open_storage: F)
+where
+    F: Fn() -> Option<StaticStorage> + Send + Sync + 'static,
+{
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if let Some(game_report) = game::diagnostics::latest_report() {
+            let rng_seed = RNG_SEED
+                .try_with(Cell::get)
+                .ok()
+                .flatten()
+                .map(|seed| seed.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let report = format!("{}\n\nrng seed: {}\n{}", info, rng_seed, game_report);
+            if let Some(mut storage) = open_storage() {
+                let _ = storage.store_raw(CRASH_REPORT_KEY, report);
+            }
+        }
+        previous_hook(info);
+    }));
+}
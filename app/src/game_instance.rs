@@ -1,21 +1,202 @@
-use crate::colours;
+use crate::{colours, crash};
 use chargrid::{
     prelude::*,
     text::{self, Text},
 };
 use game::{
     witness::{self, Game, RunningGame},
-    ActionError, CellVisibility, Config, Item, Layer, LayerTable, Message, Meter, NpcType, Organ,
-    OrganTrait, OrganTraits, OrganType, Tile, Victory, VisibleEntity,
+    ActionError, Background, CardinalDirection, CellVisibility, Config, DamageModifier, DamageRoll,
+    DocumentKind, Item, ItemAppearance, Layer, LayerTable, LevelTheme, Message, Meter, MinimapCell,
+    NpcType, Objective, Organ, OrganTrait, OrganTraits, OrganType, Perk, Prop, Skill, Tile,
+    TutorialStep, Victory, VisibleEntity,
 };
-use rand::Rng;
+use rand::SeedableRng;
+use rand_isaac::Isaac64Rng;
 use rgb_int::Rgb24;
 use serde::{Deserialize, Serialize};
 
+/// The number of cells of the level summarized by each cell of the minimap
+const MINIMAP_SCALE: Size = Size::new_u16(4, 5);
+
+/// The size of the area of the screen where the level is drawn
+pub const VIEWPORT_SIZE: Size = Size::new_u16(50, 25);
+
+/// Which characters to draw the level with. Some terminals and fonts don't have the non-ASCII
+/// characters used by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GlyphSet {
+    #[default]
+    Unicode,
+    Ascii,
+}
+
+impl GlyphSet {
+    pub const ALL: [Self; 2] = [Self::Unicode, Self::Ascii];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unicode => "Unicode",
+            Self::Ascii => "ASCII",
+        }
+    }
+}
+
+/// Settings from the options menu which change how the game is drawn
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub particle_density_percent: u32,
+    pub glyph_set: GlyphSet,
+    pub combat_log: CombatLog,
+}
+
+/// How much detail the message log goes into about combat. Verbose messages show the range each
+/// hit's damage was rolled from and anything which modified it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CombatLog {
+    #[default]
+    Brief,
+    Verbose,
+}
+
+impl CombatLog {
+    pub const ALL: [Self; 2] = [Self::Brief, Self::Verbose];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Brief => "Brief",
+            Self::Verbose => "Verbose",
+        }
+    }
+}
+
+fn damage_modifier_name(damage_modifier: DamageModifier) -> &'static str {
+    match damage_modifier {
+        DamageModifier::Claws => "claws",
+    }
+}
+
+// e.g. "7" or, when verbose, "7 (3-5, +3 claws)"
+fn damage_string(damage: &DamageRoll, combat_log: CombatLog) -> String {
+    let total = damage.total();
+    if combat_log == CombatLog::Brief {
+        return total.to_string();
+    }
+    let (min, max) = (*damage.range.start(), *damage.range.end());
+    if min == max && damage.modifiers.is_empty() {
+        return total.to_string();
+    }
+    let mut details = vec![if min == max {
+        min.to_string()
+    } else {
+        format!("{}-{}", min, max)
+    }];
+    for &(modifier, amount) in &damage.modifiers {
+        details.push(format!("{:+} {}", amount, damage_modifier_name(modifier)));
+    }
+    format!("{} ({})", total, details.join(", "))
+}
+
+// The character to draw in place of a non-ASCII character when only ASCII is available
+fn ascii_glyph(ch: char) -> char {
+    match ch {
+        '≈' => ':',
+        '▓' | '▒' => '8',
+        'Ω' => '^',
+        '●' => 'o',
+        '¤' => '0',
+        '■' => '|',
+        'ɸ' => ';',
+        'θ' => 'U',
+        '▬' => '=',
+        'π' => 'n',
+        '≡' => 'E',
+        '§' => '?',
+        ch if ch.is_ascii() => ch,
+        _ => '?',
+    }
+}
+
+/// Width of the panel to the right of the map, including the border between them
+const SIDEBAR_WIDTH: u32 = 30;
+
+/// Height of the message log below the map, including the border between them
+const MESSAGE_LOG_HEIGHT: u32 = 5;
+
+// Heights of the panels stacked down the sidebar, each including the border along its top
+const SIDEBAR_INFO_HEIGHT: u32 = 7;
+const SIDEBAR_STATS_HEIGHT: u32 = 9;
+const SIDEBAR_MINIMAP_HEIGHT: u32 = 4;
+const SIDEBAR_MODE_HEIGHT: u32 = 2;
+const SIDEBAR_DESCRIPTION_MIN_HEIGHT: u32 = 8;
+
+/// The row of the border along the top of each panel in the sidebar
+struct SidebarLayout {
+    info: i32,
+    stats: i32,
+    minimap: i32,
+    mode: i32,
+    description: i32,
+}
+
+impl SidebarLayout {
+    /// The description is the last panel and takes whatever height remains on the screen, but if
+    /// the screen is too short to fit the panels above it then it's moved up to keep it visible
+    fn new(screen_size: Size) -> Self {
+        let info = 0;
+        let stats = info + SIDEBAR_INFO_HEIGHT;
+        let minimap = stats + SIDEBAR_STATS_HEIGHT;
+        let mode = minimap + SIDEBAR_MINIMAP_HEIGHT;
+        let description = (mode + SIDEBAR_MODE_HEIGHT).min(
+            screen_size
+                .height()
+                .saturating_sub(SIDEBAR_DESCRIPTION_MIN_HEIGHT),
+        );
+        Self {
+            info: info as i32,
+            stats: stats as i32,
+            minimap: minimap as i32,
+            mode: mode as i32,
+            description: description as i32,
+        }
+    }
+}
+
+// The part of the screen left of the sidebar and above the message log. The viewport is centred
+// within it when the window is larger than the viewport and panels combined.
+fn map_area(screen_size: Size) -> Size {
+    Size::new(
+        screen_size
+            .width()
+            .saturating_sub(SIDEBAR_WIDTH)
+            .max(VIEWPORT_SIZE.width()),
+        screen_size
+            .height()
+            .saturating_sub(MESSAGE_LOG_HEIGHT)
+            .max(VIEWPORT_SIZE.height()),
+    )
+}
+
+/// The position on a screen of the given size of the top-left corner of the viewport
+pub fn map_origin(screen_size: Size) -> Coord {
+    let map_area = map_area(screen_size);
+    Coord::new(
+        (map_area.width() - VIEWPORT_SIZE.width()) as i32 / 2,
+        (map_area.height() - VIEWPORT_SIZE.height()) as i32 / 2,
+    )
+}
+
+/// The camera scrolls when the player comes within this many cells of the edge of the viewport
+const CAMERA_SCROLL_MARGIN: i32 = 8;
+
+/// The player is shown as hidden when npcs would only notice them within this percentage of the
+/// distance they can see
+const HIDDEN_DETECTABILITY_PERCENT: u32 = 50;
+
 #[derive(Clone, Copy, Debug)]
 pub enum Mode {
     Normal,
     Aiming,
+    Throwing,
 }
 
 #[derive(Clone, Copy)]
@@ -35,6 +216,26 @@ impl Tint for LightBlend {
 
 pub struct GameInstance {
     pub game: Game,
+    /// The seed this game was generated from, so the same game can be played again
+    pub rng_seed: u64,
+    /// The coordinate of the level drawn in the top-left corner of the viewport
+    camera: Coord,
+}
+
+// Scroll the camera along a single axis so that `focus` is kept away from the edges of the viewport
+fn scroll_camera_axis(camera: i32, focus: i32, viewport: i32, world: i32) -> i32 {
+    if world <= viewport {
+        return 0;
+    }
+    let margin = CAMERA_SCROLL_MARGIN.min(viewport / 2);
+    let mut camera = camera;
+    if focus - camera < margin {
+        camera = focus - margin;
+    }
+    if focus - camera >= viewport - margin {
+        camera = focus - viewport + margin + 1;
+    }
+    camera.clamp(0, world - viewport)
 }
 
 fn visible_entity_on_top(layers: &LayerTable<VisibleEntity>) -> Option<(&VisibleEntity, Layer)> {
@@ -91,6 +292,59 @@ fn render_meter(meter: Meter, colour: Rgb24, ctx: Ctx, fb: &mut FrameBuffer) {
     StyledString { string, style }.render(&(), ctx.add_x(centre_offset as i32), fb);
 }
 
+fn temperature_label(temperature: Meter) -> &'static str {
+    let (current, max) = temperature.current_and_max();
+    let neutral = max / 2;
+    if current == 0 {
+        "Freezing"
+    } else if current == max {
+        "Overheating"
+    } else if current < neutral / 2 {
+        "Cold"
+    } else if current < neutral {
+        "Cool"
+    } else if current == neutral {
+        "Normal"
+    } else if current < max - (neutral / 2) {
+        "Warm"
+    } else {
+        "Hot"
+    }
+}
+
+/// Temperature is comfortable in the middle of its range, so rather than showing numbers the
+/// meter is labelled and coloured by which extreme the player is closer to
+fn render_temperature_meter(temperature: Meter, ctx: Ctx, fb: &mut FrameBuffer) {
+    use text::*;
+    let width = 15;
+    let (current, max) = temperature.current_and_max();
+    let neutral = max / 2;
+    let string = temperature_label(temperature);
+    let colour = if current < neutral {
+        colours::COLD
+    } else if current > neutral {
+        colours::HOT
+    } else {
+        colours::TEMPERATE
+    };
+    let style = Style::plain_text()
+        .with_bold(true)
+        .with_foreground(Rgb24::new_grey(255).to_rgba32(187));
+    let centre_offset = (width / 2) - string.len().div_ceil(2);
+    let filled_width = ((current * width as u32) / max.max(1)).max(1);
+    for i in 0..width {
+        let coord = Coord::new(i as i32, 0);
+        let alpha = if i < filled_width as usize { 255 } else { 63 };
+        let rc = RenderCell::default().with_background(colour.to_rgba32(alpha));
+        fb.set_cell_relative_to_ctx(ctx, coord, 0, rc);
+    }
+    StyledString {
+        string: string.to_string(),
+        style,
+    }
+    .render(&(), ctx.add_x(centre_offset as i32), fb);
+}
+
 fn render_meter_disabled(ctx: Ctx, fb: &mut FrameBuffer) {
     use text::*;
     let width = 15;
@@ -108,19 +362,116 @@ fn render_meter_disabled(ctx: Ctx, fb: &mut FrameBuffer) {
 }
 
 impl GameInstance {
-    pub fn new<R: Rng>(
+    pub fn new(
         config: &Config,
+        background: Background,
         victories: Vec<Victory>,
-        rng: &mut R,
+        rng_seed: u64,
     ) -> (Self, witness::Running) {
-        let (game, running) = witness::new_game(config, victories, rng);
-        (GameInstance { game }, running)
+        let mut rng = Isaac64Rng::seed_from_u64(rng_seed);
+        let (game, running) = witness::new_game(config, background, victories, &mut rng);
+        crash::set_rng_seed(rng_seed);
+        let mut instance = GameInstance {
+            game,
+            rng_seed,
+            camera: Coord::new(0, 0),
+        };
+        instance.update_camera();
+        (instance, running)
+    }
+
+    pub fn new_tutorial(config: &Config, rng_seed: u64) -> (Self, witness::Running) {
+        let mut rng = Isaac64Rng::seed_from_u64(rng_seed);
+        let (game, running) = witness::new_tutorial(config, &mut rng);
+        crash::set_rng_seed(rng_seed);
+        let mut instance = GameInstance {
+            game,
+            rng_seed,
+            camera: Coord::new(0, 0),
+        };
+        instance.update_camera();
+        (instance, running)
     }
 
     pub fn into_storable(self, running: witness::Running) -> GameInstanceStorable {
-        let Self { game } = self;
+        let Self {
+            game,
+            rng_seed,
+            camera,
+        } = self;
         let running_game = game.into_running_game(running);
-        GameInstanceStorable { running_game }
+        GameInstanceStorable {
+            running_game,
+            rng_seed,
+            camera,
+        }
+    }
+
+    // The camera position that keeps `focus` in view, scrolling from the current camera position
+    fn camera_following(&self, focus: Coord) -> Coord {
+        let viewport = VIEWPORT_SIZE.to_coord().unwrap();
+        let world = self.game.inner_ref().world_size().to_coord().unwrap();
+        Coord::new(
+            scroll_camera_axis(self.camera.x, focus.x, viewport.x, world.x),
+            scroll_camera_axis(self.camera.y, focus.y, viewport.y, world.y),
+        )
+    }
+
+    /// Scroll the camera to follow the player (or their drone while it's being controlled)
+    pub fn update_camera(&mut self) {
+        let game = self.game.inner_ref();
+        let focus = if game.is_controlling_drone() {
+            game.drone_coord().unwrap_or_else(|| game.player_coord())
+        } else {
+            game.player_coord()
+        };
+        self.camera = self.camera_following(focus);
+    }
+
+    /// The coordinate of the level drawn in the top-left corner of the overview map. The
+    /// overview scrolls to keep the cursor in view on levels larger than the viewport.
+    pub fn overview_origin(&self, cursor: Option<Coord>) -> Coord {
+        self.camera_following(cursor.unwrap_or_else(|| self.game.inner_ref().player_coord()))
+    }
+
+    /// Converts a coordinate relative to the top-left corner of the viewport into a coordinate
+    /// in the level, if it refers to a part of the level that's in view
+    pub fn screen_to_world(&self, screen_coord: Coord) -> Option<Coord> {
+        let coord = screen_coord + self.camera;
+        if screen_coord.is_valid(VIEWPORT_SIZE)
+            && coord.is_valid(self.game.inner_ref().world_size())
+        {
+            Some(coord)
+        } else {
+            None
+        }
+    }
+
+    /// Converts a coordinate in the level into a coordinate relative to the top-left corner of
+    /// the viewport
+    pub fn world_to_screen(&self, coord: Coord) -> Coord {
+        coord - self.camera
+    }
+
+    /// Returns true if the given coordinate in the level is currently in view
+    pub fn is_in_view(&self, coord: Coord) -> bool {
+        self.screen_to_world(self.world_to_screen(coord)).is_some()
+    }
+
+    // Walls and floors are coloured according to the theme of the current level
+    fn apply_level_theme(theme: LevelTheme, tile: Tile, render_cell: RenderCell) -> RenderCell {
+        let (wall, floor) = match theme {
+            LevelTheme::Surface => return render_cell,
+            LevelTheme::Offices => (colours::OFFICES_WALL, colours::OFFICES_FLOOR),
+            LevelTheme::Hospital => (colours::HOSPITAL_WALL, colours::HOSPITAL_FLOOR),
+            LevelTheme::Sewers => (colours::SEWERS_WALL, colours::SEWERS_FLOOR),
+            LevelTheme::Lab => (colours::LAB_WALL, colours::LAB_FLOOR),
+        };
+        match tile {
+            Tile::Wall => render_cell.with_foreground(wall.to_rgba32(255)),
+            Tile::Floor => render_cell.with_foreground(floor.to_rgba32(255)),
+            _ => render_cell,
+        }
     }
 
     fn layer_to_depth(layer: Layer) -> i8 {
@@ -132,396 +483,616 @@ impl GameInstance {
         }
     }
 
-    fn tile_to_render_cell(tile: Tile) -> RenderCell {
+    fn tile_to_render_cell(tile: Tile, glyph_set: GlyphSet) -> RenderCell {
+        let render_cell = Self::tile_to_unicode_render_cell(tile);
+        match glyph_set {
+            GlyphSet::Unicode => render_cell,
+            GlyphSet::Ascii => RenderCell {
+                character: render_cell.character.map(ascii_glyph),
+                ..render_cell
+            },
+        }
+    }
+
+    fn tile_to_unicode_render_cell(tile: Tile) -> RenderCell {
         match tile {
-            Tile::Player => {
-                return RenderCell {
-                    character: Some('@'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::DeadPlayer => {
-                return RenderCell {
-                    character: Some('@'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new(255, 0, 0, 255)),
-                };
-            }
-            Tile::Street => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(false)
-                        .with_foreground(Rgba32::new_grey(127)),
-                };
-            }
-            Tile::Alley => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(false)
-                        .with_foreground(Rgba32::new_grey(127)),
-                };
-            }
-            Tile::Footpath => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::FLOOR.to_rgba32(255)),
-                };
-            }
-            Tile::Floor => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::FLOOR.to_rgba32(255)),
-                };
-            }
-            Tile::FloorBloody => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::FLOOR_BLOODY.to_rgba32(255)),
-                };
-            }
-            Tile::FloorPoison => {
-                return RenderCell {
-                    character: Some('.'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::POISON.to_rgba32(255))
-                        .with_background(
-                            colours::POISON
-                                .to_rgba32(255)
-                                .saturating_scalar_mul_div(1, 2),
-                        ),
-                };
-            }
-            Tile::Wall => {
-                return RenderCell {
-                    character: Some('#'),
-                    style: Style::new()
-                        .with_bold(false)
-                        .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255)),
-                };
-            }
-            Tile::Debris => {
-                return RenderCell {
-                    character: Some('%'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::DebrisBurning => {
-                return RenderCell {
-                    character: Some('%'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
+            Tile::Player => RenderCell {
+                character: Some('@'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::DeadPlayer => RenderCell {
+                character: Some('@'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new(255, 0, 0, 255)),
+            },
+            Tile::Street => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(Rgba32::new_grey(127)),
+            },
+            Tile::Alley => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(Rgba32::new_grey(127)),
+            },
+            Tile::Footpath => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::FLOOR.to_rgba32(255)),
+            },
+            Tile::Water => RenderCell {
+                character: Some('~'),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(colours::WATER_FOREGROUND.to_rgba32(255))
+                    .with_background(colours::WATER.to_rgba32(255)),
+            },
+            Tile::Bridge => RenderCell {
+                character: Some('='),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(colours::BRIDGE.to_rgba32(255)),
+            },
+            Tile::Floor => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::FLOOR.to_rgba32(255)),
+            },
+            Tile::FloorBloody => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::FLOOR_BLOODY.to_rgba32(255)),
+            },
+            Tile::FloorPoison => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::POISON.to_rgba32(255))
+                    .with_background(
+                        colours::POISON
+                            .to_rgba32(255)
+                            .saturating_scalar_mul_div(1, 2),
+                    ),
+            },
+            Tile::FloorAcid => RenderCell {
+                character: Some('~'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ACID.to_rgba32(255))
+                    .with_background(colours::ACID.to_rgba32(255).saturating_scalar_mul_div(1, 3)),
+            },
+            Tile::FloorElectrified => RenderCell {
+                character: Some('≈'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ELECTRIC.to_rgba32(255))
+                    .with_background(
+                        colours::ELECTRIC
+                            .to_rgba32(255)
+                            .saturating_scalar_mul_div(1, 3),
+                    ),
+            },
+            Tile::Wall => RenderCell {
+                character: Some('#'),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255)),
+            },
+            Tile::Window => RenderCell {
+                character: Some('"'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::WINDOW.to_rgba32(255)),
+            },
+            Tile::Debris => RenderCell {
+                character: Some('%'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::DebrisBurning => RenderCell {
+                character: Some('%'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
 
-            Tile::DoorClosed => {
-                return RenderCell {
-                    character: Some('+'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::DoorOpen => {
-                return RenderCell {
-                    character: Some('-'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::StairsDown => {
-                return RenderCell {
-                    character: Some('>'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::StairsUp => {
-                return RenderCell {
-                    character: Some('<'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgba32::new_grey(255)),
-                };
-            }
-            Tile::Tentacle => {
-                return RenderCell {
-                    character: Some('▓'),
-                    style: Style::new().with_foreground(colours::CORRUPTION.to_rgba32(255)),
-                };
-            }
-            Tile::TentacleGlow => {
-                return RenderCell {
-                    character: Some('▒'),
-                    style: Style::new().with_foreground(colours::CORRUPTION.to_rgba32(255)),
-                };
-            }
-            Tile::Exit => {
-                return RenderCell {
-                    character: Some('Ω'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgb24::new(255, 0, 0).to_rgba32(255)),
-                };
-            }
-            Tile::Bullet => {
-                return RenderCell {
-                    character: Some('●'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(Rgb24::new(187, 187, 187).to_rgba32(255)),
-                };
-            }
-            Tile::Money(_) => {
-                return RenderCell {
-                    character: Some('$'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::MONEY.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Stimpack) => {
-                return RenderCell {
-                    character: Some('{'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::STIMPACK.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Antidote) => {
-                return RenderCell {
-                    character: Some('}'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ANTIDOTE.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::BloodVialEmpty) => {
-                return RenderCell {
-                    character: Some('['),
-                    style: Style::new()
-                        .with_bold(false)
-                        .with_foreground(colours::BLOOD_VIAL_EMPTY.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::BloodVialFull) => {
-                return RenderCell {
-                    character: Some('['),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::BLOOD_VIAL_FULL.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Battery) => {
-                return RenderCell {
-                    character: Some('&'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::BATTERY.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Food) => {
-                return RenderCell {
-                    character: Some('*'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::FOOD.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::AntiRads) => {
-                return RenderCell {
-                    character: Some(']'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ANTIRADS.to_rgba32(255)),
-                };
-            }
+            Tile::DoorClosed => RenderCell {
+                character: Some('+'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::DoorOpen => RenderCell {
+                character: Some('-'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::DoorBarricaded => RenderCell {
+                character: Some('+'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::PLANK.to_rgba32(255)),
+            },
+            Tile::StairsDown => RenderCell {
+                character: Some('>'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::StairsUp => RenderCell {
+                character: Some('<'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgba32::new_grey(255)),
+            },
+            Tile::Tentacle => RenderCell {
+                character: Some('▓'),
+                style: Style::new().with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            Tile::TentacleGlow => RenderCell {
+                character: Some('▒'),
+                style: Style::new().with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            Tile::Exit => RenderCell {
+                character: Some('Ω'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgb24::new(255, 0, 0).to_rgba32(255)),
+            },
+            Tile::Bullet => RenderCell {
+                character: Some('●'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(Rgb24::new(187, 187, 187).to_rgba32(255)),
+            },
+            Tile::Drone => RenderCell {
+                character: Some('¤'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::DRONE.to_rgba32(255)),
+            },
+            Tile::AlarmPanel => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ALARM_PANEL.to_rgba32(255)),
+            },
+            Tile::Generator => RenderCell {
+                character: Some('G'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::GENERATOR.to_rgba32(255)),
+            },
+            Tile::ExplosiveBarrel => RenderCell {
+                character: Some('θ'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::EXPLOSIVE_BARREL.to_rgba32(255)),
+            },
+            Tile::Stash => RenderCell {
+                character: Some('■'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::STASH.to_rgba32(255)),
+            },
+            Tile::Terminal => RenderCell {
+                character: Some('≡'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::TERMINAL.to_rgba32(255)),
+            },
+            Tile::Prop(Prop::AbandonedCar) => RenderCell {
+                character: Some('▬'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ABANDONED_CAR.to_rgba32(255)),
+            },
+            Tile::Prop(Prop::VendingMachine) => RenderCell {
+                character: Some('π'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::VENDING_MACHINE.to_rgba32(255)),
+            },
+            Tile::Prop(Prop::Graffiti) => RenderCell {
+                character: Some('#'),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(colours::GRAFFITI.to_rgba32(255)),
+            },
+            Tile::Prop(Prop::Bloodstain) => RenderCell {
+                character: Some('.'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BLOODSTAIN.to_rgba32(255)),
+            },
+            Tile::Prop(Prop::Remains) => RenderCell {
+                character: Some(','),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::REMAINS.to_rgba32(255)),
+            },
+            Tile::Money(_) => RenderCell {
+                character: Some('$'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::MONEY.to_rgba32(255)),
+            },
+            Tile::Item(Item::Stimpack) => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::STIMPACK.to_rgba32(255)),
+            },
+            Tile::Item(Item::Antidote) => RenderCell {
+                character: Some('}'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ANTIDOTE.to_rgba32(255)),
+            },
+            Tile::Item(Item::BloodVialEmpty) => RenderCell {
+                character: Some('['),
+                style: Style::new()
+                    .with_bold(false)
+                    .with_foreground(colours::BLOOD_VIAL_EMPTY.to_rgba32(255)),
+            },
+            Tile::Item(Item::BloodVialFull) => RenderCell {
+                character: Some('['),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BLOOD_VIAL_FULL.to_rgba32(255)),
+            },
+            Tile::Item(Item::Battery) => RenderCell {
+                character: Some('&'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BATTERY.to_rgba32(255)),
+            },
+            Tile::Item(Item::Food) => RenderCell {
+                character: Some('*'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::FOOD.to_rgba32(255)),
+            },
+            Tile::Item(Item::AntiRads) => RenderCell {
+                character: Some(']'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ANTIRADS.to_rgba32(255)),
+            },
 
-            Tile::Item(Item::OrganContainer(_)) => {
-                return RenderCell {
-                    character: Some('ɸ'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ORGAN_CONTAINER.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Pistol) => {
-                return RenderCell {
-                    character: Some('!'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::PISTOL.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Shotgun) => {
-                return RenderCell {
-                    character: Some('!'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SHOTGUN.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::RocketLauncher) => {
-                return RenderCell {
-                    character: Some('!'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::PistolAmmo) => {
-                return RenderCell {
-                    character: Some('"'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::PISTOL.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::ShotgunAmmo) => {
-                return RenderCell {
-                    character: Some('"'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SHOTGUN.to_rgba32(255)),
-                };
-            }
-            Tile::Item(Item::Rocket) => {
-                return RenderCell {
-                    character: Some('"'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
-                };
-            }
-            Tile::Zombie => {
-                return RenderCell {
-                    character: Some('z'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::ZOMBIE.to_rgba32(255)),
-                };
-            }
-            Tile::Climber => {
-                return RenderCell {
-                    character: Some('c'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::CLIMBER.to_rgba32(255)),
-                };
-            }
-            Tile::Trespasser => {
-                return RenderCell {
-                    character: Some('t'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::TRESPASSER.to_rgba32(255)),
-                };
-            }
-            Tile::Boomer => {
-                return RenderCell {
-                    character: Some('b'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::BOOMER.to_rgba32(255)),
-                };
-            }
-            Tile::Snatcher => {
-                return RenderCell {
-                    character: Some('s'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SNATCHER.to_rgba32(255)),
-                };
-            }
-            Tile::Poisoner => {
-                return RenderCell {
-                    character: Some('p'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::POISONER.to_rgba32(255)),
-                };
-            }
-            Tile::Divider => {
-                return RenderCell {
-                    character: Some('d'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::DIVIDER.to_rgba32(255)),
-                };
-            }
-            Tile::Glower => {
-                return RenderCell {
-                    character: Some('g'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::GLOWER.to_rgba32(255)),
-                };
-            }
-            Tile::Venter => {
-                return RenderCell {
-                    character: Some('v'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::VENTER.to_rgba32(255)),
-                };
-            }
-            Tile::Corruptor => {
-                return RenderCell {
-                    character: Some('X'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::CORRUPTOR.to_rgba32(255)),
-                };
-            }
-            Tile::GunStore => {
-                return RenderCell {
-                    character: Some('G'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SHOP_GUN.to_rgba32(255))
-                        .with_background(colours::SHOP_BG.to_rgba32(255)),
-                };
-            }
-            Tile::ItemStore => {
-                return RenderCell {
-                    character: Some('I'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SHOP_ITEM.to_rgba32(255))
-                        .with_background(colours::SHOP_BG.to_rgba32(255)),
-                };
-            }
-            Tile::OrganTrader => {
-                return RenderCell {
-                    character: Some('T'),
-                    style: Style::new(),
-                };
-            }
-            Tile::OrganClinic => {
-                return RenderCell {
-                    character: Some('O'),
-                    style: Style::new()
-                        .with_bold(true)
-                        .with_foreground(colours::SHOP_ORGAN_CLINIC.to_rgba32(255))
-                        .with_background(colours::SHOP_BG.to_rgba32(255)),
-                };
-            }
+            Tile::Item(Item::OrganContainer(_)) => RenderCell {
+                character: Some('ɸ'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ORGAN_CONTAINER.to_rgba32(255)),
+            },
+            Tile::Item(Item::Pistol) => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::PISTOL.to_rgba32(255)),
+            },
+            Tile::Item(Item::Shotgun) => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOTGUN.to_rgba32(255)),
+            },
+            Tile::Item(Item::RocketLauncher) => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+            },
+            Tile::Item(Item::PistolAmmo) => RenderCell {
+                character: Some('"'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::PISTOL.to_rgba32(255)),
+            },
+            Tile::Item(Item::ShotgunAmmo) => RenderCell {
+                character: Some('"'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOTGUN.to_rgba32(255)),
+            },
+            Tile::Item(Item::Rocket) => RenderCell {
+                character: Some('"'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+            },
+            Tile::Item(Item::SniperRifle) => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+            },
+            Tile::Item(Item::SniperRounds) => RenderCell {
+                character: Some('"'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+            },
+            Tile::Item(Item::Drone) => RenderCell {
+                character: Some('¤'),
+                style: Style::new().with_foreground(colours::DRONE.to_rgba32(255)),
+            },
+            Tile::Item(Item::Turret) => RenderCell {
+                character: Some('T'),
+                style: Style::new().with_foreground(colours::TURRET.to_rgba32(255)),
+            },
+            Tile::Item(Item::CorruptedStimpack) => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            Tile::Item(Item::CursedPistol) => RenderCell {
+                character: Some('!'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            Tile::Item(Item::Scanner) => RenderCell {
+                character: Some('%'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SCANNER.to_rgba32(255)),
+            },
+            Tile::Item(Item::GeigerCounter) => RenderCell {
+                character: Some('%'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::GEIGER_COUNTER.to_rgba32(255)),
+            },
+            Tile::Item(Item::Schematics) => RenderCell {
+                character: Some('?'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SCHEMATICS.to_rgba32(255)),
+            },
+            Tile::Item(Item::MarketReport) => RenderCell {
+                character: Some('?'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::MARKET_REPORT.to_rgba32(255)),
+            },
+            Tile::Item(Item::Plank) => RenderCell {
+                character: Some('='),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::PLANK.to_rgba32(255)),
+            },
+            Tile::Item(Item::Anesthetic) => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ANESTHETIC.to_rgba32(255)),
+            },
+            Tile::Item(Item::EmpGrenade) => RenderCell {
+                character: Some('*'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::EMP_GRENADE.to_rgba32(255)),
+            },
+            Tile::Item(Item::SmokeGrenade) => RenderCell {
+                character: Some('*'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SMOKE_GRENADE.to_rgba32(255)),
+            },
+            Tile::Item(Item::GasMask) => RenderCell {
+                character: Some('('),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::GAS_MASK.to_rgba32(255)),
+            },
+            Tile::Item(Item::HazardSuit) => RenderCell {
+                character: Some(')'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::HAZARD_SUIT.to_rgba32(255)),
+            },
+            Tile::Item(Item::RawMeat) => RenderCell {
+                character: Some(';'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::RAW_MEAT.to_rgba32(255)),
+            },
+            Tile::Item(Item::CookedMeat) => RenderCell {
+                character: Some(';'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::COOKED_MEAT.to_rgba32(255)),
+            },
+            Tile::Item(Item::RottenMeat) => RenderCell {
+                character: Some(';'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ROTTEN_MEAT.to_rgba32(255)),
+            },
+            Tile::Item(Item::Readable(_)) => RenderCell {
+                character: Some('§'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::READABLE.to_rgba32(255)),
+            },
+            Tile::Item(Item::Antipsychotic) => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ANTIPSYCHOTIC.to_rgba32(255)),
+            },
+            Tile::Item(Item::Bandage) => RenderCell {
+                character: Some('~'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BANDAGE.to_rgba32(255)),
+            },
+            Tile::Item(Item::Immunosuppressant) => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::IMMUNOSUPPRESSANT.to_rgba32(255)),
+            },
+            Tile::Item(Item::FieldSurgeryKit) => RenderCell {
+                character: Some('+'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::FIELD_SURGERY_KIT.to_rgba32(255)),
+            },
+            Tile::UnlabeledSyringe => RenderCell {
+                character: Some('{'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
+            },
+            Tile::StrangeDevice => RenderCell {
+                character: Some('¤'),
+                style: Style::new().with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
+            },
+            Tile::Zombie => RenderCell {
+                character: Some('z'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ZOMBIE.to_rgba32(255)),
+            },
+            Tile::Climber => RenderCell {
+                character: Some('c'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CLIMBER.to_rgba32(255)),
+            },
+            Tile::Trespasser => RenderCell {
+                character: Some('t'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::TRESPASSER.to_rgba32(255)),
+            },
+            Tile::Boomer => RenderCell {
+                character: Some('b'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BOOMER.to_rgba32(255)),
+            },
+            Tile::Snatcher => RenderCell {
+                character: Some('s'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SNATCHER.to_rgba32(255)),
+            },
+            Tile::Poisoner => RenderCell {
+                character: Some('p'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::POISONER.to_rgba32(255)),
+            },
+            Tile::Divider => RenderCell {
+                character: Some('d'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::DIVIDER.to_rgba32(255)),
+            },
+            Tile::Glower => RenderCell {
+                character: Some('g'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::GLOWER.to_rgba32(255)),
+            },
+            Tile::Venter => RenderCell {
+                character: Some('v'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::VENTER.to_rgba32(255)),
+            },
+            Tile::Corruptor => RenderCell {
+                character: Some('X'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CORRUPTOR.to_rgba32(255)),
+            },
+            Tile::Mercenary => RenderCell {
+                character: Some('m'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::MERCENARY.to_rgba32(255)),
+            },
+            Tile::Turret => RenderCell {
+                character: Some('T'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::TURRET.to_rgba32(255)),
+            },
+            Tile::Sentinel => RenderCell {
+                character: Some('S'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SENTINEL.to_rgba32(255)),
+            },
+            Tile::Guard => RenderCell {
+                character: Some('g'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::GUARD.to_rgba32(255)),
+            },
+            Tile::BountyHunter => RenderCell {
+                character: Some('H'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::BOUNTY_HUNTER.to_rgba32(255)),
+            },
+            Tile::Civilian => RenderCell {
+                character: Some('c'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CIVILIAN.to_rgba32(255)),
+            },
+            Tile::GunStore => RenderCell {
+                character: Some('G'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOP_GUN.to_rgba32(255))
+                    .with_background(colours::SHOP_BG.to_rgba32(255)),
+            },
+            Tile::ItemStore => RenderCell {
+                character: Some('I'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOP_ITEM.to_rgba32(255))
+                    .with_background(colours::SHOP_BG.to_rgba32(255)),
+            },
+            Tile::OrganTrader => RenderCell {
+                character: Some('T'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOP_ORGAN_TRADER.to_rgba32(255))
+                    .with_background(colours::SHOP_BG.to_rgba32(255)),
+            },
+            Tile::OrganClinic => RenderCell {
+                character: Some('O'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOP_ORGAN_CLINIC.to_rgba32(255))
+                    .with_background(colours::SHOP_BG.to_rgba32(255)),
+            },
+            Tile::Merchant => RenderCell {
+                character: Some('M'),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::SHOP_MERCHANT.to_rgba32(255))
+                    .with_background(colours::SHOP_BG.to_rgba32(255)),
+            },
             Tile::Corpse(npc_type) => {
                 let colour = match npc_type {
                     NpcType::Zombie => colours::ZOMBIE,
@@ -534,28 +1105,44 @@ impl GameInstance {
                     NpcType::Glower => colours::GLOWER,
                     NpcType::Venter => colours::VENTER,
                     NpcType::Corruptor => colours::CORRUPTOR,
+                    NpcType::Mercenary => colours::MERCENARY,
+                    NpcType::Turret => colours::TURRET,
+                    NpcType::Guard => colours::GUARD,
+                    NpcType::BountyHunter => colours::BOUNTY_HUNTER,
+                    NpcType::Civilian => colours::CIVILIAN,
+                    NpcType::Sentinel => colours::SENTINEL,
                     NpcType::GunStore => colours::SHOP_GUN,
                     NpcType::ItemStore => colours::SHOP_ITEM,
                     NpcType::OrganClinic => colours::SHOP_ORGAN_CLINIC,
-                    NpcType::OrganTrader => colours::SHOP_BG,
+                    NpcType::OrganTrader => colours::SHOP_ORGAN_TRADER,
+                    NpcType::Merchant => colours::SHOP_MERCHANT,
                 };
-                return RenderCell {
+                RenderCell {
                     character: Some('?'),
                     style: Style::new()
                         .with_bold(true)
                         .with_foreground(colour.to_rgba32(255)),
-                };
+                }
             }
-        };
+        }
     }
 
-    pub fn render_game(&self, ctx: Ctx, fb: &mut FrameBuffer) {
-        let centre_coord_delta = Coord::new(0, 0);
-        for coord in ctx.bounding_box.size().coord_iter_row_major() {
-            let cell = self
-                .game
-                .inner_ref()
-                .cell_visibility_at_coord(coord + centre_coord_delta);
+    pub fn render_game(
+        &self,
+        ctx: Ctx,
+        fb: &mut FrameBuffer,
+        particle_density_percent: u32,
+        glyph_set: GlyphSet,
+    ) {
+        let game = self.game.inner_ref();
+        let world_size = game.world_size();
+        for coord in VIEWPORT_SIZE.coord_iter_row_major() {
+            let world_coord = coord + self.camera;
+            let cell = if world_coord.is_valid(world_size) {
+                game.cell_visibility_at_coord(world_coord)
+            } else {
+                CellVisibility::Never
+            };
             let unseen_background = Rgba32::new(0, 0, 0, 255);
             match cell {
                 CellVisibility::Never => {
@@ -570,7 +1157,8 @@ impl GameInstance {
                     data.tiles.for_each_enumerate(|visible_entity, layer| {
                         if let Some(&tile) = visible_entity.tile.as_ref() {
                             let depth = Self::layer_to_depth(layer);
-                            let mut render_cell = Self::tile_to_render_cell(tile);
+                            let mut render_cell =
+                                Self::tile_to_render_cell(game.apparent_tile(tile), glyph_set);
                             render_cell.style.background = Some(background);
                             render_cell.style.foreground = Some(colours::STAIRS.to_rgba32(127));
                             fb.set_cell_relative_to_ctx(ctx, coord, depth, render_cell);
@@ -583,7 +1171,11 @@ impl GameInstance {
                     data.tiles.for_each_enumerate(|visible_entity, layer| {
                         if let Some(tile) = visible_entity.tile {
                             let depth = Self::layer_to_depth(layer);
-                            let mut render_cell = Self::tile_to_render_cell(tile);
+                            let mut render_cell = Self::apply_level_theme(
+                                game.level_theme(),
+                                tile,
+                                Self::tile_to_render_cell(game.apparent_tile(tile), glyph_set),
+                            );
                             if let Some(colour_hint) = visible_entity.colour_hint {
                                 render_cell = render_cell.with_foreground(colour_hint);
                             }
@@ -595,25 +1187,47 @@ impl GameInstance {
                             );
                         }
                     });
+                    if game.poison_gas_at(world_coord) {
+                        let render_cell =
+                            RenderCell::default().with_background(colours::POISON.to_rgba32(96));
+                        fb.set_cell_relative_to_ctx(ctx, coord, 9, render_cell);
+                    }
+                    if game.smoke_at(world_coord) {
+                        let render_cell =
+                            RenderCell::default().with_background(colours::SMOKE.to_rgba32(160));
+                        fb.set_cell_relative_to_ctx(ctx, coord, 9, render_cell);
+                    }
+                    if let Some(intensity) = game.radiation_overlay_intensity(world_coord) {
+                        if intensity > 0 {
+                            let alpha = (intensity * 40).min(200) as u8;
+                            let render_cell = RenderCell::default()
+                                .with_background(colours::RADIATION.to_rgba32(alpha));
+                            fb.set_cell_relative_to_ctx(ctx, coord, 9, render_cell);
+                        }
+                    }
                 }
             }
         }
-        self.game
-            .inner_ref()
-            .for_each_visible_particle(|coord, visible_entity, _light_colour| {
+        self.game.inner_ref().for_each_visible_particle(
+            particle_density_percent,
+            |coord, visible_entity, _light_colour| {
                 let mut render_cell = if let Some(tile) = visible_entity.tile {
-                    Self::tile_to_render_cell(tile)
+                    Self::tile_to_render_cell(tile, glyph_set)
                 } else {
                     RenderCell::default()
                 };
                 if let Some(colour_hint) = visible_entity.colour_hint {
                     render_cell = render_cell.with_background(colour_hint);
                 }
-                fb.set_cell_relative_to_ctx(ctx, coord, 10, render_cell);
-            });
+                let coord = self.world_to_screen(coord);
+                if coord.is_valid(VIEWPORT_SIZE) {
+                    fb.set_cell_relative_to_ctx(ctx, coord, 10, render_cell);
+                }
+            },
+        );
     }
 
-    fn render_messages(&self, ctx: Ctx, fb: &mut FrameBuffer) {
+    fn render_messages(&self, ctx: Ctx, fb: &mut FrameBuffer, combat_log: CombatLog) {
         use text::*;
         let max = 4;
         let mut messages: Vec<(usize, Message)> = Vec::new();
@@ -630,7 +1244,7 @@ impl GameInstance {
             messages.push((1, m.clone()));
         }
         for (i, (count, m)) in messages.into_iter().enumerate() {
-            let text = message_to_text(m);
+            let text = message_to_text(m, self.game.inner_ref(), combat_log);
             let mut text = if count == 1 {
                 text
             } else {
@@ -700,7 +1314,7 @@ impl GameInstance {
                     let Description {
                         mut name,
                         description,
-                    } = describe_tile(tile);
+                    } = describe_tile(self.game.inner_ref().apparent_tile(tile));
                     let mut text = Text {
                         parts: vec![StyledString::plain_text(format!("There is "))],
                     };
@@ -739,7 +1353,7 @@ impl GameInstance {
                     let Description {
                         mut name,
                         description,
-                    } = describe_tile(tile);
+                    } = describe_tile(self.game.inner_ref().apparent_tile(tile));
                     let mut text = Text {
                         parts: vec![StyledString::plain_text(format!("You {verb} "))],
                     };
@@ -777,15 +1391,32 @@ impl GameInstance {
         }
     }
 
+    // Npcs only notice the player from close up
+    fn is_player_hidden(&self) -> bool {
+        self.game.inner_ref().player_detectability() <= HIDDEN_DETECTABILITY_PERCENT
+    }
+
     fn render_mode(&self, ctx: Ctx, fb: &mut FrameBuffer, mode: Mode) {
         use text::*;
         let text = match mode {
+            Mode::Normal if self.game.inner_ref().is_sprinting() => {
+                Text::new(vec![StyledString::plain_text(
+                    "Sprinting two cells per turn. It's loud and costs oxygen.".to_string(),
+                )])
+            }
+            Mode::Normal if self.is_player_hidden() => Text::new(vec![StyledString::plain_text(
+                "Hidden in the dark. Keep still to stay unseen.".to_string(),
+            )]),
             Mode::Normal => Text::new(vec![StyledString::plain_text(format!(
                 "Move with ←↑→↓.\nPress ? for more info."
             ))]),
             Mode::Aiming => Text::new(vec![StyledString::plain_text(format!(
                 "Aim with the mouse or ←↑→↓. Click or ENTER to fire."
             ))]),
+            Mode::Throwing => Text::new(vec![StyledString::plain_text(
+                "Aim with the mouse or ←↑→↓. Click or ENTER to throw. Throws arc over characters and debris."
+                    .to_string(),
+            )]),
         };
         text.wrap_word().render(&(), ctx, fb);
     }
@@ -839,6 +1470,13 @@ impl GameInstance {
         } else {
             render_meter_disabled(ctx.add_x(x_offset), fb);
         }
+        let ctx = ctx.add_y(1);
+        StyledString {
+            string: "Temp.:".to_string(),
+            style: Style::plain_text(),
+        }
+        .render(&(), ctx, fb);
+        render_temperature_meter(stats.temperature, ctx.add_x(x_offset), fb);
         if let Some(satiation) = stats.satiation {
             let ctx = ctx.add_y(1);
             StyledString {
@@ -852,6 +1490,134 @@ impl GameInstance {
         }
     }
 
+    /// Renders everything the player knows about the layout of the current level, along with a
+    /// description of the cell under the cursor
+    pub fn render_overview(
+        &self,
+        ctx: Ctx,
+        fb: &mut FrameBuffer,
+        cursor: Option<Coord>,
+        glyph_set: GlyphSet,
+    ) {
+        use text::*;
+        let game = self.game.inner_ref();
+        let world_size = game.world_size();
+        let game_size = Size::new(
+            world_size.width().min(VIEWPORT_SIZE.width()),
+            world_size.height().min(VIEWPORT_SIZE.height()),
+        );
+        let origin = self.overview_origin(cursor);
+        let player_coord = game.player_coord();
+        for screen_coord in game_size.coord_iter_row_major() {
+            let coord = screen_coord + origin;
+            let data = match game.cell_visibility_at_coord(coord).into_data() {
+                Some(data) => data,
+                None => continue,
+            };
+            let shop = match data.tiles.character.tile {
+                Some(
+                    tile @ (Tile::GunStore
+                    | Tile::ItemStore
+                    | Tile::OrganTrader
+                    | Tile::OrganClinic
+                    | Tile::Merchant),
+                ) => Some(tile),
+                _ => None,
+            };
+            let tile = if coord == player_coord {
+                Some(Tile::Player)
+            } else {
+                shop.or(data.tiles.feature.tile).or(data.tiles.floor.tile)
+            };
+            if let Some(tile) = tile {
+                let render_cell = Self::tile_to_render_cell(tile, glyph_set);
+                fb.set_cell_relative_to_ctx(ctx, screen_coord, 0, render_cell);
+            }
+        }
+        if let Some(cursor) = cursor {
+            let render_cell =
+                RenderCell::default().with_background(colours::NORMAL_MODE.to_rgba32(127));
+            fb.set_cell_relative_to_ctx(ctx, cursor - origin, 1, render_cell);
+        }
+        Text::new(vec![StyledString {
+            string: format!(
+                "Map of level {}. Move the cursor to inspect a location. Press any key to return to the game.",
+                game.current_level_index() + 1
+            ),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }])
+        .wrap_word()
+        .render(
+            &(),
+            ctx.add_y(game_size.height() as i32 + 1)
+                .set_width(game_size.width()),
+            fb,
+        );
+        self.render_description(ctx.add_x(game_size.width() as i32 + 2).add_y(1), fb, cursor);
+    }
+
+    /// Everything the player knows about the current level as text, with one character per cell.
+    /// When omniscient this is the whole level.
+    pub fn map_text(&self) -> String {
+        let game = self.game.inner_ref();
+        let world_size = game.world_size();
+        let mut text = format!(
+            "{} - level {} (seed {})\n\n",
+            crate::NAME,
+            game.current_level_index() + 1,
+            self.rng_seed
+        );
+        for y in 0..world_size.height() as i32 {
+            let row = (0..world_size.width() as i32)
+                .map(|x| {
+                    let data = game
+                        .cell_visibility_at_coord(Coord::new(x, y))
+                        .into_data()?;
+                    let tiles = &data.tiles;
+                    let tile = tiles
+                        .character
+                        .tile
+                        .or(tiles.item.tile)
+                        .or(tiles.feature.tile)
+                        .or(tiles.floor.tile)?;
+                    Self::tile_to_unicode_render_cell(game.apparent_tile(tile)).character
+                })
+                .map(|character| character.unwrap_or(' '))
+                .collect::<String>();
+            text.push_str(row.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    // Each character of the minimap shows two cells, one above the other, using a half-block
+    // character with the upper cell as the foreground colour and the lower as the background.
+    fn render_minimap(&self, ctx: Ctx, fb: &mut FrameBuffer) {
+        let minimap = self.game.inner_ref().minimap_cells(MINIMAP_SCALE);
+        let colour = |cell: Option<&MinimapCell>| match cell {
+            None | Some(MinimapCell::Unexplored) => Rgba32::new_grey(0),
+            Some(MinimapCell::Floor) => colours::FLOOR.to_rgba32(255),
+            Some(MinimapCell::Water) => colours::WATER_FOREGROUND.to_rgba32(255),
+            Some(MinimapCell::Wall) => colours::VAPORWAVE_FOREGROUND.to_rgba32(255),
+            Some(MinimapCell::Stairs) => colours::STAIRS.to_rgba32(255),
+            Some(MinimapCell::Shop) => colours::SHOP_GUN.to_rgba32(255),
+            Some(MinimapCell::Player) => Rgba32::new_grey(255),
+        };
+        for y in 0..((minimap.height() as i32 + 1) / 2) {
+            for x in 0..(minimap.width() as i32) {
+                let upper = minimap.get(Coord::new(x, y * 2));
+                let lower = minimap.get(Coord::new(x, (y * 2) + 1));
+                let render_cell = RenderCell {
+                    character: Some('▀'),
+                    style: Style::new()
+                        .with_foreground(colour(upper))
+                        .with_background(colour(lower)),
+                };
+                fb.set_cell_relative_to_ctx(ctx, Coord::new(x, y), 0, render_cell);
+            }
+        }
+    }
+
     fn render_info(&self, ctx: Ctx, fb: &mut FrameBuffer) {
         use text::*;
         let current_floor = self.game.inner_ref().current_level_index();
@@ -865,6 +1631,20 @@ impl GameInstance {
                 string: format!("{}/{}", (current_floor + 1), num_floors),
                 style: Style::plain_text().with_bold(true),
             },
+            StyledString {
+                string: format!(" ({})", self.game.inner_ref().level_theme().name()),
+                style: Style::plain_text(),
+            },
+            StyledString {
+                string: if self.game.inner_ref().is_level_powered() {
+                    String::new()
+                } else {
+                    " NO POWER".to_string()
+                },
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::GENERATOR.to_rgba32(255)),
+            },
         ])
         .render(&(), ctx, fb);
         let ctx = ctx.add_y(1);
@@ -893,6 +1673,36 @@ impl GameInstance {
         ])
         .render(&(), ctx, fb);
         let ctx = ctx.add_y(1);
+        let worn_items = self.game.inner_ref().player_worn_items();
+        let mut worn = vec![StyledString {
+            string: "Worn: ".to_string(),
+            style: Style::plain_text(),
+        }];
+        if worn_items.is_empty() {
+            worn.push(StyledString {
+                string: "(nothing)".to_string(),
+                style: Style::plain_text().with_bold(true),
+            });
+        }
+        for (i, (item, durability)) in worn_items.into_iter().enumerate() {
+            let name = match item {
+                Item::GasMask => "Mask",
+                Item::HazardSuit => "Suit",
+                _ => "?",
+            };
+            worn.push(StyledString {
+                string: format!(
+                    "{}{} ({}/{})",
+                    if i == 0 { "" } else { " " },
+                    name,
+                    durability.current(),
+                    durability.max()
+                ),
+                style: Style::plain_text().with_bold(true),
+            });
+        }
+        Text::new(worn).render(&(), ctx, fb);
+        let ctx = ctx.add_y(1);
         Text::new(vec![
             StyledString {
                 string: "CyberCoinz™: ".to_string(),
@@ -906,6 +1716,207 @@ impl GameInstance {
             },
         ])
         .render(&(), ctx, fb);
+        let ctx = ctx.add_y(1);
+        let experience = self.game.inner_ref().player_experience();
+        Text::new(vec![
+            StyledString {
+                string: format!("XP (Lv. {}): ", experience.level + 1),
+                style: Style::plain_text(),
+            },
+            StyledString {
+                string: match experience.next_level_points() {
+                    Some(next_level_points) => {
+                        format!("{}/{}", experience.points, next_level_points)
+                    }
+                    None => format!("{} (max)", experience.points),
+                },
+                style: Style::plain_text().with_bold(true),
+            },
+        ])
+        .render(&(), ctx, fb);
+    }
+
+    // A short plain text description of what the player knows to be at a coordinate
+    fn narrate_cell(&self, coord: Coord, exclude_character: bool) -> String {
+        let game = self.game.inner_ref();
+        if !game.world_size().is_valid(coord) {
+            return "nothing".to_string();
+        }
+        let (visible_entity, remembered) = match game.cell_visibility_at_coord(coord) {
+            CellVisibility::Never => return "unexplored".to_string(),
+            CellVisibility::Previous(data) => (visible_entity_on_top(&data.tiles), true),
+            CellVisibility::Current { data, .. } => {
+                if exclude_character {
+                    (
+                        visible_entity_on_top_excluding_character(&data.tiles),
+                        false,
+                    )
+                } else {
+                    (visible_entity_on_top(&data.tiles), false)
+                }
+            }
+        };
+        match visible_entity.and_then(|(visible_entity, _)| visible_entity.tile) {
+            Some(tile) => {
+                let name = plain_string(&describe_tile(game.apparent_tile(tile)).name);
+                if remembered {
+                    format!("{} (remembered)", name)
+                } else {
+                    name
+                }
+            }
+            None => "nothing".to_string(),
+        }
+    }
+
+    /// Lists the player's current objectives over the top-left corner of the map
+    pub fn render_objectives(&self, ctx: Ctx, fb: &mut FrameBuffer) {
+        use text::*;
+        let lines = self
+            .game
+            .inner_ref()
+            .current_objectives()
+            .into_iter()
+            .map(objective_string)
+            .collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Objectives".len()) as i32
+            + 2;
+        let background = RenderCell::BLANK.with_background(
+            colours::VAPORWAVE_BACKGROUND
+                .to_rgba32(255)
+                .saturating_scalar_mul_div(1, 4),
+        );
+        for y in 0..(lines.len() as i32 + 1) {
+            for x in 0..width {
+                fb.set_cell_relative_to_ctx(ctx, Coord::new(x, y), 0, background);
+            }
+        }
+        StyledString {
+            string: "Objectives".to_string(),
+            style: Style::plain_text()
+                .with_bold(true)
+                .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255)),
+        }
+        .render(&(), ctx.add_x(1), fb);
+        for (i, line) in lines.into_iter().enumerate() {
+            StyledString {
+                string: line,
+                style: Style::plain_text(),
+            }
+            .render(&(), ctx.add_xy(1, i as i32 + 1), fb);
+        }
+    }
+
+    /// Renders the player's stats, surroundings and recent messages as lines of plain text in
+    /// place of the glyph grid, so the game can be followed with a screen reader
+    pub fn render_narration(&self, ctx: Ctx, fb: &mut FrameBuffer, combat_log: CombatLog) {
+        use text::*;
+        let game = self.game.inner_ref();
+        let stats = game.player_stats();
+        let mut lines = Vec::new();
+        let mut status = format!(
+            "Level {} of {}, {}.",
+            game.current_level_index() + 1,
+            game::NUM_LEVELS,
+            game.level_theme().name()
+        );
+        if !game.is_level_powered() {
+            status.push_str(" No power.");
+        }
+        lines.push(status);
+        let mut stats_line = format!(
+            "Health {}/{}. Oxygen {}/{}. Food {}/{}. Poison {}/{}. Radiation {}/{}.",
+            stats.health.current(),
+            stats.health.max(),
+            stats.oxygen.current(),
+            stats.oxygen.max(),
+            stats.food.current(),
+            stats.food.max(),
+            stats.poison.current(),
+            stats.poison.max(),
+            stats.radiation.current(),
+            stats.radiation.max(),
+        );
+        if let Some(power) = stats.power {
+            stats_line.push_str(&format!(" Power {}/{}.", power.current(), power.max()));
+        }
+        stats_line.push_str(&format!(
+            " Temperature {}.",
+            temperature_label(stats.temperature)
+        ));
+        if let Some(satiation) = stats.satiation {
+            stats_line.push_str(&format!(
+                " Vampirism {}/{}.",
+                satiation.current(),
+                satiation.max()
+            ));
+        }
+        stats_line.push_str(&format!(" Money ${}.", game.player_money()));
+        lines.push(stats_line);
+        let objectives = game
+            .current_objectives()
+            .into_iter()
+            .map(objective_string)
+            .collect::<Vec<_>>();
+        lines.push(format!("Objectives: {}.", objectives.join(". ")));
+        let player_coord = game.player_coord();
+        let mut surroundings = format!("Here: {}.", self.narrate_cell(player_coord, true));
+        for (name, direction) in [
+            ("North", CardinalDirection::North),
+            ("East", CardinalDirection::East),
+            ("South", CardinalDirection::South),
+            ("West", CardinalDirection::West),
+        ] {
+            surroundings.push_str(&format!(
+                " {}: {}.",
+                name,
+                self.narrate_cell(player_coord + direction.coord(), false)
+            ));
+        }
+        lines.push(surroundings);
+        let mut hostiles = game.visible_hostile_coords();
+        hostiles.sort_by_key(|&coord| (coord - player_coord).magnitude2());
+        if hostiles.is_empty() {
+            lines.push("No enemies in sight.".to_string());
+        } else {
+            let mut enemies = "Enemies in sight:".to_string();
+            for coord in hostiles {
+                let delta = coord - player_coord;
+                let mut offsets = Vec::new();
+                if delta.y != 0 {
+                    let direction = if delta.y < 0 { "north" } else { "south" };
+                    offsets.push(format!("{} {}", delta.y.abs(), direction));
+                }
+                if delta.x != 0 {
+                    let direction = if delta.x < 0 { "west" } else { "east" };
+                    offsets.push(format!("{} {}", delta.x.abs(), direction));
+                }
+                enemies.push_str(&format!(
+                    " {}, {}.",
+                    self.narrate_cell(coord, false),
+                    offsets.join(" ")
+                ));
+            }
+            lines.push(enemies);
+        }
+        let max_messages = 4;
+        let message_log = game.message_log();
+        let first_message = message_log.len().saturating_sub(max_messages);
+        for message in &message_log[first_message..] {
+            lines.push(plain_string(&message_to_text(
+                message.clone(),
+                game,
+                combat_log,
+            )));
+        }
+        Text::new(vec![StyledString::plain_text(lines.join("\n\n"))])
+            .wrap_word()
+            .render(&(), ctx.add_xy(1, 1), fb);
     }
 
     pub fn render(
@@ -915,13 +1926,26 @@ impl GameInstance {
         cursor: Option<Coord>,
         mode: Mode,
         offset: Coord,
+        settings: RenderSettings,
     ) {
         use text::*;
-        self.render_game(ctx.add_offset(offset), fb);
+        let RenderSettings {
+            particle_density_percent,
+            glyph_set,
+            combat_log,
+        } = settings;
+        let screen_size = ctx.bounding_box.size();
+        self.render_game(
+            ctx.add_offset(map_origin(screen_size) + offset),
+            fb,
+            particle_density_percent,
+            glyph_set,
+        );
         self.render_messages(
             ctx.add_xy(1, ctx.bounding_box.size().height() as i32 - 4)
                 .add_depth(20),
             fb,
+            combat_log,
         );
         let border_style = Style::new()
             .with_bold(true)
@@ -929,7 +1953,8 @@ impl GameInstance {
         let border_text_style = Style::new()
             .with_bold(true)
             .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255));
-        let game_size = self.game.inner_ref().world_size();
+        let game_size = map_area(screen_size);
+        let sidebar = SidebarLayout::new(screen_size);
         let box_render_cell = RenderCell::default().with_style(border_style);
         // line to the right of game
         {
@@ -974,7 +1999,7 @@ impl GameInstance {
         );
         // description
         {
-            let offset_y = 21;
+            let offset_y = sidebar.description;
             let render_cell = box_render_cell.with_character('═');
             for i in (game_size.width() + 1)..ctx.bounding_box.size().width() {
                 let coord = Coord::new(i as i32, offset_y);
@@ -996,7 +2021,7 @@ impl GameInstance {
                             style: border_text_style
                                 .with_foreground(colours::NORMAL_MODE.to_rgba32(255)),
                         },
-                        Mode::Aiming => StyledString {
+                        Mode::Aiming | Mode::Throwing => StyledString {
                             string: format!("AT TARGET"),
                             style: border_text_style
                                 .with_foreground(colours::AIMING_MODE.to_rgba32(255)),
@@ -1029,7 +2054,7 @@ impl GameInstance {
         }
         // mode
         {
-            let offset_y = 16;
+            let offset_y = sidebar.mode;
             let render_cell = box_render_cell.with_character('═');
             for i in (game_size.width() + 1)..ctx.bounding_box.size().width() {
                 let coord = Coord::new(i as i32, offset_y);
@@ -1045,6 +2070,16 @@ impl GameInstance {
                     style: border_text_style,
                 },
                 match mode {
+                    Mode::Normal if self.game.inner_ref().is_sprinting() => StyledString {
+                        string: "SPRINTING".to_string(),
+                        style: border_text_style
+                            .with_foreground(colours::AIMING_MODE.to_rgba32(255)),
+                    },
+                    Mode::Normal if self.is_player_hidden() => StyledString {
+                        string: "HIDDEN".to_string(),
+                        style: border_text_style
+                            .with_foreground(colours::HIDDEN_MODE.to_rgba32(255)),
+                    },
                     Mode::Normal => StyledString {
                         string: "NORMAL".to_string(),
                         style: border_text_style
@@ -1055,6 +2090,11 @@ impl GameInstance {
                         style: border_text_style
                             .with_foreground(colours::AIMING_MODE.to_rgba32(255)),
                     },
+                    Mode::Throwing => StyledString {
+                        string: "THROWING".to_string(),
+                        style: border_text_style
+                            .with_foreground(colours::AIMING_MODE.to_rgba32(255)),
+                    },
                 },
                 StyledString {
                     string: "╞".to_string(),
@@ -1070,14 +2110,49 @@ impl GameInstance {
             );
             self.render_mode(
                 ctx.add_offset(game_size.to_coord().unwrap().set_y(offset_y + 1))
-                    .add_xy(2, 1),
+                    .add_xy(2, 0),
                 fb,
                 mode,
             );
         }
+        // minimap
+        {
+            let offset_y = sidebar.minimap;
+            let render_cell = box_render_cell.with_character('═');
+            for i in (game_size.width() + 1)..ctx.bounding_box.size().width() {
+                let coord = Coord::new(i as i32, offset_y);
+                fb.set_cell_relative_to_ctx(ctx, coord, 0, render_cell);
+            }
+            Text::new(vec![
+                StyledString {
+                    string: "╡".to_string(),
+                    style: border_style,
+                },
+                StyledString {
+                    string: "Map".to_string(),
+                    style: border_text_style,
+                },
+                StyledString {
+                    string: "╞".to_string(),
+                    style: border_style,
+                },
+            ])
+            .render(&(), ctx.add_xy(game_size.width() as i32 + 1, offset_y), fb);
+            fb.set_cell_relative_to_ctx(
+                ctx,
+                game_size.to_coord().unwrap().set_y(offset_y),
+                0,
+                box_render_cell.with_character('╠'),
+            );
+            self.render_minimap(
+                ctx.add_offset(game_size.to_coord().unwrap().set_y(offset_y + 1))
+                    .add_xy(2, 0),
+                fb,
+            );
+        }
         // stats
         {
-            let offset_y = 6;
+            let offset_y = sidebar.stats;
             let render_cell = box_render_cell.with_character('═');
             for i in (game_size.width() + 1)..ctx.bounding_box.size().width() {
                 let coord = Coord::new(i as i32, offset_y);
@@ -1106,13 +2181,13 @@ impl GameInstance {
             );
             self.render_stats(
                 ctx.add_offset(game_size.to_coord().unwrap().set_y(offset_y + 1))
-                    .add_xy(2, 1),
+                    .add_xy(2, 0),
                 fb,
             );
         }
         // info
         {
-            let offset_y = 0;
+            let offset_y = sidebar.info;
             self.render_info(
                 ctx.add_offset(game_size.to_coord().unwrap().set_y(offset_y + 1))
                     .add_xy(2, 0),
@@ -1125,13 +2200,27 @@ impl GameInstance {
 #[derive(Serialize, Deserialize)]
 pub struct GameInstanceStorable {
     running_game: RunningGame,
+    rng_seed: u64,
+    camera: Coord,
 }
 
 impl GameInstanceStorable {
     pub fn into_game_instance(self) -> (GameInstance, witness::Running) {
-        let Self { running_game } = self;
+        let Self {
+            running_game,
+            rng_seed,
+            camera,
+        } = self;
         let (game, running) = running_game.into_game();
-        (GameInstance { game }, running)
+        crash::set_rng_seed(rng_seed);
+        (
+            GameInstance {
+                game,
+                rng_seed,
+                camera,
+            },
+            running,
+        )
     }
 }
 
@@ -1140,6 +2229,10 @@ struct Description {
     description: Option<Text>,
 }
 
+fn plain_string(text: &Text) -> String {
+    text.parts.iter().map(|part| part.string.as_str()).collect()
+}
+
 fn describe_tile(tile: Tile) -> Description {
     use text::*;
     match tile {
@@ -1151,7 +2244,9 @@ fn describe_tile(tile: Tile) -> Description {
             name: Text::new(vec![StyledString::plain_text(
                 "yourself (dead)".to_string(),
             )]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Anything you were carrying when you died can be picked up from here.".to_string(),
+            )])),
         },
         Tile::Floor => Description {
             name: Text::new(vec![StyledString::plain_text("the floor".to_string())]),
@@ -1169,10 +2264,41 @@ fn describe_tile(tile: Tile) -> Description {
             )]),
             description: None,
         },
+        Tile::FloorAcid => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a pool of ".to_string()),
+                StyledString {
+                    string: "acid".to_string(),
+                    style: Style::plain_text().with_foreground(colours::ACID.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Burns anything that stands in it.".to_string(),
+            )])),
+        },
+        Tile::FloorElectrified => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("the floor (".to_string()),
+                StyledString {
+                    string: "electrified".to_string(),
+                    style: Style::plain_text().with_foreground(colours::ELECTRIC.to_rgba32(255)),
+                },
+                StyledString::plain_text(")".to_string()),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Exposed wiring shocks anything that stands here.".to_string(),
+            )])),
+        },
         Tile::Wall => Description {
             name: Text::new(vec![StyledString::plain_text("a wall".to_string())]),
             description: None,
         },
+        Tile::Window => Description {
+            name: Text::new(vec![StyledString::plain_text("a window".to_string())]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "You can see through the glass, and shoot through it too.".to_string(),
+            )])),
+        },
         Tile::Street => Description {
             name: Text::new(vec![StyledString::plain_text("the street".to_string())]),
             description: None,
@@ -1185,6 +2311,17 @@ fn describe_tile(tile: Tile) -> Description {
             name: Text::new(vec![StyledString::plain_text("a sidewalk".to_string())]),
             description: None,
         },
+        Tile::Water => Description {
+            name: Text::new(vec![StyledString::plain_text("the river".to_string())]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Deep, dark and fast flowing. Swimming is slow and leaves you short of breath."
+                    .to_string(),
+            )])),
+        },
+        Tile::Bridge => Description {
+            name: Text::new(vec![StyledString::plain_text("a bridge".to_string())]),
+            description: None,
+        },
         Tile::DoorClosed => Description {
             name: Text::new(vec![StyledString::plain_text("a closed door".to_string())]),
             description: None,
@@ -1193,6 +2330,14 @@ fn describe_tile(tile: Tile) -> Description {
             name: Text::new(vec![StyledString::plain_text("an open door".to_string())]),
             description: None,
         },
+        Tile::DoorBarricaded => Description {
+            name: Text::new(vec![StyledString::plain_text(
+                "a barricaded door".to_string(),
+            )]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Planks are nailed across this door. It will take a while to bash down.".to_string(),
+            )])),
+        },
         Tile::StairsDown => Description {
             name: Text::new(vec![StyledString::plain_text(
                 "a downwards elevator shaft".to_string(),
@@ -1207,7 +2352,10 @@ fn describe_tile(tile: Tile) -> Description {
         },
         Tile::Debris => Description {
             name: Text::new(vec![StyledString::plain_text("some debris".to_string())]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Too high to step over, but claws or a cybernetic skeleton would let you climb it."
+                    .to_string(),
+            )])),
         },
         Tile::DebrisBurning => Description {
             name: Text::new(vec![StyledString::plain_text(
@@ -1437,46 +2585,387 @@ fn describe_tile(tile: Tile) -> Description {
                     string: "rocket launcher".to_string(),
                     style: Style::new()
                         .with_bold(true)
-                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+                },
+            ]),
+            description: None,
+        },
+        Tile::Item(Item::PistolAmmo) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                StyledString {
+                    string: "pistol bullets".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::PISTOL.to_rgba32(255)),
+                },
+            ]),
+            description: None,
+        },
+        Tile::Item(Item::ShotgunAmmo) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                StyledString {
+                    string: "shotgun shells".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SHOTGUN.to_rgba32(255)),
+                },
+            ]),
+            description: None,
+        },
+        Tile::Item(Item::Rocket) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "rocket".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+                },
+            ]),
+            description: None,
+        },
+        Tile::Item(Item::SniperRifle) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "sniper rifle".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Its rounds pass straight through the first person they hit.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::SniperRounds) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                StyledString {
+                    string: "sniper rounds".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+                },
+            ]),
+            description: None,
+        },
+        Tile::Item(Item::Drone) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "scout drone".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::DRONE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Deploy it to scout ahead of you.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::Turret) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "turret".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::TURRET.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Place it to have it fire at nearby enemies.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::CorruptedStimpack) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "corrupted stimpack".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Heals, but also increases radiation.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::CursedPistol) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "cursed pistol".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Occasionally fires on its own.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::Scanner) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "scanner".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SCANNER.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Identifies the items you are carrying. Single use.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::GeigerCounter) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "geiger counter".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::GEIGER_COUNTER.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Clicks in the presence of radiation. Use it to show the radiation levels around you."
+                    .to_string(),
+            )])),
+        },
+        Tile::Item(Item::Schematics) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                StyledString {
+                    string: "building schematics".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SCHEMATICS.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Reveals the walls, doors and stairs of the current level.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::MarketReport) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                item_styled_string_for_message(Item::MarketReport),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Lists the going rate for each type of organ on the black market. Prices shift \
+                between levels, and drop when too many of the same organ are sold."
+                    .to_string(),
+            )])),
+        },
+        Tile::Item(Item::Anesthetic) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "anesthetic".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::ANESTHETIC.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Clinics use one of these from your inventory when operating on you. Surgery without one hurts.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::Bandage) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "bandage".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::BANDAGE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Apply to stop bleeding.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::EmpGrenade) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "EMP grenade".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::EMP_GRENADE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Apply to throw. Damages robots and knocks out cybernetic organs nearby, including yours.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::SmokeGrenade) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "smoke grenade".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SMOKE_GRENADE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Apply to throw. Fills the area around where it lands with smoke which nobody can see through for a while.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::GasMask) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "gas mask".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::GAS_MASK.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Apply to wear. Protects you from smoke and poison gas, but its filter wears out with use.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::HazardSuit) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "hazard suit".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::HAZARD_SUIT.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Apply to wear. Shields you from radiation until it wears out.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::RawMeat) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                item_styled_string_for_message(Item::RawMeat),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Consume to increase food, at the risk of food poisoning. Apply next to a fire to cook it. Spoils if kept too long.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::CookedMeat) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                item_styled_string_for_message(Item::CookedMeat),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Consume to increase food. Keeps for longer than raw meat, but will still spoil eventually.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::RottenMeat) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("some ".to_string()),
+                item_styled_string_for_message(Item::RottenMeat),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Consume to increase food a little. You will get food poisoning.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::Readable(document)) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                item_styled_string_for_message(Item::Readable(document)),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(format!(
+                "\"{}\". Apply to read. Anything you've read can be read again from the pause menu.",
+                document.title()
+            ))])),
+        },
+        Tile::Item(Item::Antipsychotic) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "antipsychotic".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::ANTIPSYCHOTIC.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Eases the psychosis brought on by too many cybernetic organs.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::Immunosuppressant) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "immunosuppressant".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::IMMUNOSUPPRESSANT.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Consume to stop your body rejecting organs harvested from corpses for a while.".to_string(),
+            )])),
+        },
+        Tile::Item(Item::FieldSurgeryKit) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "field surgery kit".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::FIELD_SURGERY_KIT.to_rgba32(255)),
                 },
             ]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Install an organ from a container or remove one without visiting a clinic. Much riskier than a professional job, and takes several turns. Single use.".to_string(),
+            )])),
         },
-        Tile::Item(Item::PistolAmmo) => Description {
+        Tile::Item(Item::Plank) => Description {
             name: Text::new(vec![
-                StyledString::plain_text("some ".to_string()),
+                StyledString::plain_text("a ".to_string()),
                 StyledString {
-                    string: "pistol bullets".to_string(),
+                    string: "plank".to_string(),
                     style: Style::new()
                         .with_bold(true)
-                        .with_foreground(colours::PISTOL.to_rgba32(255)),
+                        .with_foreground(colours::PLANK.to_rgba32(255)),
                 },
             ]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Nail it across an adjacent closed door to make it harder to bash down.".to_string(),
+            )])),
         },
-        Tile::Item(Item::ShotgunAmmo) => Description {
+        Tile::UnlabeledSyringe => Description {
             name: Text::new(vec![
-                StyledString::plain_text("some ".to_string()),
+                StyledString::plain_text("an ".to_string()),
                 StyledString {
-                    string: "shotgun shells".to_string(),
+                    string: "unlabeled syringe".to_string(),
                     style: Style::new()
                         .with_bold(true)
-                        .with_foreground(colours::SHOTGUN.to_rgba32(255)),
+                        .with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
                 },
             ]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Who knows what's in it? Use it or scan it to find out.".to_string(),
+            )])),
         },
-        Tile::Item(Item::Rocket) => Description {
+        Tile::StrangeDevice => Description {
             name: Text::new(vec![
                 StyledString::plain_text("a ".to_string()),
                 StyledString {
-                    string: "rocket".to_string(),
+                    string: "strange device".to_string(),
                     style: Style::new()
                         .with_bold(true)
-                        .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
+                        .with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
                 },
             ]),
-            description: None,
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Some kind of machine. Use it or scan it to find out what it does.".to_string(),
+            )])),
         },
         Tile::Zombie => Description {
             name: Text::new(vec![
@@ -1559,7 +3048,7 @@ fn describe_tile(tile: Tile) -> Description {
                 },
             ]),
             description: Some(Text::new(vec![StyledString::plain_text(
-                "Spreads poison.".to_string(),
+                "Breathes out clouds of poison gas which drift around it.".to_string(),
             )])),
         },
         Tile::Divider => Description {
@@ -1630,6 +3119,90 @@ fn describe_tile(tile: Tile) -> Description {
                 "The source of the corruption aflicting the city. Destroy it!".to_string(),
             )])),
         },
+        Tile::Mercenary => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "mercenary".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::MERCENARY.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "A gun for hire. Walk into them to hire them. Once hired they follow you, fight hostiles, and use any gun you give them.".to_string(),
+            )])),
+        },
+        Tile::Turret => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "turret".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::TURRET.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "An automated turret. It fires at hostiles until it runs out of ammo. Walk into it to pick it up.".to_string(),
+            )])),
+        },
+        Tile::Guard => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "security guard".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::GUARD.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Reinforcements summoned by an alarm.".to_string(),
+            )])),
+        },
+        Tile::BountyHunter => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "bounty hunter".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::BOUNTY_HUNTER.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Here to collect the price on your head. Always knows roughly where you are, and follows you between levels.".to_string(),
+            )])),
+        },
+        Tile::Sentinel => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "sentinel".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SENTINEL.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "A security robot. Its hits can knock out your cybernetic organs. Vulnerable to EMP.".to_string(),
+            )])),
+        },
+        Tile::Civilian => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "civilian".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::CIVILIAN.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "An unarmed local who runs from trouble. The vendors on this level won't take kindly to you killing them.".to_string(),
+            )])),
+        },
         Tile::GunStore => Description {
             name: Text::new(vec![
                 StyledString::plain_text("a ".to_string()),
@@ -1641,7 +3214,7 @@ fn describe_tile(tile: Tile) -> Description {
                 },
             ]),
             description: Some(Text::new(vec![StyledString::plain_text(
-                "Walk into them to buy guns.".to_string(),
+                "Walk into them to buy or sell guns.".to_string(),
             )])),
         },
         Tile::ItemStore => Description {
@@ -1655,7 +3228,7 @@ fn describe_tile(tile: Tile) -> Description {
                 },
             ]),
             description: Some(Text::new(vec![StyledString::plain_text(
-                "Walk into them to buy items.".to_string(),
+                "Walk into them to buy or sell items.".to_string(),
             )])),
         },
         Tile::OrganTrader => Description {
@@ -1665,11 +3238,12 @@ fn describe_tile(tile: Tile) -> Description {
                     string: "organ trader".to_string(),
                     style: Style::new()
                         .with_bold(true)
-                        .with_foreground(colours::SHOP_BG.to_rgba32(255)),
+                        .with_foreground(colours::SHOP_ORGAN_TRADER.to_rgba32(255)),
                 },
             ]),
             description: Some(Text::new(vec![StyledString::plain_text(
-                "Buys and sells organs".to_string(),
+                "Buys and sells organs. Walk into them to sell organs from your organ containers."
+                    .to_string(),
             )])),
         },
         Tile::OrganClinic => Description {
@@ -1686,6 +3260,169 @@ fn describe_tile(tile: Tile) -> Description {
                 "Walk into them to add or remove organs.".to_string(),
             )])),
         },
+        Tile::Merchant => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "travelling merchant".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::SHOP_MERCHANT.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Sells rare goods and buys almost anything. Walk into them to trade, but be quick as they move between levels.".to_string(),
+            )])),
+        },
+        Tile::Drone => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("your ".to_string()),
+                StyledString {
+                    string: "scout drone".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::DRONE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Press x to take control of it. Fly it back to you to recall it.".to_string(),
+            )])),
+        },
+        Tile::AlarmPanel => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "alarm panel".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::ALARM_PANEL.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Attacking a vendor or firing a weapon in view of it will summon security guards.".to_string(),
+            )])),
+        },
+        Tile::Generator => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "generator".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::GENERATOR.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Powers the elevators and lights on this level. Walk into it to start it up.".to_string(),
+            )])),
+        },
+        Tile::ExplosiveBarrel => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "explosive barrel".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::EXPLOSIVE_BARREL.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Full of fuel. Explodes when shot or caught in a blast, setting off any other barrels nearby.".to_string(),
+            )])),
+        },
+        Tile::Stash => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("your ".to_string()),
+                StyledString {
+                    string: "storage locker".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::STASH.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Walk into it to deposit or withdraw items. Anything left here stays here while you explore other levels.".to_string(),
+            )])),
+        },
+        Tile::Terminal => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "terminal".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::TERMINAL.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Still running on backup power. Walk into it to read the log on its screen.".to_string(),
+            )])),
+        },
+        Tile::Prop(Prop::AbandonedCar) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an ".to_string()),
+                StyledString {
+                    string: "abandoned car".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::ABANDONED_CAR.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "The doors hang open and the keys are still in the ignition. Whoever was driving left in a hurry.".to_string(),
+            )])),
+        },
+        Tile::Prop(Prop::VendingMachine) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a ".to_string()),
+                StyledString {
+                    string: "vending machine".to_string(),
+                    style: Style::new()
+                        .with_bold(true)
+                        .with_foreground(colours::VENDING_MACHINE.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "The glass has been smashed and most of the shelves picked clean. Its display still flickers with adverts.".to_string(),
+            )])),
+        },
+        Tile::Prop(Prop::Graffiti) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("a wall covered in ".to_string()),
+                StyledString {
+                    string: "graffiti".to_string(),
+                    style: Style::plain_text().with_foreground(colours::GRAFFITI.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Warnings about the tentacles, names of the missing, and a crude map of the way out - mostly painted over.".to_string(),
+            )])),
+        },
+        Tile::Prop(Prop::Bloodstain) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("an old ".to_string()),
+                StyledString {
+                    string: "bloodstain".to_string(),
+                    style: Style::plain_text().with_foreground(colours::BLOODSTAIN.to_rgba32(255)),
+                },
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Dried brown at the edges. Drag marks lead away from it.".to_string(),
+            )])),
+        },
+        Tile::Prop(Prop::Remains) => Description {
+            name: Text::new(vec![
+                StyledString::plain_text("the ".to_string()),
+                StyledString {
+                    string: "remains".to_string(),
+                    style: Style::plain_text().with_foreground(colours::REMAINS.to_rgba32(255)),
+                },
+                StyledString::plain_text(" of a civilian".to_string()),
+            ]),
+            description: Some(Text::new(vec![StyledString::plain_text(
+                "Someone who didn't make it out. There may be something useful in their pockets.".to_string(),
+            )])),
+        },
         Tile::Corpse(npc_type) => match npc_type {
             NpcType::Zombie => Description {
                 name: Text::new(vec![
@@ -1793,35 +3530,107 @@ fn describe_tile(tile: Tile) -> Description {
                     },
                 ])),
             },
-            NpcType::Venter => Description {
+            NpcType::Venter => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of a ".to_string()),
+                    StyledString {
+                        string: "venter".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::VENTER.to_rgba32(255)),
+                    },
+                ]),
+                description: Some(Text::new(vec![
+                    StyledString::plain_text("Emits ".to_string()),
+                    StyledString {
+                        string: "smoke".to_string(),
+                        style: Style::new()
+                            .with_bold(false)
+                            .with_foreground(colours::VENTER.to_rgba32(255)),
+                    },
+                ])),
+            },
+
+            NpcType::Corruptor => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of the ".to_string()),
+                    StyledString {
+                        string: "CORRUPTOR".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::CORRUPTOR.to_rgba32(255)),
+                    },
+                ]),
+                description: None,
+            },
+            NpcType::Mercenary => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of a ".to_string()),
+                    StyledString {
+                        string: "mercenary".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::MERCENARY.to_rgba32(255)),
+                    },
+                ]),
+                description: None,
+            },
+            NpcType::Turret => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of a ".to_string()),
+                    StyledString {
+                        string: "turret".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::TURRET.to_rgba32(255)),
+                    },
+                ]),
+                description: None,
+            },
+            NpcType::Guard => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of a ".to_string()),
+                    StyledString {
+                        string: "security guard".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::GUARD.to_rgba32(255)),
+                    },
+                ]),
+                description: None,
+            },
+            NpcType::BountyHunter => Description {
                 name: Text::new(vec![
                     StyledString::plain_text("the corpse of a ".to_string()),
                     StyledString {
-                        string: "venter".to_string(),
+                        string: "bounty hunter".to_string(),
                         style: Style::new()
                             .with_bold(true)
-                            .with_foreground(colours::VENTER.to_rgba32(255)),
+                            .with_foreground(colours::BOUNTY_HUNTER.to_rgba32(255)),
                     },
                 ]),
-                description: Some(Text::new(vec![
-                    StyledString::plain_text("Emits ".to_string()),
+                description: None,
+            },
+            NpcType::Sentinel => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the wreck of a ".to_string()),
                     StyledString {
-                        string: "smoke".to_string(),
+                        string: "sentinel".to_string(),
                         style: Style::new()
-                            .with_bold(false)
-                            .with_foreground(colours::VENTER.to_rgba32(255)),
+                            .with_bold(true)
+                            .with_foreground(colours::SENTINEL.to_rgba32(255)),
                     },
-                ])),
+                ]),
+                description: None,
             },
-
-            NpcType::Corruptor => Description {
+            NpcType::Civilian => Description {
                 name: Text::new(vec![
-                    StyledString::plain_text("the corpse of the ".to_string()),
+                    StyledString::plain_text("the corpse of a ".to_string()),
                     StyledString {
-                        string: "CORRUPTOR".to_string(),
+                        string: "civilian".to_string(),
                         style: Style::new()
                             .with_bold(true)
-                            .with_foreground(colours::CORRUPTOR.to_rgba32(255)),
+                            .with_foreground(colours::CIVILIAN.to_rgba32(255)),
                     },
                 ]),
                 description: None,
@@ -1869,7 +3678,19 @@ fn describe_tile(tile: Tile) -> Description {
                         string: "organ trader".to_string(),
                         style: Style::new()
                             .with_bold(true)
-                            .with_foreground(colours::SHOP_BG.to_rgba32(255)),
+                            .with_foreground(colours::SHOP_ORGAN_TRADER.to_rgba32(255)),
+                    },
+                ]),
+                description: None,
+            },
+            NpcType::Merchant => Description {
+                name: Text::new(vec![
+                    StyledString::plain_text("the corpse of a ".to_string()),
+                    StyledString {
+                        string: "travelling merchant".to_string(),
+                        style: Style::new()
+                            .with_bold(true)
+                            .with_foreground(colours::SHOP_MERCHANT.to_rgba32(255)),
                     },
                 ]),
                 description: None,
@@ -1941,6 +3762,42 @@ fn npc_type_to_styled_string(npc_type: NpcType) -> text::StyledString {
                 .with_bold(true)
                 .with_foreground(colours::CORRUPTOR.to_rgba32(255)),
         },
+        NpcType::Mercenary => StyledString {
+            string: "mercenary".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::MERCENARY.to_rgba32(255)),
+        },
+        NpcType::Turret => StyledString {
+            string: "turret".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::TURRET.to_rgba32(255)),
+        },
+        NpcType::Guard => StyledString {
+            string: "security guard".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::GUARD.to_rgba32(255)),
+        },
+        NpcType::BountyHunter => StyledString {
+            string: "bounty hunter".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::BOUNTY_HUNTER.to_rgba32(255)),
+        },
+        NpcType::Sentinel => StyledString {
+            string: "sentinel".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SENTINEL.to_rgba32(255)),
+        },
+        NpcType::Civilian => StyledString {
+            string: "civilian".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::CIVILIAN.to_rgba32(255)),
+        },
         NpcType::GunStore => StyledString {
             string: "gun vendor".to_string(),
             style: Style::new()
@@ -1963,12 +3820,18 @@ fn npc_type_to_styled_string(npc_type: NpcType) -> text::StyledString {
             string: "organ trader".to_string(),
             style: Style::new()
                 .with_bold(true)
-                .with_foreground(colours::SHOP_BG.to_rgba32(255)),
+                .with_foreground(colours::SHOP_ORGAN_TRADER.to_rgba32(255)),
+        },
+        NpcType::Merchant => StyledString {
+            string: "travelling merchant".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SHOP_MERCHANT.to_rgba32(255)),
         },
     }
 }
 
-pub fn message_to_text(message: Message) -> Text {
+pub fn message_to_text(message: Message, game: &game::Game, combat_log: CombatLog) -> Text {
     use text::*;
     match message {
         Message::Wait => Text::new(vec![StyledString::plain_text(
@@ -1983,6 +3846,9 @@ pub fn message_to_text(message: Message) -> Text {
         Message::OpenDoor => Text::new(vec![StyledString::plain_text(
             "You open the door.".to_string(),
         )]),
+        Message::Climb => Text::new(vec![StyledString::plain_text(
+            "You haul yourself up and over. It's slow going.".to_string(),
+        )]),
         Message::CloseDoor => Text::new(vec![StyledString::plain_text(
             "You close the door.".to_string(),
         )]),
@@ -2073,13 +3939,34 @@ pub fn message_to_text(message: Message) -> Text {
             }
             ActionError::RefusingToTargetSelf => "Refusing to target self.".to_string(),
             ActionError::NoBodyGuns => "No active Cronenberg guns installed.".to_string(),
+            ActionError::NoDrone => "You have no deployed drone.".to_string(),
+            ActionError::DroneAlreadyDeployed => "Your drone is already deployed.".to_string(),
+            ActionError::NothingToScan => "You have nothing that needs scanning.".to_string(),
+            ActionError::NoDoorToBarricade => {
+                "There's no closed door next to you to barricade.".to_string()
+            }
+            ActionError::GeneratorNeedsBattery => {
+                "The generator is out of fuel. It could run off a battery.".to_string()
+            }
+            ActionError::AnestheticNeedsSurgery => {
+                "Better save it for your next visit to a clinic.".to_string()
+            }
+            ActionError::NothingToOperateOn => {
+                "You have no organs to remove and no organs in containers to install.".to_string()
+            }
+            ActionError::ImmunosuppressantIsFull => {
+                "You can't take any more immunosuppressants for now.".to_string()
+            }
+            ActionError::NotBleeding => "But you aren't bleeding.".to_string(),
+            ActionError::NotPsychotic => "But your mind is clear.".to_string(),
+            ActionError::NotThrowable => "You can't throw that.".to_string(),
         })]),
         Message::NpcHit { npc_type, damage } => Text::new(vec![
             StyledString::plain_text("The ".to_string()),
             npc_type_to_styled_string(npc_type),
             StyledString::plain_text(" is hit for ".to_string()),
             StyledString {
-                string: format!("{damage}"),
+                string: damage_string(&damage, combat_log),
                 style: Style::plain_text().with_bold(true),
             },
             StyledString::plain_text(" damage.".to_string()),
@@ -2089,6 +3976,7 @@ pub fn message_to_text(message: Message) -> Text {
             npc_type_to_styled_string(npc_type),
             StyledString::plain_text(" dies.".to_string()),
         ]),
+        Message::Script(text) => Text::new(vec![StyledString::plain_text(text)]),
         Message::PlayerHit {
             attacker_npc_type,
             damage,
@@ -2097,7 +3985,7 @@ pub fn message_to_text(message: Message) -> Text {
             npc_type_to_styled_string(attacker_npc_type),
             StyledString::plain_text(" hits you for ".to_string()),
             StyledString {
-                string: format!("{damage}"),
+                string: damage_string(&damage, combat_log),
                 style: Style::plain_text().with_bold(true),
             },
             StyledString::plain_text(" damage.".to_string()),
@@ -2114,12 +4002,12 @@ pub fn message_to_text(message: Message) -> Text {
         ]),
         Message::GetItem(item) => Text::new(vec![
             StyledString::plain_text("You pick up the ".to_string()),
-            item_styled_string_for_message(item),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
             StyledString::plain_text(".".to_string()),
         ]),
         Message::DropItem(item) => Text::new(vec![
             StyledString::plain_text("You drop the ".to_string()),
-            item_styled_string_for_message(item),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
             StyledString::plain_text(".".to_string()),
         ]),
         Message::UnequipItem(item) => Text::new(vec![
@@ -2228,98 +4116,532 @@ pub fn message_to_text(message: Message) -> Text {
             StyledString::plain_text(organ_trait_name(trait_).to_string()),
             StyledString::plain_text(".".to_string()),
         ]),
-        Message::AmbientRadiation => Text::new(vec![
-            StyledString::plain_text("You absorb some ambient ".to_string()),
-            StyledString {
-                string: "radiation".to_string(),
-                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
-            },
-            StyledString::plain_text(".".to_string()),
+        Message::AmbientRadiation => Text::new(vec![
+            StyledString::plain_text("You absorb some ambient ".to_string()),
+            StyledString {
+                string: "radiation".to_string(),
+                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
+            },
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::DigestFood { health_gain } => Text::new(vec![
+            StyledString::plain_text("You digest some ".to_string()),
+            StyledString {
+                string: "food".to_string(),
+                style: Style::plain_text().with_foreground(colours::FOOD.to_rgba32(255)),
+            },
+            StyledString::plain_text(format!(" gaining {health_gain} health.")),
+        ]),
+        Message::DigestFoodNoHealthIncrease => Text::new(vec![
+            StyledString::plain_text("You digest some ".to_string()),
+            StyledString {
+                string: "food".to_string(),
+                style: Style::plain_text().with_foreground(colours::FOOD.to_rgba32(255)),
+            },
+            StyledString::plain_text(format!(".")),
+        ]),
+        Message::ClawDrop(item) => Text::new(vec![
+            StyledString::plain_text("You drop your ".to_string()),
+            item_styled_string_for_message(item),
+            StyledString::plain_text(" (can't hold in claw).".to_string()),
+        ]),
+        Message::LackOfOxygen => Text::new(vec![
+            StyledString::plain_text("You are damaged by a lack of ".to_string()),
+            StyledString {
+                string: "oxygen".to_string(),
+                style: Style::plain_text().with_foreground(
+                    colours::OXYGEN
+                        .to_rgba32(255)
+                        .saturating_scalar_mul_div(3, 2),
+                ),
+            },
+            StyledString::plain_text(format!(".")),
+        ]),
+        Message::Smoke => Text::new(vec![StyledString::plain_text(
+            "The smoke makes it hard to breath here.".to_string(),
+        )]),
+        Message::RadiationClose => Text::new(vec![
+            StyledString::plain_text("You absorb ".to_string()),
+            StyledString {
+                string: "radiation".to_string(),
+                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
+            },
+            StyledString::plain_text(" from a nearby source.".to_string()),
+        ]),
+        Message::RadiationVeryClose => Text::new(vec![
+            StyledString::plain_text("You absorb ".to_string()),
+            StyledString {
+                string: "radiation".to_string(),
+                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
+            },
+            StyledString::plain_text(" from a very nearby source.".to_string()),
+        ]),
+        Message::WornItemFallsApart(item) => Text::new(vec![
+            StyledString::plain_text("Your ".to_string()),
+            item_styled_string_for_message(item),
+            StyledString::plain_text(" is worn out and falls apart!".to_string()),
+        ]),
+        Message::PoisonGas => Text::new(vec![
+            StyledString::plain_text("You breathe in ".to_string()),
+            StyledString {
+                string: "poison gas".to_string(),
+                style: Style::plain_text().with_foreground(colours::POISON.to_rgba32(255)),
+            },
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::Poison => Text::new(vec![
+            StyledString::plain_text("You are being ".to_string()),
+            StyledString {
+                string: "poisoned".to_string(),
+                style: Style::plain_text().with_foreground(colours::POISON.to_rgba32(255)),
+            },
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::AcidBurn => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            StyledString {
+                string: "acid".to_string(),
+                style: Style::plain_text().with_foreground(colours::ACID.to_rgba32(255)),
+            },
+            StyledString::plain_text(" burns you!".to_string()),
+        ]),
+        Message::Electrocuted => Text::new(vec![
+            StyledString::plain_text("You are ".to_string()),
+            StyledString {
+                string: "electrocuted".to_string(),
+                style: Style::plain_text().with_foreground(colours::ELECTRIC.to_rgba32(255)),
+            },
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::Sinking => Text::new(vec![
+            StyledString::plain_text("Your heavy weapon is dragging you under! ".to_string()),
+            StyledString {
+                string: "Drop it or drown.".to_string(),
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::WATER_FOREGROUND.to_rgba32(255)),
+            },
+        ]),
+        Message::ItemSinks(item) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
+            StyledString::plain_text(" sinks out of sight.".to_string()),
+        ]),
+        Message::BecomesHostile(npc_type) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" becomes hostile.".to_string()),
+        ]),
+        Message::DisguiseBlown(npc_type) => Text::new(vec![
+            StyledString::plain_text("Your disguise is blown! Every ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" knows you're not one of them.".to_string()),
+        ]),
+        Message::MerchantArrives => Text::new(vec![
+            StyledString::plain_text("You hear that a ".to_string()),
+            npc_type_to_styled_string(NpcType::Merchant),
+            StyledString::plain_text(" has arrived on this level.".to_string()),
+        ]),
+        Message::MerchantLeaves => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(NpcType::Merchant),
+            StyledString::plain_text(" moves on to another level.".to_string()),
+        ]),
+        Message::BountyPosted => Text::new(vec![StyledString::plain_text(
+            "Word of what you've done spreads. Someone has put a price on your head.".to_string(),
+        )]),
+        Message::BountyHunterArrives => Text::new(vec![
+            StyledString::plain_text("A ".to_string()),
+            npc_type_to_styled_string(NpcType::BountyHunter),
+            StyledString::plain_text(" has tracked you to this level!".to_string()),
+        ]),
+        Message::NpcCorrupted(npc_type) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" is overcome by the ".to_string()),
+            StyledString {
+                string: "corruption".to_string(),
+                style: Style::plain_text().with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::VendorsRaisePrices => Text::new(vec![StyledString::plain_text(
+            "Word of the killing spreads. The vendors here raise their prices.".to_string(),
+        )]),
+        Message::StealItem(item) => Text::new(vec![
+            StyledString::plain_text("You take the ".to_string()),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
+            StyledString::plain_text(" without paying.".to_string()),
+        ]),
+        Message::TheftNoticed(npc_type) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" notices what you stole!".to_string()),
+        ]),
+        Message::HotOrganDamaged(organ) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            StyledString::plain_text(organ_string_for_description(&organ)),
+            StyledString::plain_text(" was damaged when it was stolen. No refunds.".to_string()),
+        ]),
+        Message::CantAfford(item) => Text::new(vec![
+            StyledString::plain_text("You can't afford that ".to_string()),
+            item_styled_string_for_message(item),
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::Buy(item) => Text::new(vec![
+            StyledString::plain_text("You buy the ".to_string()),
+            item_styled_string_for_message(item),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::Sell { item, price } => Text::new(vec![
+            StyledString::plain_text("You sell the ".to_string()),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
+            StyledString::plain_text(" for ".to_string()),
+            StyledString {
+                string: format!("{price} CCz"),
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::MONEY.to_rgba32(255)),
+            },
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::SellOrgan { organ, price } => Text::new(vec![
+            StyledString::plain_text("You sell ".to_string()),
+            StyledString::plain_text(organ_string_for_description(&organ)),
+            StyledString::plain_text(" for ".to_string()),
+            StyledString {
+                string: format!("{price} CCz"),
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::MONEY.to_rgba32(255)),
+            },
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::StashDeposit(item) => Text::new(vec![
+            StyledString::plain_text("You deposit the ".to_string()),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
+            StyledString::plain_text(" in your storage locker.".to_string()),
+        ]),
+        Message::StashWithdraw(item) => Text::new(vec![
+            StyledString::plain_text("You withdraw the ".to_string()),
+            item_appearance_styled_string_for_message(game.item_appearance(item)),
+            StyledString::plain_text(" from your storage locker.".to_string()),
+        ]),
+        Message::StashIsFull => Text::new(vec![StyledString::plain_text(
+            "Your storage locker is full.".to_string(),
+        )]),
+        Message::HireMercenary => Text::new(vec![
+            StyledString::plain_text("You hire the ".to_string()),
+            npc_type_to_styled_string(NpcType::Mercenary),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::GiveMercenaryGun(item) => Text::new(vec![
+            StyledString::plain_text("You give the ".to_string()),
+            item_styled_string_for_message(item),
+            StyledString::plain_text(" to the ".to_string()),
+            npc_type_to_styled_string(NpcType::Mercenary),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::MercenaryFollows => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(NpcType::Mercenary),
+            StyledString::plain_text(" follows you.".to_string()),
+        ]),
+        Message::DeployDrone => Text::new(vec![
+            StyledString::plain_text("You deploy the ".to_string()),
+            item_styled_string_for_message(Item::Drone),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::RecallDrone => Text::new(vec![
+            StyledString::plain_text("You recall the ".to_string()),
+            item_styled_string_for_message(Item::Drone),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::ControlDrone => Text::new(vec![
+            StyledString::plain_text("You take control of the ".to_string()),
+            item_styled_string_for_message(Item::Drone),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::ControlPlayer => Text::new(vec![StyledString::plain_text(
+            "You return your attention to your surroundings.".to_string(),
+        )]),
+        Message::StartSprinting => Text::new(vec![StyledString::plain_text(
+            "You break into a sprint.".to_string(),
+        )]),
+        Message::StopSprinting => Text::new(vec![StyledString::plain_text(
+            "You slow to a walk.".to_string(),
+        )]),
+        Message::OutOfBreath => Text::new(vec![StyledString::plain_text(
+            "You're too out of breath to keep sprinting!".to_string(),
+        )]),
+        Message::DroneDestroyed => Text::new(vec![
+            StyledString::plain_text("Your ".to_string()),
+            item_styled_string_for_message(Item::Drone),
+            StyledString::plain_text(" is destroyed!".to_string()),
+        ]),
+        Message::DroneLost => Text::new(vec![
+            StyledString::plain_text("Your inventory is full so you leave the ".to_string()),
+            item_styled_string_for_message(Item::Drone),
+            StyledString::plain_text(" behind.".to_string()),
+        ]),
+        Message::DeployTurret => Text::new(vec![
+            StyledString::plain_text("You place the ".to_string()),
+            item_styled_string_for_message(Item::Turret),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::AlarmTriggered => Text::new(vec![StyledString {
+            string: "An alarm starts blaring!".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::ALARM_PANEL.to_rgba32(255)),
+        }]),
+        Message::Reinforcements => Text::new(vec![
+            StyledString::plain_text("Reinforcements arrive: ".to_string()),
+            npc_type_to_styled_string(NpcType::Guard),
+            StyledString::plain_text("s storm in from the stairs!".to_string()),
+        ]),
+        Message::AlarmStops => Text::new(vec![StyledString::plain_text(
+            "The alarm falls silent.".to_string(),
+        )]),
+        Message::ApplyCorruptedStimpack => Text::new(vec![
+            StyledString::plain_text("You apply the stimpack but it was ".to_string()),
+            StyledString {
+                string: "corrupted".to_string(),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+            },
+            StyledString::plain_text(" (health increased, radiation increased).".to_string()),
+        ]),
+        Message::CursedGunFires => Text::new(vec![
+            StyledString::plain_text("Your pistol fires on its own! It's a ".to_string()),
+            item_styled_string_for_message(Item::CursedPistol),
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::Scan(items) => {
+            let mut parts = vec![StyledString::plain_text(
+                "The scanner identifies: ".to_string(),
+            )];
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    parts.push(StyledString::plain_text(", ".to_string()));
+                }
+                parts.push(item_styled_string_for_message(item));
+            }
+            parts.push(StyledString::plain_text(".".to_string()));
+            Text::new(parts)
+        }
+        Message::BarricadeDoor => Text::new(vec![
+            StyledString::plain_text("You nail a ".to_string()),
+            item_styled_string_for_message(Item::Plank),
+            StyledString::plain_text(" across the door.".to_string()),
+        ]),
+        Message::RemoveBarricade => Text::new(vec![StyledString::plain_text(
+            "You pry the barricade off the door.".to_string(),
+        )]),
+        Message::BarricadeGivesWay => Text::new(vec![StyledString::plain_text(
+            "A barricade gives way!".to_string(),
+        )]),
+        Message::DoorBreaks => Text::new(vec![StyledString::plain_text(
+            "A door is smashed to pieces!".to_string(),
+        )]),
+        Message::WindowShatters => Text::new(vec![StyledString::plain_text(
+            "A window shatters!".to_string(),
+        )]),
+        Message::ExplosiveBarrelDetonates => Text::new(vec![StyledString::plain_text(
+            "A barrel explodes!".to_string(),
+        )]),
+        Message::PowerRestored => Text::new(vec![StyledString::plain_text(
+            "The generator rumbles to life and the lights flicker on.".to_string(),
+        )]),
+        Message::ElevatorUnpowered => Text::new(vec![StyledString::plain_text(
+            "The elevator has no power. Find the generator.".to_string(),
+        )]),
+        Message::ReadDocument(document) => Text::new(vec![StyledString::plain_text(format!(
+            "You read \"{}\".",
+            document.title()
+        ))]),
+        Message::ReadSchematics => Text::new(vec![
+            StyledString::plain_text("You study the ".to_string()),
+            item_styled_string_for_message(Item::Schematics),
+            StyledString::plain_text(" and memorize the layout of the area.".to_string()),
+        ]),
+        Message::ReadMarketReport(prices) => {
+            let mut parts = vec![
+                StyledString::plain_text("You read the ".to_string()),
+                item_styled_string_for_message(Item::MarketReport),
+                StyledString::plain_text(". Organs are trading at:".to_string()),
+            ];
+            for price in prices {
+                let trend = if price.percent > price.previous_percent {
+                    "↑"
+                } else if price.percent < price.previous_percent {
+                    "↓"
+                } else {
+                    ""
+                };
+                parts.push(StyledString::plain_text(format!(
+                    " {} {}%{}",
+                    organ_type_name(price.organ_type),
+                    price.percent,
+                    trend
+                )));
+            }
+            parts.push(StyledString::plain_text(".".to_string()));
+            Text::new(parts)
+        }
+        Message::RadiationOverlayOn => Text::new(vec![
+            StyledString::plain_text("You switch your ".to_string()),
+            item_styled_string_for_message(Item::GeigerCounter),
+            StyledString::plain_text(" to display mode.".to_string()),
+        ]),
+        Message::RadiationOverlayOff => Text::new(vec![
+            StyledString::plain_text("You switch off your ".to_string()),
+            item_styled_string_for_message(Item::GeigerCounter),
+            StyledString::plain_text("'s display.".to_string()),
         ]),
-        Message::DigestFood { health_gain } => Text::new(vec![
-            StyledString::plain_text("You digest some ".to_string()),
-            StyledString {
-                string: "food".to_string(),
-                style: Style::plain_text().with_foreground(colours::FOOD.to_rgba32(255)),
-            },
-            StyledString::plain_text(format!(" gaining {health_gain} health.")),
+        Message::PickUpTurret => Text::new(vec![
+            StyledString::plain_text("You pick up the ".to_string()),
+            item_styled_string_for_message(Item::Turret),
+            StyledString::plain_text(".".to_string()),
         ]),
-        Message::DigestFoodNoHealthIncrease => Text::new(vec![
-            StyledString::plain_text("You digest some ".to_string()),
-            StyledString {
-                string: "food".to_string(),
-                style: Style::plain_text().with_foreground(colours::FOOD.to_rgba32(255)),
-            },
-            StyledString::plain_text(format!(".")),
+        Message::FillBloodVial => Text::new(vec![
+            StyledString::plain_text("You fill the ".to_string()),
+            item_styled_string_for_message(Item::BloodVialEmpty),
+            StyledString::plain_text(".".to_string()),
         ]),
-        Message::ClawDrop(item) => Text::new(vec![
-            StyledString::plain_text("You drop your ".to_string()),
+        Message::EatFood(item) => Text::new(vec![
+            StyledString::plain_text("You eat the ".to_string()),
             item_styled_string_for_message(item),
-            StyledString::plain_text(" (can't hold in claw).".to_string()),
+            StyledString::plain_text(" (food increased).".to_string()),
         ]),
-        Message::LackOfOxygen => Text::new(vec![
-            StyledString::plain_text("You are damaged by a lack of ".to_string()),
-            StyledString {
-                string: "oxygen".to_string(),
-                style: Style::plain_text().with_foreground(
-                    colours::OXYGEN
-                        .to_rgba32(255)
-                        .saturating_scalar_mul_div(3, 2),
-                ),
-            },
-            StyledString::plain_text(format!(".")),
+        Message::ApplyImmunosuppressant => Text::new(vec![
+            StyledString::plain_text("You apply the ".to_string()),
+            item_styled_string_for_message(Item::Immunosuppressant),
+            StyledString::plain_text(".".to_string()),
         ]),
-        Message::Smoke => Text::new(vec![StyledString::plain_text(
-            "The smoke makes it hard to breath here.".to_string(),
+        Message::ImmunosuppressantWearingOff => Text::new(vec![StyledString::plain_text(
+            "Your immunosuppressants are wearing off.".to_string(),
         )]),
-        Message::RadiationClose => Text::new(vec![
-            StyledString::plain_text("You absorb ".to_string()),
-            StyledString {
-                string: "radiation".to_string(),
-                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
-            },
-            StyledString::plain_text(" from a nearby source.".to_string()),
+        Message::ImmunosuppressantWoreOff => Text::new(vec![StyledString::plain_text(
+            "Your immunosuppressants have worn off. Your body may reject harvested organs."
+                .to_string(),
+        )]),
+        Message::OrganRejection(organ) => Text::new(vec![
+            StyledString::plain_text("Your body rejects the harvested ".to_string()),
+            StyledString::plain_text(organ_type_name(organ.type_).to_string()),
+            StyledString::plain_text("! It is damaged.".to_string()),
         ]),
-        Message::RadiationVeryClose => Text::new(vec![
-            StyledString::plain_text("You absorb ".to_string()),
-            StyledString {
-                string: "radiation".to_string(),
-                style: Style::plain_text().with_foreground(colours::RADIATION.to_rgba32(255)),
-            },
-            StyledString::plain_text(" from a very nearby source.".to_string()),
+        Message::OrganHit(organ) => Text::new(vec![
+            StyledString::plain_text("The blow damages your ".to_string()),
+            StyledString::plain_text(organ_type_name(organ.type_).to_string()),
+            StyledString::plain_text("!".to_string()),
         ]),
-        Message::Poison => Text::new(vec![
-            StyledString::plain_text("You are being ".to_string()),
-            StyledString {
-                string: "poisoned".to_string(),
-                style: Style::plain_text().with_foreground(colours::POISON.to_rgba32(255)),
-            },
-            StyledString::plain_text(".".to_string()),
+        Message::TumourDestroyed => Text::new(vec![StyledString::plain_text(
+            "The blow destroys one of your tumours.".to_string(),
+        )]),
+        Message::StartBleeding => Text::new(vec![StyledString::plain_text(
+            "You are bleeding! Apply a bandage to stop it.".to_string(),
+        )]),
+        Message::NpcStartBleeding(npc_type) => Text::new(vec![
+            StyledString::plain_text("The ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" is bleeding.".to_string()),
         ]),
-        Message::BecomesHostile(npc_type) => Text::new(vec![
+        Message::ApplyBandage => Text::new(vec![
+            StyledString::plain_text("You apply the ".to_string()),
+            item_styled_string_for_message(Item::Bandage),
+            StyledString::plain_text(" (bleeding stopped).".to_string()),
+        ]),
+        Message::CyberpsychosisOnset => Text::new(vec![StyledString::plain_text(
+            "So much of you is machine now. You feel your grip on reality slipping.".to_string(),
+        )]),
+        Message::CyberpsychosisSubsides => Text::new(vec![StyledString::plain_text(
+            "Your mind feels your own again.".to_string(),
+        )]),
+        Message::PsychosisStumble => Text::new(vec![StyledString::plain_text(
+            "Your limbs don't go where you tell them!".to_string(),
+        )]),
+        Message::HallucinationDispelled(npc_type) => Text::new(vec![
             StyledString::plain_text("The ".to_string()),
             npc_type_to_styled_string(npc_type),
-            StyledString::plain_text(" becomes hostile.".to_string()),
+            StyledString::plain_text(" was never there.".to_string()),
         ]),
-        Message::CantAfford(item) => Text::new(vec![
-            StyledString::plain_text("You can't afford that ".to_string()),
-            item_styled_string_for_message(item),
-            StyledString::plain_text("!".to_string()),
+        Message::ApplyAntipsychotic => Text::new(vec![
+            StyledString::plain_text("You take the ".to_string()),
+            item_styled_string_for_message(Item::Antipsychotic),
+            StyledString::plain_text(" (psychosis eased).".to_string()),
         ]),
-        Message::Buy(item) => Text::new(vec![
-            StyledString::plain_text("You buy the ".to_string()),
+        Message::Throw(item) => Text::new(vec![
+            StyledString::plain_text("You throw the ".to_string()),
             item_styled_string_for_message(item),
             StyledString::plain_text(".".to_string()),
         ]),
-        Message::FillBloodVial => Text::new(vec![
-            StyledString::plain_text("You fill the ".to_string()),
-            item_styled_string_for_message(Item::BloodVialEmpty),
-            StyledString::plain_text(".".to_string()),
+        Message::Overwatch(direction) => {
+            let direction = match direction {
+                CardinalDirection::North => "north",
+                CardinalDirection::East => "east",
+                CardinalDirection::South => "south",
+                CardinalDirection::West => "west",
+            };
+            Text::new(vec![StyledString::plain_text(format!(
+                "You take aim and watch the {}.",
+                direction
+            ))])
+        }
+        Message::OverwatchFire(npc_type) => Text::new(vec![
+            StyledString::plain_text("You fire at the ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(" as it moves into your sights!".to_string()),
         ]),
-        Message::EatFood => Text::new(vec![
-            StyledString::plain_text("You eat the ".to_string()),
-            item_styled_string_for_message(Item::Food),
-            StyledString::plain_text(" (food increased).".to_string()),
+        Message::EmpHitPlayer => Text::new(vec![StyledString::plain_text(
+            "An EMP washes over you! Your cybernetics go dark and your power drains.".to_string(),
+        )]),
+        Message::CyberneticsRestored => Text::new(vec![StyledString::plain_text(
+            "Your cybernetic organs reboot.".to_string(),
+        )]),
+        Message::LevelUp(level) => Text::new(vec![
+            StyledString::plain_text("You reach experience level ".to_string()),
+            StyledString {
+                string: format!("{}", level + 1),
+                style: Style::plain_text().with_bold(true),
+            },
+            StyledString::plain_text("!".to_string()),
+        ]),
+        Message::SkillImproved { skill, level } => Text::new(vec![
+            StyledString::plain_text("Your ".to_string()),
+            StyledString {
+                string: skill_string(skill).to_string(),
+                style: Style::plain_text().with_bold(true),
+            },
+            StyledString::plain_text(format!(" skill improves to level {level}.")),
+        ]),
+        Message::HackAlarmPanelProgress => Text::new(vec![StyledString::plain_text(
+            "You work at the alarm panel's wiring.".to_string(),
+        )]),
+        Message::HackAlarmPanel => Text::new(vec![StyledString::plain_text(
+            "You hack the alarm panel. It no longer watches for you.".to_string(),
+        )]),
+        Message::Tutorial(step) => {
+            let prompt = match step {
+                TutorialStep::Move => "Walk with the arrow keys. Head through the door into the next room.",
+                TutorialStep::Shoot => "There's a zombie in the room to the east. Press f to aim your pistol and fire at it.",
+                TutorialStep::Reload => "Press r to reload your pistol.",
+                TutorialStep::Harvest => "Pick up the organ container with g, stand on a corpse and apply the container with a to harvest an organ.",
+                TutorialStep::VisitClinic => "Collect the money and walk into the organ clinic to the south to have the harvested organ installed.",
+                TutorialStep::Done => "Tutorial complete! Press escape and start a new game when you're ready.",
+            };
+            Text::new(vec![StyledString {
+                string: prompt.to_string(),
+                style: Style::plain_text().with_foreground(colours::STAIRS.to_rgba32(255)),
+            }])
+        }
+        Message::ChoosePerk(perk) => Text::new(vec![
+            StyledString::plain_text("You gain the perk: ".to_string()),
+            StyledString {
+                string: perk_string(perk).to_string(),
+                style: Style::plain_text().with_bold(true),
+            },
         ]),
         Message::ApplyAntidote => Text::new(vec![
             StyledString::plain_text("You apply the ".to_string()),
@@ -2354,6 +4676,31 @@ pub fn message_to_text(message: Message) -> Text {
         Message::CantAffordGeneral => Text::new(vec![StyledString::plain_text(
             "You can't afford that!".to_string(),
         )]),
+        Message::BuyInsurance(price) => Text::new(vec![
+            StyledString::plain_text(
+                "You sign up for a CloneSure™ policy and give a tissue sample for ".to_string(),
+            ),
+            StyledString {
+                string: format!("{}", price),
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::MONEY.to_rgba32(255)),
+            },
+            StyledString::plain_text(" CyberCoinz™. If you die, you'll be back.".to_string()),
+        ]),
+        Message::AlreadyInsured => Text::new(vec![StyledString::plain_text(
+            "You are already covered by a CloneSure™ policy.".to_string(),
+        )]),
+        Message::CloneRespawn => Text::new(vec![StyledString::plain_text(
+            "You die... and wake up gasping in a vat at the clinic. Your CloneSure™ policy has \
+            paid out. Everything you were carrying is still on your old body."
+                .to_string(),
+        )]),
+        Message::RecoverRemains(num_items) => Text::new(vec![StyledString::plain_text(format!(
+            "You recover {} item{} from your old body.",
+            num_items,
+            if num_items == 1 { "" } else { "s" }
+        ))]),
         Message::NoSpaceForOrgan(organ) => Text::new(vec![
             StyledString::plain_text("There is no space in your body for the ".to_string()),
             StyledString::plain_text(organ_string_for_description(&organ)),
@@ -2364,6 +4711,68 @@ pub fn message_to_text(message: Message) -> Text {
             StyledString::plain_text(organ_string_for_description(&organ)),
             StyledString::plain_text(" is installed in your body.".to_string()),
         ]),
+        Message::SurgeryWithoutAnesthetic => Text::new(vec![StyledString::plain_text(
+            "You have no anesthetic. The surgery is agonizing.".to_string(),
+        )]),
+        Message::SurgeryFailed(organ) => Text::new(vec![
+            StyledString::plain_text("The surgery on the ".to_string()),
+            StyledString::plain_text(organ_string_for_description(&organ)),
+            StyledString::plain_text(" goes wrong. At least they don't charge you.".to_string()),
+        ]),
+        Message::RecoverFromSurgery => Text::new(vec![StyledString::plain_text(
+            "You have recovered from your surgery.".to_string(),
+        )]),
+        Message::FoodSpoiled => Text::new(vec![
+            StyledString::plain_text("Some of the meat you are carrying has gone ".to_string()),
+            StyledString {
+                string: "rotten".to_string(),
+                style: Style::new()
+                    .with_bold(true)
+                    .with_foreground(colours::ROTTEN_MEAT.to_rgba32(255)),
+            },
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::ButcherCorpse(npc_type) => Text::new(vec![
+            StyledString::plain_text("You cut some ".to_string()),
+            item_styled_string_for_message(Item::RawMeat),
+            StyledString::plain_text(" from the corpse of the ".to_string()),
+            npc_type_to_styled_string(npc_type),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::CookMeat => Text::new(vec![
+            StyledString::plain_text("You hold the meat over the flames until it is ".to_string()),
+            item_styled_string_for_message(Item::CookedMeat),
+            StyledString::plain_text(".".to_string()),
+        ]),
+        Message::FoodPoisoning => Text::new(vec![StyledString::plain_text(
+            "Your stomach churns. That didn't agree with you (poison increased).".to_string(),
+        )]),
+        Message::Freezing => Text::new(vec![
+            StyledString::plain_text("You are damaged by the ".to_string()),
+            StyledString {
+                string: "cold".to_string(),
+                style: Style::plain_text()
+                    .with_foreground(colours::COLD.to_rgba32(255))
+                    .with_bold(true),
+            },
+            StyledString::plain_text(". Get out of the water and find somewhere warm!".to_string()),
+        ]),
+        Message::Overheating => Text::new(vec![
+            StyledString::plain_text("You are damaged by the ".to_string()),
+            StyledString {
+                string: "heat".to_string(),
+                style: Style::plain_text()
+                    .with_foreground(colours::HOT.to_rgba32(255))
+                    .with_bold(true),
+            },
+            StyledString::plain_text(". Get away from the fire!".to_string()),
+        ]),
+        Message::FieldSurgeryBegin => Text::new(vec![StyledString::plain_text(
+            "You unpack the field surgery kit and begin operating on yourself.".to_string(),
+        )]),
+        Message::FieldSurgeryInterrupted => Text::new(vec![StyledString::plain_text(
+            "You are interrupted! The field surgery kit is ruined.".to_string(),
+        )]),
         Message::RemoveOrgan(organ) => Text::new(vec![
             StyledString::plain_text("The ".to_string()),
             StyledString::plain_text(organ_string_for_description(&organ)),
@@ -2413,6 +4822,7 @@ pub fn organ_type_name(organ_type: OrganType) -> &'static str {
         CyberCore => "CyberCore™",
         Claw => "claw",
         CorruptedHeart => "CORRUPTED HEART",
+        ReinforcedSkeleton => "reinforced skeleton",
     }
 }
 
@@ -2430,6 +4840,7 @@ pub fn organ_type_name_cap(organ_type: OrganType) -> &'static str {
         CyberCore => "CyberCore™",
         Claw => "Claw",
         CorruptedHeart => "CORRUPTED HEART",
+        ReinforcedSkeleton => "Reinforced Skeleton",
     }
 }
 
@@ -2477,10 +4888,42 @@ fn organ_string_for_description(organ: &Organ) -> String {
     )
 }
 
+pub fn perk_string(perk: Perk) -> &'static str {
+    match perk {
+        Perk::ExtraOrganSlot => "Extra Organ Slot",
+        Perk::QuickReload => "Quick Reload",
+        Perk::PoisonResistance => "Poison Resistance",
+    }
+}
+
+pub fn perk_description_string(perk: Perk) -> &'static str {
+    match perk {
+        Perk::ExtraOrganSlot => "room for one more organ",
+        Perk::QuickReload => "reloading doesn't take a turn",
+        Perk::PoisonResistance => "poison builds up more slowly",
+    }
+}
+
+pub fn skill_string(skill: Skill) -> &'static str {
+    match skill {
+        Skill::Guns => "Guns",
+        Skill::Surgery => "Surgery",
+        Skill::Hacking => "Hacking",
+    }
+}
+
 pub fn organ_string_for_menu(organ: &Organ) -> String {
     let cybernetic = if organ.cybernetic { "Cybernetic " } else { "" };
+    let harvested = match (organ.harvested, organ.donor) {
+        (true, Some(donor)) => format!(
+            " (harvested from {})",
+            npc_type_to_styled_string(donor).string
+        ),
+        (true, None) => " (harvested)".to_string(),
+        (false, _) => String::new(),
+    };
     format!(
-        "{cybernetic}{}{}",
+        "{cybernetic}{}{}{harvested}",
         organ_type_name_cap(organ.type_),
         organ_traits_string(organ.traits)
     )
@@ -2579,6 +5022,169 @@ fn item_styled_string_for_message(item: Item) -> text::StyledString {
                 .with_bold(true)
                 .with_foreground(colours::ROCKET_LAUNCHER.to_rgba32(255)),
         },
+        Item::SniperRifle => StyledString {
+            string: "sniper rifle".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+        },
+        Item::SniperRounds => StyledString {
+            string: "sniper rounds".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SNIPER_RIFLE.to_rgba32(255)),
+        },
+        Item::Drone => StyledString {
+            string: "scout drone".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::DRONE.to_rgba32(255)),
+        },
+        Item::Turret => StyledString {
+            string: "turret".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::TURRET.to_rgba32(255)),
+        },
+        Item::CorruptedStimpack => StyledString {
+            string: "corrupted stimpack".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+        },
+        Item::CursedPistol => StyledString {
+            string: "cursed pistol".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::CORRUPTION.to_rgba32(255)),
+        },
+        Item::Scanner => StyledString {
+            string: "scanner".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SCANNER.to_rgba32(255)),
+        },
+        Item::GeigerCounter => StyledString {
+            string: "geiger counter".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::GEIGER_COUNTER.to_rgba32(255)),
+        },
+        Item::Schematics => StyledString {
+            string: "building schematics".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SCHEMATICS.to_rgba32(255)),
+        },
+        Item::MarketReport => StyledString {
+            string: "market report".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::MARKET_REPORT.to_rgba32(255)),
+        },
+        Item::Plank => StyledString {
+            string: "plank".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::PLANK.to_rgba32(255)),
+        },
+        Item::Anesthetic => StyledString {
+            string: "anesthetic".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::ANESTHETIC.to_rgba32(255)),
+        },
+        Item::EmpGrenade => StyledString {
+            string: "EMP grenade".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::EMP_GRENADE.to_rgba32(255)),
+        },
+        Item::SmokeGrenade => StyledString {
+            string: "smoke grenade".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::SMOKE_GRENADE.to_rgba32(255)),
+        },
+        Item::GasMask => StyledString {
+            string: "gas mask".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::GAS_MASK.to_rgba32(255)),
+        },
+        Item::HazardSuit => StyledString {
+            string: "hazard suit".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::HAZARD_SUIT.to_rgba32(255)),
+        },
+        Item::RawMeat => StyledString {
+            string: "raw meat".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::RAW_MEAT.to_rgba32(255)),
+        },
+        Item::CookedMeat => StyledString {
+            string: "cooked meat".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::COOKED_MEAT.to_rgba32(255)),
+        },
+        Item::RottenMeat => StyledString {
+            string: "rotten meat".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::ROTTEN_MEAT.to_rgba32(255)),
+        },
+        Item::Readable(document) => StyledString {
+            string: document_kind_name(document.kind()).to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::READABLE.to_rgba32(255)),
+        },
+        Item::Antipsychotic => StyledString {
+            string: "antipsychotic".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::ANTIPSYCHOTIC.to_rgba32(255)),
+        },
+        Item::Bandage => StyledString {
+            string: "bandage".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::BANDAGE.to_rgba32(255)),
+        },
+        Item::Immunosuppressant => StyledString {
+            string: "immunosuppressant".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::IMMUNOSUPPRESSANT.to_rgba32(255)),
+        },
+        Item::FieldSurgeryKit => StyledString {
+            string: "field surgery kit".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::FIELD_SURGERY_KIT.to_rgba32(255)),
+        },
+    }
+}
+
+fn item_appearance_styled_string_for_message(appearance: ItemAppearance) -> text::StyledString {
+    use text::*;
+    match appearance {
+        ItemAppearance::Identified(item) => item_styled_string_for_message(item),
+        ItemAppearance::UnlabeledSyringe => StyledString {
+            string: "unlabeled syringe".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
+        },
+        ItemAppearance::StrangeDevice => StyledString {
+            string: "strange device".to_string(),
+            style: Style::new()
+                .with_bold(true)
+                .with_foreground(colours::UNIDENTIFIED.to_rgba32(255)),
+        },
     }
 }
 
@@ -2602,5 +5208,72 @@ pub fn item_string_for_menu(item: Item) -> String {
         Item::PistolAmmo => "Pistol Bullets".to_string(),
         Item::ShotgunAmmo => "Shotgun Shells".to_string(),
         Item::Rocket => "Rocket".to_string(),
+        Item::SniperRifle => "Sniper Rifle".to_string(),
+        Item::SniperRounds => "Sniper Rounds".to_string(),
+        Item::Drone => "Scout Drone".to_string(),
+        Item::Turret => "Turret".to_string(),
+        Item::CorruptedStimpack => "Corrupted Stimpack".to_string(),
+        Item::CursedPistol => "Cursed Pistol".to_string(),
+        Item::Scanner => "Scanner".to_string(),
+        Item::GeigerCounter => "Geiger Counter".to_string(),
+        Item::Schematics => "Building Schematics".to_string(),
+        Item::MarketReport => "Market Report".to_string(),
+        Item::Plank => "Plank".to_string(),
+        Item::Anesthetic => "Anesthetic".to_string(),
+        Item::FieldSurgeryKit => "Field Surgery Kit".to_string(),
+        Item::Immunosuppressant => "Immunosuppressant".to_string(),
+        Item::Bandage => "Bandage".to_string(),
+        Item::Antipsychotic => "Antipsychotic".to_string(),
+        Item::EmpGrenade => "EMP Grenade".to_string(),
+        Item::SmokeGrenade => "Smoke Grenade".to_string(),
+        Item::GasMask => "Gas Mask".to_string(),
+        Item::HazardSuit => "Hazard Suit".to_string(),
+        Item::RawMeat => "Raw Meat".to_string(),
+        Item::CookedMeat => "Cooked Meat".to_string(),
+        Item::RottenMeat => "Rotten Meat".to_string(),
+        Item::Readable(document) => document.title().to_string(),
+    }
+}
+
+fn objective_string(objective: Objective) -> String {
+    match objective {
+        Objective::FindTheCorruptor => {
+            format!("Find the CORRUPTOR on level {}", game::NUM_LEVELS)
+        }
+        Objective::KillTheCorruptor => "Kill the CORRUPTOR".to_string(),
+        Objective::ReturnToTheEvacZone => "Return to the Evac Zone on level 1".to_string(),
+        Objective::RestorePower {
+            needs_battery: false,
+        } => "Start the generator to restore power".to_string(),
+        Objective::RestorePower {
+            needs_battery: true,
+        } => "Bring a battery to the generator to restore power".to_string(),
+        Objective::TradeWithTheMerchant => "A travelling merchant is on this level".to_string(),
+        Objective::SurviveTheBountyHunter => "Survive the bounty hunter".to_string(),
+        Objective::Tutorial(step) => match step {
+            TutorialStep::Move => "Head through the door into the next room",
+            TutorialStep::Shoot => "Shoot the zombie in the room to the east",
+            TutorialStep::Reload => "Reload your pistol",
+            TutorialStep::Harvest => "Harvest an organ from the zombie's corpse",
+            TutorialStep::VisitClinic => "Have the organ installed at the clinic",
+            TutorialStep::Done => "Tutorial complete",
+        }
+        .to_string(),
+    }
+}
+
+fn document_kind_name(kind: DocumentKind) -> &'static str {
+    match kind {
+        DocumentKind::Note => "note",
+        DocumentKind::Email => "printed email",
+        DocumentKind::Log => "log printout",
+    }
+}
+
+pub fn item_appearance_string_for_menu(appearance: ItemAppearance) -> String {
+    match appearance {
+        ItemAppearance::Identified(item) => item_string_for_menu(item),
+        ItemAppearance::UnlabeledSyringe => "Unlabeled Syringe".to_string(),
+        ItemAppearance::StrangeDevice => "Strange Device".to_string(),
     }
 }
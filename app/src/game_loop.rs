@@ -1,19 +1,27 @@
+#[cfg(feature = "leaderboard")]
+use crate::leaderboard::{LeaderboardClient, RunResult, RunSummary};
+use crate::presence::{Activity, Presence, PresenceClient};
+use crate::vote::{option_letter, Vote, Voting, MAX_OPTIONS};
 use crate::{
-    colours,
-    controls::{AppInput, Controls},
+    colours::{self, Palette},
+    controls::{key_name, AppInput, Controls},
     game_instance::{
-        item_string_for_menu, message_to_text, organ_string_for_menu, GameInstance,
-        GameInstanceStorable, Mode,
+        item_appearance_string_for_menu, item_string_for_menu, map_origin, message_to_text,
+        organ_string_for_menu, perk_description_string, perk_string, skill_string, CombatLog,
+        GameInstance, GameInstanceStorable, GlyphSet, Mode, RenderSettings, VIEWPORT_SIZE,
     },
     image::Images,
     music::{MusicState, Track},
-    text,
+    text, AppArgs,
 };
 use chargrid::{self, border::BorderStyle, control_flow::*, menu, prelude::*};
 use game::{
-    witness::{self, FireBody, FireEquipped, Running, Witness},
-    Config as GameConfig, ExternalEvent, GameOverReason, Item, Menu as GameMenu,
-    MenuChoice as GameMenuChoice, Victory, WhichHand,
+    headless::{self, GreedyBot, Replay, Step, StepError},
+    witness::{self, FireBody, FireEquipped, Running, Throw, Witness},
+    Background, CardinalDirection, Config as GameConfig, Document, ExternalEvent, GameOverReason,
+    Item, ItemAppearance, Menu as GameMenu, MenuChoice as GameMenuChoice, Message as GameMessage,
+    NewGamePlusBonus, OrganType, Remnant, Script, Skill, Victory, WearSlot, WhichHand,
+    MAX_SKILL_LEVEL,
 };
 use general_storage_static::{self as storage, format, StaticStorage as Storage};
 use line_2d;
@@ -21,16 +29,104 @@ use rand::{Rng, SeedableRng};
 use rand_isaac::Isaac64Rng;
 use rgb_int::Rgb24;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
 
-const LEVEL_TRACKS: &[Track] = &[Track::Level1, Track::Level2];
+const LEVEL_TRACKS: &[Track] = &[Track::Level1, Track::Level2, Track::Level3, Track::Level4];
+
+// Time between steps when the player is walking along a path chosen with the mouse
+const AUTO_WALK_STEP_PERIOD: Duration = Duration::from_millis(60);
+
+// Time the main menu waits for input before a bot starts playing a game behind the title
+const ATTRACT_MODE_IDLE: Duration = Duration::from_secs(30);
+
+// Time between the bot's steps in attract mode, slow enough that the game can be followed
+const ATTRACT_MODE_STEP_PERIOD: Duration = Duration::from_millis(150);
+
+// Amount by which each press of left or right changes a volume slider in the options menu, or
+// each press of a volume hotkey changes the volume
+const VOLUME_STEP: f32 = 0.1;
+
+fn step_volume(volume: &mut f32, step: f32) {
+    // round so repeated steps don't accumulate floating point error
+    *volume = ((*volume + step) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Config {
     music_volume: f32,
     sfx_volume: f32,
+    #[serde(default)]
+    muted: bool,
     won: bool,
     first_run: bool,
     victories: Vec<Victory>,
+    #[serde(default)]
+    hints_seen: Vec<Hint>,
+    #[serde(default = "default_screen_shake")]
+    screen_shake: bool,
+    #[serde(default)]
+    particle_density: ParticleDensity,
+    #[serde(default)]
+    palette: Palette,
+    #[serde(default)]
+    screen_reader: bool,
+    #[serde(default)]
+    glyph_set: GlyphSet,
+    #[serde(default)]
+    combat_log: CombatLog,
+    #[serde(default = "default_show_objectives")]
+    show_objectives: bool,
+    #[serde(default)]
+    previous_death: Option<PreviousDeath>,
+}
+
+/// Where the most recent character died, so the next character to play the same seed can find
+/// some of what they were carrying
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PreviousDeath {
+    rng_seed: u64,
+    remnant: Remnant,
+}
+
+fn default_screen_shake() -> bool {
+    true
+}
+
+fn default_show_objectives() -> bool {
+    true
+}
+
+/// How many of the particles from explosions, gunfire and the like are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum ParticleDensity {
+    #[default]
+    Full,
+    Reduced,
+    Minimal,
+    Off,
+}
+
+impl ParticleDensity {
+    const ALL: [Self; 4] = [Self::Full, Self::Reduced, Self::Minimal, Self::Off];
+
+    fn percent(self) -> u32 {
+        match self {
+            Self::Full => 100,
+            Self::Reduced => 50,
+            Self::Minimal => 20,
+            Self::Off => 0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Full => "Full",
+            Self::Reduced => "Reduced",
+            Self::Minimal => "Minimal",
+            Self::Off => "Off",
+        }
+    }
 }
 
 impl Default for Config {
@@ -38,9 +134,76 @@ impl Default for Config {
         Self {
             music_volume: 0.2,
             sfx_volume: 0.5,
+            muted: false,
             won: false,
             first_run: true,
             victories: Vec::new(),
+            hints_seen: Vec::new(),
+            screen_shake: true,
+            particle_density: ParticleDensity::Full,
+            palette: Palette::Standard,
+            screen_reader: false,
+            glyph_set: GlyphSet::Unicode,
+            combat_log: CombatLog::Brief,
+            show_objectives: true,
+            previous_death: None,
+        }
+    }
+}
+
+impl Config {
+    fn render_settings(&self) -> RenderSettings {
+        RenderSettings {
+            particle_density_percent: self.particle_density.percent(),
+            glyph_set: self.glyph_set,
+            combat_log: self.combat_log,
+        }
+    }
+}
+
+/// Mechanics which are explained to the player the first time they come up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Hint {
+    Corpse,
+    Claw,
+    Radiation,
+}
+
+impl Hint {
+    const ALL: [Self; 3] = [Self::Corpse, Self::Claw, Self::Radiation];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Corpse => "Corpses",
+            Self::Claw => "Claws",
+            Self::Radiation => "Radiation",
+        }
+    }
+
+    fn body(self) -> &'static str {
+        match self {
+            Self::Corpse => "Most creatures leave a corpse when they die. Stand on a corpse and apply an empty organ container to harvest one of its organs. Harvested organs can be sold, or installed at an organ clinic.",
+            Self::Claw => "One of your hands has become a claw. Claws greatly increase melee damage but can't hold items. With one claw you can still hold a pistol. With two claws you can't hold any guns.",
+            Self::Radiation => "You are absorbing radiation. When your radiation meter fills up you gain a mutation. Anti-rads reduce your radiation.",
+        }
+    }
+
+    // Whether the hint's event is happening, given the messages logged during the current turn
+    fn is_triggered(self, game: &game::Game, messages: &[GameMessage]) -> bool {
+        match self {
+            Self::Corpse => game.is_corpse_visible(),
+            Self::Claw => game
+                .player_organs()
+                .iter()
+                .any(|player_organ| player_organ.organ.type_ == OrganType::Claw),
+            Self::Radiation => messages.iter().any(|message| {
+                matches!(
+                    message,
+                    GameMessage::AmbientRadiation
+                        | GameMessage::RadiationClose
+                        | GameMessage::RadiationVeryClose
+                )
+            }),
         }
     }
 }
@@ -97,6 +260,12 @@ pub enum InitialRngSeed {
     Random,
 }
 
+/// Called with the seed of a game that has just ended when the player chooses to share it
+pub type ShareSeed = Box<dyn Fn(u64)>;
+
+/// Called with the name and contents of a file which the player has asked to export
+pub type ExportFile = Box<dyn Fn(&str, &[u8])>;
+
 struct RngSeedSource {
     next_seed: u64,
     seed_rng: Isaac64Rng,
@@ -126,17 +295,25 @@ impl RngSeedSource {
     }
 }
 
+// Exported saves start with this so they can be recognised when imported into another build of
+// the game. What follows is the save game exactly as it's stored. Saves are bincode, which can't
+// fill in missing fields, so the version here goes up whenever the saved game's layout changes.
+const SAVE_EXPORT_HEADER: &[u8] = b"electric-organ-save-v2\n";
+
 pub struct AppStorage {
     pub handle: Storage,
     pub save_game_key: String,
     pub config_key: String,
     pub controls_key: String,
+    /// Where a replay of the most recently finished run is kept
+    pub replay_key: String,
 }
 
 impl AppStorage {
     const SAVE_GAME_STORAGE_FORMAT: format::Bincode = format::Bincode;
     const CONFIG_STORAGE_FORMAT: format::JsonPretty = format::JsonPretty;
     const CONTROLS_STORAGE_FORMAT: format::JsonPretty = format::JsonPretty;
+    const REPLAY_STORAGE_FORMAT: format::Bincode = format::Bincode;
 
     fn save_game(&mut self, instance: &GameInstanceStorable) {
         let result = self.handle.store(
@@ -180,6 +357,31 @@ impl AppStorage {
         }
     }
 
+    /// The stored save game in a form which can be imported by any build of the game (web or
+    /// native), or `None` if there's no save game
+    pub fn export_save(&self) -> Option<Vec<u8>> {
+        let raw = self.handle.load_raw(&self.save_game_key).ok()?;
+        let mut bytes = SAVE_EXPORT_HEADER.to_vec();
+        bytes.extend_from_slice(&raw);
+        Some(bytes)
+    }
+
+    /// Replace the stored save game with an exported one. Save files copied directly out of a
+    /// native build's storage directory are accepted too.
+    pub fn import_save(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let raw = bytes.strip_prefix(SAVE_EXPORT_HEADER).unwrap_or(bytes);
+        // make sure this build can load it before replacing the current save
+        if let Err(e) = bincode::deserialize::<GameInstanceStorable>(raw) {
+            return Err(format!(
+                "Not a valid save file for this version of the game: {}",
+                e
+            ));
+        }
+        self.handle.store_raw(&self.save_game_key, raw).map_err(
+            |storage::StoreRawError::IoError(e)| format!("Error while writing save data: {}", e),
+        )
+    }
+
     fn clear_game(&mut self) {
         if self.handle.exists(&self.save_game_key) {
             if let Err(e) = self.handle.remove(&self.save_game_key) {
@@ -211,6 +413,51 @@ impl AppStorage {
         }
     }
 
+    fn save_replay(&mut self, replay: &Replay) {
+        let result = self
+            .handle
+            .store(&self.replay_key, replay, Self::REPLAY_STORAGE_FORMAT);
+        if let Err(e) = result {
+            use storage::{StoreError, StoreRawError};
+            match e {
+                StoreError::FormatError(e) => log::error!("Failed to format replay: {}", e),
+                StoreError::Raw(e) => match e {
+                    StoreRawError::IoError(e) => {
+                        log::error!("Error while writing replay: {}", e)
+                    }
+                },
+            }
+        }
+    }
+
+    fn load_replay(&self) -> Option<Replay> {
+        let result = self
+            .handle
+            .load::<_, Replay, _>(&self.replay_key, Self::REPLAY_STORAGE_FORMAT);
+        match result {
+            Err(e) => {
+                use storage::{LoadError, LoadRawError};
+                match e {
+                    LoadError::FormatError(e) => log::error!("Failed to parse replay: {}", e),
+                    LoadError::Raw(e) => match e {
+                        LoadRawError::IoError(e) => {
+                            log::error!("Error while reading replay: {}", e)
+                        }
+                        LoadRawError::NoSuchKey => (),
+                    },
+                }
+                None
+            }
+            Ok(replay) => Some(replay),
+        }
+    }
+
+    fn store_export(&mut self, name: &str, contents: &[u8]) {
+        if let Err(storage::StoreRawError::IoError(e)) = self.handle.store_raw(name, contents) {
+            log::error!("Error while writing {}: {}", name, e);
+        }
+    }
+
     fn load_config(&self) -> Option<Config> {
         let result = self
             .handle
@@ -278,10 +525,20 @@ impl AppStorage {
 fn new_game(
     rng_seed_source: &mut RngSeedSource,
     game_config: &GameConfig,
+    background: Background,
     victories: Vec<Victory>,
+    previous_death: Option<&PreviousDeath>,
 ) -> (GameInstance, witness::Running) {
-    let mut rng = Isaac64Rng::seed_from_u64(rng_seed_source.next_seed());
-    GameInstance::new(game_config, victories, &mut rng)
+    let rng_seed = rng_seed_source.next_seed();
+    let (mut instance, running) = GameInstance::new(game_config, background, victories, rng_seed);
+    if let Some(previous_death) = previous_death {
+        if previous_death.rng_seed == rng_seed {
+            instance
+                .game
+                .inherit_remnant(previous_death.remnant.clone());
+        }
+    }
+    (instance, running)
 }
 
 #[derive(Clone, Copy)]
@@ -300,18 +557,55 @@ pub struct GameLoopData {
     images: Images,
     cursor: Option<Coord>,
     music_state: MusicState,
+    // muted by the command line, which isn't remembered between runs
+    mute: bool,
+    audio: bool,
     screen_shake: Option<ScreenShake>,
     level_track_index: usize,
+    auto_walk: VecDeque<CardinalDirection>,
+    auto_walk_countdown: Duration,
+    // the overwatch key was pressed, so the next direction chooses which way to watch
+    overwatch_pending: bool,
+    share_seed: Option<ShareSeed>,
+    export_file: Option<ExportFile>,
+    #[cfg(feature = "leaderboard")]
+    leaderboard: Option<Box<dyn LeaderboardClient>>,
+    presence: Option<Box<dyn PresenceClient>>,
+    // the boss has been seen on the current level and not yet killed
+    fighting_boss: bool,
+    voting: Option<Voting>,
+    script: Option<Box<dyn Script>>,
 }
 
 impl GameLoopData {
     pub fn new(
-        game_config: GameConfig,
-        mut storage: AppStorage,
-        initial_rng_seed: InitialRngSeed,
-        force_new_game: bool,
-        mute: bool,
+        AppArgs {
+            mut storage,
+            initial_rng_seed,
+            omniscient,
+            new_game: force_new_game,
+            mute,
+            audio,
+            share_seed,
+            export_file,
+            balance,
+            #[cfg(feature = "leaderboard")]
+            leaderboard,
+            presence,
+            voting,
+            script,
+        }: AppArgs,
     ) -> (Self, GameLoopState) {
+        let game_config = GameConfig {
+            omniscient: if omniscient {
+                GameConfig::OMNISCIENT
+            } else {
+                None
+            },
+            demo: false,
+            debug: false,
+            balance,
+        };
         let mut rng_seed_source = RngSeedSource::new(initial_rng_seed);
         let config = storage.load_config().unwrap_or_default();
         let (instance, state) = match storage.load_game() {
@@ -324,8 +618,13 @@ impl GameLoopData {
             }
             None => {
                 if force_new_game {
-                    let (instance, running) =
-                        new_game(&mut rng_seed_source, &game_config, config.victories.clone());
+                    let (instance, running) = new_game(
+                        &mut rng_seed_source,
+                        &game_config,
+                        Background::default(),
+                        config.victories.clone(),
+                        config.previous_death.as_ref(),
+                    );
                     (
                         Some(instance),
                         GameLoopState::Playing(running.into_witness()),
@@ -343,32 +642,70 @@ impl GameLoopData {
             controls
         };
         let music_state = MusicState::new();
-        if mute {
-            music_state.set_volume(0.0);
-        } else {
-            music_state.set_volume(0.5);
-        }
         if instance.is_some() {
             music_state.set_track(Some(Track::Level1));
         } else {
             music_state.set_track(Some(Track::Menu));
         };
-        (
-            Self {
-                instance,
-                controls,
-                game_config,
-                storage,
-                rng_seed_source,
-                config,
-                images: Images::new(),
-                cursor: None,
-                music_state,
-                screen_shake: None,
-                level_track_index: 0,
-            },
-            state,
-        )
+        let data = Self {
+            instance,
+            controls,
+            game_config,
+            storage,
+            rng_seed_source,
+            config,
+            images: Images::new(),
+            cursor: None,
+            music_state,
+            mute,
+            audio,
+            screen_shake: None,
+            level_track_index: 0,
+            auto_walk: VecDeque::new(),
+            overwatch_pending: false,
+            auto_walk_countdown: Duration::ZERO,
+            share_seed,
+            export_file,
+            #[cfg(feature = "leaderboard")]
+            leaderboard,
+            presence,
+            fighting_boss: false,
+            voting,
+            script,
+        }
+        .with_volume_applied();
+        let data = data.with_presence_published();
+        let data = data.with_script_hooks_attached();
+        (data, state)
+    }
+
+    fn with_volume_applied(self) -> Self {
+        self.apply_volume();
+        self
+    }
+
+    fn with_script_hooks_attached(mut self) -> Self {
+        self.attach_script_hooks();
+        self
+    }
+
+    // Script hooks aren't saved with the game, so they're attached to each new or loaded game
+    fn attach_script_hooks(&mut self) {
+        if let (Some(script), Some(instance)) = (self.script.as_ref(), self.instance.as_mut()) {
+            instance.game.set_script_hooks(script.hooks());
+        }
+    }
+
+    fn apply_volume(&self) {
+        if self.mute || self.config.muted {
+            self.music_state.set_music_volume(0.0);
+            self.music_state.set_sfx_volume(0.0);
+        } else {
+            self.music_state
+                .set_music_volume(self.config.music_volume as f64);
+            self.music_state
+                .set_sfx_volume(self.config.sfx_volume as f64);
+        }
     }
 
     // XXX the witness system is overly restrictive
@@ -389,49 +726,219 @@ impl GameLoopData {
         running
     }
 
+    #[cfg(feature = "leaderboard")]
+    fn submit_run(&mut self, result: RunResult) {
+        if let (Some(leaderboard), Some(instance)) =
+            (self.leaderboard.as_mut(), self.instance.as_ref())
+        {
+            let stats = instance.game.inner_ref().run_stats();
+            leaderboard.submit(&RunSummary::new(instance.rng_seed, stats, result));
+        }
+    }
+
+    fn with_presence_published(mut self) -> Self {
+        self.publish_presence(None);
+        self
+    }
+
+    // Tell the frontend what the player is doing. If no activity is given it's worked out from
+    // the state of the game.
+    fn publish_presence(&mut self, activity: Option<Activity>) {
+        if self.presence.is_none() {
+            return;
+        }
+        let presence = match self.instance.as_ref() {
+            None => Presence::MAIN_MENU,
+            Some(instance) => {
+                let game = instance.game.inner_ref();
+                let stats = game.run_stats();
+                let activity = activity.unwrap_or(if self.fighting_boss {
+                    Activity::FightingBoss
+                } else if game.is_hostile_visible() {
+                    Activity::Fighting
+                } else {
+                    Activity::Exploring
+                });
+                Presence {
+                    activity,
+                    level: stats.level_reached,
+                    turns: stats.turns,
+                }
+            }
+        };
+        if let Some(presence_client) = self.presence.as_mut() {
+            presence_client.update(&presence);
+        }
+    }
+
     fn clear_saved_game(&mut self) {
         self.music_state.set_track(Some(Track::Menu));
         self.storage.clear_game();
         self.instance = None;
+        self.fighting_boss = false;
+        self.publish_presence(None);
     }
 
-    fn new_game(&mut self) -> witness::Running {
+    fn new_game(&mut self, background: Background) -> witness::Running {
         let victories = self.config.victories.clone();
-        let (instance, running) = new_game(&mut self.rng_seed_source, &self.game_config, victories);
+        let (instance, running) = new_game(
+            &mut self.rng_seed_source,
+            &self.game_config,
+            background,
+            victories,
+            self.config.previous_death.as_ref(),
+        );
+        self.instance = Some(instance);
+        self.attach_script_hooks();
+        self.music_state.set_track(Some(Track::Level1));
+        running
+    }
+
+    fn new_tutorial(&mut self) -> witness::Running {
+        let (instance, running) =
+            GameInstance::new_tutorial(&self.game_config, self.rng_seed_source.next_seed());
         self.instance = Some(instance);
+        self.attach_script_hooks();
         self.music_state.set_track(Some(Track::Level1));
         running
     }
 
+    // Keep a replay of the run which just ended so it can be watched from the main menu
+    fn save_replay(&mut self) {
+        let replay = self
+            .instance
+            .as_ref()
+            .and_then(|instance| instance.game.inner_ref().replay(instance.rng_seed));
+        if let Some(replay) = replay {
+            self.storage.save_replay(&replay);
+        }
+    }
+
+    // Remember what the character who just died left behind in case the seed is played again
+    fn record_death(&mut self) {
+        let previous_death = self.instance.as_ref().and_then(|instance| {
+            instance
+                .game
+                .inner_ref()
+                .remnant()
+                .map(|remnant| PreviousDeath {
+                    rng_seed: instance.rng_seed,
+                    remnant: remnant.clone(),
+                })
+        });
+        if previous_death.is_some() {
+            self.config.previous_death = previous_death;
+        }
+    }
+
     fn save_config(&mut self) {
         self.storage.save_config(&self.config);
     }
 
+    fn save_controls(&mut self) {
+        self.storage.save_controls(&self.controls);
+    }
+
+    // Write the player's map of the current level to a text file, or offer it as a download if
+    // the frontend can't write files the player can get at
+    fn export_map(&mut self) {
+        let instance = match self.instance.as_ref() {
+            Some(instance) => instance,
+            None => return,
+        };
+        let name = format!(
+            "map-{}-level{}.txt",
+            instance.rng_seed,
+            instance.game.inner_ref().current_level_index() + 1
+        );
+        let text = instance.map_text();
+        if let Some(export_file) = self.export_file.as_ref() {
+            export_file(&name, text.as_bytes());
+        } else {
+            self.storage.store_export(&name, text.as_bytes());
+            log::info!("Exported map to {}", name);
+        }
+    }
+
+    fn toggle_mute(&mut self) {
+        self.config.muted = !(self.mute || self.config.muted);
+        self.mute = false;
+        self.apply_volume();
+        self.save_config();
+    }
+
+    fn toggle_objectives(&mut self) {
+        self.config.show_objectives = !self.config.show_objectives;
+        self.save_config();
+    }
+
+    // Change the music and sfx volume together, unmuting if necessary
+    fn nudge_volume(&mut self, step: f32) {
+        step_volume(&mut self.config.music_volume, step);
+        step_volume(&mut self.config.sfx_volume, step);
+        self.config.muted = false;
+        self.mute = false;
+        self.apply_volume();
+        self.save_config();
+    }
+
     fn render(&self, ctx: Ctx, fb: &mut FrameBuffer, mode: Mode) {
         if let Some(instance) = self.instance.as_ref() {
+            if self.config.screen_reader {
+                instance.render_narration(ctx, fb, self.config.combat_log);
+                return;
+            }
             let offset = self
                 .screen_shake
                 .map(|s| s.offset)
                 .unwrap_or(Coord::new(0, 0));
-            instance.render(ctx, fb, self.cursor, mode, offset);
+            instance.render(
+                ctx,
+                fb,
+                self.cursor,
+                mode,
+                offset,
+                self.config.render_settings(),
+            );
+            let map_ctx = ctx.add_offset(map_origin(ctx.bounding_box.size()));
+            if self.config.show_objectives {
+                instance.render_objectives(map_ctx.add_depth(60), fb);
+            }
             match mode {
                 Mode::Normal => {
                     let colour = colours::NORMAL_MODE.to_rgba32(127);
                     if let Some(cursor) = self.cursor {
-                        let render_cell = RenderCell::default().with_background(colour);
-                        fb.set_cell_relative_to_ctx(ctx, cursor, 50, render_cell);
+                        if instance.is_in_view(cursor) {
+                            let render_cell = RenderCell::default().with_background(colour);
+                            let cursor = instance.world_to_screen(cursor);
+                            fb.set_cell_relative_to_ctx(map_ctx, cursor, 50, render_cell);
+                        }
                     }
                 }
-                Mode::Aiming => {
+                Mode::Aiming | Mode::Throwing => {
                     if let Some(cursor) = self.cursor {
-                        let colour = colours::AIMING_MODE.to_rgba32(127);
-                        let render_cell = RenderCell::default().with_background(colour);
-                        let instance = self.instance.as_ref().unwrap();
-                        for coord in line_2d::coords_between(
-                            instance.game.inner_ref().player_coord(),
-                            cursor,
-                        ) {
-                            fb.set_cell_relative_to_ctx(ctx, coord, 50, render_cell);
+                        let game = instance.game.inner_ref();
+                        let trace = match mode {
+                            Mode::Throwing => game.trace_throw(cursor),
+                            _ => game.trace_shot(cursor),
+                        };
+                        let mut highlight = |coords: &[Coord], colour: Rgba32| {
+                            let render_cell = RenderCell::default().with_background(colour);
+                            for &coord in coords {
+                                if instance.is_in_view(coord) {
+                                    let coord = instance.world_to_screen(coord);
+                                    fb.set_cell_relative_to_ctx(map_ctx, coord, 50, render_cell);
+                                }
+                            }
+                        };
+                        highlight(&trace.spread, colours::AIMING_MODE.to_rgba32(63));
+                        highlight(&trace.path, colours::AIMING_MODE.to_rgba32(127));
+                        highlight(
+                            &trace.obstructions,
+                            colours::AIMING_OBSTRUCTION.to_rgba32(127),
+                        );
+                        if let Some(impact) = trace.impact {
+                            highlight(&[impact], colours::AIMING_MODE.to_rgba32(255));
                         }
                     }
                 }
@@ -439,31 +946,128 @@ impl GameLoopData {
         }
     }
 
+    fn handle_external_events(&mut self) {
+        let instance = self.instance.as_mut().unwrap();
+        self.music_state
+            .set_listener(instance.game.inner_ref().player_coord());
+        for external_event in instance.game.drain_external_events() {
+            self.music_state.sfx_external_event(external_event);
+            match external_event {
+                ExternalEvent::BossSighted => self.fighting_boss = true,
+                ExternalEvent::BossKill | ExternalEvent::ChangeLevel => self.fighting_boss = false,
+                _ => (),
+            }
+            match external_event {
+                ExternalEvent::ChangeLevel => {
+                    self.level_track_index += 1;
+                    self.music_state.set_track(Some(
+                        LEVEL_TRACKS[self.level_track_index % LEVEL_TRACKS.len()],
+                    ));
+                }
+                ExternalEvent::BossSighted => self.music_state.set_track(Some(Track::Boss)),
+                ExternalEvent::BossKill => self.music_state.set_track(Some(
+                    LEVEL_TRACKS[self.level_track_index % LEVEL_TRACKS.len()],
+                )),
+                ExternalEvent::Explosion(_) if self.config.screen_shake => {
+                    let mut rng = Isaac64Rng::from_entropy();
+                    let screen_shake = ScreenShake {
+                        countdown: 2,
+                        offset: if rng.gen() {
+                            Coord::new(-1, 0)
+                        } else {
+                            Coord::new(1, 0)
+                        },
+                    };
+                    self.screen_shake = Some(screen_shake);
+                }
+                _ => (),
+            }
+        }
+        self.publish_presence(None);
+    }
+
+    // Start walking the player towards a clicked cell. Nothing happens if there is no known
+    // route, or if there are hostiles in view.
+    fn start_auto_walk(&mut self, target: Coord) {
+        let game = self.instance.as_ref().unwrap().game.inner_ref();
+        if game.is_gameplay_blocked() || game.is_hostile_visible() {
+            return;
+        }
+        if let Some(path) = game.path_to(target) {
+            self.auto_walk = path.into();
+            self.auto_walk_countdown = Duration::ZERO;
+        }
+    }
+
+    // Take the next step of a path chosen with the mouse. Walking stops as soon as a hostile
+    // comes into view or something happens that's worth telling the player about.
+    fn auto_walk_step(&mut self, running: witness::Running, since_previous: Duration) -> Witness {
+        if self.auto_walk.is_empty() {
+            return running.into_witness();
+        }
+        let instance = self.instance.as_mut().unwrap();
+        if instance.game.inner_ref().is_gameplay_blocked() {
+            return running.into_witness();
+        }
+        if instance.game.inner_ref().is_hostile_visible() {
+            self.auto_walk.clear();
+            return running.into_witness();
+        }
+        if let Some(remaining) = self.auto_walk_countdown.checked_sub(since_previous) {
+            if remaining > Duration::ZERO {
+                self.auto_walk_countdown = remaining;
+                return running.into_witness();
+            }
+        }
+        self.auto_walk_countdown = AUTO_WALK_STEP_PERIOD;
+        let direction = self.auto_walk.pop_front().unwrap();
+        let num_messages = instance.game.inner_ref().message_log().len();
+        let (witness, action_result) = running.walk(&mut instance.game, direction);
+        let game = instance.game.inner_ref();
+        let interrupted = action_result.is_err()
+            || game.message_log()[num_messages..]
+                .iter()
+                .any(|message| *message != GameMessage::OpenDoor)
+            || game.is_hostile_visible();
+        if interrupted {
+            self.auto_walk.clear();
+        }
+        self.handle_external_events();
+        witness
+    }
+
     fn update(&mut self, event: Event, running: witness::Running) -> GameLoopState {
+        if let Event::Input(input) = event {
+            // volume can be changed and the map exported even while gameplay is blocked
+            match self.controls.get(input) {
+                Some(AppInput::ToggleMute) => self.toggle_mute(),
+                Some(AppInput::VolumeUp) => self.nudge_volume(VOLUME_STEP),
+                Some(AppInput::VolumeDown) => self.nudge_volume(-VOLUME_STEP),
+                Some(AppInput::ExportMap) => self.export_map(),
+                Some(AppInput::ToggleObjectives) => self.toggle_objectives(),
+                _ => (),
+            }
+        }
         let instance = self.instance.as_mut().unwrap();
+        let num_messages = instance.game.inner_ref().message_log().len();
         let witness = match event {
             Event::Input(input) => {
                 self.cursor = None;
+                if let Input::Keyboard(_) = input {
+                    self.auto_walk.clear();
+                }
                 if let Some(app_input) = self.controls.get(input) {
                     if instance.game.inner_ref().is_gameplay_blocked() {
                         running.into_witness()
                     } else {
+                        let overwatch_pending = std::mem::take(&mut self.overwatch_pending);
                         let (witness, _action_result) = match app_input {
+                            AppInput::Direction(direction) if overwatch_pending => {
+                                running.overwatch(&mut instance.game, direction)
+                            }
                             AppInput::Direction(direction) => {
                                 let witness = running.walk(&mut instance.game, direction);
-                                for external_event in instance.game.take_external_events() {
-                                    match external_event {
-                                        ExternalEvent::Melee => self.music_state.sfx_melee(),
-                                        ExternalEvent::ChangeLevel => {
-                                            self.level_track_index += 1;
-                                            self.music_state.set_track(Some(
-                                                LEVEL_TRACKS
-                                                    [self.level_track_index % LEVEL_TRACKS.len()],
-                                            ));
-                                        }
-                                        _ => (),
-                                    }
-                                }
+                                self.handle_external_events();
                                 witness
                             }
                             AppInput::Wait => running.wait(&mut instance.game),
@@ -482,6 +1086,18 @@ impl GameLoopData {
                             AppInput::ViewOrgans => {
                                 return GameLoopState::ViewOrgans(running);
                             }
+                            AppInput::CharacterSheet => {
+                                return GameLoopState::CharacterSheet(running);
+                            }
+                            AppInput::OverviewMap => {
+                                return GameLoopState::OverviewMap(running);
+                            }
+                            // already handled above
+                            AppInput::ToggleMute
+                            | AppInput::VolumeUp
+                            | AppInput::VolumeDown
+                            | AppInput::ExportMap
+                            | AppInput::ToggleObjectives => (running.into_witness(), Ok(())),
                             AppInput::DropItem => (
                                 drop_menu_witness(instance.game.inner_ref(), running),
                                 Ok(()),
@@ -492,18 +1108,33 @@ impl GameLoopData {
                             ),
                             AppInput::UnequipItem => running.unequip(&mut instance.game),
                             AppInput::Reload => running.reload(&mut instance.game),
+                            AppInput::Overwatch => {
+                                self.overwatch_pending = true;
+                                (running.into_witness(), Ok(()))
+                            }
+                            AppInput::ToggleDroneControl => {
+                                running.toggle_drone_control(&mut instance.game)
+                            }
+                            AppInput::ToggleSprint => running.toggle_sprint(&mut instance.game),
+                            AppInput::ScriptActions => (
+                                script_actions_menu_witness(instance.game.inner_ref(), running),
+                                Ok(()),
+                            ),
                         };
                         witness
                     }
                 } else {
                     if let Input::Mouse(MouseInput::MouseMove { coord, .. }) = input {
-                        self.cursor = Some(coord);
-                    }
-                    if let Input::Mouse(MouseInput::MousePress { coord, .. }) = input {
-                        self.cursor = Some(coord);
+                        self.cursor = instance.screen_to_world(coord);
                     }
                     if let Input::Mouse(MouseInput::MouseRelease { coord, .. }) = input {
-                        self.cursor = Some(coord);
+                        self.cursor = instance.screen_to_world(coord);
+                    }
+                    if let Input::Mouse(MouseInput::MousePress { coord, .. }) = input {
+                        self.cursor = instance.screen_to_world(coord);
+                        if let Some(cursor) = self.cursor {
+                            self.start_auto_walk(cursor);
+                        }
                     }
                     if let Input::Keyboard(KeyboardInput::Char('?')) = input {
                         return GameLoopState::Help(running);
@@ -521,29 +1152,37 @@ impl GameLoopData {
                         Some(screen_shake)
                     }
                 });
-                for external_event in instance.game.take_external_events() {
-                    match external_event {
-                        ExternalEvent::Explosion(_) => {
-                            self.music_state.sfx_explosion();
-                            let mut rng = Isaac64Rng::from_entropy();
-                            let screen_shake = ScreenShake {
-                                countdown: 2,
-                                offset: if rng.gen() {
-                                    Coord::new(-1, 0)
-                                } else {
-                                    Coord::new(1, 0)
-                                },
-                            };
-                            self.screen_shake = Some(screen_shake);
-                        }
-                        _ => (),
+                self.handle_external_events();
+                match witness {
+                    Witness::Running(running) => self.auto_walk_step(running, since_previous),
+                    other => {
+                        self.auto_walk.clear();
+                        other
                     }
                 }
-                witness
             }
             _ => Witness::Running(running),
         };
-        GameLoopState::Playing(witness)
+        match witness {
+            Witness::Running(running) => match self.take_pending_hint(num_messages) {
+                Some(hint) => GameLoopState::Hint(running, hint),
+                None => GameLoopState::Playing(Witness::Running(running)),
+            },
+            other => GameLoopState::Playing(other),
+        }
+    }
+
+    // The first hint whose event has just happened for the first time, which is then recorded
+    // as seen so it's never shown again
+    fn take_pending_hint(&mut self, num_messages: usize) -> Option<Hint> {
+        let game = self.instance.as_ref().unwrap().game.inner_ref();
+        let messages = &game.message_log()[num_messages..];
+        let hint = Hint::ALL.into_iter().find(|hint| {
+            !self.config.hints_seen.contains(hint) && hint.is_triggered(game, messages)
+        })?;
+        self.config.hints_seen.push(hint);
+        self.save_config();
+        Some(hint)
     }
 }
 
@@ -573,6 +1212,20 @@ fn apply_menu_witness(game: &game::Game, running: witness::Running) -> Witness {
     running.menu(menu)
 }
 
+// Mods can offer extra actions through script hooks. There's no menu to open without any.
+fn script_actions_menu_witness(game: &game::Game, running: witness::Running) -> Witness {
+    let num_actions = game.script_menu_actions().len();
+    if num_actions == 0 {
+        return running.into_witness();
+    }
+    let menu = GameMenu {
+        text: "Choose an action (escape to cancel):".to_string(),
+        choices: (0..num_actions).map(GameMenuChoice::ScriptAction).collect(),
+        image: None,
+    };
+    running.menu(menu)
+}
+
 impl GameInstanceComponent {
     fn new(running: witness::Running) -> Self {
         Self(Some(running))
@@ -584,8 +1237,11 @@ pub enum GameLoopState {
     Playing(Witness),
     MainMenu,
     Help(witness::Running),
+    Hint(witness::Running, Hint),
     MessageLog(witness::Running),
     ViewOrgans(witness::Running),
+    CharacterSheet(witness::Running),
+    OverviewMap(witness::Running),
 }
 
 impl Component for GameInstanceComponent {
@@ -596,12 +1252,12 @@ impl Component for GameInstanceComponent {
         state.render(ctx, fb, Mode::Normal);
     }
 
-    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
         let running = witness::Running::cheat(); // XXX
         if event.is_escape() {
             GameLoopState::Paused(running)
         } else {
-            state.update(event, running)
+            state.update(map_relative_event(event, ctx), running)
         }
     }
 
@@ -622,15 +1278,15 @@ impl Component for GameInstanceFireEquippedComponent {
         state.render(ctx, fb, Mode::Aiming);
     }
 
-    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
         let instance = state.instance.as_mut().unwrap();
         if event.is_escape() {
             return Some((Err(Cancel), self.0.take().unwrap()));
         }
-        match event {
+        match map_relative_event(event, ctx) {
             Event::Input(input) => {
                 if let Input::Mouse(MouseInput::MouseMove { coord, .. }) = input {
-                    if coord.is_valid(instance.game.inner_ref().world_size()) {
+                    if let Some(coord) = instance.screen_to_world(coord) {
                         state.cursor = Some(coord);
                     }
                 }
@@ -640,7 +1296,9 @@ impl Component for GameInstanceFireEquippedComponent {
                     }
                 }
                 if let Input::Mouse(MouseInput::MousePress { coord, .. }) = input {
-                    return Some((Ok(coord), self.0.take().unwrap()));
+                    if let Some(coord) = instance.screen_to_world(coord) {
+                        return Some((Ok(coord), self.0.take().unwrap()));
+                    }
                 }
                 if let Input::Keyboard(key) = input {
                     let delta = match key {
@@ -652,7 +1310,7 @@ impl Component for GameInstanceFireEquippedComponent {
                     };
                     if let Some(cursor) = state.cursor {
                         let new_cursor = cursor + delta;
-                        if new_cursor.is_valid(instance.game.inner_ref().world_size()) {
+                        if instance.is_in_view(new_cursor) {
                             state.cursor = Some(new_cursor);
                         }
                     }
@@ -671,25 +1329,29 @@ impl Component for GameInstanceFireEquippedComponent {
     }
 }
 
-struct GameInstanceFireBodyComponent(Option<FireBody>);
+struct GameInstanceThrowComponent(Option<Throw>);
 
-impl Component for GameInstanceFireBodyComponent {
-    type Output = Option<(Result<Coord, Cancel>, FireBody)>;
+impl Component for GameInstanceThrowComponent {
+    type Output = Option<(Result<Coord, Cancel>, Throw)>;
     type State = GameLoopData;
 
     fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
         state.render(ctx, fb, Mode::Aiming);
     }
 
-    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
         let instance = state.instance.as_mut().unwrap();
         if event.is_escape() {
             return Some((Err(Cancel), self.0.take().unwrap()));
         }
-        match event {
+        // throwing starts from the apply menu so there may not be a cursor yet
+        if state.cursor.is_none() {
+            state.cursor = Some(instance.game.inner_ref().player_coord());
+        }
+        match map_relative_event(event, ctx) {
             Event::Input(input) => {
                 if let Input::Mouse(MouseInput::MouseMove { coord, .. }) = input {
-                    if coord.is_valid(instance.game.inner_ref().world_size()) {
+                    if let Some(coord) = instance.screen_to_world(coord) {
                         state.cursor = Some(coord);
                     }
                 }
@@ -699,7 +1361,9 @@ impl Component for GameInstanceFireBodyComponent {
                     }
                 }
                 if let Input::Mouse(MouseInput::MousePress { coord, .. }) = input {
-                    return Some((Ok(coord), self.0.take().unwrap()));
+                    if let Some(coord) = instance.screen_to_world(coord) {
+                        return Some((Ok(coord), self.0.take().unwrap()));
+                    }
                 }
                 if let Input::Keyboard(key) = input {
                     let delta = match key {
@@ -711,7 +1375,7 @@ impl Component for GameInstanceFireBodyComponent {
                     };
                     if let Some(cursor) = state.cursor {
                         let new_cursor = cursor + delta;
-                        if new_cursor.is_valid(instance.game.inner_ref().world_size()) {
+                        if instance.is_in_view(new_cursor) {
                             state.cursor = Some(new_cursor);
                         }
                     }
@@ -730,35 +1394,100 @@ impl Component for GameInstanceFireBodyComponent {
     }
 }
 
-fn menu_style<T: 'static>(menu: AppCF<T>) -> AppCF<T> {
-    let mut border_style = BorderStyle::default();
-    border_style.foreground = colours::VAPORWAVE_FOREGROUND.to_rgba32(255);
-    menu.border(border_style)
-        .fill(MENU_BACKGROUND)
-        .centre()
-        .overlay_tint(
-            render_state(|state: &State, ctx, fb| state.render(ctx, fb, Mode::Normal)),
-            chargrid::core::TintDim(63),
-            60,
-        )
-}
+struct GameInstanceFireBodyComponent(Option<FireBody>);
 
-#[derive(Clone)]
-enum MainMenuEntry {
-    NewGame,
-    Help,
-    Quit,
-}
+impl Component for GameInstanceFireBodyComponent {
+    type Output = Option<(Result<Coord, Cancel>, FireBody)>;
+    type State = GameLoopData;
 
-fn title_decorate<T: 'static>(cf: AppCF<T>) -> AppCF<T> {
-    let decoration = {
-        let style = Style::plain_text();
-        chargrid::many![styled_string(
-            "Electric Organ".to_string(),
-            style
-                .with_bold(true)
-                .with_foreground(colours::VAPORWAVE_BACKGROUND.to_rgba32(255))
-                .with_background(
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        state.render(ctx, fb, Mode::Throwing);
+    }
+
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let instance = state.instance.as_mut().unwrap();
+        if event.is_escape() {
+            return Some((Err(Cancel), self.0.take().unwrap()));
+        }
+        match map_relative_event(event, ctx) {
+            Event::Input(input) => {
+                if let Input::Mouse(MouseInput::MouseMove { coord, .. }) = input {
+                    if let Some(coord) = instance.screen_to_world(coord) {
+                        state.cursor = Some(coord);
+                    }
+                }
+                if let Input::Keyboard(input::keys::RETURN) = input {
+                    if let Some(coord) = state.cursor {
+                        return Some((Ok(coord), self.0.take().unwrap()));
+                    }
+                }
+                if let Input::Mouse(MouseInput::MousePress { coord, .. }) = input {
+                    if let Some(coord) = instance.screen_to_world(coord) {
+                        return Some((Ok(coord), self.0.take().unwrap()));
+                    }
+                }
+                if let Input::Keyboard(key) = input {
+                    let delta = match key {
+                        KeyboardInput::Left => Coord::new(-1, 0),
+                        KeyboardInput::Right => Coord::new(1, 0),
+                        KeyboardInput::Up => Coord::new(0, -1),
+                        KeyboardInput::Down => Coord::new(0, 1),
+                        _ => Coord::new(0, 0),
+                    };
+                    if let Some(cursor) = state.cursor {
+                        let new_cursor = cursor + delta;
+                        if instance.is_in_view(new_cursor) {
+                            state.cursor = Some(new_cursor);
+                        }
+                    }
+                }
+            }
+            Event::Tick(since_previous) => {
+                Running::cheat().tick(&mut instance.game, since_previous, &state.game_config);
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, ctx: Ctx) -> Size {
+        ctx.bounding_box.size()
+    }
+}
+
+fn menu_style<T: 'static>(menu: AppCF<T>) -> AppCF<T> {
+    let mut border_style = BorderStyle::default();
+    border_style.foreground = colours::VAPORWAVE_FOREGROUND.to_rgba32(255);
+    menu.border(border_style)
+        .fill(MENU_BACKGROUND)
+        .centre()
+        .overlay_tint(
+            render_state(|state: &State, ctx, fb| state.render(ctx, fb, Mode::Normal)),
+            chargrid::core::TintDim(63),
+            60,
+        )
+}
+
+#[derive(Clone)]
+enum MainMenuEntry {
+    NewGame,
+    Tutorial,
+    WatchLastRun,
+    Help,
+    #[cfg(feature = "leaderboard")]
+    Leaderboard,
+    Quit,
+}
+
+fn title_decorate<T: 'static>(cf: AppCF<T>) -> AppCF<T> {
+    let decoration = {
+        let style = Style::plain_text();
+        chargrid::many![styled_string(
+            "Electric Organ".to_string(),
+            style
+                .with_bold(true)
+                .with_foreground(colours::VAPORWAVE_BACKGROUND.to_rgba32(255))
+                .with_background(
                     colours::VAPORWAVE_FOREGROUND
                         .to_rgba32(255)
                         .saturating_scalar_mul_div(4, 3)
@@ -778,7 +1507,11 @@ fn main_menu() -> AppCF<MainMenuEntry> {
         builder.add_item_mut(item(entry, identifier).add_hotkey_char(ch));
     };
     add_item(NewGame, "New Game", 'n');
+    add_item(Tutorial, "Tutorial", 't');
+    add_item(WatchLastRun, "Watch Last Run", 'w');
     add_item(Help, "Help", 'h');
+    #[cfg(feature = "leaderboard")]
+    add_item(Leaderboard, "Leaderboard", 'l');
     if !cfg!(feature = "web") {
         add_item(Quit, "Quit", 'q');
     }
@@ -951,206 +1684,1452 @@ impl Component for MainMenuBackground {
     }
 }
 
-fn help() -> AppCF<()> {
-    use chargrid::pad_by::Padding;
-    menu_style(
-        text::help(60)
-            .pad_by(Padding {
-                left: 1,
-                right: 4,
-                top: 1,
-                bottom: 1,
-            })
-            .overlay(background(), 1),
-    )
-}
+// Time passed to a game being driven by steps for each tick while skipping ahead
+const SKIP_TICK: Duration = Duration::from_millis(16);
 
-struct MessageLog {
-    scroll_from_bottom: usize,
-    reason: MessageLogReason,
+// Animations which take longer than this many ticks are assumed to never end while skipping ahead
+const MAX_SKIP_TICKS_PER_STEP: usize = 10000;
+
+/// A game driven by steps (as headless games are) rather than by the player's input, for showing
+/// a bot playing or a replay
+struct DrivenGame {
+    instance: GameInstance,
+    // only `None` while a step is being taken
+    witness: Option<Witness>,
+    config: GameConfig,
 }
 
-impl Component for MessageLog {
-    type Output = Option<()>;
-    type State = GameLoopData;
+impl DrivenGame {
+    fn new(
+        config: GameConfig,
+        background: Background,
+        victories: Vec<Victory>,
+        remnant: Option<Remnant>,
+        rng_seed: u64,
+    ) -> Self {
+        let (mut instance, running) = GameInstance::new(&config, background, victories, rng_seed);
+        if let Some(remnant) = remnant {
+            instance.game.inherit_remnant(remnant);
+        }
+        Self {
+            instance,
+            witness: Some(running.into_witness()),
+            config,
+        }
+    }
 
-    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
-        use chargrid::text::*;
-        let ctx = ctx.set_size(self.size(state, ctx));
-        match self.reason {
-            MessageLogReason::Die => {
-                Text::new(vec![StyledString {
-                string: format!(
-                    "Your final moments. Scroll with ↑↓. Press any other key to return to main menu."
-                ),
-                style: Style::plain_text().with_foreground(Rgba32::new_grey(187)),
-            }])
-            .wrap_word()
-            .render(&(), ctx, fb);
-            }
-            MessageLogReason::Gameplay => {
-                Text::new(vec![StyledString {
-                    string: format!("Scroll with ↑↓. Press any other key to return to the game."),
-                    style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
-                }])
-                .wrap_word()
-                .render(&(), ctx, fb);
+    fn game(&self) -> &game::Game {
+        self.instance.game.inner_ref()
+    }
+
+    fn has_ended(&self) -> bool {
+        matches!(
+            self.witness,
+            Some(Witness::GameOver(_)) | Some(Witness::Win(_))
+        )
+    }
+
+    fn is_menu_open(&self) -> bool {
+        matches!(self.witness, Some(Witness::Menu(_)))
+    }
+
+    // Advance any animations that are playing. Returns true if the game is still waiting for
+    // them to finish before it can take another step.
+    fn tick(&mut self, since_previous: Duration) -> bool {
+        let blocked = self.game().is_gameplay_blocked();
+        if blocked {
+            if let Some(Witness::Running(running)) = self.witness.take() {
+                self.witness =
+                    Some(running.tick(&mut self.instance.game, since_previous, &self.config));
             }
-            MessageLogReason::Win => {
-                Text::new(vec![StyledString {
-                    string: format!(
-                        "You won. Scroll with ↑↓. Press any other key to return to main menu."
-                    ),
-                    style: Style::plain_text().with_foreground(Rgba32::new_grey(187)),
-                }])
-                .wrap_word()
-                .render(&(), ctx, fb);
+        }
+        // driven games play silently
+        self.instance.game.drain_external_events();
+        self.instance.update_camera();
+        blocked && self.game().is_gameplay_blocked()
+    }
+
+    fn step(&mut self, step: Step) -> Result<(), StepError> {
+        let witness = self.witness.take().unwrap();
+        let (witness, result) = headless::apply_step(&mut self.instance.game, witness, step);
+        self.witness = Some(witness);
+        result
+    }
+
+    // Take a step and let any animations it causes play out immediately
+    fn step_and_settle(&mut self, step: Step) {
+        let _ = self.step(step);
+        for _ in 0..MAX_SKIP_TICKS_PER_STEP {
+            if !self.tick(SKIP_TICK) {
+                break;
             }
         }
-        let ctx = ctx.add_xy(0, 3);
-        let instance = state.instance.as_ref().unwrap();
-        let message_log = instance.game.inner_ref().message_log();
-        let num_messages = message_log.len();
-        if num_messages == 0 {
-            StyledString {
-                string: format!("(No messages in log.)"),
-                style: Style::plain_text(),
+    }
+}
+
+/// A game played by a bot, shown on the title screen when the main menu has been idle for a while
+struct AttractModeGame {
+    game: DrivenGame,
+    bot: GreedyBot,
+    countdown: Duration,
+}
+
+impl AttractModeGame {
+    fn new(game_config: &GameConfig) -> Self {
+        let config = GameConfig {
+            demo: true,
+            ..game_config.clone()
+        };
+        let mut rng = Isaac64Rng::from_entropy();
+        Self {
+            game: DrivenGame::new(config, Background::default(), Vec::new(), None, rng.gen()),
+            bot: GreedyBot::new(rng.gen()),
+            countdown: ATTRACT_MODE_STEP_PERIOD,
+        }
+    }
+
+    // Let animations play out, and have the bot take a step when it's due. Returns false once
+    // the game has ended.
+    fn tick(&mut self, since_previous: Duration) -> bool {
+        if self.game.has_ended() {
+            return false;
+        }
+        if self.game.tick(since_previous) {
+            return true;
+        }
+        if let Some(remaining) = self.countdown.checked_sub(since_previous) {
+            if remaining > Duration::ZERO {
+                self.countdown = remaining;
+                return true;
             }
-            .render(&(), ctx, fb);
+        }
+        self.countdown = ATTRACT_MODE_STEP_PERIOD;
+        let step = self
+            .bot
+            .choose_in_game(self.game.game(), self.game.is_menu_open());
+        let num_messages = self.game.game().message_log().len();
+        let result = self.game.step(step);
+        self.bot
+            .observe(self.game.game(), step, result, num_messages);
+        true
+    }
+}
+
+// Time between steps while watching a replay, and while fast-forwarding through one
+const REPLAY_STEP_PERIOD: Duration = Duration::from_millis(200);
+const REPLAY_FAST_FORWARD_STEP_PERIOD: Duration = Duration::from_millis(25);
+
+/// Plays back a recorded run. The run can be paused, stepped through one step at a time,
+/// fast-forwarded, or skipped to the end.
+struct ReplayViewer {
+    game: DrivenGame,
+    steps: Vec<Step>,
+    next_step: usize,
+    paused: bool,
+    fast_forward: bool,
+    countdown: Duration,
+}
+
+impl ReplayViewer {
+    fn new(replay: Replay, game_config: &GameConfig) -> Self {
+        let Replay {
+            rng_seed,
+            background,
+            victories,
+            remnant,
+            steps,
+            ..
+        } = replay;
+        Self {
+            game: DrivenGame::new(
+                game_config.clone(),
+                background,
+                victories,
+                remnant,
+                rng_seed,
+            ),
+            steps,
+            next_step: 0,
+            paused: false,
+            fast_forward: false,
+            countdown: REPLAY_STEP_PERIOD,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.next_step >= self.steps.len() || self.game.has_ended()
+    }
+
+    fn take_step(&mut self) {
+        if let Some(&step) = self.steps.get(self.next_step) {
+            self.next_step += 1;
+            let _ = self.game.step(step);
+        }
+    }
+
+    // Play the remainder of the run without stopping for animations, which for most recorded
+    // runs means jumping to the player's death
+    fn skip_to_end(&mut self) {
+        while !self.is_finished() {
+            let step = self.steps[self.next_step];
+            self.next_step += 1;
+            self.game.step_and_settle(step);
+        }
+    }
+
+    fn render_status(&self, ctx: Ctx, fb: &mut FrameBuffer) {
+        let state = if self.is_finished() {
+            "finished"
+        } else if self.paused {
+            "paused"
+        } else if self.fast_forward {
+            "fast-forward"
         } else {
-            let message_log_start = message_log
-                .len()
-                .saturating_sub(ctx.bounding_box.size().height() as usize)
-                - self.scroll_from_bottom;
-            for (i, &ref message) in message_log[message_log_start..].into_iter().enumerate() {
-                message_to_text(message.clone()).render(&(), ctx.add_y(i as i32), fb);
-            }
+            "playing"
+        };
+        let string = format!(
+            " Replay {}/{} ({}) | space: pause, →: step, f: fast-forward, d: skip to end, escape: exit ",
+            self.next_step,
+            self.steps.len(),
+            state,
+        );
+        let style = Style::plain_text()
+            .with_bold(true)
+            .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255))
+            .with_background(colours::VAPORWAVE_BACKGROUND.to_rgba32(255));
+        styled_string(string, style).render(&(), ctx.add_depth(100), fb);
+    }
+}
+
+impl Component for ReplayViewer {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        if state.config.screen_reader {
+            self.game
+                .instance
+                .render_narration(ctx, fb, state.config.combat_log);
+        } else {
+            self.game.instance.render(
+                ctx,
+                fb,
+                None,
+                Mode::Normal,
+                Coord::new(0, 0),
+                state.config.render_settings(),
+            );
         }
+        self.render_status(ctx, fb);
     }
 
-    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
-        let ctx = ctx.set_size(self.size(state, ctx));
-        let ctx = ctx.add_xy(0, 3);
-        let instance = state.instance.as_ref().unwrap();
-        let message_log = instance.game.inner_ref().message_log();
-        let num_messages = message_log.len();
+    fn update(&mut self, _state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        if event.is_escape() {
+            return Some(());
+        }
         match event {
-            Event::Input(Input::Keyboard(key)) => match key {
-                KeyboardInput::Up => {
-                    if num_messages > ctx.bounding_box.size().height() as usize {
-                        let offset = num_messages - ctx.bounding_box.size().height() as usize;
-                        let new_scroll = self.scroll_from_bottom + 1;
-                        if new_scroll < offset {
-                            self.scroll_from_bottom = new_scroll;
-                        }
+            Event::Input(Input::Keyboard(keyboard_input)) => match keyboard_input {
+                KeyboardInput::Char(' ') => self.paused = !self.paused,
+                KeyboardInput::Char('f') => self.fast_forward = !self.fast_forward,
+                KeyboardInput::Char('d') => self.skip_to_end(),
+                KeyboardInput::Right | KeyboardInput::Char('.') => {
+                    // stepping pauses playback so each step can be looked at
+                    self.paused = true;
+                    if !self.game.game().is_gameplay_blocked() {
+                        self.take_step();
                     }
                 }
-                KeyboardInput::Down => {
-                    self.scroll_from_bottom = self.scroll_from_bottom.saturating_sub(1);
-                }
-                _ => return Some(()),
+                _ => (),
             },
+            Event::Tick(since_previous) => {
+                if self.game.tick(since_previous) || self.paused || self.is_finished() {
+                    return None;
+                }
+                if let Some(remaining) = self.countdown.checked_sub(since_previous) {
+                    if remaining > Duration::ZERO {
+                        self.countdown = remaining;
+                        return None;
+                    }
+                }
+                self.countdown = if self.fast_forward {
+                    REPLAY_FAST_FORWARD_STEP_PERIOD
+                } else {
+                    REPLAY_STEP_PERIOD
+                };
+                self.take_step();
+            }
             _ => (),
         }
         None
     }
 
-    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
-        Size::new(60, 25)
+    fn size(&self, _state: &Self::State, ctx: Ctx) -> Size {
+        ctx.bounding_box.size()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum MessageLogReason {
-    Gameplay,
-    Die,
-    Win,
+fn watch_last_run() -> AppCF<()> {
+    on_state_then(|state: &mut State| {
+        match state.storage.load_replay() {
+            Some(replay) => cf(ReplayViewer::new(replay, &state.game_config)),
+            // there's nothing to watch until a run has finished
+            None => unit().some(),
+        }
+    })
 }
 
-fn message_log(reason: MessageLogReason) -> AppCF<()> {
-    menu_style(cf(MessageLog {
-        scroll_from_bottom: 0,
-        reason,
-    }))
+/// Wraps the main menu, replacing it with a game played by a bot after a period without input.
+/// Any input stops the bot and brings the menu back.
+struct AttractMode<C> {
+    menu: C,
+    idle: Duration,
+    game: Option<AttractModeGame>,
+}
+
+impl<C> AttractMode<C> {
+    fn new(menu: C) -> Self {
+        Self {
+            menu,
+            idle: Duration::ZERO,
+            game: None,
+        }
+    }
+}
+
+impl<T, C> Component for AttractMode<C>
+where
+    C: Component<Output = Option<T>, State = GameLoopData>,
+{
+    type Output = Option<T>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        match self.game.as_ref() {
+            None => self.menu.render(state, ctx, fb),
+            Some(attract_mode_game) => {
+                attract_mode_game.game.instance.render(
+                    ctx,
+                    fb,
+                    None,
+                    Mode::Normal,
+                    Coord::new(0, 0),
+                    state.config.render_settings(),
+                );
+                let screen_size = ctx.bounding_box.size();
+                let title_style = Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255))
+                    .with_background(colours::VAPORWAVE_BACKGROUND.to_rgba32(255));
+                for (y, string) in [(2, " Electric Organ "), (4, " Press any key ")] {
+                    let x = (screen_size.width() as i32 - string.len() as i32) / 2;
+                    styled_string(string.to_string(), title_style).render(
+                        state,
+                        ctx.add_xy(x, y).add_depth(100),
+                        fb,
+                    );
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        match event {
+            Event::Input(_) => {
+                self.idle = Duration::ZERO;
+                if self.game.take().is_some() {
+                    // the input that stops the bot doesn't also select a menu item
+                    return None;
+                }
+            }
+            Event::Tick(since_previous) => match self.game.as_mut() {
+                Some(game) => {
+                    if !game.tick(since_previous) {
+                        self.game = Some(AttractModeGame::new(&state.game_config));
+                    }
+                    return None;
+                }
+                None => {
+                    self.idle += since_previous;
+                    if self.idle >= ATTRACT_MODE_IDLE {
+                        self.game = Some(AttractModeGame::new(&state.game_config));
+                    }
+                }
+            },
+            _ => (),
+        }
+        self.menu.update(state, ctx, event)
+    }
+
+    fn size(&self, state: &Self::State, ctx: Ctx) -> Size {
+        self.menu.size(state, ctx)
+    }
+}
+
+fn help() -> AppCF<()> {
+    use chargrid::pad_by::Padding;
+    menu_style(
+        text::help(60)
+            .pad_by(Padding {
+                left: 1,
+                right: 4,
+                top: 1,
+                bottom: 1,
+            })
+            .overlay(background(), 1),
+    )
+}
+
+fn hint(hint: Hint) -> AppCF<()> {
+    use chargrid::pad_by::Padding;
+    menu_style(
+        text::hint(60, hint.title(), hint.body())
+            .pad_by(Padding {
+                left: 1,
+                right: 4,
+                top: 1,
+                bottom: 1,
+            })
+            .overlay(background(), 1),
+    )
+}
+
+#[cfg(feature = "leaderboard")]
+struct LeaderboardView {
+    requested: bool,
+    entries: Option<Result<Vec<RunSummary>, String>>,
+}
+
+#[cfg(feature = "leaderboard")]
+impl LeaderboardView {
+    const SIZE: Size = Size::new_u16(60, 23);
+    const NUM_ENTRIES: usize = 10;
+}
+
+#[cfg(feature = "leaderboard")]
+impl Component for LeaderboardView {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let plain = |string: String| StyledString {
+            string,
+            style: Style::plain_text(),
+        };
+        let faint = |string: String| StyledString {
+            string,
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        };
+        let mut lines = vec![
+            faint("Leaderboard. Press any key to return to the main menu.".to_string()),
+            plain(String::new()),
+        ];
+        match self.entries.as_ref() {
+            _ if state.leaderboard.is_none() => {
+                lines.push(plain("No leaderboard server is configured.".to_string()))
+            }
+            None => lines.push(plain("Loading...".to_string())),
+            Some(Err(e)) => lines.push(plain(format!("Couldn't load the leaderboard: {}", e))),
+            Some(Ok(entries)) if entries.is_empty() => {
+                lines.push(plain("No runs have been submitted yet.".to_string()))
+            }
+            Some(Ok(entries)) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    lines.push(plain(format!(
+                        "{:>2}. {:>6}  {:<15}  seed {}",
+                        i + 1,
+                        entry.score,
+                        entry.result.name(),
+                        entry.seed
+                    )));
+                    lines.push(faint(format!("    {}", entry.conducts.join(", "))));
+                }
+            }
+        }
+        for (i, line) in lines.into_iter().enumerate() {
+            line.render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        if let Some(leaderboard) = state.leaderboard.as_mut() {
+            if !self.requested {
+                leaderboard.request_top_entries();
+                self.requested = true;
+            }
+            if let Some(entries) = leaderboard.poll_top_entries() {
+                self.entries = Some(entries.map(|mut entries| {
+                    entries.truncate(Self::NUM_ENTRIES);
+                    entries
+                }));
+            }
+        }
+        event.keyboard_input().map(|_| ())
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+#[cfg(feature = "leaderboard")]
+fn leaderboard() -> AppCF<()> {
+    menu_style(
+        cf(LeaderboardView {
+            requested: false,
+            entries: None,
+        })
+        .overlay(background(), 1),
+    )
+}
+
+struct MessageLog {
+    scroll_from_bottom: usize,
+    reason: MessageLogReason,
+}
+
+impl Component for MessageLog {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(self.size(state, ctx));
+        match self.reason {
+            MessageLogReason::Die => {
+                Text::new(vec![StyledString {
+                string: format!(
+                    "Your final moments. Scroll with ↑↓. Press any other key to return to main menu."
+                ),
+                style: Style::plain_text().with_foreground(Rgba32::new_grey(187)),
+            }])
+            .wrap_word()
+            .render(&(), ctx, fb);
+            }
+            MessageLogReason::Gameplay => {
+                Text::new(vec![StyledString {
+                    string: format!("Scroll with ↑↓. Press any other key to return to the game."),
+                    style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+                }])
+                .wrap_word()
+                .render(&(), ctx, fb);
+            }
+            MessageLogReason::Win => {
+                Text::new(vec![StyledString {
+                    string: format!(
+                        "You won. Scroll with ↑↓. Press any other key to return to main menu."
+                    ),
+                    style: Style::plain_text().with_foreground(Rgba32::new_grey(187)),
+                }])
+                .wrap_word()
+                .render(&(), ctx, fb);
+            }
+        }
+        let ctx = ctx.add_xy(0, 3);
+        let instance = state.instance.as_ref().unwrap();
+        let message_log = instance.game.inner_ref().message_log();
+        let num_messages = message_log.len();
+        if num_messages == 0 {
+            StyledString {
+                string: format!("(No messages in log.)"),
+                style: Style::plain_text(),
+            }
+            .render(&(), ctx, fb);
+        } else {
+            let message_log_start = message_log
+                .len()
+                .saturating_sub(ctx.bounding_box.size().height() as usize)
+                - self.scroll_from_bottom;
+            for (i, &ref message) in message_log[message_log_start..].into_iter().enumerate() {
+                message_to_text(
+                    message.clone(),
+                    instance.game.inner_ref(),
+                    state.config.combat_log,
+                )
+                .render(&(), ctx.add_y(i as i32), fb);
+            }
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let ctx = ctx.set_size(self.size(state, ctx));
+        let ctx = ctx.add_xy(0, 3);
+        let instance = state.instance.as_ref().unwrap();
+        let message_log = instance.game.inner_ref().message_log();
+        let num_messages = message_log.len();
+        match event {
+            Event::Input(Input::Keyboard(key)) => match key {
+                KeyboardInput::Up => {
+                    if num_messages > ctx.bounding_box.size().height() as usize {
+                        let offset = num_messages - ctx.bounding_box.size().height() as usize;
+                        let new_scroll = self.scroll_from_bottom + 1;
+                        if new_scroll < offset {
+                            self.scroll_from_bottom = new_scroll;
+                        }
+                    }
+                }
+                KeyboardInput::Down => {
+                    self.scroll_from_bottom = self.scroll_from_bottom.saturating_sub(1);
+                }
+                _ => return Some(()),
+            },
+            _ => (),
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Size::new(60, 25)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MessageLogReason {
+    Gameplay,
+    Die,
+    Win,
+}
+
+fn message_log(reason: MessageLogReason) -> AppCF<()> {
+    menu_style(cf(MessageLog {
+        scroll_from_bottom: 0,
+        reason,
+    }))
+}
+
+// Splits text into lines no wider than `width`, breaking between words where possible
+fn wrap_lines(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+struct DocumentView {
+    document: Document,
+    lines: Vec<String>,
+    scroll: usize,
+}
+
+impl DocumentView {
+    const SIZE: Size = Size::new_u16(60, 25);
+    // The title and instructions are drawn above the body
+    const BODY_OFFSET: u32 = 3;
+
+    fn new(document: Document) -> Self {
+        Self {
+            document,
+            lines: wrap_lines(document.body(), Self::SIZE.width() as usize),
+            scroll: 0,
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines
+            .len()
+            .saturating_sub((Self::SIZE.height() - Self::BODY_OFFSET) as usize)
+    }
+}
+
+impl Component for DocumentView {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, _state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE);
+        StyledString {
+            string: self.document.title().to_string(),
+            style: Style::plain_text().with_bold(true),
+        }
+        .render(&(), ctx, fb);
+        let instructions = if self.max_scroll() > 0 {
+            "Scroll with ↑↓. Press any other key to close."
+        } else {
+            "Press any key to close."
+        };
+        StyledString {
+            string: instructions.to_string(),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }
+        .render(&(), ctx.add_y(1), fb);
+        let ctx = ctx.add_y(Self::BODY_OFFSET as i32);
+        let num_lines = ctx.bounding_box.size().height() as usize;
+        for (i, line) in self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(num_lines)
+            .enumerate()
+        {
+            StyledString::plain_text(line.clone()).render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, _state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        match event {
+            Event::Input(Input::Keyboard(key)) => match key {
+                KeyboardInput::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyboardInput::Down => self.scroll = (self.scroll + 1).min(self.max_scroll()),
+                _ => return Some(()),
+            },
+            _ => (),
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn document_view(document: Document) -> AppCF<()> {
+    menu_style(cf(DocumentView::new(document)))
+}
+
+fn read(read: witness::Read) -> AppCF<Witness> {
+    document_view(read.document).map_val(|| read.close())
+}
+
+// Everything the player has read this game, any of which can be read again
+fn collected_logs() -> AppCF<()> {
+    use menu::builder::*;
+    on_state_then(|state: &mut State| {
+        let documents = state
+            .instance
+            .as_ref()
+            .map(|instance| instance.game.inner_ref().documents_read().to_vec())
+            .unwrap_or_default();
+        if documents.is_empty() {
+            return menu_style(
+                chargrid::text::StyledString::plain_text(
+                    "You haven't found anything to read yet.".to_string(),
+                )
+                .cf::<State>()
+                .press_any_key(),
+            );
+        }
+        let mut builder = menu_builder();
+        for (document, ch) in documents.into_iter().zip(ALPHABET.chars()) {
+            let identifier = MENU_FADE_SPEC
+                .identifier(move |b| write!(b, "{}) {}", ch, document.title()).unwrap());
+            builder.add_item_mut(item(document, identifier).add_hotkey_char(ch));
+        }
+        let title = {
+            use chargrid::text::*;
+            Text::new(vec![StyledString {
+                string: "Collected logs (escape to return):".to_string(),
+                style: Style::plain_text(),
+            }])
+            .wrap_word()
+            .cf::<State>()
+            .set_width(50)
+        };
+        menu_style(
+            builder
+                .build_cf()
+                .menu_harness()
+                .with_title_vertical(title, 2),
+        )
+        .repeat_unit(|document_or_close| match document_or_close {
+            Ok(document) => document_view(document).continue_(),
+            Err(Close) => val_once(()).break_(),
+        })
+    })
+}
+
+struct ViewOrgans;
+impl ViewOrgans {
+    const SIZE: Size = Size::new_u16(70, 14);
+}
+impl Component for ViewOrgans {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
+        Text::new(vec![StyledString {
+            string: format!("Viewing your organs. Press any key to return to the game."),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }])
+        .wrap_word()
+        .render(&(), ctx, fb);
+        let instance = state.instance.as_ref().unwrap();
+        let ctx = ctx.add_y(2);
+        let organs = instance.game.inner_ref().player_organs();
+        for i in 0..instance.game.inner_ref().num_player_organ_slots() {
+            let s = if let Some(organ) = organs.get(i) {
+                let string = organ_string_for_menu(&organ.organ);
+                let string = if organ.active {
+                    string
+                } else {
+                    format!("INACTIVE {string}")
+                };
+                StyledString {
+                    string,
+                    style: Style::plain_text(),
+                }
+            } else {
+                StyledString {
+                    string: "(empty)".to_string(),
+                    style: Style::plain_text().with_foreground(Rgb24::new_grey(127).to_rgba32(255)),
+                }
+            };
+            s.render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, _state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        if event.keyboard_input().is_some() {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn view_organs() -> AppCF<()> {
+    menu_style(cf(ViewOrgans))
+}
+
+struct CharacterSheet;
+impl CharacterSheet {
+    const SIZE: Size = Size::new_u16(70, 16);
+}
+impl Component for CharacterSheet {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
+        Text::new(vec![StyledString {
+            string: "Viewing your character. Press any key to return to the game.".to_string(),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }])
+        .wrap_word()
+        .render(&(), ctx, fb);
+        let game = state.instance.as_ref().unwrap().game.inner_ref();
+        let heading = |string: &str| StyledString {
+            string: string.to_string(),
+            style: Style::plain_text().with_bold(true),
+        };
+        let experience = game.player_experience();
+        let mut lines = vec![
+            vec![
+                heading("Background: "),
+                StyledString::plain_text(background_string(game.background()).to_string()),
+            ],
+            vec![
+                heading("Experience level: "),
+                StyledString::plain_text(format!(
+                    "{} ({} XP)",
+                    experience.level + 1,
+                    experience.points
+                )),
+            ],
+            vec![],
+            vec![heading("Skills")],
+        ];
+        let skills = game.player_skills();
+        for &skill in Skill::ALL {
+            lines.push(vec![StyledString::plain_text(format!(
+                "{}: {}/{}",
+                skill_string(skill),
+                skills.level(skill),
+                MAX_SKILL_LEVEL
+            ))]);
+        }
+        lines.push(vec![]);
+        lines.push(vec![heading("Perks")]);
+        if experience.perks.is_empty() {
+            lines.push(vec![StyledString {
+                string: "(none)".to_string(),
+                style: Style::plain_text().with_foreground(Rgb24::new_grey(127).to_rgba32(255)),
+            }]);
+        }
+        for &perk in &experience.perks {
+            lines.push(vec![StyledString::plain_text(format!(
+                "{} ({})",
+                perk_string(perk),
+                perk_description_string(perk)
+            ))]);
+        }
+        let ctx = ctx.add_y(2);
+        for (i, line) in lines.into_iter().enumerate() {
+            Text::new(line).render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, _state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        if event.keyboard_input().is_some() {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn character_sheet() -> AppCF<()> {
+    menu_style(cf(CharacterSheet))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OptionsEntry {
+    MusicVolume,
+    SfxVolume,
+    CombatLog,
+}
+
+impl OptionsEntry {
+    const ALL: [Self; 3] = [Self::MusicVolume, Self::SfxVolume, Self::CombatLog];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::MusicVolume => "Music Volume",
+            Self::SfxVolume => "SFX Volume",
+            Self::CombatLog => "Combat Log",
+        }
+    }
+
+    // `None` for entries which aren't volume sliders
+    fn volume_mut(self, config: &mut Config) -> Option<&mut f32> {
+        match self {
+            Self::MusicVolume => Some(&mut config.music_volume),
+            Self::SfxVolume => Some(&mut config.sfx_volume),
+            Self::CombatLog => None,
+        }
+    }
+}
+
+struct Options {
+    selected: usize,
+}
+impl Options {
+    const SIZE: Size = Size::new_u16(50, 8);
+    const SLIDER_WIDTH: usize = 20;
+}
+impl Component for Options {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
+        Text::new(vec![StyledString {
+            string: "Up/down to choose, left/right to adjust. Press escape to return.".to_string(),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }])
+        .wrap_word()
+        .render(&(), ctx, fb);
+        let ctx = ctx.add_y(3);
+        let mut config = state.config.clone();
+        for (i, entry) in OptionsEntry::ALL.into_iter().enumerate() {
+            let string = match entry.volume_mut(&mut config) {
+                Some(&mut volume) => {
+                    let filled = (volume * Self::SLIDER_WIDTH as f32).round() as usize;
+                    format!(
+                        "{:<14}[{}{}] {:>3}%",
+                        entry.name(),
+                        "=".repeat(filled),
+                        " ".repeat(Self::SLIDER_WIDTH - filled),
+                        (volume * 100.0).round() as u32,
+                    )
+                }
+                None => format!("{:<14}< {} >", entry.name(), config.combat_log.name()),
+            };
+            let style = if i == self.selected {
+                Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255))
+            } else {
+                Style::plain_text()
+            };
+            StyledString { string, style }.render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        let input = match event {
+            Event::Input(input @ Input::Keyboard(_)) => input,
+            _ => return None,
+        };
+        let direction = match state.controls.get(input) {
+            Some(AppInput::Direction(direction)) => direction,
+            _ => {
+                if let Input::Keyboard(input::keys::ESCAPE | input::keys::RETURN) = input {
+                    state.save_config();
+                    return Some(());
+                }
+                return None;
+            }
+        };
+        let entry = OptionsEntry::ALL[self.selected];
+        match direction {
+            CardinalDirection::North => {
+                self.selected =
+                    (self.selected + OptionsEntry::ALL.len() - 1) % OptionsEntry::ALL.len()
+            }
+            CardinalDirection::South => {
+                self.selected = (self.selected + 1) % OptionsEntry::ALL.len()
+            }
+            CardinalDirection::West | CardinalDirection::East => {
+                let step = if direction == CardinalDirection::West {
+                    -VOLUME_STEP
+                } else {
+                    VOLUME_STEP
+                };
+                match entry.volume_mut(&mut state.config) {
+                    Some(volume) => {
+                        step_volume(volume, step);
+                        state.apply_volume();
+                    }
+                    None => {
+                        let all = CombatLog::ALL;
+                        let index = all
+                            .iter()
+                            .position(|&combat_log| combat_log == state.config.combat_log)
+                            .unwrap_or(0);
+                        let index = if direction == CardinalDirection::West {
+                            index.saturating_sub(1)
+                        } else {
+                            (index + 1).min(all.len() - 1)
+                        };
+                        state.config.combat_log = all[index];
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn options() -> AppCF<()> {
+    cf(Options { selected: 0 })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AccessibilityEntry {
+    ScreenShake,
+    ParticleDensity,
+    Palette,
+    GlyphSet,
+    ScreenReader,
+}
+
+impl AccessibilityEntry {
+    const ALL: [Self; 5] = [
+        Self::ScreenShake,
+        Self::ParticleDensity,
+        Self::Palette,
+        Self::GlyphSet,
+        Self::ScreenReader,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::ScreenShake => "Screen Shake",
+            Self::ParticleDensity => "Particles",
+            Self::Palette => "Colours",
+            Self::GlyphSet => "Characters",
+            Self::ScreenReader => "Screen Reader",
+        }
+    }
+
+    fn value_string(self, config: &Config) -> &'static str {
+        match self {
+            Self::ScreenShake => {
+                if config.screen_shake {
+                    "On"
+                } else {
+                    "Off"
+                }
+            }
+            Self::ParticleDensity => config.particle_density.name(),
+            Self::Palette => config.palette.name(),
+            Self::GlyphSet => config.glyph_set.name(),
+            Self::ScreenReader => {
+                if config.screen_reader {
+                    "On"
+                } else {
+                    "Off"
+                }
+            }
+        }
+    }
+
+    // Move the setting to its next (`step` is 1) or previous (`step` is -1) value
+    fn adjust(self, config: &mut Config, step: i32) {
+        match self {
+            Self::ScreenShake => config.screen_shake = !config.screen_shake,
+            Self::ScreenReader => config.screen_reader = !config.screen_reader,
+            Self::ParticleDensity => {
+                let all = ParticleDensity::ALL;
+                let index = all
+                    .iter()
+                    .position(|&density| density == config.particle_density)
+                    .unwrap_or(0) as i32;
+                let index = (index + step).clamp(0, all.len() as i32 - 1);
+                config.particle_density = all[index as usize];
+            }
+            Self::Palette => {
+                let all = Palette::ALL;
+                let index = all
+                    .iter()
+                    .position(|&palette| palette == config.palette)
+                    .unwrap_or(0) as i32;
+                let index = (index + step).clamp(0, all.len() as i32 - 1);
+                config.palette = all[index as usize];
+            }
+            Self::GlyphSet => {
+                let all = GlyphSet::ALL;
+                let index = all
+                    .iter()
+                    .position(|&glyph_set| glyph_set == config.glyph_set)
+                    .unwrap_or(0) as i32;
+                let index = (index + step).clamp(0, all.len() as i32 - 1);
+                config.glyph_set = all[index as usize];
+            }
+        }
+    }
+}
+
+/// Settings for players who are sensitive to motion and flashing, who are colour blind, or who
+/// use a screen reader
+struct Accessibility {
+    selected: usize,
+}
+impl Accessibility {
+    const SIZE: Size = Size::new_u16(50, 9);
+}
+impl Component for Accessibility {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
+        Text::new(vec![StyledString {
+            string: "Up/down to choose, left/right to adjust. Press escape to return.".to_string(),
+            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
+        }])
+        .wrap_word()
+        .render(&(), ctx, fb);
+        let ctx = ctx.add_y(3);
+        for (i, entry) in AccessibilityEntry::ALL.into_iter().enumerate() {
+            let string = format!(
+                "{:<14}< {} >",
+                entry.name(),
+                entry.value_string(&state.config)
+            );
+            let style = if i == self.selected {
+                Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255))
+            } else {
+                Style::plain_text()
+            };
+            StyledString { string, style }.render(&(), ctx.add_y(i as i32), fb);
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        let input = match event {
+            Event::Input(input @ Input::Keyboard(_)) => input,
+            _ => return None,
+        };
+        let direction = match state.controls.get(input) {
+            Some(AppInput::Direction(direction)) => direction,
+            _ => {
+                if let Input::Keyboard(input::keys::ESCAPE | input::keys::RETURN) = input {
+                    state.save_config();
+                    return Some(());
+                }
+                return None;
+            }
+        };
+        let num_entries = AccessibilityEntry::ALL.len();
+        let entry = AccessibilityEntry::ALL[self.selected];
+        match direction {
+            CardinalDirection::North => {
+                self.selected = (self.selected + num_entries - 1) % num_entries
+            }
+            CardinalDirection::South => self.selected = (self.selected + 1) % num_entries,
+            CardinalDirection::West => entry.adjust(&mut state.config, -1),
+            CardinalDirection::East => entry.adjust(&mut state.config, 1),
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn accessibility() -> AppCF<()> {
+    cf(Accessibility { selected: 0 })
+}
+
+enum KeyCapture {
+    // The next key pressed will be bound to the selected action
+    Waiting,
+    // The key pressed is already bound to a different action, and must be pressed again to
+    // confirm that it should be moved to the selected action
+    Conflict {
+        key: KeyboardInput,
+        existing: AppInput,
+    },
+}
+
+/// Lists every action along with the keys bound to it, and lets the player change them
+struct KeyBindings {
+    selected: usize,
+    capture: Option<KeyCapture>,
+}
+impl KeyBindings {
+    // The list of actions starts below the instructions and a blank line
+    const LIST_OFFSET: u16 = 2;
+    // The prompt shown while capturing a key goes below the list and a blank line
+    const PROMPT_OFFSET: u16 = Self::LIST_OFFSET + AppInput::ALL.len() as u16 + 1;
+    // The longest prompt (a conflicting binding) wraps onto two lines
+    const PROMPT_HEIGHT: u16 = 2;
+    // Includes the 1-cell top margin. There's no bottom margin so the menu fits on the screen.
+    const SIZE: Size = Size::new_u16(60, Self::PROMPT_OFFSET + Self::PROMPT_HEIGHT + 1);
+    // These keys are needed to navigate menus so they can't be bound to actions
+    const RESERVED_KEYS: [KeyboardInput; 2] = [input::keys::ESCAPE, input::keys::RETURN];
+}
+impl Component for KeyBindings {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
+        let hint_style = Style::plain_text().with_foreground(Rgba32::new_grey(127));
+        StyledString {
+            string: "Up/down to choose, enter to rebind. Press escape to return.".to_string(),
+            style: hint_style,
+        }
+        .render(&(), ctx, fb);
+        let list_ctx = ctx.add_y(Self::LIST_OFFSET as i32);
+        for (i, app_input) in AppInput::ALL.into_iter().enumerate() {
+            let keys = state
+                .controls
+                .keys_for(app_input)
+                .into_iter()
+                .map(key_name)
+                .collect::<Vec<_>>();
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys.join(", ")
+            };
+            let string = format!("{:<24}{}", app_input.name(), keys);
+            let style = if i == self.selected {
+                Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::VAPORWAVE_FOREGROUND.to_rgba32(255))
+            } else {
+                Style::plain_text()
+            };
+            StyledString { string, style }.render(&(), list_ctx.add_y(i as i32), fb);
+        }
+        let selected = AppInput::ALL[self.selected];
+        let status = match self.capture {
+            None => return,
+            Some(KeyCapture::Waiting) => format!(
+                "Press a key for {}, or escape to cancel.",
+                selected.name()
+            ),
+            Some(KeyCapture::Conflict { key, existing }) => format!(
+                "{} is already bound to {}. Press it again to bind it to {} instead, or any other key to cancel.",
+                key_name(key),
+                existing.name(),
+                selected.name(),
+            ),
+        };
+        Text::new(vec![StyledString {
+            string: status,
+            style: Style::plain_text()
+                .with_bold(true)
+                .with_foreground(colours::STAIRS.to_rgba32(255)),
+        }])
+        .wrap_word()
+        .render(&(), ctx.add_y(Self::PROMPT_OFFSET as i32), fb);
+    }
+
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        let key = match event {
+            Event::Input(Input::Keyboard(key)) => key,
+            _ => return None,
+        };
+        let selected = AppInput::ALL[self.selected];
+        match self.capture.take() {
+            Some(KeyCapture::Waiting) => {
+                if Self::RESERVED_KEYS.contains(&key) {
+                    return None;
+                }
+                match state.controls.action_for(key) {
+                    Some(existing) if existing != selected => {
+                        self.capture = Some(KeyCapture::Conflict { key, existing });
+                    }
+                    _ => state.controls.bind(key, selected),
+                }
+            }
+            Some(KeyCapture::Conflict {
+                key: conflicting_key,
+                ..
+            }) => {
+                if key == conflicting_key {
+                    state.controls.bind(key, selected);
+                }
+            }
+            None => {
+                if key == input::keys::ESCAPE {
+                    state.save_controls();
+                    return Some(());
+                }
+                if key == input::keys::RETURN {
+                    self.capture = Some(KeyCapture::Waiting);
+                    return None;
+                }
+                // The arrow keys always work here so a bad binding can't make this menu unusable
+                let direction = match (state.controls.get(Input::Keyboard(key)), key) {
+                    (Some(AppInput::Direction(direction)), _) => direction,
+                    (_, KeyboardInput::Up) => CardinalDirection::North,
+                    (_, KeyboardInput::Down) => CardinalDirection::South,
+                    _ => return None,
+                };
+                let num_entries = AppInput::ALL.len();
+                match direction {
+                    CardinalDirection::North => {
+                        self.selected = (self.selected + num_entries - 1) % num_entries
+                    }
+                    CardinalDirection::South => self.selected = (self.selected + 1) % num_entries,
+                    CardinalDirection::East | CardinalDirection::West => (),
+                }
+            }
+        }
+        None
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+fn key_bindings() -> AppCF<()> {
+    cf(KeyBindings {
+        selected: 0,
+        capture: None,
+    })
+}
+
+struct OverviewMap {
+    cursor: Option<Coord>,
 }
 
-struct ViewOrgans;
-impl ViewOrgans {
-    const SIZE: Size = Size::new_u16(70, 14);
-}
-impl Component for ViewOrgans {
+impl Component for OverviewMap {
     type Output = Option<()>;
     type State = GameLoopData;
 
     fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
-        use chargrid::text::*;
-        let ctx = ctx.set_size(Self::SIZE).add_xy(1, 1);
-        Text::new(vec![StyledString {
-            string: format!("Viewing your organs. Press any key to return to the game."),
-            style: Style::plain_text().with_foreground(Rgba32::new_grey(127)),
-        }])
-        .wrap_word()
-        .render(&(), ctx, fb);
         let instance = state.instance.as_ref().unwrap();
-        let ctx = ctx.add_y(2);
-        let organs = instance.game.inner_ref().player_organs();
-        for i in 0..game::MAX_ORGANS {
-            let s = if let Some(organ) = organs.get(i) {
-                let string = organ_string_for_menu(&organ.organ);
-                let string = if organ.active {
-                    string
-                } else {
-                    format!("INACTIVE {string}")
-                };
-                StyledString {
-                    string,
-                    style: Style::plain_text(),
-                }
-            } else {
-                StyledString {
-                    string: "(empty)".to_string(),
-                    style: Style::plain_text().with_foreground(Rgb24::new_grey(127).to_rgba32(255)),
-                }
-            };
-            s.render(&(), ctx.add_y(i as i32), fb);
-        }
+        instance.render_overview(ctx, fb, self.cursor, state.config.glyph_set);
     }
 
-    fn update(&mut self, _state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
-        if event.keyboard_input().is_some() {
-            Some(())
-        } else {
-            None
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        let instance = state.instance.as_ref().unwrap();
+        let game = instance.game.inner_ref();
+        match event {
+            Event::Input(Input::Mouse(MouseInput::MouseMove { coord, .. })) => {
+                let world_coord = coord + instance.overview_origin(self.cursor);
+                if coord.is_valid(VIEWPORT_SIZE) && game.world_size().is_valid(world_coord) {
+                    self.cursor = Some(world_coord);
+                }
+                None
+            }
+            Event::Input(input @ Input::Keyboard(_)) => {
+                let direction = match state.controls.get(input) {
+                    Some(AppInput::Direction(direction)) => direction,
+                    _ => return Some(()),
+                };
+                let cursor = self.cursor.unwrap_or(game.player_coord()) + direction.coord();
+                if game.world_size().is_valid(cursor) {
+                    self.cursor = Some(cursor);
+                }
+                None
+            }
+            _ => None,
         }
     }
 
-    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
-        Self::SIZE
+    fn size(&self, _state: &Self::State, ctx: Ctx) -> Size {
+        ctx.bounding_box.size()
     }
 }
 
-fn view_organs() -> AppCF<()> {
-    menu_style(cf(ViewOrgans))
+fn overview_map() -> AppCF<()> {
+    cf(OverviewMap { cursor: None }).fill(MENU_BACKGROUND)
 }
 
 fn main_menu_loop() -> AppCF<MainMenuOutput> {
     use MainMenuEntry::*;
-    title_decorate(
+    cf(AttractMode::new(title_decorate(
         main_menu()
             .add_offset(Coord::new(32, 12))
             .overlay(MainMenuBackground::new(), 1),
-    )
+    )))
     .repeat_unit(move |entry| match entry {
-        NewGame => text::loading(MAIN_MENU_TEXT_WIDTH)
+        NewGame => background_menu().and_then(|result| match result {
+            Ok(chosen) => text::loading(MAIN_MENU_TEXT_WIDTH)
+                .centre()
+                .overlay(background(), 1)
+                .then(move || {
+                    on_state(move |state: &mut State| MainMenuOutput::NewGame {
+                        new_running: state.new_game(chosen),
+                    })
+                })
+                .break_(),
+            Err(_) => unit().some().continue_(),
+        }),
+        Tutorial => text::loading(MAIN_MENU_TEXT_WIDTH)
             .centre()
             .overlay(background(), 1)
             .then(|| {
                 on_state(|state: &mut State| MainMenuOutput::NewGame {
-                    new_running: state.new_game(),
+                    new_running: state.new_tutorial(),
                 })
             })
             .break_(),
+        WatchLastRun => watch_last_run().continue_(),
         Help => help().continue_(),
+        #[cfg(feature = "leaderboard")]
+        Leaderboard => leaderboard().continue_(),
         Quit => val_once(MainMenuOutput::Quit).break_(),
     })
 }
@@ -1161,7 +3140,11 @@ enum PauseMenuEntry {
     SaveQuit,
     Save,
     NewGame,
+    Options,
+    Accessibility,
+    KeyBindings,
     Help,
+    CollectedLogs,
     Clear,
 }
 
@@ -1177,10 +3160,15 @@ fn pause_menu() -> AppCF<PauseMenuEntry> {
     add_item(Resume, "Resume", 'r');
     if !cfg!(feature = "web") {
         add_item(SaveQuit, "Save and Quit", 'q');
-        add_item(Save, "Save", 's');
     }
+    // on the web, saving makes the game available to export
+    add_item(Save, "Save", 's');
     add_item(NewGame, "New Game", 'n');
+    add_item(Options, "Options", 'o');
+    add_item(Accessibility, "Accessibility", 'a');
+    add_item(KeyBindings, "Key Bindings", 'b');
     add_item(Help, "Help", 'h');
+    add_item(CollectedLogs, "Collected Logs", 'l');
     add_item(Clear, "Clear", 'c');
     builder.build_cf()
 }
@@ -1210,14 +3198,21 @@ fn pause_menu_loop(running: witness::Running) -> AppCF<PauseOutput> {
                             })
                         })
                         .break_(),
-                    NewGame => text::loading(MAIN_MENU_TEXT_WIDTH)
-                        .then(|| {
-                            on_state(|state: &mut State| PauseOutput::ContinueGame {
-                                running: state.new_game(),
+                    NewGame => background_menu().and_then(|result| match result {
+                        Ok(chosen) => text::loading(MAIN_MENU_TEXT_WIDTH)
+                            .then(move || {
+                                on_state(move |state: &mut State| PauseOutput::ContinueGame {
+                                    running: state.new_game(chosen),
+                                })
                             })
-                        })
-                        .break_(),
+                            .break_(),
+                        Err(_) => unit().some().continue_with(running),
+                    }),
+                    Options => options().continue_with(running),
+                    Accessibility => accessibility().continue_with(running),
+                    KeyBindings => key_bindings().continue_with(running),
                     Help => text::help(text_width).continue_with(running),
+                    CollectedLogs => collected_logs().continue_with(running),
                     Clear => on_state(|state: &mut State| {
                         state.clear_saved_game();
                         PauseOutput::MainMenu
@@ -1239,6 +3234,19 @@ fn pause(running: witness::Running) -> AppCF<PauseOutput> {
     menu_style(pause_menu_loop(running))
 }
 
+// Makes the coordinates of mouse events relative to the top-left corner of the viewport, which
+// moves when the window is resized
+fn map_relative_event(event: Event, ctx: Ctx) -> Event {
+    match event {
+        Event::Input(Input::Mouse(mouse_input)) => {
+            Event::Input(Input::Mouse(mouse_input.relative_to_coord(
+                ctx.bounding_box.top_left() + map_origin(ctx.bounding_box.size()),
+            )))
+        }
+        other => other,
+    }
+}
+
 fn game_instance_component(running: witness::Running) -> AppCF<GameLoopState> {
     cf(GameInstanceComponent::new(running)).some().no_peek()
 }
@@ -1250,14 +3258,7 @@ fn fire_equipped(fire_equipped: FireEquipped) -> AppCF<Witness> {
             Ok(coord) => {
                 let instance = state.instance.as_mut().unwrap();
                 let (witness, _) = fire_equipped.commit(&mut instance.game, coord);
-                for external_event in instance.game.take_external_events() {
-                    match external_event {
-                        ExternalEvent::FirePistol => state.music_state.sfx_pistol(),
-                        ExternalEvent::FireShotgun => state.music_state.sfx_shotgun(),
-                        ExternalEvent::FireRocket => state.music_state.sfx_rocket(),
-                        _ => (),
-                    }
-                }
+                state.handle_external_events();
                 witness
             }
             Err(Cancel) => fire_equipped.cancel(),
@@ -1271,26 +3272,213 @@ fn fire_body(fire_body: FireBody) -> AppCF<Witness> {
             Ok(coord) => {
                 let instance = state.instance.as_mut().unwrap();
                 let (witness, _) = fire_body.commit(&mut instance.game, coord);
-                for external_event in instance.game.take_external_events() {
-                    match external_event {
-                        ExternalEvent::FirePistol => state.music_state.sfx_pistol(),
-                        ExternalEvent::FireShotgun => state.music_state.sfx_shotgun(),
-                        _ => (),
-                    }
-                }
+                state.handle_external_events();
                 witness
             }
             Err(Cancel) => fire_body.cancel(),
         })
 }
 
+fn throw(throw: Throw) -> AppCF<Witness> {
+    cf(GameInstanceThrowComponent(Some(throw)))
+        .no_peek()
+        .map_side_effect(|(result, throw), state: &mut State| match result {
+            Ok(coord) => {
+                let instance = state.instance.as_mut().unwrap();
+                let (witness, _) = throw.commit(&mut instance.game, coord);
+                state.handle_external_events();
+                witness
+            }
+            Err(Cancel) => throw.cancel(),
+        })
+}
+
+struct SeedLink {
+    seed: u64,
+    shared: bool,
+}
+impl SeedLink {
+    const SIZE: Size = Size::new_u16(MAIN_MENU_TEXT_WIDTH as u16, 6);
+}
+impl Component for SeedLink {
+    type Output = Option<()>;
+    type State = GameLoopData;
+
+    fn render(&self, _state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        let string = if self.shared {
+            "Link copied. Press any key to continue.".to_string()
+        } else {
+            format!(
+                "This game's seed was {}. Press (c) to copy a link so others can play the same game, or any other key to continue.",
+                self.seed
+            )
+        };
+        Text::new(vec![StyledString {
+            string,
+            style: Style::plain_text(),
+        }])
+        .wrap_word()
+        .render(&(), ctx.set_size(Self::SIZE), fb);
+    }
+
+    fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
+        match event.keyboard_input() {
+            Some(KeyboardInput::Char('c')) if !self.shared => {
+                if let Some(share_seed) = state.share_seed.as_ref() {
+                    share_seed(self.seed);
+                }
+                self.shared = true;
+                None
+            }
+            Some(_) => Some(()),
+            None => None,
+        }
+    }
+
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> Size {
+        Self::SIZE
+    }
+}
+
+// Offer to share the seed of the game which just ended, if the frontend supports it
+fn seed_link() -> AppCF<()> {
+    on_state_then(
+        |state: &mut State| match (state.share_seed.as_ref(), state.instance.as_ref()) {
+            (Some(_), Some(instance)) => menu_style(cf(SeedLink {
+                seed: instance.rng_seed,
+                shared: false,
+            })),
+            _ => unit().some(),
+        },
+    )
+}
+
+fn background_string(background: Background) -> &'static str {
+    match background {
+        Background::StreetDoc => "Street Doc",
+        Background::Mercenary => "Mercenary",
+        Background::VampireInitiate => "Vampire Initiate",
+        Background::Scavenger => "Scavenger",
+    }
+}
+
+fn background_perk_string(background: Background) -> &'static str {
+    match background {
+        Background::StreetDoc => "medical supplies; stimpacks heal twice as much",
+        Background::Mercenary => "spare ammo; hiring mercenaries costs half",
+        Background::VampireInitiate => "a second, vampiric heart; blood vials also heal",
+        Background::Scavenger => "money and junk; items sell for more",
+    }
+}
+
+// Shown before each new game. Escaping goes back to the previous menu.
+fn background_menu() -> AppCF<OrClose<Background>> {
+    use menu::builder::*;
+    let mut builder = menu_builder();
+    for (&background, ch) in Background::ALL.iter().zip(ALPHABET.chars()) {
+        let identifier = MENU_FADE_SPEC.identifier(move |b| {
+            write!(
+                b,
+                "{}) {} ({})",
+                ch,
+                background_string(background),
+                background_perk_string(background)
+            )
+            .unwrap()
+        });
+        builder.add_item_mut(item(background, identifier).add_hotkey_char(ch));
+    }
+    let title = {
+        use chargrid::text::*;
+        Text::new(vec![StyledString {
+            string: "Choose your background:".to_string(),
+            style: Style::plain_text(),
+        }])
+        .wrap_word()
+        .cf::<State>()
+        .set_width(50)
+    };
+    menu_style(
+        builder
+            .build_cf()
+            .menu_harness()
+            .with_title_vertical(title, 2),
+    )
+}
+
+fn new_game_plus_bonus_string(bonus: NewGamePlusBonus) -> String {
+    match bonus {
+        NewGamePlusBonus::StartingMoney => "Start with extra money".to_string(),
+        NewGamePlusBonus::RetainedOrgan(organ) => {
+            format!("Start with your {}", organ_string_for_menu(&organ))
+        }
+        NewGamePlusBonus::HarderEnemies => "Face tougher enemies".to_string(),
+    }
+}
+
+// Winning unlocks a bonus for all future runs. Escaping skips the bonus but the victory is still
+// recorded.
+fn new_game_plus_menu() -> AppCF<()> {
+    use menu::builder::*;
+    let menu_cf = on_state_then(|state: &mut State| {
+        let mut bonuses = vec![
+            NewGamePlusBonus::StartingMoney,
+            NewGamePlusBonus::HarderEnemies,
+        ];
+        if let Some(instance) = state.instance.as_ref() {
+            for player_organ in instance.game.inner_ref().player_organs() {
+                if !player_organ.organ.original {
+                    bonuses.push(NewGamePlusBonus::RetainedOrgan(player_organ.organ));
+                }
+            }
+        }
+        let mut builder = menu_builder();
+        for (bonus, ch) in bonuses.into_iter().zip(ALPHABET.chars()) {
+            let name = new_game_plus_bonus_string(bonus);
+            let identifier =
+                MENU_FADE_SPEC.identifier(move |b| write!(b, "{}) {}", ch, name).unwrap());
+            builder.add_item_mut(item(bonus, identifier).add_hotkey_char(ch));
+        }
+        let title = {
+            use chargrid::text::*;
+            Text::new(vec![StyledString {
+                string: "Choose a bonus for your future runs (escape to skip):".to_string(),
+                style: Style::plain_text(),
+            }])
+            .wrap_word()
+            .cf::<State>()
+            .set_width(50)
+        };
+        menu_style(
+            builder
+                .build_cf()
+                .menu_harness()
+                .with_title_vertical(title, 2),
+        )
+    });
+    menu_cf.map_side_effect(|result, state: &mut State| {
+        let bonus = result.ok();
+        state.config.victories.push(Victory { bonus });
+        state.config.won = true;
+    })
+}
+
 fn win(win: game::Win) -> AppCF<()> {
     let text = match win {
         game::Win::Good => text::win(MAIN_MENU_TEXT_WIDTH),
         game::Win::Bad => text::bad_win(MAIN_MENU_TEXT_WIDTH),
     };
+    #[cfg(feature = "leaderboard")]
+    let text =
+        on_state(move |state: &mut State| state.submit_run(RunResult::from(win))).then(|| text);
+    let text =
+        on_state(|state: &mut State| state.publish_presence(Some(Activity::Won))).then(|| text);
+    let text = on_state(|state: &mut State| state.save_replay()).then(|| text);
     menu_style(text)
+        .then(new_game_plus_menu)
         .then(|| message_log(MessageLogReason::Win))
+        .then(seed_link)
         .map_side_effect(|_, state: &mut State| {
             state.clear_saved_game();
             state.save_config();
@@ -1300,9 +3488,15 @@ fn win(win: game::Win) -> AppCF<()> {
 fn game_over(reason: GameOverReason) -> AppCF<()> {
     menu_style(on_state_then(move |state: &mut State| {
         state.music_state.sfx_death();
+        state.save_replay();
+        state.record_death();
+        #[cfg(feature = "leaderboard")]
+        state.submit_run(RunResult::Died);
+        state.publish_presence(Some(Activity::Died));
         text::game_over(MAIN_MENU_TEXT_WIDTH, reason)
     }))
     .then(|| message_log(MessageLogReason::Die))
+    .then(seed_link)
     .map_side_effect(|_, state: &mut State| {
         state.clear_saved_game();
         state.save_config();
@@ -1313,6 +3507,7 @@ fn apply_item_description(item: Item) -> String {
     use Item::*;
     match item {
         Stimpack => "Consume to increase health".to_string(),
+        CorruptedStimpack => "Consume to increase health and radiation".to_string(),
         Antidote => "Consume to decrease poison".to_string(),
         BloodVialEmpty => "Fill with blood (must be standing on corpse)".to_string(),
         BloodVialFull => "Consume to increase oxygen".to_string(),
@@ -1321,30 +3516,65 @@ fn apply_item_description(item: Item) -> String {
         AntiRads => "Consume to reduce radiation".to_string(),
         OrganContainer(Some(_)) => "Dump contents".to_string(),
         OrganContainer(None) => "Harvest organ (must be standing on corpse)".to_string(),
-        Pistol => "Equip weapon (requires non-claw hand)".to_string(),
-        Shotgun | RocketLauncher => "Equip weapon (requires two non-claw hands)".to_string(),
-        PistolAmmo | ShotgunAmmo | Rocket => "Load into current weapon".to_string(),
+        Pistol | CursedPistol => "Equip weapon (requires non-claw hand)".to_string(),
+        Shotgun | RocketLauncher | SniperRifle => {
+            "Equip weapon (requires two non-claw hands)".to_string()
+        }
+        PistolAmmo | ShotgunAmmo | Rocket | SniperRounds => "Load into current weapon".to_string(),
+        Drone => "Deploy drone".to_string(),
+        Turret => "Place turret".to_string(),
+        Scanner => "Identify carried items".to_string(),
+        GeigerCounter => "Toggle radiation overlay".to_string(),
+        Schematics => "Reveal the layout of this level".to_string(),
+        MarketReport => "Check the price of organs on the black market".to_string(),
+        Plank => "Barricade an adjacent closed door".to_string(),
+        Anesthetic => "Used automatically during surgery".to_string(),
+        FieldSurgeryKit => "Install or remove an organ yourself".to_string(),
+        Immunosuppressant => "Consume to prevent rejection of harvested organs".to_string(),
+        Bandage => "Stop bleeding".to_string(),
+        Antipsychotic => "Ease psychosis from cybernetic organs".to_string(),
+        EmpGrenade => "Throw to disable cybernetics and damage robots".to_string(),
+        SmokeGrenade => "Throw to fill an area with smoke which blocks vision".to_string(),
+        GasMask => "Wear to protect against smoke and poison gas".to_string(),
+        HazardSuit => "Wear to protect against radiation".to_string(),
+        RawMeat => "Consume to gain food, or cook next to a fire".to_string(),
+        CookedMeat => "Consume to gain food".to_string(),
+        RottenMeat => "Consume to gain food (causes food poisoning)".to_string(),
+        Readable(_) => "Read it".to_string(),
     }
 }
 
+fn surgery_risk_string(game: &game::Game, organ: &game::Organ) -> String {
+    format!(
+        " ({}% risk)",
+        (game.surgery_failure_probability(organ) * 100.0).round()
+    )
+}
+
 fn menu_choice_string(game: &game::Game, choice: GameMenuChoice) -> String {
     match choice {
         GameMenuChoice::Empty => format!("(empty)"),
         GameMenuChoice::Dummy => panic!(),
         GameMenuChoice::DropItem(i) => {
             if let Some(item) = game.inventory_item(i) {
-                item_string_for_menu(item)
+                item_appearance_string_for_menu(game.item_appearance(item))
             } else {
                 format!("(empty)")
             }
         }
         GameMenuChoice::ApplyItem(i) => {
             if let Some(item) = game.inventory_item(i) {
-                format!(
-                    "{} - {}",
-                    item_string_for_menu(item),
-                    apply_item_description(item)
-                )
+                match game.item_appearance(item) {
+                    ItemAppearance::Identified(item) => format!(
+                        "{} - {}",
+                        item_string_for_menu(item),
+                        apply_item_description(item)
+                    ),
+                    appearance => format!(
+                        "{} - Use and find out",
+                        item_appearance_string_for_menu(appearance)
+                    ),
+                }
             } else {
                 format!("(empty)")
             }
@@ -1358,46 +3588,240 @@ fn menu_choice_string(game: &game::Game, choice: GameMenuChoice) -> String {
             WhichHand::Left => "Left Hand".to_string(),
             WhichHand::Right => "Right Hand".to_string(),
         },
+        GameMenuChoice::TakeOff(slot) => match slot {
+            WearSlot::Head => "Head".to_string(),
+            WearSlot::Body => "Body".to_string(),
+        },
         GameMenuChoice::BuyItem { item, .. } => {
-            format!("{} - {} CCz", item_string_for_menu(item), item.price())
+            format!(
+                "{} - {} CCz",
+                item_string_for_menu(item),
+                game.item_price(item)
+            )
         }
         GameMenuChoice::ClinicBuy { .. } => "Buy Organ".to_string(),
         GameMenuChoice::ClinicRemove => "Remove Organ".to_string(),
         GameMenuChoice::ClinicInstallFromContainer => "Install Organ from Container".to_string(),
+        GameMenuChoice::ClinicBuyInsurance => {
+            if game.has_clone_insurance() {
+                "CloneSure™ Policy (insured)".to_string()
+            } else {
+                format!("CloneSure™ Policy - {} CCz", game.clone_insurance_price())
+            }
+        }
         GameMenuChoice::ClinicBuyOrgan { organ, .. } => format!(
-            "{} - {} CCz",
+            "{} - {} CCz{}",
+            organ_string_for_menu(&organ),
+            game.organ_buy_price(&organ),
+            surgery_risk_string(game, &organ)
+        ),
+        GameMenuChoice::BuyOrgan { organ, hot, .. } => format!(
+            "{} - {} CCz{}",
             organ_string_for_menu(&organ),
-            organ.player_buy_price()
+            game.organ_black_market_price(&organ, hot),
+            if hot { " (hot)" } else { "" }
+        ),
+        GameMenuChoice::FieldSurgeryInstall { organ, .. } => format!(
+            "Install {} ({}% risk)",
+            organ_string_for_menu(&organ),
+            (game.field_surgery_failure_probability(&organ) * 100.0).round()
+        ),
+        GameMenuChoice::FieldSurgeryRemove { organ, .. } => format!(
+            "Remove {} ({}% risk)",
+            organ_string_for_menu(&organ),
+            (game.field_surgery_failure_probability(&organ) * 100.0).round()
         ),
         GameMenuChoice::ClinicInstallFromContainerOrgan { organ, .. } => {
             format!(
-                "{} - {} CCz",
+                "{} - {} CCz{}",
                 organ_string_for_menu(&organ),
-                organ.container_install_cost()
+                game.organ_install_cost(),
+                surgery_risk_string(game, &organ)
             )
         }
         GameMenuChoice::ClinicRemoveOrgan { organ, .. } => {
-            let price = organ.remove_price();
+            let price = game.organ_remove_price(&organ);
             if price < 0 {
                 format!(
-                    "{} - I'll pay you {} CCz",
+                    "{} - I'll pay you {} CCz{}",
                     organ_string_for_menu(&organ),
-                    -price
+                    -price,
+                    surgery_risk_string(game, &organ)
                 )
             } else {
-                format!("{} - {} CCz", organ_string_for_menu(&organ), price)
+                format!(
+                    "{} - {} CCz{}",
+                    organ_string_for_menu(&organ),
+                    price,
+                    surgery_risk_string(game, &organ)
+                )
             }
         }
+        GameMenuChoice::Sell { .. } => "Sell".to_string(),
+        GameMenuChoice::SellItem {
+            item,
+            inventory_index,
+            ..
+        } => {
+            format!(
+                "{} - {} CCz",
+                item_appearance_string_for_menu(game.item_appearance(item)),
+                game.inventory_item_sell_price(inventory_index).unwrap_or(0)
+            )
+        }
+        GameMenuChoice::SellOrgan { organ, .. } => format!(
+            "{} - {} CCz",
+            organ_string_for_menu(&organ),
+            game.organ_sell_price(&organ)
+        ),
+        GameMenuChoice::StashDeposit { .. } => "Deposit Item".to_string(),
+        GameMenuChoice::StashWithdraw { .. } => "Withdraw Item".to_string(),
+        GameMenuChoice::StashDepositItem { item, .. }
+        | GameMenuChoice::StashWithdrawItem { item, .. } => {
+            item_appearance_string_for_menu(game.item_appearance(item))
+        }
+        GameMenuChoice::HireMercenary { price, .. } => format!("Hire - {} CCz", price),
+        GameMenuChoice::GiveMercenaryGun { .. } => "Give Gun".to_string(),
+        GameMenuChoice::GiveMercenaryGunItem { item, .. } => item_string_for_menu(item),
+        GameMenuChoice::SwapWithMercenary { .. } => "Swap Places".to_string(),
+        GameMenuChoice::ChoosePerk(perk) => {
+            format!("{} ({})", perk_string(perk), perk_description_string(perk))
+        }
+        GameMenuChoice::ScriptAction(i) => game
+            .script_menu_actions()
+            .get(i)
+            .cloned()
+            .unwrap_or_default(),
     }
 }
 
 const ALPHABET: &'static str = "abcdefghijklmnopqrstuvwxyz";
 
+// Lets the stream's chat vote on a menu opened by the game. The vote starts when the menu is
+// first updated. The player can still choose for themselves before the vote ends, and if no one
+// votes the menu stays open until they do.
+struct VoteMenu<C> {
+    menu: C,
+    choices: Vec<game::MenuChoice>,
+    started: bool,
+    vote: Option<Vote>,
+}
+
+impl<C> VoteMenu<C> {
+    fn new(menu: C, choices: Vec<game::MenuChoice>) -> Self {
+        Self {
+            menu,
+            choices,
+            started: false,
+            vote: None,
+        }
+    }
+
+    fn start(&mut self, state: &mut GameLoopData) {
+        self.started = true;
+        let (voting, instance) = match (state.voting.as_mut(), state.instance.as_ref()) {
+            (Some(voting), Some(instance)) => (voting, instance),
+            _ => return,
+        };
+        // votes posted before this menu opened were for an earlier one
+        voting.chat_client.poll_messages();
+        let options = self
+            .choices
+            .iter()
+            .take(MAX_OPTIONS)
+            .enumerate()
+            .map(|(i, &choice)| {
+                let name = menu_choice_string(instance.game.inner_ref(), choice);
+                format!("{}) {}", option_letter(i), name)
+            })
+            .collect::<Vec<_>>();
+        voting.chat_client.announce(&format!(
+            "Vote for the next choice! You have {} seconds. {}",
+            voting.window.as_secs(),
+            options.join(", ")
+        ));
+        self.vote = Some(Vote::new(self.choices.len(), voting.window));
+    }
+}
+
+impl<C> Component for VoteMenu<C>
+where
+    C: Component<State = GameLoopData, Output = Option<Result<game::MenuChoice, Close>>>,
+{
+    type Output = C::Output;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        use chargrid::text::*;
+        self.menu.render(state, ctx, fb);
+        if let Some(vote) = self.vote.as_ref() {
+            let tally = vote
+                .tally()
+                .into_iter()
+                .enumerate()
+                .map(|(i, count)| format!("{}: {}", option_letter(i), count))
+                .collect::<Vec<_>>();
+            let string = format!(
+                "Chat vote ({}s left) - {}",
+                vote.remaining().as_secs() + 1,
+                tally.join("  ")
+            );
+            StyledString {
+                string,
+                style: Style::plain_text()
+                    .with_bold(true)
+                    .with_foreground(colours::STAIRS.to_rgba32(255)),
+            }
+            .render(
+                &(),
+                ctx.add_xy(1, ctx.bounding_box.size().height() as i32 - 2),
+                fb,
+            );
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        if !self.started {
+            self.start(state);
+        }
+        if let (Some(vote), Some(voting)) = (self.vote.as_mut(), state.voting.as_mut()) {
+            for message in voting.chat_client.poll_messages() {
+                vote.handle_message(&message);
+            }
+            if let Event::Tick(since_previous) = event {
+                vote.tick(since_previous);
+                if vote.is_finished() {
+                    match vote.winner() {
+                        Some(index) => {
+                            voting
+                                .chat_client
+                                .announce(&format!("Chat chose {}!", option_letter(index)));
+                            return Some(Ok(self.choices[index]));
+                        }
+                        None => {
+                            voting
+                                .chat_client
+                                .announce("No one voted, so the streamer will choose.");
+                            self.vote = None;
+                        }
+                    }
+                }
+            }
+        }
+        self.menu.update(state, ctx, event)
+    }
+
+    fn size(&self, state: &Self::State, ctx: Ctx) -> Size {
+        self.menu.size(state, ctx)
+    }
+}
+
 fn game_menu(menu_witness: witness::Menu) -> AppCF<Witness> {
     use chargrid::align::*;
     use game::MenuChoice;
     use menu::builder::*;
     let game_menu = menu_witness.menu.clone();
+    let choices = game_menu.choices.clone();
     let menu_cf = on_state_then(move |state: &mut State| {
         let instance = state.instance.as_ref().unwrap();
         let mut builder = menu_builder();
@@ -1447,6 +3871,7 @@ fn game_menu(menu_witness: witness::Menu) -> AppCF<Witness> {
             menu_style(menu)
         }
     });
+    let menu_cf = cf(VoteMenu::new(menu_cf, choices));
     menu_cf.and_then_side_effect(|result, state: &mut State| {
         let witness = match result {
             Err(Close) => menu_witness.cancel(),
@@ -1470,9 +3895,35 @@ pub fn pre_game_screen() -> AppCF<()> {
     }
 }
 
+// Draws everything in the colour palette chosen in the accessibility settings
+struct PaletteTint<C>(C);
+
+impl<C: Component<State = GameLoopData>> Component for PaletteTint<C> {
+    type Output = C::Output;
+    type State = GameLoopData;
+
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let palette = state.config.palette;
+        if palette == Palette::Standard {
+            self.0.render(state, ctx, fb);
+        } else {
+            self.0
+                .render(state, ctx.with_tint(&ctx.compose_tint(&palette)), fb);
+        }
+    }
+
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        self.0.update(state, ctx, event)
+    }
+
+    fn size(&self, state: &Self::State, ctx: Ctx) -> Size {
+        self.0.size(state, ctx)
+    }
+}
+
 pub fn game_loop_component(initial_state: GameLoopState) -> AppCF<()> {
     use GameLoopState::*;
-    pre_game_screen().then(|| {
+    let game_loop = pre_game_screen().then(|| {
         loop_(initial_state, |state| match state {
             Playing(witness) => match witness {
                 Witness::Running(running) => game_instance_component(running).continue_(),
@@ -1483,6 +3934,8 @@ pub fn game_loop_component(initial_state: GameLoopState) -> AppCF<()> {
                     fire_equipped(fire_equipped_).map(Playing).continue_()
                 }
                 Witness::FireBody(fire_body_) => fire_body(fire_body_).map(Playing).continue_(),
+                Witness::Throw(throw_) => throw(throw_).map(Playing).continue_(),
+                Witness::Read(read_) => read(read_).map(Playing).continue_(),
             },
             Paused(running) => pause(running).map(|pause_output| match pause_output {
                 PauseOutput::ContinueGame { running } => {
@@ -1506,9 +3959,34 @@ pub fn game_loop_component(initial_state: GameLoopState) -> AppCF<()> {
             ViewOrgans(running) => view_organs()
                 .map(|()| GameLoopState::Playing(running.into_witness()))
                 .continue_(),
+            CharacterSheet(running) => character_sheet()
+                .map(|()| GameLoopState::Playing(running.into_witness()))
+                .continue_(),
+            Hint(running, hint_) => hint(hint_)
+                .map(|()| GameLoopState::Playing(running.into_witness()))
+                .continue_(),
+            OverviewMap(running) => overview_map()
+                .map(|()| GameLoopState::Playing(running.into_witness()))
+                .continue_(),
+        })
+        .on_each_tick_with_state(|state| {
+            if state.audio {
+                state.music_state.tick();
+            }
+            state.music_state.set_ambience(
+                state
+                    .instance
+                    .as_ref()
+                    .map(|instance| instance.game.inner_ref().level_theme()),
+            );
+            if let Some(instance) = state.instance.as_mut() {
+                instance.update_camera();
+                state
+                    .music_state
+                    .set_intensity(instance.game.inner_ref().danger());
+            }
         })
-        .bound_size(Size::new_u16(80, 30))
-        .on_each_tick_with_state(|state| state.music_state.tick())
         .on_exit_with_state(|state| state.try_save_instance_cheat())
-    })
+    });
+    cf(PaletteTint(game_loop))
 }
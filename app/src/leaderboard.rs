@@ -0,0 +1,97 @@
+//! Submitting finished runs to an online leaderboard. The app decides what gets submitted and
+//! displays the results, while the frontend provides a `LeaderboardClient` which talks to the
+//! server.
+//!
+//! The server is expected to accept a POST of a single json-encoded `RunSummary` at its
+//! endpoint, and to respond to a GET of the same endpoint with a json array of the best
+//! `RunSummary`s, best first.
+use game::{RunStats, Win};
+use serde::{Deserialize, Serialize};
+
+const LEVEL_SCORE: u64 = 1000;
+const KILL_SCORE: u64 = 10;
+const GOOD_ENDING_SCORE: u64 = 10000;
+const BAD_ENDING_SCORE: u64 = 5000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunResult {
+    Died,
+    GoodEnding,
+    BadEnding,
+}
+
+impl From<Win> for RunResult {
+    fn from(win: Win) -> Self {
+        match win {
+            Win::Good => Self::GoodEnding,
+            Win::Bad => Self::BadEnding,
+        }
+    }
+}
+
+impl RunResult {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Died => "Died",
+            Self::GoodEnding => "Won",
+            Self::BadEnding => "Won (corrupted)",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub score: u64,
+    pub result: RunResult,
+    /// Optional challenges which the player kept to for the whole run
+    pub conducts: Vec<String>,
+}
+
+impl RunSummary {
+    pub fn new(seed: u64, stats: RunStats, result: RunResult) -> Self {
+        let ending_score = match result {
+            RunResult::Died => 0,
+            RunResult::GoodEnding => GOOD_ENDING_SCORE,
+            RunResult::BadEnding => BAD_ENDING_SCORE,
+        };
+        let score = stats.level_reached as u64 * LEVEL_SCORE
+            + stats.npcs_killed as u64 * KILL_SCORE
+            + stats.money as u64
+            + ending_score;
+        let mut conducts = Vec::new();
+        if stats.all_original_organs {
+            conducts.push("Original organs".to_string());
+        }
+        if stats.no_cybernetics {
+            conducts.push("Flesh only".to_string());
+        }
+        Self {
+            seed,
+            score,
+            result,
+            conducts,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+pub fn entries_from_json(json: &str) -> Result<Vec<RunSummary>, String> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/// Frontends implement this to connect the game to a leaderboard server. Methods are called from
+/// the game loop so they must not block while waiting for the server.
+pub trait LeaderboardClient {
+    /// Send the summary of a finished run. Failures are logged rather than reported to the player.
+    fn submit(&mut self, summary: &RunSummary);
+
+    /// Start fetching the best entries on the leaderboard
+    fn request_top_entries(&mut self);
+
+    /// Returns the outcome of the most recent request for the best entries once it has completed
+    fn poll_top_entries(&mut self) -> Option<Result<Vec<RunSummary>, String>>;
+}
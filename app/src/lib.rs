@@ -1,15 +1,23 @@
 use chargrid::{control_flow::*, core::*};
-use game::Config;
 
 mod colours;
 mod controls;
+mod crash;
 mod game_instance;
 mod game_loop;
 mod image;
+#[cfg(feature = "leaderboard")]
+pub mod leaderboard;
 mod music;
+pub mod presence;
 mod text;
+pub mod vote;
 
-pub use game_loop::{AppStorage, InitialRngSeed};
+pub use crash::{install_crash_report_hook, CRASH_REPORT_KEY};
+#[cfg(feature = "script")]
+pub use game::RhaiScript;
+pub use game::{Balance, Script};
+pub use game_loop::{AppStorage, ExportFile, InitialRngSeed, ShareSeed};
 
 pub const NAME: &'static str = "Electric Organ";
 
@@ -23,24 +31,30 @@ pub struct AppArgs {
     pub omniscient: bool,
     pub new_game: bool,
     pub mute: bool,
+    /// Frontends where no one can hear the sound (e.g. the telnet server) set this to false so
+    /// no audio device is opened
+    pub audio: bool,
+    /// Frontends which can share links to a seed (e.g. the web build) provide this to offer
+    /// sharing when a game ends
+    pub share_seed: Option<ShareSeed>,
+    /// Frontends which can't write files where the player can find them (e.g. the web build)
+    /// provide this to offer exported files as downloads instead
+    pub export_file: Option<ExportFile>,
+    /// Frontends which can read files (e.g. the native builds) may provide tunable numbers to
+    /// override the game's defaults
+    pub balance: Balance,
+    #[cfg(feature = "leaderboard")]
+    pub leaderboard: Option<Box<dyn leaderboard::LeaderboardClient>>,
+    pub presence: Option<Box<dyn presence::PresenceClient>>,
+    /// Frontends which can read a stream's chat provide this to let viewers vote on the choices
+    /// in the game's menus
+    pub voting: Option<vote::Voting>,
+    /// Frontends which can read files may provide a mod script to hook into the game
+    pub script: Option<Box<dyn Script>>,
 }
 
-pub fn app(
-    AppArgs {
-        storage,
-        initial_rng_seed,
-        omniscient,
-        new_game,
-        mute,
-    }: AppArgs,
-) -> impl Component<Output = app::Output, State = ()> {
-    let config = Config {
-        omniscient: if omniscient { Config::OMNISCIENT } else { None },
-        demo: false,
-        debug: false,
-    };
-    let (game_loop_data, initial_state) =
-        game_loop::GameLoopData::new(config, storage, initial_rng_seed, new_game, mute);
+pub fn app(args: AppArgs) -> impl Component<Output = app::Output, State = ()> {
+    let (game_loop_data, initial_state) = game_loop::GameLoopData::new(args);
     let state = AppState { game_loop_data };
     game_loop::game_loop_component(initial_state)
         .lens_state(lens!(AppState[game_loop_data]: game_loop::GameLoopData))
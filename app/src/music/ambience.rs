@@ -0,0 +1,123 @@
+use currawong::prelude::*;
+use game::LevelTheme;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cell::RefCell;
+
+// A trigger which fires at random moments, on average `rate_hz` times per second
+fn random_trigger(rate_hz: f64) -> Trigger {
+    let rng = RefCell::new(StdRng::from_entropy());
+    Gate::from_fn(move |ctx| {
+        rng.borrow_mut()
+            .gen_bool((rate_hz / ctx.sample_rate_hz).min(1.0))
+    })
+    .to_trigger_rising_edge()
+}
+
+fn wind() -> Sf64 {
+    let gust = oscillator_s(Waveform::Sine, 11.0).build().signed_to_01();
+    let swell = oscillator_s(Waveform::Sine, 29.0).build().signed_to_01();
+    noise().filter(
+        low_pass_moog_ladder(&gust * 600.0 + 200.0)
+            .resonance(&swell * 2.0)
+            .build(),
+    ) * (&swell * 0.5 + 0.5)
+}
+
+fn hum(freq_hz: f64) -> Sf64 {
+    let osc = oscillator_hz(Waveform::Saw, freq_hz).build()
+        + oscillator_hz(Waveform::Sine, freq_hz * 2.0).build();
+    osc.filter(low_pass_moog_ladder(300.0).build()) * 0.5
+}
+
+fn running_water() -> Sf64 {
+    let swell = oscillator_s(Waveform::Sine, 7.0).build().signed_to_01();
+    noise().filter(low_pass_moog_ladder(&swell * 200.0 + 300.0).build())
+}
+
+fn drips(rate_hz: f64) -> Sf64 {
+    let trigger = random_trigger(rate_hz);
+    let make_noise = || noise().filter(sample_and_hold(trigger.clone()).build());
+    let duration = 0.15;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .decay_s(duration)
+        .sustain_01(0.0)
+        .build()
+        .exp_01(1.0);
+    // the pitch falls as each drip rings out
+    let osc = oscillator_hz(
+        Waveform::Sine,
+        &env * (make_noise() * 600.0 + 1200.0) + 400.0,
+    )
+    .build();
+    (osc * &env).lazy_zero(&env)
+}
+
+// A distant siren rising and falling
+fn siren(trigger: Trigger) -> Sf64 {
+    let duration = 6.0;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .attack_s(2.0)
+        .release_s(3.0)
+        .build();
+    let lfo = oscillator_hz(Waveform::Sine, 0.5).build();
+    let osc = oscillator_hz(Waveform::Saw, lfo * 150.0 + 600.0).build();
+    (osc.filter(low_pass_moog_ladder(1200.0).build()) * &env * 0.1).lazy_zero(&env)
+}
+
+// A burst of electronic beeping, like a phone or a monitor
+fn beeps(trigger: Trigger, freq_hz: f64) -> Sf64 {
+    let duration = 1.0;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .release_s(0.05)
+        .build();
+    let chop = oscillator_hz(Waveform::Pulse, 4.0)
+        .pulse_width_01(0.3)
+        .build()
+        .signed_to_01();
+    (oscillator_hz(Waveform::Sine, freq_hz).build() * chop * &env * 0.1).lazy_zero(&env)
+}
+
+// Something large moving through the tunnels
+fn rumble(trigger: Trigger) -> Sf64 {
+    let duration = 3.0;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .attack_s(0.5)
+        .release_s(2.0)
+        .build()
+        .exp_01(1.0);
+    (noise().filter(low_pass_moog_ladder(150.0).build()) * &env * 2.0).lazy_zero(&env)
+}
+
+// Electricity arcing between exposed wires
+fn zap(trigger: Trigger) -> Sf64 {
+    let duration = 0.3;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .decay_s(duration)
+        .sustain_01(0.0)
+        .build()
+        .exp_01(1.0);
+    let crackle = noise()
+        * oscillator_hz(Waveform::Pulse, 60.0)
+            .pulse_width_01(0.2)
+            .build()
+            .signed_to_01();
+    (crackle.filter(high_pass_butterworth(1500.0).build()) * &env * 0.2).lazy_zero(&env)
+}
+
+/// A bed of background sound for a level, with the occasional one-off sound on top
+pub fn signal(theme: LevelTheme) -> Sf64 {
+    match theme {
+        LevelTheme::Surface => wind() * 0.3 + siren(random_trigger(1.0 / 60.0)),
+        LevelTheme::Offices => hum(60.0) * 0.1 + beeps(random_trigger(1.0 / 40.0), 880.0),
+        LevelTheme::Hospital => hum(50.0) * 0.08 + beeps(random_trigger(1.0 / 20.0), 1000.0),
+        LevelTheme::Sewers => {
+            running_water() * 0.1 + drips(0.7) * 0.2 + rumble(random_trigger(1.0 / 45.0))
+        }
+        LevelTheme::Lab => hum(50.0) * 0.1 + hum(75.0) * 0.05 + zap(random_trigger(1.0 / 25.0)),
+    }
+}
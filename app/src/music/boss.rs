@@ -0,0 +1,216 @@
+use currawong::prelude::*;
+
+struct Effects {
+    tempo: Sf64,
+    drum_volume: Sf64,
+    drum_low_pass_filter: Sf64,
+}
+
+impl Effects {
+    fn new() -> Self {
+        Self {
+            tempo: const_(1.0),
+            drum_volume: const_(0.7),
+            drum_low_pass_filter: const_(0.8),
+        }
+    }
+}
+
+fn drum_loop(trigger: Trigger, pattern: Vec<u8>) -> Sf64 {
+    drum_loop_8(
+        trigger.divide(1),
+        pattern,
+        vec![
+            triggerable::hat_closed().build(),
+            triggerable::snare().build(),
+            triggerable::kick().build(),
+        ],
+    )
+}
+
+fn voice4(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+    effect_x: Sf64,
+    effect_y: Sf64,
+) -> Sf64 {
+    let oscillator = supersaw_hz(note.freq_hz()).build();
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.0)
+        .decay_s(0.3)
+        .sustain_01(0.2)
+        .release_s(0.0)
+        .build()
+        .exp_01(1.0);
+    oscillator.filter(
+        low_pass_moog_ladder(env * (40 * note.freq_hz() * effect_x))
+            .resonance(4.0 * effect_y)
+            .build(),
+    )
+}
+
+fn bass_voice(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+) -> Sf64 {
+    let freq = note.freq_hz() / 2;
+    let osc = oscillator_hz(Waveform::Saw, &freq).build()
+        + oscillator_hz(Waveform::Pulse, &freq / 2).build();
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.0)
+        .decay_s(0.2)
+        .sustain_01(0.5)
+        .release_s(0.05)
+        .build()
+        .exp_01(1.0);
+    osc.filter(low_pass_moog_ladder(env * 4000.0).resonance(1.0).build())
+}
+
+fn virtual_key_events_bass(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let notes = [
+        note::C2,
+        note::C2,
+        note::C2.add_semitones(1),
+        note::C2,
+        note::C2,
+        note::G2,
+        note::G2.add_semitones(-1),
+        note::G2,
+    ];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(8)
+        .on({
+            let state = Rc::clone(&state);
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_note = notes[(state.index - 1) % notes.len()];
+                    events.push(KeyEvent {
+                        note: prev_note,
+                        pressed: false,
+                        velocity_01: 1.0,
+                    })
+                }
+                let current_note = notes[state.index % notes.len()];
+                events.push(KeyEvent {
+                    note: current_note,
+                    pressed: true,
+                    velocity_01: 1.0,
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+fn virtual_key_events(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let chords = [
+        chord(note_name::C, MINOR),
+        chord(note_name::C_SHARP, MAJOR),
+        chord(note_name::C, MINOR),
+        chord(note_name::G, MAJOR),
+    ];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(32)
+        .on({
+            let state = Rc::clone(&state);
+            let inversion = Inversion::InOctave {
+                octave_base: note::C3,
+            };
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_chord = chords[(state.index - 1) % chords.len()];
+                    prev_chord.with_notes(inversion, |note| {
+                        events.push(KeyEvent {
+                            note,
+                            pressed: false,
+                            velocity_01: 1.0,
+                        });
+                    })
+                }
+                let current_chord = chords[state.index % chords.len()];
+                current_chord.with_notes(inversion, |note| {
+                    events.push(KeyEvent {
+                        note,
+                        pressed: true,
+                        velocity_01: 1.0,
+                    });
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+pub fn signal(intensity: Sf64) -> Sf64 {
+    let effects = Effects::new();
+    let hat_closed = 1 << 0;
+    let snare = 1 << 1;
+    let kick = 1 << 2;
+
+    let trigger = periodic_trigger_hz(effects.tempo * 8).build();
+
+    #[rustfmt::skip]
+    let drums = drum_loop(
+        trigger.clone(),
+        vec![
+            kick, hat_closed, kick, hat_closed, kick | snare, hat_closed, kick, hat_closed,
+            kick, hat_closed, kick, snare, kick | snare, hat_closed, snare, snare,
+        ],
+    );
+    let arp_config = ArpeggiatorConfig::default()
+        .shape(ArpeggiatorShape::Random)
+        .extend_octaves_high(1);
+    let resonance = oscillator_s(Waveform::Sine, 23.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.3;
+    let cutoff = oscillator_s(Waveform::Sine, 17.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.4
+        + 0.2;
+    let keys = virtual_key_events(trigger.clone())
+        .arpeggiate(trigger.clone(), arp_config)
+        .voice_descs_polyphonic(1, 0)
+        .into_iter()
+        .map(|voice_desc| voice4(voice_desc, cutoff.clone(), resonance.clone()))
+        .sum::<Sf64>()
+        .filter(compress().scale(3.0).threshold(0.6).build())
+        .mix(|dry| dry.filter(reverb().room_size(0.5).damping(0.5).build()))
+        .filter(high_pass_butterworth(1.0).build());
+    let bass = bass_voice(virtual_key_events_bass(trigger.clone()).voice_desc_monophonic());
+    let calm = keys * 0.2 + bass * 0.25;
+    let combat = drums.filter(
+        low_pass_moog_ladder(effects.drum_low_pass_filter * 20000 * (&intensity * 0.8 + 0.2))
+            .build(),
+    ) * effects.drum_volume;
+    calm * (1.0 - &intensity * 0.4) + combat * (&intensity * 0.7 + 0.3)
+}
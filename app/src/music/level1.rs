@@ -173,7 +173,7 @@ fn virtual_key_events(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
         .map(|opt| if let Some(x) = opt { x } else { Vec::new() })
 }
 
-pub fn signal() -> Sf64 {
+pub fn signal(intensity: Sf64) -> Sf64 {
     let effects = Effects::new();
     let hat_closed = 1 << 0;
     let snare = 1 << 1;
@@ -248,8 +248,10 @@ pub fn signal() -> Sf64 {
         .mix(|dry| dry.filter(reverb().room_size(0.9).damping(0.5).build()))
         .filter(high_pass_butterworth(1.0).build());
     let bass = bass_voice(virtual_key_events_bass(trigger.clone()).voice_desc_monophonic());
-    (drums.filter(low_pass_moog_ladder(effects.drum_low_pass_filter * 20000).build())
-        * effects.drum_volume)
-        + keys * (0.6 - cutoff)
-        + bass * 0.2
+    let calm = keys * (0.6 - cutoff) + bass * 0.2;
+    let combat = drums.filter(
+        low_pass_moog_ladder(effects.drum_low_pass_filter * 20000 * (&intensity * 0.8 + 0.2))
+            .build(),
+    ) * effects.drum_volume;
+    calm * (1.0 - &intensity * 0.4) + combat * (&intensity * 0.7 + 0.3)
 }
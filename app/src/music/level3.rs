@@ -0,0 +1,206 @@
+use currawong::prelude::*;
+
+struct Effects {
+    tempo: Sf64,
+    drum_volume: Sf64,
+    drum_low_pass_filter: Sf64,
+}
+
+impl Effects {
+    fn new() -> Self {
+        Self {
+            tempo: const_(0.5),
+            drum_volume: const_(0.6),
+            drum_low_pass_filter: const_(0.3),
+        }
+    }
+}
+
+fn drum_loop(trigger: Trigger, pattern: Vec<u8>) -> Sf64 {
+    drum_loop_8(
+        trigger.divide(1),
+        pattern,
+        vec![
+            triggerable::hat_closed().build(),
+            triggerable::snare().build(),
+            triggerable::kick().build(),
+        ],
+    )
+}
+
+fn voice4(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+    effect_x: Sf64,
+    effect_y: Sf64,
+) -> Sf64 {
+    let oscillator = oscillator_hz(Waveform::Saw, note.freq_hz()).build()
+        + oscillator_hz(Waveform::Triangle, note.freq_hz() * 2.0).build() * 0.5;
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.0)
+        .decay_s(1.5)
+        .sustain_01(0.2)
+        .release_s(0.2)
+        .build()
+        .exp_01(1.0);
+    oscillator.filter(
+        low_pass_moog_ladder(env * (20 * note.freq_hz() * effect_x))
+            .resonance(2.0 * effect_y)
+            .build(),
+    )
+}
+
+fn bass_voice(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+) -> Sf64 {
+    let freq = note.freq_hz() / 2;
+    let osc = oscillator_hz(Waveform::Saw, &freq).build();
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.05)
+        .sustain_01(0.8)
+        .release_s(0.2)
+        .build()
+        .exp_01(1.0);
+    osc.filter(low_pass_moog_ladder(env * 800.0).build())
+}
+
+fn virtual_key_events_bass(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let notes = [note::A2, note::F2, note::D2, note::E2];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(32)
+        .on({
+            let state = Rc::clone(&state);
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_note = notes[(state.index - 1) % notes.len()];
+                    events.push(KeyEvent {
+                        note: prev_note,
+                        pressed: false,
+                        velocity_01: 1.0,
+                    })
+                }
+                let current_note = notes[state.index % notes.len()];
+                events.push(KeyEvent {
+                    note: current_note,
+                    pressed: true,
+                    velocity_01: 1.0,
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+fn virtual_key_events(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let chords = [
+        chord(note_name::A, MINOR),
+        chord(note_name::F, MAJOR),
+        chord(note_name::D, MINOR),
+        chord(note_name::E, MAJOR),
+    ];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(32)
+        .on({
+            let state = Rc::clone(&state);
+            let inversion = Inversion::InOctave {
+                octave_base: note::A2,
+            };
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_chord = chords[(state.index - 1) % chords.len()];
+                    prev_chord.with_notes(inversion, |note| {
+                        events.push(KeyEvent {
+                            note,
+                            pressed: false,
+                            velocity_01: 1.0,
+                        });
+                    })
+                }
+                let current_chord = chords[state.index % chords.len()];
+                current_chord.with_notes(inversion, |note| {
+                    events.push(KeyEvent {
+                        note,
+                        pressed: true,
+                        velocity_01: 1.0,
+                    });
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+pub fn signal(intensity: Sf64) -> Sf64 {
+    let effects = Effects::new();
+    let hat_closed = 1 << 0;
+    let snare = 1 << 1;
+    let kick = 1 << 2;
+
+    let trigger = periodic_trigger_hz(effects.tempo * 8).build();
+
+    #[rustfmt::skip]
+    let drums = drum_loop(
+        trigger.clone(),
+        vec![
+            kick, 0, hat_closed, 0, 0, 0, hat_closed, 0,
+            snare, 0, hat_closed, 0, 0, kick, hat_closed, 0,
+        ],
+    );
+    let arp_config = ArpeggiatorConfig::default()
+        .shape(ArpeggiatorShape::UpDown)
+        .extend_octaves_high(1);
+    let resonance = oscillator_s(Waveform::Sine, 71.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.5;
+    let cutoff = oscillator_s(Waveform::Sine, 43.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.4
+        + 0.2;
+    let keys = virtual_key_events(trigger.clone())
+        .arpeggiate(trigger.clone(), arp_config)
+        .voice_descs_polyphonic(1, 0)
+        .into_iter()
+        .map(|voice_desc| voice4(voice_desc, cutoff.clone(), resonance.clone()))
+        .sum::<Sf64>()
+        .mix(|dry| dry.filter(echo().time_s(0.3).scale(0.6).build()))
+        .mix(|dry| dry.filter(reverb().room_size(0.95).damping(0.7).build()))
+        .filter(high_pass_butterworth(1.0).build());
+    let bass = bass_voice(virtual_key_events_bass(trigger.clone()).voice_desc_monophonic());
+    let calm = keys * 0.3 + bass * 0.3;
+    let combat = drums.filter(
+        low_pass_moog_ladder(effects.drum_low_pass_filter * 20000 * (&intensity * 0.8 + 0.2))
+            .build(),
+    ) * effects.drum_volume;
+    calm * (1.0 - &intensity * 0.4) + combat * (&intensity * 0.7 + 0.3)
+}
@@ -0,0 +1,237 @@
+use currawong::prelude::*;
+
+struct Effects {
+    tempo: Sf64,
+    drum_volume: Sf64,
+    drum_low_pass_filter: Sf64,
+}
+
+impl Effects {
+    fn new() -> Self {
+        Self {
+            tempo: const_(0.7),
+            drum_volume: const_(0.5),
+            drum_low_pass_filter: const_(0.6),
+        }
+    }
+}
+
+fn drum_loop(trigger: Trigger, pattern: Vec<u8>) -> Sf64 {
+    drum_loop_8(
+        trigger.divide(1),
+        pattern,
+        vec![
+            triggerable::hat_closed().build(),
+            triggerable::snare().build(),
+            triggerable::kick().build(),
+        ],
+    )
+}
+
+fn voice4(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+    effect_x: Sf64,
+    effect_y: Sf64,
+    effect_z: Sf64,
+) -> Sf64 {
+    let oscillator = oscillator_hz(Waveform::Pulse, note.freq_hz())
+        .pulse_width_01(effect_z)
+        .build();
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.0)
+        .decay_s(0.4)
+        .sustain_01(0.05)
+        .release_s(0.0)
+        .build()
+        .exp_01(1.0);
+    oscillator.filter(
+        low_pass_moog_ladder(env * (40 * note.freq_hz() * effect_x))
+            .resonance(3.0 * effect_y)
+            .build(),
+    )
+}
+
+fn bass_voice(
+    VoiceDesc {
+        note,
+        key_down,
+        key_press,
+        ..
+    }: VoiceDesc,
+) -> Sf64 {
+    let freq = note.freq_hz() / 2;
+    let osc = pulse_pwm_hz(&freq).build();
+    let env = adsr_linear_01(&key_down)
+        .key_press(key_press)
+        .attack_s(0.0)
+        .release_s(0.1)
+        .build()
+        .exp_01(1.0);
+    osc.filter(low_pass_moog_ladder(3000.0).build()) * env
+}
+
+fn virtual_key_events_bass(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let notes = [note::D2, note::B2.add_semitones(-1), note::G2, note::A2];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(16)
+        .on({
+            let state = Rc::clone(&state);
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_note = notes[((state.index - 1) / 2) % notes.len()];
+                    events.push(KeyEvent {
+                        note: prev_note,
+                        pressed: false,
+                        velocity_01: 1.0,
+                    })
+                }
+                let current_note = notes[(state.index / 2) % notes.len()];
+                events.push(KeyEvent {
+                    note: current_note,
+                    pressed: true,
+                    velocity_01: 1.0,
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+fn virtual_key_events(trigger: Trigger) -> Signal<Vec<KeyEvent>> {
+    use std::{cell::RefCell, rc::Rc};
+    let chords = [
+        chord(note_name::D, MINOR),
+        chord(note_name::A_SHARP, MAJOR),
+        chord(note_name::G, MINOR),
+        chord(note_name::A, MAJOR),
+    ];
+    struct State {
+        index: usize,
+    }
+    let state = Rc::new(RefCell::new(State { index: 0 }));
+    trigger
+        .divide(32)
+        .on({
+            let state = Rc::clone(&state);
+            let inversion = Inversion::InOctave {
+                octave_base: note::D3,
+            };
+            move || {
+                let mut state = state.borrow_mut();
+                let mut events = Vec::new();
+                if state.index > 0 {
+                    let prev_chord = chords[(state.index - 1) % chords.len()];
+                    prev_chord.with_notes(inversion, |note| {
+                        events.push(KeyEvent {
+                            note,
+                            pressed: false,
+                            velocity_01: 1.0,
+                        });
+                    })
+                }
+                let current_chord = chords[state.index % chords.len()];
+                current_chord.with_notes(inversion, |note| {
+                    events.push(KeyEvent {
+                        note,
+                        pressed: true,
+                        velocity_01: 1.0,
+                    });
+                });
+                state.index += 1;
+                events
+            }
+        })
+        .map(Option::unwrap_or_default)
+}
+
+pub fn signal(intensity: Sf64) -> Sf64 {
+    let effects = Effects::new();
+    let hat_closed = 1 << 0;
+    let snare = 1 << 1;
+    let kick = 1 << 2;
+
+    let trigger = periodic_trigger_hz(effects.tempo * 8).build();
+
+    #[rustfmt::skip]
+    let drums0 = drum_loop(
+        trigger.clone(),
+        vec![
+            kick, hat_closed, hat_closed, hat_closed, snare, hat_closed, hat_closed, kick,
+            kick, hat_closed, hat_closed, hat_closed, snare, hat_closed, snare, hat_closed,
+        ],
+    );
+    #[rustfmt::skip]
+    let drums1 = drum_loop(
+        trigger.clone(),
+        vec![
+            kick, 0, hat_closed, 0, snare, 0, hat_closed, kick,
+            0, kick, hat_closed, 0, snare, 0, hat_closed, 0,
+        ],
+    );
+    let drums = trigger.divide(128).to_signal().map_ctx({
+        use std::cell::Cell;
+        let count = Cell::new(-1);
+        move |value, ctx| {
+            if value {
+                count.set(count.get() + 1);
+            }
+            if count.get() % 2 == 0 {
+                drums1.sample(ctx)
+            } else {
+                drums0.sample(ctx)
+            }
+        }
+    });
+    let arp_config = ArpeggiatorConfig::default()
+        .shape(ArpeggiatorShape::Up)
+        .extend_octaves_high(1);
+    let resonance = oscillator_s(Waveform::Sine, 61.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.4;
+    let cutoff = oscillator_s(Waveform::Sine, 37.0)
+        .reset_offset_01(-0.25)
+        .build()
+        .signed_to_01()
+        * 0.3
+        + 0.15;
+    let effect_z = oscillator_s(Waveform::Sine, 79.0).build().signed_to_01() * 0.3 + 0.1;
+    let keys = virtual_key_events(trigger.clone())
+        .arpeggiate(trigger.clone(), arp_config)
+        .voice_descs_polyphonic(1, 0)
+        .into_iter()
+        .map(|voice_desc| {
+            voice4(
+                voice_desc,
+                cutoff.clone(),
+                resonance.clone(),
+                effect_z.clone(),
+            )
+        })
+        .sum::<Sf64>()
+        .mix(|dry| dry.filter(echo().time_s(0.15).scale(0.4).build()))
+        .mix(|dry| dry.filter(reverb().room_size(0.7).damping(0.5).build()))
+        .filter(high_pass_butterworth(1.0).build());
+    let bass = bass_voice(virtual_key_events_bass(trigger.clone()).voice_desc_monophonic());
+    let calm = keys * 0.3 + bass * 0.2;
+    let combat = drums.filter(
+        low_pass_moog_ladder(effects.drum_low_pass_filter * 20000 * (&intensity * 0.8 + 0.2))
+            .build(),
+    ) * effects.drum_volume;
+    calm * (1.0 - &intensity * 0.4) + combat * (&intensity * 0.7 + 0.3)
+}
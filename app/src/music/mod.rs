@@ -1,34 +1,83 @@
 use currawong::{prelude::*, signal_player::SignalPlayer};
-use std::{cell::RefCell, rc::Rc};
+use game::{Coord, ExternalEvent, LevelTheme};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
+mod ambience;
+mod boss;
 mod level1;
 mod level2;
+mod level3;
+mod level4;
 mod menu;
 mod sound_effects;
 
+// Sound effects get quieter with distance from the listener, but never fall completely silent
+const HEARING_DISTANCE: f64 = 40.0;
+const MIN_SFX_GAIN: f64 = 0.15;
+
+// The ambient sound of each level plays quietly underneath the music
+const AMBIENCE_VOLUME: f64 = 0.5;
+
+// Time taken for the music to fade fully between its calm and combat layers
+const INTENSITY_CROSSFADE_S: f64 = 3.0;
+
 #[derive(Clone, Copy, Debug)]
 pub enum Track {
     Menu,
     Level1,
     Level2,
+    Level3,
+    Level4,
+    Boss,
 }
 
 struct Control {
-    volume: f64,
+    music_volume: f64,
+    sfx_volume: f64,
     signal: Sf64,
+    ambience: Sf64,
 }
 
 impl Control {
     fn new() -> Self {
         Self {
             signal: const_(0.0),
-            volume: 1.0,
+            ambience: const_(0.0),
+            music_volume: 1.0,
+            sfx_volume: 1.0,
         }
     }
 }
 
+/// How much of the combat layer of the current track to play, from 0 to 1. Each level track
+/// has a calm layer (its melody and bass) and a combat layer (its drums, which are muffled at low
+/// intensity and open up as intensity rises).
+#[derive(Default)]
+struct Intensity {
+    target: Cell<f64>,
+    current: Cell<f64>,
+}
+
+// The intensity of the current track, moving gradually towards the target intensity so the
+// layers crossfade rather than cutting in and out
+fn intensity_signal(intensity: &Rc<Intensity>) -> Sf64 {
+    let intensity = Rc::clone(intensity);
+    Sf64::from_fn(move |ctx| {
+        let max_step = 1.0 / (INTENSITY_CROSSFADE_S * ctx.sample_rate_hz);
+        let delta = intensity.target.get() - intensity.current.get();
+        intensity
+            .current
+            .set(intensity.current.get() + delta.clamp(-max_step, max_step));
+        intensity.current.get()
+    })
+}
+
 pub struct MusicState {
     control: Rc<RefCell<Control>>,
+    intensity: Rc<Intensity>,
     sfx: Sfx,
     signal: Sf64,
     // This starts as `None` becuse when running in a browser, an audio context can only be created
@@ -36,6 +85,8 @@ pub struct MusicState {
     signal_player: Option<SignalPlayer>,
     sfx_signal: Sf64,
     sfx_signal_player: Option<SignalPlayer>,
+    listener: Coord,
+    ambience_theme: Option<LevelTheme>,
 }
 
 fn make_signal_player() -> SignalPlayer {
@@ -58,36 +109,66 @@ impl MusicState {
             let control = Rc::clone(&control);
             move |ctx| {
                 let control = control.borrow();
-                let sample = control.signal.sample(ctx);
-                sample * control.volume
+                let sample =
+                    control.signal.sample(ctx) + control.ambience.sample(ctx) * AMBIENCE_VOLUME;
+                sample * control.music_volume
             }
         });
         let sfx_signal = Signal::from_fn({
             let control = Rc::clone(&control);
-            move |ctx| sfx_signal.sample(ctx) * control.borrow().volume
+            move |ctx| sfx_signal.sample(ctx) * control.borrow().sfx_volume
         });
         Self {
             sfx,
             control,
+            intensity: Rc::new(Intensity::default()),
             signal,
             signal_player: None,
             sfx_signal,
             sfx_signal_player: None,
+            listener: Coord::new(0, 0),
+            ambience_theme: None,
         }
     }
 
     pub fn set_track(&self, track: Option<Track>) {
-        let mut control = self.control.borrow_mut();
-        control.signal = match track {
+        let intensity = intensity_signal(&self.intensity);
+        let signal = match track {
             None => const_(0.0),
-            Some(Track::Level1) => level1::signal(),
-            Some(Track::Level2) => level2::signal(),
+            Some(Track::Level1) => level1::signal(intensity),
+            Some(Track::Level2) => level2::signal(intensity),
+            Some(Track::Level3) => level3::signal(intensity),
+            Some(Track::Level4) => level4::signal(intensity),
+            Some(Track::Boss) => boss::signal(intensity),
             Some(Track::Menu) => menu::signal(),
+        };
+        self.control.borrow_mut().signal = signal;
+    }
+
+    /// Change the ambient sound to that of a level theme, or silence it with `None`
+    pub fn set_ambience(&mut self, theme: Option<LevelTheme>) {
+        if theme == self.ambience_theme {
+            return;
         }
+        self.ambience_theme = theme;
+        self.control.borrow_mut().ambience = match theme {
+            None => const_(0.0),
+            Some(theme) => ambience::signal(theme),
+        };
+    }
+
+    /// Set how dangerous the player's situation is, from 0 to 1. The music fades towards its
+    /// combat layer as this increases.
+    pub fn set_intensity(&self, intensity: f64) {
+        self.intensity.target.set(intensity.clamp(0.0, 1.0));
+    }
+
+    pub fn set_music_volume(&self, volume: f64) {
+        self.control.borrow_mut().music_volume = volume;
     }
 
-    pub fn set_volume(&self, volume: f64) {
-        self.control.borrow_mut().volume = volume;
+    pub fn set_sfx_volume(&self, volume: f64) {
+        self.control.borrow_mut().sfx_volume = volume;
     }
 
     pub fn tick(&mut self) {
@@ -107,39 +188,76 @@ impl MusicState {
             .send_signal(&mut self.sfx_signal);
     }
 
-    pub fn sfx_pistol(&self) {
-        self.sfx.pistol.fire()
-    }
-    pub fn sfx_shotgun(&self) {
-        self.sfx.shotgun.fire()
+    /// Set the position that positional sound effects are heard from
+    pub fn set_listener(&mut self, coord: Coord) {
+        self.listener = coord;
     }
-    pub fn sfx_rocket(&self) {
-        self.sfx.rocket.fire()
-    }
-    pub fn sfx_explosion(&self) {
-        self.sfx.explosion.fire()
+
+    fn gain_at(&self, source: Coord) -> f64 {
+        let delta = source - self.listener;
+        let distance = ((delta.x * delta.x + delta.y * delta.y) as f64).sqrt();
+        (1.0 - (distance / HEARING_DISTANCE)).max(MIN_SFX_GAIN)
     }
-    pub fn sfx_melee(&self) {
-        self.sfx.melee.fire()
+
+    /// Play the sound effect (if any) of an event from the game
+    pub fn sfx_external_event(&self, external_event: ExternalEvent) {
+        match external_event {
+            ExternalEvent::FirePistol(coord) => self.sfx.pistol.fire_with_gain(self.gain_at(coord)),
+            ExternalEvent::FireShotgun(coord) => {
+                self.sfx.shotgun.fire_with_gain(self.gain_at(coord))
+            }
+            ExternalEvent::FireRocket(coord) => self.sfx.rocket.fire_with_gain(self.gain_at(coord)),
+            ExternalEvent::Explosion(coord) => {
+                self.sfx.explosion.fire_with_gain(self.gain_at(coord))
+            }
+            ExternalEvent::ShatterWindow(coord) => {
+                self.sfx.glass.fire_with_gain(self.gain_at(coord))
+            }
+            ExternalEvent::Melee(coord) => self.sfx.melee.fire_with_gain(self.gain_at(coord)),
+            ExternalEvent::Alarm => self.sfx_alarm(),
+            ExternalEvent::GeigerClick(intensity) => self.sfx_geiger(intensity),
+            // the death sound is played by the game over screen
+            ExternalEvent::Death(_)
+            | ExternalEvent::ChangeLevel
+            | ExternalEvent::BossSighted
+            | ExternalEvent::BossKill => (),
+        }
     }
     pub fn sfx_death(&self) {
         self.sfx.death.fire()
     }
+    pub fn sfx_alarm(&self) {
+        self.sfx.alarm.fire()
+    }
+    pub fn sfx_geiger(&self, intensity: u32) {
+        self.sfx.geiger_intensity.set(intensity as f64);
+        self.sfx.geiger.fire()
+    }
 }
 
 struct SfxTrigger {
     state: Rc<RefCell<bool>>,
+    gain: Rc<Cell<f64>>,
 }
 
 impl SfxTrigger {
     fn new() -> Self {
         Self {
             state: Rc::new(RefCell::new(false)),
+            gain: Rc::new(Cell::new(1.0)),
         }
     }
     fn fire(&self) {
+        self.fire_with_gain(1.0);
+    }
+    fn fire_with_gain(&self, gain: f64) {
+        self.gain.set(gain);
         *self.state.borrow_mut() = true;
     }
+    fn gain(&self) -> Sf64 {
+        let gain = Rc::clone(&self.gain);
+        Sf64::from_fn(move |_| gain.get())
+    }
     fn trigger(&self) -> Trigger {
         let state = Rc::clone(&self.state);
         Gate::from_fn(move |_| {
@@ -159,6 +277,10 @@ struct Sfx {
     explosion: SfxTrigger,
     melee: SfxTrigger,
     death: SfxTrigger,
+    alarm: SfxTrigger,
+    glass: SfxTrigger,
+    geiger: SfxTrigger,
+    geiger_intensity: Rc<Cell<f64>>,
 }
 
 fn make_sfx() -> (Sfx, Sf64) {
@@ -169,14 +291,25 @@ fn make_sfx() -> (Sfx, Sf64) {
         explosion: SfxTrigger::new(),
         melee: SfxTrigger::new(),
         death: SfxTrigger::new(),
+        alarm: SfxTrigger::new(),
+        glass: SfxTrigger::new(),
+        geiger: SfxTrigger::new(),
+        geiger_intensity: Rc::new(Cell::new(0.0)),
     };
+    let geiger_intensity = Rc::clone(&sfx.geiger_intensity);
     let signal = sum([
-        sound_effects::pistol(sfx.pistol.trigger()),
-        sound_effects::shotgun(sfx.shotgun.trigger()),
-        sound_effects::rocket(sfx.rocket.trigger()),
-        sound_effects::explosion(sfx.explosion.trigger()),
-        sound_effects::melee(sfx.melee.trigger()),
+        sound_effects::pistol(sfx.pistol.trigger()) * sfx.pistol.gain(),
+        sound_effects::shotgun(sfx.shotgun.trigger()) * sfx.shotgun.gain(),
+        sound_effects::rocket(sfx.rocket.trigger()) * sfx.rocket.gain(),
+        sound_effects::explosion(sfx.explosion.trigger()) * sfx.explosion.gain(),
+        sound_effects::melee(sfx.melee.trigger()) * sfx.melee.gain(),
         sound_effects::death(sfx.death.trigger()),
+        sound_effects::alarm(sfx.alarm.trigger()),
+        sound_effects::glass(sfx.glass.trigger()) * sfx.glass.gain(),
+        sound_effects::geiger(
+            sfx.geiger.trigger(),
+            Sf64::from_fn(move |_| geiger_intensity.get()),
+        ),
     ])
     .mix(|dry| dry.filter(reverb().room_size(0.8).build()));
     (sfx, signal)
@@ -105,3 +105,45 @@ pub fn explosion(trigger: Trigger) -> Sf64 {
         (osc + noise).filter(low_pass_moog_ladder(&env * (10000.0 + make_noise() * 5000)).build());
     filtered_osc.lazy_zero(&env)
 }
+
+pub fn alarm(trigger: Trigger) -> Sf64 {
+    let duration = 1.5;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .attack_s(0.05)
+        .release_s(0.2)
+        .build();
+    let lfo = oscillator_hz(Waveform::Triangle, 2.0).build();
+    let osc = oscillator_hz(Waveform::Saw, (lfo * 200.0) + 700.0).build();
+    let filtered_osc = osc.filter(low_pass_moog_ladder(3000.0).build());
+    (filtered_osc * &env * 0.2).lazy_zero(&env)
+}
+
+pub fn glass(trigger: Trigger) -> Sf64 {
+    let make_noise = || noise().filter(sample_and_hold(trigger.clone()).build());
+    let duration = 0.3;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .decay_s(duration)
+        .sustain_01(0.0)
+        .build()
+        .exp_01(1.0);
+    // a bright ring over a burst of hiss for the falling shards
+    let ring = oscillator_hz(Waveform::Sine, (make_noise() * 1500.0) + 3000.0).build();
+    let shards = noise().filter(low_pass_moog_ladder(&env * 12000.0).build());
+    ((ring + shards) * &env * 0.3).lazy_zero(&env)
+}
+
+pub fn geiger(trigger: Trigger, intensity: Sf64) -> Sf64 {
+    let duration = 0.5;
+    let env = adsr_linear_01(trigger.to_gate_with_duration_s(duration))
+        .key_press(&trigger)
+        .release_s(0.05)
+        .build();
+    // stronger radiation makes the clicks more frequent
+    let clicks = oscillator_hz(Waveform::Pulse, (intensity * 6.0) + 4.0)
+        .pulse_width_01(0.05)
+        .build();
+    let crackle = noise() * ((clicks + 1.0) * 0.5);
+    (crackle * &env * 0.3).lazy_zero(&env)
+}
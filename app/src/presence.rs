@@ -0,0 +1,57 @@
+//! Publishing what the player is doing to the status shown by chat programs (e.g. Discord's rich
+//! presence). The app decides what to publish, while the frontend provides a `PresenceClient`
+//! which talks to the chat program.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activity {
+    MainMenu,
+    Exploring,
+    Fighting,
+    FightingBoss,
+    Died,
+    Won,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Presence {
+    pub activity: Activity,
+    /// Counting from 1, as displayed to the player
+    pub level: usize,
+    pub turns: u64,
+}
+
+impl Presence {
+    pub const MAIN_MENU: Self = Self {
+        activity: Activity::MainMenu,
+        level: 0,
+        turns: 0,
+    };
+
+    /// What the player is doing, e.g. "Fighting the CORRUPTOR on Level 4"
+    pub fn details(&self) -> String {
+        let level = self.level;
+        match self.activity {
+            Activity::MainMenu => "In the main menu".to_string(),
+            Activity::Exploring => format!("Exploring Level {}", level),
+            Activity::Fighting => format!("Fighting on Level {}", level),
+            Activity::FightingBoss => format!("Fighting the CORRUPTOR on Level {}", level),
+            Activity::Died => format!("Died on Level {}", level),
+            Activity::Won => "Escaped the city".to_string(),
+        }
+    }
+
+    /// A second line of detail, or `None` when there's nothing to add
+    pub fn state(&self) -> Option<String> {
+        match self.activity {
+            Activity::MainMenu => None,
+            _ => Some(format!("Turn {}", self.turns)),
+        }
+    }
+}
+
+/// Frontends implement this to publish the player's status. `update` is called from the game loop
+/// every time the player acts, so it must not block, and implementations are expected to drop
+/// updates which are made faster than the chat program accepts them.
+pub trait PresenceClient {
+    fn update(&mut self, presence: &Presence);
+}
@@ -32,8 +32,16 @@ pub fn help(width: u32) -> AppCF<()> {
             t("Drop item: d\n\n"),
             t("Unequip item: u\n\n"),
             t("Reload equipped weapon: r\n\n"),
+            t("Overwatch (then choose a direction): w\n\n"),
             t("Display message log: m\n\n"),
             t("Display list of organs: o\n\n"),
+            t("Display character sheet: @\n\n"),
+            t("Display map of the current level: v\n\n"),
+            t("Show/hide objectives: O\n\n"),
+            t("Take/release control of deployed drone: x\n\n"),
+            t("Start/stop sprinting (costs oxygen): s\n\n"),
+            t("Mute/unmute: M, Volume down/up: -/+\n\n"),
+            t("Export the map of the current level to a file: P\n\n"),
             t("Display this help message: ?\n\n"),
         ],
     )
@@ -87,7 +95,7 @@ pub fn help2(width: u32) -> AppCF<()> {
             b("\nLiver: "),
             t("Each liver speeds up poison recovery."),
             b("\nLung: "),
-            t("Each lung increases the amount of oxygen you recover each turn."),
+            t("Each lung increases the amount of oxygen you recover each turn. A cybernetic lung filters out poison gas."),
             b("\nStomach: "),
             t("Your food will decrease over time and increase your health by an amount determined by how many stomachs you have."),
             b("\nAppendix: "),
@@ -165,6 +173,27 @@ pub fn saving(width: u32) -> AppCF<()> {
     text_component(width, vec![t("Saving...")]).delay(Duration::from_millis(100))
 }
 
+pub fn hint(width: u32, title: &str, body: &str) -> AppCF<()> {
+    let t = |s: &str| StyledString {
+        string: s.to_string(),
+        style: Style::plain_text(),
+    };
+    let b = |s: &str| StyledString {
+        string: s.to_string(),
+        style: Style::plain_text().with_bold(true),
+    };
+    text_component(
+        width,
+        vec![
+            b(title),
+            t("\n\n"),
+            t(body),
+            t("\n\n(press any key to dismiss)"),
+        ],
+    )
+    .press_any_key()
+}
+
 fn game_over_text(width: u32, _reason: GameOverReason) -> CF<(), State> {
     let t = |s: &str| StyledString {
         string: s.to_string(),
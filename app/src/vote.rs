@@ -0,0 +1,100 @@
+//! Letting the viewers of a stream vote on the choices in menus opened by the game (e.g. which
+//! organ to install or which item to buy). The app runs the votes, while the frontend provides a
+//! `ChatClient` which reads (and optionally posts to) the stream's chat.
+//!
+//! Viewers vote by posting the letter of an option, e.g. "b" or "!vote b". Each viewer has a
+//! single vote, and voting again replaces their earlier vote.
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Options are voted for by letter, so only the first this many options of a menu can be chosen
+pub const MAX_OPTIONS: usize = 26;
+
+pub struct ChatMessage {
+    pub user: String,
+    pub text: String,
+}
+
+/// Frontends implement this to connect the game to a stream's chat. Methods are called from the
+/// game loop so they must not block.
+pub trait ChatClient {
+    /// Messages posted since the previous call
+    fn poll_messages(&mut self) -> Vec<ChatMessage>;
+
+    /// Post a message to the chat. Clients which can only read the chat ignore this.
+    fn announce(&mut self, text: &str);
+}
+
+pub struct Voting {
+    pub chat_client: Box<dyn ChatClient>,
+    /// How long viewers have to vote each time a menu opens
+    pub window: Duration,
+}
+
+pub fn option_letter(index: usize) -> char {
+    assert!(index < MAX_OPTIONS, "no letter for option {}", index);
+    (b'a' + index as u8) as char
+}
+
+/// A vote between the options of a single menu
+pub struct Vote {
+    num_options: usize,
+    remaining: Duration,
+    votes: HashMap<String, usize>,
+}
+
+impl Vote {
+    pub fn new(num_options: usize, window: Duration) -> Self {
+        Self {
+            num_options: num_options.min(MAX_OPTIONS),
+            remaining: window,
+            votes: HashMap::new(),
+        }
+    }
+
+    pub fn handle_message(&mut self, message: &ChatMessage) {
+        let text = message.text.trim();
+        let text = text.strip_prefix("!vote").unwrap_or(text).trim();
+        let mut chars = text.chars();
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            let ch = ch.to_ascii_lowercase();
+            if let Some(index) = (0..self.num_options).find(|&i| option_letter(i) == ch) {
+                self.votes.insert(message.user.clone(), index);
+            }
+        }
+    }
+
+    pub fn tick(&mut self, since_previous: Duration) {
+        self.remaining = self.remaining.saturating_sub(since_previous);
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining.is_zero()
+    }
+
+    /// The number of votes for each option
+    pub fn tally(&self) -> Vec<usize> {
+        let mut tally = vec![0; self.num_options];
+        for &index in self.votes.values() {
+            tally[index] += 1;
+        }
+        tally
+    }
+
+    /// The option with the most votes, preferring earlier options in a tie, or `None` if no one
+    /// has voted
+    pub fn winner(&self) -> Option<usize> {
+        let tally = self.tally();
+        let max = *tally.iter().max()?;
+        if max == 0 {
+            return None;
+        }
+        tally.iter().position(|&count| count == max)
+    }
+}
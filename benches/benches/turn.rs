@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use game::{bench::generate_terrain, Config, Game, NUM_LEVELS};
+use rand::SeedableRng;
+use rand_isaac::Isaac64Rng;
+
+const RNG_SEED: u64 = 0;
+
+// A game with the player at the start of the given level
+fn game_on_level(level_index: usize) -> Game {
+    let mut rng = Isaac64Rng::seed_from_u64(RNG_SEED);
+    let mut game = Game::new(&Config::default(), Default::default(), Vec::new(), &mut rng);
+    for level_index in 1..=level_index {
+        game.enter_level(level_index);
+    }
+    game
+}
+
+fn terrain_generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("terrain_generate");
+    group.sample_size(20);
+    for level_index in 0..NUM_LEVELS {
+        let mut rng = Isaac64Rng::seed_from_u64(RNG_SEED);
+        group.bench_function(format!("level {}", level_index), |b| {
+            b.iter(|| generate_terrain(level_index, &mut rng))
+        });
+    }
+    group.finish();
+}
+
+fn npc_turn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("npc_turn");
+    for level_index in 0..NUM_LEVELS {
+        let mut game = game_on_level(level_index);
+        group.bench_function(format!("level {}", level_index), |b| {
+            b.iter(|| game.bench_npc_turn())
+        });
+    }
+    group.finish();
+}
+
+fn update_visibility(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_visibility");
+    for level_index in 0..NUM_LEVELS {
+        let mut game = game_on_level(level_index);
+        group.bench_function(format!("level {}", level_index), |b| {
+            b.iter(|| game.update_visibility())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, terrain_generate, npc_turn, update_visibility);
+criterion_main!(benches);
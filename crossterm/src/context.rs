@@ -0,0 +1,236 @@
+use chargrid_input::{keys, Input, KeyboardInput, MouseButton, MouseInput, ScrollDirection};
+use chargrid_runtime::{
+    app, on_frame, on_input, rgb_int::Rgb24, Component, Coord, FrameBuffer, FrameBufferCell, Size,
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind},
+    style::{self, Attribute, Color},
+    terminal, QueueableCommand,
+};
+use std::io::{self, Stdout, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / 60);
+
+// The cell most recently drawn at each position, so only cells which change need to be redrawn
+#[derive(Clone, PartialEq)]
+struct OutputCell {
+    character: char,
+    bold: bool,
+    underline: bool,
+    foreground: Rgb24,
+    background: Rgb24,
+}
+
+impl OutputCell {
+    fn from_frame_buffer_cell(cell: &FrameBufferCell) -> Self {
+        Self {
+            character: cell.character,
+            bold: cell.bold,
+            underline: cell.underline,
+            foreground: cell.foreground.to_rgb24(),
+            background: cell.background.to_rgb24(),
+        }
+    }
+}
+
+fn colour(rgb24: Rgb24) -> Color {
+    Color::Rgb {
+        r: rgb24.r,
+        g: rgb24.g,
+        b: rgb24.b,
+    }
+}
+
+fn keyboard_input(code: KeyCode, modifiers: KeyModifiers) -> Option<KeyboardInput> {
+    let keyboard_input = match code {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => keys::ETX,
+        KeyCode::Char(ch) => KeyboardInput::Char(ch),
+        KeyCode::Enter => keys::RETURN,
+        KeyCode::Esc => keys::ESCAPE,
+        KeyCode::Backspace => keys::BACKSPACE,
+        KeyCode::Tab => keys::TAB,
+        KeyCode::F(n) => KeyboardInput::Function(n),
+        KeyCode::Up => KeyboardInput::Up,
+        KeyCode::Down => KeyboardInput::Down,
+        KeyCode::Left => KeyboardInput::Left,
+        KeyCode::Right => KeyboardInput::Right,
+        KeyCode::Home => KeyboardInput::Home,
+        KeyCode::End => KeyboardInput::End,
+        KeyCode::PageUp => KeyboardInput::PageUp,
+        KeyCode::PageDown => KeyboardInput::PageDown,
+        KeyCode::Delete => KeyboardInput::Delete,
+        _ => return None,
+    };
+    Some(keyboard_input)
+}
+
+fn mouse_button(button: event::MouseButton) -> MouseButton {
+    match button {
+        event::MouseButton::Left => MouseButton::Left,
+        event::MouseButton::Right => MouseButton::Right,
+        event::MouseButton::Middle => MouseButton::Middle,
+    }
+}
+
+fn input(event: Event) -> Option<Input> {
+    match event {
+        Event::Key(key_event) => {
+            // Windows reports key releases as well as presses
+            if key_event.kind == KeyEventKind::Release {
+                return None;
+            }
+            keyboard_input(key_event.code, key_event.modifiers).map(Input::Keyboard)
+        }
+        Event::Mouse(mouse_event) => {
+            let coord = Coord::new(mouse_event.column as i32, mouse_event.row as i32);
+            let mouse_input = match mouse_event.kind {
+                MouseEventKind::Down(button) => MouseInput::MousePress {
+                    button: mouse_button(button),
+                    coord,
+                },
+                MouseEventKind::Up(button) => MouseInput::MouseRelease {
+                    button: Ok(mouse_button(button)),
+                    coord,
+                },
+                MouseEventKind::Drag(button) => MouseInput::MouseMove {
+                    button: Some(mouse_button(button)),
+                    coord,
+                },
+                MouseEventKind::Moved => MouseInput::MouseMove {
+                    button: None,
+                    coord,
+                },
+                MouseEventKind::ScrollUp => MouseInput::MouseScroll {
+                    direction: ScrollDirection::Up,
+                    coord,
+                },
+                MouseEventKind::ScrollDown => MouseInput::MouseScroll {
+                    direction: ScrollDirection::Down,
+                    coord,
+                },
+                MouseEventKind::ScrollLeft => MouseInput::MouseScroll {
+                    direction: ScrollDirection::Left,
+                    coord,
+                },
+                MouseEventKind::ScrollRight => MouseInput::MouseScroll {
+                    direction: ScrollDirection::Right,
+                    coord,
+                },
+            };
+            Some(Input::Mouse(mouse_input))
+        }
+        _ => None,
+    }
+}
+
+fn terminal_size() -> io::Result<Size> {
+    let (width, height) = terminal::size()?;
+    Ok(Size::new(width as u32, height as u32))
+}
+
+/// Runs the game in a terminal using crossterm, which unlike the ansi terminal frontend also
+/// works in the Windows console
+pub struct Context {
+    stdout: Stdout,
+    frame_buffer: FrameBuffer,
+    output_cells: Vec<Option<OutputCell>>,
+}
+
+impl Context {
+    pub fn new() -> io::Result<Self> {
+        let size = terminal_size()?;
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        stdout
+            .queue(terminal::EnterAlternateScreen)?
+            .queue(event::EnableMouseCapture)?
+            .queue(cursor::Hide)?
+            .flush()?;
+        Ok(Self {
+            stdout,
+            frame_buffer: FrameBuffer::new(size),
+            output_cells: vec![None; size.count()],
+        })
+    }
+
+    fn resize_if_necessary(&mut self) -> io::Result<()> {
+        let size = terminal_size()?;
+        if size != self.frame_buffer.size() {
+            self.frame_buffer.resize(size);
+            self.output_cells = vec![None; size.count()];
+            self.stdout
+                .queue(terminal::Clear(terminal::ClearType::All))?;
+        }
+        Ok(())
+    }
+
+    fn draw_frame(&mut self) -> io::Result<()> {
+        for ((coord, cell), output_cell) in self
+            .frame_buffer
+            .enumerate()
+            .zip(self.output_cells.iter_mut())
+        {
+            let cell = OutputCell::from_frame_buffer_cell(cell);
+            if output_cell.as_ref() == Some(&cell) {
+                continue;
+            }
+            self.stdout
+                .queue(cursor::MoveTo(coord.x as u16, coord.y as u16))?
+                .queue(style::SetAttribute(Attribute::Reset))?
+                .queue(style::SetForegroundColor(colour(cell.foreground)))?
+                .queue(style::SetBackgroundColor(colour(cell.background)))?;
+            if cell.bold {
+                self.stdout.queue(style::SetAttribute(Attribute::Bold))?;
+            }
+            if cell.underline {
+                self.stdout
+                    .queue(style::SetAttribute(Attribute::Underlined))?;
+            }
+            self.stdout.queue(style::Print(cell.character))?;
+            *output_cell = Some(cell);
+        }
+        self.stdout.flush()
+    }
+
+    pub fn run<C>(mut self, mut component: C) -> io::Result<()>
+    where
+        C: 'static + Component<State = (), Output = app::Output>,
+    {
+        loop {
+            let frame_start = Instant::now();
+            while event::poll(Duration::ZERO)? {
+                if let Some(input) = input(event::read()?) {
+                    if let Some(app::Exit) = on_input(&mut component, input, &self.frame_buffer) {
+                        return Ok(());
+                    }
+                }
+            }
+            self.resize_if_necessary()?;
+            if let Some(app::Exit) =
+                on_frame(&mut component, FRAME_DURATION, &mut self.frame_buffer)
+            {
+                return Ok(());
+            }
+            self.draw_frame()?;
+            if let Some(until_next_frame) = FRAME_DURATION.checked_sub(frame_start.elapsed()) {
+                thread::sleep(until_next_frame);
+            }
+        }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        let _ = self
+            .stdout
+            .queue(style::SetAttribute(Attribute::Reset))
+            .and_then(|stdout| stdout.queue(cursor::Show))
+            .and_then(|stdout| stdout.queue(event::DisableMouseCapture))
+            .and_then(|stdout| stdout.queue(terminal::LeaveAlternateScreen))
+            .and_then(|stdout| stdout.flush());
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,52 @@
+use app::{app, AppArgs, InitialRngSeed};
+use native::NativeCommon;
+use rand::Rng;
+
+mod context;
+
+use context::Context;
+
+fn main() {
+    use meap::Parser;
+    let NativeCommon {
+        storage,
+        initial_rng_seed,
+        omniscient,
+        new_game,
+        mute,
+        balance,
+        #[cfg(feature = "leaderboard")]
+        leaderboard,
+        presence,
+        voting,
+        script,
+    } = NativeCommon::parser()
+        .with_help_default()
+        .parse_env_or_exit();
+    // As with the ansi terminal frontend, print the first seed before the game takes over the
+    // terminal so there's a chance of replaying the first playthrough if something goes wrong.
+    let initial_rng_seed = match initial_rng_seed {
+        InitialRngSeed::U64(seed) => seed,
+        InitialRngSeed::Random => rand::thread_rng().gen(),
+    };
+    println!("Initial RNG Seed: {}", initial_rng_seed);
+    let context = Context::new().unwrap();
+    context
+        .run(app(AppArgs {
+            storage,
+            initial_rng_seed: InitialRngSeed::U64(initial_rng_seed),
+            omniscient,
+            new_game,
+            mute,
+            balance,
+            audio: true,
+            share_seed: None,
+            export_file: None,
+            #[cfg(feature = "leaderboard")]
+            leaderboard,
+            presence,
+            voting,
+            script,
+        }))
+        .unwrap();
+}
@@ -0,0 +1,41 @@
+use game::{
+    headless::{BotOutcome, GreedyBot, Headless},
+    Config,
+};
+
+const DEFAULT_NUM_RUNS: u64 = 10;
+const MAX_STEPS: usize = 20000;
+
+// Usage: bot [NUM_RUNS] [FIRST_SEED]
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let num_runs = args
+        .next()
+        .map(|s| s.parse().expect("NUM_RUNS must be an integer"))
+        .unwrap_or(DEFAULT_NUM_RUNS);
+    let first_seed = args
+        .next()
+        .map(|s| s.parse().expect("FIRST_SEED must be an integer"))
+        .unwrap_or(0);
+    let config = Config::default();
+    let mut num_wins = 0;
+    let mut total_level_reached = 0;
+    for rng_seed in first_seed..(first_seed + num_runs) {
+        let mut headless = Headless::new(config.clone(), rng_seed);
+        let report = GreedyBot::new(rng_seed).play(&mut headless, MAX_STEPS);
+        println!(
+            "seed {}: {:?} on level {} after {} turns ({} steps)",
+            rng_seed, report.outcome, report.level_reached, report.turns, report.steps
+        );
+        if let BotOutcome::Win(_) = report.outcome {
+            num_wins += 1;
+        }
+        total_level_reached += report.level_reached;
+    }
+    println!(
+        "won {}/{} runs, reaching level {:.1} on average",
+        num_wins,
+        num_runs,
+        total_level_reached as f64 / num_runs as f64
+    );
+}
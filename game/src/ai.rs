@@ -1,6 +1,6 @@
 use crate::{
-    world::data::{Disposition, Npc, NpcMovement},
-    Input, World,
+    world::data::{Disposition, GunType, Item, Npc, NpcMovement},
+    World,
 };
 use coord_2d::{Coord, Size};
 use direction::CardinalDirection;
@@ -22,6 +22,48 @@ use shadowcast::{vision_distance, Context as ShadowcastContext, InputGrid, Visio
 use std::collections::HashMap;
 
 const FLEE_DISTANCE: u32 = 10;
+const SCAVENGE_DISTANCE: u32 = 8;
+const VISION_DISTANCE_SQUARED: u32 = 40;
+
+/// Something an npc decides to do on its turn
+#[derive(Clone, Copy, Debug)]
+pub enum NpcAction {
+    Walk(CardinalDirection),
+    FireGun(Coord),
+    /// Pick up the item the npc is standing on
+    PickUp,
+    /// Refill the npc's gun with ammo from its inventory
+    Reload,
+    ApplyStimpack,
+}
+
+/// Items which npcs that scavenge will go out of their way to pick up
+pub fn is_scavenged_item(item: Item) -> bool {
+    matches!(
+        item,
+        Item::Pistol
+            | Item::Shotgun
+            | Item::RocketLauncher
+            | Item::SniperRifle
+            | Item::CursedPistol
+            | Item::PistolAmmo
+            | Item::ShotgunAmmo
+            | Item::Rocket
+            | Item::SniperRounds
+            | Item::Stimpack
+            | Item::CorruptedStimpack
+    )
+}
+
+/// The item which refills guns of a given type
+pub fn gun_ammo_item(gun_type: GunType) -> Item {
+    match gun_type {
+        GunType::Pistol => Item::PistolAmmo,
+        GunType::Shotgun => Item::ShotgunAmmo,
+        GunType::RocketLauncher => Item::Rocket,
+        GunType::SniperRifle => Item::SniperRounds,
+    }
+}
 
 struct Visibility;
 
@@ -62,6 +104,35 @@ impl<'a> CanEnter for WorldCanEnterAvoidNpcs<'a> {
     }
 }
 
+// Step onto the freshest part of a trail left by the player next to the npc, if it's fresher
+// than where the npc is standing. `trail_at` is how fresh the trail is at a coord.
+fn follow_trail(
+    world: &World,
+    npc_movement: NpcMovement,
+    coord: Coord,
+    trail_at: fn(&World, Coord) -> u32,
+) -> Option<CardinalDirection> {
+    let can_enter = WorldCanEnterAvoidNpcs {
+        world,
+        npc_movement,
+    };
+    let mut best = (trail_at(world, coord), None);
+    for direction in CardinalDirection::all() {
+        let neighbour = coord + direction.coord();
+        let trail = trail_at(world, neighbour);
+        if trail > best.0 && can_enter.can_enter(neighbour) {
+            best = (trail, Some(direction));
+        }
+    }
+    best.1
+}
+
+// Npcs only notice the player within a fraction of the distance they can see, depending on how
+// detectable the player is
+fn notices_player(eye: Coord, player_coord: Coord, player_detectability: u32) -> bool {
+    eye.distance2(player_coord) * 100 <= VISION_DISTANCE_SQUARED * player_detectability
+}
+
 fn has_line_of_sight(
     eye: Coord,
     dest: Coord,
@@ -91,8 +162,15 @@ pub struct AiContext {
     player_approach: HashMap<NpcMovement, DistanceMap>,
     player_flee: HashMap<NpcMovement, DistanceMap>,
     item_distance: DistanceMap,
+    scavenge_distance: DistanceMap,
     wander_path: Path,
     shadowcast: ShadowcastContext<u8>,
+    /// Shared by all the hostile npcs without guns, which would otherwise each need a grid the
+    /// size of the world. Such npcs roam in hordes, heading for cells none of them have seen
+    /// recently.
+    horde_last_seen_grid: LastSeenGrid,
+    /// How easily npcs notice the player this turn, as a percentage
+    player_detectability: u32,
 }
 
 impl AiContext {
@@ -111,11 +189,53 @@ impl AiContext {
                 .map(|&npc_movement| (npc_movement, DistanceMap::new(size)))
                 .collect(),
             item_distance: DistanceMap::new(size),
+            scavenge_distance: DistanceMap::new(size),
             wander_path: Path::default(),
             shadowcast: ShadowcastContext::default(),
+            horde_last_seen_grid: LastSeenGrid::new(size),
+            player_detectability: 100,
         }
     }
-    pub fn update(&mut self, player: Entity, world: &World) {
+
+    // Step to a neighbouring cell closer to the player according to the shared distance map,
+    // choosing randomly between equally good cells. This is much cheaper than a search, so lets
+    // hordes of npcs chase the player, but fails if the way is blocked by other npcs.
+    fn descend_player_approach<R: Rng>(
+        &self,
+        world: &World,
+        npc_movement: NpcMovement,
+        coord: Coord,
+        rng: &mut R,
+    ) -> Option<CardinalDirection> {
+        let player_approach = self.player_approach.get(&npc_movement)?;
+        let current_distance = player_approach.distance(coord)?;
+        let can_enter = WorldCanEnterAvoidNpcs {
+            world,
+            npc_movement,
+        };
+        let mut best_distance = current_distance;
+        let mut best_directions = [CardinalDirection::North; 4];
+        let mut num_best_directions = 0;
+        for direction in CardinalDirection::all() {
+            let neighbour = coord + direction.coord();
+            if !can_enter.can_enter(neighbour) {
+                continue;
+            }
+            if let Some(distance) = player_approach.distance(neighbour) {
+                if distance < best_distance {
+                    best_distance = distance;
+                    num_best_directions = 0;
+                }
+                if distance == best_distance && distance < current_distance {
+                    best_directions[num_best_directions] = direction;
+                    num_best_directions += 1;
+                }
+            }
+        }
+        best_directions[..num_best_directions].choose(rng).cloned()
+    }
+    pub fn update(&mut self, player: Entity, world: &World, player_detectability: u32) {
+        self.player_detectability = player_detectability;
         if let Some(player_coord) = world.entity_coord(player) {
             for (&npc_movement, player_approach) in self.player_approach.iter_mut() {
                 self.distance_map_populate_context.add(player_coord);
@@ -165,6 +285,29 @@ impl AiContext {
             20,
             &mut self.item_distance,
         );
+        if world.components.scavenger.is_empty() {
+            return;
+        }
+        for (item_entity, &item) in world.components.item.iter() {
+            if is_scavenged_item(item) {
+                if let Some(coord) = world.spatial_table.coord_of(item_entity) {
+                    self.distance_map_populate_context.add(coord);
+                }
+            }
+        }
+        self.distance_map_populate_context.populate_approach(
+            &WorldCanEnterIgnoreCharacters {
+                world,
+                npc_movement: NpcMovement {
+                    // XXX note that this is based on the movement ability of the humanoid npcs
+                    // which scavenge
+                    can_traverse_difficult: false,
+                    can_open_doors: true,
+                },
+            },
+            SCAVENGE_DISTANCE,
+            &mut self.scavenge_distance,
+        );
     }
 }
 
@@ -196,16 +339,15 @@ impl LastSeenGrid {
 
     fn update(
         &mut self,
-        npc: &Npc,
         eye: Coord,
         vision_distance: vision_distance::Circle,
         world: &World,
         can_see_player: Option<CanSeePlayer>,
-        ai_context: &mut AiContext,
+        distance_map_to_player: &DistanceMap,
+        shadowcast: &mut ShadowcastContext<u8>,
     ) {
         self.count += 1;
-        let distance_map_to_player = ai_context.player_approach.get(&npc.movement).unwrap();
-        ai_context.shadowcast.for_each_visible(
+        shadowcast.for_each_visible(
             eye,
             &Visibility,
             world,
@@ -288,7 +430,8 @@ impl<'a, R: Rng> BestSearch for Wander<'a, R> {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Agent {
-    last_seen_grid: LastSeenGrid,
+    /// `None` for members of the horde, which use the grid in the `AiContext`
+    last_seen_grid: Option<LastSeenGrid>,
     vision_distance: vision_distance::Circle,
     behaviour: Behaviour,
 }
@@ -310,11 +453,171 @@ enum Behaviour {
 impl Agent {
     pub fn new(size: Size) -> Self {
         Self {
-            last_seen_grid: LastSeenGrid::new(size),
-            vision_distance: vision_distance::Circle::new_squared(40),
+            last_seen_grid: Some(LastSeenGrid::new(size)),
+            vision_distance: vision_distance::Circle::new_squared(VISION_DISTANCE_SQUARED),
+            behaviour: Behaviour::Wander { avoid: true },
+        }
+    }
+
+    /// An agent for a hostile npc without a gun, which chases the player with the shared
+    /// distance map and shares its memory of where it's been with the rest of the horde
+    pub fn new_horde() -> Self {
+        Self {
+            last_seen_grid: None,
+            vision_distance: vision_distance::Circle::new_squared(VISION_DISTANCE_SQUARED),
             behaviour: Behaviour::Wander { avoid: true },
         }
     }
+
+    /// Go to investigate a noise made by the player, unless the npc has no interest in chasing
+    /// the player
+    pub fn hear_noise(&mut self, coord: Coord) {
+        match self.behaviour {
+            Behaviour::Wander { .. } | Behaviour::Chase { .. } => {
+                self.behaviour = Behaviour::Chase {
+                    last_seen_player_coord: coord,
+                    accurate: false,
+                };
+            }
+            Behaviour::Flee | Behaviour::Steal | Behaviour::Nothing => (),
+        }
+    }
+
+    fn update_last_seen_grid(
+        &mut self,
+        npc: &Npc,
+        eye: Coord,
+        world: &World,
+        can_see_player: Option<CanSeePlayer>,
+        ai_context: &mut AiContext,
+    ) {
+        let last_seen_grid = match self.last_seen_grid.as_mut() {
+            Some(last_seen_grid) => last_seen_grid,
+            None => &mut ai_context.horde_last_seen_grid,
+        };
+        last_seen_grid.update(
+            eye,
+            self.vision_distance,
+            world,
+            can_see_player,
+            ai_context.player_approach.get(&npc.movement).unwrap(),
+            &mut ai_context.shadowcast,
+        );
+    }
+
+    // Allies attack the nearest hostile npc they can see, and otherwise stay close to the player.
+    fn act_ally(
+        &mut self,
+        entity: Entity,
+        coord: Coord,
+        npc_movement: NpcMovement,
+        world: &World,
+        player: Entity,
+        ai_context: &mut AiContext,
+    ) -> Option<NpcAction> {
+        let target = world
+            .components
+            .npc
+            .entities()
+            .filter(|&other| world.is_hostile(other) && world.components.character.contains(other))
+            .filter_map(|other| world.entity_coord(other))
+            .filter(|&other_coord| {
+                has_line_of_sight(coord, other_coord, self.vision_distance, world)
+            })
+            .min_by_key(|other_coord| other_coord.manhattan_distance(coord));
+        if let Some(turret_ammo) = world.components.turret_ammo.get(entity) {
+            // turrets are stationary and fire at anything hostile in view until out of ammo
+            if turret_ammo.is_empty() {
+                return None;
+            }
+            return target.map(NpcAction::FireGun);
+        }
+        if let Some(target) = target {
+            self.behaviour = Behaviour::Chase {
+                last_seen_player_coord: target,
+                accurate: true,
+            };
+            let has_loaded_gun = world
+                .npc_gun_entity(entity)
+                .and_then(|gun_entity| world.components.gun.get(gun_entity))
+                .map(|gun| !gun.ammo.is_empty())
+                .unwrap_or(false);
+            if has_loaded_gun && target.manhattan_distance(coord) > 1 {
+                return Some(NpcAction::FireGun(target));
+            }
+            let result = ai_context
+                .point_to_point_search_context
+                .point_to_point_search_first(
+                    expand::JumpPoint,
+                    &WorldCanEnterIgnoreCharacters {
+                        world,
+                        npc_movement,
+                    },
+                    coord,
+                    target,
+                );
+            return match result {
+                Err(NoPath) | Ok(None) => None,
+                Ok(Some(cardinal_direction)) => Some(NpcAction::Walk(cardinal_direction)),
+            };
+        }
+        self.behaviour = Behaviour::Nothing;
+        let player_coord = world.entity_coord(player)?;
+        if player_coord.manhattan_distance(coord) <= 2 {
+            return None;
+        }
+        let player_approach = ai_context.player_approach.get(&npc_movement)?;
+        ai_context
+            .distance_map_search_context
+            .search_first(
+                &WorldCanEnterAvoidNpcs {
+                    world,
+                    npc_movement,
+                },
+                coord,
+                5,
+                player_approach,
+            )
+            .map(NpcAction::Walk)
+    }
+
+    // Npcs which scavenge use the things they've picked up before doing anything else
+    fn use_items(&self, entity: Entity, world: &World) -> Option<NpcAction> {
+        let inventory = world.components.simple_inventory.get(entity)?;
+        let has_item = |item| {
+            inventory
+                .iter()
+                .any(|&item_entity| world.components.item.get(item_entity) == Some(&item))
+        };
+        if let Some(health) = world.components.health.get(entity) {
+            if health.current() <= health.max() / 2
+                && (has_item(Item::Stimpack) || has_item(Item::CorruptedStimpack))
+            {
+                return Some(NpcAction::ApplyStimpack);
+            }
+        }
+        if let Some(gun) = world
+            .npc_gun_entity(entity)
+            .and_then(|gun_entity| world.components.gun.get(gun_entity))
+        {
+            if gun.ammo.is_empty() && has_item(gun_ammo_item(gun.type_)) {
+                return Some(NpcAction::Reload);
+            }
+        }
+        let coord = world.entity_coord(entity)?;
+        let item_entity = world.spatial_table.layers_at(coord)?.item?;
+        if world
+            .components
+            .item
+            .get(item_entity)
+            .map(|&item| is_scavenged_item(item))
+            .unwrap_or(false)
+        {
+            return Some(NpcAction::PickUp);
+        }
+        None
+    }
+
     pub fn act<R: Rng>(
         &mut self,
         entity: Entity,
@@ -322,27 +625,36 @@ impl Agent {
         player: Entity,
         ai_context: &mut AiContext,
         rng: &mut R,
-    ) -> Option<Input> {
+    ) -> Option<NpcAction> {
         let coord = world.entity_coord(entity)?;
         let npc = world.entity_npc(entity).expect("not an npc");
-        self.behaviour = if let Some(player_coord) = world.entity_coord(player) {
+        if let Disposition::Ally = npc.disposition {
+            return self.act_ally(entity, coord, npc.movement, world, player, ai_context);
+        }
+        let scavenger = world.components.scavenger.contains(entity);
+        if scavenger {
+            if let Some(action) = self.use_items(entity, world) {
+                return Some(action);
+            }
+        }
+        // a disguised player passes for one of the npc's own kind, so hostile npcs ignore them
+        let disguised = matches!(npc.disposition, Disposition::Hostile)
+            && world.is_player_disguised_from(entity);
+        self.behaviour = if disguised {
+            Behaviour::Wander { avoid: false }
+        } else if let Some(player_coord) = world.entity_coord(player) {
             let can_see_player =
-                if has_line_of_sight(coord, player_coord, self.vision_distance, world) {
+                if has_line_of_sight(coord, player_coord, self.vision_distance, world)
+                    && notices_player(coord, player_coord, ai_context.player_detectability)
+                {
                     Some(CanSeePlayer)
                 } else {
                     None
                 };
-            self.last_seen_grid.update(
-                npc,
-                coord,
-                self.vision_distance,
-                world,
-                can_see_player,
-                ai_context,
-            );
+            self.update_last_seen_grid(npc, coord, world, can_see_player, ai_context);
             if let Some(CanSeePlayer) = can_see_player {
                 match npc.disposition {
-                    Disposition::Neutral => Behaviour::Nothing,
+                    Disposition::Neutral | Disposition::Ally => Behaviour::Nothing,
                     Disposition::Thief => Behaviour::Steal,
                     Disposition::Hostile => Behaviour::Chase {
                         last_seen_player_coord: player_coord,
@@ -362,6 +674,14 @@ impl Agent {
                         }
                     }
                 }
+            } else if world.components.hunts_player.contains(entity)
+                && matches!(npc.disposition, Disposition::Hostile)
+            {
+                // hunters always know roughly where the player is
+                Behaviour::Chase {
+                    last_seen_player_coord: player_coord,
+                    accurate: false,
+                }
             } else {
                 match npc.disposition {
                     Disposition::Neutral => Behaviour::Nothing,
@@ -376,9 +696,11 @@ impl Agent {
                                 // walk up to where the player was last seen, then go back to wandering
                                 Behaviour::Wander { avoid: true }
                             } else {
+                                // the player may have moved since they were last seen (e.g. into
+                                // smoke), so head to where they were rather than where they are
                                 Behaviour::Chase {
                                     last_seen_player_coord,
-                                    accurate: last_seen_player_coord == player_coord,
+                                    accurate: false,
                                 }
                             }
                         }
@@ -394,14 +716,7 @@ impl Agent {
             match npc.disposition {
                 Disposition::Neutral => Behaviour::Nothing,
                 _ => {
-                    self.last_seen_grid.update(
-                        npc,
-                        coord,
-                        self.vision_distance,
-                        world,
-                        None,
-                        ai_context,
-                    );
+                    self.update_last_seen_grid(npc, coord, world, None, ai_context);
                     Behaviour::Wander { avoid: false }
                 }
             }
@@ -420,10 +735,40 @@ impl Agent {
                 );
                 match maybe_cardinal_direction {
                     None => None,
-                    Some(cardinal_direction) => Some(Input::Walk(cardinal_direction)),
+                    Some(cardinal_direction) => Some(NpcAction::Walk(cardinal_direction)),
                 }
             }
             Behaviour::Wander { avoid } => {
+                if matches!(npc.disposition, Disposition::Hostile) && !disguised {
+                    // predators can follow the player's scent without ever seeing them
+                    if world.components.tracks_scent.contains(entity) {
+                        if let Some(cardinal_direction) =
+                            follow_trail(world, npc.movement, coord, World::scent_at)
+                        {
+                            return Some(NpcAction::Walk(cardinal_direction));
+                        }
+                    }
+                    if let Some(cardinal_direction) =
+                        follow_trail(world, npc.movement, coord, World::blood_trail_at)
+                    {
+                        return Some(NpcAction::Walk(cardinal_direction));
+                    }
+                }
+                if scavenger {
+                    if let Some(cardinal_direction) =
+                        ai_context.distance_map_search_context.search_first(
+                            &WorldCanEnterAvoidNpcs {
+                                world,
+                                npc_movement: npc.movement,
+                            },
+                            coord,
+                            5,
+                            &ai_context.scavenge_distance,
+                        )
+                    {
+                        return Some(NpcAction::Walk(cardinal_direction));
+                    }
+                }
                 let mut path_node = ai_context.wander_path.pop();
                 let need_new_path = if let Some(path_node) = path_node {
                     let implied_current_coord = path_node.to_coord - path_node.in_direction.coord();
@@ -432,16 +777,16 @@ impl Agent {
                     true
                 };
                 if need_new_path {
+                    let last_seen_grid = match self.last_seen_grid.as_ref() {
+                        Some(last_seen_grid) => last_seen_grid,
+                        None => &ai_context.horde_last_seen_grid,
+                    };
                     ai_context.best_search_context.best_search_path(
                         Wander {
                             world,
-                            last_seen_grid: &self.last_seen_grid,
+                            last_seen_grid,
                             min_last_seen_coord: None,
-                            min_last_seen_count: self
-                                .last_seen_grid
-                                .last_seen
-                                .get_checked(coord)
-                                .count,
+                            min_last_seen_count: last_seen_grid.last_seen.get_checked(coord).count,
                             entity,
                             avoid,
                             rng,
@@ -452,7 +797,7 @@ impl Agent {
                     path_node = ai_context.wander_path.pop();
                 }
                 if let Some(path_node) = path_node {
-                    Some(Input::Walk(path_node.in_direction))
+                    Some(NpcAction::Walk(path_node.in_direction))
                 } else {
                     None
                 }
@@ -473,7 +818,7 @@ impl Agent {
                         self.behaviour = Behaviour::Wander { avoid: true };
                         None
                     }
-                    Some(cardinal_direction) => Some(Input::Walk(cardinal_direction)),
+                    Some(cardinal_direction) => Some(NpcAction::Walk(cardinal_direction)),
                 }
             }
             Behaviour::Chase {
@@ -481,6 +826,21 @@ impl Agent {
                 accurate,
             } => {
                 if accurate {
+                    let has_loaded_gun = world
+                        .npc_gun_entity(entity)
+                        .and_then(|gun_entity| world.components.gun.get(gun_entity))
+                        .map(|gun| !gun.ammo.is_empty())
+                        .unwrap_or(false);
+                    if has_loaded_gun && last_seen_player_coord.manhattan_distance(coord) > 1 {
+                        return Some(NpcAction::FireGun(last_seen_player_coord));
+                    }
+                    if self.last_seen_grid.is_none() {
+                        if let Some(cardinal_direction) =
+                            ai_context.descend_player_approach(world, npc.movement, coord, rng)
+                        {
+                            return Some(NpcAction::Walk(cardinal_direction));
+                        }
+                    }
                     let player_approach = ai_context.player_approach.get(&npc.movement).unwrap();
                     let maybe_cardinal_direction =
                         ai_context.distance_map_search_context.search_first(
@@ -517,7 +877,7 @@ impl Agent {
                             } else {
                                 cardinal_direction
                             };
-                            Some(Input::Walk(cardinal_direction))
+                            Some(NpcAction::Walk(cardinal_direction))
                         }
                     }
                 } else {
@@ -537,7 +897,7 @@ impl Agent {
                             self.behaviour = Behaviour::Wander { avoid: true };
                             None
                         }
-                        Ok(Some(cardinal_direction)) => Some(Input::Walk(cardinal_direction)),
+                        Ok(Some(cardinal_direction)) => Some(NpcAction::Walk(cardinal_direction)),
                     }
                 }
             }
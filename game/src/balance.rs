@@ -0,0 +1,148 @@
+use crate::{
+    loot::{self, LevelSpawns},
+    world::data::{Item, NpcType, Organ},
+    NUM_LEVELS,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, ops::RangeInclusive};
+
+/// The size of each of the player's meters at the start of the game
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PlayerBalance {
+    /// The player's max health, which comes from their hearts, is scaled by this percentage
+    pub health_percent: u32,
+    pub oxygen: u32,
+    pub food: u32,
+    pub poison: u32,
+    pub radiation: u32,
+    pub satiation: u32,
+    pub temperature: u32,
+}
+
+impl Default for PlayerBalance {
+    fn default() -> Self {
+        Self {
+            health_percent: 100,
+            oxygen: 20,
+            food: 50,
+            poison: 10,
+            radiation: 80,
+            satiation: 20,
+            temperature: 20,
+        }
+    }
+}
+
+/// Tunable numbers which affect the difficulty of the game. Anything not specified in a balance
+/// file takes its compiled-in default.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Balance {
+    pub player: PlayerBalance,
+    /// Replacements for the default prices of items, keyed by item name (e.g. "Stimpack")
+    pub item_prices: HashMap<Item, u32>,
+    /// Organ prices at clinics and organ traders are scaled by this percentage
+    pub organ_price_percent: u32,
+    pub organ_install_cost: u32,
+    /// The number of enemies, items and organs placed on each level
+    pub level_spawns: Vec<LevelSpawns>,
+    /// Replacements for the default range of melee damage dealt by each type of npc, as an
+    /// inclusive `[min, max]` pair keyed by npc name (e.g. "Zombie")
+    pub npc_damage: HashMap<NpcType, [u32; 2]>,
+    /// Enemy health is scaled by this percentage
+    pub enemy_health_percent: u32,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self {
+            player: PlayerBalance::default(),
+            item_prices: HashMap::new(),
+            organ_price_percent: 100,
+            organ_install_cost: 20,
+            level_spawns: (0..NUM_LEVELS).map(loot::level_spawns).collect(),
+            npc_damage: HashMap::new(),
+            enemy_health_percent: 100,
+        }
+    }
+}
+
+impl Balance {
+    pub fn from_toml_str(s: &str) -> Result<Self, String> {
+        let balance: Self = toml::from_str(s).map_err(|e| e.to_string())?;
+        for (npc_type, &[min, max]) in &balance.npc_damage {
+            if min > max {
+                return Err(format!(
+                    "npc_damage for {:?} has min {} greater than max {}",
+                    npc_type, min, max
+                ));
+            }
+        }
+        Ok(balance)
+    }
+
+    /// The melee damage dealt by an npc of the given type, if it's overridden
+    pub fn npc_damage(&self, npc_type: NpcType) -> Option<RangeInclusive<u32>> {
+        self.npc_damage.get(&npc_type).map(|&[min, max]| min..=max)
+    }
+
+    pub fn item_price(&self, item: Item) -> u32 {
+        self.item_prices
+            .get(&item)
+            .cloned()
+            .unwrap_or_else(|| item.price())
+    }
+
+    pub fn item_sell_price(&self, item: Item) -> u32 {
+        self.item_price(item) / 2
+    }
+
+    pub fn organ_buy_price(&self, organ: &Organ) -> u32 {
+        (organ.player_buy_price() * self.organ_price_percent) / 100
+    }
+
+    pub fn organ_sell_price(&self, organ: &Organ) -> u32 {
+        (organ.player_sell_price() * self.organ_price_percent) / 100
+    }
+
+    pub fn organ_black_market_price(&self, organ: &Organ, hot: bool) -> u32 {
+        (organ.black_market_price(hot) * self.organ_price_percent) / 100
+    }
+
+    pub fn organ_remove_price(&self, organ: &Organ) -> i32 {
+        (organ.remove_price() * self.organ_price_percent as i32) / 100
+    }
+
+    pub fn level_spawns(&self, level_index: usize) -> LevelSpawns {
+        self.level_spawns
+            .get(level_index)
+            .cloned()
+            .unwrap_or_else(|| loot::level_spawns(level_index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn npc_damage() {
+        let balance = Balance::from_toml_str("[npc_damage]\nZombie = [2, 5]\n").unwrap();
+        assert_eq!(balance.npc_damage(NpcType::Zombie), Some(2..=5));
+        assert_eq!(balance.npc_damage(NpcType::Snatcher), None);
+    }
+
+    #[test]
+    fn npc_damage_min_greater_than_max() {
+        assert!(Balance::from_toml_str("[npc_damage]\nZombie = [5, 2]\n").is_err());
+    }
+
+    #[test]
+    fn defaults() {
+        let balance = Balance::from_toml_str("[player]\noxygen = 30\n").unwrap();
+        assert_eq!(balance.player.oxygen, 30);
+        assert_eq!(balance.player.health_percent, 100);
+        assert_eq!(balance.level_spawns.len(), NUM_LEVELS);
+    }
+}
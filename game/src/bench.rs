@@ -0,0 +1,15 @@
+//! Entry points into otherwise private parts of the game, for benchmarking
+use crate::{balance::Balance, terrain::Terrain, Game};
+use rand::Rng;
+
+/// Generate the terrain of a level using the default balance
+pub fn generate_terrain<R: Rng>(level_index: usize, rng: &mut R) {
+    Terrain::generate(level_index, &Balance::default(), rng);
+}
+
+impl Game {
+    /// Let each npc take a turn and run the per-turn systems, as happens after each player turn
+    pub fn bench_npc_turn(&mut self) {
+        let _ = self.npc_turn();
+    }
+}
@@ -0,0 +1,102 @@
+use crate::Game;
+use std::cell::RefCell;
+use std::fmt::{Debug, Write};
+
+// A panic can happen while the game is partway through handling an input, when nothing can look
+// at the game any more. So a report of the game's state is written before each input is handled,
+// and kept where a panic hook can find it.
+
+const MAX_RECENT: usize = 50;
+
+thread_local! {
+    static LATEST_REPORT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// A description of the game's state from just before it handled the most recent input, for
+/// including in crash reports
+pub fn latest_report() -> Option<String> {
+    LATEST_REPORT
+        .try_with(|report| report.try_borrow().ok()?.clone())
+        .ok()
+        .flatten()
+}
+
+impl Game {
+    /// Called with each input (including menu choices) just before it's handled
+    pub(crate) fn record_input<I: Debug>(&mut self, input: I) {
+        if self.recent_inputs.len() == MAX_RECENT {
+            self.recent_inputs.pop_front();
+        }
+        self.recent_inputs.push_back(format!("{:?}", input));
+        let report = self.diagnostic_report();
+        let _ = LATEST_REPORT.try_with(|latest| *latest.borrow_mut() = Some(report));
+    }
+
+    fn diagnostic_report(&self) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "turn: {}", self.turn_count);
+        let _ = writeln!(report, "level: {}", self.current_level_index + 1);
+        let _ = writeln!(report, "player coord: {:?}", self.player_coord());
+        let stats = self.player_stats();
+        for (name, meter) in [
+            ("health", Some(stats.health)),
+            ("oxygen", Some(stats.oxygen)),
+            ("food", Some(stats.food)),
+            ("poison", Some(stats.poison)),
+            ("radiation", Some(stats.radiation)),
+            ("power", stats.power),
+            ("satiation", stats.satiation),
+        ] {
+            if let Some(meter) = meter {
+                let _ = writeln!(report, "{}: {}/{}", name, meter.current(), meter.max());
+            }
+        }
+        let _ = writeln!(report, "money: {}", self.player_money());
+        let _ = writeln!(report, "organs:");
+        for organ in self.player_organs() {
+            let _ = writeln!(report, "  {:?} (active: {})", organ.organ, organ.active);
+        }
+        let _ = writeln!(report, "recent messages:");
+        let start = self.message_log.len().saturating_sub(MAX_RECENT);
+        for message in &self.message_log[start..] {
+            let _ = writeln!(report, "  {:?}", message);
+        }
+        let _ = writeln!(report, "recent inputs (most recent last):");
+        for input in &self.recent_inputs {
+            let _ = writeln!(report, "  {}", input);
+        }
+        report
+    }
+
+    /// A hash of the state of the game which is the same every time a game with the same seed is
+    /// played with the same inputs. Caches which don't affect gameplay, and anything stored in a
+    /// hash map (whose iteration order varies between runs), are left out.
+    pub fn state_hash(&self) -> u64 {
+        let levels = self
+            .other_levels
+            .iter()
+            .map(|level| level.as_ref().map(|level| &level.world))
+            .collect::<Vec<_>>();
+        let mut identified_items = self
+            .identified_items
+            .iter()
+            .map(|item| format!("{:?}", item))
+            .collect::<Vec<_>>();
+        identified_items.sort();
+        let state = (
+            self.current_level_index,
+            &self.world,
+            levels,
+            &self.rng,
+            &self.message_log,
+            identified_items,
+            self.turn_count,
+            self.game_over,
+        );
+        let bytes = serde_json::to_vec(&state).expect("failed to serialize game state");
+        // FNV-1a, used rather than the standard library's hasher which may change between releases
+        bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Note,
+    Email,
+    /// Displayed on a terminal rather than carried around
+    Log,
+}
+
+struct Entry {
+    kind: DocumentKind,
+    level_index: usize,
+    title: &'static str,
+    body: &'static str,
+}
+
+const fn entry(
+    kind: DocumentKind,
+    level_index: usize,
+    title: &'static str,
+    body: &'static str,
+) -> Entry {
+    Entry {
+        kind,
+        level_index,
+        title,
+        body,
+    }
+}
+
+// Every piece of writing in the game, each placed on the level where it's found. Together they
+// tell the story of the city's fall in the order the player descends through it.
+const DOCUMENTS: &[Entry] = &[
+    entry(
+        DocumentKind::Note,
+        0,
+        "Note taped to a lamppost",
+        "MISSING: Dana Okafor, 34. Last seen entering the Meridian building for a night shift on \
+        the 14th. She wouldn't just leave. If you've seen her please call.\n\n\
+        Someone has written underneath in marker: \"they're ALL in there. don't go in.\"",
+    ),
+    entry(
+        DocumentKind::Note,
+        0,
+        "Crumpled flyer",
+        "TRADE IN, TRADE UP!\n\n\
+        Tired of that sluggish original liver? Meridian Biotech will pay top dollar for your \
+        healthy organs and fit you with a CyberCore™-ready replacement the same afternoon.\n\n\
+        No appointment necessary. No questions asked.",
+    ),
+    entry(
+        DocumentKind::Email,
+        1,
+        "Email: Quarterly targets",
+        "From: R. Hale, Acquisitions\n\
+        To: All floor managers\n\n\
+        Harvest volumes are down 12% on last quarter. I don't want to hear about \"donor \
+        reluctance\". The basement facility needs a steady supply and we are contractually \
+        obliged to provide it.\n\n\
+        Effective immediately, the night shift will process walk-ins without the consent forms.",
+    ),
+    entry(
+        DocumentKind::Log,
+        1,
+        "Security log",
+        "02:14 Motion detected, stairwell B. Nobody on camera.\n\
+        02:31 Motion detected, stairwell B. Nobody on camera.\n\
+        02:47 Cleaning staff report something growing out of the floor drains. Sent maintenance.\n\
+        03:05 Maintenance not responding.\n\
+        03:06 Maintenance not responding.\n\
+        03:40 Lockdown initiated from the basement. Override refused.",
+    ),
+    entry(
+        DocumentKind::Note,
+        1,
+        "Sticky note",
+        "Dana - I swapped shifts with you so you can make your sister's thing. Don't thank me, \
+        just bring back coffee. And DON'T go down to B3 again, Hale is watching the badge logs.",
+    ),
+    entry(
+        DocumentKind::Email,
+        2,
+        "Email: Re: Re: Re: the smell",
+        "From: Facilities\n\
+        To: Ward 6 nursing staff\n\n\
+        We have checked the ventilation three times. There is nothing in the ducts. Please stop \
+        submitting tickets about the walls being warm.\n\n\
+        Also, whoever keeps taping over the drains, please stop. It's a fire hazard.",
+    ),
+    entry(
+        DocumentKind::Log,
+        2,
+        "Clinic intake records",
+        "Patient 4471 - consented, liver + kidney, discharged.\n\
+        Patient 4472 - consented, heart, discharged (CyberCore™ fitted).\n\
+        Patient 4473 - no consent on file, full harvest, remains sent to B4.\n\
+        Patient 4474 - no consent on file, full harvest, remains sent to B4.\n\
+        Patient 4475 - DANA OKAFOR - no consent on file - ESCAPED DURING PREP\n\
+        Patient 4476 - no consent on file, full harvest, remains sent to B4.",
+    ),
+    entry(
+        DocumentKind::Note,
+        2,
+        "Diary page",
+        "Day 9. The ones who went down to B4 to look for the missing came back wrong. They don't \
+        talk, they just stand at the stairwell door and wait. Marcus says they're listening to \
+        something. I can hear it too now when it's quiet. A heartbeat, much too slow, coming up \
+        through the floor.",
+    ),
+    entry(
+        DocumentKind::Log,
+        3,
+        "Experiment log 7",
+        "Subject CORRUPTOR continues to incorporate donor tissue at an accelerating rate. It no \
+        longer requires surgical grafting; organs placed within 2m of the primary mass are \
+        absorbed unassisted.\n\n\
+        Tendrils have breached the containment floor and are propagating through the building's \
+        plumbing. Recommend immediate incineration.\n\n\
+        Recommendation denied by R. Hale. The board considers the heart too valuable to lose.",
+    ),
+    entry(
+        DocumentKind::Email,
+        3,
+        "Email: Containment",
+        "From: R. Hale\n\
+        To: Board of Directors\n\n\
+        The situation is under control. The city has not been informed and will not be. Once the \
+        heart is stable it can be transplanted into a suitable host, and whoever carries it will \
+        be in no position to refuse our instructions.\n\n\
+        Please keep this message confidential.",
+    ),
+    entry(
+        DocumentKind::Note,
+        3,
+        "Scrawled on the back of a consent form",
+        "If anyone finds this - it's the heart. Everything down here is connected to it. Kill it \
+        and the rest will die too.\n\n\
+        Don't put it in yourself. Whatever they told you, don't.\n\n\
+        - D.O.",
+    ),
+];
+
+/// A piece of writing the player can read, either carried as an item or displayed on a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Document(usize);
+
+impl Document {
+    fn entry(self) -> &'static Entry {
+        &DOCUMENTS[self.0]
+    }
+
+    pub fn kind(self) -> DocumentKind {
+        self.entry().kind
+    }
+
+    pub fn title(self) -> &'static str {
+        self.entry().title
+    }
+
+    pub fn body(self) -> &'static str {
+        self.entry().body
+    }
+
+    /// The documents which can be found on the level with the given index
+    pub fn on_level(level_index: usize) -> impl Iterator<Item = Self> {
+        DOCUMENTS
+            .iter()
+            .enumerate()
+            .filter(move |(_, entry)| entry.level_index == level_index)
+            .map(|(i, _)| Self(i))
+    }
+}
@@ -0,0 +1,461 @@
+//! Drive a game without a frontend, e.g. to have a bot play it for soak testing or balance
+//! analysis. This goes through the same witness api as the app so games played headlessly follow
+//! the same rules.
+use crate::{
+    witness::{self, Witness},
+    ActionError, Background, CellVisibility, Config, GameOverReason, Input, Menu, MenuChoice,
+    Message, Remnant, Tile, Victory, Win,
+};
+use coord_2d::Coord;
+use direction::CardinalDirection;
+use rand::{Rng, SeedableRng};
+use rand_isaac::Isaac64Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+/// Time passed to the game for each tick while waiting for animations to finish
+const TICK: Duration = Duration::from_millis(16);
+
+/// Animations which take longer than this many ticks are assumed to never end
+const MAX_TICKS_PER_STEP: usize = 10000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum Step {
+    Input(Input),
+    /// Choose an option from the open menu. If there's no menu open this behaves like the
+    /// frontend's inventory menus, which are opened and committed to in a single step.
+    Choice(MenuChoice),
+    /// Close the open menu without choosing anything
+    CancelMenu,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepError {
+    Action(ActionError),
+    /// The game has ended so can't take any more steps
+    GameEnded,
+    /// Inputs can't be given while a menu is open
+    MenuOpen,
+    NoMenu,
+    /// The choice isn't one of the open menu's choices
+    InvalidChoice,
+}
+
+pub enum Status<'a> {
+    Running,
+    Menu(&'a Menu),
+    GameOver(GameOverReason),
+    Win(Win),
+}
+
+pub struct Headless {
+    game: witness::Game,
+    // only `None` while a step is being taken
+    witness: Option<Witness>,
+    config: Config,
+    rng_seed: u64,
+    steps: Vec<Step>,
+}
+
+/// The seed and steps of a headless game, along with the hash of the game's state after the
+/// steps were taken. Replaying it and getting a different hash means something changed the way
+/// the game plays out, such as the order in which random numbers are drawn.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Replay {
+    pub rng_seed: u64,
+    #[serde(default)]
+    pub background: Background,
+    #[serde(default)]
+    pub victories: Vec<Victory>,
+    /// Left by a previous character who died playing the same seed
+    #[serde(default)]
+    pub remnant: Option<Remnant>,
+    pub steps: Vec<Step>,
+    pub state_hash: u64,
+}
+
+impl Replay {
+    /// Take each step of the replay in a new game, returning the hash of the resulting state.
+    /// Steps which fail are ignored, as they were when they were recorded.
+    pub fn play(&self, config: Config) -> u64 {
+        let mut headless = Headless::new_with(
+            config,
+            self.rng_seed,
+            self.background,
+            self.victories.clone(),
+        );
+        if let Some(remnant) = self.remnant.as_ref() {
+            headless.game.inherit_remnant(remnant.clone());
+        }
+        for &step in &self.steps {
+            let _ = headless.step(step);
+        }
+        headless.game().state_hash()
+    }
+}
+
+impl Headless {
+    pub fn new(config: Config, rng_seed: u64) -> Self {
+        Self::new_with(config, rng_seed, Default::default(), Vec::new())
+    }
+
+    /// Start a game with the given background and New Game Plus bonuses
+    pub fn new_with(
+        config: Config,
+        rng_seed: u64,
+        background: Background,
+        victories: Vec<Victory>,
+    ) -> Self {
+        let mut rng = Isaac64Rng::seed_from_u64(rng_seed);
+        let (game, running) = witness::new_game(&config, background, victories, &mut rng);
+        Self {
+            game,
+            witness: Some(running.into_witness()),
+            config,
+            rng_seed,
+            steps: Vec::new(),
+        }
+    }
+
+    /// A replay of all the steps taken so far
+    pub fn replay(&self) -> Replay {
+        Replay {
+            rng_seed: self.rng_seed,
+            background: self.game().background(),
+            victories: self.game().victories.clone(),
+            remnant: self.game().inherited_remnant.clone(),
+            steps: self.steps.clone(),
+            state_hash: self.game().state_hash(),
+        }
+    }
+
+    pub fn game(&self) -> &crate::Game {
+        self.game.inner_ref()
+    }
+
+    pub fn status(&self) -> Status<'_> {
+        match self.witness.as_ref().unwrap() {
+            Witness::Menu(menu) => Status::Menu(&menu.menu),
+            Witness::GameOver(reason) => Status::GameOver(*reason),
+            Witness::Win(win) => Status::Win(win.win),
+            // firing is committed in the same step as it's started so can't be observed
+            Witness::Running(_) | Witness::FireEquipped(_) | Witness::FireBody(_) => {
+                Status::Running
+            }
+            // throwing is started by applying an item and committed by the next step
+            Witness::Throw(_) => Status::Running,
+            // a document stays open until the next step
+            Witness::Read(_) => Status::Running,
+        }
+    }
+
+    /// Apply a step then let the game run until it's waiting for the next one. The step takes
+    /// effect even if an error is returned, as it would if the player made it.
+    pub fn step(&mut self, step: Step) -> Result<(), StepError> {
+        self.steps.push(step);
+        let witness = self.witness.take().unwrap();
+        let (witness, result) = apply_step(&mut self.game, witness, step);
+        self.witness = Some(self.settle(witness));
+        result
+    }
+
+    fn settle(&mut self, mut witness: Witness) -> Witness {
+        for _ in 0..MAX_TICKS_PER_STEP {
+            match witness {
+                Witness::Running(running) if self.game.inner_ref().is_gameplay_blocked() => {
+                    witness = running.tick(&mut self.game, TICK, &self.config);
+                }
+                _ => break,
+            }
+        }
+        // nothing is listening for sounds or screen shake
+        self.game.drain_external_events();
+        witness
+    }
+}
+
+/// Apply a step to a game which is waiting for one. This is how headless games are driven, and is
+/// exposed so a frontend can drive a game it's rendering in the same way (e.g. to let a bot play
+/// on the title screen).
+pub fn apply_step(
+    game: &mut witness::Game,
+    witness: Witness,
+    step: Step,
+) -> (Witness, Result<(), StepError>) {
+    let running = match (witness, step) {
+        (Witness::Running(running), Step::Input(_) | Step::Choice(_)) => running,
+        (Witness::Throw(throw), Step::Input(Input::Throw { target, .. })) => {
+            let (witness, result) = throw.commit(game, target);
+            return (witness, result.map_err(StepError::Action));
+        }
+        (Witness::Throw(throw), Step::Input(_) | Step::Choice(_)) => match throw.cancel() {
+            Witness::Running(running) => running,
+            _ => unreachable!(),
+        },
+        (Witness::Throw(throw), Step::CancelMenu) => return (throw.cancel(), Ok(())),
+        (Witness::Read(read), Step::Input(_) | Step::Choice(_)) => match read.close() {
+            Witness::Running(running) => running,
+            _ => unreachable!(),
+        },
+        (Witness::Read(read), Step::CancelMenu) => return (read.close(), Err(StepError::NoMenu)),
+        (Witness::Menu(menu), Step::Choice(choice)) => return (menu.commit(game, choice), Ok(())),
+        (Witness::Menu(menu), Step::CancelMenu) => return (menu.cancel(), Ok(())),
+        (witness @ Witness::Menu(_), Step::Input(_)) => return (witness, Err(StepError::MenuOpen)),
+        (witness @ Witness::Running(_), Step::CancelMenu) => {
+            return (witness, Err(StepError::NoMenu))
+        }
+        (witness, _) => return (witness, Err(StepError::GameEnded)),
+    };
+    let (witness, result) = match step {
+        Step::Input(Input::Walk(direction)) => running.walk(game, direction),
+        Step::Input(Input::Wait) => running.wait(game),
+        Step::Input(Input::Get) => running.get(game),
+        Step::Input(Input::Unequip) => running.unequip(game),
+        Step::Input(Input::Reload) => running.reload(game),
+        Step::Input(Input::ToggleDroneControl) => running.toggle_drone_control(game),
+        Step::Input(Input::ToggleSprint) => running.toggle_sprint(game),
+        Step::Input(Input::Overwatch(direction)) => running.overwatch(game, direction),
+        Step::Input(Input::FireEquipped(coord)) => match running.fire_equipped() {
+            Witness::FireEquipped(fire_equipped) => fire_equipped.commit(game, coord),
+            _ => unreachable!(),
+        },
+        Step::Input(Input::FireBody(coord)) => match running.fire_body() {
+            Witness::FireBody(fire_body) => fire_body.commit(game, coord),
+            _ => unreachable!(),
+        },
+        Step::Input(Input::Throw {
+            inventory_index,
+            target,
+        }) => match running.throw(inventory_index) {
+            Witness::Throw(throw) => throw.commit(game, target),
+            _ => unreachable!(),
+        },
+        Step::Choice(choice) => {
+            let menu = Menu {
+                choices: vec![choice],
+                text: String::new(),
+                image: None,
+            };
+            match running.menu(menu) {
+                Witness::Menu(menu) => (menu.commit(game, choice), Ok(())),
+                _ => unreachable!(),
+            }
+        }
+        Step::CancelMenu => unreachable!(),
+    };
+    (witness, result.map_err(StepError::Action))
+}
+
+/// Result of a bot playing a game until it ends or it runs out of steps
+#[derive(Debug, Clone, Copy)]
+pub enum BotOutcome {
+    GameOver(GameOverReason),
+    Win(Win),
+    OutOfSteps,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BotReport {
+    pub outcome: BotOutcome,
+    pub steps: usize,
+    pub turns: u64,
+    pub level_reached: usize,
+}
+
+/// A bot which shoots (or walks into) the nearest hostile, and otherwise heads for the stairs
+/// down or explores. It makes no attempt at using items, shopping or surgery.
+pub struct GreedyBot {
+    rng: Isaac64Rng,
+    // the level on which the elevator was found to be unpowered, so the bot explores instead
+    unpowered_level: Option<usize>,
+    gun_failed: bool,
+}
+
+impl GreedyBot {
+    pub fn new(rng_seed: u64) -> Self {
+        Self {
+            rng: Isaac64Rng::seed_from_u64(rng_seed),
+            unpowered_level: None,
+            gun_failed: false,
+        }
+    }
+
+    /// Play the game until it ends or `max_steps` steps have been taken
+    pub fn play(&mut self, headless: &mut Headless, max_steps: usize) -> BotReport {
+        let mut steps = 0;
+        let outcome = loop {
+            match headless.status() {
+                Status::GameOver(reason) => break BotOutcome::GameOver(reason),
+                Status::Win(win) => break BotOutcome::Win(win),
+                _ if steps >= max_steps => break BotOutcome::OutOfSteps,
+                _ => (),
+            }
+            let step = self.choose(headless);
+            let num_messages = headless.game().message_log().len();
+            let result = headless.step(step);
+            self.observe(headless.game(), step, result, num_messages);
+            steps += 1;
+        };
+        let stats = headless.game().run_stats();
+        BotReport {
+            outcome,
+            steps,
+            turns: stats.turns,
+            level_reached: stats.level_reached,
+        }
+    }
+
+    /// Update what the bot knows about the game after it took a step. `num_messages` is the
+    /// length of the message log before the step was taken.
+    pub fn observe(
+        &mut self,
+        game: &crate::Game,
+        step: Step,
+        result: Result<(), StepError>,
+        num_messages: usize,
+    ) {
+        if let (Step::Input(Input::FireEquipped(_)), Err(_)) = (step, result) {
+            self.gun_failed = true;
+        }
+        if !game.is_hostile_visible() {
+            self.gun_failed = false;
+        }
+        if game.message_log()[num_messages..].contains(&Message::ElevatorUnpowered) {
+            self.unpowered_level = Some(game.current_level_index());
+        }
+    }
+
+    pub fn choose(&mut self, headless: &Headless) -> Step {
+        let menu_open = matches!(headless.status(), Status::Menu(_));
+        self.choose_in_game(headless.game(), menu_open)
+    }
+
+    pub fn choose_in_game(&mut self, game: &crate::Game, menu_open: bool) -> Step {
+        if menu_open {
+            return Step::CancelMenu;
+        }
+        let player_coord = game.player_coord();
+        let nearest_hostile = game
+            .visible_hostile_coords()
+            .into_iter()
+            .min_by_key(|&coord| (coord - player_coord).magnitude2());
+        if let Some(hostile) = nearest_hostile {
+            if !self.gun_failed && (hostile - player_coord).manhattan_magnitude() > 1 {
+                return Step::Input(Input::FireEquipped(hostile));
+            }
+            if let Some(direction) = self.first_step_towards(game, |coord| coord == hostile) {
+                return Step::Input(Input::Walk(direction));
+            }
+        }
+        let use_stairs = self.unpowered_level != Some(game.current_level_index());
+        let is_stairs = |coord| use_stairs && feature_at(game, coord) == Some(Tile::StairsDown);
+        let direction = self
+            .first_step_towards(game, is_stairs)
+            .or_else(|| self.first_step_towards(game, |coord| is_frontier(game, coord)));
+        match direction {
+            Some(direction) => Step::Input(Input::Walk(direction)),
+            None => {
+                let direction = CardinalDirection::all().nth(self.rng.gen_range(0..4));
+                Step::Input(Input::Walk(direction.unwrap()))
+            }
+        }
+    }
+
+    // Breadth-first search through cells the player knows to be passable, returning the first
+    // step along the shortest path to the nearest cell satisfying `is_target`
+    fn first_step_towards<F: Fn(Coord) -> bool>(
+        &self,
+        game: &crate::Game,
+        is_target: F,
+    ) -> Option<CardinalDirection> {
+        let start = game.player_coord();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(start);
+        for direction in CardinalDirection::all() {
+            let coord = start + direction.coord();
+            if seen.insert(coord) {
+                queue.push_back((coord, direction));
+            }
+        }
+        while let Some((coord, first_direction)) = queue.pop_front() {
+            if is_target(coord) {
+                return Some(first_direction);
+            }
+            // hazardous floors are only walked onto when they are the destination
+            if !is_passable(game, coord) || is_hazard(game, coord) {
+                continue;
+            }
+            for direction in CardinalDirection::all() {
+                let next = coord + direction.coord();
+                if seen.insert(next) {
+                    queue.push_back((next, first_direction));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn feature_at(game: &crate::Game, coord: Coord) -> Option<Tile> {
+    game.cell_visibility_at_coord(coord)
+        .into_data()?
+        .tiles
+        .feature
+        .tile
+}
+
+fn is_passable(game: &crate::Game, coord: Coord) -> bool {
+    if !coord.is_valid(game.world_size()) {
+        return false;
+    }
+    let data = match game.cell_visibility_at_coord(coord) {
+        CellVisibility::Never => return false,
+        other => other.into_data().unwrap(),
+    };
+    if data.tiles.character.tile.is_some() {
+        return false;
+    }
+    !matches!(
+        data.tiles.feature.tile,
+        Some(
+            Tile::Wall
+                | Tile::Window
+                | Tile::Water
+                | Tile::DoorBarricaded
+                | Tile::StairsUp
+                | Tile::Exit
+                | Tile::Generator
+                | Tile::ExplosiveBarrel
+                | Tile::AlarmPanel
+                | Tile::Stash
+                | Tile::Terminal
+                | Tile::Prop(_)
+        )
+    )
+}
+
+fn is_hazard(game: &crate::Game, coord: Coord) -> bool {
+    game.cell_visibility_at_coord(coord)
+        .into_data()
+        .is_some_and(|data| {
+            matches!(
+                data.tiles.floor.tile,
+                Some(Tile::FloorAcid | Tile::FloorElectrified)
+            )
+        })
+}
+
+// A passable cell next to one the player has never seen
+fn is_frontier(game: &crate::Game, coord: Coord) -> bool {
+    is_passable(game, coord)
+        && CardinalDirection::all().any(|direction| {
+            let neighbour = coord + direction.coord();
+            neighbour.is_valid(game.world_size())
+                && matches!(
+                    game.cell_visibility_at_coord(neighbour),
+                    CellVisibility::Never
+                )
+        })
+}
@@ -8,6 +8,8 @@ use rand_isaac::Isaac64Rng;
 pub use rgb_int::{Rgb24, Rgba32};
 use serde::{Deserialize, Serialize};
 pub use spatial_table::UpdateError;
+use std::collections::{HashSet, VecDeque};
+use std::ops::RangeInclusive;
 use std::time::Duration;
 use vector::{Radial, Radians};
 
@@ -15,36 +17,64 @@ pub use visible_area_detection::{
     vision_distance::Circle, CellVisibility, Light, VisibilityGrid, World as VisibleWorld,
 };
 
+mod balance;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod diagnostics;
+mod documents;
+pub mod headless;
+mod loot;
+mod market;
+mod prefab;
+#[cfg(feature = "remote")]
+pub mod remote;
 mod terrain;
 mod world;
 use terrain::Terrain;
 mod ai;
 mod realtime;
+mod script;
+mod tutorial;
 pub mod witness;
 
-use ai::{Agent, AiContext};
+pub use documents::{Document, DocumentKind};
+use market::OrganMarket;
+pub use market::OrganMarketPrice;
+#[cfg(feature = "rhai")]
+pub use script::rhai::RhaiScript;
+pub use script::{Script, ScriptApi, ScriptHooks};
+pub use tutorial::TutorialStep;
+
+use ai::{Agent, AiContext, NpcAction};
+pub use balance::Balance;
 use realtime::AnimationContext;
-use world::{
-    data::{DoorState, EntityData, EntityUpdate, GunType, Hand, ProjectileDamage},
-    spatial::Layers,
-    World,
-};
 pub use world::{
     data::{
-        Item, Layer, Location, Meter, NpcType, Organ, OrganTrait, OrganTraits, OrganType, Tile,
+        DamageModifier, DamageRoll, Experience, Item, ItemAppearance, Layer, LevelTheme, Location,
+        Meter, NpcType, Organ, OrganTrait, OrganTraits, OrganType, Perk, Prop, Skill, Skills, Tile,
+        WearSlot, MAX_SKILL_LEVEL,
     },
     query::PlayerOrgan,
     spatial::LayerTable,
 };
+use world::{
+    data::{
+        Disposition, DoorState, EntityData, EntityUpdate, GunType, Hand, ProjectileDamage, Worn,
+    },
+    delta::WorldDelta,
+    spatial::Layers,
+    World,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Omniscient;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Config {
     pub omniscient: Option<Omniscient>,
     pub demo: bool,
     pub debug: bool,
+    pub balance: Balance,
 }
 impl Config {
     pub const OMNISCIENT: Option<Omniscient> = Some(Omniscient);
@@ -55,23 +85,112 @@ impl Default for Config {
             omniscient: None,
             demo: false,
             debug: false,
+            balance: Balance::default(),
         }
     }
 }
 
 pub const MAX_ORGANS: usize = 8;
+pub const MERCENARY_HIRE_PRICE: u32 = 100;
+// Doors are bashed down by npcs which can't open them, and barricading a door adds to its health
+pub const DOOR_HEALTH: u32 = 6;
+pub const BARRICADE_HEALTH: u32 = 12;
+// Each move onto debris by a player who can climb takes this many turns on top of the usual one
+const CLIMB_EXTRA_TURNS: usize = 2;
+// Each extra step taken while sprinting costs this much oxygen, and can be heard by hostile npcs
+// this far away
+const SPRINT_OXYGEN_COST: u32 = 2;
+const SPRINT_NOISE_DISTANCE: u32 = 8;
+// How easily npcs notice the player, as a percentage of the distance they can see. A player in
+// the dark is harder to spot, and one who stays still harder still.
+const DARK_DETECTABILITY_PERCENT: u32 = 40;
+const STILL_DETECTABILITY_PERCENT: u32 = 50;
+const MIN_DETECTABILITY_PERCENT: u32 = 20;
+
+/// What the player did on their last turn, which affects how easily npcs notice them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum Activity {
+    Still,
+    #[default]
+    Moving,
+    /// Firing guns and sprinting give the player away regardless of how well hidden they are
+    Noisy,
+}
+// This many visible hostiles is as dangerous as it gets
+const DANGER_MAX_HOSTILES: f64 = 4.0;
+// Hot organs sold by the organ trader may turn out to be damaged
+const HOT_ORGAN_DAMAGED_PROBABILITY: f64 = 0.5;
+// Surgery at a clinic can fail, more often on organs with troublesome traits or a wounded patient
+const SURGERY_BASE_FAILURE_PROBABILITY: f64 = 0.05;
+const SURGERY_MAX_FAILURE_PROBABILITY: f64 = 0.75;
+const SURGERY_FAILURE_DAMAGE: u32 = 4;
+const SURGERY_WITHOUT_ANESTHETIC_DAMAGE: u32 = 3;
+const SURGERY_RECOVERY_TURNS: u32 = 40;
+// Operating on yourself with a field surgery kit is much riskier and takes a while
+const FIELD_SURGERY_RISK_MULTIPLIER: f64 = 3.0;
+const FIELD_SURGERY_MAX_FAILURE_PROBABILITY: f64 = 0.9;
+const FIELD_SURGERY_TURNS: usize = 5;
+// Each immunosuppressant stops the player's body rejecting harvested organs for a while
+const IMMUNOSUPPRESSANT_DOSE_TURNS: u32 = 150;
+const IMMUNOSUPPRESSANT_MAX_TURNS: u32 = 300;
+// Sniper rounds pass through the first character they hit
+const SNIPER_ROUND_PENETRATION: u32 = 1;
+// Shotgun pellets occasionally hit hard enough to cause bleeding
+const SHOTGUN_CRIT_BLEEDING_PROBABILITY: f64 = 0.1;
+const SHOTGUN_SPREAD: f64 = 3.0;
+/// How far past the player the target of a shot is extended, so bullets fly past what they're
+/// aimed at
+const BULLET_RANGE: usize = 20;
+// Each level of a skill improves its effect by this fraction
+const GUN_SKILL_SPREAD_REDUCTION_PER_LEVEL: f64 = 0.1;
+const SURGERY_SKILL_FAILURE_REDUCTION_PER_LEVEL: f64 = 0.1;
+const SURGERY_SKILL_CLINIC_DISCOUNT_PERCENT_PER_LEVEL: u32 = 5;
+// Each attempt at hacking an alarm panel makes this much progress, plus the player's hacking level
+const ALARM_PANEL_HACK_DIFFICULTY: u32 = 6;
+// A fully psychotic player sees enemies that aren't there, sees harmless npcs as monsters and
+// stumbles the wrong way. Each effect becomes more likely as psychosis worsens.
+const HALLUCINATION_MAX_PROBABILITY: f64 = 0.2;
+const HALLUCINATION_TURNS: u32 = 12;
+const HALLUCINATION_MAX_DISTANCE: i32 = 6;
+const HALLUCINATION_MIN_DISTANCE: i32 = 3;
+const HOSTILE_FLASH_MAX_PROBABILITY: f64 = 0.2;
+const PSYCHOSIS_STUMBLE_MAX_PROBABILITY: f64 = 0.25;
+const ANTIPSYCHOTIC_DOSE: u32 = 50;
+const THROW_MAX_DISTANCE: usize = 8;
+const EMP_GRENADE_RANGE: u32 = 2;
+const SMOKE_GRENADE_RADIUS: u32 = 3;
+const OVERWATCH_RANGE: u32 = 10;
+const FOOD_POISONING: u32 = 4;
+const CLONE_INSURANCE_PRICE: u32 = 120;
+const HALLUCINATION_NPC_TYPES: &[NpcType] = &[
+    NpcType::Zombie,
+    NpcType::Climber,
+    NpcType::Trespasser,
+    NpcType::Snatcher,
+    NpcType::Boomer,
+    NpcType::Poisoner,
+    NpcType::Divider,
+    NpcType::Glower,
+    NpcType::Venter,
+    NpcType::Sentinel,
+];
 
 /// Events which the game can report back to the io layer so it can
 /// respond with a sound/visual effect.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum ExternalEvent {
-    FirePistol,
-    FireShotgun,
-    FireRocket,
+    FirePistol(Coord),
+    FireShotgun(Coord),
+    FireRocket(Coord),
     Explosion(Coord),
+    ShatterWindow(Coord),
     ChangeLevel,
-    Melee,
-    Death,
+    Melee(Coord),
+    Death(Coord),
+    Alarm,
+    GeigerClick(u32),
+    BossSighted,
+    BossKill,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -79,15 +198,27 @@ pub enum Message {
     Wait,
     OpenDoor,
     CloseDoor,
+    Climb,
     ActionError(ActionError),
     NpcHit {
         npc_type: NpcType,
-        damage: u32,
+        damage: DamageRoll,
     },
     NpcDies(NpcType),
+    BarricadeDoor,
+    RemoveBarricade,
+    BarricadeGivesWay,
+    DoorBreaks,
+    WindowShatters,
+    ExplosiveBarrelDetonates,
+    PoisonGas,
+    WornItemFallsApart(Item),
+    PowerRestored,
+    ElevatorUnpowered,
+    Script(String),
     PlayerHit {
         attacker_npc_type: NpcType,
-        damage: u32,
+        damage: DamageRoll,
     },
     GetMoney(u32),
     GetItem(Item),
@@ -125,11 +256,104 @@ pub enum Message {
     RadiationClose,
     RadiationVeryClose,
     Poison,
+    AcidBurn,
+    Electrocuted,
+    Sinking,
+    ItemSinks(Item),
     BecomesHostile(NpcType),
+    DisguiseBlown(NpcType),
+    NpcCorrupted(NpcType),
+    MerchantArrives,
+    MerchantLeaves,
+    BountyPosted,
+    BountyHunterArrives,
+    VendorsRaisePrices,
+    HotOrganDamaged(Organ),
+    SurgeryWithoutAnesthetic,
+    SurgeryFailed(Organ),
+    RecoverFromSurgery,
+    FoodSpoiled,
+    ButcherCorpse(NpcType),
+    CookMeat,
+    FoodPoisoning,
+    BuyInsurance(u32),
+    AlreadyInsured,
+    CloneRespawn,
+    RecoverRemains(usize),
+    Freezing,
+    Overheating,
+    FieldSurgeryBegin,
+    FieldSurgeryInterrupted,
+    ApplyImmunosuppressant,
+    ImmunosuppressantWearingOff,
+    ImmunosuppressantWoreOff,
+    OrganRejection(Organ),
+    StartBleeding,
+    NpcStartBleeding(NpcType),
+    OrganHit(Organ),
+    TumourDestroyed,
+    ApplyBandage,
+    CyberpsychosisOnset,
+    CyberpsychosisSubsides,
+    PsychosisStumble,
+    HallucinationDispelled(NpcType),
+    ApplyAntipsychotic,
+    Throw(Item),
+    Overwatch(CardinalDirection),
+    OverwatchFire(NpcType),
+    EmpHitPlayer,
+    CyberneticsRestored,
+    LevelUp(u32),
+    ChoosePerk(Perk),
+    SkillImproved {
+        skill: Skill,
+        level: u32,
+    },
+    HackAlarmPanelProgress,
+    HackAlarmPanel,
+    Tutorial(TutorialStep),
+    StealItem(Item),
+    TheftNoticed(NpcType),
     CantAfford(Item),
     Buy(Item),
+    Sell {
+        item: Item,
+        price: u32,
+    },
+    SellOrgan {
+        organ: Organ,
+        price: u32,
+    },
+    StashDeposit(Item),
+    StashWithdraw(Item),
+    StashIsFull,
+    HireMercenary,
+    GiveMercenaryGun(Item),
+    MercenaryFollows,
+    DeployDrone,
+    RecallDrone,
+    ControlDrone,
+    ControlPlayer,
+    StartSprinting,
+    StopSprinting,
+    OutOfBreath,
+    DroneDestroyed,
+    DroneLost,
+    DeployTurret,
+    PickUpTurret,
+    AlarmTriggered,
+    Reinforcements,
+    AlarmStops,
+    ApplyCorruptedStimpack,
+    CursedGunFires,
+    Scan(Vec<Item>),
+    RadiationOverlayOn,
+    ReadSchematics,
+    ReadMarketReport(Vec<OrganMarketPrice>),
+    ReadDocument(Document),
+    RadiationOverlayOff,
     FillBloodVial,
-    EatFood,
+    EatFood(Item),
     ApplyAntidote,
     ApplyAntiRads,
     ApplyStimpack,
@@ -151,13 +375,13 @@ pub enum Message {
 #[derive(Debug, Clone, Copy)]
 pub enum MenuImage {}
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WhichHand {
     Left,
     Right,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum MenuChoice {
     Empty,
     DropItem(usize),
@@ -172,17 +396,26 @@ pub enum MenuChoice {
         inventory_index: usize,
     },
     UnequipWhichHand(WhichHand),
+    TakeOff(WearSlot),
     BuyItem {
         item: Item,
         shop_entity: Entity,
         item_entity: Entity,
         shop_inventory_index: usize,
     },
+    BuyOrgan {
+        organ: Organ,
+        hot: bool,
+        shop_entity: Entity,
+        item_entity: Entity,
+        shop_inventory_index: usize,
+    },
     ClinicBuy {
         clinic_entity: Entity,
     },
     ClinicRemove,
     ClinicInstallFromContainer,
+    ClinicBuyInsurance,
     ClinicRemoveOrgan {
         organ: Organ,
         index: usize,
@@ -192,10 +425,82 @@ pub enum MenuChoice {
         index: usize,
         organ: Organ,
     },
+    FieldSurgeryInstall {
+        kit_inventory_index: usize,
+        container_inventory_index: usize,
+        organ: Organ,
+    },
+    FieldSurgeryRemove {
+        kit_inventory_index: usize,
+        organ: Organ,
+        index: usize,
+    },
     ClinicInstallFromContainerOrgan {
         inventory_index: usize,
         organ: Organ,
     },
+    Sell {
+        shop_entity: Entity,
+    },
+    SellItem {
+        item: Item,
+        shop_entity: Entity,
+        inventory_index: usize,
+    },
+    SellOrgan {
+        organ: Organ,
+        shop_entity: Entity,
+        inventory_index: usize,
+    },
+    StashDeposit {
+        stash_entity: Entity,
+    },
+    StashWithdraw {
+        stash_entity: Entity,
+    },
+    StashDepositItem {
+        item: Item,
+        stash_entity: Entity,
+        inventory_index: usize,
+    },
+    StashWithdrawItem {
+        item: Item,
+        stash_entity: Entity,
+        stash_index: usize,
+    },
+    HireMercenary {
+        mercenary_entity: Entity,
+        price: u32,
+    },
+    GiveMercenaryGun {
+        mercenary_entity: Entity,
+    },
+    GiveMercenaryGunItem {
+        item: Item,
+        mercenary_entity: Entity,
+        inventory_index: usize,
+    },
+    SwapWithMercenary {
+        mercenary_entity: Entity,
+    },
+    ChoosePerk(Perk),
+    /// One of the actions offered by the script hooks, by its index in `script_menu_actions`
+    ScriptAction(usize),
+}
+
+/// The predicted path of a shot from the player's equipped weapon or of a thrown item, for
+/// previewing while aiming. Only things the player can see are taken into account.
+#[derive(Debug, Clone, Default)]
+pub struct ShotTrace {
+    /// The cells the shot passes through, ending where it stops
+    pub path: Vec<Coord>,
+    /// The cell where the shot stops, if it hits something before running out of range, or
+    /// where a thrown item lands
+    pub impact: Option<Coord>,
+    /// Cells on the line from the player to the target which would stop the shot
+    pub obstructions: Vec<Coord>,
+    /// Cells which shotgun pellets may pass through before reaching the impact point
+    pub spread: Vec<Coord>,
 }
 
 #[derive(Debug, Clone)]
@@ -205,15 +510,115 @@ pub struct Menu {
     pub image: Option<MenuImage>,
 }
 
+/// A bonus chosen after winning which applies to every later run
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum NewGamePlusBonus {
+    StartingMoney,
+    /// Start with this organ from the winning run installed
+    RetainedOrgan(Organ),
+    /// Enemies are tougher, for players who want more of a challenge
+    HarderEnemies,
+}
+
+/// Some of what a dead character was carrying, left where they died for the next character to
+/// play the same seed to find
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Remnant {
+    pub level_index: usize,
+    pub coord: Coord,
+    pub items: Vec<Item>,
+}
+
+/// The most valuable items carried by a dead character are left in their remnant
+const REMNANT_NUM_ITEMS: usize = 4;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Victory {}
+pub struct Victory {
+    /// The bonus chosen on the victory screen, or `None` if the player skipped it
+    pub bonus: Option<NewGamePlusBonus>,
+}
 
-#[derive(Debug, Clone, Copy)]
+const NEW_GAME_PLUS_STARTING_MONEY: u32 = 100;
+const NEW_GAME_PLUS_ENEMY_HEALTH_PERCENT: u32 = 50;
+
+/// The combined bonuses from all of the player's past victories
+#[derive(Debug, Clone, Default)]
+pub struct NewGamePlus {
+    pub starting_money: u32,
+    pub retained_organs: Vec<Organ>,
+    /// Added to the percentage by which enemy health is scaled
+    pub enemy_health_percent: u32,
+}
+
+impl NewGamePlus {
+    pub fn from_victories(victories: &[Victory]) -> Self {
+        let mut ret = Self::default();
+        for victory in victories {
+            match victory.bonus {
+                None => (),
+                Some(NewGamePlusBonus::StartingMoney) => {
+                    ret.starting_money += NEW_GAME_PLUS_STARTING_MONEY
+                }
+                Some(NewGamePlusBonus::RetainedOrgan(organ)) => ret.retained_organs.push(organ),
+                Some(NewGamePlusBonus::HarderEnemies) => {
+                    ret.enemy_health_percent += NEW_GAME_PLUS_ENEMY_HEALTH_PERCENT
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// The player's life before the game, chosen when starting a new game. Each background changes
+/// the starting kit and grants a perk which lasts the whole run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Stimpacks heal twice as much
+    StreetDoc,
+    /// Mercenaries charge half as much to be hired
+    #[default]
+    Mercenary,
+    /// Starts with a second, vampiric heart, and blood vials also heal
+    VampireInitiate,
+    /// Items sell for more
+    Scavenger,
+}
+
+impl Background {
+    pub const ALL: &'static [Self] = &[
+        Self::StreetDoc,
+        Self::Mercenary,
+        Self::VampireInitiate,
+        Self::Scavenger,
+    ];
+
+    pub fn starting_money(self) -> u32 {
+        match self {
+            Self::Scavenger => 40,
+            Self::StreetDoc => 20,
+            Self::Mercenary | Self::VampireInitiate => 0,
+        }
+    }
+}
+
+const STREET_DOC_STIMPACK_BONUS: u32 = 5;
+const VAMPIRE_INITIATE_BLOOD_VIAL_HEALTH: u32 = 4;
+const SCAVENGER_SELL_PRICE_PERCENT: u32 = 150;
+/// Shops can't tell what an unidentified item is any better than the player can, so they pay
+/// this for it whatever it looks like
+const UNIDENTIFIED_ITEM_SELL_PRICE: u32 = 2;
+
+/// Experience gained for reaching a level for the first time
+const NEW_LEVEL_EXPERIENCE: u32 = 15;
+/// Added to the player's maximum poison by the poison resistance perk
+const POISON_RESISTANCE_BONUS: u32 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum GameOverReason {
     YouDied,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum Win {
     Good,
     Bad,
@@ -224,8 +629,39 @@ pub enum GameControlFlow {
     GameOver(GameOverReason),
     Win(Win),
     Menu(Menu),
+    /// The player is choosing where to throw the item at this inventory index
+    Throw(usize),
+    /// The player is reading a document
+    Read(Document),
+}
+
+/// Something the player is currently working towards. The main objective comes first, followed
+/// by anything else going on which the player may want to deal with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    FindTheCorruptor,
+    KillTheCorruptor,
+    ReturnToTheEvacZone,
+    RestorePower { needs_battery: bool },
+    TradeWithTheMerchant,
+    SurviveTheBountyHunter,
+    Tutorial(TutorialStep),
+}
+
+/// Facts about a run, for summarising it once it's over
+#[derive(Clone, Copy, Debug)]
+pub struct RunStats {
+    pub turns: u64,
+    pub level_reached: usize,
+    pub money: u32,
+    pub npcs_killed: usize,
+    /// The player ended the run with only the organs they started with
+    pub all_original_organs: bool,
+    /// The player ended the run without any cybernetic organs
+    pub no_cybernetics: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct PlayerStats {
     pub health: Meter,
     pub oxygen: Meter,
@@ -234,17 +670,28 @@ pub struct PlayerStats {
     pub radiation: Meter,
     pub power: Option<Meter>,
     pub satiation: Option<Meter>,
+    pub temperature: Meter,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum Input {
     Walk(CardinalDirection),
     Wait,
     FireEquipped(Coord),
     FireBody(Coord),
+    Throw {
+        inventory_index: usize,
+        target: Coord,
+    },
     Get,
     Unequip,
     Reload,
+    ToggleDroneControl,
+    /// Skip the turn, but shoot the first hostile npc which moves within the quarter circle
+    /// facing the given direction before the player's next turn
+    Overwatch(CardinalDirection),
+    /// While sprinting the player moves two cells per turn, at the cost of oxygen and noise
+    ToggleSprint,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -254,6 +701,21 @@ pub struct VisibleEntity {
     pub health: Option<Meter>,
 }
 
+/// A single cell of the minimap, summarizing what the player knows about a block of the level.
+/// Variants are in increasing order of importance, so when a block contains several things the
+/// greatest one is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum MinimapCell {
+    #[default]
+    Unexplored,
+    Floor,
+    Water,
+    Wall,
+    Stairs,
+    Shop,
+    Player,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VisibleCellData {
     pub tiles: LayerTable<VisibleEntity>,
@@ -289,6 +751,39 @@ impl VisibleCellData {
                 .unwrap_or_default()
         });
     }
+
+    fn hallucinate(&mut self, npc_type: NpcType) {
+        // The health of a real npc seen as a monster is left alone so nothing gives it away
+        self.tiles.character.tile = Some(npc_type.tile());
+        self.tiles.character.colour_hint = None;
+    }
+
+    // Like `update` but only records the floor and structural features (walls, water, doors and
+    // stairs), so none of the cell's contents are revealed
+    fn update_layout(&mut self, world: &World, coord: Coord) {
+        self.update(world, coord);
+        self.tiles.character = Default::default();
+        self.tiles.item = Default::default();
+        let is_structural = matches!(
+            self.tiles.feature.tile,
+            Some(
+                Tile::Wall
+                    | Tile::Window
+                    | Tile::Water
+                    | Tile::DoorClosed
+                    | Tile::DoorOpen
+                    | Tile::DoorBarricaded
+                    | Tile::StairsDown
+                    | Tile::StairsUp
+                    | Tile::Exit
+                    | Tile::Generator
+                    | Tile::Prop(Prop::Graffiti)
+            )
+        );
+        if !is_structural {
+            self.tiles.feature = Default::default();
+        }
+    }
 }
 
 impl VisibleWorld for World {
@@ -304,6 +799,9 @@ impl VisibleWorld for World {
 
     fn for_each_light_by_coord<F: FnMut(Coord, &Light<Self::VisionDistance>)>(&self, mut f: F) {
         for (entity, light) in self.components.light.iter() {
+            if !self.powered && self.components.needs_power.contains(entity) {
+                continue;
+            }
             if let Some(coord) = self.spatial_table.coord_of(entity) {
                 f(coord, light);
             }
@@ -333,97 +831,594 @@ pub enum ActionError {
     FoodIsFull,
     RefusingToTargetSelf,
     NoBodyGuns,
+    NoDrone,
+    DroneAlreadyDeployed,
+    NothingToScan,
+    NoDoorToBarricade,
+    GeneratorNeedsBattery,
+    AnestheticNeedsSurgery,
+    NothingToOperateOn,
+    ImmunosuppressantIsFull,
+    NotBleeding,
+    NotPsychotic,
+    NotThrowable,
+}
+
+/// An enemy which only the psychotic player can see
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct Hallucination {
+    coord: Coord,
+    npc_type: NpcType,
+    turns: u32,
+}
+
+enum FieldSurgery {
+    Install { container_inventory_index: usize },
+    Remove { index: usize },
+}
+
+/// Where the player appears when they arrive on a level
+enum Arrival {
+    /// At the stairs leading back the way they came
+    Stairs,
+    /// Next to the level's clinic, after being cloned
+    Clinic,
 }
 
-#[derive(Serialize, Deserialize)]
 struct Level {
     world: World,
     visibility_grid: VisibilityGrid<VisibleCellData>,
     agents: ComponentTable<Agent>,
 }
 
+/// A level other than the current one, whose world is stored as the differences between it and
+/// the world generated from the level's seed
+#[derive(Serialize, Deserialize)]
+struct StoredLevel {
+    world: WorldDelta,
+    visibility_grid: VisibilityGrid<VisibleCellData>,
+    agents: ComponentTable<Agent>,
+    /// The turn on which the player left the level
+    left_at_turn: u64,
+}
+
+impl Level {
+    // Each level has its own rng so the level generated from a seed doesn't depend on when it's
+    // generated
+    fn generate(level_index: usize, rng_seed: u64, balance: &Balance) -> Self {
+        let mut rng = Isaac64Rng::seed_from_u64(rng_seed);
+        let Terrain { world } = Terrain::generate(level_index, balance, &mut rng);
+        let visibility_grid = VisibilityGrid::new(world.spatial_table.grid_size());
+        Self {
+            world,
+            visibility_grid,
+            agents: Default::default(),
+        }
+    }
+
+    fn store(
+        self,
+        level_index: usize,
+        rng_seed: u64,
+        balance: &Balance,
+        left_at_turn: u64,
+    ) -> StoredLevel {
+        let base = Level::generate(level_index, rng_seed, balance);
+        StoredLevel {
+            world: self.world.into_delta(&base.world),
+            visibility_grid: self.visibility_grid,
+            agents: self.agents,
+            left_at_turn,
+        }
+    }
+
+    /// Levels don't change while the player is elsewhere, so when the player returns the level is
+    /// caught up on what would have happened in the meantime. Nothing that happens is reported to
+    /// the player. Shops are restocked from the loot tables of `deepest_level_index`.
+    fn catch_up<R: Rng>(&mut self, turns: u64, deepest_level_index: usize, rng: &mut R) {
+        let mut external_events = Vec::new();
+        let mut message_log = Vec::new();
+        for _ in 0..turns.min(MAX_CATCH_UP_TURNS) {
+            self.world.wander_npcs(rng);
+            self.world.handle_resurrection();
+            self.world.handle_spoilage(&mut message_log);
+            self.world.handle_scent();
+            self.world.grow_corruption(rng);
+            self.world
+                .handle_corruption(rng, &mut external_events, &mut message_log);
+            let dead = self
+                .world
+                .components
+                .to_remove
+                .entities()
+                .collect::<Vec<_>>();
+            for entity in dead {
+                self.world.remove_entity(entity);
+                self.agents.remove(entity);
+            }
+        }
+        // Shops stock items based on how deep the player has been so that shops on earlier levels
+        // remain useful when returning to them.
+        if turns >= SHOP_RESTOCK_TURNS {
+            self.world.restock_shops(deepest_level_index, rng);
+        }
+    }
+}
+
+impl StoredLevel {
+    fn restore(self, level_index: usize, rng_seed: u64, balance: &Balance) -> Level {
+        let Level { mut world, .. } = Level::generate(level_index, rng_seed, balance);
+        world.apply_delta(self.world);
+        Level {
+            world,
+            visibility_grid: self.visibility_grid,
+            agents: self.agents,
+        }
+    }
+}
+
+/// An npc which isn't part of any one level. While it's on the current level it's an entity in
+/// the world, and while it's elsewhere it's kept here rather than with the level it's on.
+#[derive(Serialize, Deserialize, Default)]
+struct CrossLevelNpc {
+    entity: Option<Entity>,
+    /// The npc and its inventory while it's not on the current level. This is `None` until the
+    /// npc first appears on the current level.
+    stored: Option<(EntityData, Vec<EntityData>)>,
+}
+
+impl CrossLevelNpc {
+    /// The npc's coord if it's alive on the current level
+    fn coord(&self, world: &World) -> Option<Coord> {
+        let entity = self.entity?;
+        if !world.components.npc.contains(entity)
+            || world.components.corpse.contains(entity)
+            || world.components.to_remove.contains(entity)
+        {
+            return None;
+        }
+        world.entity_coord(entity)
+    }
+
+    fn is_dead(&self, world: &World) -> bool {
+        self.entity.is_some() && self.coord(world).is_none()
+    }
+
+    fn leave_world(&mut self, world: &mut World) {
+        if let Some(entity) = self.entity.take() {
+            self.stored = Some(world.remove_character_with_inventory(entity));
+        }
+    }
+
+    /// Put the npc on the current level as close to `coord` as possible, spawning it with `spawn`
+    /// if this is its first appearance
+    fn enter_world<F: FnOnce(&mut World, Coord) -> Entity>(
+        &mut self,
+        world: &mut World,
+        coord: Coord,
+        spawn: F,
+    ) {
+        let coord = match world.nearest_characterless_coord(coord) {
+            Some(coord) => coord,
+            None => return,
+        };
+        let entity = match self.stored.take() {
+            Some((entity_data, inventory_data)) => {
+                world.insert_character_with_inventory(coord, entity_data, inventory_data)
+            }
+            None => spawn(world, coord),
+        };
+        self.entity = Some(entity);
+    }
+}
+
+/// A merchant who travels between levels on a schedule, whether or not the player is around
+#[derive(Serialize, Deserialize)]
+struct TravellingMerchant {
+    level_index: usize,
+    /// The level the merchant moves to next
+    next_level_index: usize,
+    /// The turn on which the merchant heads for the stairs to the next level
+    leaves_at_turn: u64,
+    npc: CrossLevelNpc,
+}
+
+impl TravellingMerchant {
+    fn new<R: Rng>(level_index: usize, turn_count: u64, rng: &mut R) -> Self {
+        Self {
+            level_index,
+            next_level_index: Self::choose_next_level_index(level_index, rng),
+            leaves_at_turn: turn_count + MERCHANT_STAY_TURNS,
+            npc: CrossLevelNpc::default(),
+        }
+    }
+
+    fn choose_next_level_index<R: Rng>(level_index: usize, rng: &mut R) -> usize {
+        if level_index == 0 || (level_index < NUM_LEVELS - 1 && rng.gen()) {
+            level_index + 1
+        } else {
+            level_index - 1
+        }
+    }
+
+    /// The stairs the merchant takes to reach its next level
+    fn exit_coord(&self, world: &World) -> Option<Coord> {
+        if self.next_level_index > self.level_index {
+            world.stairs_down_coord()
+        } else {
+            world.stairs_up_or_exit_coord()
+        }
+    }
+
+    // A merchant which has been provoked stays to fight
+    fn is_leaving(&self, entity: Entity, world: &World, turn_count: u64) -> bool {
+        self.npc.entity == Some(entity)
+            && turn_count >= self.leaves_at_turn
+            && !world
+                .components
+                .npc
+                .get(entity)
+                .is_some_and(|npc| matches!(npc.disposition, Disposition::Hostile))
+    }
+
+    fn move_to_next_level<R: Rng>(&mut self, turn_count: u64, rng: &mut R) {
+        let level_index = self.next_level_index;
+        self.level_index = level_index;
+        self.next_level_index = Self::choose_next_level_index(level_index, rng);
+        self.leaves_at_turn = turn_count + MERCHANT_STAY_TURNS;
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+enum MerchantState {
+    #[default]
+    NotYetArrived,
+    Travelling(Box<TravellingMerchant>),
+    Dead,
+}
+
+/// Hunts the player from level to level once there's a price on their head
+#[derive(Serialize, Deserialize)]
+struct BountyHunter {
+    npc: CrossLevelNpc,
+    /// The turn on which the hunter arrives on the current level if it isn't already here
+    arrives_at_turn: u64,
+    /// Whether the hunter arrives by the stairs up, having followed the player down, rather than
+    /// by the stairs down
+    arrives_from_above: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+enum BountyHunterState {
+    #[default]
+    NotHunting,
+    Hunting(Box<BountyHunter>),
+    Dead,
+}
+
+/// Levels are generated the first time they are entered rather than all at the start of the
+/// game. Where threads are available, the level below the deepest one visited is generated in the
+/// background so it's usually ready by the time the player descends.
+#[derive(Default)]
+struct LevelGenerator {
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Option<(usize, std::thread::JoinHandle<Level>)>,
+}
+
+impl LevelGenerator {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start(&mut self, level_index: usize, rng_seed: u64, balance: &Balance) {
+        let balance = balance.clone();
+        let handle = std::thread::spawn(move || Level::generate(level_index, rng_seed, &balance));
+        self.pending = Some((level_index, handle));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start(&mut self, _level_index: usize, _rng_seed: u64, _balance: &Balance) {}
+
+    /// Returns the level, waiting for it to finish being generated in the background if it was
+    /// started, and otherwise generating it now
+    fn take(&mut self, level_index: usize, rng_seed: u64, balance: &Balance) -> Level {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((pending_index, handle)) = self.pending.take() {
+            if pending_index == level_index {
+                return match handle.join() {
+                    Ok(level) => level,
+                    Err(e) => std::panic::resume_unwind(e),
+                };
+            }
+        }
+        Level::generate(level_index, rng_seed, balance)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Game {
     current_level_index: usize,
-    other_levels: Vec<Option<Level>>,
+    deepest_level_index: usize,
+    /// Levels other than the current one, or `None` for levels which haven't been generated yet
+    other_levels: Vec<Option<StoredLevel>>,
+    level_rng_seeds: Vec<u64>,
+    #[serde(skip)]
+    level_generator: LevelGenerator,
     world: World,
     visibility_grid: VisibilityGrid<VisibleCellData>,
+    drone_visibility_grid: VisibilityGrid<VisibleCellData>,
     agents: ComponentTable<Agent>,
     rng: Isaac64Rng,
     animation_rng: Isaac64Rng,
     player_entity: Entity,
+    drone_entity: Option<Entity>,
+    controlling_drone: bool,
+    /// The direction the player is watching while in the overwatch stance
+    overwatch: Option<CardinalDirection>,
+    sprinting: bool,
+    player_activity: Activity,
+    identified_items: HashSet<Item>,
+    radiation_overlay: bool,
     message_log: Vec<Message>,
     ai_context: AiContext,
     animation_context: AnimationContext,
     omniscient: bool,
+    /// Demo games are played by a bot, so don't stop to offer choices only a person would make
+    demo: bool,
     external_events: Vec<ExternalEvent>,
     turn_count: u64,
+    merchant: MerchantState,
+    bounty_hunter: BountyHunterState,
+    /// Every document the player has read, in the order they first read them
+    documents_read: Vec<Document>,
+    organ_market: OrganMarket,
+    /// Left by a previous character who died playing the same seed
+    inherited_remnant: Option<Remnant>,
+    /// Left by this character when they died
+    remnant: Option<Remnant>,
     game_over: bool,
     boss_dead: bool,
+    boss_sighted: bool,
+    background: Background,
+    /// The victories which gave this game its New Game Plus bonuses
+    victories: Vec<Victory>,
+    /// Every input and menu choice the player has made, so the game can be replayed
+    recorded_steps: Vec<headless::Step>,
+    hallucinations: Vec<Hallucination>,
+    /// Npcs which the psychotic player sees as monsters this turn
+    hostile_flashes: Vec<(Entity, NpcType)>,
+    balance: Balance,
+    #[serde(skip)]
+    script_hooks: Option<Box<dyn ScriptHooks>>,
+    script_message_cursor: usize,
+    tutorial: Option<tutorial::Tutorial>,
+    #[serde(skip)]
+    recent_inputs: VecDeque<String>,
+    // Reused each turn to avoid allocating while npcs take their turns
+    #[serde(skip)]
+    agent_entities: Vec<Entity>,
+    #[serde(skip)]
+    entities_to_remove: Vec<Entity>,
 }
 
 pub const NUM_LEVELS: usize = 4;
+/// Catching up a level the player has been away from for a long time is cut short at this many
+/// turns to keep changing levels quick
+const MAX_CATCH_UP_TURNS: u64 = 500;
+/// The turn on which the travelling merchant first appears somewhere in the city
+const MERCHANT_FIRST_APPEARANCE_TURN: u64 = 300;
+/// How long the travelling merchant stays on each level before moving on
+const MERCHANT_STAY_TURNS: u64 = 200;
+/// A merchant who can't reach the stairs in this many turns leaves the level anyway
+const MERCHANT_MAX_WALK_TURNS: u64 = 50;
+/// Killing this many vendors puts a bounty on the player. Killing the boss always does.
+const BOUNTY_HUNTER_VENDOR_KILLS: usize = 2;
+/// How long after the bounty is posted the hunter arrives
+const BOUNTY_HUNTER_DELAY_TURNS: u64 = 30;
+/// How far behind the player the hunter is when it follows them to another level
+const BOUNTY_HUNTER_FOLLOW_TURNS: u64 = 15;
+/// Shops on a level are only restocked if the player has been away for at least this long
+const SHOP_RESTOCK_TURNS: u64 = 100;
 
 impl Game {
-    pub fn new<R: Rng>(config: &Config, _victories: Vec<Victory>, base_rng: &mut R) -> Self {
-        let mut rng = Isaac64Rng::seed_from_u64(base_rng.gen());
-        let animation_rng = Isaac64Rng::seed_from_u64(base_rng.gen());
-        let mut other_levels = (0..NUM_LEVELS)
-            .map(|i| {
-                let Terrain { world } = Terrain::generate(i, &mut rng);
-                let visibility_grid = VisibilityGrid::new(world.spatial_table.grid_size());
-                Some(Level {
+    pub fn new<R: Rng>(
+        config: &Config,
+        background: Background,
+        victories: Vec<Victory>,
+        base_rng: &mut R,
+    ) -> Self {
+        Self::new_with_first_level(
+            config,
+            background,
+            victories,
+            base_rng,
+            |balance, level_rng_seeds| {
+                let level = Level::generate(0, level_rng_seeds[0], balance);
+                let player_spawn = level.world.stairs_up_or_exit_coord().unwrap();
+                (level, player_spawn, None)
+            },
+        )
+    }
+
+    /// Start a game on the tutorial level rather than the first level of the normal game
+    pub fn new_tutorial<R: Rng>(config: &Config, base_rng: &mut R) -> Self {
+        let mut game = Self::new_with_first_level(
+            config,
+            Background::default(),
+            Vec::new(),
+            base_rng,
+            |_balance, level_rng_seeds| {
+                let mut rng = Isaac64Rng::seed_from_u64(level_rng_seeds[0]);
+                let tutorial::TutorialLevel {
+                    world,
+                    player_spawn,
+                    tutorial,
+                } = tutorial::generate(&mut rng);
+                let level = Level {
+                    visibility_grid: VisibilityGrid::new(world.spatial_table.grid_size()),
                     world,
-                    visibility_grid,
                     agents: Default::default(),
-                })
-            })
-            .collect::<Vec<_>>();
+                };
+                (level, player_spawn, Some(tutorial))
+            },
+        );
+        game.start_tutorial();
+        game
+    }
+
+    fn new_with_first_level<R: Rng, F>(
+        config: &Config,
+        background: Background,
+        victories: Vec<Victory>,
+        base_rng: &mut R,
+        first_level: F,
+    ) -> Self
+    where
+        F: FnOnce(&Balance, &[u64]) -> (Level, Coord, Option<tutorial::Tutorial>),
+    {
+        let new_game_plus = NewGamePlus::from_victories(&victories);
+        let mut balance = config.balance.clone();
+        balance.enemy_health_percent += new_game_plus.enemy_health_percent;
+        let mut rng = Isaac64Rng::seed_from_u64(base_rng.gen());
+        let animation_rng = Isaac64Rng::seed_from_u64(base_rng.gen());
+        let level_rng_seeds = (0..NUM_LEVELS).map(|_| rng.gen()).collect::<Vec<u64>>();
         let current_level_index = 0;
-        let Level {
-            mut world,
-            visibility_grid,
-            agents,
-        } = other_levels[current_level_index].take().unwrap();
-        let player_spawn = world.stairs_up_or_exit_coord().unwrap();
-        let player_data = world::spawn::make_player();
+        let (
+            Level {
+                mut world,
+                visibility_grid,
+                agents,
+            },
+            player_spawn,
+            tutorial,
+        ) = first_level(&balance, &level_rng_seeds);
+        let player_data = world::spawn::make_player(&balance.player, background, &new_game_plus);
         let player_location = Location {
             coord: player_spawn,
             layer: Some(Layer::Character),
         };
         let player_entity = world.insert_entity_data(player_location, player_data);
-        world.add_player_initial_items();
+        world.add_player_initial_items(background);
         let mut game = Self {
             ai_context: AiContext::new(world.size()),
             current_level_index,
-            other_levels,
+            deepest_level_index: current_level_index,
+            other_levels: (0..NUM_LEVELS).map(|_| None).collect(),
+            level_rng_seeds,
+            level_generator: Default::default(),
+            drone_visibility_grid: VisibilityGrid::new(world.size()),
             world,
             visibility_grid,
             agents,
             rng,
             animation_rng,
             player_entity,
+            drone_entity: None,
+            controlling_drone: false,
+            overwatch: None,
+            sprinting: false,
+            player_activity: Activity::Moving,
+            identified_items: HashSet::new(),
+            radiation_overlay: false,
             message_log: Vec::new(),
             animation_context: Default::default(),
             omniscient: config.omniscient.is_some(),
+            demo: config.demo,
             external_events: Default::default(),
             turn_count: 0,
+            merchant: MerchantState::NotYetArrived,
+            bounty_hunter: BountyHunterState::NotHunting,
+            documents_read: Vec::new(),
+            organ_market: OrganMarket::default(),
+            inherited_remnant: None,
+            remnant: None,
             game_over: false,
             boss_dead: false,
+            boss_sighted: false,
+            background,
+            victories,
+            recorded_steps: Vec::new(),
+            hallucinations: Vec::new(),
+            hostile_flashes: Vec::new(),
+            balance,
+            script_hooks: None,
+            script_message_cursor: 0,
+            tutorial,
+            recent_inputs: VecDeque::new(),
+            agent_entities: Vec::new(),
+            entities_to_remove: Vec::new(),
         };
+        // the player knows what the items they start with are
+        for i in 0..game.inventory_size() {
+            if let Some(item) = game.inventory_item(i) {
+                game.identified_items.insert(item);
+            }
+        }
         game.systems();
         game.update_visibility();
+        // the tutorial is a single level so there's nothing to generate in the background
+        if game.tutorial.is_none() {
+            game.start_generating_next_level();
+        }
         game
     }
 
+    fn start_generating_next_level(&mut self) {
+        let level_index = self.deepest_level_index + 1;
+        if level_index < NUM_LEVELS {
+            self.level_generator.start(
+                level_index,
+                self.level_rng_seeds[level_index],
+                &self.balance,
+            );
+        }
+    }
+
     pub fn enter_level(&mut self, level_index: usize) {
-        use std::mem;
         assert!(
             level_index == self.current_level_index + 1
                 || level_index == self.current_level_index - 1
         );
-        let down = level_index == self.current_level_index + 1;
-        let mut level = self.other_levels[level_index].take().unwrap();
+        self.move_player_to_level(level_index, Arrival::Stairs);
+    }
+
+    fn move_player_to_level(&mut self, level_index: usize, arrival: Arrival) {
+        use std::mem;
+        let down = level_index > self.current_level_index;
+        let mut level = match self.other_levels[level_index].take() {
+            Some(stored_level) => {
+                let turns_away = self.turn_count.saturating_sub(stored_level.left_at_turn);
+                let mut level = stored_level.restore(
+                    level_index,
+                    self.level_rng_seeds[level_index],
+                    &self.balance,
+                );
+                level.catch_up(turns_away, self.deepest_level_index, &mut self.rng);
+                level
+            }
+            None => {
+                let mut level = self.level_generator.take(
+                    level_index,
+                    self.level_rng_seeds[level_index],
+                    &self.balance,
+                );
+                if let Some(remnant) = self.inherited_remnant.as_ref() {
+                    if remnant.level_index == level_index {
+                        level.world.spawn_remnant(remnant);
+                    }
+                }
+                level
+            }
+        };
+        if self.drone_entity.is_some() {
+            // The drone can't follow the player between levels so it's recalled automatically
+            if self.recall_drone().is_err() {
+                if let Some(drone_entity) = self.drone_entity.take() {
+                    self.world.remove_entity(drone_entity);
+                }
+                self.controlling_drone = false;
+                self.message_log.push(Message::DroneLost);
+            }
+        }
+        // vendors forget about thefts once the player leaves the level
+        self.world.components.stolen_from.clear();
         {
             let mut inventory = self
                 .world
@@ -479,16 +1474,63 @@ impl Game {
                 .hands
                 .insert(self.player_entity, hands);
         }
+        if let Some(mut worn) = self.world.components.worn.get(self.player_entity).cloned() {
+            for slot in [WearSlot::Head, WearSlot::Body] {
+                if let Some(worn_entity) = worn.slot_mut(slot) {
+                    let data = self.world.components.remove_entity_data(*worn_entity);
+                    let new_item_entity = level.world.entity_allocator.alloc();
+                    level
+                        .world
+                        .components
+                        .insert_entity_data(new_item_entity, data);
+                    *worn_entity = new_item_entity;
+                }
+            }
+            self.world.components.worn.insert(self.player_entity, worn);
+        }
+        let followers = self
+            .world
+            .player_followers()
+            .into_iter()
+            .map(|follower| self.world.remove_character_with_inventory(follower))
+            .collect::<Vec<_>>();
+        // The travelling merchant and bounty hunter aren't stored with the level they're on
+        if let MerchantState::Travelling(merchant) = &mut self.merchant {
+            merchant.npc.leave_world(&mut self.world);
+        }
+        if let BountyHunterState::Hunting(hunter) = &mut self.bounty_hunter {
+            if hunter.npc.entity.is_some() {
+                hunter.npc.leave_world(&mut self.world);
+                hunter.arrives_at_turn = self.turn_count + BOUNTY_HUNTER_FOLLOW_TURNS;
+            }
+            hunter.arrives_from_above = down;
+        }
         let player_data = self.world.remove_entity(self.player_entity);
         mem::swap(&mut self.world, &mut level.world);
         mem::swap(&mut self.visibility_grid, &mut level.visibility_grid);
         mem::swap(&mut self.agents, &mut level.agents);
-        self.other_levels[self.current_level_index] = Some(level);
+        self.hallucinations.clear();
+        self.hostile_flashes.clear();
+        self.drone_visibility_grid = VisibilityGrid::new(self.world.size());
+        // levels aren't all the same size
+        self.ai_context = AiContext::new(self.world.size());
+        self.other_levels[self.current_level_index] = Some(level.store(
+            self.current_level_index,
+            self.level_rng_seeds[self.current_level_index],
+            &self.balance,
+            self.turn_count,
+        ));
         self.current_level_index = level_index;
-        let player_coord = if down {
-            self.world.stairs_up_or_exit_coord().unwrap()
-        } else {
-            self.world.stairs_down_coord().unwrap()
+        self.organ_market.drift(&mut self.rng);
+        let new_depth = level_index > self.deepest_level_index;
+        if new_depth {
+            self.deepest_level_index = level_index;
+            self.start_generating_next_level();
+        }
+        let player_coord = match arrival {
+            Arrival::Stairs if down => self.world.stairs_up_or_exit_coord().unwrap(),
+            Arrival::Stairs => self.world.stairs_down_coord().unwrap(),
+            Arrival::Clinic => self.clinic_arrival_coord(),
         };
         self.player_entity = self.world.insert_entity_data(
             Location {
@@ -497,17 +1539,188 @@ impl Game {
             },
             player_data,
         );
+        for (follower_data, inventory_data) in followers {
+            if let Some(coord) = self.world.nearest_characterless_coord(player_coord) {
+                self.world
+                    .insert_character_with_inventory(coord, follower_data, inventory_data);
+                self.message_log.push(Message::MercenaryFollows);
+            }
+        }
+        if let MerchantState::Travelling(mut merchant) = mem::take(&mut self.merchant) {
+            if merchant.level_index == level_index {
+                if let Some(coord) = self.world.random_characterless_coord(&mut self.rng) {
+                    self.place_merchant(&mut merchant, coord);
+                }
+            }
+            self.merchant = MerchantState::Travelling(merchant);
+        }
+        if new_depth {
+            self.world
+                .gain_player_experience(NEW_LEVEL_EXPERIENCE, &mut self.message_log);
+        }
         self.update_visibility();
         self.external_events.push(ExternalEvent::ChangeLevel);
     }
 
+    fn is_player_at_clinic(&self) -> bool {
+        let player_coord = self.player_coord();
+        self.world
+            .components
+            .organ_clinic
+            .entities()
+            .filter_map(|entity| self.world.spatial_table.coord_of(entity))
+            .any(|coord| coord.distance2(player_coord) <= 2)
+    }
+
+    // Next to the clinic if the level has one, otherwise at the start of the level
+    fn clinic_arrival_coord(&self) -> Coord {
+        self.world
+            .components
+            .organ_clinic
+            .entities()
+            .filter_map(|entity| self.world.spatial_table.coord_of(entity))
+            .find_map(|coord| self.world.nearest_characterless_coord(coord))
+            .or_else(|| self.world.stairs_up_or_exit_coord())
+            .unwrap()
+    }
+
+    /// An insured player's body is left where they died along with everything they were
+    /// carrying, and a clone with the organs they started the game with wakes up at the clinic on
+    /// the first level. Money, experience and skills are kept.
+    fn respawn_clone(&mut self) {
+        let death_coord = self.player_coord();
+        let old = self.world.components.remove_entity_data(self.player_entity);
+        let mut items = Vec::new();
+        if let Some(inventory) = &old.inventory {
+            items.extend(inventory.items().iter().flatten().cloned());
+        }
+        if let Some(hands) = &old.hands {
+            items.extend(hands.left.holding());
+            items.extend(hands.right.holding());
+        }
+        if let Some(worn) = &old.worn {
+            for slot in [WearSlot::Head, WearSlot::Body] {
+                items.extend(worn.slot(slot));
+            }
+        }
+        self.world.spawn_player_remains(death_coord, items);
+        let new_game_plus = NewGamePlus::from_victories(&self.victories);
+        let mut data =
+            world::spawn::make_player(&self.balance.player, self.background, &new_game_plus);
+        data.money = old.money;
+        data.experience = old.experience;
+        data.skills = old.skills;
+        self.world
+            .components
+            .insert_entity_data(self.player_entity, data);
+        self.controlling_drone = false;
+        self.overwatch = None;
+        self.hallucinations.clear();
+        self.external_events.push(ExternalEvent::Death(death_coord));
+        self.message_log.push(Message::CloneRespawn);
+        if self.current_level_index == 0 {
+            let coord = self.clinic_arrival_coord();
+            let _ = self.world.spatial_table.update(
+                self.player_entity,
+                Location {
+                    coord,
+                    layer: Some(Layer::Character),
+                },
+            );
+            self.update_visibility();
+        } else {
+            self.move_player_to_level(0, Arrival::Clinic);
+        }
+    }
+
+    /// Must be called before the game starts. The remnant appears when the player reaches the
+    /// level it was left on.
+    pub fn inherit_remnant(&mut self, remnant: Remnant) {
+        if remnant.level_index == self.current_level_index {
+            self.world.spawn_remnant(&remnant);
+            self.update_visibility();
+        }
+        self.inherited_remnant = Some(remnant);
+    }
+
+    /// What this character left behind when they died, if they have died
+    pub fn remnant(&self) -> Option<&Remnant> {
+        self.remnant.as_ref()
+    }
+
+    fn make_remnant(&self) -> Remnant {
+        let mut items = Vec::new();
+        if let Some(inventory) = self.world.components.inventory.get(self.player_entity) {
+            items.extend(inventory.items().iter().flatten().cloned());
+        }
+        if let Some(hands) = self.world.components.hands.get(self.player_entity) {
+            items.extend(hands.left.holding());
+            items.extend(hands.right.holding());
+        }
+        if let Some(worn) = self.world.components.worn.get(self.player_entity) {
+            for slot in [WearSlot::Head, WearSlot::Body] {
+                items.extend(worn.slot(slot));
+            }
+        }
+        let mut items = items
+            .into_iter()
+            .filter_map(|entity| self.world.components.item.get(entity).cloned())
+            .filter(|&item| self.item_price(item) > 0)
+            .collect::<Vec<_>>();
+        items.sort_by_key(|&item| std::cmp::Reverse(self.item_price(item)));
+        items.truncate(REMNANT_NUM_ITEMS);
+        Remnant {
+            level_index: self.current_level_index,
+            coord: self.player_coord(),
+            items,
+        }
+    }
+
     pub fn message_log(&self) -> &[Message] {
         &self.message_log
     }
 
+    pub fn current_objectives(&self) -> Vec<Objective> {
+        if let Some(step) = self.tutorial_step() {
+            return vec![Objective::Tutorial(step)];
+        }
+        let mut objectives = vec![if self.boss_dead {
+            Objective::ReturnToTheEvacZone
+        } else if self.boss_sighted {
+            Objective::KillTheCorruptor
+        } else {
+            Objective::FindTheCorruptor
+        }];
+        if !self.world.powered {
+            if let Some(generator_entity) = self.world.components.generator.entities().next() {
+                objectives.push(Objective::RestorePower {
+                    needs_battery: self.world.components.out_of_fuel.contains(generator_entity),
+                });
+            }
+        }
+        if let MerchantState::Travelling(merchant) = &self.merchant {
+            if merchant.npc.coord(&self.world).is_some() {
+                objectives.push(Objective::TradeWithTheMerchant);
+            }
+        }
+        if let BountyHunterState::Hunting(_) = self.bounty_hunter {
+            objectives.push(Objective::SurviveTheBountyHunter);
+        }
+        objectives
+    }
+
+    /// Documents the player has read, so they can be read again at any time
+    pub fn documents_read(&self) -> &[Document] {
+        &self.documents_read
+    }
+
     pub fn update_visibility(&mut self) {
+        let hallucinated = self.hallucinated_npcs();
         let update_fn = |data: &mut VisibleCellData, coord| {
             data.update(&self.world, coord);
+            if let Some(&(_, npc_type)) = hallucinated.iter().find(|&&(c, _)| c == coord) {
+                data.hallucinate(npc_type);
+            }
         };
         if self.omniscient {
             self.visibility_grid.update_omniscient_custom(
@@ -516,6 +1729,30 @@ impl Game {
                 update_fn,
             );
         } else {
+            // A visibility grid only supports a single eye, so what the drone sees is recorded in
+            // the player's memory before the player looks around, and the drone's current view is
+            // kept in a separate grid which is combined with the player's in
+            // `cell_visibility_at_coord`.
+            if let Some(drone_coord) = self.drone_coord() {
+                let distance = Circle::new_squared(world::spawn::DRONE_VISION_DISTANCE_SQUARED);
+                let update_drone_fn = |data: &mut VisibleCellData, coord| {
+                    data.update(&self.world, coord);
+                };
+                self.visibility_grid.update_custom(
+                    Rgb24::new_grey(0),
+                    &self.world,
+                    distance,
+                    drone_coord,
+                    update_drone_fn,
+                );
+                self.drone_visibility_grid.update_custom(
+                    Rgb24::new_grey(0),
+                    &self.world,
+                    distance,
+                    drone_coord,
+                    update_drone_fn,
+                );
+            }
             let distance = Circle::new_squared(200);
             self.visibility_grid.update_custom(
                 Rgb24::new_grey(0),
@@ -525,10 +1762,557 @@ impl Game {
                 update_fn,
             );
         }
+        if !self.boss_sighted && !self.boss_dead {
+            let boss_visible = self.world.components.boss.entities().any(|entity| {
+                self.world
+                    .spatial_table
+                    .coord_of(entity)
+                    .is_some_and(|coord| {
+                        matches!(
+                            self.cell_visibility_at_coord(coord),
+                            CellVisibility::Current { .. }
+                        )
+                    })
+            });
+            if boss_visible {
+                self.boss_sighted = true;
+                self.external_events.push(ExternalEvent::BossSighted);
+            }
+        }
     }
 
     pub fn cell_visibility_at_coord(&self, coord: Coord) -> CellVisibility<&VisibleCellData> {
-        self.visibility_grid.get_visibility(coord)
+        let player_visibility = self.visibility_grid.get_visibility(coord);
+        if let CellVisibility::Current { .. } = player_visibility {
+            return player_visibility;
+        }
+        if self.drone_entity.is_some() {
+            let drone_visibility = self.drone_visibility_grid.get_visibility(coord);
+            if let CellVisibility::Current { .. } = drone_visibility {
+                return drone_visibility;
+            }
+        }
+        player_visibility
+    }
+
+    /// Whether a currently-visible cell contains poison gas
+    pub fn poison_gas_at(&self, coord: Coord) -> bool {
+        matches!(
+            self.cell_visibility_at_coord(coord),
+            CellVisibility::Current { .. }
+        ) && self.world.is_poison_gas_at(coord)
+    }
+
+    /// Whether a currently-visible cell is filled with smoke
+    pub fn smoke_at(&self, coord: Coord) -> bool {
+        matches!(
+            self.cell_visibility_at_coord(coord),
+            CellVisibility::Current { .. }
+        ) && self.world.is_smoke_at(coord)
+    }
+
+    /// The radiation intensity to display over a cell, if the radiation overlay is enabled. Only
+    /// cells which are currently visible can be measured, and the player must be carrying a
+    /// geiger counter.
+    pub fn radiation_overlay_intensity(&self, coord: Coord) -> Option<u32> {
+        if !self.radiation_overlay
+            || self
+                .world
+                .player_inventory_item_index(Item::GeigerCounter)
+                .is_none()
+        {
+            return None;
+        }
+        if let CellVisibility::Current { .. } = self.cell_visibility_at_coord(coord) {
+            Some(self.world.radiation_intensity_at(coord))
+        } else {
+            None
+        }
+    }
+
+    /// Summarizes the explored parts of the current level at a reduced scale. Each cell of the
+    /// returned grid covers a block of `scale` cells of the level.
+    pub fn minimap_cells(&self, scale: Size) -> Grid<MinimapCell> {
+        let world_size = self.world_size();
+        let size = Size::new(
+            world_size.width().div_ceil(scale.width()),
+            world_size.height().div_ceil(scale.height()),
+        );
+        let mut grid: Grid<MinimapCell> = Grid::new_default(size);
+        let scale_coord = scale.to_coord().unwrap();
+        for coord in world_size.coord_iter_row_major() {
+            let data = match self.cell_visibility_at_coord(coord).into_data() {
+                Some(data) => data,
+                None => continue,
+            };
+            let mut cell = MinimapCell::Floor;
+            if let Some(tile) = data.tiles.feature.tile {
+                match tile {
+                    Tile::Wall | Tile::Window | Tile::Prop(Prop::Graffiti) => {
+                        cell = MinimapCell::Wall
+                    }
+                    Tile::Water => cell = MinimapCell::Water,
+                    Tile::StairsDown | Tile::StairsUp | Tile::Exit => cell = MinimapCell::Stairs,
+                    _ => (),
+                }
+            }
+            if let Some(
+                Tile::GunStore
+                | Tile::ItemStore
+                | Tile::OrganTrader
+                | Tile::OrganClinic
+                | Tile::Merchant,
+            ) = data.tiles.character.tile
+            {
+                cell = MinimapCell::Shop;
+            }
+            let minimap_coord = Coord::new(coord.x / scale_coord.x, coord.y / scale_coord.y);
+            let minimap_cell = grid.get_checked_mut(minimap_coord);
+            *minimap_cell = (*minimap_cell).max(cell);
+        }
+        let player_coord = self.player_coord();
+        *grid.get_checked_mut(Coord::new(
+            player_coord.x / scale_coord.x,
+            player_coord.y / scale_coord.y,
+        )) = MinimapCell::Player;
+        grid
+    }
+
+    /// Finds the shortest path from the player to `target` through cells the player has seen,
+    /// avoiding remembered obstacles and any characters currently in view. Closed doors are
+    /// considered passable since walking into them opens them. Hazardous floors are only walked
+    /// onto if they are the target. Returns the sequence of directions to walk, or `None` if
+    /// there is no known route.
+    pub fn path_to(&self, target: Coord) -> Option<Vec<CardinalDirection>> {
+        use std::collections::VecDeque;
+        let size = self.world_size();
+        if !target.is_valid(size) {
+            return None;
+        }
+        let start = self.player_coord();
+        let can_climb = self.world.can_player_climb();
+        let is_passable = |coord: Coord| {
+            let data = match self.cell_visibility_at_coord(coord) {
+                CellVisibility::Current { data, .. } => {
+                    if data.tiles.character.tile.is_some() {
+                        return false;
+                    }
+                    data
+                }
+                CellVisibility::Previous(data) => data,
+                CellVisibility::Never => return false,
+            };
+            let feature = data.tiles.feature.tile;
+            if can_climb
+                && matches!(
+                    feature,
+                    Some(Tile::Debris | Tile::DebrisBurning | Tile::Tentacle | Tile::TentacleGlow)
+                )
+            {
+                return true;
+            }
+            !matches!(
+                feature,
+                Some(
+                    Tile::Wall
+                        | Tile::Window
+                        | Tile::Debris
+                        | Tile::DebrisBurning
+                        | Tile::Tentacle
+                        | Tile::TentacleGlow
+                        | Tile::Stash
+                        | Tile::Terminal
+                        | Tile::AlarmPanel
+                        | Tile::Generator
+                        | Tile::ExplosiveBarrel
+                        | Tile::Water
+                        | Tile::Prop(_)
+                )
+            )
+        };
+        let is_hazard = |coord: Coord| {
+            self.cell_visibility_at_coord(coord)
+                .into_data()
+                .is_some_and(|data| {
+                    matches!(
+                        data.tiles.floor.tile,
+                        Some(Tile::FloorAcid | Tile::FloorElectrified)
+                    )
+                })
+        };
+        if target == start || !is_passable(target) {
+            return None;
+        }
+        // for each visited cell, the direction that was walked to reach it
+        let mut came_from: Grid<Option<CardinalDirection>> = Grid::new_default(size);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(coord) = queue.pop_front() {
+            if coord == target {
+                let mut path = Vec::new();
+                let mut coord = target;
+                while coord != start {
+                    let direction = came_from.get_checked(coord).unwrap();
+                    path.push(direction);
+                    coord -= direction.coord();
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for direction in CardinalDirection::all() {
+                let neighbour = coord + direction.coord();
+                if !neighbour.is_valid(size) || neighbour == start {
+                    continue;
+                }
+                let cell = came_from.get_checked_mut(neighbour);
+                if cell.is_none()
+                    && is_passable(neighbour)
+                    && (neighbour == target || !is_hazard(neighbour))
+                {
+                    *cell = Some(direction);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn item_price(&self, item: Item) -> u32 {
+        // vendors charge half as much again for each civilian killed on the level
+        let price = self.balance.item_price(item);
+        price + price * self.world.civilian_deaths / 2
+    }
+
+    pub fn item_sell_price(&self, item: Item) -> u32 {
+        self.background_sell_price(self.balance.item_sell_price(item))
+    }
+
+    /// What a shop will pay for the item in the given inventory slot, which is a flat price if
+    /// the item hasn't been identified (including cursed items disguised as something else)
+    pub fn inventory_item_sell_price(&self, inventory_index: usize) -> Option<u32> {
+        let item_entity = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)?
+            .get(inventory_index)?;
+        let &item = self.world.components.item.get(item_entity)?;
+        let identified = !self.world.components.unidentified.contains(item_entity)
+            && matches!(self.item_appearance(item), ItemAppearance::Identified(_));
+        Some(if identified {
+            self.item_sell_price(item)
+        } else {
+            self.background_sell_price(UNIDENTIFIED_ITEM_SELL_PRICE)
+        })
+    }
+
+    fn background_sell_price(&self, price: u32) -> u32 {
+        if self.background == Background::Scavenger {
+            (price * SCAVENGER_SELL_PRICE_PERCENT) / 100
+        } else {
+            price
+        }
+    }
+
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// A replay of everything the player has done so far in a game generated from `rng_seed`,
+    /// or `None` for the tutorial which can't be replayed
+    pub fn replay(&self, rng_seed: u64) -> Option<headless::Replay> {
+        if self.tutorial.is_some() {
+            return None;
+        }
+        Some(headless::Replay {
+            rng_seed,
+            background: self.background,
+            victories: self.victories.clone(),
+            remnant: self.inherited_remnant.clone(),
+            steps: self.recorded_steps.clone(),
+            state_hash: self.state_hash(),
+        })
+    }
+
+    // Clinics charge less to a player who knows their way around an operating table
+    fn clinic_price(&self, price: u32) -> u32 {
+        let discount_percent = self.player_skill_level(Skill::Surgery)
+            * SURGERY_SKILL_CLINIC_DISCOUNT_PERCENT_PER_LEVEL;
+        (price * (100 - discount_percent)) / 100
+    }
+
+    // Organ prices follow the black market, which shifts as the player moves between levels and
+    // as they buy and sell organs
+    fn market_price(&self, organ: &Organ, price: u32) -> u32 {
+        (price * self.organ_market.percent(organ.type_)) / 100
+    }
+
+    pub fn organ_buy_price(&self, organ: &Organ) -> u32 {
+        self.clinic_price(self.market_price(organ, self.balance.organ_buy_price(organ)))
+    }
+
+    pub fn organ_sell_price(&self, organ: &Organ) -> u32 {
+        self.market_price(organ, self.balance.organ_sell_price(organ))
+    }
+
+    pub fn organ_black_market_price(&self, organ: &Organ, hot: bool) -> u32 {
+        self.market_price(organ, self.balance.organ_black_market_price(organ, hot))
+    }
+
+    pub fn organ_remove_price(&self, organ: &Organ) -> i32 {
+        let price = self.balance.organ_remove_price(organ);
+        if price > 0 {
+            self.clinic_price(price as u32) as i32
+        } else {
+            price
+        }
+    }
+
+    pub fn organ_install_cost(&self) -> u32 {
+        self.clinic_price(self.balance.organ_install_cost)
+    }
+
+    /// Returns true if the player can currently see a hostile character
+    pub fn is_hostile_visible(&self) -> bool {
+        self.world.components.npc.entities().any(|entity| {
+            self.world.is_hostile(entity)
+                && self
+                    .world
+                    .spatial_table
+                    .coord_of(entity)
+                    .map(|coord| {
+                        matches!(
+                            self.cell_visibility_at_coord(coord),
+                            CellVisibility::Current { .. }
+                        )
+                    })
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Returns true if the player can currently see a corpse
+    pub fn is_corpse_visible(&self) -> bool {
+        self.world.components.corpse.entities().any(|entity| {
+            self.world
+                .spatial_table
+                .coord_of(entity)
+                .map(|coord| {
+                    matches!(
+                        self.cell_visibility_at_coord(coord),
+                        CellVisibility::Current { .. }
+                    )
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    pub fn num_visible_hostiles(&self) -> usize {
+        self.visible_hostile_coords().len()
+    }
+
+    pub fn visible_hostile_coords(&self) -> Vec<Coord> {
+        self.world
+            .components
+            .npc
+            .entities()
+            .filter(|&entity| self.world.is_hostile(entity))
+            .filter_map(|entity| self.world.spatial_table.coord_of(entity))
+            .filter(|&coord| {
+                matches!(
+                    self.cell_visibility_at_coord(coord),
+                    CellVisibility::Current { .. }
+                )
+            })
+            .collect()
+    }
+
+    /// How much danger the player is in, from 0 (safe) to 1. Rises with the number of visible
+    /// hostiles and as the player's health drops.
+    pub fn danger(&self) -> f64 {
+        let hostiles = (self.num_visible_hostiles() as f64 / DANGER_MAX_HOSTILES).min(1.0);
+        let (health, max_health) = self
+            .world
+            .components
+            .health
+            .get(self.player_entity)
+            .map(|health| health.current_and_max())
+            .unwrap_or((0, 1));
+        let injury = 1.0 - (health as f64 / max_health.max(1) as f64);
+        if hostiles == 0.0 {
+            // being hurt with nothing around is tense, but not as tense as a fight
+            injury * 0.5
+        } else {
+            (hostiles + injury * 0.5).min(1.0)
+        }
+    }
+
+    pub fn level_theme(&self) -> LevelTheme {
+        self.world.theme
+    }
+
+    /// Returns the coordinate of the player's deployed drone, if any
+    pub fn drone_coord(&self) -> Option<Coord> {
+        self.world.spatial_table.coord_of(self.drone_entity?)
+    }
+
+    pub fn is_controlling_drone(&self) -> bool {
+        self.controlling_drone
+    }
+
+    pub fn is_sprinting(&self) -> bool {
+        self.sprinting
+    }
+
+    /// How easily npcs notice the player, as a percentage of the distance they can see. Depends
+    /// on how well lit the player is by lights other than their own, and on what they did on
+    /// their last turn.
+    pub fn player_detectability(&self) -> u32 {
+        if self.player_activity == Activity::Noisy {
+            return 100;
+        }
+        let player_coord = match self.world.entity_coord(self.player_entity) {
+            Some(player_coord) => player_coord,
+            None => return 100,
+        };
+        let light_level = self
+            .world
+            .light_level_at(player_coord, self.player_entity)
+            .min(1.0);
+        let detectability = DARK_DETECTABILITY_PERCENT
+            + ((100 - DARK_DETECTABILITY_PERCENT) as f64 * light_level) as u32;
+        let detectability = match self.player_activity {
+            Activity::Still => detectability * STILL_DETECTABILITY_PERCENT / 100,
+            Activity::Moving | Activity::Noisy => detectability,
+        };
+        detectability.max(MIN_DETECTABILITY_PERCENT)
+    }
+
+    // Whether the player knows that `entity` is at `coord`, for predicting what shots will hit
+    fn player_knows_about(&self, entity: Entity, coord: Coord) -> bool {
+        match self.cell_visibility_at_coord(coord) {
+            CellVisibility::Never => false,
+            CellVisibility::Current { .. } => true,
+            // characters seen previously may have moved away
+            CellVisibility::Previous(_) => !self.world.components.character.contains(entity),
+        }
+    }
+
+    fn visible_bullet_obstruction(&self, coord: Coord) -> Option<Entity> {
+        self.world
+            .bullet_obstruction(coord)
+            .filter(|&entity| self.player_knows_about(entity, coord))
+    }
+
+    fn visible_arcing_obstruction(&self, coord: Coord) -> Option<Entity> {
+        self.world
+            .arcing_obstruction(coord)
+            .filter(|&entity| self.player_knows_about(entity, coord))
+    }
+
+    // Where a thrown item aimed at `target` is released towards, as throws are limited in range
+    fn throw_destination(&self, target: Coord) -> Coord {
+        line_2d::LineSegment::new(self.player_coord(), target)
+            .iter()
+            .take(THROW_MAX_DISTANCE + 1)
+            .last()
+            .unwrap()
+    }
+
+    /// Predict where an item thrown at `target` would land. Thrown items arc over characters and
+    /// low obstacles, so only walls, doors and windows get in their way.
+    pub fn trace_throw(&self, target: Coord) -> ShotTrace {
+        let start = self.player_coord();
+        if target == start {
+            return ShotTrace::default();
+        }
+        let destination = self.throw_destination(target);
+        let mut trace = ShotTrace::default();
+        let mut coord = start;
+        for step in line_2d::StepIter::new(destination - start) {
+            let next_coord = coord + step.coord();
+            if !next_coord.is_valid(self.world_size())
+                || self.visible_arcing_obstruction(next_coord).is_some()
+            {
+                break;
+            }
+            coord = next_coord;
+            trace.path.push(coord);
+        }
+        trace.impact = Some(coord);
+        trace.obstructions = line_2d::coords_between(start, target)
+            .skip(1)
+            .filter(|&coord| self.visible_arcing_obstruction(coord).is_some())
+            .collect();
+        trace
+    }
+
+    /// Predict what a shot from the player's equipped weapon at `target` would hit
+    pub fn trace_shot(&self, target: Coord) -> ShotTrace {
+        let start = self.player_coord();
+        if target == start {
+            return ShotTrace::default();
+        }
+        let far_target = line_2d::LineSegment::new(start, target)
+            .infinite_iter()
+            .nth(BULLET_RANGE)
+            .unwrap();
+        let mut trace = ShotTrace::default();
+        let mut coord = start;
+        // projectiles step along the line the same way
+        for step in line_2d::StepIter::new(far_target - start) {
+            coord += step.coord();
+            if !coord.is_valid(self.world_size()) {
+                break;
+            }
+            trace.path.push(coord);
+            if self.visible_bullet_obstruction(coord).is_some() {
+                trace.impact = Some(coord);
+                break;
+            }
+        }
+        trace.obstructions = line_2d::coords_between(start, target)
+            .skip(1)
+            .filter(|&coord| self.visible_bullet_obstruction(coord).is_some())
+            .collect();
+        let player_hands = self.world.components.hands.get(self.player_entity).unwrap();
+        let has_shotgun = [player_hands.left.holding(), player_hands.right.holding()]
+            .into_iter()
+            .flatten()
+            .filter_map(|entity| self.world.components.gun.get(entity))
+            .any(|gun| gun.type_ == GunType::Shotgun);
+        if has_shotgun {
+            trace.spread = self.shotgun_spread_cells(start, far_target, trace.path.len());
+        }
+        trace
+    }
+
+    // Pellets are aimed at random points within the spread of the far target, so at each
+    // distance along the line they deviate from it in proportion to that distance
+    fn shotgun_spread_cells(&self, start: Coord, far_target: Coord, length: usize) -> Vec<Coord> {
+        let spread = self.shotgun_spread();
+        let delta = far_target - start;
+        let (dx, dy) = (delta.x as f64, delta.y as f64);
+        let delta_length = (dx * dx + dy * dy).sqrt();
+        let radius = length as i32 + spread.ceil() as i32;
+        let mut cells = Vec::new();
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let coord = start + Coord::new(x, y);
+                if !coord.is_valid(self.world_size()) {
+                    continue;
+                }
+                let (x, y) = (x as f64, y as f64);
+                let along = (x * dx + y * dy) / delta_length;
+                let across = (x * dy - y * dx).abs() / delta_length;
+                if along > 0.0
+                    && along <= length as f64
+                    && across <= (spread * along / delta_length) + 0.5
+                {
+                    cells.push(coord);
+                }
+            }
+        }
+        cells
     }
 
     /// Returns the coordinate of the player character
@@ -591,28 +2375,166 @@ impl Game {
         );
     }
 
-    fn player_walk(
-        &mut self,
-        direction: CardinalDirection,
-    ) -> Result<Option<GameControlFlow>, ActionError> {
-        let player_coord = self.player_coord();
-        let new_player_coord = player_coord + direction.coord();
-        if !new_player_coord.is_valid(self.world.size()) {
-            // player would walk outside bounds of map
-            return Err(ActionError::InvalidMove);
+    fn barricade_door(&mut self, entity: Entity) {
+        self.world.components.apply_entity_update(
+            entity,
+            entity_update! {
+                door_state: Some(DoorState::Barricaded),
+                tile: Some(Tile::DoorBarricaded),
+            },
+        );
+        if let Some(door_health) = self.world.components.door_health.get_mut(entity) {
+            door_health.set_max(DOOR_HEALTH + BARRICADE_HEALTH);
+            door_health.increase(BARRICADE_HEALTH);
         }
-        if let Some(layers) = self.world.spatial_table.layers_at(new_player_coord) {
-            if let Some(feature_entity) = layers.feature {
+    }
+
+    fn remove_barricade(&mut self, entity: Entity) {
+        self.world.components.apply_entity_update(
+            entity,
+            entity_update! {
+                door_state: Some(DoorState::Closed),
+                tile: Some(Tile::DoorClosed),
+            },
+        );
+        if let Some(door_health) = self.world.components.door_health.get_mut(entity) {
+            door_health.set_max(DOOR_HEALTH);
+        }
+    }
+
+    /// The range of melee damage dealt by an npc, which the balance can override for each type
+    /// of npc
+    fn npc_bump_damage(&self, entity: Entity) -> RangeInclusive<u32> {
+        self.world
+            .components
+            .npc_type
+            .get(entity)
+            .and_then(|&npc_type| self.balance.npc_damage(npc_type))
+            .or_else(|| self.world.components.bump_damage.get(entity).cloned())
+            .unwrap_or(1..=1)
+    }
+
+    fn npc_bash_door(&mut self, npc_entity: Entity, door_entity: Entity) {
+        let damage_range = self.npc_bump_damage(npc_entity);
+        let damage = self.rng.gen_range(damage_range);
+        let door_health = match self.world.components.door_health.get_mut(door_entity) {
+            Some(door_health) => door_health,
+            None => return,
+        };
+        door_health.decrease(damage);
+        if door_health.is_empty() {
+            self.world.remove_entity(door_entity);
+            self.message_log.push(Message::DoorBreaks);
+        } else if door_health.current() <= DOOR_HEALTH {
+            if let Some(DoorState::Barricaded) = self.world.components.door_state.get(door_entity) {
+                self.remove_barricade(door_entity);
+                self.message_log.push(Message::BarricadeGivesWay);
+            }
+        }
+    }
+
+    // Generators which are out of fuel need a battery from the player's inventory to start
+    fn player_start_generator(&mut self, generator_entity: Entity) -> Result<(), ActionError> {
+        if self.world.components.out_of_fuel.contains(generator_entity) {
+            let battery_index = (0..self.inventory_size())
+                .find(|&i| self.inventory_item(i) == Some(Item::Battery))
+                .ok_or(ActionError::GeneratorNeedsBattery)?;
+            let inventory = self
+                .world
+                .components
+                .inventory
+                .get_mut(self.player_entity)
+                .unwrap();
+            if let Some(battery_entity) = inventory.remove(battery_index) {
+                self.world.remove_entity(battery_entity);
+            }
+            self.world.components.out_of_fuel.remove(generator_entity);
+        }
+        self.world.powered = true;
+        self.message_log.push(Message::PowerRestored);
+        Ok(())
+    }
+
+    // Hacking an alarm panel takes several attempts, fewer for a skilled hacker. A hacked panel no
+    // longer watches for the player.
+    fn player_hack_alarm_panel(&mut self, panel_entity: Entity) {
+        let progress = 1 + self.player_skill_level(Skill::Hacking);
+        self.world
+            .train_player_skill(Skill::Hacking, &mut self.message_log);
+        let hack_progress = match self.world.components.hack_progress.get_mut(panel_entity) {
+            Some(hack_progress) => {
+                *hack_progress += progress;
+                *hack_progress
+            }
+            None => {
+                self.world
+                    .components
+                    .hack_progress
+                    .insert(panel_entity, progress);
+                progress
+            }
+        };
+        if hack_progress >= ALARM_PANEL_HACK_DIFFICULTY {
+            self.world.components.alarm_panel.remove(panel_entity);
+            self.world.components.hack_progress.remove(panel_entity);
+            self.message_log.push(Message::HackAlarmPanel);
+        } else {
+            self.message_log.push(Message::HackAlarmPanelProgress);
+        }
+    }
+
+    pub fn is_level_powered(&self) -> bool {
+        self.world.powered
+    }
+
+    fn player_walk(
+        &mut self,
+        direction: CardinalDirection,
+    ) -> Result<Option<GameControlFlow>, ActionError> {
+        let player_coord = self.player_coord();
+        let new_player_coord = player_coord + direction.coord();
+        if !new_player_coord.is_valid(self.world.size()) {
+            // player would walk outside bounds of map
+            return Err(ActionError::InvalidMove);
+        }
+        if let Some(layers) = self.world.spatial_table.layers_at(new_player_coord) {
+            if let Some(feature_entity) = layers.feature {
                 // If the player bumps into a door, open the door
-                if let Some(DoorState::Closed) =
-                    self.world.components.door_state.get(feature_entity)
-                {
-                    self.open_door(feature_entity);
-                    self.message_log.push(Message::OpenDoor);
+                match self.world.components.door_state.get(feature_entity) {
+                    Some(DoorState::Closed) => {
+                        self.open_door(feature_entity);
+                        self.message_log.push(Message::OpenDoor);
+                        return Ok(None);
+                    }
+                    Some(DoorState::Barricaded) => {
+                        self.remove_barricade(feature_entity);
+                        self.message_log.push(Message::RemoveBarricade);
+                        return Ok(None);
+                    }
+                    _ => (),
+                }
+                if self.world.components.stash.contains(feature_entity) {
+                    return Ok(Some(GameControlFlow::Menu(self.stash_menu(feature_entity))));
+                }
+                if self.world.components.generator.contains(feature_entity) && !self.world.powered {
+                    self.player_start_generator(feature_entity)?;
                     return Ok(None);
                 }
-                // Don't let the player walk through solid entities
-                if self.world.components.solid.contains(feature_entity) {
+                if self.world.components.alarm_panel.contains(feature_entity) {
+                    self.player_hack_alarm_panel(feature_entity);
+                    return Ok(None);
+                }
+                if let Some(&document) = self.world.components.terminal.get(feature_entity) {
+                    return Ok(Some(self.read_document(document)));
+                }
+                // Don't let the player walk through solid entities unless they can climb over them
+                if self.world.components.solid.contains(feature_entity)
+                    && !(self.world.components.difficult.contains(feature_entity)
+                        && self.world.can_npc_traverse_feature_at_coord_with_entity(
+                            new_player_coord,
+                            self.player_entity,
+                        ))
+                {
                     if let Some(open_door_entity) =
                         self.open_door_entity_adjacent_to_coord(player_coord, new_player_coord)
                     {
@@ -624,6 +2546,23 @@ impl Game {
                 }
             }
             if let Some(character_entity) = layers.character {
+                if Some(character_entity) == self.drone_entity {
+                    self.recall_drone()?;
+                    return Ok(None);
+                }
+                if self.world.components.turret_ammo.contains(character_entity)
+                    && self.world.is_ally(character_entity)
+                {
+                    self.player_pick_up_turret(character_entity)?;
+                    return Ok(None);
+                }
+                if self.world.components.mercenary.contains(character_entity)
+                    && !self.world.is_hostile(character_entity)
+                {
+                    return Ok(Some(GameControlFlow::Menu(
+                        self.mercenary_menu(character_entity),
+                    )));
+                }
                 if self.world.components.shop.contains(character_entity) {
                     return Ok(Some(GameControlFlow::Menu(
                         self.shop_menu(character_entity),
@@ -637,6 +2576,9 @@ impl Game {
                 );
                 return Ok(None);
             }
+            if self.world.is_difficult_at(new_player_coord) {
+                self.message_log.push(Message::Climb);
+            }
             self.world
                 .spatial_table
                 .update_coord(self.player_entity, new_player_coord)
@@ -646,6 +2588,63 @@ impl Game {
         Ok(None)
     }
 
+    // A sprinting player takes a second step in the same direction each turn, as long as the way
+    // is clear. Bumping into anything ends the turn as if the player had just walked.
+    fn player_sprint_step(
+        &mut self,
+        direction: CardinalDirection,
+    ) -> Result<Option<GameControlFlow>, ActionError> {
+        let player_coord = self.player_coord();
+        if self.world.is_water_at(player_coord) || self.world.is_difficult_at(player_coord) {
+            return Ok(None);
+        }
+        let next_coord = player_coord + direction.coord();
+        let is_clear = match self.world.spatial_table.layers_at(next_coord) {
+            Some(layers) => {
+                layers.character.is_none()
+                    && layers.feature.is_none_or(|feature_entity| {
+                        !self.world.components.solid.contains(feature_entity)
+                            && !self.world.components.water.contains(feature_entity)
+                    })
+            }
+            None => false,
+        };
+        if !is_clear {
+            return Ok(None);
+        }
+        let oxygen = self
+            .world
+            .components
+            .oxygen
+            .get_mut(self.player_entity)
+            .unwrap();
+        if oxygen.current() < SPRINT_OXYGEN_COST {
+            self.sprinting = false;
+            self.message_log.push(Message::OutOfBreath);
+            return Ok(None);
+        }
+        oxygen.decrease(SPRINT_OXYGEN_COST);
+        let game_control_flow = self.player_walk(direction)?;
+        self.make_noise(self.player_coord(), SPRINT_NOISE_DISTANCE);
+        Ok(game_control_flow)
+    }
+
+    // Hostile npcs within earshot head for the source of a noise. Npcs which are already chasing
+    // the player update where they think the player is, so they don't fall behind a player who
+    // moves more than one cell per turn.
+    fn make_noise(&mut self, coord: Coord, distance: u32) {
+        self.player_activity = Activity::Noisy;
+        for (entity, agent) in self.agents.iter_mut() {
+            let npc_coord = match self.world.entity_coord(entity) {
+                Some(npc_coord) => npc_coord,
+                None => continue,
+            };
+            if npc_coord.distance2(coord) <= distance * distance && self.world.is_hostile(entity) {
+                agent.hear_noise(coord);
+            }
+        }
+    }
+
     fn clinic_menu(&self, shop_entity: Entity) -> Menu {
         let shop = self.world.components.shop.get(shop_entity).unwrap();
         Menu {
@@ -657,14 +2656,47 @@ impl Game {
                     clinic_entity: shop_entity,
                 },
                 MenuChoice::ClinicInstallFromContainer,
+                MenuChoice::ClinicBuyInsurance,
             ],
         }
     }
 
+    fn organ_trader_menu(&self, shop_entity: Entity) -> Menu {
+        let shop = self.world.components.shop.get(shop_entity).unwrap();
+        let inventory = self
+            .world
+            .components
+            .simple_inventory
+            .get(shop_entity)
+            .unwrap();
+        let mut choices = vec![MenuChoice::Sell { shop_entity }];
+        for (i, &item_entity) in inventory.iter().enumerate() {
+            if let Some(&Item::OrganContainer(Some(organ))) =
+                self.world.components.item.get(item_entity)
+            {
+                choices.push(MenuChoice::BuyOrgan {
+                    organ,
+                    hot: self.world.components.hot.contains(item_entity),
+                    shop_entity,
+                    item_entity,
+                    shop_inventory_index: i,
+                });
+            }
+        }
+        Menu {
+            image: None,
+            text: shop.message.clone(),
+            choices,
+        }
+    }
+
     fn shop_menu(&self, shop_entity: Entity) -> Menu {
         if self.world.components.organ_clinic.contains(shop_entity) {
             return self.clinic_menu(shop_entity);
         }
+        if self.world.components.npc_type.get(shop_entity) == Some(&NpcType::OrganTrader) {
+            return self.organ_trader_menu(shop_entity);
+        }
         let shop = self.world.components.shop.get(shop_entity).unwrap();
         let inventory = self
             .world
@@ -672,19 +2704,16 @@ impl Game {
             .simple_inventory
             .get(shop_entity)
             .unwrap();
-        let choices = inventory
-            .into_iter()
-            .enumerate()
-            .map(|(i, &item_entity)| {
-                let item = *self.world.components.item.get(item_entity).unwrap();
-                MenuChoice::BuyItem {
-                    item,
-                    shop_entity,
-                    item_entity,
-                    shop_inventory_index: i,
-                }
-            })
-            .collect::<Vec<_>>();
+        let mut choices = vec![MenuChoice::Sell { shop_entity }];
+        choices.extend(inventory.into_iter().enumerate().map(|(i, &item_entity)| {
+            let item = *self.world.components.item.get(item_entity).unwrap();
+            MenuChoice::BuyItem {
+                item,
+                shop_entity,
+                item_entity,
+                shop_inventory_index: i,
+            }
+        }));
         Menu {
             image: None,
             text: shop.message.clone(),
@@ -700,6 +2729,12 @@ impl Game {
             .layers_at_checked(player_coord)
             .feature
         {
+            let is_elevator = self.world.components.stairs_down.contains(feature_entity)
+                || self.world.components.stairs_up.contains(feature_entity);
+            if is_elevator && !self.world.powered {
+                self.message_log.push(Message::ElevatorUnpowered);
+                return;
+            }
             if self.world.components.stairs_down.contains(feature_entity) {
                 self.enter_level(self.current_level_index + 1);
             } else if self.world.components.stairs_up.contains(feature_entity) {
@@ -727,27 +2762,53 @@ impl Game {
         }) = self.world.spatial_table.layers_at(new_coord)
         {
             if let Some(feature_entity) = feature {
-                // If the npc bumps into a door, open the door
-                if let Some(DoorState::Closed) =
-                    self.world.components.door_state.get(feature_entity)
-                {
-                    self.open_door(feature_entity);
-                    return None;
+                // If the npc bumps into a door, open the door if it can or otherwise bash it
+                let can_open_doors = self
+                    .world
+                    .components
+                    .npc
+                    .get(entity)
+                    .is_some_and(|npc| npc.movement.can_open_doors);
+                match self.world.components.door_state.get(feature_entity) {
+                    Some(DoorState::Closed) if can_open_doors => {
+                        self.open_door(feature_entity);
+                        return None;
+                    }
+                    Some(DoorState::Closed | DoorState::Barricaded) => {
+                        self.npc_bash_door(entity, feature_entity);
+                        return None;
+                    }
+                    _ => (),
                 }
             }
             // Don't let them walk into other characters
             if let Some(character_entity) = character {
                 if self.world.components.player.contains(character_entity) {
-                    let damage_range = self
-                        .world
-                        .components
-                        .bump_damage
-                        .get(entity)
-                        .cloned()
-                        .unwrap_or_else(|| 1..=1);
+                    let damage_range = self.npc_bump_damage(entity);
                     self.world.damage_player(
                         entity,
-                        self.rng.gen_range(damage_range),
+                        DamageRoll::roll(damage_range, &mut self.rng),
+                        &mut self.rng,
+                        &mut self.external_events,
+                        &mut self.message_log,
+                    );
+                    self.world.npc_claw_hit(
+                        entity,
+                        character_entity,
+                        &mut self.rng,
+                        &mut self.message_log,
+                    );
+                    self.world.npc_emp_hit(
+                        entity,
+                        character_entity,
+                        &mut self.rng,
+                        &mut self.message_log,
+                    );
+                } else if self.world.are_enemies(entity, character_entity) {
+                    let damage_range = self.npc_bump_damage(entity);
+                    self.world.damage_character(
+                        character_entity,
+                        DamageRoll::roll(damage_range, &mut self.rng),
                         &mut self.rng,
                         &mut self.external_events,
                         &mut self.message_log,
@@ -766,33 +2827,216 @@ impl Game {
             .spatial_table
             .update_coord(entity, new_coord)
             .unwrap();
+        self.overwatch_fire(entity, new_coord);
         None
     }
 
+    // Shoot at a hostile npc which has just moved to `coord`, if it's in the arc the player is
+    // watching. The stance ends after a single shot.
+    fn overwatch_fire(&mut self, entity: Entity, coord: Coord) {
+        let direction = match self.overwatch {
+            Some(direction) => direction,
+            None => return,
+        };
+        if !self.world.are_enemies(self.player_entity, entity) {
+            return;
+        }
+        let delta = coord - self.player_coord();
+        let (forward, sideways) = match direction {
+            CardinalDirection::North => (-delta.y, delta.x),
+            CardinalDirection::East => (delta.x, delta.y),
+            CardinalDirection::South => (delta.y, delta.x),
+            CardinalDirection::West => (-delta.x, delta.y),
+        };
+        let in_arc = forward > 0
+            && sideways.abs() <= forward
+            && delta.magnitude2() <= OVERWATCH_RANGE * OVERWATCH_RANGE;
+        let is_visible = matches!(
+            self.visibility_grid.get_visibility(coord),
+            CellVisibility::Current { .. }
+        );
+        if !in_arc || !is_visible || self.check_loaded_gun().is_err() {
+            return;
+        }
+        self.overwatch = None;
+        if let Some(&npc_type) = self.world.components.npc_type.get(entity) {
+            self.message_log.push(Message::OverwatchFire(npc_type));
+        }
+        if self.fire_equipped(coord).is_ok() {
+            // The npc turn is already underway, so the shot mustn't block gameplay, or the npcs
+            // would take another turn once it lands
+            self.world.components.blocks_gameplay.clear();
+        }
+    }
+
+    // Npc projectiles don't block gameplay, so that firing doesn't delay the rest of the npc turn
+    fn npc_fire_gun(&mut self, entity: Entity, target: Coord) {
+        let start = match self.world.entity_coord(entity) {
+            Some(coord) => coord,
+            None => return,
+        };
+        if let Some(turret_ammo) = self.world.components.turret_ammo.get_mut(entity) {
+            if turret_ammo.is_empty() {
+                return;
+            }
+            turret_ammo.decrease(1);
+            self.external_events.push(ExternalEvent::FirePistol(start));
+            let bullet = self.world.spawn_bullet(
+                start,
+                target,
+                ProjectileDamage {
+                    hit_points: 1..=2,
+                    bleeding_probability: 0.0,
+                    ricochet: false,
+                    penetration: 0,
+                },
+                &mut self.animation_rng,
+            );
+            self.world.components.blocks_gameplay.remove(bullet);
+            return;
+        }
+        let gun_entity = match self.world.npc_gun_entity(entity) {
+            Some(gun_entity) => gun_entity,
+            None => return,
+        };
+        let gun = self.world.components.gun.get_mut(gun_entity).unwrap();
+        if gun.ammo.is_empty() {
+            return;
+        }
+        gun.ammo.decrease(1);
+        let gun_type = gun.type_;
+        let mut projectiles = Vec::new();
+        match gun_type {
+            GunType::Pistol => {
+                self.external_events.push(ExternalEvent::FirePistol(start));
+                projectiles.push(self.world.spawn_bullet(
+                    start,
+                    target,
+                    ProjectileDamage {
+                        hit_points: 1..=2,
+                        bleeding_probability: 0.0,
+                        ricochet: true,
+                        penetration: 0,
+                    },
+                    &mut self.animation_rng,
+                ));
+            }
+            GunType::Shotgun => {
+                self.external_events.push(ExternalEvent::FireShotgun(start));
+                let is_ally = self.world.is_ally(entity);
+                let player_coord = self.player_coord();
+                for _ in 0..8 {
+                    let angle = Radians::random(&mut self.rng);
+                    let pellet_target = Radial { angle, length: 3.0 }
+                        .to_cartesian()
+                        .to_coord_round_nearest()
+                        + target;
+                    // An ally's pellets which would stray into the player or back into the ally
+                    // are aimed straight at the target instead
+                    let target = if is_ally
+                        && (pellet_target == start
+                            || line_2d::LineSegment::new(start, pellet_target)
+                                .iter()
+                                .any(|coord| coord == player_coord))
+                    {
+                        target
+                    } else {
+                        pellet_target
+                    };
+                    projectiles.push(self.world.spawn_bullet(
+                        start,
+                        target,
+                        ProjectileDamage {
+                            hit_points: 2..=3,
+                            bleeding_probability: SHOTGUN_CRIT_BLEEDING_PROBABILITY,
+                            ricochet: false,
+                            penetration: 0,
+                        },
+                        &mut self.animation_rng,
+                    ));
+                }
+            }
+            GunType::SniperRifle => {
+                self.external_events.push(ExternalEvent::FirePistol(start));
+                projectiles.push(self.world.spawn_bullet(
+                    start,
+                    target,
+                    ProjectileDamage {
+                        hit_points: 4..=6,
+                        bleeding_probability: 0.0,
+                        ricochet: false,
+                        penetration: SNIPER_ROUND_PENETRATION,
+                    },
+                    &mut self.animation_rng,
+                ));
+            }
+            GunType::RocketLauncher => {
+                self.external_events.push(ExternalEvent::FireRocket(start));
+                projectiles.push(
+                    self.world
+                        .spawn_rocket(start, target, &mut self.animation_rng),
+                );
+            }
+        }
+        for projectile in projectiles {
+            self.world.components.blocks_gameplay.remove(projectile);
+        }
+    }
+
     // Create agents for npcs that lack agents and remove agents for agents whose npcs have been
     // removed.
     fn npc_setup_agents(&mut self) {
-        for entity in self.world.components.npc.entities() {
+        for (entity, npc) in self.world.components.npc.iter() {
             if !self.agents.contains(entity) {
-                self.agents.insert(entity, Agent::new(self.world.size()));
+                let is_horde = matches!(npc.disposition, Disposition::Hostile)
+                    && self.world.npc_gun_entity(entity).is_none();
+                let agent = if is_horde {
+                    Agent::new_horde()
+                } else {
+                    Agent::new(self.world.size())
+                };
+                self.agents.insert(entity, agent);
             }
         }
-        let mut agents_to_remove = Vec::new();
+        self.entities_to_remove.clear();
         for entity in self.agents.entities() {
             if !self.world.components.npc.contains(entity) {
-                agents_to_remove.push(entity);
+                self.entities_to_remove.push(entity);
             }
         }
-        for entity in agents_to_remove {
+        for &entity in &self.entities_to_remove {
             self.agents.remove(entity);
         }
     }
 
     fn npc_turn(&mut self) -> Option<GameControlFlow> {
         self.npc_setup_agents();
-        self.ai_context.update(self.player_entity, &self.world);
-        let agent_entities = self.agents.entities().collect::<Vec<_>>();
-        for agent_entity in agent_entities {
+        let player_detectability = self.player_detectability();
+        self.ai_context
+            .update(self.player_entity, &self.world, player_detectability);
+        // The buffer is moved out of `self` while agents act as acting needs `&mut self`
+        let mut agent_entities = std::mem::take(&mut self.agent_entities);
+        agent_entities.clear();
+        agent_entities.extend(self.agents.entities());
+        let control_flow = self.npc_act(&agent_entities);
+        self.agent_entities = agent_entities;
+        if control_flow.is_some() {
+            return control_flow;
+        }
+        self.systems();
+        self.turn_count += 1;
+        self.run_script_turn_hooks();
+        self.handle_tutorial();
+        if let Some(win) = self.win() {
+            self.message_log.push(Message::Escape);
+            self.update_visibility();
+            return Some(GameControlFlow::Win(win));
+        }
+        self.check_game_over()
+    }
+
+    fn npc_act(&mut self, agent_entities: &[Entity]) -> Option<GameControlFlow> {
+        for &agent_entity in agent_entities {
             if self.world.components.corpse.contains(agent_entity) {
                 continue;
             }
@@ -801,43 +3045,83 @@ impl Game {
                     continue;
                 }
             }
-            let ai_input = self.agents.get_mut(agent_entity).unwrap().act(
-                agent_entity,
-                &self.world,
-                self.player_entity,
-                &mut self.ai_context,
-                &mut self.rng,
-            );
-            if let Some(input) = ai_input {
-                match input {
-                    Input::Wait => (),
-                    Input::Walk(direction) => {
+            let ai_input = match &self.merchant {
+                // The travelling merchant makes its own way to the stairs when it's time to leave,
+                // unless it's been provoked
+                MerchantState::Travelling(merchant)
+                    if merchant.is_leaving(agent_entity, &self.world, self.turn_count) =>
+                {
+                    merchant
+                        .exit_coord(&self.world)
+                        .and_then(|coord| self.world.npc_first_step_towards(agent_entity, coord))
+                        .map(NpcAction::Walk)
+                }
+                _ => self.agents.get_mut(agent_entity).unwrap().act(
+                    agent_entity,
+                    &self.world,
+                    self.player_entity,
+                    &mut self.ai_context,
+                    &mut self.rng,
+                ),
+            };
+            if let Some(action) = ai_input {
+                match action {
+                    NpcAction::Walk(direction) => {
                         if let Some(control_flow) = self.npc_walk(agent_entity, direction) {
                             return Some(control_flow);
                         }
                     }
-                    _ => (),
+                    NpcAction::FireGun(target) => self.npc_fire_gun(agent_entity, target),
+                    NpcAction::PickUp => self.world.npc_pick_up(agent_entity),
+                    NpcAction::Reload => self.world.npc_reload(agent_entity),
+                    NpcAction::ApplyStimpack => self.world.npc_apply_stimpack(agent_entity),
                 }
             }
         }
-        self.systems();
-        self.turn_count += 1;
-        if let Some(win) = self.win() {
-            self.message_log.push(Message::Escape);
-            self.update_visibility();
-            return Some(GameControlFlow::Win(win));
-        }
-        self.check_game_over()
+        None
     }
 
     fn systems(&mut self) {
+        self.handle_drone_destroyed();
+        self.handle_cursed_guns();
+        self.world.handle_alarm(
+            &mut self.rng,
+            &mut self.external_events,
+            &mut self.message_log,
+        );
+        self.world
+            .handle_theft(&mut self.external_events, &mut self.message_log);
         self.world.handle_poison(&mut self.message_log);
+        self.handle_corruption();
+        self.world.handle_poison_gas(
+            &mut self.rng,
+            &mut self.external_events,
+            &mut self.message_log,
+        );
+        self.world.handle_bleeding(
+            &mut self.rng,
+            &mut self.external_events,
+            &mut self.message_log,
+        );
+        self.world.handle_floor_hazards(
+            &mut self.rng,
+            &mut self.external_events,
+            &mut self.message_log,
+        );
         self.world.handle_radiation(&mut self.message_log);
+        self.handle_geiger_counter();
         self.world.handle_smoke(&mut self.message_log);
+        self.world.disperse_smoke_clouds();
+        self.world.handle_submersion(&mut self.message_log);
         self.world.handle_asphyxiation(&mut self.message_log);
+        self.world.handle_temperature(&mut self.message_log);
         self.world.handle_resurrection();
+        self.world.handle_surgery_recovery(&mut self.message_log);
+        self.world.handle_spoilage(&mut self.message_log);
+        self.world.handle_scent();
+        self.world.handle_emp_recovery(&mut self.message_log);
         self.world.handle_get_on_touch();
-        self.world.handle_spread_poison();
+        self.world.handle_spread_poison(&mut self.rng);
         self.world
             .handle_full_poison(&mut self.rng, &mut self.message_log);
         self.world
@@ -845,32 +3129,210 @@ impl Game {
         self.world
             .handle_player_organ_traits(&mut self.rng, &mut self.message_log);
         self.world
-            .handle_player_organs(&mut self.rng, &mut self.message_log);
+            .handle_organ_rejection(&mut self.rng, &mut self.message_log);
+        self.world
+            .handle_cyberpsychosis(&mut self.rng, &mut self.message_log);
+        self.handle_hallucinations();
+        self.handle_travelling_merchant();
+        self.handle_bounty_hunter();
+        self.world.handle_player_organs(
+            self.balance.player.health_percent,
+            &mut self.rng,
+            &mut self.message_log,
+        );
         if self.world.is_boss_dead() {
             if !self.boss_dead {
                 self.message_log.push(Message::BossKill);
                 self.message_log.push(Message::GetToTheEvacZone);
+                self.external_events.push(ExternalEvent::BossKill);
                 self.remove_corruption();
             }
             self.boss_dead = true;
         }
     }
 
-    fn remove_corruption(&mut self) {
-        self.world.remove_corrpution();
-        for level in self.other_levels.iter_mut() {
-            if let Some(level) = level.as_mut() {
-                level.world.remove_corrpution();
+    fn player_psychosis_fraction(&self) -> f64 {
+        self.world
+            .components
+            .psychosis
+            .get(self.player_entity)
+            .map_or(0.0, |psychosis| {
+                psychosis.current() as f64 / psychosis.max() as f64
+            })
+    }
+
+    fn psychosis_stumble(&mut self, direction: CardinalDirection) -> CardinalDirection {
+        let probability = self.player_psychosis_fraction() * PSYCHOSIS_STUMBLE_MAX_PROBABILITY;
+        if probability > 0.0 && self.rng.gen_bool(probability) {
+            let stumble_direction = CardinalDirection::all()
+                .nth(self.rng.gen_range(0..4))
+                .unwrap();
+            if stumble_direction != direction {
+                self.message_log.push(Message::PsychosisStumble);
+                return stumble_direction;
             }
         }
+        direction
     }
 
-    fn check_game_over(&mut self) -> Option<GameControlFlow> {
-        if self.game_over {
+    /// The coordinates of everything the player is currently hallucinating and what they see there
+    fn hallucinated_npcs(&self) -> Vec<(Coord, NpcType)> {
+        let mut ret = self
+            .hallucinations
+            .iter()
+            .map(|hallucination| (hallucination.coord, hallucination.npc_type))
+            .collect::<Vec<_>>();
+        for &(entity, npc_type) in &self.hostile_flashes {
+            if let Some(coord) = self.world.spatial_table.coord_of(entity) {
+                ret.push((coord, npc_type));
+            }
+        }
+        ret
+    }
+
+    fn is_free_for_hallucination(&self, coord: Coord) -> bool {
+        coord != self.player_coord()
+            && matches!(
+                self.world.spatial_table.layers_at(coord),
+                Some(Layers {
+                    floor: Some(_),
+                    feature: None,
+                    character: None,
+                    ..
+                })
+            )
+    }
+
+    fn handle_hallucinations(&mut self) {
+        let player_coord = self.player_coord();
+        if let Some(i) = self
+            .hallucinations
+            .iter()
+            .position(|hallucination| hallucination.coord == player_coord)
+        {
+            let hallucination = self.hallucinations.swap_remove(i);
+            self.message_log
+                .push(Message::HallucinationDispelled(hallucination.npc_type));
+        }
+        self.hallucinations.retain_mut(|hallucination| {
+            hallucination.turns -= 1;
+            hallucination.turns > 0
+        });
+        // hallucinations stalk the player
+        for i in 0..self.hallucinations.len() {
+            let delta = player_coord - self.hallucinations[i].coord;
+            let step = if delta.x.abs() > delta.y.abs() {
+                Coord::new(delta.x.signum(), 0)
+            } else {
+                Coord::new(0, delta.y.signum())
+            };
+            let coord = self.hallucinations[i].coord + step;
+            if self.is_free_for_hallucination(coord)
+                && self.hallucinations.iter().all(|h| h.coord != coord)
+            {
+                self.hallucinations[i].coord = coord;
+            }
+        }
+        self.hostile_flashes.clear();
+        let psychosis_fraction = self.player_psychosis_fraction();
+        if psychosis_fraction == 0.0 {
+            self.hallucinations.clear();
+            return;
+        }
+        if self
+            .rng
+            .gen_bool(psychosis_fraction * HALLUCINATION_MAX_PROBABILITY)
+        {
+            let offset = Coord::new(
+                self.rng
+                    .gen_range(-HALLUCINATION_MAX_DISTANCE..=HALLUCINATION_MAX_DISTANCE),
+                self.rng
+                    .gen_range(-HALLUCINATION_MAX_DISTANCE..=HALLUCINATION_MAX_DISTANCE),
+            );
+            let coord = player_coord + offset;
+            let is_visible = matches!(
+                self.visibility_grid.get_visibility(coord),
+                CellVisibility::Current { .. }
+            );
+            if offset.magnitude2()
+                >= (HALLUCINATION_MIN_DISTANCE * HALLUCINATION_MIN_DISTANCE) as u32
+                && is_visible
+                && self.is_free_for_hallucination(coord)
+            {
+                let npc_type =
+                    HALLUCINATION_NPC_TYPES[self.rng.gen_range(0..HALLUCINATION_NPC_TYPES.len())];
+                self.hallucinations.push(Hallucination {
+                    coord,
+                    npc_type,
+                    turns: HALLUCINATION_TURNS,
+                });
+            }
+        }
+        if self
+            .rng
+            .gen_bool(psychosis_fraction * HOSTILE_FLASH_MAX_PROBABILITY)
+        {
+            let candidates = self
+                .world
+                .components
+                .npc
+                .iter()
+                .filter(|(_, npc)| !matches!(npc.disposition, Disposition::Hostile))
+                .filter_map(|(entity, _)| {
+                    let coord = self.world.spatial_table.coord_of(entity)?;
+                    if self.world.components.corpse.contains(entity) {
+                        return None;
+                    }
+                    if let CellVisibility::Current { .. } =
+                        self.visibility_grid.get_visibility(coord)
+                    {
+                        Some(entity)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !candidates.is_empty() {
+                let entity = candidates[self.rng.gen_range(0..candidates.len())];
+                let npc_type =
+                    HALLUCINATION_NPC_TYPES[self.rng.gen_range(0..HALLUCINATION_NPC_TYPES.len())];
+                self.hostile_flashes.push((entity, npc_type));
+            }
+        }
+    }
+
+    fn remove_corruption(&mut self) {
+        self.world.remove_corrpution();
+        for level_index in 0..NUM_LEVELS {
+            if let Some(stored_level) = self.other_levels[level_index].take() {
+                let rng_seed = self.level_rng_seeds[level_index];
+                let left_at_turn = stored_level.left_at_turn;
+                let mut level = stored_level.restore(level_index, rng_seed, &self.balance);
+                level.world.remove_corrpution();
+                self.other_levels[level_index] =
+                    Some(level.store(level_index, rng_seed, &self.balance, left_at_turn));
+            }
+        }
+    }
+
+    fn check_game_over(&mut self) -> Option<GameControlFlow> {
+        if self.game_over {
             return Some(GameControlFlow::GameOver(GameOverReason::YouDied));
         }
         if self.world.is_game_over() {
+            if self
+                .world
+                .components
+                .clone_insurance
+                .contains(self.player_entity)
+            {
+                self.respawn_clone();
+                return None;
+            }
             self.game_over = true;
+            if self.tutorial.is_none() {
+                self.remnant = Some(self.make_remnant());
+            }
             self.world
                 .components
                 .tile
@@ -878,7 +3340,8 @@ impl Game {
             self.player_drop_all_items();
             self.update_visibility();
             self.message_log.push(Message::YouDie);
-            self.external_events.push(ExternalEvent::Death);
+            self.external_events
+                .push(ExternalEvent::Death(self.player_coord()));
             Some(GameControlFlow::GameOver(GameOverReason::YouDied))
         } else {
             None
@@ -908,13 +3371,10 @@ impl Game {
     }
 
     fn cleanup(&mut self) {
-        let to_remove = self
-            .world
-            .components
-            .to_remove
-            .entities()
-            .collect::<Vec<_>>();
-        for entity in to_remove {
+        self.entities_to_remove.clear();
+        self.entities_to_remove
+            .extend(self.world.components.to_remove.entities());
+        for &entity in &self.entities_to_remove {
             self.world.remove_entity(entity);
         }
     }
@@ -940,7 +3400,7 @@ impl Game {
         }
         self.cleanup();
         self.update_visibility();
-        self.check_game_over()
+        self.check_game_over().or_else(|| self.check_level_up())
     }
 
     fn pass_time(&mut self) {}
@@ -950,53 +3410,160 @@ impl Game {
     }
 
     fn fire_pistol(&mut self, target: Coord) {
+        self.spawn_pistol_bullet(target);
+        self.message_log.push(Message::FireGun(Item::Pistol));
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
+    }
+
+    // A pistol going off without the player pulling the trigger teaches them nothing
+    fn fire_pistol_involuntarily(&mut self, target: Coord) {
+        self.spawn_pistol_bullet(target);
+        self.message_log.push(Message::CursedGunFires);
+    }
+
+    fn spawn_pistol_bullet(&mut self, target: Coord) {
         let start = self.player_coord();
         let target = line_2d::LineSegment::new(start, target)
             .infinite_iter()
-            .nth(20)
+            .nth(BULLET_RANGE)
             .unwrap();
-        self.external_events.push(ExternalEvent::FirePistol);
+        self.external_events.push(ExternalEvent::FirePistol(start));
         self.world.spawn_bullet(
             start,
             target,
-            ProjectileDamage { hit_points: 1..=2 },
+            ProjectileDamage {
+                hit_points: 1..=2,
+                bleeding_probability: 0.0,
+                ricochet: true,
+                penetration: 0,
+            },
             &mut self.animation_rng,
         );
-        self.message_log.push(Message::FireGun(Item::Pistol));
+    }
+
+    // Skill with guns keeps shotgun pellets closer together
+    fn shotgun_spread(&self) -> f64 {
+        SHOTGUN_SPREAD
+            * (1.0
+                - (self.player_skill_level(Skill::Guns) as f64
+                    * GUN_SKILL_SPREAD_REDUCTION_PER_LEVEL))
     }
 
     fn fire_shotgun(&mut self, target: Coord) {
         let start = self.player_coord();
         let target = line_2d::LineSegment::new(start, target)
             .infinite_iter()
-            .nth(20)
+            .nth(BULLET_RANGE)
             .unwrap();
-        self.external_events.push(ExternalEvent::FireShotgun);
+        self.external_events.push(ExternalEvent::FireShotgun(start));
+        let spread = self.shotgun_spread();
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
         for _ in 0..8 {
             let angle = Radians::random(&mut self.rng);
-            let target = Radial { angle, length: 3.0 }
-                .to_cartesian()
-                .to_coord_round_nearest()
+            let target = Radial {
+                angle,
+                length: spread,
+            }
+            .to_cartesian()
+            .to_coord_round_nearest()
                 + target;
             self.world.spawn_bullet(
                 start,
                 target,
-                ProjectileDamage { hit_points: 2..=3 },
+                ProjectileDamage {
+                    hit_points: 2..=3,
+                    bleeding_probability: SHOTGUN_CRIT_BLEEDING_PROBABILITY,
+                    ricochet: false,
+                    penetration: 0,
+                },
                 &mut self.animation_rng,
             );
         }
         self.message_log.push(Message::FireGun(Item::Shotgun));
     }
 
+    fn fire_sniper_rifle(&mut self, target: Coord) {
+        let start = self.player_coord();
+        let target = line_2d::LineSegment::new(start, target)
+            .infinite_iter()
+            .nth(BULLET_RANGE)
+            .unwrap();
+        self.external_events.push(ExternalEvent::FirePistol(start));
+        self.world.spawn_bullet(
+            start,
+            target,
+            ProjectileDamage {
+                hit_points: 4..=6,
+                bleeding_probability: 0.0,
+                ricochet: false,
+                penetration: SNIPER_ROUND_PENETRATION,
+            },
+            &mut self.animation_rng,
+        );
+        self.message_log.push(Message::FireGun(Item::SniperRifle));
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
+    }
+
     fn fire_rocket(&mut self, target: Coord) {
         let start = self.player_coord();
-        self.external_events.push(ExternalEvent::FireRocket);
+        self.external_events.push(ExternalEvent::FireRocket(start));
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
         self.world
             .spawn_rocket(start, target, &mut self.animation_rng);
         self.message_log
             .push(Message::FireGun(Item::RocketLauncher));
     }
 
+    fn player_throw(&mut self, inventory_index: usize, target: Coord) -> Result<(), ActionError> {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        let item_entity = inventory
+            .get(inventory_index)
+            .ok_or(ActionError::NotThrowable)?;
+        let item = match self.world.components.item.get(item_entity).cloned() {
+            Some(item @ (Item::EmpGrenade | Item::SmokeGrenade)) => item,
+            _ => return Err(ActionError::NotThrowable),
+        };
+        inventory.remove(inventory_index);
+        self.world.remove_entity(item_entity);
+        let start = self.player_coord();
+        let target = self.throw_destination(target);
+        if item == Item::SmokeGrenade {
+            self.world
+                .spawn_smoke_grenade(start, target, SMOKE_GRENADE_RADIUS);
+        } else {
+            self.world
+                .spawn_emp_grenade(start, target, EMP_GRENADE_RANGE);
+        }
+        self.message_log.push(Message::Throw(item));
+        Ok(())
+    }
+
+    // The error firing the equipped weapon would fail with, without firing it
+    fn check_loaded_gun(&self) -> Result<(), ActionError> {
+        let player_hands = self.world.components.hands.get(self.player_entity).unwrap();
+        let guns = [player_hands.left.holding(), player_hands.right.holding()]
+            .into_iter()
+            .flatten()
+            .filter_map(|e| self.world.components.gun.get(e))
+            .collect::<Vec<_>>();
+        if guns.is_empty() {
+            Err(ActionError::NoGun)
+        } else if guns.iter().all(|gun| gun.ammo.is_empty()) {
+            Err(ActionError::OutOfLoadedAmmo)
+        } else {
+            Ok(())
+        }
+    }
+
     fn fire_equipped(&mut self, target: Coord) -> Result<(), ActionError> {
         let mut has_gun = false;
         let mut has_ammo = false;
@@ -1011,6 +3578,7 @@ impl Game {
                         GunType::Pistol => self.fire_pistol(target),
                         GunType::Shotgun => self.fire_shotgun(target),
                         GunType::RocketLauncher => self.fire_rocket(target),
+                        GunType::SniperRifle => self.fire_sniper_rifle(target),
                     }
                 }
             }
@@ -1026,6 +3594,7 @@ impl Game {
                         GunType::Pistol => self.fire_pistol(target),
                         GunType::Shotgun => self.fire_shotgun(target),
                         GunType::RocketLauncher => self.fire_rocket(target),
+                        GunType::SniperRifle => self.fire_sniper_rifle(target),
                     }
                 }
             }
@@ -1043,30 +3612,48 @@ impl Game {
         let start = self.player_coord();
         let target = line_2d::LineSegment::new(start, target)
             .infinite_iter()
-            .nth(20)
+            .nth(BULLET_RANGE)
             .unwrap();
-        self.external_events.push(ExternalEvent::FirePistol);
+        self.external_events.push(ExternalEvent::FirePistol(start));
         self.world.spawn_bullet(
             start,
             target,
-            ProjectileDamage { hit_points: 1..=2 },
+            ProjectileDamage {
+                hit_points: 1..=2,
+                bleeding_probability: 0.0,
+                ricochet: false,
+                penetration: 0,
+            },
             &mut self.animation_rng,
         );
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
     }
 
     fn fire_body_shotgun(&mut self, target: Coord) {
         let start = self.player_coord();
-        self.external_events.push(ExternalEvent::FireShotgun);
+        self.external_events.push(ExternalEvent::FireShotgun(start));
+        let spread = self.shotgun_spread();
+        self.world
+            .train_player_skill(Skill::Guns, &mut self.message_log);
         for _ in 0..8 {
             let angle = Radians::random(&mut self.rng);
-            let target = Radial { angle, length: 3.0 }
-                .to_cartesian()
-                .to_coord_round_nearest()
+            let target = Radial {
+                angle,
+                length: spread,
+            }
+            .to_cartesian()
+            .to_coord_round_nearest()
                 + target;
             self.world.spawn_bullet(
                 start,
                 target,
-                ProjectileDamage { hit_points: 2..=3 },
+                ProjectileDamage {
+                    hit_points: 2..=3,
+                    bleeding_probability: SHOTGUN_CRIT_BLEEDING_PROBABILITY,
+                    ricochet: false,
+                    penetration: 0,
+                },
                 &mut self.animation_rng,
             );
         }
@@ -1111,7 +3698,7 @@ impl Game {
         self.message_log.push(Message::FireOrganDamage(health_cost));
         self.world.damage_character(
             self.player_entity,
-            health_cost,
+            DamageRoll::fixed(health_cost),
             &mut self.rng,
             &mut self.external_events,
             &mut self.message_log,
@@ -1124,9 +3711,38 @@ impl Game {
         &mut self,
         input: Input,
     ) -> Result<Option<GameControlFlow>, ActionError> {
+        self.record_input(input);
+        // the stance only lasts until the player's next turn
+        self.overwatch = None;
+        self.player_activity = match input {
+            Input::Wait | Input::Overwatch(_) => Activity::Still,
+            Input::FireEquipped(_) | Input::FireBody(_) => Activity::Noisy,
+            // these don't take a turn
+            Input::ToggleDroneControl | Input::ToggleSprint => self.player_activity,
+            _ => Activity::Moving,
+        };
+        let player_walking = !self.controlling_drone && matches!(input, Input::Walk(_));
         let game_control_flow = match input {
+            Input::Walk(direction) if self.controlling_drone => {
+                if let Err(action_error) = self.drone_walk(direction) {
+                    self.message_log.push(Message::ActionError(action_error));
+                    return Err(action_error);
+                }
+                None
+            }
             Input::Walk(direction) => {
-                let result = self.player_walk(direction);
+                let direction = self.psychosis_stumble(direction);
+                let start_coord = self.player_coord();
+                let result = self.player_walk(direction).and_then(|game_control_flow| {
+                    if game_control_flow.is_none()
+                        && self.sprinting
+                        && self.player_coord() == start_coord + direction.coord()
+                    {
+                        self.player_sprint_step(direction)
+                    } else {
+                        Ok(game_control_flow)
+                    }
+                });
                 match result {
                     Ok(x) => x,
                     Err(action_error) => {
@@ -1150,6 +3766,7 @@ impl Game {
                     self.message_log.push(Message::ActionError(e));
                     return Err(e);
                 }
+                self.trip_alarm_sensors();
                 None
             }
             Input::FireBody(target) => {
@@ -1162,6 +3779,22 @@ impl Game {
                     self.message_log.push(Message::ActionError(e));
                     return Err(e);
                 }
+                self.trip_alarm_sensors();
+                None
+            }
+            Input::Throw {
+                inventory_index,
+                target,
+            } => {
+                if target == self.player_coord() {
+                    self.message_log
+                        .push(Message::ActionError(ActionError::RefusingToTargetSelf));
+                    return Err(ActionError::RefusingToTargetSelf);
+                }
+                if let Err(e) = self.player_throw(inventory_index, target) {
+                    self.message_log.push(Message::ActionError(e));
+                    return Err(e);
+                }
                 None
             }
             Input::Get => {
@@ -1178,23 +3811,43 @@ impl Game {
                     .hands
                     .get_mut(self.player_entity)
                     .unwrap();
-                if player_hands.left.is_holding() && player_hands.right.is_holding() {
-                    return Ok(Some(GameControlFlow::Menu(Menu {
-                        image: None,
-                        text: "Unequip from which hand? (escape to cancel)".to_string(),
-                        choices: vec![
-                            MenuChoice::UnequipWhichHand(WhichHand::Left),
-                            MenuChoice::UnequipWhichHand(WhichHand::Right),
-                        ],
-                    })));
-                } else if player_hands.left.is_holding() {
-                    self.unequip_from_hand(WhichHand::Left)
-                } else if player_hands.right.is_holding() {
-                    self.unequip_from_hand(WhichHand::Right)
-                } else {
-                    let e = ActionError::NothingToUnequip;
-                    self.message_log.push(Message::ActionError(e));
-                    return Err(e);
+                let mut choices = Vec::new();
+                if player_hands.left.is_holding() {
+                    choices.push(MenuChoice::UnequipWhichHand(WhichHand::Left));
+                }
+                if player_hands.right.is_holding() {
+                    choices.push(MenuChoice::UnequipWhichHand(WhichHand::Right));
+                }
+                let num_hand_choices = choices.len();
+                if let Some(worn) = self.world.components.worn.get(self.player_entity) {
+                    for slot in [WearSlot::Head, WearSlot::Body] {
+                        if worn.slot(slot).is_some() {
+                            choices.push(MenuChoice::TakeOff(slot));
+                        }
+                    }
+                }
+                match choices.as_slice() {
+                    [] => {
+                        let e = ActionError::NothingToUnequip;
+                        self.message_log.push(Message::ActionError(e));
+                        return Err(e);
+                    }
+                    &[MenuChoice::UnequipWhichHand(which_hand)] => {
+                        self.unequip_from_hand(which_hand)
+                    }
+                    &[MenuChoice::TakeOff(slot)] => self.take_off(slot),
+                    _ => {
+                        let text = if choices.len() == num_hand_choices {
+                            "Unequip from which hand? (escape to cancel)"
+                        } else {
+                            "Unequip what? (escape to cancel)"
+                        };
+                        return Ok(Some(GameControlFlow::Menu(Menu {
+                            image: None,
+                            text: text.to_string(),
+                            choices,
+                        })));
+                    }
                 }
                 None
             }
@@ -1203,12 +3856,71 @@ impl Game {
                     self.message_log.push(Message::ActionError(e));
                     return Err(e);
                 }
+                if self.has_perk(Perk::QuickReload) {
+                    return Ok(None);
+                }
+                None
+            }
+            Input::ToggleDroneControl => {
+                if self.drone_entity.is_none() {
+                    let e = ActionError::NoDrone;
+                    self.message_log.push(Message::ActionError(e));
+                    return Err(e);
+                }
+                self.controlling_drone = !self.controlling_drone;
+                if self.controlling_drone {
+                    self.message_log.push(Message::ControlDrone);
+                } else {
+                    self.message_log.push(Message::ControlPlayer);
+                }
+                // switching control doesn't take a turn
+                return Ok(None);
+            }
+            Input::ToggleSprint => {
+                self.sprinting = !self.sprinting;
+                if self.sprinting {
+                    self.message_log.push(Message::StartSprinting);
+                } else {
+                    self.message_log.push(Message::StopSprinting);
+                }
+                // breaking into a sprint doesn't take a turn
+                return Ok(None);
+            }
+            Input::Overwatch(direction) => {
+                if let Err(e) = self.check_loaded_gun() {
+                    self.message_log.push(Message::ActionError(e));
+                    return Err(e);
+                }
+                self.message_log.push(Message::Overwatch(direction));
+                self.overwatch = Some(direction);
                 None
             }
         };
         if game_control_flow.is_some() {
             return Ok(game_control_flow);
         }
+        // swimming is slow so each move through water takes an extra turn
+        if player_walking
+            && self.world.is_water_at(self.player_coord())
+            && !self.is_gameplay_blocked()
+        {
+            let game_control_flow = self.npc_turn();
+            if game_control_flow.is_some() {
+                return Ok(game_control_flow);
+            }
+        }
+        // climbing is slower still
+        if player_walking && self.world.is_difficult_at(self.player_coord()) {
+            for _ in 0..CLIMB_EXTRA_TURNS {
+                if self.is_gameplay_blocked() {
+                    break;
+                }
+                let game_control_flow = self.npc_turn();
+                if game_control_flow.is_some() {
+                    return Ok(game_control_flow);
+                }
+            }
+        }
         if !self.is_gameplay_blocked() {
             let game_control_flow = self.npc_turn();
             if game_control_flow.is_some() {
@@ -1216,7 +3928,7 @@ impl Game {
             }
         }
         self.update_visibility();
-        Ok(self.check_game_over())
+        Ok(self.check_game_over().or_else(|| self.check_level_up()))
     }
 
     fn player_reload_pistol(&mut self) -> Result<(), ActionError> {
@@ -1308,6 +4020,33 @@ impl Game {
         }
     }
 
+    fn player_reload_sniper_rifle(&mut self) -> Result<(), ActionError> {
+        if self
+            .get_appropriate_gun_ammo(WhichHand::Left, GunType::SniperRifle)
+            .is_some()
+        {
+            if let Some(index) = self.world.player_inventory_item_index(Item::SniperRounds) {
+                self.message_log.push(Message::ReloadGun(Item::SniperRifle));
+                self.reload_gun_in_hand(WhichHand::Left);
+                if let Some(entity) = self
+                    .world
+                    .components
+                    .inventory
+                    .get_mut(self.player_entity)
+                    .unwrap()
+                    .remove(index)
+                {
+                    self.world.remove_entity(entity);
+                }
+                Ok(())
+            } else {
+                Err(ActionError::OutOfAmmo)
+            }
+        } else {
+            Err(ActionError::NothingToReload)
+        }
+    }
+
     fn player_reload(&mut self) -> Result<(), ActionError> {
         if self
             .get_appropriate_gun_ammo(WhichHand::Left, GunType::Pistol)
@@ -1327,6 +4066,11 @@ impl Game {
             .is_some()
         {
             return self.player_reload_rocket_launcher();
+        } else if self
+            .get_appropriate_gun_ammo(WhichHand::Left, GunType::SniperRifle)
+            .is_some()
+        {
+            return self.player_reload_sniper_rifle();
         }
         Err(ActionError::NothingToReload)
     }
@@ -1335,6 +4079,12 @@ impl Game {
         let player_coord = self.player_coord();
         let layers = self.world.spatial_table.layers_at_checked(player_coord);
         if let Some(item_entity) = layers.item {
+            if self.world.components.player_remains.contains(item_entity) {
+                return self.player_recover_remains(item_entity);
+            }
+            if self.world.components.corpse.contains(item_entity) {
+                return self.player_butcher_corpse(item_entity, player_coord);
+            }
             if let Some(money) = self.world.components.money_item.get(item_entity).cloned() {
                 *self
                     .world
@@ -1355,7 +4105,15 @@ impl Game {
                 if let Some(slot) = inventry.first_free_slot() {
                     *slot = Some(item_entity);
                     self.world.spatial_table.remove(item_entity);
-                    self.message_log.push(Message::GetItem(item));
+                    if let Some(vendor) = self.world.components.for_sale.remove(item_entity) {
+                        self.world
+                            .components
+                            .stolen_from
+                            .insert(item_entity, vendor);
+                        self.message_log.push(Message::StealItem(item));
+                    } else {
+                        self.message_log.push(Message::GetItem(item));
+                    }
                 } else {
                     return Err(ActionError::InventoryIsFull);
                 }
@@ -1366,15 +4124,105 @@ impl Game {
         }
     }
 
+    /// Take back whatever fits in the player's inventory from the body of their previous clone.
+    /// The body is left behind until everything has been recovered.
+    fn player_recover_remains(&mut self, remains_entity: Entity) -> Result<(), ActionError> {
+        let remaining = self
+            .world
+            .components
+            .simple_inventory
+            .get_mut(remains_entity)
+            .unwrap();
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        let mut num_recovered = 0;
+        while let Some(&item_entity) = remaining.last() {
+            match inventory.first_free_slot() {
+                Some(slot) => *slot = Some(item_entity),
+                None => break,
+            }
+            remaining.pop();
+            num_recovered += 1;
+        }
+        if remaining.is_empty() {
+            self.world.remove_entity(remains_entity);
+        }
+        if num_recovered == 0 {
+            return Err(ActionError::InventoryIsFull);
+        }
+        self.message_log
+            .push(Message::RecoverRemains(num_recovered));
+        Ok(())
+    }
+
+    /// Cut a piece of raw meat from a corpse. Robots have nothing worth eating, and each corpse
+    /// can only be butchered once.
+    fn player_butcher_corpse(
+        &mut self,
+        corpse_entity: Entity,
+        coord: Coord,
+    ) -> Result<(), ActionError> {
+        if self.world.components.robotic.contains(corpse_entity)
+            || self.world.components.butchered.contains(corpse_entity)
+        {
+            return Err(ActionError::NothingToGet);
+        }
+        let npc_type = match self.world.components.npc_type.get(corpse_entity) {
+            Some(&npc_type) => npc_type,
+            None => return Err(ActionError::NothingToGet),
+        };
+        if self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap()
+            .items()
+            .iter()
+            .all(Option::is_some)
+        {
+            return Err(ActionError::InventoryIsFull);
+        }
+        let meat = self.world.spawn_item_no_coord(Item::RawMeat);
+        if let Some(slot) = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap()
+            .first_free_slot()
+        {
+            *slot = Some(meat);
+        }
+        self.world.components.butchered.insert(corpse_entity, ());
+        self.world.make_floor_bloody(coord);
+        self.message_log.push(Message::ButcherCorpse(npc_type));
+        Ok(())
+    }
+
     pub(crate) fn handle_choice(&mut self, choice: MenuChoice) -> Option<GameControlFlow> {
+        self.record_input(choice);
         match choice {
             MenuChoice::Empty => (),
             MenuChoice::Dummy => panic!(),
             MenuChoice::DropItem(i) => self.player_drop_item(i),
             MenuChoice::ApplyItem(i) => {
+                let item = self.inventory_item(i);
+                let num_messages = self.message_log.len();
                 if let Some(control_flow) = self.player_apply_item(i) {
                     return Some(control_flow);
                 }
+                // failing to apply an item is reported in the message log
+                let applied = !self.message_log[num_messages..]
+                    .iter()
+                    .any(|message| matches!(message, Message::ActionError(_)));
+                if let (Some(item), true) = (item, applied) {
+                    self.run_script_hook(|script_hooks, api| script_hooks.on_item_use(item, api));
+                }
             }
             MenuChoice::HarvestOrgan {
                 inventory_index,
@@ -1385,6 +4233,7 @@ impl Game {
                 inventory_index,
             } => self.player_equip_weapon_in_hand(which_hand, inventory_index),
             MenuChoice::UnequipWhichHand(which_hand) => self.unequip_from_hand(which_hand),
+            MenuChoice::TakeOff(slot) => self.take_off(slot),
             MenuChoice::BuyItem {
                 item,
                 shop_entity,
@@ -1392,9 +4241,29 @@ impl Game {
                 shop_inventory_index,
                 ..
             } => self.player_buy_item(item, item_entity, shop_entity, shop_inventory_index),
-            MenuChoice::ClinicBuy { clinic_entity } => {
-                return Some(GameControlFlow::Menu(self.clinic_buy_menu(clinic_entity)))
-            }
+            MenuChoice::BuyOrgan {
+                organ,
+                hot,
+                shop_entity,
+                item_entity,
+                shop_inventory_index,
+            } => self.player_buy_black_market_organ(
+                organ,
+                hot,
+                item_entity,
+                shop_entity,
+                shop_inventory_index,
+            ),
+            // the clinic's services can only be used while standing at the clinic
+            MenuChoice::ClinicRemove
+            | MenuChoice::ClinicInstallFromContainer
+            | MenuChoice::ClinicBuyInsurance
+            | MenuChoice::ClinicRemoveOrgan { .. }
+            | MenuChoice::ClinicInstallFromContainerOrgan { .. }
+                if !self.is_player_at_clinic() => {}
+            MenuChoice::ClinicBuy { clinic_entity } => {
+                return Some(GameControlFlow::Menu(self.clinic_buy_menu(clinic_entity)))
+            }
             MenuChoice::ClinicRemove => {
                 return Some(GameControlFlow::Menu(self.clinic_remove_menu()))
             }
@@ -1403,6 +4272,7 @@ impl Game {
                     self.clinic_install_from_container_menu(),
                 ))
             }
+            MenuChoice::ClinicBuyInsurance => self.clinic_buy_insurance(),
             MenuChoice::ClinicBuyOrgan {
                 clinic_entity,
                 index,
@@ -1415,20 +4285,152 @@ impl Game {
                 inventory_index,
                 organ,
             } => self.clinic_install_from_container(inventory_index, organ),
+            MenuChoice::FieldSurgeryInstall {
+                kit_inventory_index,
+                container_inventory_index,
+                organ,
+            } => {
+                if let Some(control_flow) = self.player_field_surgery(
+                    kit_inventory_index,
+                    organ,
+                    FieldSurgery::Install {
+                        container_inventory_index,
+                    },
+                ) {
+                    return Some(control_flow);
+                }
+            }
+            MenuChoice::FieldSurgeryRemove {
+                kit_inventory_index,
+                organ,
+                index,
+            } => {
+                if let Some(control_flow) = self.player_field_surgery(
+                    kit_inventory_index,
+                    organ,
+                    FieldSurgery::Remove { index },
+                ) {
+                    return Some(control_flow);
+                }
+            }
+            MenuChoice::Sell { shop_entity } => {
+                return Some(GameControlFlow::Menu(self.sell_menu(shop_entity)))
+            }
+            MenuChoice::SellItem {
+                item,
+                shop_entity,
+                inventory_index,
+            } => self.player_sell_item(item, shop_entity, inventory_index),
+            MenuChoice::SellOrgan {
+                organ,
+                shop_entity,
+                inventory_index,
+            } => self.player_sell_organ(organ, shop_entity, inventory_index),
+            MenuChoice::StashDeposit { stash_entity } => {
+                return Some(GameControlFlow::Menu(self.stash_deposit_menu(stash_entity)))
+            }
+            MenuChoice::StashWithdraw { stash_entity } => {
+                return Some(GameControlFlow::Menu(
+                    self.stash_withdraw_menu(stash_entity),
+                ))
+            }
+            MenuChoice::StashDepositItem {
+                item,
+                stash_entity,
+                inventory_index,
+            } => self.player_stash_deposit(item, stash_entity, inventory_index),
+            MenuChoice::StashWithdrawItem {
+                item,
+                stash_entity,
+                stash_index,
+            } => self.player_stash_withdraw(item, stash_entity, stash_index),
+            MenuChoice::HireMercenary {
+                mercenary_entity,
+                price,
+            } => self.player_hire_mercenary(mercenary_entity, price),
+            MenuChoice::GiveMercenaryGun { mercenary_entity } => {
+                return Some(GameControlFlow::Menu(
+                    self.give_mercenary_gun_menu(mercenary_entity),
+                ))
+            }
+            MenuChoice::GiveMercenaryGunItem {
+                item,
+                mercenary_entity,
+                inventory_index,
+            } => self.player_give_mercenary_gun(item, mercenary_entity, inventory_index),
+            MenuChoice::SwapWithMercenary { mercenary_entity } => {
+                self.player_swap_with_mercenary(mercenary_entity)
+            }
+            MenuChoice::ChoosePerk(perk) => {
+                // choosing a perk doesn't take a turn
+                self.player_choose_perk(perk);
+                return self.check_level_up();
+            }
+            MenuChoice::ScriptAction(index) => {
+                self.run_script_hook(|script_hooks, api| script_hooks.on_menu_action(index, api));
+            }
         }
         self.npc_turn();
         self.check_game_over()
     }
 
-    fn clinic_install_from_container(&mut self, inventory_index: usize, organ: Organ) {
-        let price = organ.container_install_cost();
-        let money = self
+    fn player_choose_perk(&mut self, perk: Perk) {
+        let experience = self
             .world
             .components
-            .money
+            .experience
             .get_mut(self.player_entity)
             .unwrap();
-        if *money < price {
+        if !experience.available_perks().contains(&perk) {
+            return;
+        }
+        experience.perks.push(perk);
+        match perk {
+            Perk::ExtraOrganSlot => self
+                .world
+                .components
+                .organs
+                .get_mut(self.player_entity)
+                .unwrap()
+                .add_slot(),
+            Perk::QuickReload => (),
+            Perk::PoisonResistance => {
+                let poison = self
+                    .world
+                    .components
+                    .poison
+                    .get_mut(self.player_entity)
+                    .unwrap();
+                poison.set_max(poison.max() + POISON_RESISTANCE_BONUS);
+            }
+        }
+        self.message_log.push(Message::ChoosePerk(perk));
+    }
+
+    // Level-ups are spent on perks once nothing is animating
+    fn check_level_up(&self) -> Option<GameControlFlow> {
+        if self.demo || self.is_gameplay_blocked() {
+            return None;
+        }
+        let perks = self
+            .world
+            .components
+            .experience
+            .get(self.player_entity)?
+            .available_perks();
+        if perks.is_empty() {
+            return None;
+        }
+        Some(GameControlFlow::Menu(Menu {
+            image: None,
+            text: "You feel more experienced. Choose a perk:".to_string(),
+            choices: perks.into_iter().map(MenuChoice::ChoosePerk).collect(),
+        }))
+    }
+
+    fn clinic_install_from_container(&mut self, inventory_index: usize, organ: Organ) {
+        let price = self.organ_install_cost();
+        if self.player_money() < price {
             self.message_log.push(Message::CantAffordGeneral);
             return;
         }
@@ -1436,20 +4438,41 @@ impl Game {
             .world
             .components
             .organs
-            .get_mut(self.player_entity)
+            .get(self.player_entity)
             .unwrap();
         if organs.num_free_slots() == 0 {
             self.message_log.push(Message::NoSpaceForOrgan(organ));
             return;
         }
-        *money -= price;
+        if !self.perform_surgery(&organ, self.surgery_failure_probability(&organ)) {
+            return;
+        }
+        *self
+            .world
+            .components
+            .money
+            .get_mut(self.player_entity)
+            .unwrap() -= price;
+        self.install_organ_from_container(inventory_index, organ);
+    }
+
+    fn install_organ_from_container(&mut self, inventory_index: usize, organ: Organ) {
+        let organs = self
+            .world
+            .components
+            .organs
+            .get_mut(self.player_entity)
+            .unwrap();
         let inventory = self
             .world
             .components
             .inventory
             .get_mut(self.player_entity)
             .unwrap();
-        let container = inventory.get(inventory_index).unwrap();
+        let container = match inventory.get(inventory_index) {
+            Some(container) => container,
+            None => return,
+        };
         let item = self.world.components.item.get_mut(container).unwrap();
         if let Item::OrganContainer(Some(organ)) = item {
             *organs.first_free_slot().unwrap() = Some(*organ);
@@ -1463,7 +4486,23 @@ impl Game {
     }
 
     fn clinic_remove_organ(&mut self, organ: Organ, index: usize) {
-        let price = organ.remove_price();
+        let organs = self
+            .world
+            .components
+            .organs
+            .get(self.player_entity)
+            .unwrap();
+        if organs.organs().get(index) != Some(&Some(organ)) {
+            return;
+        }
+        let price = self.organ_remove_price(&organ);
+        if price > 0 && self.player_money() < price as u32 {
+            self.message_log.push(Message::CantAffordGeneral);
+            return;
+        }
+        if !self.perform_surgery(&organ, self.surgery_failure_probability(&organ)) {
+            return;
+        }
         let money = self
             .world
             .components
@@ -1473,25 +4512,34 @@ impl Game {
         if price < 0 {
             *money += (-price) as u32;
         } else {
-            let price = price as u32;
-            if *money < price {
-                self.message_log.push(Message::CantAffordGeneral);
-                return;
-            }
-            *money -= price;
+            *money -= price as u32;
         }
         self.message_log.push(Message::RemoveOrgan(organ));
-        let organs = self
-            .world
+        self.world
             .components
             .organs
             .get_mut(self.player_entity)
-            .unwrap();
-        *organs.get_slot_mut(index) = None;
+            .unwrap()
+            .remove(index);
     }
 
-    fn clinic_buy_organ(&mut self, clinic_entity: Entity, index: usize, organ: Organ) {
-        let price = organ.player_buy_price();
+    pub fn clone_insurance_price(&self) -> u32 {
+        CLONE_INSURANCE_PRICE
+    }
+
+    pub fn has_clone_insurance(&self) -> bool {
+        self.world
+            .components
+            .clone_insurance
+            .contains(self.player_entity)
+    }
+
+    fn clinic_buy_insurance(&mut self) {
+        if self.has_clone_insurance() {
+            self.message_log.push(Message::AlreadyInsured);
+            return;
+        }
+        let price = self.clone_insurance_price();
         let money = self
             .world
             .components
@@ -1502,18 +4550,48 @@ impl Game {
             self.message_log.push(Message::CantAffordGeneral);
             return;
         }
+        *money -= price;
+        self.world
+            .components
+            .clone_insurance
+            .insert(self.player_entity, ());
+        self.message_log.push(Message::BuyInsurance(price));
+    }
+
+    fn clinic_buy_organ(&mut self, clinic_entity: Entity, index: usize, organ: Organ) {
+        let price = self.organ_buy_price(&organ);
+        if self.player_money() < price {
+            self.message_log.push(Message::CantAffordGeneral);
+            return;
+        }
         let organs = self
             .world
             .components
             .organs
-            .get_mut(self.player_entity)
+            .get(self.player_entity)
             .unwrap();
         if organs.num_free_slots() == 0 {
             self.message_log.push(Message::NoSpaceForOrgan(organ));
             return;
         }
-        *money -= price;
-        *organs.first_free_slot().unwrap() = Some(organ);
+        if !self.perform_surgery(&organ, self.surgery_failure_probability(&organ)) {
+            return;
+        }
+        *self
+            .world
+            .components
+            .money
+            .get_mut(self.player_entity)
+            .unwrap() -= price;
+        *self
+            .world
+            .components
+            .organs
+            .get_mut(self.player_entity)
+            .unwrap()
+            .first_free_slot()
+            .unwrap() = Some(organ);
+        self.organ_market.record_purchase(organ.type_);
         self.message_log.push(Message::InstallOrgan(organ));
         let clinic_organs = self
             .world
@@ -1524,6 +4602,207 @@ impl Game {
         clinic_organs.remove(index);
     }
 
+    pub fn surgery_failure_probability(&self, organ: &Organ) -> f64 {
+        let mut probability = SURGERY_BASE_FAILURE_PROBABILITY;
+        if organ.traits.damaged {
+            probability += 0.1;
+        }
+        if organ.traits.embedded {
+            probability += 0.1;
+        }
+        if organ.traits.radioactitve {
+            probability += 0.05;
+        }
+        if organ.cybernetic {
+            probability += 0.05;
+        }
+        let health = self
+            .world
+            .components
+            .health
+            .get(self.player_entity)
+            .unwrap();
+        if health.max() > 0 {
+            probability += 0.3 * (1.0 - (health.current() as f64 / health.max() as f64));
+        }
+        probability *= 1.0
+            - (self.player_skill_level(Skill::Surgery) as f64
+                * SURGERY_SKILL_FAILURE_REDUCTION_PER_LEVEL);
+        probability.min(SURGERY_MAX_FAILURE_PROBABILITY)
+    }
+
+    // Operating on the player uses up an anesthetic they carry, or hurts if they don't have one,
+    // and leaves them recovering for a while. Returns false if the surgery failed, in which case
+    // the clinic doesn't charge for it.
+    fn perform_surgery(&mut self, organ: &Organ, failure_probability: f64) -> bool {
+        let anesthetic_index =
+            (0..self.inventory_size()).find(|&i| self.inventory_item(i) == Some(Item::Anesthetic));
+        let mut damage = 0;
+        if let Some(anesthetic_index) = anesthetic_index {
+            let inventory = self
+                .world
+                .components
+                .inventory
+                .get_mut(self.player_entity)
+                .unwrap();
+            if let Some(anesthetic_entity) = inventory.remove(anesthetic_index) {
+                self.world.remove_entity(anesthetic_entity);
+            }
+        } else {
+            damage += SURGERY_WITHOUT_ANESTHETIC_DAMAGE;
+            self.message_log.push(Message::SurgeryWithoutAnesthetic);
+        }
+        let success = !self.rng.gen_bool(failure_probability);
+        self.world
+            .train_player_skill(Skill::Surgery, &mut self.message_log);
+        if !success {
+            damage += SURGERY_FAILURE_DAMAGE;
+            self.message_log.push(Message::SurgeryFailed(*organ));
+        }
+        self.world
+            .components
+            .health
+            .get_mut(self.player_entity)
+            .unwrap()
+            .decrease(damage);
+        self.world.components.surgery_recovery.insert(
+            self.player_entity,
+            Meter::new(SURGERY_RECOVERY_TURNS, SURGERY_RECOVERY_TURNS),
+        );
+        success
+    }
+
+    pub fn field_surgery_failure_probability(&self, organ: &Organ) -> f64 {
+        (self.surgery_failure_probability(organ) * FIELD_SURGERY_RISK_MULTIPLIER)
+            .min(FIELD_SURGERY_MAX_FAILURE_PROBABILITY)
+    }
+
+    // Returns `None` if there's nothing the kit can be used for
+    fn field_surgery_menu(&self, kit_inventory_index: usize) -> Option<Menu> {
+        let mut choices = Vec::new();
+        let organs = self
+            .world
+            .components
+            .organs
+            .get(self.player_entity)
+            .unwrap();
+        if organs.num_free_slots() > 0 {
+            let inventory = self
+                .world
+                .components
+                .inventory
+                .get(self.player_entity)
+                .unwrap();
+            for (i, slot) in inventory.items().iter().enumerate() {
+                if let Some(item_entity) = slot {
+                    if let Some(Item::OrganContainer(Some(organ))) =
+                        self.world.components.item.get(*item_entity)
+                    {
+                        choices.push(MenuChoice::FieldSurgeryInstall {
+                            kit_inventory_index,
+                            container_inventory_index: i,
+                            organ: *organ,
+                        });
+                    }
+                }
+            }
+        }
+        for (i, organ) in organs.organs().iter().enumerate() {
+            if let Some(organ) = organ {
+                choices.push(MenuChoice::FieldSurgeryRemove {
+                    kit_inventory_index,
+                    organ: *organ,
+                    index: i,
+                });
+            }
+        }
+        if choices.is_empty() {
+            return None;
+        }
+        Some(Menu {
+            text: format!(
+                "Operate on yourself? This takes {} turns and any injury will interrupt it. (escape to cancel)",
+                FIELD_SURGERY_TURNS
+            ),
+            choices,
+            image: None,
+        })
+    }
+
+    // The surgery takes several turns, and is abandoned if the player is hurt before it's done
+    fn player_field_surgery(
+        &mut self,
+        kit_inventory_index: usize,
+        organ: Organ,
+        operation: FieldSurgery,
+    ) -> Option<GameControlFlow> {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(kit_entity) = inventory.remove(kit_inventory_index) {
+            self.world.remove_entity(kit_entity);
+        }
+        self.message_log.push(Message::FieldSurgeryBegin);
+        let initial_health = self
+            .world
+            .components
+            .health
+            .get(self.player_entity)
+            .unwrap()
+            .current();
+        // the final turn is taken after the menu choice is handled
+        for _ in 1..FIELD_SURGERY_TURNS {
+            if self.is_gameplay_blocked() {
+                break;
+            }
+            if let Some(control_flow) = self.npc_turn() {
+                return Some(control_flow);
+            }
+            let health = self
+                .world
+                .components
+                .health
+                .get(self.player_entity)
+                .unwrap()
+                .current();
+            if health < initial_health {
+                break;
+            }
+        }
+        let health = self
+            .world
+            .components
+            .health
+            .get(self.player_entity)
+            .unwrap()
+            .current();
+        if health < initial_health || self.is_gameplay_blocked() {
+            self.message_log.push(Message::FieldSurgeryInterrupted);
+            return None;
+        }
+        if !self.perform_surgery(&organ, self.field_surgery_failure_probability(&organ)) {
+            return None;
+        }
+        match operation {
+            FieldSurgery::Install {
+                container_inventory_index,
+            } => self.install_organ_from_container(container_inventory_index, organ),
+            FieldSurgery::Remove { index } => {
+                self.message_log.push(Message::RemoveOrgan(organ));
+                self.world
+                    .components
+                    .organs
+                    .get_mut(self.player_entity)
+                    .unwrap()
+                    .remove(index);
+            }
+        }
+        None
+    }
+
     fn clinic_install_from_container_menu(&self) -> Menu {
         let mut choices = Vec::new();
         let inventory = self
@@ -1601,43 +4880,961 @@ impl Game {
         }
     }
 
-    fn player_buy_item(
-        &mut self,
-        item: Item,
-        item_entity: Entity,
-        shop_entity: Entity,
-        shop_inventory_index: usize,
-    ) {
-        let money = self
-            .world
-            .components
-            .money
-            .get_mut(self.player_entity)
-            .unwrap();
-        if item.price() > *money {
-            self.message_log.push(Message::CantAfford(item));
-            return;
-        }
-        let inventory = self
-            .world
-            .components
-            .inventory
-            .get_mut(self.player_entity)
-            .unwrap();
-        if let Some(first_free_slot) = inventory.first_free_slot() {
-            *money -= item.price();
-            *first_free_slot = Some(item_entity);
-            let shop_inventory = self
-                .world
-                .components
-                .simple_inventory
-                .get_mut(shop_entity)
-                .unwrap();
-            shop_inventory.remove(shop_inventory_index);
-            self.message_log.push(Message::Buy(item));
-        } else {
-            self.message_log
-                .push(Message::ActionError(ActionError::InventoryIsFull));
+    fn player_buy_item(
+        &mut self,
+        item: Item,
+        item_entity: Entity,
+        shop_entity: Entity,
+        shop_inventory_index: usize,
+    ) {
+        let price = self.item_price(item);
+        self.player_pay_for_item(item, price, item_entity, shop_entity, shop_inventory_index);
+    }
+
+    fn player_buy_black_market_organ(
+        &mut self,
+        organ: Organ,
+        hot: bool,
+        item_entity: Entity,
+        shop_entity: Entity,
+        shop_inventory_index: usize,
+    ) {
+        let item = Item::OrganContainer(Some(organ));
+        let price = self.organ_black_market_price(&organ, hot);
+        if !self.player_pay_for_item(item, price, item_entity, shop_entity, shop_inventory_index) {
+            return;
+        }
+        self.world.components.hot.remove(item_entity);
+        self.organ_market.record_purchase(organ.type_);
+        if hot && self.rng.gen_bool(HOT_ORGAN_DAMAGED_PROBABILITY) {
+            let mut organ = organ;
+            organ.traits.damaged = true;
+            let item = Item::OrganContainer(Some(organ));
+            self.world.components.item.insert(item_entity, item);
+            self.world
+                .components
+                .tile
+                .insert(item_entity, Tile::Item(item));
+            self.message_log.push(Message::HotOrganDamaged(organ));
+        }
+    }
+
+    // Returns true if the player could afford the item and had space for it
+    fn player_pay_for_item(
+        &mut self,
+        item: Item,
+        price: u32,
+        item_entity: Entity,
+        shop_entity: Entity,
+        shop_inventory_index: usize,
+    ) -> bool {
+        let money = self
+            .world
+            .components
+            .money
+            .get_mut(self.player_entity)
+            .unwrap();
+        if price > *money {
+            self.message_log.push(Message::CantAfford(item));
+            return false;
+        }
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(first_free_slot) = inventory.first_free_slot() {
+            *money -= price;
+            *first_free_slot = Some(item_entity);
+            let shop_inventory = self
+                .world
+                .components
+                .simple_inventory
+                .get_mut(shop_entity)
+                .unwrap();
+            shop_inventory.remove(shop_inventory_index);
+            // shops label the things they sell
+            self.identified_items.insert(item);
+            self.message_log.push(Message::Buy(item));
+            true
+        } else {
+            self.message_log
+                .push(Message::ActionError(ActionError::InventoryIsFull));
+            false
+        }
+    }
+
+    fn shop_buys_item(&self, shop_entity: Entity, item: Item) -> bool {
+        let npc_type = match self.world.components.npc_type.get(shop_entity) {
+            Some(&npc_type) => npc_type,
+            None => return false,
+        };
+        // The travelling merchant will take anything but organs, paperwork and rotten food off the
+        // player's hands
+        if npc_type == NpcType::Merchant {
+            return !matches!(
+                item,
+                Item::OrganContainer(Some(_)) | Item::Readable(_) | Item::RottenMeat
+            );
+        }
+        match item {
+            Item::Pistol
+            | Item::Shotgun
+            | Item::RocketLauncher
+            | Item::SniperRifle
+            | Item::PistolAmmo
+            | Item::ShotgunAmmo
+            | Item::Rocket
+            | Item::SniperRounds
+            | Item::Turret
+            | Item::EmpGrenade
+            | Item::SmokeGrenade
+            | Item::CursedPistol => npc_type == NpcType::GunStore,
+            Item::OrganContainer(Some(_)) => npc_type == NpcType::OrganTrader,
+            Item::Stimpack
+            | Item::Antidote
+            | Item::BloodVialEmpty
+            | Item::BloodVialFull
+            | Item::Battery
+            | Item::Food
+            | Item::RawMeat
+            | Item::CookedMeat
+            | Item::AntiRads
+            | Item::Drone
+            | Item::CorruptedStimpack
+            | Item::Scanner
+            | Item::GeigerCounter
+            | Item::Schematics
+            | Item::MarketReport
+            | Item::Plank
+            | Item::Anesthetic
+            | Item::FieldSurgeryKit
+            | Item::Immunosuppressant
+            | Item::Bandage
+            | Item::Antipsychotic
+            | Item::GasMask
+            | Item::HazardSuit
+            | Item::OrganContainer(None) => npc_type == NpcType::ItemStore,
+            Item::Readable(_) | Item::RottenMeat => false,
+        }
+    }
+
+    fn sell_menu(&self, shop_entity: Entity) -> Menu {
+        let mut choices = Vec::new();
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap();
+        for (i, slot) in inventory.items().iter().enumerate() {
+            if let Some(item_entity) = slot {
+                if let Some(&item) = self.world.components.item.get(*item_entity) {
+                    if !self.shop_buys_item(shop_entity, item) {
+                        continue;
+                    }
+                    if let Item::OrganContainer(Some(organ)) = item {
+                        choices.push(MenuChoice::SellOrgan {
+                            organ,
+                            shop_entity,
+                            inventory_index: i,
+                        });
+                    } else {
+                        choices.push(MenuChoice::SellItem {
+                            item,
+                            shop_entity,
+                            inventory_index: i,
+                        });
+                    }
+                }
+            }
+        }
+        if choices.is_empty() {
+            choices.push(MenuChoice::Empty);
+        }
+        Menu {
+            text: "Choose something to sell: (escape to cancel)".to_string(),
+            choices,
+            image: None,
+        }
+    }
+
+    fn player_sell_item(&mut self, item: Item, shop_entity: Entity, inventory_index: usize) {
+        let price = match self.inventory_item_sell_price(inventory_index) {
+            Some(price) => price,
+            None => return,
+        };
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            *self
+                .world
+                .components
+                .money
+                .get_mut(self.player_entity)
+                .unwrap() += price;
+            let shop_inventory = self
+                .world
+                .components
+                .simple_inventory
+                .get_mut(shop_entity)
+                .unwrap();
+            shop_inventory.push(item_entity);
+            self.message_log.push(Message::Sell { item, price });
+        }
+    }
+
+    fn player_sell_organ(&mut self, organ: Organ, shop_entity: Entity, inventory_index: usize) {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap();
+        let container = match inventory.get(inventory_index) {
+            Some(container) => container,
+            None => return,
+        };
+        let price = self.organ_sell_price(&organ);
+        *self
+            .world
+            .components
+            .money
+            .get_mut(self.player_entity)
+            .unwrap() += price;
+        self.world
+            .components
+            .item
+            .insert(container, Item::OrganContainer(None));
+        self.world
+            .components
+            .tile
+            .insert(container, Tile::Item(Item::OrganContainer(None)));
+        // The player keeps the empty container and the trader puts the organ up for sale in one of
+        // their own
+        let stock_entity = self
+            .world
+            .spawn_item_no_coord(Item::OrganContainer(Some(organ)));
+        self.world
+            .components
+            .simple_inventory
+            .get_mut(shop_entity)
+            .unwrap()
+            .push(stock_entity);
+        self.organ_market.record_sale(organ.type_);
+        self.message_log.push(Message::SellOrgan { organ, price });
+    }
+
+    fn stash_menu(&self, stash_entity: Entity) -> Menu {
+        Menu {
+            image: None,
+            text: "Your storage locker. Anything you leave here will be safe until you return."
+                .to_string(),
+            choices: vec![
+                MenuChoice::StashDeposit { stash_entity },
+                MenuChoice::StashWithdraw { stash_entity },
+            ],
+        }
+    }
+
+    fn stash_deposit_menu(&self, stash_entity: Entity) -> Menu {
+        let mut choices = Vec::new();
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap();
+        for (i, slot) in inventory.items().iter().enumerate() {
+            if let Some(item_entity) = slot {
+                if let Some(&item) = self.world.components.item.get(*item_entity) {
+                    choices.push(MenuChoice::StashDepositItem {
+                        item,
+                        stash_entity,
+                        inventory_index: i,
+                    });
+                }
+            }
+        }
+        if choices.is_empty() {
+            choices.push(MenuChoice::Empty);
+        }
+        Menu {
+            text: "Choose an item to deposit: (escape to cancel)".to_string(),
+            choices,
+            image: None,
+        }
+    }
+
+    fn stash_withdraw_menu(&self, stash_entity: Entity) -> Menu {
+        let mut choices = Vec::new();
+        let stash = self.world.components.inventory.get(stash_entity).unwrap();
+        for (i, slot) in stash.items().iter().enumerate() {
+            if let Some(item_entity) = slot {
+                if let Some(&item) = self.world.components.item.get(*item_entity) {
+                    choices.push(MenuChoice::StashWithdrawItem {
+                        item,
+                        stash_entity,
+                        stash_index: i,
+                    });
+                }
+            }
+        }
+        if choices.is_empty() {
+            choices.push(MenuChoice::Empty);
+        }
+        Menu {
+            text: "Choose an item to withdraw: (escape to cancel)".to_string(),
+            choices,
+            image: None,
+        }
+    }
+
+    fn player_stash_deposit(&mut self, item: Item, stash_entity: Entity, inventory_index: usize) {
+        let stash = self
+            .world
+            .components
+            .inventory
+            .get_mut(stash_entity)
+            .unwrap();
+        if stash.first_free_slot().is_none() {
+            self.message_log.push(Message::StashIsFull);
+            return;
+        }
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            let stash = self
+                .world
+                .components
+                .inventory
+                .get_mut(stash_entity)
+                .unwrap();
+            *stash.first_free_slot().unwrap() = Some(item_entity);
+            self.message_log.push(Message::StashDeposit(item));
+        }
+    }
+
+    fn player_stash_withdraw(&mut self, item: Item, stash_entity: Entity, stash_index: usize) {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if inventory.first_free_slot().is_none() {
+            self.message_log
+                .push(Message::ActionError(ActionError::InventoryIsFull));
+            return;
+        }
+        let stash = self
+            .world
+            .components
+            .inventory
+            .get_mut(stash_entity)
+            .unwrap();
+        if let Some(item_entity) = stash.remove(stash_index) {
+            let inventory = self
+                .world
+                .components
+                .inventory
+                .get_mut(self.player_entity)
+                .unwrap();
+            *inventory.first_free_slot().unwrap() = Some(item_entity);
+            self.message_log.push(Message::StashWithdraw(item));
+        }
+    }
+
+    fn mercenary_menu(&self, mercenary_entity: Entity) -> Menu {
+        // mercenaries give a discount to one of their own
+        let price = if self.background == Background::Mercenary {
+            MERCENARY_HIRE_PRICE / 2
+        } else {
+            MERCENARY_HIRE_PRICE
+        };
+        if self.world.is_ally(mercenary_entity) {
+            Menu {
+                image: None,
+                text: "What do you need, boss?".to_string(),
+                choices: vec![
+                    MenuChoice::GiveMercenaryGun { mercenary_entity },
+                    MenuChoice::SwapWithMercenary { mercenary_entity },
+                ],
+            }
+        } else {
+            Menu {
+                image: None,
+                text: format!("Need some muscle? I'll watch your back for {price} CCz. Hand me a gun and I'll even use it."),
+                choices: vec![MenuChoice::HireMercenary {
+                    mercenary_entity,
+                    price,
+                }],
+            }
+        }
+    }
+
+    fn player_hire_mercenary(&mut self, mercenary_entity: Entity, price: u32) {
+        let money = self
+            .world
+            .components
+            .money
+            .get_mut(self.player_entity)
+            .unwrap();
+        if *money < price {
+            self.message_log.push(Message::CantAffordGeneral);
+            return;
+        }
+        *money -= price;
+        if let Some(npc) = self.world.components.npc.get_mut(mercenary_entity) {
+            npc.disposition = Disposition::Ally;
+        }
+        self.message_log.push(Message::HireMercenary);
+    }
+
+    fn give_mercenary_gun_menu(&self, mercenary_entity: Entity) -> Menu {
+        let mut choices = Vec::new();
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap();
+        for (i, slot) in inventory.items().iter().enumerate() {
+            if let Some(item_entity) = slot {
+                if self.world.components.gun.contains(*item_entity) {
+                    if let Some(&item) = self.world.components.item.get(*item_entity) {
+                        choices.push(MenuChoice::GiveMercenaryGunItem {
+                            item,
+                            mercenary_entity,
+                            inventory_index: i,
+                        });
+                    }
+                }
+            }
+        }
+        if choices.is_empty() {
+            choices.push(MenuChoice::Empty);
+        }
+        Menu {
+            text: "Choose a gun to give to your mercenary. They'll hand back any gun they're already carrying. (escape to cancel)".to_string(),
+            choices,
+            image: None,
+        }
+    }
+
+    fn player_give_mercenary_gun(
+        &mut self,
+        item: Item,
+        mercenary_entity: Entity,
+        inventory_index: usize,
+    ) {
+        let previous_gun = self.world.npc_gun_entity(mercenary_entity);
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(gun_entity) = inventory.remove(inventory_index) {
+            if let Some(previous_gun) = previous_gun {
+                inventory.items[inventory_index] = Some(previous_gun);
+            }
+            if let Some(simple_inventory) = self
+                .world
+                .components
+                .simple_inventory
+                .get_mut(mercenary_entity)
+            {
+                simple_inventory.retain(|&entity| Some(entity) != previous_gun);
+                simple_inventory.push(gun_entity);
+            }
+            self.message_log.push(Message::GiveMercenaryGun(item));
+        }
+    }
+
+    fn player_swap_with_mercenary(&mut self, mercenary_entity: Entity) {
+        let player_coord = self.player_coord();
+        if let Some(mercenary_coord) = self.world.entity_coord(mercenary_entity) {
+            self.world.spatial_table.remove(mercenary_entity);
+            self.world
+                .spatial_table
+                .update_coord(self.player_entity, mercenary_coord)
+                .unwrap();
+            self.world
+                .spatial_table
+                .update(
+                    mercenary_entity,
+                    Location {
+                        coord: player_coord,
+                        layer: Some(Layer::Character),
+                    },
+                )
+                .unwrap();
+            self.change_level_if_player_is_on_stairs();
+        }
+    }
+
+    fn player_deploy_drone(&mut self, inventory_index: usize) {
+        if self.drone_entity.is_some() {
+            self.message_log
+                .push(Message::ActionError(ActionError::DroneAlreadyDeployed));
+            return;
+        }
+        let player_coord = self.player_coord();
+        let coord = match self.world.nearest_characterless_coord(player_coord) {
+            Some(coord) => coord,
+            None => {
+                self.message_log
+                    .push(Message::ActionError(ActionError::InvalidMove));
+                return;
+            }
+        };
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            self.world.remove_entity(item_entity);
+            self.drone_entity = Some(self.world.spawn_drone(coord));
+            self.message_log.push(Message::DeployDrone);
+        }
+    }
+
+    // Identifies every other item in the player's inventory, revealing corrupted items for what
+    // they are. The scanner is used up in the process.
+    fn player_apply_scanner(&mut self, inventory_index: usize) {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get(self.player_entity)
+            .unwrap();
+        let entities = (0..inventory.size())
+            .filter(|&i| i != inventory_index)
+            .filter_map(|i| inventory.get(i))
+            .collect::<Vec<_>>();
+        let mut revealed = Vec::new();
+        for entity in entities {
+            let was_disguised = self.world.identify_item(entity).is_some();
+            let item = *self.world.components.item.get(entity).unwrap();
+            let was_unknown =
+                self.identified_items.insert(item) && item.unidentified_appearance().is_some();
+            if was_disguised || was_unknown {
+                revealed.push(item);
+            }
+        }
+        if revealed.is_empty() {
+            self.message_log
+                .push(Message::ActionError(ActionError::NothingToScan));
+            return;
+        }
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            self.world.remove_entity(item_entity);
+        }
+        self.message_log.push(Message::Scan(revealed));
+    }
+
+    // Marks the layout of the current level as previously seen. Cells the player has already seen
+    // are left alone so their remembered contents aren't lost.
+    fn player_read_schematics(&mut self, inventory_index: usize) {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            self.world.remove_entity(item_entity);
+        }
+        let unseen = self
+            .visibility_grid
+            .enumerate()
+            .filter_map(|(coord, visibility)| match visibility {
+                CellVisibility::Never => Some(coord),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let world = &self.world;
+        // Every cell becomes visible for an instant, then the regular visibility update
+        // immediately demotes the cells the player can't actually see to previously-seen.
+        self.visibility_grid.update_omniscient_custom(
+            Rgb24::new_grey(0),
+            world,
+            |data: &mut VisibleCellData, coord| {
+                if unseen.contains(&coord) {
+                    data.update_layout(world, coord);
+                }
+            },
+        );
+        self.update_visibility();
+        self.message_log.push(Message::ReadSchematics);
+    }
+
+    fn read_document(&mut self, document: Document) -> GameControlFlow {
+        if !self.documents_read.contains(&document) {
+            self.documents_read.push(document);
+        }
+        self.message_log.push(Message::ReadDocument(document));
+        GameControlFlow::Read(document)
+    }
+
+    fn recall_drone(&mut self) -> Result<(), ActionError> {
+        let drone_entity = self.drone_entity.ok_or(ActionError::NoDrone)?;
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if inventory.first_free_slot().is_none() {
+            return Err(ActionError::InventoryIsFull);
+        }
+        self.world.remove_entity(drone_entity);
+        let item_entity = self.world.spawn_item_no_coord(Item::Drone);
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        *inventory.first_free_slot().unwrap() = Some(item_entity);
+        self.drone_entity = None;
+        self.controlling_drone = false;
+        self.message_log.push(Message::RecallDrone);
+        Ok(())
+    }
+
+    fn drone_walk(&mut self, direction: CardinalDirection) -> Result<(), ActionError> {
+        let drone_entity = self.drone_entity.ok_or(ActionError::NoDrone)?;
+        let drone_coord = self.drone_coord().ok_or(ActionError::NoDrone)?;
+        let new_drone_coord = drone_coord + direction.coord();
+        if !new_drone_coord.is_valid(self.world.size()) {
+            return Err(ActionError::InvalidMove);
+        }
+        // flying the drone back to the player picks it up
+        if new_drone_coord == self.player_coord() {
+            return self.recall_drone();
+        }
+        if let Some(layers) = self.world.spatial_table.layers_at(new_drone_coord) {
+            if layers.character.is_some() {
+                return Err(ActionError::InvalidMove);
+            }
+            if let Some(feature_entity) = layers.feature {
+                // drones can't open doors
+                if self.world.components.solid.contains(feature_entity) {
+                    return Err(ActionError::InvalidMove);
+                }
+            }
+            self.world
+                .spatial_table
+                .update_coord(drone_entity, new_drone_coord)
+                .unwrap();
+        }
+        Ok(())
+    }
+
+    fn player_deploy_turret(&mut self, inventory_index: usize) {
+        let player_coord = self.player_coord();
+        let coord = match self.world.nearest_characterless_coord(player_coord) {
+            Some(coord) => coord,
+            None => {
+                self.message_log
+                    .push(Message::ActionError(ActionError::InvalidMove));
+                return;
+            }
+        };
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(item_entity) = inventory.remove(inventory_index) {
+            let turret_ammo = self.world.components.turret_ammo.get(item_entity).cloned();
+            self.world.remove_entity(item_entity);
+            self.world
+                .spawn_turret(coord, turret_ammo.unwrap_or(Meter::new(0, 0)));
+            self.message_log.push(Message::DeployTurret);
+        }
+    }
+
+    fn player_pick_up_turret(&mut self, turret_entity: Entity) -> Result<(), ActionError> {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if inventory.first_free_slot().is_none() {
+            return Err(ActionError::InventoryIsFull);
+        }
+        let turret_ammo = self
+            .world
+            .components
+            .turret_ammo
+            .get(turret_entity)
+            .cloned();
+        self.world.remove_entity(turret_entity);
+        let item_entity = self.world.spawn_item_no_coord(Item::Turret);
+        if let Some(turret_ammo) = turret_ammo {
+            self.world
+                .components
+                .turret_ammo
+                .insert(item_entity, turret_ammo);
+        }
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        *inventory.first_free_slot().unwrap() = Some(item_entity);
+        self.message_log.push(Message::PickUpTurret);
+        Ok(())
+    }
+
+    // Cursed guns held by the player occasionally go off in a random direction, which reveals
+    // their nature
+    fn handle_cursed_guns(&mut self) {
+        let player_hands = self.world.components.hands.get(self.player_entity).unwrap();
+        let held = [player_hands.left.holding(), player_hands.right.holding()];
+        for entity in held.iter().flatten().cloned() {
+            if self.world.item_identity(entity) != Some(Item::CursedPistol) {
+                continue;
+            }
+            if !self.rng.gen_ratio(1, 10) {
+                continue;
+            }
+            let gun = self.world.components.gun.get_mut(entity).unwrap();
+            if gun.ammo.is_empty() {
+                continue;
+            }
+            gun.ammo.decrease(1);
+            self.world.identify_item(entity);
+            let target = self.player_coord() + self.rng.gen::<Direction>().coord();
+            self.fire_pistol_involuntarily(target);
+        }
+    }
+
+    // A geiger counter in the player's inventory clicks when they are exposed to radiation
+    fn handle_geiger_counter(&mut self) {
+        if self
+            .world
+            .player_inventory_item_index(Item::GeigerCounter)
+            .is_none()
+        {
+            return;
+        }
+        let intensity = self.world.radiation_intensity_at(self.player_coord());
+        if intensity > 0 {
+            self.external_events
+                .push(ExternalEvent::GeigerClick(intensity));
+        }
+    }
+
+    // Alarm panels go off if they see the player firing a weapon
+    fn trip_alarm_sensors(&mut self) {
+        if self.world.is_seen_by_alarm_panel(self.player_coord()) {
+            self.world
+                .trigger_alarm(&mut self.external_events, &mut self.message_log);
+        }
+    }
+
+    fn handle_corruption(&mut self) {
+        let corrupted = self.world.handle_corruption(
+            &mut self.rng,
+            &mut self.external_events,
+            &mut self.message_log,
+        );
+        for entity in corrupted {
+            let visible = self
+                .world
+                .spatial_table
+                .coord_of(entity)
+                .is_some_and(|coord| {
+                    matches!(
+                        self.cell_visibility_at_coord(coord),
+                        CellVisibility::Current { .. }
+                    )
+                });
+            if visible {
+                if let Some(&npc_type) = self.world.components.npc_type.get(entity) {
+                    self.message_log.push(Message::NpcCorrupted(npc_type));
+                }
+            }
+        }
+    }
+
+    fn place_merchant(&mut self, merchant: &mut TravellingMerchant, coord: Coord) {
+        let level = self.deepest_level_index;
+        let rng = &mut self.rng;
+        merchant
+            .npc
+            .enter_world(&mut self.world, coord, |world, coord| {
+                world.spawn_merchant(coord, level, rng)
+            });
+    }
+
+    fn handle_travelling_merchant(&mut self) {
+        if self.tutorial.is_some() {
+            return;
+        }
+        let mut merchant = match std::mem::take(&mut self.merchant) {
+            MerchantState::NotYetArrived => {
+                if self.turn_count >= MERCHANT_FIRST_APPEARANCE_TURN {
+                    let level_index = self.rng.gen_range(0..=self.deepest_level_index);
+                    let mut merchant = Box::new(TravellingMerchant::new(
+                        level_index,
+                        self.turn_count,
+                        &mut self.rng,
+                    ));
+                    if level_index == self.current_level_index {
+                        if let Some(coord) = self.world.random_characterless_coord(&mut self.rng) {
+                            self.place_merchant(&mut merchant, coord);
+                            self.message_log.push(Message::MerchantArrives);
+                        }
+                    }
+                    self.merchant = MerchantState::Travelling(merchant);
+                }
+                return;
+            }
+            MerchantState::Travelling(merchant) => merchant,
+            MerchantState::Dead => {
+                self.merchant = MerchantState::Dead;
+                return;
+            }
+        };
+        match merchant.npc.entity {
+            Some(entity) => {
+                let coord = match merchant.npc.coord(&self.world) {
+                    Some(coord) => coord,
+                    None => {
+                        self.merchant = MerchantState::Dead;
+                        return;
+                    }
+                };
+                let reached_exit = Some(coord) == merchant.exit_coord(&self.world);
+                let gave_up = self.turn_count >= merchant.leaves_at_turn + MERCHANT_MAX_WALK_TURNS;
+                if merchant.is_leaving(entity, &self.world, self.turn_count)
+                    && (reached_exit || gave_up)
+                {
+                    if let CellVisibility::Current { .. } = self.cell_visibility_at_coord(coord) {
+                        self.message_log.push(Message::MerchantLeaves);
+                    }
+                    merchant.npc.leave_world(&mut self.world);
+                    merchant.move_to_next_level(self.turn_count, &mut self.rng);
+                }
+            }
+            None => {
+                if self.turn_count >= merchant.leaves_at_turn {
+                    let coming_down = merchant.next_level_index > merchant.level_index;
+                    merchant.move_to_next_level(self.turn_count, &mut self.rng);
+                    if merchant.level_index == self.current_level_index {
+                        let arrival_coord = if coming_down {
+                            self.world.stairs_up_or_exit_coord()
+                        } else {
+                            self.world.stairs_down_coord()
+                        };
+                        if let Some(coord) = arrival_coord {
+                            self.place_merchant(&mut merchant, coord);
+                            self.message_log.push(Message::MerchantArrives);
+                        }
+                    }
+                }
+            }
+        }
+        self.merchant = MerchantState::Travelling(merchant);
+    }
+
+    fn num_vendors_killed(&self) -> usize {
+        self.message_log
+            .iter()
+            .filter(|message| {
+                matches!(
+                    message,
+                    Message::NpcDies(
+                        NpcType::GunStore
+                            | NpcType::ItemStore
+                            | NpcType::OrganTrader
+                            | NpcType::OrganClinic
+                            | NpcType::Merchant
+                    )
+                )
+            })
+            .count()
+    }
+
+    fn handle_bounty_hunter(&mut self) {
+        if self.tutorial.is_some() {
+            return;
+        }
+        let mut hunter = match std::mem::take(&mut self.bounty_hunter) {
+            BountyHunterState::NotHunting => {
+                if self.boss_dead || self.num_vendors_killed() >= BOUNTY_HUNTER_VENDOR_KILLS {
+                    self.message_log.push(Message::BountyPosted);
+                    self.bounty_hunter = BountyHunterState::Hunting(Box::new(BountyHunter {
+                        npc: CrossLevelNpc::default(),
+                        arrives_at_turn: self.turn_count + BOUNTY_HUNTER_DELAY_TURNS,
+                        arrives_from_above: true,
+                    }));
+                }
+                return;
+            }
+            BountyHunterState::Hunting(hunter) => hunter,
+            BountyHunterState::Dead => {
+                self.bounty_hunter = BountyHunterState::Dead;
+                return;
+            }
+        };
+        if hunter.npc.is_dead(&self.world) {
+            self.bounty_hunter = BountyHunterState::Dead;
+            return;
+        }
+        if hunter.npc.entity.is_none() && self.turn_count >= hunter.arrives_at_turn {
+            let arrival_coord = if hunter.arrives_from_above {
+                self.world.stairs_up_or_exit_coord()
+            } else {
+                self.world.stairs_down_coord()
+            };
+            if let Some(coord) = arrival_coord {
+                let rng = &mut self.rng;
+                hunter
+                    .npc
+                    .enter_world(&mut self.world, coord, |world, coord| {
+                        world.spawn_bounty_hunter(coord, rng)
+                    });
+                self.message_log.push(Message::BountyHunterArrives);
+            }
+        }
+        self.bounty_hunter = BountyHunterState::Hunting(hunter);
+    }
+
+    fn handle_drone_destroyed(&mut self) {
+        if let Some(drone_entity) = self.drone_entity {
+            if self.world.components.to_remove.contains(drone_entity)
+                || self.drone_coord().is_none()
+            {
+                self.drone_entity = None;
+                self.controlling_drone = false;
+                self.message_log.push(Message::DroneDestroyed);
+            }
         }
     }
 
@@ -1702,6 +5899,10 @@ impl Game {
             .get_mut(self.player_entity)
             .unwrap();
         if let Some(entity) = inventory.get(inventory_index) {
+            let organ = Organ {
+                harvested: true,
+                ..organ
+            };
             self.world
                 .components
                 .item
@@ -1726,7 +5927,13 @@ impl Game {
             .get_mut(self.player_entity)
             .unwrap();
         if let Some(item_entity) = inventory.get(i) {
-            if let Some(&item) = self.world.components.item.get(item_entity) {
+            if let Some(&item) = self.world.components.unidentified.get(item_entity).or(self
+                .world
+                .components
+                .item
+                .get(item_entity))
+            {
+                self.identified_items.insert(item);
                 match item {
                     Item::OrganContainer(None) => {
                         if let Some(organs) = self.organs_of_corpse_at_player() {
@@ -1760,6 +5967,35 @@ impl Game {
                             self.world.make_floor_bloody(player_coord);
                         }
                     }
+                    Item::Plank => {
+                        let player_coord = self
+                            .world
+                            .spatial_table
+                            .coord_of(self.player_entity)
+                            .unwrap();
+                        let door_entity = CardinalDirection::all()
+                            .filter_map(|direction| {
+                                self.world
+                                    .spatial_table
+                                    .layers_at(player_coord + direction.coord())
+                                    .and_then(|layers| layers.feature)
+                            })
+                            .find(|&feature_entity| {
+                                matches!(
+                                    self.world.components.door_state.get(feature_entity),
+                                    Some(DoorState::Closed)
+                                )
+                            });
+                        if let Some(door_entity) = door_entity {
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.barricade_door(door_entity);
+                            self.message_log.push(Message::BarricadeDoor);
+                        } else {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::NoDoorToBarricade));
+                        }
+                    }
                     Item::Antidote => {
                         let poison = self
                             .world
@@ -1777,6 +6013,69 @@ impl Game {
                             self.message_log.push(Message::ApplyAntidote);
                         }
                     }
+                    Item::Immunosuppressant => {
+                        if !self
+                            .world
+                            .components
+                            .immunosuppressed
+                            .contains(self.player_entity)
+                        {
+                            self.world.components.immunosuppressed.insert(
+                                self.player_entity,
+                                Meter::new(0, IMMUNOSUPPRESSANT_MAX_TURNS),
+                            );
+                        }
+                        let immunosuppressed = self
+                            .world
+                            .components
+                            .immunosuppressed
+                            .get_mut(self.player_entity)
+                            .unwrap();
+                        if immunosuppressed.is_full() {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::ImmunosuppressantIsFull));
+                        } else {
+                            immunosuppressed.increase(IMMUNOSUPPRESSANT_DOSE_TURNS);
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.message_log.push(Message::ApplyImmunosuppressant);
+                        }
+                    }
+                    Item::EmpGrenade | Item::SmokeGrenade => {
+                        return Some(GameControlFlow::Throw(i))
+                    }
+                    Item::Readable(document) => return Some(self.read_document(document)),
+                    Item::GasMask | Item::HazardSuit => self.player_wear_item(i),
+                    Item::Antipsychotic => {
+                        if let Some(psychosis) =
+                            self.world.components.psychosis.get_mut(self.player_entity)
+                        {
+                            psychosis.decrease(ANTIPSYCHOTIC_DOSE);
+                            self.hallucinations.clear();
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.message_log.push(Message::ApplyAntipsychotic);
+                        } else {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::NotPsychotic));
+                        }
+                    }
+                    Item::Bandage => {
+                        if self
+                            .world
+                            .components
+                            .bleeding
+                            .remove(self.player_entity)
+                            .is_some()
+                        {
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.message_log.push(Message::ApplyBandage);
+                        } else {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::NotBleeding));
+                        }
+                    }
                     Item::AntiRads => {
                         let radiation = self
                             .world
@@ -1805,13 +6104,72 @@ impl Game {
                             self.message_log
                                 .push(Message::ActionError(ActionError::HealthIsFull));
                         } else {
-                            health.increase(5);
+                            if self.background == Background::StreetDoc {
+                                health.increase(5 + STREET_DOC_STIMPACK_BONUS);
+                            } else {
+                                health.increase(5);
+                            }
                             inventory.remove(i);
                             self.world.remove_entity(item_entity);
                             self.message_log.push(Message::ApplyStimpack);
                         }
                     }
-                    Item::Food => {
+                    Item::CorruptedStimpack => {
+                        let health = self
+                            .world
+                            .components
+                            .health
+                            .get_mut(self.player_entity)
+                            .unwrap();
+                        if health.is_full() {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::HealthIsFull));
+                        } else {
+                            health.increase(5);
+                            self.world
+                                .components
+                                .radiation
+                                .get_mut(self.player_entity)
+                                .unwrap()
+                                .increase(20);
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.message_log.push(Message::ApplyCorruptedStimpack);
+                        }
+                    }
+                    Item::RawMeat
+                        if Direction::all().any(|direction| {
+                            self.world
+                                .spatial_table
+                                .layers_at(
+                                    self.world
+                                        .spatial_table
+                                        .coord_of(self.player_entity)
+                                        .unwrap()
+                                        + direction.coord(),
+                                )
+                                .and_then(|layers| layers.feature)
+                                .and_then(|feature| self.world.components.tile.get(feature))
+                                == Some(&Tile::DebrisBurning)
+                        }) =>
+                    {
+                        self.world
+                            .components
+                            .item
+                            .insert(item_entity, Item::CookedMeat);
+                        self.world
+                            .components
+                            .tile
+                            .insert(item_entity, Tile::Item(Item::CookedMeat));
+                        if let Some(turns) = Item::CookedMeat.turns_until_spoiled() {
+                            self.world
+                                .components
+                                .freshness
+                                .insert(item_entity, Meter::new_full(turns));
+                        }
+                        self.message_log.push(Message::CookMeat);
+                    }
+                    Item::Food | Item::RawMeat | Item::CookedMeat | Item::RottenMeat => {
                         let food = self
                             .world
                             .components
@@ -1822,10 +6180,26 @@ impl Game {
                             self.message_log
                                 .push(Message::ActionError(ActionError::FoodIsFull));
                         } else {
-                            food.increase(25);
+                            food.increase(item.nutrition().unwrap_or(0));
                             inventory.remove(i);
                             self.world.remove_entity(item_entity);
-                            self.message_log.push(Message::EatFood);
+                            self.message_log.push(Message::EatFood(item));
+                            // Raw meat sometimes disagrees with the player, and rotten meat always
+                            // does
+                            let food_poisoning = match item {
+                                Item::RawMeat => self.rng.gen_range(0..3) == 0,
+                                Item::RottenMeat => true,
+                                _ => false,
+                            };
+                            if food_poisoning {
+                                self.world
+                                    .components
+                                    .poison
+                                    .get_mut(self.player_entity)
+                                    .unwrap()
+                                    .increase(FOOD_POISONING);
+                                self.message_log.push(Message::FoodPoisoning);
+                            }
                         }
                     }
                     Item::BloodVialFull => {
@@ -1855,6 +6229,14 @@ impl Game {
                                 .get_mut(self.player_entity)
                                 .unwrap()
                                 .fill();
+                            if self.background == Background::VampireInitiate {
+                                self.world
+                                    .components
+                                    .health
+                                    .get_mut(self.player_entity)
+                                    .unwrap()
+                                    .increase(VAMPIRE_INITIATE_BLOOD_VIAL_HEALTH);
+                            }
                             self.message_log.push(Message::ApplyFullBlodVial);
                         }
                     }
@@ -1878,6 +6260,33 @@ impl Game {
                                 .push(Message::ActionError(ActionError::NoCyberCore));
                         }
                     }
+                    Item::Drone => self.player_deploy_drone(i),
+                    Item::Turret => self.player_deploy_turret(i),
+                    Item::Anesthetic => self
+                        .message_log
+                        .push(Message::ActionError(ActionError::AnestheticNeedsSurgery)),
+                    Item::FieldSurgeryKit => match self.field_surgery_menu(i) {
+                        Some(menu) => return Some(GameControlFlow::Menu(menu)),
+                        None => self
+                            .message_log
+                            .push(Message::ActionError(ActionError::NothingToOperateOn)),
+                    },
+                    Item::Scanner => self.player_apply_scanner(i),
+                    Item::Schematics => self.player_read_schematics(i),
+                    Item::MarketReport => {
+                        inventory.remove(i);
+                        self.world.remove_entity(item_entity);
+                        self.message_log
+                            .push(Message::ReadMarketReport(self.organ_market.report()));
+                    }
+                    Item::GeigerCounter => {
+                        self.radiation_overlay = !self.radiation_overlay;
+                        self.message_log.push(if self.radiation_overlay {
+                            Message::RadiationOverlayOn
+                        } else {
+                            Message::RadiationOverlayOff
+                        });
+                    }
                     Item::OrganContainer(Some(organ)) => {
                         self.world.make_floor_bloody(self.player_coord());
                         self.world
@@ -1890,7 +6299,7 @@ impl Game {
                             .insert(item_entity, Tile::Item(Item::OrganContainer(None)));
                         self.message_log.push(Message::DumpOrgan(organ));
                     }
-                    Item::Pistol => {
+                    Item::Pistol | Item::CursedPistol => {
                         if self.world.num_player_claws() >= 2 {
                             self.message_log
                                 .push(Message::ActionError(ActionError::NeedsOneHand));
@@ -1925,7 +6334,7 @@ impl Game {
                             }
                         }
                     }
-                    Item::Shotgun | Item::RocketLauncher => {
+                    Item::Shotgun | Item::RocketLauncher | Item::SniperRifle => {
                         if self.world.num_player_claws() >= 1 {
                             self.message_log
                                 .push(Message::ActionError(ActionError::NeedsTwoHands));
@@ -2014,6 +6423,27 @@ impl Game {
                                 .push(Message::ActionError(ActionError::NothingToReload));
                         }
                     }
+                    Item::SniperRounds => {
+                        // 2 handed weapons are always in the left hand
+                        if self
+                            .get_appropriate_gun_ammo(WhichHand::Left, GunType::SniperRifle)
+                            .is_some()
+                        {
+                            self.reload_gun_in_hand(WhichHand::Left);
+                            let inventory = self
+                                .world
+                                .components
+                                .inventory
+                                .get_mut(self.player_entity)
+                                .unwrap();
+                            inventory.remove(i);
+                            self.world.remove_entity(item_entity);
+                            self.message_log.push(Message::ReloadGun(Item::SniperRifle));
+                        } else {
+                            self.message_log
+                                .push(Message::ActionError(ActionError::NothingToReload));
+                        }
+                    }
                 }
             }
         }
@@ -2116,6 +6546,105 @@ impl Game {
         }
     }
 
+    fn player_wear_item(&mut self, inventory_index: usize) {
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        let entity = match inventory.get(inventory_index) {
+            Some(entity) => entity,
+            None => return,
+        };
+        let item = *self.world.components.item.get(entity).unwrap();
+        let slot = match item.wear_slot() {
+            Some(slot) => slot,
+            None => return,
+        };
+        inventory.remove(inventory_index);
+        if !self.world.components.worn.contains(self.player_entity) {
+            self.world
+                .components
+                .worn
+                .insert(self.player_entity, Worn::default());
+        }
+        let worn = self
+            .world
+            .components
+            .worn
+            .get_mut(self.player_entity)
+            .unwrap();
+        let previous = worn.slot_mut(slot).replace(entity);
+        self.message_log.push(Message::EquipItem(item));
+        if let Some(previous) = previous {
+            // taking the item out of the inventory made room for the one it replaces
+            let inventory = self
+                .world
+                .components
+                .inventory
+                .get_mut(self.player_entity)
+                .unwrap();
+            if let Some(slot) = inventory.first_free_slot() {
+                *slot = Some(previous);
+            }
+            if let Some(&item) = self.world.components.item.get(previous) {
+                self.message_log.push(Message::UnequipItem(item));
+            }
+        }
+    }
+
+    fn take_off(&mut self, slot: WearSlot) {
+        let entity = match self
+            .world
+            .components
+            .worn
+            .get_mut(self.player_entity)
+            .and_then(|worn| worn.slot_mut(slot).take())
+        {
+            Some(entity) => entity,
+            None => return,
+        };
+        let item = *self.world.components.item.get(entity).unwrap();
+        let inventory = self
+            .world
+            .components
+            .inventory
+            .get_mut(self.player_entity)
+            .unwrap();
+        if let Some(slot) = inventory.first_free_slot() {
+            self.message_log.push(Message::UnequipItem(item));
+            *slot = Some(entity);
+        } else if let Some(coord) = self.world.nearest_itemless_coord(self.player_coord()) {
+            // no room in inventory
+            self.message_log.push(Message::DropUnequipItem(item));
+            let _ = self.world.spatial_table.update(
+                entity,
+                Location {
+                    coord,
+                    layer: Some(Layer::Item),
+                },
+            );
+        }
+    }
+
+    /// Worn equipment, along with how much wear each item has left
+    pub fn player_worn_items(&self) -> Vec<(Item, Meter)> {
+        let worn = match self.world.components.worn.get(self.player_entity) {
+            Some(worn) => worn,
+            None => return Vec::new(),
+        };
+        [WearSlot::Head, WearSlot::Body]
+            .into_iter()
+            .filter_map(|slot| worn.slot(slot))
+            .filter_map(|entity| {
+                let item = *self.world.components.item.get(entity)?;
+                let durability = *self.world.components.durability.get(entity)?;
+                Some((item, durability))
+            })
+            .collect()
+    }
+
     fn organs_of_corpse_at_player(&self) -> Option<Vec<Organ>> {
         let player_coord = self.player_coord();
         if let Some(Layers {
@@ -2123,7 +6652,17 @@ impl Game {
         }) = self.world.spatial_table.layers_at(player_coord)
         {
             if self.world.components.corpse.contains(*entity) {
-                self.world.components.simple_organs.get(*entity).cloned()
+                let donor = self.world.components.npc_type.get(*entity).cloned();
+                self.world
+                    .components
+                    .simple_organs
+                    .get(*entity)
+                    .map(|organs| {
+                        organs
+                            .iter()
+                            .map(|&organ| Organ { donor, ..organ })
+                            .collect()
+                    })
             } else {
                 None
             }
@@ -2189,6 +6728,14 @@ impl Game {
             .get_mut(self.player_entity)
             .unwrap();
         if let Some(item_entity) = inventory.remove(i) {
+            if self.world.is_water_at(self.player_coord()) {
+                // items dropped while swimming are lost
+                if let Some(&item) = self.world.components.item.get(item_entity) {
+                    self.message_log.push(Message::ItemSinks(item));
+                }
+                self.world.remove_entity(item_entity);
+                return;
+            }
             if let Some(&item) = self.world.components.item.get(item_entity) {
                 self.message_log.push(Message::DropItem(item));
             }
@@ -2204,11 +6751,22 @@ impl Game {
         }
     }
 
+    /// Calls `f` on the visible particles. Only about `density_percent` percent of particles are
+    /// included, chosen so that a particle is either always or never included throughout its life.
     pub fn for_each_visible_particle<F: FnMut(Coord, VisibleEntity, Option<Rgb24>)>(
         &self,
+        density_percent: u32,
         mut f: F,
     ) {
+        use std::hash::{Hash, Hasher};
         for entity in self.world.components.particle.entities() {
+            if density_percent < 100 {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                entity.hash(&mut hasher);
+                if hasher.finish() % 100 >= density_percent as u64 {
+                    continue;
+                }
+            }
             if let Some(coord) = self.world.spatial_table.coord_of(entity) {
                 if let CellVisibility::Current { light_colour, .. } =
                     self.cell_visibility_at_coord(coord)
@@ -2228,9 +6786,10 @@ impl Game {
         self.world.spatial_table.grid_size()
     }
 
-    pub fn take_external_events(&mut self) -> Vec<ExternalEvent> {
-        use std::mem;
-        mem::replace(&mut self.external_events, Vec::new())
+    /// Removes and returns the events that have happened since this was last called, keeping
+    /// the buffer they're stored in for reuse
+    pub fn drain_external_events(&mut self) -> std::vec::Drain<'_, ExternalEvent> {
+        self.external_events.drain(..)
     }
 
     pub fn player_stats(&self) -> PlayerStats {
@@ -2274,6 +6833,13 @@ impl Game {
             } else {
                 None
             },
+            temperature: self
+                .world
+                .components
+                .temperature
+                .get(self.player_entity)
+                .cloned()
+                .unwrap_or_else(|| Meter::new(10, 20)),
         }
     }
 
@@ -2290,6 +6856,23 @@ impl Game {
             .size()
     }
 
+    /// How an item appears to the player given which kinds of item they have identified
+    pub fn item_appearance(&self, item: Item) -> ItemAppearance {
+        match item.unidentified_appearance() {
+            Some(appearance) if !self.identified_items.contains(&item) => appearance,
+            _ => ItemAppearance::Identified(item),
+        }
+    }
+
+    /// Like `item_appearance` but for tiles. Tiles other than items are unchanged.
+    pub fn apparent_tile(&self, tile: Tile) -> Tile {
+        if let Tile::Item(item) = tile {
+            self.item_appearance(item).tile()
+        } else {
+            tile
+        }
+    }
+
     pub fn inventory_item(&self, i: usize) -> Option<Item> {
         let inventory = self
             .world
@@ -2312,6 +6895,7 @@ impl Game {
                         GunType::Pistol => "Pistol",
                         GunType::Shotgun => "Shotgun",
                         GunType::RocketLauncher => "Rkt Launcher",
+                        GunType::SniperRifle => "Sniper Rifle",
                     };
                     let both = gun.hands_required > 1;
                     (
@@ -2340,7 +6924,85 @@ impl Game {
         self.world.player_organs()
     }
 
+    pub fn num_player_organ_slots(&self) -> usize {
+        self.world
+            .components
+            .organs
+            .get(self.player_entity)
+            .unwrap()
+            .num_slots()
+    }
+
+    pub fn player_experience(&self) -> &Experience {
+        self.world
+            .components
+            .experience
+            .get(self.player_entity)
+            .unwrap()
+    }
+
+    fn has_perk(&self, perk: Perk) -> bool {
+        self.player_experience().has_perk(perk)
+    }
+
+    pub fn player_skills(&self) -> &Skills {
+        self.world
+            .components
+            .skills
+            .get(self.player_entity)
+            .unwrap()
+    }
+
+    pub fn player_skill_level(&self, skill: Skill) -> u32 {
+        self.player_skills().level(skill)
+    }
+
     pub fn current_level_index(&self) -> usize {
         self.current_level_index
     }
+
+    pub fn run_stats(&self) -> RunStats {
+        let organs = self.player_organs();
+        RunStats {
+            turns: self.turn_count,
+            level_reached: self.current_level_index + 1,
+            money: self.player_money(),
+            npcs_killed: self
+                .message_log
+                .iter()
+                .filter(|message| matches!(message, Message::NpcDies(_)))
+                .count(),
+            all_original_organs: organs.iter().all(|o| o.organ.original),
+            no_cybernetics: organs.iter().all(|o| !o.organ.cybernetic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use world::data::FloorHazard;
+
+    #[test]
+    fn path_to_avoids_hazards() {
+        let config = Config {
+            omniscient: Config::OMNISCIENT,
+            ..Default::default()
+        };
+        let mut game = Game::new_tutorial(&config, &mut Isaac64Rng::seed_from_u64(0));
+        let start = game.player_coord();
+        let hazard = start + Coord::new(1, 0);
+        let target = start + Coord::new(2, 0);
+        assert_eq!(game.path_to(target).map(|path| path.len()), Some(2));
+        game.world.make_floor_hazard(hazard, FloorHazard::Acid);
+        game.update_visibility();
+        let path = game.path_to(target).unwrap();
+        assert_eq!(path.len(), 4);
+        let mut coord = start;
+        for direction in path {
+            coord += direction.coord();
+            assert_ne!(coord, hazard);
+        }
+        assert_eq!(game.path_to(hazard), Some(vec![CardinalDirection::East]));
+    }
 }
@@ -0,0 +1,301 @@
+use crate::world::data::{Item, LevelTheme, NpcType, OrganType};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+}
+
+impl Rarity {
+    pub fn weight(self) -> u32 {
+        match self {
+            Self::Common => 12,
+            Self::Uncommon => 4,
+            Self::Rare => 1,
+        }
+    }
+
+    fn more_common(self) -> Self {
+        match self {
+            Self::Common | Self::Uncommon => Self::Common,
+            Self::Rare => Self::Uncommon,
+        }
+    }
+}
+
+struct Entry<T> {
+    value: T,
+    min_level: usize,
+    rarity: Rarity,
+}
+
+const fn entry<T>(value: T, min_level: usize, rarity: Rarity) -> Entry<T> {
+    Entry {
+        value,
+        min_level,
+        rarity,
+    }
+}
+
+// Each roll has this chance of being made as though it were on a deeper level, so enemies and
+// items can occasionally turn up before the level where they normally appear.
+const OUT_OF_DEPTH_PROBABILITY: f64 = 0.05;
+const OUT_OF_DEPTH_LEVELS: usize = 2;
+
+const ENEMIES: &[Entry<NpcType>] = &[
+    entry(NpcType::Zombie, 0, Rarity::Common),
+    entry(NpcType::Climber, 0, Rarity::Common),
+    entry(NpcType::Trespasser, 0, Rarity::Common),
+    entry(NpcType::Snatcher, 0, Rarity::Common),
+    entry(NpcType::Boomer, 0, Rarity::Uncommon),
+    entry(NpcType::Divider, 0, Rarity::Uncommon),
+    entry(NpcType::Poisoner, 0, Rarity::Uncommon),
+    entry(NpcType::Venter, 1, Rarity::Uncommon),
+    entry(NpcType::Glower, 1, Rarity::Uncommon),
+    entry(NpcType::Boomer, 2, Rarity::Common),
+    entry(NpcType::Divider, 2, Rarity::Common),
+    entry(NpcType::Poisoner, 2, Rarity::Common),
+];
+
+const OFFICES_ENEMIES: &[Entry<NpcType>] = &[
+    entry(NpcType::Trespasser, 0, Rarity::Common),
+    entry(NpcType::Snatcher, 0, Rarity::Common),
+    entry(NpcType::Climber, 0, Rarity::Uncommon),
+    entry(NpcType::Boomer, 0, Rarity::Uncommon),
+    entry(NpcType::Glower, 2, Rarity::Uncommon),
+    entry(NpcType::Sentinel, 1, Rarity::Uncommon),
+];
+
+const HOSPITAL_ENEMIES: &[Entry<NpcType>] = &[
+    entry(NpcType::Zombie, 0, Rarity::Common),
+    entry(NpcType::Snatcher, 0, Rarity::Common),
+    entry(NpcType::Poisoner, 0, Rarity::Uncommon),
+    entry(NpcType::Divider, 1, Rarity::Uncommon),
+];
+
+const SEWERS_ENEMIES: &[Entry<NpcType>] = &[
+    entry(NpcType::Poisoner, 0, Rarity::Common),
+    entry(NpcType::Divider, 0, Rarity::Common),
+    entry(NpcType::Zombie, 0, Rarity::Uncommon),
+    entry(NpcType::Climber, 0, Rarity::Uncommon),
+    entry(NpcType::Venter, 1, Rarity::Uncommon),
+];
+
+const LAB_ENEMIES: &[Entry<NpcType>] = &[
+    entry(NpcType::Glower, 0, Rarity::Common),
+    entry(NpcType::Venter, 0, Rarity::Common),
+    entry(NpcType::Boomer, 0, Rarity::Uncommon),
+    entry(NpcType::Trespasser, 0, Rarity::Uncommon),
+    entry(NpcType::Divider, 2, Rarity::Uncommon),
+    entry(NpcType::Sentinel, 0, Rarity::Uncommon),
+];
+
+const ITEMS: &[Entry<Item>] = &[
+    entry(Item::Bandage, 0, Rarity::Common),
+    entry(Item::PistolAmmo, 0, Rarity::Common),
+    entry(Item::ShotgunAmmo, 0, Rarity::Common),
+    entry(Item::Rocket, 0, Rarity::Uncommon),
+    entry(Item::Pistol, 0, Rarity::Uncommon),
+    entry(Item::Battery, 1, Rarity::Common),
+    entry(Item::Scanner, 0, Rarity::Uncommon),
+    entry(Item::Plank, 0, Rarity::Uncommon),
+    entry(Item::GeigerCounter, 1, Rarity::Uncommon),
+    entry(Item::Shotgun, 1, Rarity::Uncommon),
+    entry(Item::Rocket, 2, Rarity::Common),
+    entry(Item::Drone, 1, Rarity::Rare),
+    entry(Item::EmpGrenade, 1, Rarity::Uncommon),
+    entry(Item::SmokeGrenade, 0, Rarity::Uncommon),
+    entry(Item::Turret, 2, Rarity::Rare),
+    entry(Item::RocketLauncher, 2, Rarity::Rare),
+    entry(Item::SniperRounds, 1, Rarity::Uncommon),
+    entry(Item::SniperRifle, 2, Rarity::Rare),
+];
+
+const OFFICES_ITEMS: &[Entry<Item>] = &[
+    entry(Item::Battery, 0, Rarity::Common),
+    entry(Item::PistolAmmo, 0, Rarity::Common),
+    entry(Item::Schematics, 0, Rarity::Uncommon),
+];
+
+const HOSPITAL_ITEMS: &[Entry<Item>] = &[
+    entry(Item::Stimpack, 0, Rarity::Common),
+    entry(Item::Antidote, 0, Rarity::Common),
+    entry(Item::BloodVialEmpty, 0, Rarity::Uncommon),
+    entry(Item::Anesthetic, 0, Rarity::Uncommon),
+    entry(Item::FieldSurgeryKit, 1, Rarity::Rare),
+    entry(Item::Immunosuppressant, 0, Rarity::Uncommon),
+    entry(Item::Bandage, 0, Rarity::Common),
+    entry(Item::Antipsychotic, 1, Rarity::Uncommon),
+];
+
+const SEWERS_ITEMS: &[Entry<Item>] = &[
+    entry(Item::Antidote, 0, Rarity::Common),
+    entry(Item::Food, 0, Rarity::Uncommon),
+    entry(Item::GasMask, 0, Rarity::Uncommon),
+];
+
+const LAB_ITEMS: &[Entry<Item>] = &[
+    entry(Item::AntiRads, 0, Rarity::Common),
+    entry(Item::Scanner, 0, Rarity::Uncommon),
+    entry(Item::GeigerCounter, 0, Rarity::Uncommon),
+    entry(Item::GasMask, 0, Rarity::Uncommon),
+    entry(Item::HazardSuit, 1, Rarity::Rare),
+];
+
+// Shops further from the surface are better supplied. Every this many levels, each item a shop
+// stocks is treated as one step more common than its entry says.
+const SHOP_TIER_LEVELS: usize = 2;
+
+// Chance that an item is chosen from the level theme's table rather than the general one
+const THEME_ITEM_PROBABILITY: f64 = 0.4;
+
+const ORGAN_TYPES: &[Entry<OrganType>] = &[
+    entry(OrganType::Claw, 0, Rarity::Common),
+    entry(OrganType::CronenbergPistol, 0, Rarity::Common),
+    entry(OrganType::CronenbergShotgun, 1, Rarity::Uncommon),
+    entry(OrganType::CyberCore, 1, Rarity::Uncommon),
+    entry(OrganType::ReinforcedSkeleton, 1, Rarity::Rare),
+];
+
+const GUN_STORE_STOCK: &[Entry<Item>] = &[
+    entry(Item::Pistol, 0, Rarity::Common),
+    entry(Item::PistolAmmo, 0, Rarity::Common),
+    entry(Item::Shotgun, 0, Rarity::Common),
+    entry(Item::ShotgunAmmo, 0, Rarity::Common),
+    entry(Item::RocketLauncher, 0, Rarity::Common),
+    entry(Item::Rocket, 0, Rarity::Common),
+    entry(Item::Turret, 1, Rarity::Uncommon),
+    entry(Item::EmpGrenade, 1, Rarity::Common),
+    entry(Item::SmokeGrenade, 0, Rarity::Common),
+    entry(Item::SniperRifle, 1, Rarity::Uncommon),
+    entry(Item::SniperRounds, 1, Rarity::Common),
+];
+
+const ITEM_STORE_STOCK: &[Entry<Item>] = &[
+    entry(Item::Stimpack, 0, Rarity::Common),
+    entry(Item::Antidote, 0, Rarity::Common),
+    entry(Item::BloodVialEmpty, 0, Rarity::Common),
+    entry(Item::Battery, 0, Rarity::Common),
+    entry(Item::Food, 0, Rarity::Common),
+    entry(Item::AntiRads, 0, Rarity::Common),
+    entry(Item::OrganContainer(None), 0, Rarity::Common),
+    entry(Item::Scanner, 0, Rarity::Uncommon),
+    entry(Item::GeigerCounter, 0, Rarity::Uncommon),
+    entry(Item::Schematics, 0, Rarity::Uncommon),
+    entry(Item::MarketReport, 0, Rarity::Uncommon),
+    entry(Item::Plank, 0, Rarity::Uncommon),
+    entry(Item::Anesthetic, 0, Rarity::Common),
+    entry(Item::Immunosuppressant, 0, Rarity::Common),
+    entry(Item::Bandage, 0, Rarity::Common),
+    entry(Item::GasMask, 0, Rarity::Uncommon),
+    entry(Item::Drone, 1, Rarity::Uncommon),
+    entry(Item::HazardSuit, 1, Rarity::Rare),
+    entry(Item::Antipsychotic, 1, Rarity::Common),
+    entry(Item::FieldSurgeryKit, 1, Rarity::Uncommon),
+];
+
+// The travelling merchant carries things which are hard to find elsewhere
+const MERCHANT_STOCK: &[Entry<Item>] = &[
+    entry(Item::Drone, 0, Rarity::Common),
+    entry(Item::Turret, 0, Rarity::Common),
+    entry(Item::FieldSurgeryKit, 0, Rarity::Common),
+    entry(Item::HazardSuit, 0, Rarity::Uncommon),
+    entry(Item::SniperRifle, 0, Rarity::Uncommon),
+    entry(Item::Schematics, 0, Rarity::Uncommon),
+    entry(Item::MarketReport, 0, Rarity::Common),
+    entry(Item::RocketLauncher, 1, Rarity::Uncommon),
+    entry(Item::CorruptedStimpack, 1, Rarity::Rare),
+];
+
+/// The number of things of each kind to place on a level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelSpawns {
+    pub num_enemies: usize,
+    pub num_items: usize,
+    pub num_organs: usize,
+}
+
+pub fn level_spawns(level_index: usize) -> LevelSpawns {
+    let (num_enemies, num_items, num_organs) = match level_index {
+        0 => (5, 4, 1),
+        1 => (8, 6, 2),
+        2 => (9, 4, 1),
+        3 => (8, 8, 0),
+        _ => panic!(),
+    };
+    LevelSpawns {
+        num_enemies,
+        num_items,
+        num_organs,
+    }
+}
+
+fn choose<T: Copy, R: Rng>(table: &[Entry<T>], level_index: usize, rng: &mut R) -> T {
+    choose_with_rarity(table, level_index, rng, |rarity| rarity)
+}
+
+fn choose_with_rarity<T: Copy, R: Rng>(
+    table: &[Entry<T>],
+    level_index: usize,
+    rng: &mut R,
+    rarity: impl Fn(Rarity) -> Rarity,
+) -> T {
+    let level_index = if rng.gen_bool(OUT_OF_DEPTH_PROBABILITY) {
+        level_index + OUT_OF_DEPTH_LEVELS
+    } else {
+        level_index
+    };
+    let candidates = table
+        .iter()
+        .filter(|entry| entry.min_level <= level_index)
+        .collect::<Vec<_>>();
+    candidates
+        .choose_weighted(rng, |entry| rarity(entry.rarity).weight())
+        .expect("no candidates in loot table")
+        .value
+}
+
+pub fn choose_enemy<R: Rng>(theme: LevelTheme, level_index: usize, rng: &mut R) -> NpcType {
+    let table = match theme {
+        LevelTheme::Surface => ENEMIES,
+        LevelTheme::Offices => OFFICES_ENEMIES,
+        LevelTheme::Hospital => HOSPITAL_ENEMIES,
+        LevelTheme::Sewers => SEWERS_ENEMIES,
+        LevelTheme::Lab => LAB_ENEMIES,
+    };
+    choose(table, level_index, rng)
+}
+
+pub fn choose_item<R: Rng>(theme: LevelTheme, level_index: usize, rng: &mut R) -> Item {
+    let theme_table = match theme {
+        LevelTheme::Surface => None,
+        LevelTheme::Offices => Some(OFFICES_ITEMS),
+        LevelTheme::Hospital => Some(HOSPITAL_ITEMS),
+        LevelTheme::Sewers => Some(SEWERS_ITEMS),
+        LevelTheme::Lab => Some(LAB_ITEMS),
+    };
+    match theme_table {
+        Some(table) if rng.gen_bool(THEME_ITEM_PROBABILITY) => choose(table, level_index, rng),
+        _ => choose(ITEMS, level_index, rng),
+    }
+}
+
+pub fn choose_shop_item<R: Rng>(shop: NpcType, level_index: usize, rng: &mut R) -> Item {
+    let table = match shop {
+        NpcType::GunStore => GUN_STORE_STOCK,
+        NpcType::ItemStore => ITEM_STORE_STOCK,
+        NpcType::Merchant => MERCHANT_STOCK,
+        other => panic!("{:?} doesn't stock items", other),
+    };
+    let tier = level_index / SHOP_TIER_LEVELS;
+    choose_with_rarity(table, level_index, rng, |rarity| {
+        (0..tier).fold(rarity, |rarity, _| rarity.more_common())
+    })
+}
+
+pub fn choose_organ_type<R: Rng>(level_index: usize, rng: &mut R) -> OrganType {
+    choose(ORGAN_TYPES, level_index, rng)
+}
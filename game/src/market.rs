@@ -0,0 +1,117 @@
+use crate::world::data::OrganType;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Organ types which are bought and sold by clinics and traders
+const TRADED_ORGAN_TYPES: &[OrganType] = &[
+    OrganType::Heart,
+    OrganType::Liver,
+    OrganType::Lung,
+    OrganType::Stomach,
+    OrganType::CronenbergPistol,
+    OrganType::CronenbergShotgun,
+    OrganType::CyberCore,
+    OrganType::Claw,
+    OrganType::ReinforcedSkeleton,
+];
+
+const MIN_PRICE_PERCENT: u32 = 40;
+const MAX_PRICE_PERCENT: u32 = 200;
+/// Prices move by up to this many percentage points in either direction each time the player
+/// changes level
+const DRIFT_PERCENT: i32 = 15;
+/// Prices are pulled back towards normal by this many percentage points each time the player
+/// changes level, so a crashed market eventually recovers
+const RECOVERY_PERCENT: u32 = 5;
+/// Each organ the player sells floods the market, and each organ they buy makes the rest scarcer
+const SALE_PERCENT: u32 = 12;
+const PURCHASE_PERCENT: u32 = 6;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrganMarketPrice {
+    pub organ_type: OrganType,
+    /// The percentage of the organ type's usual price it currently trades at
+    pub percent: u32,
+    /// The percentage it traded at before the most recent change of level
+    pub previous_percent: u32,
+}
+
+/// The price of organs on the black market this game. Organ types missing from the list trade
+/// at their usual price.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OrganMarket {
+    prices: Vec<OrganMarketPrice>,
+}
+
+impl OrganMarket {
+    pub fn percent(&self, organ_type: OrganType) -> u32 {
+        self.prices
+            .iter()
+            .find(|price| price.organ_type == organ_type)
+            .map(|price| price.percent)
+            .unwrap_or(100)
+    }
+
+    fn price_mut(&mut self, organ_type: OrganType) -> &mut OrganMarketPrice {
+        if let Some(i) = self
+            .prices
+            .iter()
+            .position(|price| price.organ_type == organ_type)
+        {
+            &mut self.prices[i]
+        } else {
+            self.prices.push(OrganMarketPrice {
+                organ_type,
+                percent: 100,
+                previous_percent: 100,
+            });
+            self.prices.last_mut().unwrap()
+        }
+    }
+
+    /// Called each time the player changes level
+    pub fn drift<R: Rng>(&mut self, rng: &mut R) {
+        for &organ_type in TRADED_ORGAN_TYPES {
+            let price = self.price_mut(organ_type);
+            price.previous_percent = price.percent;
+            let recovered = if price.percent > 100 {
+                price.percent.saturating_sub(RECOVERY_PERCENT).max(100)
+            } else {
+                (price.percent + RECOVERY_PERCENT).min(100)
+            };
+            let drifted = recovered as i32 + rng.gen_range(-DRIFT_PERCENT..=DRIFT_PERCENT);
+            price.percent = (drifted.max(0) as u32).clamp(MIN_PRICE_PERCENT, MAX_PRICE_PERCENT);
+        }
+    }
+
+    pub fn record_sale(&mut self, organ_type: OrganType) {
+        let price = self.price_mut(organ_type);
+        price.percent = price
+            .percent
+            .saturating_sub(SALE_PERCENT)
+            .max(MIN_PRICE_PERCENT);
+    }
+
+    pub fn record_purchase(&mut self, organ_type: OrganType) {
+        let price = self.price_mut(organ_type);
+        price.percent = (price.percent + PURCHASE_PERCENT).min(MAX_PRICE_PERCENT);
+    }
+
+    /// The current price of every traded organ type, for the market report
+    pub fn report(&self) -> Vec<OrganMarketPrice> {
+        TRADED_ORGAN_TYPES
+            .iter()
+            .map(|&organ_type| {
+                self.prices
+                    .iter()
+                    .find(|price| price.organ_type == organ_type)
+                    .cloned()
+                    .unwrap_or(OrganMarketPrice {
+                        organ_type,
+                        percent: 100,
+                        previous_percent: 100,
+                    })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,219 @@
+use crate::loot::Rarity;
+use coord_2d::{Coord, Size};
+use direction::CardinalDirection;
+use grid_2d::Grid;
+use procgen::city::Tile;
+use rand::{seq::SliceRandom, Rng};
+use std::collections::{HashSet, VecDeque};
+
+// Prefabs are hand-authored rooms which are stamped into generated levels. Each is described by
+// a grid of characters:
+// ' ' leaves the generated level untouched
+// '#' wall
+// '.' floor
+// '+' door (exactly one, on the outside of the prefab so the room can be entered)
+// 'g' a gun
+// 'a' ammunition
+// 's' a stimpack
+// 'o' an organ in a container
+// '$' money
+// 'C' an organ clinic
+
+/// Something to spawn inside a prefab once it has been placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefabContent {
+    Gun,
+    Ammo,
+    Stimpack,
+    Organ,
+    Money,
+    OrganClinic,
+}
+
+struct Prefab {
+    text: &'static str,
+    min_level: usize,
+    rarity: Rarity,
+}
+
+const ARMORY: Prefab = Prefab {
+    text: "\
+#######
+#g.a.g#
+#.....#
+#a.g.a#
+###+###",
+    min_level: 1,
+    rarity: Rarity::Uncommon,
+};
+
+const SURGERY_THEATER: Prefab = Prefab {
+    text: "\
+#######
+#s...s#
+#..C..#
+#s...s#
+###+###",
+    min_level: 1,
+    rarity: Rarity::Rare,
+};
+
+const ORGAN_SHRINE: Prefab = Prefab {
+    text: "  #####
+ ##o.o##
+##.....##
+#o..$..o#
+##.....##
+ ##o.o##
+  ##+##",
+    min_level: 2,
+    rarity: Rarity::Rare,
+};
+
+const PREFABS: &[Prefab] = &[ARMORY, SURGERY_THEATER, ORGAN_SHRINE];
+
+// Chance that a level gets a prefab at all
+const PREFAB_PROBABILITY: f64 = 0.5;
+const MAX_PLACEMENT_ATTEMPTS: usize = 200;
+
+impl Prefab {
+    fn cells(&self) -> impl Iterator<Item = (Coord, char)> {
+        self.text.split('\n').enumerate().flat_map(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .filter(|&(_, ch)| ch != ' ')
+                .map(move |(x, ch)| (Coord::new(x as i32, y as i32), ch))
+        })
+    }
+
+    fn size(&self) -> Size {
+        let width = self.text.split('\n').map(|row| row.len()).max().unwrap();
+        let height = self.text.split('\n').count();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+fn is_open(tile: Tile) -> bool {
+    !matches!(
+        tile,
+        Tile::Wall | Tile::Debris | Tile::Tentacle | Tile::Water
+    )
+}
+
+fn is_path_between(grid: &Grid<Tile>, start: Coord, end: Coord) -> bool {
+    let mut seen = HashSet::new();
+    seen.insert(start);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(coord) = queue.pop_front() {
+        if coord == end {
+            return true;
+        }
+        for d in CardinalDirection::all() {
+            let neighbour = coord + d.coord();
+            if let Some(&tile) = grid.get(neighbour) {
+                if is_open(tile) && seen.insert(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+    false
+}
+
+// Attempt to stamp the prefab into the grid with its top-left corner at `offset`. The prefab may
+// only replace walls and floors inside buildings, its door must lead somewhere open, and it
+// mustn't cut the stairs off from one another.
+fn try_place(
+    prefab: &Prefab,
+    grid: &mut Grid<Tile>,
+    offset: Coord,
+    stairs: (Coord, Coord),
+) -> Option<Vec<(Coord, PrefabContent)>> {
+    let footprint = prefab
+        .cells()
+        .map(|(coord, ch)| (coord + offset, ch))
+        .collect::<Vec<_>>();
+    let footprint_coords = footprint
+        .iter()
+        .map(|&(coord, _)| coord)
+        .collect::<HashSet<_>>();
+    for &(coord, _) in &footprint {
+        match grid.get(coord) {
+            Some(Tile::Wall | Tile::Floor) => (),
+            _ => return None,
+        }
+    }
+    let &(door_coord, _) = footprint.iter().find(|&&(_, ch)| ch == '+')?;
+    let entrance_is_open = CardinalDirection::all().any(|d| {
+        let coord = door_coord + d.coord();
+        !footprint_coords.contains(&coord) && grid.get(coord).cloned().is_some_and(is_open)
+    });
+    if !entrance_is_open {
+        return None;
+    }
+    let mut stamped = grid.clone();
+    let mut contents = Vec::new();
+    for &(coord, ch) in &footprint {
+        let tile = match ch {
+            '#' => Tile::Wall,
+            '+' => Tile::Door,
+            _ => Tile::Floor,
+        };
+        *stamped.get_checked_mut(coord) = tile;
+        let content = match ch {
+            'g' => PrefabContent::Gun,
+            'a' => PrefabContent::Ammo,
+            's' => PrefabContent::Stimpack,
+            'o' => PrefabContent::Organ,
+            '$' => PrefabContent::Money,
+            'C' => PrefabContent::OrganClinic,
+            _ => continue,
+        };
+        contents.push((coord, content));
+    }
+    if !is_path_between(&stamped, stairs.0, stairs.1) {
+        return None;
+    }
+    *grid = stamped;
+    Some(contents)
+}
+
+/// Possibly stamps a prefab into the level, returning the things which should be spawned inside
+/// it
+pub fn place_prefab<R: Rng>(
+    grid: &mut Grid<Tile>,
+    level_index: usize,
+    rng: &mut R,
+) -> Vec<(Coord, PrefabContent)> {
+    if !rng.gen_bool(PREFAB_PROBABILITY) {
+        return Vec::new();
+    }
+    let candidates = PREFABS
+        .iter()
+        .filter(|prefab| prefab.min_level <= level_index)
+        .collect::<Vec<_>>();
+    let prefab = match candidates.choose_weighted(rng, |prefab| prefab.rarity.weight()) {
+        Ok(prefab) => prefab,
+        Err(_) => return Vec::new(),
+    };
+    let find = |tile| grid.enumerate().find(|&(_, &t)| t == tile).map(|(c, _)| c);
+    let stairs = match (find(Tile::StairsUp), find(Tile::StairsDown)) {
+        (Some(up), Some(down)) => (up, down),
+        _ => return Vec::new(),
+    };
+    let size = prefab.size();
+    if size.width() > grid.width() || size.height() > grid.height() {
+        return Vec::new();
+    }
+    for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+        let offset = Coord::new(
+            rng.gen_range(0..=(grid.width() - size.width())) as i32,
+            rng.gen_range(0..=(grid.height() - size.height())) as i32,
+        );
+        if let Some(contents) = try_place(prefab, grid, offset, stairs) {
+            return contents;
+        }
+    }
+    Vec::new()
+}
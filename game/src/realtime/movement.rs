@@ -1,11 +1,25 @@
 use crate::realtime::Context;
+use coord_2d::Coord;
 use direction::Direction;
 use entity_table_realtime::{Entity, RealtimeComponent, RealtimeComponentApplyEvent};
 use line_2d::{InfiniteStepIter, StepIter};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// How high a projectile flies, which decides what it collides with before reaching the end of
+/// its path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FlightHeight {
+    /// Collides with the first solid thing or character in its path
+    #[default]
+    Level,
+    /// Arcs over characters and low obstacles such as debris, only colliding with tall things
+    /// like walls, doors and windows on the way to where it lands
+    Arcing,
+}
+
 pub mod spec {
+    pub use super::FlightHeight;
     pub use coord_2d::Coord;
     pub use std::time::Duration;
 
@@ -21,6 +35,7 @@ pub mod spec {
         pub path: Coord,
         pub repeat: Repeat,
         pub cardinal_step_duration: Duration,
+        pub flight_height: FlightHeight,
     }
 }
 
@@ -37,8 +52,11 @@ enum Path {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MovementState {
     path: Path,
+    /// The vector the path follows, or repeats
+    delta: Coord,
     cardinal_step_duration: Duration,
     ordinal_step_duration: Duration,
+    flight_height: FlightHeight,
 }
 
 fn ordinal_duration_from_cardinal_duration(duration: Duration) -> Duration {
@@ -50,6 +68,7 @@ fn ordinal_duration_from_cardinal_duration(duration: Duration) -> Duration {
 impl spec::Movement {
     pub fn build(self) -> MovementState {
         MovementState {
+            delta: self.path,
             path: match self.repeat {
                 spec::Repeat::Forever => Path::Forever(InfiniteStepIter::new(self.path)),
                 spec::Repeat::Once => Path::Once(StepIter::new(self.path)),
@@ -62,6 +81,7 @@ impl spec::Movement {
             ordinal_step_duration: ordinal_duration_from_cardinal_duration(
                 self.cardinal_step_duration,
             ),
+            flight_height: self.flight_height,
         }
     }
 }
@@ -70,10 +90,14 @@ impl MovementState {
     pub fn cardinal_step_duration(&self) -> Duration {
         self.cardinal_step_duration
     }
+
+    pub fn delta(&self) -> Coord {
+        self.delta
+    }
 }
 
 impl RealtimeComponent for MovementState {
-    type Event = Option<Direction>;
+    type Event = (Option<Direction>, FlightHeight);
 
     fn tick(&mut self) -> (Self::Event, Duration) {
         let event = match self.path {
@@ -100,16 +124,21 @@ impl RealtimeComponent for MovementState {
         } else {
             self.cardinal_step_duration
         };
-        (event, until_next_event)
+        ((event, self.flight_height), until_next_event)
     }
 }
 
 impl<'a> RealtimeComponentApplyEvent<Context<'a>> for MovementState {
-    fn apply_event(event: Option<Direction>, entity: Entity, context: &mut Context<'a>) {
+    fn apply_event(
+        (event, flight_height): (Option<Direction>, FlightHeight),
+        entity: Entity,
+        context: &mut Context<'a>,
+    ) {
         if let Some(movement_direction) = event {
             context.world.projectile_move(
                 entity,
                 movement_direction,
+                flight_height,
                 context.external_events,
                 context.message_log,
                 context.rng,
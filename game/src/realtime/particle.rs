@@ -118,6 +118,7 @@ impl spec::Movement {
             path: radial.to_cartesian().to_coord_round_nearest(),
             repeat: movement::spec::Repeat::Forever,
             cardinal_step_duration: self.cardinal_period_range.choose(rng),
+            flight_height: movement::FlightHeight::Level,
         }
         .build()
     }
@@ -0,0 +1,206 @@
+//! A protocol for playing the game remotely, so frontends which don't use chargrid (e.g. a mobile
+//! app or a richer web ui) can be built against the game. After each turn the server sends a
+//! `ServerMessage` describing everything the player can see, and the client replies with a
+//! `ClientMessage`. Both are sent as json, one message per websocket text frame.
+use crate::{
+    headless::{Headless, Status, Step, StepError},
+    CellVisibility, Config, GameOverReason, Input, MenuChoice, Message, PlayerStats,
+    VisibleCellData, VisibleEntity, Win,
+};
+use coord_2d::{Coord, Size};
+use serde::{Deserialize, Serialize};
+
+/// Something the client wants to do
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum ClientMessage {
+    Input(Input),
+    MenuChoice(MenuChoice),
+    CancelMenu,
+}
+
+impl ClientMessage {
+    fn step(self) -> Step {
+        match self {
+            Self::Input(input) => Step::Input(input),
+            Self::MenuChoice(choice) => Step::Choice(choice),
+            Self::CancelMenu => Step::CancelMenu,
+        }
+    }
+}
+
+/// A cell the player has seen. Cells the player has never seen, or which they know to be empty,
+/// are omitted, as are empty layers of each cell, to keep the messages small.
+#[derive(Serialize, Debug)]
+pub struct RemoteCell<'a> {
+    pub coord: Coord,
+    /// Whether the cell is currently visible, rather than remembered from when it was last seen
+    pub current: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub floor: Option<&'a VisibleEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature: Option<&'a VisibleEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<&'a VisibleEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<&'a VisibleEntity>,
+}
+
+impl<'a> RemoteCell<'a> {
+    fn new(coord: Coord, current: bool, data: &'a VisibleCellData) -> Option<Self> {
+        let layer = |entity: &'a VisibleEntity| entity.tile.map(|_| entity);
+        let cell = Self {
+            coord,
+            current,
+            floor: layer(&data.tiles.floor),
+            feature: layer(&data.tiles.feature),
+            character: layer(&data.tiles.character),
+            item: layer(&data.tiles.item),
+        };
+        let is_empty = cell.floor.is_none()
+            && cell.feature.is_none()
+            && cell.character.is_none()
+            && cell.item.is_none();
+        if is_empty {
+            None
+        } else {
+            Some(cell)
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum RemoteStatus {
+    Running,
+    Menu {
+        text: String,
+        choices: Vec<MenuChoice>,
+    },
+    GameOver(GameOverReason),
+    Win(Win),
+}
+
+/// Everything a client needs to display the game after a turn
+#[derive(Serialize, Debug)]
+pub struct ServerMessage<'a> {
+    pub world_size: Size,
+    pub level_index: usize,
+    pub player_coord: Coord,
+    pub cells: Vec<RemoteCell<'a>>,
+    pub stats: PlayerStats,
+    pub money: u32,
+    /// Messages added to the message log since the previous `ServerMessage`
+    pub messages: &'a [Message],
+    pub status: RemoteStatus,
+    /// Why the client's last message had no effect, if it didn't
+    pub error: Option<StepError>,
+}
+
+/// A game played by a remote client
+pub struct RemoteGame {
+    headless: Headless,
+    // how much of the message log has already been sent to the client
+    num_messages_sent: usize,
+    error: Option<StepError>,
+}
+
+impl RemoteGame {
+    pub fn new(config: Config, rng_seed: u64) -> Self {
+        Self {
+            headless: Headless::new(config, rng_seed),
+            num_messages_sent: 0,
+            error: None,
+        }
+    }
+
+    pub fn headless(&self) -> &Headless {
+        &self.headless
+    }
+
+    pub fn handle_client_message(&mut self, client_message: ClientMessage) {
+        // Remote clients can only choose from the menu they've been shown, as anything else
+        // could be a choice the game would never have offered
+        if let ClientMessage::MenuChoice(choice) = client_message {
+            let offered = match self.headless.status() {
+                Status::Menu(menu) => menu.choices.contains(&choice),
+                _ => false,
+            };
+            if !offered {
+                self.error = Some(StepError::InvalidChoice);
+                return;
+            }
+        }
+        self.error = self.headless.step(client_message.step()).err();
+    }
+
+    /// Parse a message sent by the client and apply it to the game
+    pub fn handle_client_json(&mut self, json: &str) -> serde_json::Result<()> {
+        let client_message = serde_json::from_str(json)?;
+        self.handle_client_message(client_message);
+        Ok(())
+    }
+
+    /// Describe the game as it is after the most recent client message
+    pub fn server_message(&mut self) -> ServerMessage<'_> {
+        let game = self.headless.game();
+        let world_size = game.world_size();
+        let cells = world_size
+            .coord_iter_row_major()
+            .filter_map(|coord| {
+                let (current, data) = match game.cell_visibility_at_coord(coord) {
+                    CellVisibility::Never => return None,
+                    CellVisibility::Previous(data) => (false, data),
+                    CellVisibility::Current { data, .. } => (true, data),
+                };
+                RemoteCell::new(coord, current, data)
+            })
+            .collect();
+        let status = match self.headless.status() {
+            Status::Running => RemoteStatus::Running,
+            Status::Menu(menu) => RemoteStatus::Menu {
+                text: menu.text.clone(),
+                choices: menu.choices.clone(),
+            },
+            Status::GameOver(reason) => RemoteStatus::GameOver(reason),
+            Status::Win(win) => RemoteStatus::Win(win),
+        };
+        let messages = &game.message_log()[self.num_messages_sent..];
+        self.num_messages_sent = game.message_log().len();
+        ServerMessage {
+            world_size,
+            level_index: game.current_level_index(),
+            player_coord: game.player_coord(),
+            cells,
+            stats: game.player_stats(),
+            money: game.player_money(),
+            messages,
+            status,
+            error: self.error,
+        }
+    }
+
+    /// The json of the next message to send to the client
+    pub fn server_json(&mut self) -> String {
+        serde_json::to_string(&self.server_message()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn choice_without_menu_is_rejected() {
+        let mut remote_game = RemoteGame::new(Config::default(), 0);
+        assert!(matches!(remote_game.headless().status(), Status::Running));
+        let state_hash = remote_game.headless().game().state_hash();
+        remote_game.handle_client_message(ClientMessage::MenuChoice(MenuChoice::Dummy));
+        assert_eq!(
+            remote_game.server_message().error,
+            Some(StepError::InvalidChoice)
+        );
+        assert_eq!(remote_game.headless().game().state_hash(), state_hash);
+        assert!(remote_game.headless().replay().steps.is_empty());
+        remote_game.handle_client_message(ClientMessage::Input(Input::Wait));
+        assert_eq!(remote_game.server_message().error, None);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    world::data::{Item, NpcType},
+    Game, Message,
+};
+use coord_2d::Coord;
+
+#[cfg(feature = "rhai")]
+pub mod rhai;
+
+// Mods observe and adjust the game through these hooks rather than by modifying this crate. A
+// scripting backend (such as the rhai one enabled by the "rhai" feature) implements
+// `ScriptHooks` by forwarding each hook to a function defined in a script, and is attached to a
+// game with `Game::set_script_hooks`. Hooks aren't saved along with the game, so they must be
+// attached again after loading.
+
+/// A loaded mod, which hooks can be made from for each game it's attached to
+pub trait Script {
+    fn hooks(&self) -> Box<dyn ScriptHooks>;
+}
+
+/// Callbacks invoked by the game as things happen. Every hook does nothing by default.
+pub trait ScriptHooks {
+    /// Called at the end of each turn
+    fn on_turn(&mut self, _api: &mut ScriptApi) {}
+
+    /// Called when an npc dies, regardless of who killed it
+    fn on_kill(&mut self, _npc_type: NpcType, _api: &mut ScriptApi) {}
+
+    /// Called when the player applies an item from their inventory
+    fn on_item_use(&mut self, _item: Item, _api: &mut ScriptApi) {}
+
+    /// Names of extra actions to offer the player. Choosing one calls `on_menu_action` with its
+    /// index and takes a turn.
+    fn menu_actions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn on_menu_action(&mut self, _index: usize, _api: &mut ScriptApi) {}
+}
+
+/// The parts of the game which scripts are allowed to read and change
+pub struct ScriptApi<'a> {
+    game: &'a mut Game,
+}
+
+impl<'a> ScriptApi<'a> {
+    pub fn turn_count(&self) -> u64 {
+        self.game.turn_count
+    }
+
+    pub fn level_index(&self) -> usize {
+        self.game.current_level_index
+    }
+
+    pub fn player_coord(&self) -> Coord {
+        self.game.player_coord()
+    }
+
+    pub fn player_money(&self) -> u32 {
+        self.game.player_money()
+    }
+
+    /// Add to (or with a negative amount, remove from) the player's money
+    pub fn add_money(&mut self, amount: i32) {
+        let money = self
+            .game
+            .world
+            .components
+            .money
+            .get_mut(self.game.player_entity)
+            .unwrap();
+        *money = money.saturating_add_signed(amount);
+    }
+
+    pub fn heal_player(&mut self, amount: u32) {
+        self.game
+            .world
+            .components
+            .health
+            .get_mut(self.game.player_entity)
+            .unwrap()
+            .increase(amount);
+    }
+
+    /// Put an item in the player's inventory. Returns false if the inventory is full.
+    pub fn give_item(&mut self, item: Item) -> bool {
+        let player_entity = self.game.player_entity;
+        let has_space = self
+            .game
+            .world
+            .components
+            .inventory
+            .get(player_entity)
+            .unwrap()
+            .items()
+            .iter()
+            .any(Option::is_none);
+        if !has_space {
+            return false;
+        }
+        let item_entity = self.game.world.spawn_item_no_coord(item);
+        let inventory = self
+            .game
+            .world
+            .components
+            .inventory
+            .get_mut(player_entity)
+            .unwrap();
+        *inventory.first_free_slot().unwrap() = Some(item_entity);
+        true
+    }
+
+    /// Add a line to the message log
+    pub fn message(&mut self, text: String) {
+        self.game.message_log.push(Message::Script(text));
+    }
+}
+
+impl Game {
+    pub fn set_script_hooks(&mut self, script_hooks: Box<dyn ScriptHooks>) {
+        self.script_hooks = Some(script_hooks);
+    }
+
+    pub fn script_menu_actions(&self) -> Vec<String> {
+        self.script_hooks
+            .as_ref()
+            .map(|script_hooks| script_hooks.menu_actions())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn run_script_hook<F: FnOnce(&mut dyn ScriptHooks, &mut ScriptApi)>(
+        &mut self,
+        f: F,
+    ) {
+        // the hooks are taken out of the game while they run so they can be given mutable access
+        // to the rest of the game
+        if let Some(mut script_hooks) = self.script_hooks.take() {
+            f(script_hooks.as_mut(), &mut ScriptApi { game: self });
+            self.script_hooks = Some(script_hooks);
+        }
+    }
+
+    pub(crate) fn run_script_turn_hooks(&mut self) {
+        if self.script_hooks.is_none() {
+            self.script_message_cursor = self.message_log.len();
+            return;
+        }
+        let kills = self.message_log[self.script_message_cursor..]
+            .iter()
+            .filter_map(|message| match message {
+                Message::NpcDies(npc_type) => Some(*npc_type),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for npc_type in kills {
+            self.run_script_hook(|script_hooks, api| script_hooks.on_kill(npc_type, api));
+        }
+        self.run_script_hook(|script_hooks, api| script_hooks.on_turn(api));
+        self.script_message_cursor = self.message_log.len();
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    script::{Script, ScriptApi, ScriptHooks},
+    world::data::{Item, NpcType},
+};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::{cell::RefCell, rc::Rc};
+
+// A script defines whichever of these functions it needs. Each hook is passed a map describing
+// the game (see `game_map`), and scripts change the game by calling the functions registered in
+// `RhaiScriptHooks::new`:
+//
+//   fn on_turn(game) { if game.turn_count % 100 == 0 { add_money(1); } }
+//   fn on_kill(game, npc_type) { message(`The ${npc_type} is dead`); }
+//   fn on_item_use(game, item) { if item == "Stimpack" { heal_player(1); } }
+//   fn menu_actions() { ["Pray"] }
+//   fn on_menu_action(game, index) { message("Nobody answers"); }
+//
+// Npc types and items are named as in the game's `NpcType` and `Item` enums.
+
+/// A compiled rhai script, which hooks can be made from for each game the script is attached to
+#[derive(Clone)]
+pub struct RhaiScript {
+    ast: Rc<AST>,
+}
+
+impl RhaiScript {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        Engine::new()
+            .compile(source)
+            .map(|ast| Self { ast: Rc::new(ast) })
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Script for RhaiScript {
+    fn hooks(&self) -> Box<dyn ScriptHooks> {
+        Box::new(RhaiScriptHooks::new(self.ast.clone()))
+    }
+}
+
+// Scripts can't hold on to the game between calls, so the changes they ask for are queued up
+// and made once the script function returns
+enum Command {
+    AddMoney(i64),
+    HealPlayer(i64),
+    GiveItem(String),
+    Message(String),
+}
+
+struct RhaiScriptHooks {
+    engine: Engine,
+    ast: Rc<AST>,
+    commands: Rc<RefCell<Vec<Command>>>,
+}
+
+fn game_map(api: &ScriptApi) -> Dynamic {
+    let player_coord = api.player_coord();
+    let mut map = Map::new();
+    map.insert("turn_count".into(), (api.turn_count() as i64).into());
+    map.insert("level_index".into(), (api.level_index() as i64).into());
+    map.insert("player_x".into(), (player_coord.x as i64).into());
+    map.insert("player_y".into(), (player_coord.y as i64).into());
+    map.insert("player_money".into(), (api.player_money() as i64).into());
+    map.into()
+}
+
+// Unit variants serialize as their names, so a name can be turned back into an item the same way
+fn parse_item(name: &str) -> Option<Item> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+impl RhaiScriptHooks {
+    fn new(ast: Rc<AST>) -> Self {
+        let commands = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        let queue = commands.clone();
+        engine.register_fn("add_money", move |amount: i64| {
+            queue.borrow_mut().push(Command::AddMoney(amount))
+        });
+        let queue = commands.clone();
+        engine.register_fn("heal_player", move |amount: i64| {
+            queue.borrow_mut().push(Command::HealPlayer(amount))
+        });
+        let queue = commands.clone();
+        engine.register_fn("give_item", move |name: &str| {
+            queue.borrow_mut().push(Command::GiveItem(name.to_string()))
+        });
+        let queue = commands.clone();
+        engine.register_fn("message", move |text: &str| {
+            queue.borrow_mut().push(Command::Message(text.to_string()))
+        });
+        Self {
+            engine,
+            ast,
+            commands,
+        }
+    }
+
+    fn has_fn(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    fn call(&mut self, name: &str, mut args: Vec<Dynamic>, api: &mut ScriptApi) {
+        if !self.has_fn(name) {
+            return;
+        }
+        args.insert(0, game_map(api));
+        if let Err(e) = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args)
+        {
+            log::error!("script error in {}: {}", name, e);
+        }
+        for command in self.commands.borrow_mut().drain(..) {
+            match command {
+                Command::AddMoney(amount) => {
+                    api.add_money(amount.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+                }
+                Command::HealPlayer(amount) => {
+                    api.heal_player(amount.clamp(0, u32::MAX as i64) as u32)
+                }
+                Command::GiveItem(name) => match parse_item(&name) {
+                    Some(item) => {
+                        if !api.give_item(item) {
+                            log::warn!("script couldn't give {} as the inventory is full", name);
+                        }
+                    }
+                    None => log::error!("script tried to give unknown item {}", name),
+                },
+                Command::Message(text) => api.message(text),
+            }
+        }
+    }
+}
+
+impl ScriptHooks for RhaiScriptHooks {
+    fn on_turn(&mut self, api: &mut ScriptApi) {
+        self.call("on_turn", Vec::new(), api);
+    }
+
+    fn on_kill(&mut self, npc_type: NpcType, api: &mut ScriptApi) {
+        self.call("on_kill", vec![format!("{:?}", npc_type).into()], api);
+    }
+
+    fn on_item_use(&mut self, item: Item, api: &mut ScriptApi) {
+        self.call("on_item_use", vec![format!("{:?}", item).into()], api);
+    }
+
+    fn menu_actions(&self) -> Vec<String> {
+        if !self.has_fn("menu_actions") {
+            return Vec::new();
+        }
+        match self
+            .engine
+            .call_fn::<Array>(&mut Scope::new(), &self.ast, "menu_actions", ())
+        {
+            Ok(actions) => actions
+                .into_iter()
+                .map(|action| action.to_string())
+                .collect(),
+            Err(e) => {
+                log::error!("script error in menu_actions: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn on_menu_action(&mut self, index: usize, api: &mut ScriptApi) {
+        self.call("on_menu_action", vec![(index as i64).into()], api);
+    }
+}
@@ -1,13 +1,138 @@
-use crate::world::{data::*, World};
+use crate::{
+    balance::Balance,
+    documents::{Document, DocumentKind},
+    loot::{self, LevelSpawns},
+    prefab::{self, PrefabContent},
+    world::{data::*, World},
+};
 use coord_2d::{Coord, Size};
-use direction::Direction;
+use direction::{CardinalDirection, Direction};
+use grid_2d::Grid;
 use procgen::city::{Map, TentacleSpec, Tile};
 use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
+// Chance that an indoor level starts without power, leaving its elevators and lights off until
+// the player starts its generator
+const POWER_OUTAGE_PROBABILITY: f64 = 0.5;
+const GENERATOR_OUT_OF_FUEL_PROBABILITY: f64 = 0.5;
+
+// Chance that a section of building wall between a street and a room is glazed
+const WINDOW_PROBABILITY: f64 = 0.25;
+
+const CIVILIANS_PER_SHOP: usize = 2;
+const ITEMS_ON_DISPLAY_PER_SHOP: usize = 2;
+
+// A wall with the street on one side and a room on the opposite side
+fn is_window_candidate(grid: &Grid<Tile>, coord: Coord) -> bool {
+    CardinalDirection::all().any(|direction| {
+        let outside = grid.get(coord + direction.coord());
+        let inside = grid.get(coord - direction.coord());
+        matches!(outside, Some(Tile::Street | Tile::Footpath | Tile::Alley))
+            && matches!(inside, Some(Tile::Floor))
+    })
+}
+
 pub struct Terrain {
     pub world: World,
 }
 
+// Chance that a body has something worth taking lying on it
+const REMAINS_LOOT_PROBABILITY: f64 = 0.3;
+// Chance that a vending machine has dropped a snack
+const VENDING_MACHINE_FOOD_PROBABILITY: f64 = 0.25;
+
+// Whether a coordinate and all its neighbours are clear floor, so placing something solid there
+// can't block a corridor or doorway
+fn is_open_floor(world: &World, coord: Coord) -> bool {
+    std::iter::once(coord)
+        .chain(Direction::all().map(|direction| coord + direction.coord()))
+        .all(|coord| {
+            world.spatial_table.layers_at(coord).is_some_and(|layers| {
+                layers.floor.is_some()
+                    && layers.feature.is_none()
+                    && layers.character.is_none()
+                    && layers.item.is_none()
+            })
+        })
+}
+
+// Leaves the notes and emails belonging to a level lying around, and puts its logs on terminals
+fn place_documents(world: &mut World, level_index: usize, candidates: &mut Vec<Coord>) {
+    for document in Document::on_level(level_index) {
+        while let Some(coord) = candidates.pop() {
+            if document.kind() == DocumentKind::Log {
+                if is_open_floor(world, coord) {
+                    world.spawn_terminal(coord, document);
+                    break;
+                }
+            } else if world
+                .spatial_table
+                .layers_at(coord)
+                .is_some_and(|layers| layers.item.is_none() && layers.feature.is_none())
+            {
+                world.spawn_item(coord, Item::Readable(document));
+                break;
+            }
+        }
+    }
+}
+
+// Scatters props hinting at what happened to the city's inhabitants
+fn decorate<R: Rng>(
+    world: &mut World,
+    theme: LevelTheme,
+    level_index: usize,
+    candidates: &mut Vec<Coord>,
+    rng: &mut R,
+) {
+    for &(prop, count) in theme.props() {
+        if prop == Prop::Graffiti {
+            world.spray_graffiti(count, rng);
+            continue;
+        }
+        let mut placed = 0;
+        while placed < count {
+            let coord = match candidates.pop() {
+                Some(coord) => coord,
+                None => return,
+            };
+            if prop.is_floor_decoration() {
+                if !world.decorate_floor(coord, prop) {
+                    continue;
+                }
+                if prop == Prop::Remains && rng.gen_bool(REMAINS_LOOT_PROBABILITY) {
+                    let item = loot::choose_item(theme, level_index, rng);
+                    world.spawn_item(coord, item);
+                }
+            } else {
+                if !is_open_floor(world, coord) {
+                    continue;
+                }
+                world.spawn_prop(coord, prop);
+                if prop == Prop::VendingMachine && rng.gen_bool(VENDING_MACHINE_FOOD_PROBABILITY) {
+                    let direction = *CardinalDirection::all()
+                        .collect::<Vec<_>>()
+                        .choose(rng)
+                        .unwrap();
+                    world.spawn_item(coord + direction.coord(), Item::Food);
+                }
+            }
+            placed += 1;
+        }
+    }
+}
+
+// Levels may be larger than the part of the screen where the game is drawn, in which case the
+// camera scrolls to follow the player. The first level is the surface of the city which is wider
+// than the buildings beneath it.
+fn level_size(level_index: usize) -> Size {
+    if level_index == 0 {
+        Size::new(80, 30)
+    } else {
+        Size::new(50, 25)
+    }
+}
+
 impl Terrain {
     #[allow(unused)]
     pub fn generate_text() -> Self {
@@ -25,10 +150,13 @@ impl Terrain {
                         world.spawn_organ_clinic(coord, 2, &mut rng);
                     }
                     'I' => {
-                        world.spawn_item_store(coord, &mut rng);
+                        world.spawn_item_store(coord, 2, &mut rng);
                     }
                     'G' => {
-                        world.spawn_gun_store(coord, &mut rng);
+                        world.spawn_gun_store(coord, 2, &mut rng);
+                    }
+                    'T' => {
+                        world.spawn_organ_trader(coord, &mut rng);
                     }
                     'x' => {
                         world.spawn_corruptor(coord, &mut rng);
@@ -118,6 +246,9 @@ impl Terrain {
                                 },
                                 cybernetic: false,
                                 original: false,
+                                harvested: false,
+                                disabled: false,
+                                donor: None,
                             })),
                         );
                     }
@@ -128,7 +259,7 @@ impl Terrain {
         Self { world }
     }
 
-    pub fn generate<R: Rng>(level_index: usize, rng: &mut R) -> Self {
+    pub fn generate<R: Rng>(level_index: usize, balance: &Balance, rng: &mut R) -> Self {
         //return Self::generate_text();
         let tentacle_spec = TentacleSpec {
             num_tentacles: 2,
@@ -136,12 +267,24 @@ impl Terrain {
             distance_from_centre: 35.0,
             spread: 0.2,
         };
-        let map = Map::generate(&tentacle_spec, rng);
+        let mut map = if level_index == 0 {
+            Map::generate_surface(level_size(level_index), &tentacle_spec, rng)
+        } else {
+            Map::generate(level_size(level_index), &tentacle_spec, rng)
+        };
+        let prefab_contents = prefab::place_prefab(&mut map.grid, level_index, rng);
         let mut world = World::new(map.grid.size());
+        world.theme = if level_index == 0 {
+            LevelTheme::Surface
+        } else {
+            *LevelTheme::INDOOR.choose(rng).unwrap()
+        };
+        let theme = world.theme;
         let mut tentacle_count = 0;
         let mut debris_count = 0;
         let mut empty_space = Vec::new();
         let mut player_spawn = None;
+        let mut tentacle_coords = Vec::new();
         for (coord, &tile) in map.grid.enumerate() {
             match tile {
                 Tile::Street => {
@@ -156,13 +299,28 @@ impl Terrain {
                     empty_space.push(coord);
                     world.spawn_footpath(coord);
                 }
+                Tile::Bridge => {
+                    empty_space.push(coord);
+                    world.spawn_bridge(coord);
+                }
+                Tile::Water => {
+                    world.spawn_floor(coord);
+                    world.spawn_water(coord);
+                }
                 Tile::Wall => {
                     world.spawn_floor(coord);
-                    world.spawn_wall(coord);
+                    if is_window_candidate(&map.grid, coord) && rng.gen_bool(WINDOW_PROBABILITY) {
+                        world.spawn_window(coord);
+                    } else {
+                        world.spawn_wall(coord);
+                    }
                 }
                 Tile::Floor => {
                     empty_space.push(coord);
                     world.spawn_floor(coord);
+                    if level_index > 0 && coord.x % 8 == 4 && coord.y % 6 == 3 {
+                        world.spawn_ceiling_light(coord);
+                    }
                 }
                 Tile::Debris => {
                     if debris_count % 5 == 0 {
@@ -178,6 +336,7 @@ impl Terrain {
                     world.spawn_door(coord);
                 }
                 Tile::Tentacle => {
+                    tentacle_coords.push(coord);
                     world.spawn_floor(coord);
                     if tentacle_count % 10 == 0 {
                         world.spawn_tentacle_glow(coord);
@@ -202,6 +361,66 @@ impl Terrain {
             }
         }
         let player_spawn = player_spawn.expect("no player spawn");
+        for &(coord, content) in prefab_contents.iter() {
+            empty_space.retain(|&c| c != coord);
+            match content {
+                PrefabContent::Gun => {
+                    let item = *[Item::Pistol, Item::Shotgun, Item::RocketLauncher]
+                        .choose(rng)
+                        .unwrap();
+                    world.spawn_item(coord, item);
+                }
+                PrefabContent::Ammo => {
+                    let item = *[Item::PistolAmmo, Item::ShotgunAmmo, Item::Rocket]
+                        .choose(rng)
+                        .unwrap();
+                    world.spawn_item(coord, item);
+                }
+                PrefabContent::Stimpack => {
+                    world.spawn_item(coord, Item::Stimpack);
+                }
+                PrefabContent::Organ => {
+                    let organ = Organ {
+                        type_: loot::choose_organ_type(level_index, rng),
+                        cybernetic: false,
+                        original: false,
+                        harvested: false,
+                        disabled: false,
+                        donor: None,
+                        traits: OrganTraits::with_one_random(rng),
+                    };
+                    world.spawn_item(coord, Item::OrganContainer(Some(organ)));
+                }
+                PrefabContent::Money => {
+                    world.spawn_money(coord, rng);
+                }
+                PrefabContent::OrganClinic => {
+                    world.spawn_organ_clinic(coord, level_index, rng);
+                }
+            }
+        }
+        if level_index == 0 {
+            let mut stash_candidates = empty_space.clone();
+            stash_candidates.sort_by_key(|coord| coord.manhattan_distance(player_spawn));
+            for coord in stash_candidates {
+                if coord.manhattan_distance(player_spawn) < 2 {
+                    continue;
+                }
+                let mut good = true;
+                for d in Direction::all() {
+                    if let Some(layers) = world.spatial_table.layers_at(coord + d.coord()) {
+                        if layers.feature.is_some() {
+                            good = false;
+                        }
+                    }
+                }
+                if good {
+                    empty_space.retain(|&c| c != coord);
+                    world.spawn_stash(coord);
+                    break;
+                }
+            }
+        }
         let mut npc_spawn_candidates = empty_space
             .iter()
             .cloned()
@@ -244,7 +463,24 @@ impl Terrain {
             if good {
                 let coord = *coord;
                 npc_spawn_candidates.swap_remove(i);
-                world.spawn_item_store(coord, rng);
+                world.spawn_item_store(coord, level_index, rng);
+                break;
+            }
+        }
+
+        for (i, coord) in npc_spawn_candidates.iter().enumerate() {
+            let mut good = true;
+            for d in Direction::all() {
+                if let Some(layers) = world.spatial_table.layers_at(*coord + d.coord()) {
+                    if layers.feature.is_some() || layers.character.is_some() {
+                        good = false;
+                    }
+                }
+            }
+            if good {
+                let coord = *coord;
+                npc_spawn_candidates.swap_remove(i);
+                world.spawn_gun_store(coord, level_index, rng);
                 break;
             }
         }
@@ -261,11 +497,122 @@ impl Terrain {
             if good {
                 let coord = *coord;
                 npc_spawn_candidates.swap_remove(i);
-                world.spawn_gun_store(coord, rng);
+                world.spawn_organ_trader(coord, rng);
                 break;
             }
         }
 
+        // corrupted items turn up close to the corruption
+        let mut corrupted_item_candidates = npc_spawn_candidates
+            .iter()
+            .cloned()
+            .filter(|coord| {
+                tentacle_coords
+                    .iter()
+                    .any(|tentacle_coord| tentacle_coord.manhattan_distance(*coord) <= 4)
+            })
+            .collect::<Vec<_>>();
+        corrupted_item_candidates.shuffle(rng);
+        for coord in corrupted_item_candidates.into_iter().take(2) {
+            npc_spawn_candidates.retain(|&c| c != coord);
+            let item = *[Item::CorruptedStimpack, Item::CursedPistol]
+                .choose(rng)
+                .unwrap();
+            world.spawn_unidentified_item(coord, item);
+        }
+
+        let shop_coords = world
+            .components
+            .shop
+            .entities()
+            .filter_map(|entity| world.spatial_table.coord_of(entity))
+            .collect::<Vec<_>>();
+        for &shop_coord in shop_coords.iter() {
+            let mut alarm_panel_candidates = npc_spawn_candidates
+                .iter()
+                .cloned()
+                .filter(|coord| (2..=4).contains(&coord.manhattan_distance(shop_coord)))
+                .collect::<Vec<_>>();
+            alarm_panel_candidates.sort_by_key(|coord| coord.manhattan_distance(shop_coord));
+            for coord in alarm_panel_candidates {
+                let mut good = true;
+                for d in Direction::all() {
+                    if let Some(layers) = world.spatial_table.layers_at(coord + d.coord()) {
+                        if layers.feature.is_some() || layers.character.is_some() {
+                            good = false;
+                        }
+                    }
+                }
+                if good {
+                    npc_spawn_candidates.retain(|&c| c != coord);
+                    world.spawn_alarm_panel(coord);
+                    break;
+                }
+            }
+        }
+
+        // vendors put some of their range on display nearby, where it can be stolen
+        let vendors = world.components.shop.entities().collect::<Vec<_>>();
+        for vendor in vendors {
+            let vendor_coord = match world.spatial_table.coord_of(vendor) {
+                Some(coord) => coord,
+                None => continue,
+            };
+            let mut display_candidates = npc_spawn_candidates
+                .iter()
+                .cloned()
+                .filter(|coord| (3..=6).contains(&coord.manhattan_distance(vendor_coord)))
+                .collect::<Vec<_>>();
+            display_candidates.shuffle(rng);
+            for coord in display_candidates
+                .into_iter()
+                .take(ITEMS_ON_DISPLAY_PER_SHOP)
+            {
+                if world
+                    .spawn_item_for_sale(coord, vendor, level_index, rng)
+                    .is_some()
+                {
+                    npc_spawn_candidates.retain(|&c| c != coord);
+                }
+            }
+        }
+
+        // civilians go about their business around the shops
+        for &shop_coord in shop_coords.iter() {
+            let mut civilian_candidates = npc_spawn_candidates
+                .iter()
+                .cloned()
+                .filter(|coord| (3..=8).contains(&coord.manhattan_distance(shop_coord)))
+                .collect::<Vec<_>>();
+            civilian_candidates.shuffle(rng);
+            for coord in civilian_candidates.into_iter().take(CIVILIANS_PER_SHOP) {
+                npc_spawn_candidates.retain(|&c| c != coord);
+                world.spawn_civilian(coord, rng);
+            }
+        }
+
+        if level_index > 0 && rng.gen_bool(POWER_OUTAGE_PROBABILITY) {
+            let generator_coord = npc_spawn_candidates.iter().cloned().find(|&coord| {
+                Direction::all().all(|d| {
+                    world
+                        .spatial_table
+                        .layers_at(coord + d.coord())
+                        .is_some_and(|layers| {
+                            layers.feature.is_none() && layers.character.is_none()
+                        })
+                })
+            });
+            if let Some(coord) = generator_coord {
+                npc_spawn_candidates.retain(|&c| c != coord);
+                world.spawn_generator(coord, rng.gen_bool(GENERATOR_OUT_OF_FUEL_PROBABILITY));
+                world.powered = false;
+            }
+        }
+
+        if let Some(coord) = npc_spawn_candidates.pop() {
+            world.spawn_mercenary(coord, rng);
+        }
+
         for _ in 0..8 {
             if let Some(coord) = npc_spawn_candidates.pop() {
                 world.spawn_money(coord, rng);
@@ -305,241 +652,80 @@ impl Terrain {
             }
         }
 
-        let num_zombies;
-        let num_climbers;
-        let num_trespassers;
-        let num_snatchers;
-        let mut num_boomers = 0;
-        let mut num_dividers = 0;
-        let mut num_poisoners = 0;
-        let mut num_venters = 0;
-        let mut num_glowers = 0;
-        match level_index {
-            0 => {
-                num_zombies = rng.gen_range(1..=1);
-                num_climbers = rng.gen_range(1..=2);
-                num_trespassers = 1;
-                num_snatchers = 1;
-                let hard_enemy_choice = rng.gen::<f64>();
-                if hard_enemy_choice < 0.33 {
-                    num_boomers = 1;
-                } else if hard_enemy_choice < 0.66 {
-                    num_dividers = 1;
-                } else {
-                    num_poisoners = 1;
-                }
-            }
-            1 => {
-                num_zombies = rng.gen_range(0..=2);
-                num_climbers = rng.gen_range(0..=2);
-                num_trespassers = rng.gen_range(0..=2);
-                num_snatchers = rng.gen_range(0..=2);
-                num_boomers = 1;
-                num_dividers = 1;
-                num_poisoners = 1;
-                let hard_enemy_choice = rng.gen::<f64>();
-                if hard_enemy_choice < 0.33 {
-                    num_boomers = 0;
-                } else if hard_enemy_choice < 0.66 {
-                    num_dividers = 0;
-                } else {
-                    num_poisoners = 0;
-                }
-                num_venters = 1;
-                num_glowers = 1;
-            }
-            2 => {
-                num_zombies = rng.gen_range(0..=2);
-                num_climbers = rng.gen_range(0..=2);
-                num_trespassers = rng.gen_range(0..=2);
-                num_snatchers = rng.gen_range(0..=2);
-                num_boomers = rng.gen_range(0..=2);
-                num_dividers = rng.gen_range(0..=2);
-                num_poisoners = rng.gen_range(0..=2);
-                num_venters = rng.gen_range(0..=2);
-                num_glowers = rng.gen_range(0..=2);
-            }
-            3 => {
-                num_zombies = rng.gen_range(1..=2);
-                num_climbers = rng.gen_range(1..=2);
-                num_trespassers = 1;
-                num_snatchers = 1;
-                let hard_enemy_choice = rng.gen::<f64>();
-                if hard_enemy_choice < 0.33 {
-                    num_boomers = 1;
-                } else if hard_enemy_choice < 0.66 {
-                    num_dividers = 1;
-                } else {
-                    num_poisoners = 1;
-                }
-                num_venters = 1;
-                num_glowers = 1;
-            }
-            _ => panic!(),
-        }
-
-        for _ in 0..num_zombies {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_zombie(coord, rng);
-            }
-        }
-        for _ in 0..num_climbers {
+        let LevelSpawns {
+            num_enemies,
+            num_items,
+            num_organs,
+        } = balance.level_spawns(level_index);
+        for _ in 0..num_enemies {
             if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_climber(coord, rng);
-            }
-        }
-        for _ in 0..num_trespassers {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_trespasser(coord, rng);
-            }
-        }
-        for _ in 0..num_snatchers {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_snatcher(coord, rng);
+                let npc_type = loot::choose_enemy(theme, level_index, rng);
+                let entity = world.spawn_enemy(npc_type, coord, rng);
+                if let Some(health) = world.components.health.get_mut(entity) {
+                    let max = (health.max() * balance.enemy_health_percent / 100).max(1);
+                    *health = Meter::new_full(max);
+                }
             }
         }
-        for _ in 0..num_boomers {
+        for _ in 0..num_organs {
             if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_boomer(coord, rng);
+                let organ = Organ {
+                    type_: loot::choose_organ_type(level_index, rng),
+                    cybernetic: false,
+                    original: false,
+                    harvested: false,
+                    disabled: false,
+                    donor: None,
+                    traits: OrganTraits::with_one_random(rng),
+                };
+                world.spawn_item(coord, Item::OrganContainer(Some(organ)));
             }
         }
-        for _ in 0..num_dividers {
+        for _ in 0..num_items {
             if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_divider(coord, rng);
+                let item = loot::choose_item(theme, level_index, rng);
+                world.spawn_item(coord, item);
             }
         }
-        for _ in 0..num_poisoners {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_poisoner(coord, rng);
+
+        // pools of acid, and on deeper levels, floors electrified by exposed wiring. These are kept
+        // away from shops so shopkeepers don't get hurt and turn hostile.
+        let mut hazard_candidates = npc_spawn_candidates
+            .iter()
+            .cloned()
+            .filter(|coord| {
+                shop_coords
+                    .iter()
+                    .all(|shop_coord| shop_coord.manhattan_distance(*coord) > 4)
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..theme.num_acid_pools() {
+            if let Some(coord) = hazard_candidates.pop() {
+                world.spread_floor_hazard(coord, FloorHazard::Acid, 2, rng);
             }
         }
-        for _ in 0..num_venters {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_venter(coord, rng);
+        for _ in 0..theme.num_electrified_patches(level_index) {
+            if let Some(coord) = hazard_candidates.pop() {
+                world.spread_floor_hazard(coord, FloorHazard::Electrified, 1, rng);
             }
         }
-        for _ in 0..num_glowers {
-            if let Some(coord) = npc_spawn_candidates.pop() {
-                world.spawn_glower(coord, rng);
+        // fuel drums which explode when shot or caught in a blast
+        for _ in 0..theme.num_explosive_barrels(level_index) {
+            if let Some(coord) = hazard_candidates.pop() {
+                world.spawn_explosive_barrel(coord);
             }
         }
-        match level_index {
-            0 => {
-                let pool = vec![OrganType::Claw, OrganType::CronenbergPistol];
-                if let Some(coord) = npc_spawn_candidates.pop() {
-                    let organ = Organ {
-                        type_: *pool.choose(rng).unwrap(),
-                        cybernetic: false,
-                        original: false,
-                        traits: OrganTraits::with_one_random(rng),
-                    };
-                    world.spawn_item(coord, Item::OrganContainer(Some(organ)));
-                }
-                let pool = vec![
-                    Item::PistolAmmo,
-                    Item::PistolAmmo,
-                    Item::PistolAmmo,
-                    Item::ShotgunAmmo,
-                    Item::Rocket,
-                    Item::Pistol,
-                ];
-                for &item in pool.choose_multiple(rng, 4) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        world.spawn_item(coord, item);
-                    }
-                }
-            }
-            1 => {
-                let pool = vec![
-                    OrganType::Claw,
-                    OrganType::CronenbergPistol,
-                    OrganType::CronenbergShotgun,
-                    OrganType::CyberCore,
-                ];
-                for &type_ in pool.choose_multiple(rng, 2) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        let organ = Organ {
-                            type_,
-                            cybernetic: false,
-                            original: false,
-                            traits: OrganTraits::with_one_random(rng),
-                        };
-                        world.spawn_item(coord, Item::OrganContainer(Some(organ)));
-                    }
-                }
-                let pool = vec![
-                    Item::Battery,
-                    Item::Battery,
-                    Item::PistolAmmo,
-                    Item::PistolAmmo,
-                    Item::ShotgunAmmo,
-                    Item::ShotgunAmmo,
-                    Item::Rocket,
-                    Item::Rocket,
-                    Item::Shotgun,
-                    Item::Shotgun,
-                    Item::Pistol,
-                    Item::Pistol,
-                ];
-                for &item in pool.choose_multiple(rng, 6) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        world.spawn_item(coord, item);
-                    }
-                }
-            }
-            2 => {
-                let pool = vec![
-                    OrganType::Claw,
-                    OrganType::CronenbergPistol,
-                    OrganType::CronenbergShotgun,
-                    OrganType::CyberCore,
-                ];
-                for &type_ in pool.choose_multiple(rng, 1) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        let organ = Organ {
-                            type_,
-                            cybernetic: false,
-                            original: false,
-                            traits: OrganTraits::with_one_random(rng),
-                        };
-                        world.spawn_item(coord, Item::OrganContainer(Some(organ)));
-                    }
-                }
-                let pool = vec![
-                    Item::Battery,
-                    Item::Battery,
-                    Item::PistolAmmo,
-                    Item::PistolAmmo,
-                    Item::ShotgunAmmo,
-                    Item::ShotgunAmmo,
-                    Item::Rocket,
-                    Item::Rocket,
-                ];
-                for &item in pool.choose_multiple(rng, 4) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        world.spawn_item(coord, item);
-                    }
-                }
-            }
-            3 => {
-                let pool = vec![
-                    Item::Battery,
-                    Item::Battery,
-                    Item::PistolAmmo,
-                    Item::PistolAmmo,
-                    Item::ShotgunAmmo,
-                    Item::ShotgunAmmo,
-                    Item::Rocket,
-                    Item::Rocket,
-                ];
-                for &item in pool.choose_multiple(rng, 8) {
-                    if let Some(coord) = npc_spawn_candidates.pop() {
-                        world.spawn_item(coord, item);
-                    }
-                }
+        // invisible areas of radiation which can only be measured with a geiger counter
+        for _ in 0..theme.num_radiation_zones(level_index) {
+            if let Some(centre) = hazard_candidates.pop() {
+                world.radiation_zones.push(RadiationZone {
+                    centre,
+                    radius: rng.gen_range(6..=10),
+                });
             }
-            _ => panic!(),
         }
+        place_documents(&mut world, level_index, &mut hazard_candidates);
+        decorate(&mut world, theme, level_index, &mut hazard_candidates, rng);
         Self { world }
     }
 }
@@ -0,0 +1,151 @@
+use crate::{
+    world::data::{Item, LevelTheme},
+    Game, Message, World,
+};
+use coord_2d::{Coord, Size};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// The tutorial takes place on a single hand-authored level:
+// '#' wall
+// '.' floor
+// '*' floor which completes the movement step when the player stands on it
+// '+' door
+// '@' where the player starts
+// 'z' a zombie
+// '8' an empty organ container
+// '$' money
+// 'C' an organ clinic
+const MAP: &str = "\
+##################################
+#.......#*********#..............#
+#.......#*********#..............#
+#..@....+*********+.......z......#
+#.......#*********#..............#
+#.......#*********#..8...$...$...#
+###########################+######
+##################...............#
+##################............C..#
+##################################";
+
+/// Each step of the tutorial is completed by doing what its prompt asks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialStep {
+    Move,
+    Shoot,
+    Reload,
+    Harvest,
+    VisitClinic,
+    Done,
+}
+
+impl TutorialStep {
+    fn next(self) -> Self {
+        match self {
+            Self::Move => Self::Shoot,
+            Self::Shoot => Self::Reload,
+            Self::Reload => Self::Harvest,
+            Self::Harvest => Self::VisitClinic,
+            Self::VisitClinic | Self::Done => Self::Done,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tutorial {
+    step: TutorialStep,
+    move_goal: Vec<Coord>,
+    // Messages before this index were logged before the current step was checked
+    message_cursor: usize,
+}
+
+pub struct TutorialLevel {
+    pub world: World,
+    pub player_spawn: Coord,
+    pub tutorial: Tutorial,
+}
+
+pub fn generate<R: Rng>(rng: &mut R) -> TutorialLevel {
+    let rows = MAP.split('\n').collect::<Vec<_>>();
+    let size = Size::new(rows[0].len() as u32, rows.len() as u32);
+    let mut world = World::new(size);
+    world.theme = LevelTheme::Offices;
+    let mut player_spawn = None;
+    let mut move_goal = Vec::new();
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            let coord = Coord::new(x as i32, y as i32);
+            world.spawn_floor(coord);
+            match ch {
+                '.' => (),
+                '*' => move_goal.push(coord),
+                '#' => {
+                    world.spawn_wall(coord);
+                }
+                '+' => {
+                    world.spawn_door(coord);
+                }
+                '@' => player_spawn = Some(coord),
+                'z' => {
+                    world.spawn_zombie(coord, rng);
+                }
+                '8' => {
+                    world.spawn_item(coord, Item::OrganContainer(None));
+                }
+                '$' => {
+                    world.spawn_money(coord, rng);
+                }
+                'C' => {
+                    world.spawn_organ_clinic(coord, 0, rng);
+                }
+                _ => log::warn!("unexpected char in tutorial map: {}", ch),
+            }
+        }
+    }
+    TutorialLevel {
+        world,
+        player_spawn: player_spawn.expect("tutorial map has no player spawn"),
+        tutorial: Tutorial {
+            step: TutorialStep::Move,
+            move_goal,
+            message_cursor: 0,
+        },
+    }
+}
+
+impl Game {
+    pub fn tutorial_step(&self) -> Option<TutorialStep> {
+        self.tutorial.as_ref().map(|tutorial| tutorial.step)
+    }
+
+    pub(crate) fn start_tutorial(&mut self) {
+        if let Some(tutorial) = self.tutorial.as_mut() {
+            self.message_log.push(Message::Tutorial(tutorial.step));
+            tutorial.message_cursor = self.message_log.len();
+        }
+    }
+
+    // Called at the end of each turn to check whether the player has done what the current step
+    // asked of them, in which case the next step's prompt is logged
+    pub(crate) fn handle_tutorial(&mut self) {
+        let player_coord = self.player_coord();
+        let tutorial = match self.tutorial.as_mut() {
+            Some(tutorial) => tutorial,
+            None => return,
+        };
+        let mut messages = self.message_log[tutorial.message_cursor..].iter();
+        let complete = match tutorial.step {
+            TutorialStep::Move => tutorial.move_goal.contains(&player_coord),
+            TutorialStep::Shoot => messages.any(|m| matches!(m, Message::FireGun(_))),
+            TutorialStep::Reload => messages.any(|m| matches!(m, Message::ReloadGun(_))),
+            TutorialStep::Harvest => messages.any(|m| matches!(m, Message::HarvestOrgan(_))),
+            TutorialStep::VisitClinic => messages.any(|m| matches!(m, Message::InstallOrgan(_))),
+            TutorialStep::Done => false,
+        };
+        if complete {
+            tutorial.step = tutorial.step.next();
+            self.message_log.push(Message::Tutorial(tutorial.step));
+        }
+        tutorial.message_cursor = self.message_log.len();
+    }
+}
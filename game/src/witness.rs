@@ -1,5 +1,6 @@
 use crate::{
-    ActionError, Config, ExternalEvent, GameControlFlow, GameOverReason, Input, Menu as GameMenu,
+    headless::Step, ActionError, Config, ExternalEvent, GameControlFlow, GameOverReason, Input,
+    Menu as GameMenu,
 };
 use coord_2d::Coord;
 use direction::CardinalDirection;
@@ -9,6 +10,9 @@ use std::time::Duration;
 
 pub struct Game {
     inner_game: crate::Game,
+    // Closing a menu doesn't involve the game, so this is used to record that a menu which the
+    // game opened was closed without a choice once the next input is made
+    menu_open: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,6 +32,7 @@ impl RunningGame {
         (
             Game {
                 inner_game: self.game,
+                menu_open: false,
             },
             Running(Private),
         )
@@ -58,6 +63,18 @@ pub struct FireEquipped(Private);
 #[derive(Debug)]
 pub struct FireBody(Private);
 
+#[derive(Debug)]
+pub struct Throw {
+    private: Private,
+    pub inventory_index: usize,
+}
+
+#[derive(Debug)]
+pub struct Read {
+    private: Private,
+    pub document: crate::Document,
+}
+
 #[derive(Debug)]
 pub enum Witness {
     Running(Running),
@@ -66,6 +83,8 @@ pub enum Witness {
     Menu(Menu),
     FireEquipped(FireEquipped),
     FireBody(FireBody),
+    Throw(Throw),
+    Read(Read),
 }
 
 impl Witness {
@@ -92,11 +111,21 @@ pub enum ControlInput {
 
 pub fn new_game<R: Rng>(
     config: &Config,
+    background: crate::Background,
     victories: Vec<crate::Victory>,
     base_rng: &mut R,
 ) -> (Game, Running) {
     let g = Game {
-        inner_game: crate::Game::new(config, victories, base_rng),
+        inner_game: crate::Game::new(config, background, victories, base_rng),
+        menu_open: false,
+    };
+    (g, Running(Private))
+}
+
+pub fn new_tutorial<R: Rng>(config: &Config, base_rng: &mut R) -> (Game, Running) {
+    let g = Game {
+        inner_game: crate::Game::new_tutorial(config, base_rng),
+        menu_open: false,
     };
     (g, Running(Private))
 }
@@ -155,6 +184,25 @@ impl Running {
         game.witness_handle_input(Input::Get, private)
     }
 
+    pub fn toggle_drone_control(self, game: &mut Game) -> (Witness, Result<(), ActionError>) {
+        let Self(private) = self;
+        game.witness_handle_input(Input::ToggleDroneControl, private)
+    }
+
+    pub fn toggle_sprint(self, game: &mut Game) -> (Witness, Result<(), ActionError>) {
+        let Self(private) = self;
+        game.witness_handle_input(Input::ToggleSprint, private)
+    }
+
+    pub fn overwatch(
+        self,
+        game: &mut Game,
+        direction: CardinalDirection,
+    ) -> (Witness, Result<(), ActionError>) {
+        let Self(private) = self;
+        game.witness_handle_input(Input::Overwatch(direction), private)
+    }
+
     pub fn fire_equipped(self) -> Witness {
         Witness::FireEquipped(FireEquipped(self.0))
     }
@@ -163,6 +211,13 @@ impl Running {
         Witness::FireBody(FireBody(self.0))
     }
 
+    pub fn throw(self, inventory_index: usize) -> Witness {
+        Witness::Throw(Throw {
+            private: self.0,
+            inventory_index,
+        })
+    }
+
     pub fn menu(self, menu: GameMenu) -> Witness {
         Witness::Menu(Menu {
             private: self.0,
@@ -177,11 +232,13 @@ impl Game {
         input: Input,
         private: Private,
     ) -> (Witness, Result<(), ActionError>) {
+        self.record_step(Step::Input(input));
         match self.inner_game.handle_input(input) {
             Err(e) => (Witness::running(private), Err(e)),
             Ok(None) => (Witness::running(private), Ok(())),
             Ok(Some(GameControlFlow::GameOver(reason))) => (Witness::GameOver(reason), Ok(())),
             Ok(Some(GameControlFlow::Menu(menu))) => {
+                self.menu_open = true;
                 (Witness::Menu(Menu { private, menu }), Ok(()))
             }
             Ok(Some(GameControlFlow::Win(win))) => (
@@ -191,6 +248,16 @@ impl Game {
                 }),
                 Ok(()),
             ),
+            Ok(Some(GameControlFlow::Throw(inventory_index))) => (
+                Witness::Throw(Throw {
+                    private,
+                    inventory_index,
+                }),
+                Ok(()),
+            ),
+            Ok(Some(GameControlFlow::Read(document))) => {
+                (Witness::Read(Read { private, document }), Ok(()))
+            }
         }
     }
 
@@ -203,7 +270,15 @@ impl Game {
             None => Witness::running(private),
             Some(GameControlFlow::GameOver(reason)) => Witness::GameOver(reason),
             Some(GameControlFlow::Win(win)) => Witness::Win(Win { private, win }),
-            Some(GameControlFlow::Menu(menu)) => Witness::Menu(Menu { private, menu }),
+            Some(GameControlFlow::Menu(menu)) => {
+                self.menu_open = true;
+                Witness::Menu(Menu { private, menu })
+            }
+            Some(GameControlFlow::Throw(inventory_index)) => Witness::Throw(Throw {
+                private,
+                inventory_index,
+            }),
+            Some(GameControlFlow::Read(document)) => Witness::Read(Read { private, document }),
         }
     }
 
@@ -218,10 +293,19 @@ impl Game {
     }
 
     fn witness_handle_choice(&mut self, choice: crate::MenuChoice, private: Private) -> Witness {
+        self.record_step(Step::Choice(choice));
         let control_flow = self.inner_game.handle_choice(choice);
         self.handle_control_flow(control_flow, private)
     }
 
+    fn record_step(&mut self, step: Step) {
+        if self.menu_open && !matches!(step, Step::Choice(_)) {
+            self.inner_game.recorded_steps.push(Step::CancelMenu);
+        }
+        self.menu_open = false;
+        self.inner_game.recorded_steps.push(step);
+    }
+
     pub fn inner_ref(&self) -> &crate::Game {
         &self.inner_game
     }
@@ -230,8 +314,16 @@ impl Game {
         RunningGame::new(self, running)
     }
 
-    pub fn take_external_events(&mut self) -> Vec<ExternalEvent> {
-        self.inner_game.take_external_events()
+    pub fn inherit_remnant(&mut self, remnant: crate::Remnant) {
+        self.inner_game.inherit_remnant(remnant);
+    }
+
+    pub fn set_script_hooks(&mut self, script_hooks: Box<dyn crate::ScriptHooks>) {
+        self.inner_game.set_script_hooks(script_hooks);
+    }
+
+    pub fn drain_external_events(&mut self) -> std::vec::Drain<'_, ExternalEvent> {
+        self.inner_game.drain_external_events()
     }
 }
 
@@ -254,3 +346,24 @@ impl FireBody {
         game.witness_handle_input(Input::FireBody(coord), self.0)
     }
 }
+
+impl Throw {
+    pub fn cancel(self) -> Witness {
+        Witness::Running(Running(self.private))
+    }
+
+    pub fn commit(self, game: &mut Game, coord: Coord) -> (Witness, Result<(), ActionError>) {
+        let input = Input::Throw {
+            inventory_index: self.inventory_index,
+            target: coord,
+        };
+        game.witness_handle_input(input, self.private)
+    }
+}
+
+impl Read {
+    /// Reading doesn't involve the game, so closing the document is never recorded
+    pub fn close(self) -> Witness {
+        Witness::Running(Running(self.private))
+    }
+}
@@ -1,27 +1,101 @@
 use crate::{
+    realtime::movement::FlightHeight,
     world::{
         data::*,
         explosion,
         spatial::{Layer, Layers, Location},
     },
-    ExternalEvent, Message, World,
+    Background, ExternalEvent, Message, World,
 };
 use coord_2d::Coord;
-use direction::Direction;
+use direction::{CardinalDirection, Direction};
 use entity_table::Entity;
-use rand::{seq::SliceRandom, Rng};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+use rgb_int::Rgb24;
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+const ALARM_DURATION: u32 = 30;
+const ALARM_WAVE_INTERVAL: u32 = 10;
+/// The number of civilians the player can kill on a level before its vendors turn on them
+const CIVILIAN_DEATHS_BEFORE_VENDORS_HOSTILE: u32 = 2;
+/// Chance each turn that each harvested organ is rejected while the player isn't immunosuppressed
+const ORGAN_REJECTION_PROBABILITY: f64 = 0.01;
+const ORGAN_REJECTION_DAMAGE: u32 = 2;
+const IMMUNOSUPPRESSANT_WARNING_TURNS: u32 = 20;
+/// Chance that a hit from a claw leaves the character bleeding
+const CLAW_BLEEDING_PROBABILITY: f64 = 0.3;
+/// Chance each turn that a bleeding character loses a hit point
+const BLEEDING_DAMAGE_PROBABILITY: f64 = 0.25;
+/// Chance each turn that an npc's bleeding stops on its own. The player needs a bandage.
+const NPC_BLEEDING_STOP_PROBABILITY: f64 = 0.05;
+/// How long the blood a bleeding player leaves behind can be followed by hostile npcs
+const BLOOD_TRAIL_TURNS: u32 = 50;
+/// How long the scent of a bleeding player, or of the fresh organs they carry, lingers for
+/// predators to track
+const SCENT_TURNS: u32 = 30;
+/// Hits at least this strong can land on one of the player's organs instead of their health
+const ORGAN_HIT_MIN_DAMAGE: u32 = 4;
+const ORGAN_HIT_PROBABILITY: f64 = 0.25;
+/// Having this many cybernetic organs installed at once starts to erode the player's humanity
+pub const CYBERPSYCHOSIS_THRESHOLD: usize = 3;
+const PSYCHOSIS_MAX: u32 = 100;
+/// Chance each turn that psychosis worsens, for each cybernetic organ at or past the threshold
+const PSYCHOSIS_INCREASE_PROBABILITY_PER_ORGAN: f64 = 0.1;
+/// Chance each turn that psychosis eases once the player is back under the threshold
+const PSYCHOSIS_RECOVERY_PROBABILITY: f64 = 0.1;
+/// How long an EMP keeps the player's cybernetic organs offline
+const EMP_DISABLE_TURNS: u32 = 20;
+const EMP_POWER_DRAIN: u32 = 50;
+const EMP_ROBOTIC_DAMAGE: RangeInclusive<u32> = 4..=8;
+/// Chance that a hit from an npc with an EMP discharger knocks out the player's cybernetics
+const EMP_ON_HIT_PROBABILITY: f64 = 0.3;
+const SMOKE_CLOUD_TURNS: u32 = 12;
+/// How long a puff of poison gas lasts, and so roughly how far it drifts
+const POISON_GAS_TURNS: u32 = 6;
+const POISON_GAS_DRIFT_PROBABILITY: f64 = 0.5;
+const POISON_GAS_POISON: u32 = 2;
+const POISON_GAS_OXYGEN_DRAIN: u32 = 1;
+const POISON_GAS_NPC_DAMAGE_PROBABILITY: f64 = 0.25;
+/// How many turns an npc must spend next to the corruption's tentacles before it's corrupted
+const CORRUPTION_TURNS_TO_CONVERT: u32 = 40;
+const CORRUPTION_DAMAGE_PROBABILITY: f64 = 0.05;
+/// Chance each turn that each tentacle grows into a neighbouring cell while the player is away
+const CORRUPTION_GROWTH_PROBABILITY: f64 = 0.001;
+const CORRUPTION_GROWTH_MIN_NEIGHBOURS: usize = 3;
+const CORRUPTED_NPC_COLOUR: Rgb24 = Rgb24::new(0, 255, 255);
+/// Projectiles only ricochet off walls they hit at an angle whose tangent is at most this, which
+/// is about 27 degrees
+const RICOCHET_MAX_SLOPE: f64 = 0.5;
+/// How far a projectile travels after ricocheting
+const RICOCHET_RANGE: usize = 10;
 
 impl World {
     pub fn projectile_move<R: Rng>(
         &mut self,
         projectile_entity: Entity,
         movement_direction: Direction,
+        flight_height: FlightHeight,
         external_events: &mut Vec<ExternalEvent>,
         message_log: &mut Vec<Message>,
         rng: &mut R,
     ) {
         if let Some(current_coord) = self.spatial_table.coord_of(projectile_entity) {
             let next_coord = current_coord + movement_direction.coord();
+            if flight_height == FlightHeight::Arcing {
+                if self.spatial_table.layers_at(next_coord).is_none()
+                    || self.arcing_obstruction(next_coord).is_some()
+                {
+                    self.projectile_stop(projectile_entity, external_events, message_log, rng);
+                } else {
+                    let _ignore_err = self
+                        .spatial_table
+                        .update_coord(projectile_entity, next_coord);
+                }
+                return;
+            }
             let collides_with = self
                 .components
                 .collides_with
@@ -50,6 +124,37 @@ impl World {
                         );
                     }
                 }
+                if let Some(feature_entity) = spatial_cell.feature {
+                    // bullets and rockets smash through glass
+                    if self.components.window.contains(feature_entity)
+                        && self
+                            .components
+                            .projectile_damage
+                            .contains(projectile_entity)
+                    {
+                        self.shatter_window(feature_entity, external_events, message_log);
+                        let _ignore_err = self
+                            .spatial_table
+                            .update_coord(projectile_entity, next_coord);
+                        return;
+                    }
+                    if self.components.explosive.contains(feature_entity)
+                        && self
+                            .components
+                            .projectile_damage
+                            .contains(projectile_entity)
+                    {
+                        explosion::detonate(
+                            self,
+                            feature_entity,
+                            external_events,
+                            message_log,
+                            rng,
+                        );
+                        self.projectile_stop(projectile_entity, external_events, message_log, rng);
+                        return;
+                    }
+                }
                 if let Some(entity_in_cell) = spatial_cell.feature.or(spatial_cell.character) {
                     let is_particle = self.components.particle.contains(projectile_entity);
                     let solid_collision = if collides_with.solid && is_particle {
@@ -61,6 +166,20 @@ impl World {
                         || (collides_with.character
                             && self.components.character.contains(entity_in_cell))
                     {
+                        if self.components.character.contains(entity_in_cell) {
+                            if self.penetrate(projectile_entity) {
+                                let _ignore_err = self
+                                    .spatial_table
+                                    .update_coord(projectile_entity, next_coord);
+                                return;
+                            }
+                        } else if self.ricochet(
+                            projectile_entity,
+                            current_coord,
+                            movement_direction,
+                        ) {
+                            return;
+                        }
                         self.projectile_stop(projectile_entity, external_events, message_log, rng);
                         return;
                     }
@@ -79,6 +198,110 @@ impl World {
         }
     }
 
+    // Let a projectile pass through a character it has hit if it can penetrate any more,
+    // returning whether it did
+    fn penetrate(&mut self, projectile_entity: Entity) -> bool {
+        match self.components.projectile_damage.get_mut(projectile_entity) {
+            Some(projectile_damage) if projectile_damage.penetration > 0 => {
+                projectile_damage.penetration -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Glance a projectile off the wall it's about to hit if it can ricochet and is travelling
+    // at a shallow enough angle to the wall, returning whether it did
+    fn ricochet(
+        &mut self,
+        projectile_entity: Entity,
+        current_coord: Coord,
+        movement_direction: Direction,
+    ) -> bool {
+        if !self
+            .components
+            .projectile_damage
+            .get(projectile_entity)
+            .is_some_and(|projectile_damage| projectile_damage.ricochet)
+        {
+            return false;
+        }
+        let movement = match self.realtime_components.movement.get(projectile_entity) {
+            Some(movement) => movement,
+            None => return false,
+        };
+        let delta = movement.delta();
+        let cardinal_step_duration = movement.cardinal_step_duration();
+        let is_wall = |offset: Coord| {
+            self.spatial_table
+                .layers_at(current_coord + offset)
+                .and_then(|layers| layers.feature)
+                .is_some_and(|feature| self.components.solid_for_particles.contains(feature))
+        };
+        // Which axis the wall faces along. A diagonal step could be into either side of the
+        // wall, so check which of the cells it passes between is part of the wall.
+        let step = movement_direction.coord();
+        let faces_x = if step.x != 0 && step.y != 0 {
+            match (
+                is_wall(Coord::new(step.x, 0)),
+                is_wall(Coord::new(0, step.y)),
+            ) {
+                (true, false) => true,
+                (false, true) => false,
+                // corners don't give a clean bounce
+                _ => return false,
+            }
+        } else {
+            step.x != 0
+        };
+        let (towards_wall, along_wall) = if faces_x {
+            (delta.x, delta.y)
+        } else {
+            (delta.y, delta.x)
+        };
+        if towards_wall.abs() as f64 > along_wall.abs() as f64 * RICOCHET_MAX_SLOPE {
+            return false;
+        }
+        let reflected = if faces_x {
+            Coord::new(-delta.x, delta.y)
+        } else {
+            Coord::new(delta.x, -delta.y)
+        };
+        if let Some(projectile_damage) =
+            self.components.projectile_damage.get_mut(projectile_entity)
+        {
+            projectile_damage.ricochet = false;
+        }
+        self.realtime_components.movement.insert(
+            projectile_entity,
+            {
+                use crate::realtime::movement::spec::*;
+                Movement {
+                    path: reflected,
+                    cardinal_step_duration,
+                    repeat: Repeat::Steps(RICOCHET_RANGE),
+                    flight_height: FlightHeight::Level,
+                }
+            }
+            .build(),
+        );
+        true
+    }
+
+    fn shatter_window(
+        &mut self,
+        window_entity: Entity,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        if let Some(coord) = self.spatial_table.coord_of(window_entity) {
+            self.remove_entity(window_entity);
+            self.spawn_debris(coord);
+            external_events.push(ExternalEvent::ShatterWindow(coord));
+            message_log.push(Message::WindowShatters);
+        }
+    }
+
     fn apply_projectile_damage<R: Rng>(
         &mut self,
         _projectile_entity: Entity,
@@ -91,11 +314,89 @@ impl World {
     ) {
         self.damage_character(
             entity_to_damage,
-            rng.gen_range(projectile_damage.hit_points),
+            DamageRoll::roll(projectile_damage.hit_points, rng),
             rng,
             external_events,
             message_log,
         );
+        if rng.gen_bool(projectile_damage.bleeding_probability) {
+            self.start_bleeding(entity_to_damage, message_log);
+        }
+    }
+
+    pub fn start_bleeding(&mut self, character: Entity, message_log: &mut Vec<Message>) {
+        if self.components.bleeding.contains(character)
+            || self.components.robotic.contains(character)
+            || self.components.to_remove.contains(character)
+            || self
+                .components
+                .health
+                .get(character)
+                .is_none_or(|health| health.is_empty())
+        {
+            return;
+        }
+        self.components.bleeding.insert(character, ());
+        if self.components.player.contains(character) {
+            message_log.push(Message::StartBleeding);
+        } else if let Some(&npc_type) = self.components.npc_type.get(character) {
+            message_log.push(Message::NpcStartBleeding(npc_type));
+        }
+    }
+
+    /// Bleeding characters lose health and leave blood on the floor. The player's blood leaves
+    /// a trail which hostile npcs can follow.
+    pub fn handle_bleeding<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        let mut to_remove = Vec::new();
+        for (entity, blood_trail) in self.components.blood_trail.iter_mut() {
+            *blood_trail -= 1;
+            if *blood_trail == 0 {
+                to_remove.push(entity);
+            }
+        }
+        for entity in to_remove {
+            self.components.blood_trail.remove(entity);
+        }
+        for entity in self.components.bleeding.entities().collect::<Vec<_>>() {
+            let is_player = self.components.player.contains(entity);
+            if self.components.corpse.contains(entity)
+                || (!is_player && rng.gen_bool(NPC_BLEEDING_STOP_PROBABILITY))
+            {
+                self.components.bleeding.remove(entity);
+                continue;
+            }
+            let coord = match self.spatial_table.coord_of(entity) {
+                Some(coord) => coord,
+                None => continue,
+            };
+            self.make_floor_bloody(coord);
+            if is_player {
+                if let Some(Layers {
+                    floor: Some(floor_entity),
+                    ..
+                }) = self.spatial_table.layers_at(coord)
+                {
+                    self.components
+                        .blood_trail
+                        .insert(*floor_entity, BLOOD_TRAIL_TURNS);
+                }
+            }
+            if rng.gen_bool(BLEEDING_DAMAGE_PROBABILITY) {
+                let health = self.components.health.get_mut(entity).unwrap();
+                if health.current() <= 1 {
+                    health.set_current(0);
+                    self.components.bleeding.remove(entity);
+                    self.character_die(entity, rng, external_events, message_log);
+                } else {
+                    health.decrease(1);
+                }
+            }
+        }
     }
 
     pub fn projectile_stop<R: Rng>(
@@ -124,6 +425,20 @@ impl World {
                         self.realtime_components.remove_entity(projectile_entity);
                     }
 
+                    OnCollision::Emp { range } => {
+                        self.emp(current_coord, range, rng, external_events, message_log);
+                        self.spatial_table.remove(projectile_entity);
+                        self.components.remove_entity(projectile_entity);
+                        self.entity_allocator.free(projectile_entity);
+                        self.realtime_components.remove_entity(projectile_entity);
+                    }
+                    OnCollision::Smoke { radius } => {
+                        self.spawn_smoke_cloud(current_coord, radius);
+                        self.spatial_table.remove(projectile_entity);
+                        self.components.remove_entity(projectile_entity);
+                        self.entity_allocator.free(projectile_entity);
+                        self.realtime_components.remove_entity(projectile_entity);
+                    }
                     OnCollision::Remove => {
                         self.spatial_table.remove(projectile_entity);
                         self.components.remove_entity(projectile_entity);
@@ -144,7 +459,7 @@ impl World {
     pub fn damage_character<R: Rng>(
         &mut self,
         character: Entity,
-        hit_points_to_lose: u32,
+        damage: DamageRoll,
         rng: &mut R,
         external_events: &mut Vec<ExternalEvent>,
         message_log: &mut Vec<Message>,
@@ -153,20 +468,16 @@ impl World {
             // prevent cascading damage on explosions
             return;
         }
+        let hit_points_to_lose = damage.total();
         if let Some(&npc_type) = self.components.npc_type.get(character) {
-            message_log.push(Message::NpcHit {
-                npc_type,
-                damage: hit_points_to_lose,
-            });
+            message_log.push(Message::NpcHit { npc_type, damage });
         }
         if self.components.shop.contains(character) {
-            if let Some(npc) = self.components.npc.get_mut(character) {
-                npc.disposition = Disposition::Hostile;
-                if let Some(npc_type) = self.components.npc_type.get_mut(character) {
-                    message_log.push(Message::BecomesHostile(*npc_type));
-                }
-            }
-            self.components.shop.remove(character);
+            self.make_vendor_hostile(character, message_log);
+            self.trigger_alarm(external_events, message_log);
+        }
+        if self.is_player_disguised_from(character) {
+            self.blow_disguise(character, message_log);
         }
         let hit_points = self
             .components
@@ -225,10 +536,104 @@ impl World {
         }
     }
 
+    // Attacking an npc gives the player's disguise away to all of its kind
+    fn blow_disguise(&mut self, npc: Entity, message_log: &mut Vec<Message>) {
+        let npc_type = match self.components.npc_type.get(npc) {
+            Some(&npc_type) => npc_type,
+            None => return,
+        };
+        if let Some(player) = self.components.player.entities().next() {
+            match self.components.blown_disguises.get_mut(player) {
+                Some(blown_disguises) => blown_disguises.push(npc_type),
+                None => {
+                    self.components
+                        .blown_disguises
+                        .insert(player, vec![npc_type]);
+                }
+            }
+            message_log.push(Message::DisguiseBlown(npc_type));
+        }
+    }
+
+    fn make_vendor_hostile(&mut self, vendor: Entity, message_log: &mut Vec<Message>) {
+        if let Some(npc) = self.components.npc.get_mut(vendor) {
+            npc.disposition = Disposition::Hostile;
+            if let Some(npc_type) = self.components.npc_type.get_mut(vendor) {
+                message_log.push(Message::BecomesHostile(*npc_type));
+            }
+        }
+        self.components.shop.remove(vendor);
+    }
+
+    // Vendors raise their prices when a civilian dies, and turn hostile if it keeps happening
+    fn civilian_killed(
+        &mut self,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        self.civilian_deaths += 1;
+        if self.components.shop.is_empty() {
+            return;
+        }
+        if self.civilian_deaths < CIVILIAN_DEATHS_BEFORE_VENDORS_HOSTILE {
+            message_log.push(Message::VendorsRaisePrices);
+            return;
+        }
+        for vendor in self.components.shop.entities().collect::<Vec<_>>() {
+            self.make_vendor_hostile(vendor, message_log);
+        }
+        self.trigger_alarm(external_events, message_log);
+    }
+
+    /// Vendors which see the player carrying something stolen from them turn hostile and raise
+    /// the alarm
+    pub fn handle_theft(
+        &mut self,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        if self.components.stolen_from.is_empty() {
+            return;
+        }
+        let player = match self.components.player.entities().next() {
+            Some(player) => player,
+            None => return,
+        };
+        let player_coord = match self.spatial_table.coord_of(player) {
+            Some(coord) => coord,
+            None => return,
+        };
+        let mut carried = Vec::new();
+        if let Some(inventory) = self.components.inventory.get(player) {
+            carried.extend(inventory.items().iter().flatten().cloned());
+        }
+        if let Some(hands) = self.components.hands.get(player) {
+            carried.extend(hands.left.holding());
+            carried.extend(hands.right.holding());
+        }
+        let mut vendors = carried
+            .into_iter()
+            .filter_map(|item_entity| self.components.stolen_from.get(item_entity).cloned())
+            .filter(|&vendor| self.components.shop.contains(vendor))
+            .collect::<Vec<_>>();
+        vendors.sort();
+        vendors.dedup();
+        for vendor in vendors {
+            if !self.is_seen_by_vendor(vendor, player_coord) {
+                continue;
+            }
+            if let Some(&npc_type) = self.components.npc_type.get(vendor) {
+                message_log.push(Message::TheftNoticed(npc_type));
+            }
+            self.make_vendor_hostile(vendor, message_log);
+            self.trigger_alarm(external_events, message_log);
+        }
+    }
+
     pub fn damage_player<R: Rng>(
         &mut self,
         character: Entity,
-        hit_points_to_lose: u32,
+        damage: DamageRoll,
         rng: &mut R,
         external_events: &mut Vec<ExternalEvent>,
         message_log: &mut Vec<Message>,
@@ -238,12 +643,19 @@ impl World {
             // prevent cascading damage on explosions
             return;
         }
+        let hit_points_to_lose = damage.total();
         if let Some(&npc_type) = self.components.npc_type.get(character) {
             message_log.push(Message::PlayerHit {
                 attacker_npc_type: npc_type,
-                damage: hit_points_to_lose,
+                damage,
             });
         }
+        if hit_points_to_lose >= ORGAN_HIT_MIN_DAMAGE
+            && rng.gen_bool(self.player_organ_hit_probability())
+            && self.damage_random_player_organ(rng, message_log)
+        {
+            return;
+        }
         let hit_points = self
             .components
             .health
@@ -257,6 +669,110 @@ impl World {
         }
     }
 
+    /// A reinforced skeleton protects the player's organs from strong hits
+    fn player_organ_hit_probability(&self) -> f64 {
+        let mut probability = ORGAN_HIT_PROBABILITY;
+        for organ in self.active_player_organs() {
+            if organ.type_ == OrganType::ReinforcedSkeleton {
+                probability *= if organ.traits.damaged {
+                    0.75
+                } else if organ.cybernetic {
+                    0.25
+                } else {
+                    0.5
+                };
+            }
+        }
+        probability
+    }
+
+    /// Damages one of the player's undamaged organs, or destroys a tumour. Returns false if
+    /// there was no organ to hit.
+    fn damage_random_player_organ<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        message_log: &mut Vec<Message>,
+    ) -> bool {
+        let player_entity = self.components.player.entities().next().unwrap();
+        let organs = self.components.organs.get_mut(player_entity).unwrap();
+        let candidates = organs
+            .organs()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| {
+                slot.filter(|organ| organ.type_ == OrganType::Tumour || !organ.traits.damaged)
+                    .map(|_| i)
+            })
+            .collect::<Vec<_>>();
+        let i = match candidates.choose(rng) {
+            Some(&i) => i,
+            None => return false,
+        };
+        let organ = organs.get_mut(i).unwrap();
+        if organ.type_ == OrganType::Tumour {
+            message_log.push(Message::TumourDestroyed);
+            organs.remove(i);
+        } else {
+            message_log.push(Message::OrganHit(*organ));
+            organ.traits.damaged = true;
+        }
+        true
+    }
+
+    /// Reveals the true identity of an item. Returns the revealed item if it was previously
+    /// unidentified.
+    pub fn identify_item(&mut self, entity: Entity) -> Option<Item> {
+        let item = self.components.unidentified.remove(entity)?;
+        self.components.item.insert(entity, item);
+        self.components.tile.insert(entity, Tile::Item(item));
+        Some(item)
+    }
+
+    /// Sets off the alarm if there are any alarm panels on this level, or extends it if it's
+    /// already going off
+    pub fn trigger_alarm(
+        &mut self,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        if self.components.alarm_panel.is_empty() {
+            return;
+        }
+        if self.alarm_turns_remaining == 0 {
+            message_log.push(Message::AlarmTriggered);
+            external_events.push(ExternalEvent::Alarm);
+        }
+        self.alarm_turns_remaining = ALARM_DURATION;
+    }
+
+    pub fn handle_alarm<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        if self.alarm_turns_remaining == 0 {
+            return;
+        }
+        self.alarm_turns_remaining -= 1;
+        if self
+            .alarm_turns_remaining
+            .is_multiple_of(ALARM_WAVE_INTERVAL)
+        {
+            let stairs_coords = [self.stairs_up_or_exit_coord(), self.stairs_down_coord()];
+            for stairs_coord in stairs_coords.iter().flatten() {
+                if let Some(coord) = self.nearest_characterless_coord(*stairs_coord) {
+                    self.spawn_guard(coord, rng);
+                }
+            }
+            message_log.push(Message::Reinforcements);
+            external_events.push(ExternalEvent::Alarm);
+        }
+        if self.alarm_turns_remaining == 0 {
+            message_log.push(Message::AlarmStops);
+        }
+    }
+
     fn character_die<R: Rng>(
         &mut self,
         character: Entity,
@@ -265,7 +781,28 @@ impl World {
         message_log: &mut Vec<Message>,
     ) {
         if let Some(&npc_type) = self.components.npc_type.get(character) {
+            if npc_type != NpcType::Civilian && !self.is_ally(character) {
+                // tougher enemies are worth more experience
+                let points = self
+                    .components
+                    .health
+                    .get(character)
+                    .map(|health| health.max())
+                    .unwrap_or(0);
+                self.gain_player_experience(points, message_log);
+            }
+        }
+        if self.components.turret_ammo.contains(character) {
+            // turrets are destroyed outright rather than leaving a corpse
+            if let Some(&npc_type) = self.components.npc_type.get(character) {
+                message_log.push(Message::NpcDies(npc_type));
+            }
+            self.components.to_remove.insert(character, ());
+        } else if let Some(&npc_type) = self.components.npc_type.get(character) {
             message_log.push(Message::NpcDies(npc_type));
+            if npc_type == NpcType::Civilian {
+                self.civilian_killed(external_events, message_log);
+            }
             self.components.corpse.insert(character, ());
             self.components.character.remove(character);
             self.components
@@ -286,6 +823,11 @@ impl World {
         } else {
             self.components.to_remove.insert(character, ());
         }
+        if let Some(hazard) = self.components.hazard_on_death.remove(character) {
+            if let Some(coord) = self.spatial_table.coord_of(character) {
+                self.spread_floor_hazard(coord, hazard, 1, rng);
+            }
+        }
         if self.components.explodes_on_death.contains(character) {
             if let Some(coord) = self.spatial_table.coord_of(character) {
                 self.components.explodes_on_death.remove(character);
@@ -321,8 +863,36 @@ impl World {
         }
     }
 
+    pub fn gain_player_experience(&mut self, points: u32, message_log: &mut Vec<Message>) {
+        let player_entity = match self.components.player.entities().next() {
+            Some(player_entity) => player_entity,
+            None => return,
+        };
+        if let Some(experience) = self.components.experience.get_mut(player_entity) {
+            if experience.gain(points) > 0 {
+                message_log.push(Message::LevelUp(experience.level));
+            }
+        }
+    }
+
+    pub fn train_player_skill(&mut self, skill: Skill, message_log: &mut Vec<Message>) {
+        let player_entity = match self.components.player.entities().next() {
+            Some(player_entity) => player_entity,
+            None => return,
+        };
+        if let Some(skills) = self.components.skills.get_mut(player_entity) {
+            if let Some(level) = skills.train(skill) {
+                message_log.push(Message::SkillImproved { skill, level });
+            }
+        }
+    }
+
     fn resurrect(&mut self, entity: Entity) {
-        let current_coord = self.spatial_table.coord_of(entity).unwrap();
+        // corpses can be carried off by snatchers, in which case they stay dead
+        let current_coord = match self.spatial_table.coord_of(entity) {
+            Some(coord) => coord,
+            None => return,
+        };
         if let Some(coord) = self.nearest_characterless_coord(current_coord) {
             let _ = self.spatial_table.update(
                 entity,
@@ -365,6 +935,104 @@ impl World {
         }
     }
 
+    pub fn handle_surgery_recovery(&mut self, message_log: &mut Vec<Message>) {
+        let mut recovered = Vec::new();
+        for (entity, surgery_recovery) in self.components.surgery_recovery.iter_mut() {
+            surgery_recovery.decrease(1);
+            if surgery_recovery.is_empty() {
+                recovered.push(entity);
+            }
+        }
+        for entity in recovered {
+            self.components.surgery_recovery.remove(entity);
+            if self.components.player.contains(entity) {
+                message_log.push(Message::RecoverFromSurgery);
+            }
+        }
+    }
+
+    // Whether predators can smell the player, because they're bleeding or carrying an organ that
+    // hasn't been replaced with a cybernetic one
+    fn player_gives_off_scent(&self, player: Entity) -> bool {
+        if self.components.bleeding.contains(player) {
+            return true;
+        }
+        self.components
+            .inventory
+            .get(player)
+            .map(|inventory| {
+                inventory.items().iter().flatten().any(|&item_entity| {
+                    matches!(
+                        self.components.item.get(item_entity),
+                        Some(Item::OrganContainer(Some(Organ {
+                            cybernetic: false,
+                            ..
+                        })))
+                    )
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn handle_scent(&mut self) {
+        let mut to_remove = Vec::new();
+        for (entity, scent) in self.components.scent.iter_mut() {
+            *scent -= 1;
+            if *scent == 0 {
+                to_remove.push(entity);
+            }
+        }
+        for entity in to_remove {
+            self.components.scent.remove(entity);
+        }
+        let player = match self.components.player.entities().next() {
+            Some(player) => player,
+            None => return,
+        };
+        if !self.player_gives_off_scent(player) {
+            return;
+        }
+        if let Some(Layers {
+            floor: Some(floor_entity),
+            ..
+        }) = self
+            .spatial_table
+            .coord_of(player)
+            .and_then(|coord| self.spatial_table.layers_at(coord))
+        {
+            self.components.scent.insert(*floor_entity, SCENT_TURNS);
+        }
+    }
+
+    pub fn handle_spoilage(&mut self, message_log: &mut Vec<Message>) {
+        let mut spoiled = Vec::new();
+        for (entity, freshness) in self.components.freshness.iter_mut() {
+            freshness.decrease(1);
+            if freshness.is_empty() {
+                spoiled.push(entity);
+            }
+        }
+        if spoiled.is_empty() {
+            return;
+        }
+        let mut carried = Vec::new();
+        if let Some(player) = self.components.player.entities().next() {
+            if let Some(inventory) = self.components.inventory.get(player) {
+                carried.extend(inventory.items().iter().flatten().cloned());
+            }
+        }
+        for entity in spoiled {
+            self.components.freshness.remove(entity);
+            self.components.item.insert(entity, Item::RottenMeat);
+            self.components
+                .tile
+                .insert(entity, Tile::Item(Item::RottenMeat));
+            if carried.contains(&entity) {
+                message_log.push(Message::FoodSpoiled);
+            }
+        }
+    }
+
     pub fn handle_get_on_touch(&mut self) {
         for entity in self.components.get_on_touch.entities() {
             if self.components.character.contains(entity) {
@@ -384,29 +1052,174 @@ impl World {
         }
     }
 
-    pub fn handle_spread_poison(&mut self) {
-        for entity in self.components.spread_poison.entities() {
-            if self.components.character.contains(entity) {
-                if let Some(coord) = self.spatial_table.coord_of(entity) {
-                    if let Some(coord) = self.nearest_non_poison_coord(coord) {
-                        if let Some(Layers {
-                            floor: Some(floor), ..
-                        }) = self.spatial_table.layers_at(coord)
-                        {
-                            self.components.tile.insert(*floor, Tile::FloorPoison);
-                            self.components.floor_poison.insert(*floor, ());
-                        }
+    pub fn npc_pick_up(&mut self, entity: Entity) {
+        if let Some(coord) = self.spatial_table.coord_of(entity) {
+            if let Some(Layers {
+                item: Some(item_entity),
+                ..
+            }) = self.spatial_table.layers_at(coord).cloned()
+            {
+                if let Some(simple_inventory) = self.components.simple_inventory.get_mut(entity) {
+                    self.spatial_table.remove(item_entity);
+                    simple_inventory.push(item_entity);
+                }
+            }
+        }
+    }
+
+    // Removes the first item of one of the given kinds from an npc's inventory, returning the
+    // kind of item removed
+    fn npc_consume_item(&mut self, entity: Entity, items: &[Item]) -> Option<Item> {
+        let simple_inventory = self.components.simple_inventory.get_mut(entity)?;
+        let (index, item) = items.iter().find_map(|item| {
+            let index = simple_inventory
+                .iter()
+                .position(|&item_entity| self.components.item.get(item_entity) == Some(item))?;
+            Some((index, *item))
+        })?;
+        let item_entity = simple_inventory.remove(index);
+        self.remove_entity(item_entity);
+        Some(item)
+    }
+
+    pub fn npc_reload(&mut self, entity: Entity) {
+        let gun_entity = match self.npc_gun_entity(entity) {
+            Some(gun_entity) => gun_entity,
+            None => return,
+        };
+        let gun_type = match self.components.gun.get(gun_entity) {
+            Some(gun) => gun.type_,
+            None => return,
+        };
+        if self
+            .npc_consume_item(entity, &[crate::ai::gun_ammo_item(gun_type)])
+            .is_some()
+        {
+            if let Some(gun) = self.components.gun.get_mut(gun_entity) {
+                gun.ammo.fill();
+            }
+        }
+    }
+
+    pub fn npc_apply_stimpack(&mut self, entity: Entity) {
+        if self
+            .npc_consume_item(entity, &[Item::Stimpack, Item::CorruptedStimpack])
+            .is_some()
+        {
+            if let Some(health) = self.components.health.get_mut(entity) {
+                health.increase(5);
+            }
+        }
+    }
+
+    /// Poisoners breathe out poison gas, which then drifts away from them
+    pub fn handle_spread_poison<R: Rng>(&mut self, rng: &mut R) {
+        for puff in self.poison_gas.iter_mut() {
+            puff.turns_remaining = puff.turns_remaining.saturating_sub(1);
+        }
+        self.poison_gas.retain(|puff| puff.turns_remaining > 0);
+        for i in 0..self.poison_gas.len() {
+            if rng.gen_bool(POISON_GAS_DRIFT_PROBABILITY) {
+                let direction = CardinalDirection::all().choose(rng).unwrap();
+                let coord = self.poison_gas[i].coord + direction.coord();
+                if self.gas_can_enter(coord) {
+                    self.poison_gas[i].coord = coord;
+                }
+            }
+        }
+        let emitter_coords = self
+            .components
+            .spread_poison
+            .entities()
+            .filter(|&entity| self.components.character.contains(entity))
+            .filter_map(|entity| self.spatial_table.coord_of(entity))
+            .collect::<Vec<_>>();
+        for coord in emitter_coords {
+            if !self.is_poison_gas_at(coord) {
+                self.poison_gas.push(GasPuff {
+                    coord,
+                    turns_remaining: POISON_GAS_TURNS,
+                });
+            }
+        }
+    }
+
+    fn gas_can_enter(&self, coord: Coord) -> bool {
+        match self.spatial_table.layers_at(coord) {
+            None => false,
+            Some(layers) => !layers
+                .feature
+                .is_some_and(|feature| self.components.solid_for_particles.contains(feature)),
+        }
+    }
+
+    /// Poison gas poisons the player, and hurts npcs which breathe, unless they're the ones
+    /// breathing it out. Gas also displaces air, so the player's oxygen runs down while inside it,
+    /// but cybernetic lungs filter out the poison.
+    pub fn handle_poison_gas<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        let gassed = self
+            .components
+            .character
+            .entities()
+            .filter(|&entity| {
+                self.spatial_table
+                    .coord_of(entity)
+                    .is_some_and(|coord| self.is_poison_gas_at(coord))
+            })
+            .collect::<Vec<_>>();
+        for entity in gassed {
+            if self.wear_protection(entity, WearSlot::Head, message_log) {
+                continue;
+            }
+            if self.components.player.contains(entity) {
+                if let Some(oxygen) = self.components.oxygen.get_mut(entity) {
+                    oxygen.decrease(POISON_GAS_OXYGEN_DRAIN);
+                }
+                if !self.player_has_cybernetic_lung() {
+                    if let Some(poison) = self.components.poison.get_mut(entity) {
+                        poison.increase(POISON_GAS_POISON);
                     }
                 }
+                message_log.push(Message::PoisonGas);
+            } else if !self.components.robotic.contains(entity)
+                && !self.components.spread_poison.contains(entity)
+                && rng.gen_bool(POISON_GAS_NPC_DAMAGE_PROBABILITY)
+            {
+                self.damage_character(
+                    entity,
+                    DamageRoll::fixed(1),
+                    rng,
+                    external_events,
+                    message_log,
+                );
             }
         }
     }
 
-    pub fn add_player_initial_items(&mut self) {
-        let entities = vec![
-            self.spawn_item_no_coord(Item::PistolAmmo),
-            self.spawn_item_no_coord(Item::AntiRads),
-        ];
+    pub fn add_player_initial_items(&mut self, background: Background) {
+        let items: &[Item] = match background {
+            Background::StreetDoc => &[
+                Item::PistolAmmo,
+                Item::Stimpack,
+                Item::Stimpack,
+                Item::Bandage,
+                Item::Anesthetic,
+            ],
+            Background::Mercenary => &[Item::PistolAmmo, Item::PistolAmmo, Item::AntiRads],
+            Background::VampireInitiate => {
+                &[Item::PistolAmmo, Item::BloodVialFull, Item::BloodVialEmpty]
+            }
+            Background::Scavenger => &[Item::PistolAmmo, Item::AntiRads, Item::Food, Item::Plank],
+        };
+        let entities = items
+            .iter()
+            .map(|&item| self.spawn_item_no_coord(item))
+            .collect::<Vec<_>>();
         let player = self.components.player.entities().next().unwrap();
         let inventory = self.components.inventory.get_mut(player).unwrap();
         for entity in entities {
@@ -422,6 +1235,10 @@ impl World {
             ..
         }) = self.spatial_table.layers_at(coord)
         {
+            if self.components.floor_hazard.contains(*floor_entity) {
+                // blood would hide the hazard
+                return;
+            }
             // XXX this changes non-floor tiles (road, footpath, etc) into floor tiles
             self.components
                 .tile
@@ -436,7 +1253,7 @@ impl World {
         external_events: &mut Vec<ExternalEvent>,
         message_log: &mut Vec<Message>,
     ) {
-        let mut damage = rng.gen_range(1..=2);
+        let mut damage = DamageRoll::roll(1..=2, rng);
         for organ in self.active_player_organs() {
             if organ.type_ == OrganType::Claw {
                 let mut mult = 1;
@@ -446,11 +1263,123 @@ impl World {
                 if organ.traits.damaged {
                     mult /= 2;
                 }
-                damage += rng.gen_range((2 * mult)..=(4 * mult));
+                let claw_damage = rng.gen_range((2 * mult)..=(4 * mult));
+                damage.add_modifier(DamageModifier::Claws, claw_damage);
             }
         }
-        external_events.push(ExternalEvent::Melee);
+        if let Some(coord) = self.spatial_table.coord_of(character) {
+            external_events.push(ExternalEvent::Melee(coord));
+        }
         self.damage_character(character, damage, rng, external_events, message_log);
+        if self.num_player_claws() > 0 && rng.gen_bool(CLAW_BLEEDING_PROBABILITY) {
+            self.start_bleeding(character, message_log);
+        }
+    }
+
+    /// Npcs with claws can leave the player bleeding when they hit them
+    pub fn npc_claw_hit<R: Rng>(
+        &mut self,
+        npc: Entity,
+        character: Entity,
+        rng: &mut R,
+        message_log: &mut Vec<Message>,
+    ) {
+        let has_claws = self
+            .components
+            .simple_organs
+            .get(npc)
+            .is_some_and(|organs| organs.iter().any(|organ| organ.type_ == OrganType::Claw));
+        if has_claws && rng.gen_bool(CLAW_BLEEDING_PROBABILITY) {
+            self.start_bleeding(character, message_log);
+        }
+    }
+
+    /// Knocks out the cybernetics of the player and damages robots within `range` of `coord`
+    pub fn emp<R: Rng>(
+        &mut self,
+        coord: Coord,
+        range: u32,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        self.spawn_emp_emitter(coord, rng);
+        for character_entity in self.components.character.entities().collect::<Vec<_>>() {
+            let in_range = self
+                .spatial_table
+                .coord_of(character_entity)
+                .is_some_and(|character_coord| character_coord.distance2(coord) <= range.pow(2));
+            if !in_range {
+                continue;
+            }
+            if self.components.player.contains(character_entity) {
+                self.emp_player(message_log);
+            }
+            if self.components.robotic.contains(character_entity) {
+                self.damage_character(
+                    character_entity,
+                    DamageRoll::roll(EMP_ROBOTIC_DAMAGE, rng),
+                    rng,
+                    external_events,
+                    message_log,
+                );
+            }
+        }
+    }
+
+    fn emp_player(&mut self, message_log: &mut Vec<Message>) {
+        let player_entity = self.components.player.entities().next().unwrap();
+        let organs = self.components.organs.get_mut(player_entity).unwrap();
+        let mut any_disabled = false;
+        for i in 0..organs.organs().len() {
+            if let Some(organ) = organs.get_mut(i) {
+                if organ.cybernetic {
+                    organ.disabled = true;
+                    any_disabled = true;
+                }
+            }
+        }
+        if any_disabled {
+            self.components
+                .emp_disabled
+                .insert(player_entity, Meter::new_full(EMP_DISABLE_TURNS));
+        }
+        if let Some(power) = self.components.power.get_mut(player_entity) {
+            power.decrease(EMP_POWER_DRAIN);
+        }
+        message_log.push(Message::EmpHitPlayer);
+    }
+
+    pub fn npc_emp_hit<R: Rng>(
+        &mut self,
+        npc: Entity,
+        character: Entity,
+        rng: &mut R,
+        message_log: &mut Vec<Message>,
+    ) {
+        if self.components.emp_on_hit.contains(npc)
+            && self.components.player.contains(character)
+            && rng.gen_bool(EMP_ON_HIT_PROBABILITY)
+        {
+            self.emp_player(message_log);
+        }
+    }
+
+    pub fn handle_emp_recovery(&mut self, message_log: &mut Vec<Message>) {
+        let player_entity = self.components.player.entities().next().unwrap();
+        if let Some(emp_disabled) = self.components.emp_disabled.get_mut(player_entity) {
+            emp_disabled.decrease(1);
+            if emp_disabled.is_empty() {
+                self.components.emp_disabled.remove(player_entity);
+                let organs = self.components.organs.get_mut(player_entity).unwrap();
+                for i in 0..organs.organs().len() {
+                    if let Some(organ) = organs.get_mut(i) {
+                        organ.disabled = false;
+                    }
+                }
+                message_log.push(Message::CyberneticsRestored);
+            }
+        }
     }
 
     pub fn handle_player_organ_traits<R: Rng>(
@@ -500,6 +1429,75 @@ impl World {
         }
     }
 
+    pub fn handle_organ_rejection<R: Rng>(&mut self, rng: &mut R, message_log: &mut Vec<Message>) {
+        let player_entity = self.components.player.entities().next().unwrap();
+        if let Some(immunosuppressed) = self.components.immunosuppressed.get_mut(player_entity) {
+            immunosuppressed.decrease(1);
+            if immunosuppressed.is_empty() {
+                self.components.immunosuppressed.remove(player_entity);
+                message_log.push(Message::ImmunosuppressantWoreOff);
+            } else if immunosuppressed.current() == IMMUNOSUPPRESSANT_WARNING_TURNS {
+                message_log.push(Message::ImmunosuppressantWearingOff);
+            }
+            return;
+        }
+        let organs = self.components.organs.get_mut(player_entity).unwrap();
+        let mut damage = 0;
+        for i in 0..organs.organs().len() {
+            if let Some(organ) = organs.get_mut(i) {
+                if organ.harvested && rng.gen_bool(ORGAN_REJECTION_PROBABILITY) {
+                    organ.traits.damaged = true;
+                    damage += ORGAN_REJECTION_DAMAGE;
+                    message_log.push(Message::OrganRejection(*organ));
+                }
+            }
+        }
+        self.components
+            .health
+            .get_mut(player_entity)
+            .unwrap()
+            .decrease(damage);
+    }
+
+    pub fn handle_cyberpsychosis<R: Rng>(&mut self, rng: &mut R, message_log: &mut Vec<Message>) {
+        let player_entity = self.components.player.entities().next().unwrap();
+        let num_cybernetic = self
+            .components
+            .organs
+            .get(player_entity)
+            .unwrap()
+            .organs()
+            .iter()
+            .flatten()
+            .filter(|organ| organ.cybernetic)
+            .count();
+        if num_cybernetic >= CYBERPSYCHOSIS_THRESHOLD {
+            if !self.components.psychosis.contains(player_entity) {
+                self.components
+                    .psychosis
+                    .insert(player_entity, Meter::new(0, PSYCHOSIS_MAX));
+                message_log.push(Message::CyberpsychosisOnset);
+            }
+            let num_excess = (num_cybernetic + 1 - CYBERPSYCHOSIS_THRESHOLD) as f64;
+            let probability = (num_excess * PSYCHOSIS_INCREASE_PROBABILITY_PER_ORGAN).min(1.0);
+            if rng.gen_bool(probability) {
+                self.components
+                    .psychosis
+                    .get_mut(player_entity)
+                    .unwrap()
+                    .increase(1);
+            }
+        } else if let Some(psychosis) = self.components.psychosis.get_mut(player_entity) {
+            if rng.gen_bool(PSYCHOSIS_RECOVERY_PROBABILITY) {
+                psychosis.decrease(1);
+            }
+            if psychosis.is_empty() {
+                self.components.psychosis.remove(player_entity);
+                message_log.push(Message::CyberpsychosisSubsides);
+            }
+        }
+    }
+
     pub fn handle_full_poison<R: Rng>(&mut self, rng: &mut R, message_log: &mut Vec<Message>) {
         let player_entity = self.components.player.entities().next().unwrap();
         let poison = self.components.poison.get_mut(player_entity).unwrap();
@@ -543,6 +1541,9 @@ impl World {
                     *organs.first_free_slot().unwrap() = Some(Organ {
                         type_: OrganType::Tumour,
                         original: false,
+                        harvested: false,
+                        disabled: false,
+                        donor: None,
                         cybernetic: false,
                         traits: OrganTraits {
                             prolific: true,
@@ -571,7 +1572,12 @@ impl World {
         }
     }
 
-    pub fn handle_player_organs<R: Rng>(&mut self, rng: &mut R, message_log: &mut Vec<Message>) {
+    pub fn handle_player_organs<R: Rng>(
+        &mut self,
+        health_percent: u32,
+        rng: &mut R,
+        message_log: &mut Vec<Message>,
+    ) {
         let player_entity = self.components.player.entities().next().unwrap();
         self.components
             .satiation
@@ -658,6 +1664,11 @@ impl World {
                 _ => (),
             }
         }
+        max_health = (max_health * health_percent) / 100;
+        // recovering from surgery leaves the player weaker for a while
+        if self.components.surgery_recovery.contains(player_entity) {
+            max_health -= max_health / 4;
+        }
         self.components
             .health
             .get_mut(player_entity)
@@ -778,6 +1789,82 @@ impl World {
         }
     }
 
+    pub fn handle_floor_hazards<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) {
+        let hazards = self
+            .components
+            .character
+            .entities()
+            // hurting a shopkeeper sets off the alarm, which the player shouldn't be blamed for
+            .filter(|&entity| !self.components.shop.contains(entity))
+            .filter_map(|entity| {
+                let coord = self.spatial_table.coord_of(entity)?;
+                let floor = self.spatial_table.layers_at(coord)?.floor?;
+                let &hazard = self.components.floor_hazard.get(floor)?;
+                Some((entity, hazard))
+            })
+            .collect::<Vec<_>>();
+        for (entity, hazard) in hazards {
+            if self.components.player.contains(entity) {
+                message_log.push(match hazard {
+                    FloorHazard::Acid => Message::AcidBurn,
+                    FloorHazard::Electrified => Message::Electrocuted,
+                });
+            }
+            if self.components.health.contains(entity) {
+                self.damage_character(
+                    entity,
+                    DamageRoll::fixed(hazard.damage()),
+                    rng,
+                    external_events,
+                    message_log,
+                );
+            }
+        }
+    }
+
+    /// Turns the floor at a coordinate into a hazard. Coordinates containing a feature such as a
+    /// wall or door are left alone.
+    pub fn make_floor_hazard(&mut self, coord: Coord, hazard: FloorHazard) {
+        if let Some(Layers {
+            floor: Some(floor_entity),
+            feature: None,
+            ..
+        }) = self.spatial_table.layers_at(coord)
+        {
+            self.components.tile.insert(*floor_entity, hazard.tile());
+            self.components.floor_hazard.insert(*floor_entity, hazard);
+            self.components.floor_poison.remove(*floor_entity);
+        }
+    }
+
+    /// Makes a roughly-circular pool of hazardous floor
+    pub fn spread_floor_hazard<R: Rng>(
+        &mut self,
+        centre: Coord,
+        hazard: FloorHazard,
+        radius: u32,
+        rng: &mut R,
+    ) {
+        let radius_i32 = radius as i32;
+        for y in -radius_i32..=radius_i32 {
+            for x in -radius_i32..=radius_i32 {
+                let offset = Coord::new(x, y);
+                let distance = offset.manhattan_magnitude();
+                if distance > radius {
+                    continue;
+                }
+                if distance == 0 || rng.gen_ratio(2, 3) {
+                    self.make_floor_hazard(centre + offset, hazard);
+                }
+            }
+        }
+    }
+
     pub fn handle_asphyxiation(&mut self, message_log: &mut Vec<Message>) {
         for (entity, oxygen) in self.components.oxygen.iter() {
             if oxygen.current() == 0 {
@@ -789,45 +1876,217 @@ impl World {
         }
     }
 
-    pub fn handle_smoke(&mut self, message_log: &mut Vec<Message>) {
+    /// The player's temperature drifts towards that of their surroundings. Nearby fires heat them
+    /// up, while swimming and standing out in the open streets chill them. Cybernetic organs run
+    /// hot, a spare heart keeps the blood moving in the cold, and a hazard suit insulates against
+    /// the cold. At either extreme the player takes damage every turn.
+    pub fn handle_temperature(&mut self, message_log: &mut Vec<Message>) {
+        let player_entity = match self.components.player.entities().next() {
+            Some(player_entity) => player_entity,
+            None => return,
+        };
+        let coord = match self.spatial_table.coord_of(player_entity) {
+            Some(coord) => coord,
+            None => return,
+        };
+        let num_fires = self
+            .components
+            .smoke
+            .entities()
+            .filter(|&entity| self.components.tile.get(entity) == Some(&Tile::DebrisBurning))
+            .filter_map(|entity| self.spatial_table.coord_of(entity))
+            .filter_map(|fire_coord| self.line_distance_stopping_at_solid(coord, fire_coord))
+            .filter(|&distance| distance < 4)
+            .count() as u32;
+        let outside = matches!(
+            self.spatial_table
+                .layers_at(coord)
+                .and_then(|layers| layers.floor)
+                .and_then(|floor| self.components.tile.get(floor)),
+            Some(Tile::Street | Tile::Footpath | Tile::Alley | Tile::Bridge)
+        );
+        let swimming = self.is_water_at(coord);
+        let (num_cybernetic, num_hearts) = match self.components.organs.get(player_entity) {
+            Some(organs) => {
+                let working = organs
+                    .organs()
+                    .iter()
+                    .flatten()
+                    .filter(|organ| !organ.disabled)
+                    .collect::<Vec<_>>();
+                (
+                    working.iter().filter(|organ| organ.cybernetic).count(),
+                    working
+                        .iter()
+                        .filter(|organ| organ.type_ == OrganType::Heart)
+                        .count(),
+                )
+            }
+            None => (0, 0),
+        };
+        let insulated = self
+            .components
+            .worn
+            .get(player_entity)
+            .and_then(|worn| worn.slot(WearSlot::Body))
+            .and_then(|item_entity| self.components.item.get(item_entity))
+            == Some(&Item::HazardSuit);
+        let temperature = match self.components.temperature.get_mut(player_entity) {
+            Some(temperature) => temperature,
+            None => return,
+        };
+        let neutral = temperature.max() / 2;
+        let cold_resistance = insulated as u32 + (num_hearts > 1) as u32;
+        if num_fires > 0 {
+            temperature.increase(num_fires + (num_cybernetic > 0) as u32);
+        } else if swimming {
+            temperature.decrease(2u32.saturating_sub(cold_resistance));
+        } else {
+            // the open air only chills the player so far
+            let target = if outside && cold_resistance == 0 {
+                neutral / 2
+            } else {
+                neutral
+            };
+            if temperature.current() < target {
+                temperature.increase(1);
+            } else if temperature.current() > target {
+                temperature.decrease(1);
+            }
+        }
+        let message = if temperature.is_empty() {
+            Message::Freezing
+        } else if temperature.is_full() {
+            Message::Overheating
+        } else {
+            return;
+        };
+        if let Some(health) = self.components.health.get_mut(player_entity) {
+            health.decrease(1);
+        }
+        message_log.push(message);
+    }
+
+    // Anything that breathes loses oxygen while swimming, and much more quickly if it's weighed
+    // down by a heavy weapon
+    pub fn handle_submersion(&mut self, message_log: &mut Vec<Message>) {
         let oxygen_entities = self.components.oxygen.entities().collect::<Vec<_>>();
         for entity in oxygen_entities {
             if let Some(coord) = self.spatial_table.coord_of(entity) {
-                for smoke_entity in self.components.smoke.entities() {
-                    if let Some(smoke_coord) = self.spatial_table.coord_of(smoke_entity) {
-                        if let Some(distance) =
-                            self.line_distance_stopping_at_solid(coord, smoke_coord)
-                        {
-                            if distance < 4 {
-                                let oxygen = self.components.oxygen.get_mut(entity).unwrap();
-                                oxygen.decrease(2);
-                                message_log.push(Message::Smoke);
-                            }
-                        }
+                if self.is_water_at(coord) {
+                    let sinking = self.carries_heavy_item(entity);
+                    let oxygen = self.components.oxygen.get_mut(entity).unwrap();
+                    if sinking {
+                        oxygen.decrease(5);
+                        message_log.push(Message::Sinking);
+                    } else {
+                        oxygen.decrease(2);
                     }
                 }
             }
         }
     }
 
+    pub fn handle_smoke(&mut self, message_log: &mut Vec<Message>) {
+        let oxygen_entities = self.components.oxygen.entities().collect::<Vec<_>>();
+        for entity in oxygen_entities {
+            if let Some(coord) = self.spatial_table.coord_of(entity) {
+                let num_smoke_sources = self
+                    .components
+                    .smoke
+                    .entities()
+                    .filter_map(|smoke_entity| self.spatial_table.coord_of(smoke_entity))
+                    .filter_map(|smoke_coord| {
+                        self.line_distance_stopping_at_solid(coord, smoke_coord)
+                    })
+                    .filter(|&distance| distance < 4)
+                    .count();
+                if num_smoke_sources == 0
+                    || self.wear_protection(entity, WearSlot::Head, message_log)
+                {
+                    continue;
+                }
+                for _ in 0..num_smoke_sources {
+                    let oxygen = self.components.oxygen.get_mut(entity).unwrap();
+                    oxygen.decrease(2);
+                    message_log.push(Message::Smoke);
+                }
+            }
+        }
+    }
+
+    /// Whether a character is wearing something in `slot` to protect it from a hazard. Each turn
+    /// of protection wears the item down, and it falls apart once it's worn out.
+    fn wear_protection(
+        &mut self,
+        entity: Entity,
+        slot: WearSlot,
+        message_log: &mut Vec<Message>,
+    ) -> bool {
+        let item_entity = match self.components.worn.get(entity).and_then(|w| w.slot(slot)) {
+            Some(item_entity) => item_entity,
+            None => return false,
+        };
+        if let Some(durability) = self.components.durability.get_mut(item_entity) {
+            durability.decrease(1);
+            if durability.is_empty() {
+                if let Some(worn) = self.components.worn.get_mut(entity) {
+                    *worn.slot_mut(slot) = None;
+                }
+                if let Some(&item) = self.components.item.get(item_entity) {
+                    message_log.push(Message::WornItemFallsApart(item));
+                }
+                self.remove_entity(item_entity);
+            }
+        }
+        true
+    }
+
+    /// Fills the cells within `radius` of `centre` with smoke, which spreads around corners but
+    /// not through walls
+    pub fn spawn_smoke_cloud(&mut self, centre: Coord, radius: u32) {
+        let mut coords = vec![centre];
+        let mut queue = VecDeque::from([centre]);
+        while let Some(coord) = queue.pop_front() {
+            for direction in CardinalDirection::all() {
+                let neighbour = coord + direction.coord();
+                if neighbour.distance2(centre) > radius.pow(2) || coords.contains(&neighbour) {
+                    continue;
+                }
+                let blocked = match self.spatial_table.layers_at(neighbour) {
+                    None => true,
+                    Some(layers) => layers.feature.is_some_and(|feature| {
+                        self.components.solid_for_particles.contains(feature)
+                    }),
+                };
+                if !blocked {
+                    coords.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        self.smoke_clouds.push(SmokeCloud {
+            coords,
+            turns_remaining: SMOKE_CLOUD_TURNS,
+        });
+    }
+
+    pub fn disperse_smoke_clouds(&mut self) {
+        for cloud in self.smoke_clouds.iter_mut() {
+            cloud.turns_remaining = cloud.turns_remaining.saturating_sub(1);
+        }
+        self.smoke_clouds.retain(|cloud| cloud.turns_remaining > 0);
+    }
+
     pub fn handle_radiation(&mut self, message_log: &mut Vec<Message>) {
         let radiation_entities = self.components.radiation.entities().collect::<Vec<_>>();
         for entity in radiation_entities {
             if let Some(coord) = self.spatial_table.coord_of(entity) {
-                for r_entity in self.components.radioactive.entities() {
-                    if let Some(r_coord) = self.spatial_table.coord_of(r_entity) {
-                        if let Some(distance) = self.line_distance_stopping_at_solid(coord, r_coord)
-                        {
-                            let radiation = self.components.radiation.get_mut(entity).unwrap();
-                            if distance < 5 {
-                                radiation.increase(2);
-                                message_log.push(Message::RadiationClose);
-                            } else if distance < 10 {
-                                message_log.push(Message::RadiationClose);
-                                radiation.increase(1);
-                            }
-                        }
-                    }
+                let intensity = self.radiation_intensity_at(coord);
+                if intensity > 0 && !self.wear_protection(entity, WearSlot::Body, message_log) {
+                    let radiation = self.components.radiation.get_mut(entity).unwrap();
+                    radiation.increase(intensity);
+                    message_log.push(Message::RadiationClose);
                 }
             }
         }
@@ -845,4 +2104,152 @@ impl World {
             }
         }
     }
+
+    fn num_neighbouring_tentacles(&self, coord: Coord) -> usize {
+        Direction::all()
+            .filter(|direction| {
+                self.spatial_table
+                    .layers_at(coord + direction.coord())
+                    .and_then(|layers| layers.feature)
+                    .map(|feature| self.components.tentacle.contains(feature))
+                    .unwrap_or(false)
+            })
+            .count()
+    }
+
+    /// Npcs standing next to the corruption's tentacles are slowly overcome by it, taking
+    /// occasional damage until they turn into hostile corrupted versions of themselves. Returns
+    /// the npcs which were corrupted this turn.
+    pub fn handle_corruption<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        external_events: &mut Vec<ExternalEvent>,
+        message_log: &mut Vec<Message>,
+    ) -> Vec<Entity> {
+        if self.components.tentacle.is_empty() {
+            return Vec::new();
+        }
+        let exposed = self
+            .components
+            .npc
+            .entities()
+            .filter(|&entity| {
+                !(self.components.corrupted.contains(entity)
+                    || self.components.robotic.contains(entity)
+                    || self.components.boss.contains(entity)
+                    || self.components.shop.contains(entity))
+            })
+            .filter(|&entity| {
+                self.spatial_table
+                    .coord_of(entity)
+                    .map(|coord| self.num_neighbouring_tentacles(coord) > 0)
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        let mut corrupted = Vec::new();
+        for entity in exposed {
+            if !self.components.corruption.contains(entity) {
+                self.components
+                    .corruption
+                    .insert(entity, Meter::new(0, CORRUPTION_TURNS_TO_CONVERT));
+            }
+            let corruption = self.components.corruption.get_mut(entity).unwrap();
+            corruption.increase(1);
+            if corruption.is_full() {
+                self.corrupt_npc(entity);
+                corrupted.push(entity);
+            } else if rng.gen_bool(CORRUPTION_DAMAGE_PROBABILITY) {
+                self.damage_character(
+                    entity,
+                    DamageRoll::fixed(1),
+                    rng,
+                    external_events,
+                    message_log,
+                );
+            }
+        }
+        corrupted
+    }
+
+    /// The corruption creeps outwards from its tentacles onto empty floor. It only grows into
+    /// cells already bordered by several tentacles so it fills in around itself rather than
+    /// reaching down corridors and cutting off parts of the level.
+    pub fn grow_corruption<R: Rng>(&mut self, rng: &mut R) {
+        let tentacles = self.components.tentacle.entities().collect::<Vec<_>>();
+        for tentacle in tentacles {
+            if !rng.gen_bool(CORRUPTION_GROWTH_PROBABILITY) {
+                continue;
+            }
+            let coord = match self.spatial_table.coord_of(tentacle) {
+                Some(coord) => coord + CardinalDirection::all().choose(rng).unwrap().coord(),
+                None => continue,
+            };
+            if let Some(Layers {
+                floor: Some(_),
+                feature: None,
+                character: None,
+                ..
+            }) = self.spatial_table.layers_at(coord)
+            {
+                if self.num_neighbouring_tentacles(coord) < CORRUPTION_GROWTH_MIN_NEIGHBOURS {
+                    continue;
+                }
+                self.spawn_tentacle(coord);
+            }
+        }
+    }
+
+    /// Npcs other than shopkeepers, bosses and turrets take a step in a random direction
+    pub fn wander_npcs<R: Rng>(&mut self, rng: &mut R) {
+        let wanderers = self
+            .components
+            .npc
+            .entities()
+            .filter(|&entity| {
+                !(self.components.shop.contains(entity)
+                    || self.components.boss.contains(entity)
+                    || self.components.corpse.contains(entity)
+                    || self.components.npc_type.get(entity) == Some(&NpcType::Turret))
+            })
+            .collect::<Vec<_>>();
+        for entity in wanderers {
+            let coord = match self.spatial_table.location_of(entity) {
+                Some(Location {
+                    coord,
+                    layer: Some(Layer::Character),
+                }) => coord + CardinalDirection::all().choose(rng).unwrap().coord(),
+                _ => continue,
+            };
+            if let Some(Layers {
+                floor: Some(_),
+                feature: None,
+                character: None,
+                ..
+            }) = self.spatial_table.layers_at(coord)
+            {
+                let _ = self.spatial_table.update(
+                    entity,
+                    Location {
+                        coord,
+                        layer: Some(Layer::Character),
+                    },
+                );
+            }
+        }
+    }
+
+    fn corrupt_npc(&mut self, entity: Entity) {
+        self.components.corruption.remove(entity);
+        self.components.corrupted.insert(entity, ());
+        self.components
+            .colour_hint
+            .insert(entity, CORRUPTED_NPC_COLOUR.to_rgba32(255));
+        if let Some(npc) = self.components.npc.get_mut(entity) {
+            npc.disposition = Disposition::Hostile;
+        }
+        if let Some(health) = self.components.health.get_mut(entity) {
+            health.set_max(health.max() + health.max() / 2);
+            health.fill();
+        }
+    }
 }
@@ -1,5 +1,6 @@
-use crate::world::explosion;
 pub use crate::world::spatial::{Layer, Location};
+use crate::{documents::Document, world::explosion};
+use coord_2d::Coord;
 use entity_table::{declare_entity_module, Entity};
 use rand::{
     seq::{IteratorRandom, SliceRandom},
@@ -7,69 +8,152 @@ use rand::{
 };
 use rgb_int::Rgba32;
 use serde::{Deserialize, Serialize};
-use std::ops::RangeInclusive;
+use std::{collections::BTreeSet, ops::RangeInclusive};
 use visible_area_detection::{vision_distance, Light};
 
-declare_entity_module! {
-    components {
-        player: (),
-        realtime: (),
-        blocks_gameplay: (),
-        tile: Tile,
-        solid: (),
-        solid_for_particles: (),
-        difficult: (),
-        character: (),
-        particle: (),
-        door_state: DoorState,
-        opacity: u8,
-        stairs_down: (),
-        stairs_up: (),
-        exit: (),
-        colour_hint: Rgba32,
-        light: Light<vision_distance::Circle>,
-        collides_with: CollidesWith,
-        projectile_damage: ProjectileDamage,
-        on_collision: OnCollision,
-        npc: Npc,
-        health: Meter,
-        oxygen: Meter,
-        food: Meter,
-        poison: Meter,
-        radiation: Meter,
-        power: Meter,
-        satiation: Meter,
-        destructible: (),
-        to_remove: (),
-        explodes_on_death: (),
-        npc_type: NpcType,
-        item: Item,
-        money_item: u32,
-        inventory: Inventory,
-        money: u32,
-        leaves_corpse: (),
-        corpse: (),
-        resurrects_in: Meter,
-        simple_inventory: Vec<Entity>,
-        get_on_touch: (),
-        organs: Organs,
-        simple_organs: Vec<Organ>,
-        gun: Gun,
-        hands: Hands,
-        spread_poison: (),
-        split_on_damage: (),
-        floor_poison: (),
-        bump_damage: RangeInclusive<u32>,
-        radioactive: (),
-        smoke: (),
-        organ_clinic: (),
-        shop: Shop,
-        slow: u64,
-        boss: (),
-        tentacle: (),
-    }
+// Calls a macro with the list of components, so the list can be used both to declare the
+// components and to find every entity with a component
+macro_rules! with_components {
+    ($macro:ident) => {
+        $macro! {
+            components {
+                player: (),
+                realtime: (),
+                blocks_gameplay: (),
+                tile: Tile,
+                solid: (),
+                solid_for_particles: (),
+                difficult: (),
+                character: (),
+                particle: (),
+                door_state: DoorState,
+                door_health: Meter,
+                opacity: u8,
+                stairs_down: (),
+                stairs_up: (),
+                exit: (),
+                colour_hint: Rgba32,
+                light: Light<vision_distance::Circle>,
+                collides_with: CollidesWith,
+                projectile_damage: ProjectileDamage,
+                on_collision: OnCollision,
+                npc: Npc,
+                health: Meter,
+                oxygen: Meter,
+                food: Meter,
+                poison: Meter,
+                radiation: Meter,
+                power: Meter,
+                satiation: Meter,
+                destructible: (),
+                to_remove: (),
+                explodes_on_death: (),
+                explosive: (),
+                npc_type: NpcType,
+                item: Item,
+                money_item: u32,
+                inventory: Inventory,
+                money: u32,
+                leaves_corpse: (),
+                corpse: (),
+                resurrects_in: Meter,
+                simple_inventory: Vec<Entity>,
+                get_on_touch: (),
+                scavenger: (),
+                for_sale: Entity,
+                stolen_from: Entity,
+                hot: (),
+                surgery_recovery: Meter,
+                immunosuppressed: Meter,
+                bleeding: (),
+                blood_trail: u32,
+                scent: u32,
+                tracks_scent: (),
+                psychosis: Meter,
+                experience: Experience,
+                skills: Skills,
+                hack_progress: u32,
+                robotic: (),
+                emp_on_hit: (),
+                emp_disabled: Meter,
+                organs: Organs,
+                simple_organs: Vec<Organ>,
+                gun: Gun,
+                hands: Hands,
+                worn: Worn,
+                durability: Meter,
+                spread_poison: (),
+                split_on_damage: (),
+                floor_poison: (),
+                floor_hazard: FloorHazard,
+                hazard_on_death: FloorHazard,
+                water: (),
+                window: (),
+                bump_damage: RangeInclusive<u32>,
+                radioactive: (),
+                smoke: (),
+                organ_clinic: (),
+                shop: Shop,
+                stash: (),
+                mercenary: (),
+                drone: (),
+                turret_ammo: Meter,
+                alarm_panel: (),
+                generator: (),
+                out_of_fuel: (),
+                needs_power: (),
+                unidentified: Item,
+                slow: u64,
+                boss: (),
+                tentacle: (),
+                corruption: Meter,
+                corrupted: (),
+                hunts_player: (),
+                terminal: Document,
+                freshness: Meter,
+                butchered: (),
+                temperature: Meter,
+                clone_insurance: (),
+                player_remains: (),
+                blown_disguises: Vec<NpcType>,
+            }
+        }
+    };
+}
+
+macro_rules! declare_entities_with_components {
+    { components { $($component_name:ident: $component_type:ty,)* } } => {
+        impl Components {
+            /// Every entity which has at least one component
+            pub fn entities_with_components(&self) -> BTreeSet<Entity> {
+                let mut entities = BTreeSet::new();
+                $(entities.extend(self.$component_name.entities());)*
+                entities
+            }
+        }
+    };
 }
+
+with_components!(declare_entity_module);
 pub use components::{Components, EntityData, EntityUpdate};
+with_components!(declare_entities_with_components);
+
+/// Scenery which has no effect on the game but can be examined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum Prop {
+    AbandonedCar,
+    VendingMachine,
+    Graffiti,
+    Bloodstain,
+    Remains,
+}
+
+impl Prop {
+    /// Props which are drawn on the floor rather than standing on it
+    pub fn is_floor_decoration(self) -> bool {
+        matches!(self, Self::Bloodstain | Self::Remains)
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum Tile {
@@ -78,12 +162,18 @@ pub enum Tile {
     Floor,
     FloorBloody,
     FloorPoison,
+    FloorAcid,
+    FloorElectrified,
     Wall,
+    Window,
     Street,
     Alley,
     Footpath,
+    Water,
+    Bridge,
     DoorClosed,
     DoorOpen,
+    DoorBarricaded,
     StairsDown,
     StairsUp,
     Debris,
@@ -106,15 +196,32 @@ pub enum Tile {
     ItemStore,
     OrganTrader,
     OrganClinic,
+    Merchant,
+    BountyHunter,
+    Mercenary,
+    Turret,
+    Guard,
+    Civilian,
+    Sentinel,
+    Stash,
+    Terminal,
+    AlarmPanel,
+    Generator,
+    ExplosiveBarrel,
+    Drone,
     Money(u32),
     Item(Item),
+    UnlabeledSyringe,
+    StrangeDevice,
     Corpse(NpcType),
+    Prop(Prop),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DoorState {
     Open,
     Closed,
+    Barricaded,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -175,6 +282,64 @@ pub struct CollidesWith {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectileDamage {
     pub hit_points: RangeInclusive<u32>,
+    /// Chance that a hit is a critical one which leaves the character bleeding
+    pub bleeding_probability: f64,
+    /// Whether the projectile can glance off a wall it hits at a shallow angle. Only the first
+    /// such wall is glanced off.
+    pub ricochet: bool,
+    /// How many characters the projectile can pass through before it stops in the next one
+    pub penetration: u32,
+}
+
+/// Something which adds to (or takes from) the damage of a hit after the base damage is rolled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageModifier {
+    Claws,
+}
+
+/// The damage dealt by a single hit, along with how it was worked out so the message log can
+/// show the roll
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DamageRoll {
+    /// The range the base damage was rolled from
+    pub range: RangeInclusive<u32>,
+    pub base: u32,
+    pub modifiers: Vec<(DamageModifier, i32)>,
+}
+
+impl DamageRoll {
+    pub fn roll<R: Rng>(range: RangeInclusive<u32>, rng: &mut R) -> Self {
+        Self {
+            base: rng.gen_range(range.clone()),
+            range,
+            modifiers: Vec::new(),
+        }
+    }
+
+    /// Damage which doesn't vary, such as from explosions
+    pub fn fixed(damage: u32) -> Self {
+        Self {
+            range: damage..=damage,
+            base: damage,
+            modifiers: Vec::new(),
+        }
+    }
+
+    pub fn add_modifier(&mut self, modifier: DamageModifier, amount: i32) {
+        match self.modifiers.iter_mut().find(|(m, _)| *m == modifier) {
+            Some((_, total)) => *total += amount,
+            None => self.modifiers.push((modifier, amount)),
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        let modifiers = self
+            .modifiers
+            .iter()
+            .map(|&(_, amount)| amount)
+            .sum::<i32>();
+        (self.base as i32 + modifiers).max(0) as u32
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -182,6 +347,159 @@ pub enum OnCollision {
     Remove,
     RemoveRealtime,
     Explode(explosion::spec::Explosion),
+    Emp { range: u32 },
+    Smoke { radius: u32 },
+}
+
+/// The setting of a level, which decides how it looks, which hazards are common, and which
+/// enemies and items turn up there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelTheme {
+    Surface,
+    Offices,
+    Hospital,
+    Sewers,
+    Lab,
+}
+
+impl LevelTheme {
+    /// Themes which can be chosen for the levels inside the building
+    pub const INDOOR: &'static [Self] = &[Self::Offices, Self::Hospital, Self::Sewers, Self::Lab];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Surface => "Streets",
+            Self::Offices => "Offices",
+            Self::Hospital => "Hospital",
+            Self::Sewers => "Sewers",
+            Self::Lab => "Lab",
+        }
+    }
+
+    pub fn num_acid_pools(self) -> usize {
+        match self {
+            Self::Sewers => 6,
+            Self::Lab => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn num_electrified_patches(self, level_index: usize) -> usize {
+        match self {
+            Self::Offices => level_index + 2,
+            Self::Lab => level_index + 1,
+            Self::Sewers => level_index / 2,
+            _ => level_index,
+        }
+    }
+
+    pub fn num_explosive_barrels(self, level_index: usize) -> usize {
+        match self {
+            Self::Surface => 2,
+            Self::Offices | Self::Lab => 3 + level_index / 3,
+            Self::Hospital | Self::Sewers => 1 + level_index / 3,
+        }
+    }
+
+    /// How many of each prop to scatter around levels with this theme
+    pub fn props(self) -> &'static [(Prop, usize)] {
+        match self {
+            Self::Surface => &[
+                (Prop::AbandonedCar, 4),
+                (Prop::Graffiti, 8),
+                (Prop::Bloodstain, 6),
+                (Prop::Remains, 3),
+            ],
+            Self::Offices => &[
+                (Prop::VendingMachine, 3),
+                (Prop::Graffiti, 2),
+                (Prop::Bloodstain, 6),
+                (Prop::Remains, 3),
+            ],
+            Self::Hospital => &[
+                (Prop::VendingMachine, 2),
+                (Prop::Bloodstain, 10),
+                (Prop::Remains, 4),
+            ],
+            Self::Sewers => &[
+                (Prop::Graffiti, 10),
+                (Prop::Bloodstain, 4),
+                (Prop::Remains, 3),
+            ],
+            Self::Lab => &[
+                (Prop::VendingMachine, 1),
+                (Prop::Bloodstain, 6),
+                (Prop::Remains, 4),
+            ],
+        }
+    }
+
+    pub fn num_radiation_zones(self, level_index: usize) -> usize {
+        let num = 1 + level_index / 2;
+        match self {
+            Self::Lab => num + 2,
+            Self::Hospital => num + 1,
+            _ => num,
+        }
+    }
+}
+
+/// An area of a level which irradiates anything inside it. Radiation is strongest at the centre
+/// and falls off towards the edge.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RadiationZone {
+    pub centre: Coord,
+    pub radius: u32,
+}
+
+impl RadiationZone {
+    const PEAK_INTENSITY: u32 = 3;
+
+    pub fn intensity_at(&self, coord: Coord) -> u32 {
+        let distance = self.centre.manhattan_distance(coord);
+        if distance >= self.radius {
+            0
+        } else {
+            // round up so the edge of the zone is still detectable
+            ((self.radius - distance) * Self::PEAK_INTENSITY).div_ceil(self.radius)
+        }
+    }
+}
+
+/// Smoke from a smoke grenade, which blocks vision until it disperses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeCloud {
+    pub coords: Vec<Coord>,
+    pub turns_remaining: u32,
+}
+
+/// A puff of poison gas breathed out by a poisoner, which drifts around until it disperses
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GasPuff {
+    pub coord: Coord,
+    pub turns_remaining: u32,
+}
+
+/// Floors which damage any character standing on them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FloorHazard {
+    Acid,
+    Electrified,
+}
+
+impl FloorHazard {
+    pub fn tile(self) -> Tile {
+        match self {
+            Self::Acid => Tile::FloorAcid,
+            Self::Electrified => Tile::FloorElectrified,
+        }
+    }
+    pub fn damage(self) -> u32 {
+        match self {
+            Self::Acid => 1,
+            Self::Electrified => 2,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -190,6 +508,7 @@ pub enum Disposition {
     Afraid,
     Thief,
     Neutral,
+    Ally,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -241,6 +560,13 @@ pub enum NpcType {
     ItemStore,
     OrganTrader,
     OrganClinic,
+    Merchant,
+    BountyHunter,
+    Mercenary,
+    Turret,
+    Guard,
+    Civilian,
+    Sentinel,
 }
 
 impl NpcType {
@@ -260,6 +586,13 @@ impl NpcType {
             Self::ItemStore => Tile::ItemStore,
             Self::OrganTrader => Tile::OrganTrader,
             Self::OrganClinic => Tile::OrganClinic,
+            Self::Merchant => Tile::Merchant,
+            Self::BountyHunter => Tile::BountyHunter,
+            Self::Mercenary => Tile::Mercenary,
+            Self::Turret => Tile::Turret,
+            Self::Guard => Tile::Guard,
+            Self::Civilian => Tile::Civilian,
+            Self::Sentinel => Tile::Sentinel,
         }
     }
 }
@@ -365,6 +698,7 @@ pub enum OrganType {
     CyberCore,
     Claw,
     CorruptedHeart,
+    ReinforcedSkeleton,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -373,11 +707,17 @@ pub struct Organ {
     pub traits: OrganTraits,
     pub cybernetic: bool,
     pub original: bool,
+    /// Taken from a corpse by the player, so the player's body may reject it
+    pub harvested: bool,
+    /// Knocked out by an EMP until the player's cybernetics reboot
+    pub disabled: bool,
+    /// The type of npc whose corpse the organ was harvested from
+    pub donor: Option<NpcType>,
 }
 
 impl Organ {
-    pub fn player_buy_price(&self) -> u32 {
-        let mut price = match self.type_ {
+    fn base_price(&self) -> u32 {
+        let price = match self.type_ {
             OrganType::Heart => 40,
             OrganType::Liver => 20,
             OrganType::Lung => 20,
@@ -389,10 +729,16 @@ impl Organ {
             OrganType::CyberCore => 50,
             OrganType::Claw => 30,
             OrganType::CorruptedHeart => 3000,
+            OrganType::ReinforcedSkeleton => 50,
         };
         if self.cybernetic {
-            price *= 2;
+            price * 2
+        } else {
+            price
         }
+    }
+    pub fn player_buy_price(&self) -> u32 {
+        let mut price = self.base_price();
         let traits = self.traits.traits();
         if traits.len() == 1 {
             price = price * 2 / 3;
@@ -401,6 +747,30 @@ impl Organ {
         }
         price
     }
+    /// What the organ trader pays for an organ. Unlike the clinics, the trader has buyers who
+    /// want some traits, so these raise the price while others lower it.
+    pub fn player_sell_price(&self) -> u32 {
+        let mut price = self.base_price();
+        for organ_trait in self.traits.traits() {
+            price = match organ_trait {
+                OrganTrait::Prolific | OrganTrait::Vampiric => price * 3 / 2,
+                OrganTrait::Embedded => price * 3 / 4,
+                OrganTrait::Radioactitve | OrganTrait::Transient => price / 2,
+                OrganTrait::Damaged => price / 4,
+            };
+        }
+        price / 2
+    }
+    /// What the organ trader charges for an organ, which is more than a clinic would as the
+    /// trader only deals in organs the clinics don't have. Hot organs go for half price.
+    pub fn black_market_price(&self, hot: bool) -> u32 {
+        let price = self.base_price() * 3 / 2;
+        if hot {
+            price / 2
+        } else {
+            price
+        }
+    }
     pub fn remove_price(&self) -> i32 {
         let traits = self.traits.traits();
         if self.original && traits.is_empty() {
@@ -418,9 +788,6 @@ impl Organ {
             20
         }
     }
-    pub fn container_install_cost(&self) -> u32 {
-        20
-    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -436,12 +803,44 @@ pub enum Item {
     Shotgun,
     Pistol,
     RocketLauncher,
+    SniperRifle,
     ShotgunAmmo,
     PistolAmmo,
     Rocket,
+    SniperRounds,
+    Drone,
+    Turret,
+    CorruptedStimpack,
+    CursedPistol,
+    Scanner,
+    GeigerCounter,
+    Schematics,
+    Plank,
+    Anesthetic,
+    FieldSurgeryKit,
+    Immunosuppressant,
+    Bandage,
+    Antipsychotic,
+    EmpGrenade,
+    SmokeGrenade,
+    GasMask,
+    HazardSuit,
+    RawMeat,
+    CookedMeat,
+    RottenMeat,
+    MarketReport,
+    Readable(Document),
 }
 
 impl Item {
+    /// Heavy weapons drag a swimmer under the water
+    pub fn is_heavy(&self) -> bool {
+        matches!(
+            self,
+            Self::Shotgun | Self::RocketLauncher | Self::SniperRifle
+        )
+    }
+
     pub fn price(&self) -> u32 {
         match self {
             Self::Stimpack => 10,
@@ -459,6 +858,95 @@ impl Item {
             Self::ShotgunAmmo => 10,
             Self::RocketLauncher => 80,
             Self::Rocket => 20,
+            Self::SniperRifle => 60,
+            Self::SniperRounds => 15,
+            Self::Drone => 60,
+            Self::Turret => 80,
+            Self::CorruptedStimpack => 5,
+            Self::CursedPistol => 15,
+            Self::Scanner => 30,
+            Self::GeigerCounter => 40,
+            Self::Schematics => 50,
+            Self::Plank => 5,
+            Self::Anesthetic => 10,
+            Self::FieldSurgeryKit => 150,
+            Self::Immunosuppressant => 15,
+            Self::Bandage => 5,
+            Self::Antipsychotic => 20,
+            Self::EmpGrenade => 40,
+            Self::SmokeGrenade => 20,
+            Self::GasMask => 30,
+            Self::HazardSuit => 60,
+            Self::RawMeat => 2,
+            Self::CookedMeat => 8,
+            Self::RottenMeat => 0,
+            Self::MarketReport => 15,
+            Self::Readable(_) => 0,
+        }
+    }
+    /// How much eating the item fills the food meter, or `None` if it can't be eaten
+    pub fn nutrition(&self) -> Option<u32> {
+        match self {
+            Self::Food => Some(25),
+            Self::RawMeat => Some(15),
+            Self::CookedMeat => Some(40),
+            Self::RottenMeat => Some(10),
+            _ => None,
+        }
+    }
+    /// How many turns the item keeps before it rots, or `None` if it never does
+    pub fn turns_until_spoiled(&self) -> Option<u32> {
+        match self {
+            Self::RawMeat => Some(200),
+            Self::CookedMeat => Some(500),
+            _ => None,
+        }
+    }
+    /// Where the item is worn, or `None` if it can't be worn
+    pub fn wear_slot(&self) -> Option<WearSlot> {
+        match self {
+            Self::GasMask => Some(WearSlot::Head),
+            Self::HazardSuit => Some(WearSlot::Body),
+            _ => None,
+        }
+    }
+    pub fn sell_price(&self) -> u32 {
+        self.price() / 2
+    }
+    /// The item this item looks like until it has been identified
+    pub fn disguise(self) -> Self {
+        match self {
+            Self::CorruptedStimpack => Self::Stimpack,
+            Self::CursedPistol => Self::Pistol,
+            other => other,
+        }
+    }
+    /// How items of this kind appear before the player has identified the kind, or `None` if
+    /// items of this kind are always recognizable
+    pub fn unidentified_appearance(self) -> Option<ItemAppearance> {
+        match self {
+            Self::Stimpack | Self::Antidote | Self::AntiRads => {
+                Some(ItemAppearance::UnlabeledSyringe)
+            }
+            Self::Drone | Self::Turret => Some(ItemAppearance::StrangeDevice),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemAppearance {
+    Identified(Item),
+    UnlabeledSyringe,
+    StrangeDevice,
+}
+
+impl ItemAppearance {
+    pub fn tile(self) -> Tile {
+        match self {
+            Self::Identified(item) => Tile::Item(item),
+            Self::UnlabeledSyringe => Tile::UnlabeledSyringe,
+            Self::StrangeDevice => Tile::StrangeDevice,
         }
     }
 }
@@ -489,12 +977,11 @@ impl Inventory {
     }
 
     pub fn get(&self, i: usize) -> Option<Entity> {
-        self.items[i]
+        self.items.get(i).copied().flatten()
     }
 
     pub fn remove(&mut self, i: usize) -> Option<Entity> {
-        use std::mem;
-        mem::replace(&mut self.items[i], None)
+        self.items.get_mut(i).and_then(Option::take)
     }
 
     pub fn items(&self) -> &[Option<Entity>] {
@@ -523,17 +1010,19 @@ impl Organs {
         None
     }
 
-    pub fn get_slot_mut(&mut self, i: usize) -> &mut Option<Organ> {
-        &mut self.organs[i]
-    }
-
     pub fn get_mut(&mut self, i: usize) -> Option<&mut Organ> {
-        self.organs[i].as_mut()
+        self.organs.get_mut(i).and_then(Option::as_mut)
     }
 
     pub fn remove(&mut self, i: usize) -> Option<Organ> {
-        use std::mem;
-        mem::replace(&mut self.organs[i], None)
+        // organs recover from an EMP once they're out of the body
+        self.organs
+            .get_mut(i)
+            .and_then(Option::take)
+            .map(|organ| Organ {
+                disabled: false,
+                ..organ
+            })
     }
 
     pub fn num_claws(&self) -> usize {
@@ -562,6 +1051,149 @@ impl Organs {
             .choose(rng);
         index.map(|i| self.organs[i].as_mut().unwrap())
     }
+
+    pub fn num_slots(&self) -> usize {
+        self.organs.len()
+    }
+
+    pub fn add_slot(&mut self) {
+        self.organs.push(None);
+    }
+}
+
+/// Chosen by the player each time they gain an experience level
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Perk {
+    ExtraOrganSlot,
+    /// Reloading doesn't take a turn
+    QuickReload,
+    /// Poison takes longer to build up to the point where it damages organs
+    PoisonResistance,
+}
+
+impl Perk {
+    pub const ALL: &'static [Self] = &[
+        Self::ExtraOrganSlot,
+        Self::QuickReload,
+        Self::PoisonResistance,
+    ];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Skill {
+    /// Tightens the spread of shotgun pellets
+    Guns,
+    /// Makes surgery safer and cheaper
+    Surgery,
+    /// Alarm panels take fewer attempts to hack
+    Hacking,
+}
+
+impl Skill {
+    pub const ALL: &'static [Self] = &[Self::Guns, Self::Surgery, Self::Hacking];
+
+    /// The number of times a skill must be used to gain each level
+    fn practice_per_level(self) -> u32 {
+        match self {
+            Self::Guns => 25,
+            Self::Surgery => 2,
+            Self::Hacking => 4,
+        }
+    }
+}
+
+pub const MAX_SKILL_LEVEL: u32 = 5;
+
+/// Skills improve the more the player uses them
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Skills {
+    guns: u32,
+    surgery: u32,
+    hacking: u32,
+}
+
+impl Skills {
+    fn practice_mut(&mut self, skill: Skill) -> &mut u32 {
+        match skill {
+            Skill::Guns => &mut self.guns,
+            Skill::Surgery => &mut self.surgery,
+            Skill::Hacking => &mut self.hacking,
+        }
+    }
+
+    /// The number of times the skill has been used
+    pub fn practice(&self, skill: Skill) -> u32 {
+        match skill {
+            Skill::Guns => self.guns,
+            Skill::Surgery => self.surgery,
+            Skill::Hacking => self.hacking,
+        }
+    }
+
+    pub fn level(&self, skill: Skill) -> u32 {
+        (self.practice(skill) / skill.practice_per_level()).min(MAX_SKILL_LEVEL)
+    }
+
+    /// Returns the new level if using the skill raised it
+    pub fn train(&mut self, skill: Skill) -> Option<u32> {
+        let level = self.level(skill);
+        *self.practice_mut(skill) += 1;
+        let new_level = self.level(skill);
+        if new_level > level {
+            Some(new_level)
+        } else {
+            None
+        }
+    }
+}
+
+/// The total experience needed to reach each experience level after the first
+const EXPERIENCE_LEVEL_THRESHOLDS: &[u32] = &[20, 60, 120];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Experience {
+    pub points: u32,
+    pub level: u32,
+    pub perks: Vec<Perk>,
+}
+
+impl Experience {
+    /// The total experience needed to reach the next level, or `None` at the maximum level
+    pub fn next_level_points(&self) -> Option<u32> {
+        EXPERIENCE_LEVEL_THRESHOLDS
+            .get(self.level as usize)
+            .cloned()
+    }
+
+    /// Returns the number of levels gained
+    pub fn gain(&mut self, points: u32) -> u32 {
+        self.points += points;
+        let mut levels = 0;
+        while let Some(next_level_points) = self.next_level_points() {
+            if self.points < next_level_points {
+                break;
+            }
+            self.level += 1;
+            levels += 1;
+        }
+        levels
+    }
+
+    pub fn has_perk(&self, perk: Perk) -> bool {
+        self.perks.contains(&perk)
+    }
+
+    /// Perks the player can still choose, if they have a level-up to spend
+    pub fn available_perks(&self) -> Vec<Perk> {
+        if self.perks.len() as u32 >= self.level {
+            return Vec::new();
+        }
+        Perk::ALL
+            .iter()
+            .cloned()
+            .filter(|&perk| !self.has_perk(perk))
+            .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -569,6 +1201,7 @@ pub enum GunType {
     Pistol,
     Shotgun,
     RocketLauncher,
+    SniperRifle,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -600,6 +1233,13 @@ impl Gun {
             hands_required: 2,
         }
     }
+    pub fn sniper_rifle() -> Self {
+        Self {
+            type_: GunType::SniperRifle,
+            ammo: Meter::new_full(4),
+            hands_required: 2,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -641,6 +1281,35 @@ pub struct Hands {
     pub right: Hand,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WearSlot {
+    Head,
+    Body,
+}
+
+/// Equipment worn by a character, which doesn't take up their hands
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct Worn {
+    pub head: Option<Entity>,
+    pub body: Option<Entity>,
+}
+
+impl Worn {
+    pub fn slot(&self, slot: WearSlot) -> Option<Entity> {
+        match slot {
+            WearSlot::Head => self.head,
+            WearSlot::Body => self.body,
+        }
+    }
+
+    pub fn slot_mut(&mut self, slot: WearSlot) -> &mut Option<Entity> {
+        match slot {
+            WearSlot::Head => &mut self.head,
+            WearSlot::Body => &mut self.body,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Shop {
     pub message: String,
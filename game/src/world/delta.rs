@@ -0,0 +1,111 @@
+use crate::{
+    realtime::RealtimeComponents,
+    world::{
+        data::{Components, GasPuff, RadiationZone, SmokeCloud},
+        spatial::Location,
+        World,
+    },
+};
+use entity_table::{Entity, EntityAllocator};
+use serde::{Deserialize, Serialize};
+
+/// The differences between a world and the world generated from the same seed. Most of a level
+/// never changes, so this is much smaller than the world itself.
+#[derive(Serialize, Deserialize)]
+pub struct WorldDelta {
+    entity_allocator: EntityAllocator,
+    removed: Vec<Entity>,
+    changed: Vec<(Entity, Option<Location>)>,
+    // Stored as component tables rather than a list of `EntityData` so the many components an
+    // entity doesn't have take up no space
+    components: Components,
+    realtime_components: RealtimeComponents,
+    alarm_turns_remaining: u32,
+    civilian_deaths: u32,
+    radiation_zones: Vec<RadiationZone>,
+    smoke_clouds: Vec<SmokeCloud>,
+    poison_gas: Vec<GasPuff>,
+    powered: bool,
+}
+
+// Animations such as flickering lights continually change the state of the realtime components
+// of the entities they animate, along with their colour and light, which is cosmetic so doesn't
+// count as the entity changing. The entity is serialized so it can be compared without every
+// component type implementing `PartialEq`.
+fn comparable_entity(world: &World, entity: Entity) -> Vec<u8> {
+    let mut data = world.components.clone_entity_data(entity);
+    if data.realtime.is_some() {
+        data.colour_hint = None;
+        data.light = None;
+    }
+    let state = (world.spatial_table.location_of(entity), data);
+    serde_json::to_vec(&state).expect("failed to serialize entity")
+}
+
+impl World {
+    /// The differences between this world and `base`, which is the world generated from the same
+    /// seed before anything happened in it
+    pub fn into_delta(self, base: &World) -> WorldDelta {
+        let entities = self.components.entities_with_components();
+        let base_entities = base.components.entities_with_components();
+        let removed = base_entities.difference(&entities).cloned().collect();
+        let mut changed = Vec::new();
+        let mut components = Components::default();
+        let mut realtime_components = RealtimeComponents::default();
+        for &entity in &entities {
+            if base_entities.contains(&entity)
+                && comparable_entity(&self, entity) == comparable_entity(base, entity)
+            {
+                continue;
+            }
+            changed.push((entity, self.spatial_table.location_of(entity).cloned()));
+            components.insert_entity_data(entity, self.components.clone_entity_data(entity));
+            realtime_components
+                .insert_entity_data(entity, self.realtime_components.clone_entity_data(entity));
+        }
+        WorldDelta {
+            entity_allocator: self.entity_allocator,
+            removed,
+            changed,
+            components,
+            realtime_components,
+            alarm_turns_remaining: self.alarm_turns_remaining,
+            civilian_deaths: self.civilian_deaths,
+            radiation_zones: self.radiation_zones,
+            smoke_clouds: self.smoke_clouds,
+            poison_gas: self.poison_gas,
+            powered: self.powered,
+        }
+    }
+
+    /// Apply the differences recorded by `into_delta` to the world they were taken relative to,
+    /// turning it back into the world they were taken from
+    pub fn apply_delta(&mut self, mut delta: WorldDelta) {
+        for entity in delta.removed {
+            self.remove_entity(entity);
+        }
+        // Remove all changed entities from the spatial table before placing any of them, as an
+        // entity may have moved into a cell another has left
+        for &(entity, _) in &delta.changed {
+            self.spatial_table.remove(entity);
+        }
+        for (entity, location) in delta.changed {
+            self.components
+                .update_entity_data(entity, delta.components.remove_entity_data(entity));
+            self.realtime_components
+                .update_entity_data(entity, delta.realtime_components.remove_entity_data(entity));
+            if let Some(location) = location {
+                self.spatial_table
+                    .update(entity, location)
+                    .expect("entities in world delta overlap");
+            }
+        }
+        self.entity_allocator = delta.entity_allocator;
+        self.alarm_turns_remaining = delta.alarm_turns_remaining;
+        self.civilian_deaths = delta.civilian_deaths;
+        self.radiation_zones = delta.radiation_zones;
+        self.smoke_clouds = delta.smoke_clouds;
+        self.poison_gas = delta.poison_gas;
+        self.powered = delta.powered;
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{realtime, world::World, Entity, ExternalEvent, Message};
+use crate::{realtime, world::World, DamageRoll, Entity, ExternalEvent, Message};
 use coord_2d::Coord;
 use direction::Direction;
 use line_2d::LineSegment;
@@ -28,6 +28,18 @@ pub mod spec {
         pub mechanics: Mechanics,
         pub particle_emitter: ParticleEmitter,
     }
+
+    /// The blast of an explosive barrel, which is larger than that of a rocket
+    pub const EXPLOSIVE_BARREL: Explosion = Explosion {
+        mechanics: Mechanics { range: 3 },
+        particle_emitter: ParticleEmitter {
+            duration: Duration::from_millis(400),
+            num_particles_per_frame: 100,
+            min_step: Duration::from_millis(100),
+            max_step: Duration::from_millis(300),
+            fade_duration: Duration::from_millis(500),
+        },
+    };
 }
 
 fn apply_indirect_hit<R: Rng>(
@@ -39,7 +51,7 @@ fn apply_indirect_hit<R: Rng>(
     message_log: &mut Vec<Message>,
 ) {
     let push_back = 2;
-    let damage = 2;
+    let damage = DamageRoll::fixed(2);
     world.components.realtime.insert(character_entity, ());
     world.realtime_components.movement.insert(
         character_entity,
@@ -47,6 +59,7 @@ fn apply_indirect_hit<R: Rng>(
             path: explosion_to_character.delta(),
             repeat: realtime::movement::spec::Repeat::Steps(push_back as usize),
             cardinal_step_duration: Duration::from_millis(100),
+            flight_height: realtime::movement::FlightHeight::Level,
         }
         .build(),
     );
@@ -71,7 +84,7 @@ fn apply_direct_hit<R: Rng>(
         }
     }
     let push_back = 2;
-    let damage = 2;
+    let damage = DamageRoll::fixed(2);
     if solid_neighbour_vector.is_zero() {
         log::warn!("Direct hit with no solid neighbours shouldn't be possible.");
     } else {
@@ -83,6 +96,7 @@ fn apply_direct_hit<R: Rng>(
                 path: travel_vector,
                 repeat: realtime::movement::spec::Repeat::Steps(push_back as usize),
                 cardinal_step_duration: Duration::from_millis(100),
+                flight_height: realtime::movement::FlightHeight::Level,
             }
             .build(),
         );
@@ -98,6 +112,19 @@ fn is_in_explosion_range(
     explosion_coord.distance2(coord) <= mechanics.range.pow(2)
 }
 
+// Removes an explosive from the world, returning the coordinate where it will explode
+fn remove_explosive(
+    world: &mut World,
+    explosive_entity: Entity,
+    message_log: &mut Vec<Message>,
+) -> Option<Coord> {
+    let coord = world.spatial_table.coord_of(explosive_entity)?;
+    world.remove_entity(explosive_entity);
+    message_log.push(Message::ExplosiveBarrelDetonates);
+    Some(coord)
+}
+
+// Returns the coordinates of explosives which were set off by the explosion
 fn apply_mechanics<R: Rng>(
     world: &mut World,
     explosion_coord: Coord,
@@ -105,7 +132,15 @@ fn apply_mechanics<R: Rng>(
     rng: &mut R,
     external_events: &mut Vec<ExternalEvent>,
     message_log: &mut Vec<Message>,
-) {
+) -> Vec<Coord> {
+    let mut detonated = Vec::new();
+    for explosive_entity in world.components.explosive.entities().collect::<Vec<_>>() {
+        if let Some(coord) = world.spatial_table.coord_of(explosive_entity) {
+            if is_in_explosion_range(explosion_coord, mechanics, coord) {
+                detonated.extend(remove_explosive(world, explosive_entity, message_log));
+            }
+        }
+    }
     for character_entity in world.components.character.entities().collect::<Vec<_>>() {
         if let Some(character_coord) = world.spatial_table.coord_of(character_entity) {
             if character_coord == explosion_coord {
@@ -140,6 +175,7 @@ fn apply_mechanics<R: Rng>(
             }
         }
     }
+    detonated
 }
 
 pub fn explode<R: Rng>(
@@ -150,14 +186,43 @@ pub fn explode<R: Rng>(
     message_log: &mut Vec<Message>,
     rng: &mut R,
 ) {
-    world.spawn_explosion_emitter(coord, &explosion.particle_emitter, rng);
-    apply_mechanics(
-        world,
-        coord,
-        &explosion.mechanics,
-        rng,
-        external_events,
-        message_log,
-    );
-    external_events.push(ExternalEvent::Explosion(coord));
+    // explosives caught in the blast explode in turn, possibly setting off more explosives
+    let mut explosions = vec![(coord, explosion)];
+    while let Some((coord, explosion)) = explosions.pop() {
+        world.spawn_explosion_emitter(coord, &explosion.particle_emitter, rng);
+        let detonated = apply_mechanics(
+            world,
+            coord,
+            &explosion.mechanics,
+            rng,
+            external_events,
+            message_log,
+        );
+        external_events.push(ExternalEvent::Explosion(coord));
+        explosions.extend(
+            detonated
+                .into_iter()
+                .map(|coord| (coord, spec::EXPLOSIVE_BARREL)),
+        );
+    }
+}
+
+/// Set off an explosive (e.g. when it's shot)
+pub fn detonate<R: Rng>(
+    world: &mut World,
+    explosive_entity: Entity,
+    external_events: &mut Vec<ExternalEvent>,
+    message_log: &mut Vec<Message>,
+    rng: &mut R,
+) {
+    if let Some(coord) = remove_explosive(world, explosive_entity, message_log) {
+        explode(
+            world,
+            coord,
+            spec::EXPLOSIVE_BARREL,
+            external_events,
+            message_log,
+            rng,
+        );
+    }
 }
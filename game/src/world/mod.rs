@@ -1,5 +1,6 @@
 use crate::realtime::RealtimeComponents;
 use coord_2d::Size;
+use data::{GasPuff, LevelTheme, RadiationZone, SmokeCloud};
 use entity_table::{Entity, EntityAllocator};
 use grid_search_cardinal::distance_map::DistanceMap;
 use serde::{Deserialize, Serialize};
@@ -15,6 +16,7 @@ use data::{Components, EntityData};
 pub mod spawn;
 
 mod action;
+pub mod delta;
 pub mod query;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +26,16 @@ pub struct World {
     pub realtime_components: RealtimeComponents,
     pub spatial_table: SpatialTable,
     pub distance_map: DistanceMap,
+    pub alarm_turns_remaining: u32,
+    pub radiation_zones: Vec<RadiationZone>,
+    pub theme: LevelTheme,
+    /// Elevators and ceiling lights only work while the level has power
+    pub powered: bool,
+    /// Civilians killed on this level, each of which makes the level's vendors trust the player
+    /// less
+    pub civilian_deaths: u32,
+    pub smoke_clouds: Vec<SmokeCloud>,
+    pub poison_gas: Vec<GasPuff>,
 }
 
 impl World {
@@ -38,6 +50,13 @@ impl World {
             realtime_components,
             spatial_table,
             distance_map: DistanceMap::new(size),
+            alarm_turns_remaining: 0,
+            civilian_deaths: 0,
+            radiation_zones: Vec::new(),
+            smoke_clouds: Vec::new(),
+            poison_gas: Vec::new(),
+            theme: LevelTheme::Surface,
+            powered: true,
         }
     }
 
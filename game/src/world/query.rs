@@ -5,7 +5,12 @@ use crate::{
 use coord_2d::Coord;
 use direction::CardinalDirection;
 use entity_table::Entity;
-use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use line_2d::LineSegment;
+use rand::{seq::SliceRandom, Rng};
+use shadowcast::VisionDistance;
+
+const ALARM_PANEL_VISION_DISTANCE_SQUARED: u32 = 64;
+const VENDOR_VISION_DISTANCE_SQUARED: u32 = 25;
 
 impl World {
     pub fn stairs_up_or_exit_coord(&self) -> Option<Coord> {
@@ -33,6 +38,24 @@ impl World {
         self.spatial_table.coord_of(entity)
     }
 
+    /// How fresh the player's blood trail is at a coord, or 0 if there's no trail there
+    pub fn blood_trail_at(&self, coord: Coord) -> u32 {
+        self.spatial_table
+            .layers_at(coord)
+            .and_then(|layers| layers.floor)
+            .and_then(|floor_entity| self.components.blood_trail.get(floor_entity).cloned())
+            .unwrap_or(0)
+    }
+
+    /// How fresh the player's scent is at a coord, or 0 if they haven't left any there
+    pub fn scent_at(&self, coord: Coord) -> u32 {
+        self.spatial_table
+            .layers_at(coord)
+            .and_then(|layers| layers.floor)
+            .and_then(|floor_entity| self.components.scent.get(floor_entity).cloned())
+            .unwrap_or(0)
+    }
+
     pub fn can_npc_traverse_feature_at_coord_with_movement(
         &self,
         coord: Coord,
@@ -40,8 +63,13 @@ impl World {
     ) -> bool {
         if let Some(layers) = self.spatial_table.layers_at(coord) {
             if let Some(feature) = layers.feature {
+                // npcs don't swim
+                if self.components.water.contains(feature) {
+                    return false;
+                }
+                // npcs which can't open doors bash them down instead
                 !self.components.solid.contains(feature)
-                    || movement.can_open_doors && self.components.door_state.contains(feature)
+                    || self.components.door_state.contains(feature)
                     || movement.can_traverse_difficult
                         && self.components.difficult.contains(feature)
             } else {
@@ -52,13 +80,84 @@ impl World {
         }
     }
 
+    /// Also works for the player, who moves like an npc which can open doors, and which can
+    /// climb over debris if the player has the organs for it
     pub fn can_npc_traverse_feature_at_coord_with_entity(
         &self,
         coord: Coord,
         npc_entity: Entity,
     ) -> bool {
-        let npc = self.components.npc.get(npc_entity).expect("not an npc");
-        self.can_npc_traverse_feature_at_coord_with_movement(coord, npc.movement)
+        let movement = if self.components.player.contains(npc_entity) {
+            NpcMovement {
+                can_traverse_difficult: self.can_player_climb(),
+                can_open_doors: true,
+            }
+        } else {
+            self.components
+                .npc
+                .get(npc_entity)
+                .expect("not an npc")
+                .movement
+        };
+        self.can_npc_traverse_feature_at_coord_with_movement(coord, movement)
+    }
+
+    /// Claws, or a cybernetic skeleton strong enough to haul the player up, let the player climb
+    /// over debris like a climber
+    pub fn can_player_climb(&self) -> bool {
+        self.active_player_organs().iter().any(|organ| {
+            organ.type_ == OrganType::Claw
+                || (organ.type_ == OrganType::ReinforcedSkeleton && organ.cybernetic)
+        })
+    }
+
+    /// How brightly a cell is lit by every light except the one belonging to `except`, where 1.0
+    /// is as bright as the cell directly beneath a white light
+    pub fn light_level_at(&self, coord: Coord, except: Entity) -> f64 {
+        let mut level = 0.0;
+        for (entity, light) in self.components.light.iter() {
+            if entity == except || (!self.powered && self.components.needs_power.contains(entity)) {
+                continue;
+            }
+            let light_coord = match self.spatial_table.coord_of(entity) {
+                Some(light_coord) => light_coord,
+                None => continue,
+            };
+            let delta = coord - light_coord;
+            if !light.vision_distance.in_range(delta) {
+                continue;
+            }
+            if let Ok(line_segment) = LineSegment::try_new(light_coord, coord) {
+                let opacity_sum = line_segment
+                    .iter()
+                    .skip(1)
+                    .map(|coord| self.get_opacity(coord) as u32)
+                    .sum::<u32>();
+                if opacity_sum >= 255 {
+                    continue;
+                }
+            }
+            // the same falloff used when drawing lights
+            let distance = (delta.magnitude2() as f64).sqrt();
+            let diminish = &light.diminish;
+            let height2 = diminish.height * diminish.height;
+            let intensity = (diminish.intensity * height2)
+                / ((distance * distance)
+                    + (2.0 * diminish.focus * diminish.height * distance)
+                    + height2);
+            let colour = light.colour;
+            let brightness = colour.r.max(colour.g).max(colour.b) as f64 / 255.0;
+            level += intensity * brightness;
+        }
+        level
+    }
+
+    pub fn is_difficult_at(&self, coord: Coord) -> bool {
+        self.spatial_table
+            .layers_at(coord)
+            .and_then(|layers| layers.feature)
+            .map(|feature| self.components.difficult.contains(feature))
+            .unwrap_or(false)
     }
 
     pub fn is_npc_at_coord(&self, coord: Coord) -> bool {
@@ -73,6 +172,9 @@ impl World {
         }
     }
     pub fn get_opacity(&self, coord: Coord) -> u8 {
+        if self.is_smoke_at(coord) {
+            return 255;
+        }
         if let Some(&Layers {
             feature: Some(feature_entity),
             ..
@@ -87,6 +189,17 @@ impl World {
             0
         }
     }
+
+    pub fn is_poison_gas_at(&self, coord: Coord) -> bool {
+        self.poison_gas.iter().any(|puff| puff.coord == coord)
+    }
+
+    pub fn is_smoke_at(&self, coord: Coord) -> bool {
+        self.smoke_clouds
+            .iter()
+            .any(|cloud| cloud.coords.contains(&coord))
+    }
+
     pub fn character_at_coord(&self, coord: Coord) -> Option<Entity> {
         if let Some(layers) = self.spatial_table.layers_at(coord) {
             layers.character
@@ -130,6 +243,43 @@ impl World {
         None
     }
 
+    /// The direction of the first step along the shortest path an npc could walk to `target`,
+    /// going around other characters, or `None` if there's no such path
+    pub fn npc_first_step_towards(
+        &self,
+        npc_entity: Entity,
+        target: Coord,
+    ) -> Option<CardinalDirection> {
+        use std::collections::{HashMap, VecDeque};
+        let start = self.spatial_table.coord_of(npc_entity)?;
+        let mut first_steps = HashMap::new();
+        let mut queue = VecDeque::new();
+        first_steps.insert(start, None);
+        queue.push_back(start);
+        while let Some(coord) = queue.pop_front() {
+            let first_step = first_steps[&coord];
+            if coord == target {
+                return first_step;
+            }
+            for direction in CardinalDirection::all() {
+                let next = coord + direction.coord();
+                if first_steps.contains_key(&next)
+                    || !self.can_npc_traverse_feature_at_coord_with_entity(next, npc_entity)
+                    || self
+                        .spatial_table
+                        .layers_at_checked(next)
+                        .character
+                        .is_some()
+                {
+                    continue;
+                }
+                first_steps.insert(next, first_step.or(Some(direction)));
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
     pub fn nearest_characterless_coord(&self, start: Coord) -> Option<Coord> {
         use std::collections::{HashSet, VecDeque};
         if self
@@ -163,41 +313,36 @@ impl World {
         None
     }
 
-    pub fn nearest_non_poison_coord(&self, start: Coord) -> Option<Coord> {
-        use std::collections::{HashSet, VecDeque};
-        let layers = self.spatial_table.layers_at_checked(start);
-        if layers.feature.is_none() {
-            if let Some(e) = layers.floor {
-                if !self.components.floor_poison.contains(e) {
-                    return Some(start);
-                }
+    pub fn is_water_at(&self, coord: Coord) -> bool {
+        self.spatial_table
+            .layers_at(coord)
+            .and_then(|layers| layers.feature)
+            .map(|feature| self.components.water.contains(feature))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the entity is holding or carrying a heavy weapon
+    pub fn carries_heavy_item(&self, entity: Entity) -> bool {
+        let is_heavy = |item_entity: Entity| {
+            self.components
+                .item
+                .get(item_entity)
+                .map(|item| item.is_heavy())
+                .unwrap_or(false)
+        };
+        if let Some(hands) = self.components.hands.get(entity) {
+            if hands.left.holding().into_iter().any(is_heavy)
+                || hands.right.holding().into_iter().any(is_heavy)
+            {
+                return true;
             }
         }
-        let mut seen = HashSet::new();
-        seen.insert(start);
-        let mut queue = VecDeque::new();
-        queue.push_back(start);
-        let mut rng = StdRng::from_entropy();
-        while let Some(coord) = queue.pop_front() {
-            let mut dirs = CardinalDirection::all().collect::<Vec<_>>();
-            dirs.shuffle(&mut rng);
-            for d in dirs {
-                let coord = coord + d.coord();
-                if seen.insert(coord) {
-                    if let Some(layers) = self.spatial_table.layers_at(coord) {
-                        if layers.feature.is_none() {
-                            if let Some(e) = layers.floor {
-                                if !self.components.floor_poison.contains(e) {
-                                    return Some(coord);
-                                }
-                            }
-                            queue.push_back(coord);
-                        }
-                    }
-                }
+        if let Some(inventory) = self.components.inventory.get(entity) {
+            if inventory.items.iter().flatten().any(|&item| is_heavy(item)) {
+                return true;
             }
         }
-        None
+        false
     }
 
     pub fn num_player_claws(&self) -> usize {
@@ -254,7 +399,8 @@ impl World {
         let organs = self.components.organs.get(player_entity).unwrap();
         for organ in organs.organs() {
             if let Some(organ) = organ {
-                if organ.type_ == OrganType::CyberCore {
+                // a core knocked out by an EMP provides no power
+                if organ.type_ == OrganType::CyberCore && !organ.disabled {
                     return true;
                 }
             }
@@ -262,6 +408,37 @@ impl World {
         false
     }
 
+    pub fn player_has_cybernetic_lung(&self) -> bool {
+        let player_entity = self.components.player.entities().next().unwrap();
+        let organs = self.components.organs.get(player_entity).unwrap();
+        organs
+            .organs()
+            .iter()
+            .flatten()
+            .any(|organ| organ.type_ == OrganType::Lung && organ.cybernetic && !organ.disabled)
+    }
+
+    /// The strength of the radiation at a coordinate, combining radioactive entities and
+    /// radiation zones
+    pub fn radiation_intensity_at(&self, coord: Coord) -> u32 {
+        let mut intensity = 0;
+        for r_entity in self.components.radioactive.entities() {
+            if let Some(r_coord) = self.spatial_table.coord_of(r_entity) {
+                if let Some(distance) = self.line_distance_stopping_at_solid(coord, r_coord) {
+                    if distance < 5 {
+                        intensity += 2;
+                    } else if distance < 10 {
+                        intensity += 1;
+                    }
+                }
+            }
+        }
+        for zone in self.radiation_zones.iter() {
+            intensity += zone.intensity_at(coord);
+        }
+        intensity
+    }
+
     pub fn player_organs(&self) -> Vec<PlayerOrgan> {
         let mut ret = Vec::new();
         let player_entity = self.components.player.entities().next().unwrap();
@@ -281,6 +458,9 @@ impl World {
                 if organ.cybernetic && power == 0 {
                     active = false;
                 }
+                if organ.disabled {
+                    active = false;
+                }
                 ret.push(PlayerOrgan {
                     organ: *organ,
                     active,
@@ -340,6 +520,171 @@ impl World {
         }
         false
     }
+
+    pub fn is_ally(&self, entity: Entity) -> bool {
+        matches!(
+            self.components.npc.get(entity),
+            Some(Npc {
+                disposition: Disposition::Ally,
+                ..
+            })
+        )
+    }
+
+    /// The entity in `coord` which would stop a bullet moving into it. This mirrors the collision
+    /// checks made as projectiles move, so windows don't count as bullets smash through them.
+    pub fn bullet_obstruction(&self, coord: Coord) -> Option<Entity> {
+        let layers = self.spatial_table.layers_at(coord)?;
+        if let Some(feature) = layers.feature {
+            if self.components.window.contains(feature) {
+                return None;
+            }
+        }
+        layers.feature.or(layers.character).filter(|&entity| {
+            self.components.solid_for_particles.contains(entity)
+                || self.components.character.contains(entity)
+        })
+    }
+
+    /// The entity in `coord` which is tall enough to stop an arcing projectile
+    pub fn arcing_obstruction(&self, coord: Coord) -> Option<Entity> {
+        self.spatial_table
+            .layers_at(coord)?
+            .feature
+            .filter(|&entity| self.components.solid_for_particles.contains(entity))
+    }
+
+    pub fn is_hostile(&self, entity: Entity) -> bool {
+        matches!(
+            self.components.npc.get(entity),
+            Some(Npc {
+                disposition: Disposition::Hostile,
+                ..
+            })
+        )
+    }
+
+    /// Returns true if the two characters are on opposing sides of the fight between the player
+    /// (and their allies) and hostile npcs
+    pub fn are_enemies(&self, a: Entity, b: Entity) -> bool {
+        if (self.components.player.contains(a) && self.is_player_disguised_from(b))
+            || (self.components.player.contains(b) && self.is_player_disguised_from(a))
+        {
+            return false;
+        }
+        let a_friendly = self.components.player.contains(a) || self.is_ally(a);
+        let b_friendly = self.components.player.contains(b) || self.is_ally(b);
+        (a_friendly && self.is_hostile(b)) || (b_friendly && self.is_hostile(a))
+    }
+
+    /// A player with a full set of organs harvested from an npc's kind passes for one of them,
+    /// until they attack one
+    pub fn is_player_disguised_from(&self, npc: Entity) -> bool {
+        let npc_type = match self.components.npc_type.get(npc) {
+            Some(&npc_type) => npc_type,
+            None => return false,
+        };
+        let num_organs = match self.components.simple_organs.get(npc) {
+            Some(organs) if !organs.is_empty() => organs.len(),
+            _ => return false,
+        };
+        let player = match self.components.player.entities().next() {
+            Some(player) => player,
+            None => return false,
+        };
+        if self
+            .components
+            .blown_disguises
+            .get(player)
+            .is_some_and(|blown_disguises| blown_disguises.contains(&npc_type))
+        {
+            return false;
+        }
+        let num_donated = self
+            .active_player_organs()
+            .iter()
+            .filter(|organ| organ.donor == Some(npc_type))
+            .count();
+        num_donated >= num_organs
+    }
+
+    // Returns true if `viewer` has an unobstructed view of `coord` no more than the given distance
+    // away
+    fn has_clear_view(&self, viewer: Entity, coord: Coord, max_distance_squared: u32) -> bool {
+        let viewer_coord = match self.spatial_table.coord_of(viewer) {
+            Some(viewer_coord) => viewer_coord,
+            None => return false,
+        };
+        if (coord - viewer_coord).magnitude2() > max_distance_squared {
+            return false;
+        }
+        let mut opacity_sum = 0;
+        for line_coord in LineSegment::new(viewer_coord, coord).iter() {
+            opacity_sum += self.get_opacity(line_coord) as u32;
+            if opacity_sum >= 255 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns true if an alarm panel has an unobstructed view of the given coord
+    pub fn is_seen_by_alarm_panel(&self, coord: Coord) -> bool {
+        self.components.alarm_panel.entities().any(|panel_entity| {
+            self.has_clear_view(panel_entity, coord, ALARM_PANEL_VISION_DISTANCE_SQUARED)
+        })
+    }
+
+    /// Returns true if a vendor is close enough to the given coord, with nothing in the way, to
+    /// see what's going on there
+    pub fn is_seen_by_vendor(&self, vendor: Entity, coord: Coord) -> bool {
+        self.has_clear_view(vendor, coord, VENDOR_VISION_DISTANCE_SQUARED)
+    }
+
+    /// The true identity of an item, which may differ from how it appears if it hasn't been
+    /// identified
+    pub fn item_identity(&self, entity: Entity) -> Option<Item> {
+        self.components
+            .unidentified
+            .get(entity)
+            .or(self.components.item.get(entity))
+            .cloned()
+    }
+
+    /// The gun held by an npc, if any
+    pub fn npc_gun_entity(&self, entity: Entity) -> Option<Entity> {
+        self.components
+            .simple_inventory
+            .get(entity)?
+            .iter()
+            .cloned()
+            .find(|&item_entity| self.components.gun.contains(item_entity))
+    }
+
+    /// Allies close enough to the player to follow them between levels
+    pub fn player_followers(&self) -> Vec<Entity> {
+        let player_coord = match self
+            .components
+            .player
+            .entities()
+            .next()
+            .and_then(|player| self.spatial_table.coord_of(player))
+        {
+            Some(coord) => coord,
+            None => return Vec::new(),
+        };
+        self.components
+            .mercenary
+            .entities()
+            .filter(|&entity| self.is_ally(entity) && self.components.character.contains(entity))
+            .filter(|&entity| {
+                self.spatial_table
+                    .coord_of(entity)
+                    .map(|coord| coord.manhattan_distance(player_coord) <= 3)
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
 }
 
 pub struct PlayerOrgan {
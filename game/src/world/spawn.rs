@@ -1,16 +1,20 @@
 use crate::{
+    balance::PlayerBalance,
+    documents::Document,
+    loot,
     realtime::{self, flicker, movement, particle},
     world::{data::*, explosion, World},
-    Entity,
+    Background, Entity, Layers, NewGamePlus, Remnant,
 };
 use coord_2d::Coord;
-use direction::Direction;
+use direction::{CardinalDirection, Direction};
 use entity_table::entity_data;
 use rand::{seq::SliceRandom, Rng};
 use rgb_int::Rgb24;
+use std::time::Duration;
 use visible_area_detection::{vision_distance, Diminish, Light};
 
-fn player_starting_organs() -> Organs {
+fn player_starting_organs(background: Background) -> Organs {
     let mut ret = Organs::new(crate::MAX_ORGANS);
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Heart,
@@ -19,38 +23,70 @@ fn player_starting_organs() -> Organs {
             ..OrganTraits::none()
         },
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Lung,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Lung,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Stomach,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Liver,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::Appendix,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
+        donor: None,
         cybernetic: false,
     });
+    if background == Background::VampireInitiate {
+        *ret.first_free_slot().unwrap() = Some(Organ {
+            type_: OrganType::Heart,
+            traits: OrganTraits {
+                vampiric: true,
+                ..OrganTraits::none()
+            },
+            original: true,
+            harvested: false,
+            disabled: false,
+            donor: None,
+            cybernetic: false,
+        });
+    }
     /*
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::CronenbergPistol,
@@ -59,12 +95,16 @@ fn player_starting_organs() -> Organs {
             ..OrganTraits::none()
         },
         original: true,
+        harvested: false,
+        disabled: false,
         cybernetic: false,
     });
     *ret.first_free_slot().unwrap() = Some(Organ {
         type_: OrganType::CronenbergShotgun,
         traits: OrganTraits::none(),
         original: true,
+        harvested: false,
+        disabled: false,
         cybernetic: false,
     }); */
     ret
@@ -92,10 +132,177 @@ fn random_basic_organ<R: Rng>(rng: &mut R) -> Organ {
         traits,
         cybernetic: false,
         original: false,
+        harvested: false,
+        disabled: false,
+        donor: None,
+    }
+}
+
+fn sentinel_core() -> Organ {
+    Organ {
+        type_: OrganType::CyberCore,
+        traits: OrganTraits::none(),
+        cybernetic: true,
+        original: false,
+        harvested: false,
+        disabled: false,
+        donor: None,
+    }
+}
+
+// The organ trader deals in organs the clinics don't have: cybernetic versions of basic organs and
+// rarer organs, each with a trait people pay extra for
+fn black_market_organ<R: Rng>(rng: &mut R) -> Organ {
+    let type_ = *[
+        OrganType::Heart,
+        OrganType::Liver,
+        OrganType::Lung,
+        OrganType::Stomach,
+        OrganType::CronenbergPistol,
+        OrganType::CronenbergShotgun,
+        OrganType::Claw,
+        OrganType::CyberCore,
+    ]
+    .choose(rng)
+    .unwrap();
+    let mut traits = OrganTraits::none();
+    *traits.get_mut(
+        *[OrganTrait::Prolific, OrganTrait::Vampiric]
+            .choose(rng)
+            .unwrap(),
+    ) = true;
+    Organ {
+        type_,
+        cybernetic: matches!(
+            type_,
+            OrganType::Heart | OrganType::Liver | OrganType::Lung | OrganType::Stomach
+        ),
+        traits,
+        original: false,
+        harvested: false,
+        disabled: false,
+        donor: None,
+    }
+}
+
+fn organ_clinic_stock<R: Rng>(level: usize, rng: &mut R) -> Vec<Organ> {
+    let mut pool = vec![
+        OrganType::Heart,
+        OrganType::Liver,
+        OrganType::Lung,
+        OrganType::Stomach,
+    ];
+    if level > 0 {
+        pool.extend([
+            OrganType::CronenbergPistol,
+            OrganType::CronenbergShotgun,
+            OrganType::Claw,
+            OrganType::ReinforcedSkeleton,
+        ]);
+    }
+    // Levels deeper than the last tier are stocked like the last tier
+    let cybernetic_chance =
+        ORGAN_CLINIC_CYBERNETIC_CHANCE[level.min(ORGAN_CLINIC_CYBERNETIC_CHANCE.len() - 1)];
+    let mut simple_organs = Vec::new();
+    if level > 0 {
+        simple_organs.push(Organ {
+            type_: OrganType::CyberCore,
+            cybernetic: false,
+            traits: OrganTraits::none(),
+            original: false,
+            harvested: false,
+            disabled: false,
+            donor: None,
+        });
+    }
+    for _ in 0..6 {
+        let type_ = *pool.choose(rng).unwrap();
+        let cybernetic = rng.gen::<f64>() < cybernetic_chance;
+        simple_organs.push(Organ {
+            type_,
+            cybernetic,
+            traits: OrganTraits::none(),
+            original: false,
+            harvested: false,
+            disabled: false,
+            donor: None,
+        });
+    }
+    for _ in 0..3 {
+        let type_ = *pool.choose(rng).unwrap();
+        let cybernetic = rng.gen::<f64>() < cybernetic_chance;
+        let mut traits = OrganTraits::none();
+        let random_trait = traits.get_mut(OrganTrait::choose(rng));
+        *random_trait = true;
+        simple_organs.push(Organ {
+            type_,
+            cybernetic,
+            traits,
+            original: false,
+            harvested: false,
+            disabled: false,
+            donor: None,
+        });
     }
+    for _ in 0..3 {
+        let type_ = *pool.choose(rng).unwrap();
+        let cybernetic = rng.gen::<f64>() < cybernetic_chance;
+        let mut traits = OrganTraits::none();
+        let random_trait = traits.get_mut(OrganTrait::choose(rng));
+        *random_trait = true;
+        let random_trait = traits.get_mut(OrganTrait::choose(rng));
+        *random_trait = true;
+        simple_organs.push(Organ {
+            type_,
+            cybernetic,
+            traits,
+            original: false,
+            harvested: false,
+            disabled: false,
+            donor: None,
+        });
+    }
+    simple_organs
 }
 
-pub fn make_player() -> EntityData {
+const SHOP_STOCK_SIZE: usize = 8;
+/// Chance of each organ in an organ clinic being cybernetic, indexed by level
+const ORGAN_CLINIC_CYBERNETIC_CHANCE: [f64; 4] = [0.0, 0.2, 0.4, 0.8];
+const BLACK_MARKET_STOCK_SIZE: usize = 4;
+const MERCHANT_STOCK_SIZE: usize = 4;
+const HOT_ORGAN_PROBABILITY: f64 = 0.5;
+/// The withdraw menu labels each stashed item with a letter, so there can be at most 26
+const STASH_SIZE: usize = 26;
+pub const DRONE_VISION_DISTANCE_SQUARED: u32 = 36;
+const TURRET_AMMO: u32 = 20;
+/// The number of turns worn equipment protects its wearer for before it falls apart
+const GAS_MASK_DURABILITY: u32 = 60;
+const HAZARD_SUIT_DURABILITY: u32 = 100;
+
+/// The max health given by the two hearts the player usually starts with
+const PLAYER_STARTING_HEALTH: u32 = 20;
+
+pub fn make_player(
+    balance: &PlayerBalance,
+    background: Background,
+    new_game_plus: &NewGamePlus,
+) -> EntityData {
+    let mut organs = player_starting_organs(background);
+    for &organ in &new_game_plus.retained_organs {
+        if let Some(slot) = organs.first_free_slot() {
+            *slot = Some(Organ {
+                harvested: false,
+                disabled: false,
+                ..organ
+            });
+        }
+    }
+    let satiation = if background == Background::VampireInitiate {
+        // start well fed so the vampiric heart works from the first turn
+        Meter::new_full(balance.satiation)
+    } else {
+        Meter::new(0, balance.satiation)
+    };
     EntityData {
         player: Some(()),
         character: Some(()),
@@ -105,20 +312,26 @@ pub fn make_player() -> EntityData {
             vision_distance: vision_distance::Circle::new_squared(200),
             diminish: Diminish::default().with_height(10.).with_focus(1.0),
         }),
-        health: Some(Meter::new(20, 20)),
-        oxygen: Some(Meter::new(20, 20)),
-        food: Some(Meter::new(50, 50)),
-        poison: Some(Meter::new(0, 10)),
-        radiation: Some(Meter::new(0, 80)),
+        health: Some(Meter::new_full(
+            (PLAYER_STARTING_HEALTH * balance.health_percent) / 100,
+        )),
+        oxygen: Some(Meter::new_full(balance.oxygen)),
+        food: Some(Meter::new_full(balance.food)),
+        poison: Some(Meter::new(0, balance.poison)),
+        radiation: Some(Meter::new(0, balance.radiation)),
         inventory: Some(Inventory::new(16)),
-        satiation: Some(Meter::new(0, 20)),
+        satiation: Some(satiation),
+        temperature: Some(Meter::new(balance.temperature / 2, balance.temperature)),
         power: Some(Meter::new(0, 0)),
-        money: Some(0),
-        organs: Some(player_starting_organs()),
+        money: Some(background.starting_money() + new_game_plus.starting_money),
+        experience: Some(Experience::default()),
+        skills: Some(Skills::default()),
+        organs: Some(organs),
         hands: Some(Hands {
             left: Hand::Empty,
             right: Hand::Empty,
         }),
+        worn: Some(Worn::default()),
         ..Default::default()
     }
 }
@@ -131,6 +344,49 @@ impl World {
         entity
     }
 
+    /// Removes a character along with the items in its simple inventory, so it can be moved to
+    /// another level with `insert_character_with_inventory`
+    pub fn remove_character_with_inventory(
+        &mut self,
+        entity: Entity,
+    ) -> (EntityData, Vec<EntityData>) {
+        let simple_inventory = self
+            .components
+            .simple_inventory
+            .get(entity)
+            .cloned()
+            .unwrap_or_default();
+        let inventory_data = simple_inventory
+            .into_iter()
+            .map(|item_entity| self.remove_entity(item_entity))
+            .collect::<Vec<_>>();
+        (self.remove_entity(entity), inventory_data)
+    }
+
+    pub fn insert_character_with_inventory(
+        &mut self,
+        coord: Coord,
+        mut entity_data: EntityData,
+        inventory_data: Vec<EntityData>,
+    ) -> Entity {
+        let simple_inventory = inventory_data
+            .into_iter()
+            .map(|data| {
+                let item_entity = self.entity_allocator.alloc();
+                self.components.insert_entity_data(item_entity, data);
+                item_entity
+            })
+            .collect::<Vec<_>>();
+        entity_data.simple_inventory = Some(simple_inventory);
+        self.insert_entity_data(
+            Location {
+                layer: Some(Layer::Character),
+                coord,
+            },
+            entity_data,
+        )
+    }
+
     fn spawn_entity<L: Into<Location>>(&mut self, location: L, entity_data: EntityData) -> Entity {
         let entity = self.entity_allocator.alloc();
         let location @ Location { layer, coord } = location.into();
@@ -154,6 +410,19 @@ impl World {
         )
     }
 
+    pub fn spawn_window(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Window,
+                window: (),
+                solid: (),
+                solid_for_particles: (),
+                destructible: (),
+            },
+        )
+    }
+
     pub fn spawn_debris(&mut self, coord: Coord) -> Entity {
         self.spawn_entity(
             (coord, Layer::Feature),
@@ -279,6 +548,25 @@ impl World {
         )
     }
 
+    pub fn spawn_bridge(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Floor),
+            entity_data! {
+                tile: Tile::Bridge,
+            },
+        )
+    }
+
+    pub fn spawn_water(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Water,
+                water: (),
+            },
+        )
+    }
+
     pub fn spawn_alley(&mut self, coord: Coord) -> Entity {
         self.spawn_entity(
             (coord, Layer::Floor),
@@ -305,6 +593,7 @@ impl World {
                 solid: (),
                 solid_for_particles: (),
                 door_state: DoorState::Closed,
+                door_health: Meter::new_full(crate::DOOR_HEALTH),
                 opacity: 255,
                 destructible: (),
             },
@@ -317,6 +606,7 @@ impl World {
             entity_data! {
                 tile: Tile::StairsDown,
                 stairs_down: (),
+                needs_power: (),
                 light: Light {
                     colour: Rgb24::new(0, 255, 255),
                     vision_distance: vision_distance::Circle::new_squared(200),
@@ -332,6 +622,7 @@ impl World {
             entity_data! {
                 tile: Tile::StairsUp,
                 stairs_up: (),
+                needs_power: (),
                 light: Light {
                     colour: Rgb24::new(0, 255, 255),
                     vision_distance: vision_distance::Circle::new_squared(200),
@@ -358,6 +649,185 @@ impl World {
         )
     }
 
+    pub fn spawn_stash(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Stash,
+                solid: (),
+                stash: (),
+                inventory: Inventory::new(STASH_SIZE),
+            },
+        )
+    }
+
+    /// The body of an insured player, holding everything they were carrying when they died
+    pub fn spawn_player_remains(&mut self, coord: Coord, items: Vec<Entity>) -> Entity {
+        let coord = self.nearest_itemless_coord(coord).unwrap_or(coord);
+        self.spawn_entity(
+            (coord, Layer::Item),
+            entity_data! {
+                tile: Tile::DeadPlayer,
+                player_remains: (),
+                simple_inventory: items,
+            },
+        )
+    }
+
+    /// What's left of a character who died on this level in a previous game
+    pub fn spawn_remnant(&mut self, remnant: &Remnant) -> Entity {
+        let items = remnant
+            .items
+            .iter()
+            .map(|&item| self.spawn_item_no_coord(item))
+            .collect();
+        self.spawn_player_remains(remnant.coord, items)
+    }
+
+    pub fn spawn_terminal(&mut self, coord: Coord, document: Document) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Terminal,
+                solid: (),
+                terminal: document,
+                destructible: (),
+            },
+        )
+    }
+
+    pub fn spawn_alarm_panel(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::AlarmPanel,
+                solid: (),
+                alarm_panel: (),
+                destructible: (),
+            },
+        )
+    }
+
+    pub fn spawn_generator(&mut self, coord: Coord, out_of_fuel: bool) -> Entity {
+        let entity = self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Generator,
+                solid: (),
+                generator: (),
+                destructible: (),
+            },
+        );
+        if out_of_fuel {
+            self.components.out_of_fuel.insert(entity, ());
+        }
+        entity
+    }
+
+    pub fn spawn_prop(&mut self, coord: Coord, prop: Prop) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::Prop(prop),
+                solid: (),
+            },
+        )
+    }
+
+    /// Draws a prop such as a bloodstain onto the floor at a coordinate. Hazardous floors are left
+    /// alone so they remain recognisable. Returns whether the prop was drawn.
+    pub fn decorate_floor(&mut self, coord: Coord, prop: Prop) -> bool {
+        if let Some(Layers {
+            floor: Some(floor_entity),
+            feature: None,
+            ..
+        }) = self.spatial_table.layers_at(coord)
+        {
+            if !self.components.floor_hazard.contains(*floor_entity) {
+                self.components.tile.insert(*floor_entity, Tile::Prop(prop));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Covers randomly chosen walls which can be seen from the floor in graffiti
+    pub fn spray_graffiti<R: Rng>(&mut self, count: usize, rng: &mut R) {
+        let mut walls = self
+            .components
+            .tile
+            .iter()
+            .filter(|&(_, &tile)| tile == Tile::Wall)
+            .filter_map(|(entity, _)| {
+                let coord = self.spatial_table.coord_of(entity)?;
+                let faces_floor = CardinalDirection::all().any(|direction| {
+                    matches!(
+                        self.spatial_table.layers_at(coord + direction.coord()),
+                        Some(Layers {
+                            floor: Some(_),
+                            feature: None,
+                            ..
+                        })
+                    )
+                });
+                if faces_floor {
+                    Some(entity)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        walls.shuffle(rng);
+        for entity in walls.into_iter().take(count) {
+            self.components
+                .tile
+                .insert(entity, Tile::Prop(Prop::Graffiti));
+        }
+    }
+
+    pub fn spawn_explosive_barrel(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Feature),
+            entity_data! {
+                tile: Tile::ExplosiveBarrel,
+                solid: (),
+                explosive: (),
+            },
+        )
+    }
+
+    pub fn spawn_ceiling_light(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            Location { coord, layer: None },
+            entity_data! {
+                needs_power: (),
+                light: Light {
+                    colour: Rgb24::new_grey(120),
+                    vision_distance: vision_distance::Circle::new_squared(60),
+                    diminish: Diminish::default().with_height(10.),
+                },
+            },
+        )
+    }
+
+    pub fn spawn_drone(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Drone,
+                character: (),
+                drone: (),
+                robotic: (),
+                health: Meter::new_full(3),
+                light: Light {
+                    colour: Rgb24::new(100, 100, 150),
+                    vision_distance: vision_distance::Circle::new_squared(DRONE_VISION_DISTANCE_SQUARED),
+                    diminish: Diminish::default().with_height(10.).with_focus(1.0),
+                },
+            },
+        )
+    }
+
     pub fn spawn_bullet<R: Rng>(
         &mut self,
         start: Coord,
@@ -393,6 +863,7 @@ impl World {
                     path: target - start,
                     cardinal_step_duration: Duration::from_millis(24),
                     repeat: Repeat::Once,
+                    flight_height: FlightHeight::Level,
                 }
             }
             .build(),
@@ -465,6 +936,7 @@ impl World {
                     path: target - start,
                     cardinal_step_duration: Duration::from_millis(24),
                     repeat: Repeat::Once,
+                    flight_height: FlightHeight::Level,
                 }
             }
             .build(),
@@ -507,9 +979,15 @@ impl World {
         );
         self.components.tile.insert(entity, Tile::Bullet);
         self.components.particle.insert(entity, ());
-        self.components
-            .projectile_damage
-            .insert(entity, ProjectileDamage { hit_points: 5..=10 });
+        self.components.projectile_damage.insert(
+            entity,
+            ProjectileDamage {
+                hit_points: 5..=10,
+                bleeding_probability: 0.0,
+                ricochet: false,
+                penetration: 0,
+            },
+        );
         self.components.on_collision.insert(
             entity,
             OnCollision::Explode({
@@ -538,6 +1016,110 @@ impl World {
         entity
     }
 
+    pub fn spawn_emp_grenade(&mut self, start: Coord, target: Coord, range: u32) -> Entity {
+        self.spawn_grenade(start, target, Item::EmpGrenade, OnCollision::Emp { range })
+    }
+
+    pub fn spawn_smoke_grenade(&mut self, start: Coord, target: Coord, radius: u32) -> Entity {
+        self.spawn_grenade(
+            start,
+            target,
+            Item::SmokeGrenade,
+            OnCollision::Smoke { radius },
+        )
+    }
+
+    fn spawn_grenade(
+        &mut self,
+        start: Coord,
+        target: Coord,
+        item: Item,
+        on_collision: OnCollision,
+    ) -> Entity {
+        let entity = self.entity_allocator.alloc();
+        self.spatial_table
+            .update(
+                entity,
+                Location {
+                    coord: start,
+                    layer: None,
+                },
+            )
+            .unwrap();
+        self.components.realtime.insert(entity, ());
+        self.components.blocks_gameplay.insert(entity, ());
+        self.realtime_components.movement.insert(
+            entity,
+            {
+                use movement::spec::*;
+                Movement {
+                    path: target - start,
+                    cardinal_step_duration: Duration::from_millis(48),
+                    repeat: Repeat::Once,
+                    flight_height: FlightHeight::Arcing,
+                }
+            }
+            .build(),
+        );
+        self.components.collides_with.insert(
+            entity,
+            CollidesWith {
+                solid: true,
+                character: true,
+            },
+        );
+        self.components.tile.insert(entity, Tile::Item(item));
+        self.components.on_collision.insert(entity, on_collision);
+        entity
+    }
+
+    pub fn spawn_emp_emitter<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        let duration = Duration::from_millis(150);
+        let emitter_entity = self.entity_allocator.alloc();
+        self.spatial_table
+            .update(emitter_entity, Location { coord, layer: None })
+            .unwrap();
+        self.realtime_components
+            .fade
+            .insert(emitter_entity, realtime::fade::FadeState::new(duration));
+        self.components.realtime.insert(emitter_entity, ());
+        self.realtime_components
+            .particle_emitter
+            .insert(emitter_entity, {
+                use realtime::particle::spec::*;
+                ParticleEmitter {
+                    emit_particle_every_period: realtime::period_per_frame(20),
+                    fade_out_duration: Some(duration),
+                    particle: Particle {
+                        tile: None,
+                        movement: Some(Movement {
+                            angle_range: Radians::uniform_range_all(),
+                            cardinal_period_range: UniformInclusiveRange {
+                                low: Duration::from_millis(20),
+                                high: Duration::from_millis(60),
+                            },
+                        }),
+                        fade_duration: Some(Duration::from_millis(300)),
+                        colour_hint: Some(UniformInclusiveRange {
+                            low: Rgb24::new(63, 127, 255).to_rgba32(255),
+                            high: Rgb24::new(191, 223, 255).to_rgba32(255),
+                        }),
+                        ..Default::default()
+                    },
+                }
+                .build(rng)
+            });
+        self.components.light.insert(
+            emitter_entity,
+            Light {
+                colour: Rgb24::new(63, 127, 255),
+                vision_distance: vision_distance::Circle::new_squared(200),
+                diminish: Diminish::default().with_height(10.),
+            },
+        );
+        emitter_entity
+    }
+
     pub fn spawn_explosion_emitter<R: Rng>(
         &mut self,
         coord: Coord,
@@ -644,6 +1226,13 @@ impl World {
         self.spawn_entity((coord, Layer::Item), data)
     }
 
+    /// Spawn an item which appears to be a regular item until it is identified
+    pub fn spawn_unidentified_item(&mut self, coord: Coord, item: Item) -> Entity {
+        let entity = self.spawn_item(coord, item.disguise());
+        self.components.unidentified.insert(entity, item);
+        entity
+    }
+
     pub fn spawn_item_no_coord(&mut self, item: Item) -> Entity {
         let mut data = entity_data! {
             tile: Tile::Item(item),
@@ -656,6 +1245,24 @@ impl World {
         entity
     }
 
+    pub fn spawn_enemy<R: Rng>(&mut self, npc_type: NpcType, coord: Coord, rng: &mut R) -> Entity {
+        match npc_type {
+            NpcType::Zombie => self.spawn_zombie(coord, rng),
+            NpcType::Climber => self.spawn_climber(coord, rng),
+            NpcType::Trespasser => self.spawn_trespasser(coord, rng),
+            NpcType::Snatcher => self.spawn_snatcher(coord, rng),
+            NpcType::Boomer => self.spawn_boomer(coord, rng),
+            NpcType::Poisoner => self.spawn_poisoner(coord, rng),
+            NpcType::Divider => self.spawn_divider(coord, rng),
+            NpcType::Glower => self.spawn_glower(coord, rng),
+            NpcType::Venter => self.spawn_venter(coord, rng),
+            NpcType::Corruptor => self.spawn_corruptor(coord, rng),
+            NpcType::Guard => self.spawn_guard(coord, rng),
+            NpcType::Sentinel => self.spawn_sentinel(coord),
+            other => panic!("{:?} is not an enemy", other),
+        }
+    }
+
     pub fn spawn_zombie<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
         self.spawn_entity(
             (coord, Layer::Character),
@@ -672,12 +1279,16 @@ impl World {
                 npc_type: NpcType::Zombie,
                 health: Meter::new_full(4),
                 resurrects_in: Meter::new_full(10),
+                tracks_scent: (),
                 bump_damage: 1..=3,
                 simple_organs: vec![
                     Organ {
                         type_: OrganType::Heart,
                         traits: random_organ_traits(rng),
                         original: false,
+                        harvested: false,
+                        disabled: false,
+                        donor: None,
                         cybernetic: false,
                     },
                     random_basic_organ(rng),
@@ -701,6 +1312,7 @@ impl World {
                 character: (),
                 npc_type: NpcType::Climber,
                 health: Meter::new_full(3),
+                tracks_scent: (),
                 bump_damage: 1..=3,
                 simple_organs: vec![
                     random_basic_organ(rng),
@@ -725,6 +1337,115 @@ impl World {
                 npc_type: NpcType::Trespasser,
                 health: Meter::new_full(3),
                 bump_damage: 1..=3,
+                simple_inventory: Vec::new(),
+                scavenger: (),
+                simple_organs: vec![
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                ],
+            },
+        )
+    }
+
+    pub fn spawn_guard<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Guard,
+                npc: Npc { disposition: Disposition::Hostile,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Guard,
+                health: Meter::new_full(8),
+                bump_damage: 2..=4,
+                simple_inventory: Vec::new(),
+                scavenger: (),
+                simple_organs: vec![
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                ],
+            },
+        )
+    }
+
+    pub fn spawn_bounty_hunter<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        let simple_inventory = [
+            Item::Shotgun,
+            Item::ShotgunAmmo,
+            Item::ShotgunAmmo,
+            Item::Stimpack,
+            Item::Stimpack,
+        ]
+        .into_iter()
+        .map(|item| self.spawn_item_no_coord(item))
+        .collect::<Vec<_>>();
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::BountyHunter,
+                npc: Npc { disposition: Disposition::Hostile,
+                    movement: NpcMovement {
+                        can_traverse_difficult: true,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::BountyHunter,
+                health: Meter::new_full(40),
+                bump_damage: 3..=6,
+                simple_inventory,
+                scavenger: (),
+                hunts_player: (),
+                simple_organs: vec![
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                ],
+            },
+        )
+    }
+
+    pub fn spawn_sentinel(&mut self, coord: Coord) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Sentinel,
+                npc: Npc { disposition: Disposition::Hostile,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Sentinel,
+                health: Meter::new_full(12),
+                bump_damage: 1..=3,
+                robotic: (),
+                emp_on_hit: (),
+                slow: 2,
+                simple_organs: vec![sentinel_core()],
+            },
+        )
+    }
+
+    pub fn spawn_civilian<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Civilian,
+                npc: Npc { disposition: Disposition::Afraid,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Civilian,
+                health: Meter::new_full(3),
                 simple_organs: vec![
                     random_basic_organ(rng),
                     random_basic_organ(rng),
@@ -824,6 +1545,7 @@ impl World {
                 health: Meter::new_full(16),
                 bump_damage: 1..=2,
                 split_on_damage: (),
+                hazard_on_death: FloorHazard::Acid,
                 simple_organs: vec![
                     random_basic_organ(rng),
                     random_basic_organ(rng),
@@ -937,6 +1659,9 @@ impl World {
                         type_: OrganType::CorruptedHeart,
                         cybernetic: false,
                         original: false,
+                        harvested: false,
+                        disabled: false,
+                        donor: None,
                         traits: OrganTraits {
                             ..OrganTraits::none()
                         }
@@ -978,21 +1703,54 @@ impl World {
         entity
     }
 
-    pub fn spawn_gun_store<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
-        let item_pool = vec![
-            Item::Pistol,
-            Item::PistolAmmo,
-            Item::Shotgun,
-            Item::ShotgunAmmo,
-            Item::RocketLauncher,
-            Item::Rocket,
-        ];
-        let mut simple_inventory = Vec::new();
-        for _ in 0..8 {
-            let item = *item_pool.choose(rng).unwrap();
-            let entity = self.spawn_item_no_coord(item);
-            simple_inventory.push(entity);
-        }
+    pub fn spawn_mercenary<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Mercenary,
+                npc: Npc { disposition: Disposition::Neutral,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Mercenary,
+                health: Meter::new_full(20),
+                bump_damage: 2..=4,
+                simple_organs: vec![
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                ],
+                simple_inventory: Vec::new(),
+                mercenary: (),
+            },
+        )
+    }
+
+    pub fn spawn_turret(&mut self, coord: Coord, turret_ammo: Meter) -> Entity {
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::Turret,
+                npc: Npc { disposition: Disposition::Ally,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: false,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Turret,
+                health: Meter::new_full(10),
+                robotic: (),
+                turret_ammo,
+                hazard_on_death: FloorHazard::Electrified,
+            },
+        )
+    }
+
+    pub fn spawn_gun_store<R: Rng>(&mut self, coord: Coord, level: usize, rng: &mut R) -> Entity {
+        let simple_inventory = self.shop_stock(NpcType::GunStore, level, rng);
         self.spawn_entity(
             (coord, Layer::Character),
             entity_data! {
@@ -1019,22 +1777,8 @@ impl World {
         )
     }
 
-    pub fn spawn_item_store<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
-        let item_pool = vec![
-            Item::Stimpack,
-            Item::Antidote,
-            Item::BloodVialEmpty,
-            Item::Battery,
-            Item::Food,
-            Item::AntiRads,
-            Item::OrganContainer(None),
-        ];
-        let mut simple_inventory = Vec::new();
-        for _ in 0..8 {
-            let item = *item_pool.choose(rng).unwrap();
-            let entity = self.spawn_item_no_coord(item);
-            simple_inventory.push(entity);
-        }
+    pub fn spawn_item_store<R: Rng>(&mut self, coord: Coord, level: usize, rng: &mut R) -> Entity {
+        let simple_inventory = self.shop_stock(NpcType::ItemStore, level, rng);
         self.spawn_entity(
             (coord, Layer::Character),
             entity_data! {
@@ -1067,96 +1811,7 @@ impl World {
         level: usize,
         rng: &mut R,
     ) -> Entity {
-        let pool = match level {
-            0 => vec![
-                OrganType::Heart,
-                OrganType::Liver,
-                OrganType::Lung,
-                OrganType::Stomach,
-            ],
-            1 => vec![
-                OrganType::Heart,
-                OrganType::Liver,
-                OrganType::Lung,
-                OrganType::Stomach,
-                OrganType::CronenbergPistol,
-                OrganType::CronenbergShotgun,
-                OrganType::Claw,
-            ],
-            2 => vec![
-                OrganType::Heart,
-                OrganType::Liver,
-                OrganType::Lung,
-                OrganType::Stomach,
-                OrganType::CronenbergPistol,
-                OrganType::CronenbergShotgun,
-                OrganType::Claw,
-            ],
-            3 => vec![
-                OrganType::Heart,
-                OrganType::Liver,
-                OrganType::Lung,
-                OrganType::Stomach,
-                OrganType::CronenbergPistol,
-                OrganType::CronenbergShotgun,
-                OrganType::Claw,
-            ],
-            _ => panic!(),
-        };
-        let cybernetic_chance = match level {
-            0 => 0.0,
-            1 => 0.2,
-            2 => 0.4,
-            3 => 0.8,
-            _ => panic!(),
-        };
-        let mut simple_organs = Vec::new();
-        if level > 0 {
-            simple_organs.push(Organ {
-                type_: OrganType::CyberCore,
-                cybernetic: false,
-                traits: OrganTraits::none(),
-                original: false,
-            });
-        }
-        for _ in 0..6 {
-            let type_ = *pool.choose(rng).unwrap();
-            let cybernetic = rng.gen::<f64>() < cybernetic_chance;
-            simple_organs.push(Organ {
-                type_,
-                cybernetic,
-                traits: OrganTraits::none(),
-                original: false,
-            });
-        }
-        for _ in 0..3 {
-            let type_ = *pool.choose(rng).unwrap();
-            let cybernetic = rng.gen::<f64>() < cybernetic_chance;
-            let mut traits = OrganTraits::none();
-            let random_trait = traits.get_mut(OrganTrait::choose(rng));
-            *random_trait = true;
-            simple_organs.push(Organ {
-                type_,
-                cybernetic,
-                traits,
-                original: false,
-            });
-        }
-        for _ in 0..3 {
-            let type_ = *pool.choose(rng).unwrap();
-            let cybernetic = rng.gen::<f64>() < cybernetic_chance;
-            let mut traits = OrganTraits::none();
-            let random_trait = traits.get_mut(OrganTrait::choose(rng));
-            *random_trait = true;
-            let random_trait = traits.get_mut(OrganTrait::choose(rng));
-            *random_trait = true;
-            simple_organs.push(Organ {
-                type_,
-                cybernetic,
-                traits,
-                original: false,
-            });
-        }
+        let simple_organs = organ_clinic_stock(level, rng);
         self.spawn_entity(
             (coord, Layer::Character),
             entity_data! {
@@ -1180,11 +1835,135 @@ impl World {
         )
     }
 
-    pub fn _spawn_organ_trader<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+    pub fn spawn_merchant<R: Rng>(&mut self, coord: Coord, level: usize, rng: &mut R) -> Entity {
+        let simple_inventory = (0..MERCHANT_STOCK_SIZE)
+            .map(|_| {
+                self.spawn_item_no_coord(loot::choose_shop_item(NpcType::Merchant, level, rng))
+            })
+            .collect::<Vec<_>>();
         self.spawn_entity(
             (coord, Layer::Character),
             entity_data! {
-                tile: Tile::OrganClinic,
+                tile: Tile::Merchant,
+                npc: Npc { disposition: Disposition::Neutral,
+                    movement: NpcMovement {
+                        can_traverse_difficult: false,
+                        can_open_doors: true,
+                    },
+                },
+                character: (),
+                npc_type: NpcType::Merchant,
+                health: Meter::new_full(50),
+                bump_damage: 10..=20,
+                simple_organs: vec![
+                    random_basic_organ(rng),
+                    random_basic_organ(rng),
+                ],
+                simple_inventory,
+                shop: Shop {
+                    message: "Rare goods from all over the city! I don't stay in one place for long, so buy while you can.".to_string(),
+                }
+            },
+        )
+    }
+
+    fn shop_stock<R: Rng>(&mut self, shop: NpcType, level: usize, rng: &mut R) -> Vec<Entity> {
+        let mut simple_inventory = Vec::new();
+        for _ in 0..SHOP_STOCK_SIZE {
+            let item = loot::choose_shop_item(shop, level, rng);
+            let entity = self.spawn_item_no_coord(item);
+            simple_inventory.push(entity);
+        }
+        simple_inventory
+    }
+
+    fn black_market_stock<R: Rng>(&mut self, rng: &mut R) -> Vec<Entity> {
+        (0..BLACK_MARKET_STOCK_SIZE)
+            .map(|_| {
+                let organ = black_market_organ(rng);
+                let entity = self.spawn_item_no_coord(Item::OrganContainer(Some(organ)));
+                if rng.gen_bool(HOT_ORGAN_PROBABILITY) {
+                    self.components.hot.insert(entity, ());
+                }
+                entity
+            })
+            .collect()
+    }
+
+    /// Put an item from a vendor's range on display, where the player can take it without paying
+    /// if the vendor isn't looking
+    pub fn spawn_item_for_sale<R: Rng>(
+        &mut self,
+        coord: Coord,
+        vendor: Entity,
+        level: usize,
+        rng: &mut R,
+    ) -> Option<Entity> {
+        let shop = match self.components.npc_type.get(vendor) {
+            Some(&shop @ (NpcType::GunStore | NpcType::ItemStore)) => shop,
+            _ => return None,
+        };
+        let item = loot::choose_shop_item(shop, level, rng);
+        let entity = self.spawn_item(coord, item);
+        self.components.for_sale.insert(entity, vendor);
+        Some(entity)
+    }
+
+    /// Rotate the stock of each shop on the level. About half the existing stock is replaced,
+    /// and the shop is topped up, with new stock drawn from the loot tables for the given level.
+    pub fn restock_shops<R: Rng>(&mut self, level: usize, rng: &mut R) {
+        let shop_entities = self.components.shop.entities().collect::<Vec<_>>();
+        for shop_entity in shop_entities {
+            if self.components.organ_clinic.contains(shop_entity) {
+                self.components
+                    .simple_organs
+                    .insert(shop_entity, organ_clinic_stock(level, rng));
+                continue;
+            }
+            if self.components.npc_type.get(shop_entity) == Some(&NpcType::OrganTrader) {
+                let old_stock = self
+                    .components
+                    .simple_inventory
+                    .get(shop_entity)
+                    .cloned()
+                    .unwrap_or_default();
+                for entity in old_stock {
+                    self.remove_entity(entity);
+                }
+                let stock = self.black_market_stock(rng);
+                self.components.simple_inventory.insert(shop_entity, stock);
+                continue;
+            }
+            let shop = match self.components.npc_type.get(shop_entity) {
+                Some(&shop @ (NpcType::GunStore | NpcType::ItemStore)) => shop,
+                _ => continue,
+            };
+            let mut simple_inventory = self
+                .components
+                .simple_inventory
+                .get(shop_entity)
+                .cloned()
+                .unwrap_or_default();
+            simple_inventory.shuffle(rng);
+            for entity in simple_inventory.split_off(simple_inventory.len() / 2) {
+                self.remove_entity(entity);
+            }
+            while simple_inventory.len() < SHOP_STOCK_SIZE {
+                let item = loot::choose_shop_item(shop, level, rng);
+                simple_inventory.push(self.spawn_item_no_coord(item));
+            }
+            self.components
+                .simple_inventory
+                .insert(shop_entity, simple_inventory);
+        }
+    }
+
+    pub fn spawn_organ_trader<R: Rng>(&mut self, coord: Coord, rng: &mut R) -> Entity {
+        let simple_inventory = self.black_market_stock(rng);
+        self.spawn_entity(
+            (coord, Layer::Character),
+            entity_data! {
+                tile: Tile::OrganTrader,
                 npc: Npc { disposition: Disposition::Neutral,
                     movement: NpcMovement {
                         can_traverse_difficult: false,
@@ -1192,13 +1971,17 @@ impl World {
                     },
                 },
                 character: (),
-                npc_type: NpcType::OrganClinic,
+                npc_type: NpcType::OrganTrader,
                 health: Meter::new_full(50),
                 bump_damage: 5..=10,
                 simple_organs: vec![
                     random_basic_organ(rng),
                     random_basic_organ(rng),
                 ],
+                simple_inventory,
+                shop: Shop {
+                    message: "Got any organs for sale? I'll take anything you've got in a container, no questions asked. I've got some merchandise of my own you won't find at the clinic, too. The cheap stuff is hot, so no refunds.".to_string(),
+                }
             },
         )
     }
@@ -1206,9 +1989,16 @@ impl World {
 
 fn make_gun(data: &mut EntityData) {
     match data.item {
-        Some(Item::Pistol) => data.gun = Some(Gun::pistol()),
+        Some(Item::Pistol | Item::CursedPistol) => data.gun = Some(Gun::pistol()),
         Some(Item::Shotgun) => data.gun = Some(Gun::shotgun()),
         Some(Item::RocketLauncher) => data.gun = Some(Gun::rocket_launcher()),
+        Some(Item::SniperRifle) => data.gun = Some(Gun::sniper_rifle()),
+        Some(Item::Turret) => data.turret_ammo = Some(Meter::new_full(TURRET_AMMO)),
+        Some(Item::GasMask) => data.durability = Some(Meter::new_full(GAS_MASK_DURABILITY)),
+        Some(Item::HazardSuit) => data.durability = Some(Meter::new_full(HAZARD_SUIT_DURABILITY)),
         _ => (),
     }
+    if let Some(turns) = data.item.and_then(|item| item.turns_until_spoiled()) {
+        data.freshness = Some(Meter::new_full(turns));
+    }
 }
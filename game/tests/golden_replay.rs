@@ -0,0 +1,49 @@
+//! Replays recorded games against the seeds they were recorded with, and checks that each ends
+//! in the same state as when it was recorded. A failure means a change has affected how games
+//! play out, e.g. by drawing random numbers in a different order. If the change was intentional,
+//! record new replays with:
+//!
+//! UPDATE_GOLDEN=1 cargo test -p game --test golden_replay
+use game::{
+    headless::{GreedyBot, Headless, Replay},
+    Config,
+};
+use std::{fs, path::PathBuf};
+
+const RNG_SEEDS: &[u64] = &[0, 1, 2];
+const NUM_STEPS: usize = 200;
+
+fn replay_path(rng_seed: u64) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("seed-{}.json", rng_seed))
+}
+
+fn record(rng_seed: u64) -> Replay {
+    let mut headless = Headless::new(Config::default(), rng_seed);
+    GreedyBot::new(rng_seed).play(&mut headless, NUM_STEPS);
+    headless.replay()
+}
+
+#[test]
+fn golden_replays() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    for &rng_seed in RNG_SEEDS {
+        let path = replay_path(rng_seed);
+        if update {
+            let replay = record(rng_seed);
+            fs::write(&path, serde_json::to_string(&replay).unwrap()).unwrap();
+            continue;
+        }
+        let contents = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("couldn't read {}: {}", path.display(), e));
+        let replay: Replay = serde_json::from_str(&contents).unwrap();
+        assert_eq!(
+            replay.play(Config::default()),
+            replay.state_hash,
+            "replay of seed {} ended in a different state",
+            rng_seed
+        );
+    }
+}
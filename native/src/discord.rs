@@ -0,0 +1,150 @@
+use app::presence::{Presence, PresenceClient};
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Discord ignores activity updates made more often than this
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+// Discord checks a handful of numbered sockets in case several copies of it are running
+const MAX_PIPE_INDEX: u32 = 10;
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+trait Pipe: Read + Write + Send {}
+impl<T: Read + Write + Send> Pipe for T {}
+
+#[cfg(unix)]
+fn open_pipe(index: u32) -> io::Result<Box<dyn Pipe>> {
+    use std::os::unix::net::UnixStream;
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .unwrap_or_else(|| "/tmp".to_string());
+    let path = std::path::Path::new(&dir).join(format!("discord-ipc-{}", index));
+    Ok(Box::new(UnixStream::connect(path)?))
+}
+
+#[cfg(windows)]
+fn open_pipe(index: u32) -> io::Result<Box<dyn Pipe>> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(format!(r"\\.\pipe\discord-ipc-{}", index))?;
+    Ok(Box::new(file))
+}
+
+// Messages in both directions are a little-endian opcode and length followed by a json payload
+fn write_message(pipe: &mut dyn Pipe, op: u32, payload: &serde_json::Value) -> io::Result<()> {
+    let payload = payload.to_string();
+    let mut message = Vec::with_capacity(8 + payload.len());
+    message.extend_from_slice(&op.to_le_bytes());
+    message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    pipe.write_all(&message)
+}
+
+fn read_message(pipe: &mut dyn Pipe) -> io::Result<(u32, String)> {
+    let mut header = [0; 8];
+    pipe.read_exact(&mut header)?;
+    let op = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let mut payload = vec![0; len as usize];
+    pipe.read_exact(&mut payload)?;
+    Ok((op, String::from_utf8_lossy(&payload).into_owned()))
+}
+
+fn connect(app_id: &str) -> io::Result<Box<dyn Pipe>> {
+    let mut error = io::Error::from(io::ErrorKind::NotFound);
+    for index in 0..MAX_PIPE_INDEX {
+        match open_pipe(index) {
+            Ok(mut pipe) => {
+                let handshake = serde_json::json!({ "v": 1, "client_id": app_id });
+                write_message(pipe.as_mut(), OP_HANDSHAKE, &handshake)?;
+                let (op, payload) = read_message(pipe.as_mut())?;
+                if op != OP_FRAME {
+                    return Err(io::Error::other(format!("handshake rejected: {}", payload)));
+                }
+                return Ok(pipe);
+            }
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+fn set_activity(pipe: &mut dyn Pipe, presence: &Presence, start: u64) -> io::Result<()> {
+    let mut activity = serde_json::json!({
+        "details": presence.details(),
+        "timestamps": { "start": start },
+    });
+    if let Some(state) = presence.state() {
+        activity["state"] = state.into();
+    }
+    let command = serde_json::json!({
+        "cmd": "SET_ACTIVITY",
+        "args": { "pid": std::process::id(), "activity": activity },
+        "nonce": start.to_string(),
+    });
+    write_message(pipe, OP_FRAME, &command)?;
+    // Discord replies to every command, and stops reading if the replies aren't read
+    read_message(pipe)?;
+    Ok(())
+}
+
+// Sends the most recent presence to Discord at most once per `MIN_UPDATE_INTERVAL`, connecting
+// (or reconnecting if Discord is restarted) as necessary
+fn run(app_id: String, receiver: Receiver<Presence>) {
+    let start = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let mut connection: Option<Box<dyn Pipe>> = None;
+    while let Ok(mut presence) = receiver.recv() {
+        while let Ok(newer) = receiver.try_recv() {
+            presence = newer;
+        }
+        if connection.is_none() {
+            connection = connect(&app_id)
+                .map_err(|e| log::info!("couldn't connect to discord: {}", e))
+                .ok();
+        }
+        if let Some(pipe) = connection.as_mut() {
+            if let Err(e) = set_activity(pipe.as_mut(), &presence, start) {
+                log::info!("lost connection to discord: {}", e);
+                connection = None;
+            }
+        }
+        thread::sleep(MIN_UPDATE_INTERVAL);
+    }
+}
+
+/// Shows what the player is doing on their Discord profile. Updates are sent to the Discord
+/// client on its own thread, so the game is unaffected if Discord isn't running.
+pub struct DiscordPresence {
+    sender: Sender<Presence>,
+    previous: Option<Presence>,
+}
+
+impl DiscordPresence {
+    pub fn new(app_id: &str) -> Self {
+        let app_id = app_id.to_string();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run(app_id, receiver));
+        Self {
+            sender,
+            previous: None,
+        }
+    }
+}
+
+impl PresenceClient for DiscordPresence {
+    fn update(&mut self, presence: &Presence) {
+        if self.previous.as_ref() != Some(presence) {
+            let _ = self.sender.send(*presence);
+            self.previous = Some(*presence);
+        }
+    }
+}
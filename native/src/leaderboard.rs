@@ -0,0 +1,151 @@
+use app::leaderboard::{entries_from_json, LeaderboardClient, RunSummary};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Responses longer than this are rejected rather than read into memory
+const MAX_RESPONSE_BYTES: u64 = 1 << 20;
+
+#[derive(Clone)]
+struct Url {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Result<Self, String> {
+        if url.starts_with("https://") {
+            return Err(format!(
+                "https isn't supported, so the leaderboard must be served over http://: {}",
+                url
+            ));
+        }
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("only http:// urls are supported: {}", url))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        // ipv6 addresses are written in brackets since they contain colons
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => match bracketed.split_once(']') {
+                Some((host, "")) => (host, None),
+                Some((host, port)) => match port.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return Err(format!("invalid host in url: {}", url)),
+                },
+                None => return Err(format!("invalid host in url: {}", url)),
+            },
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| format!("invalid port in url: {}", url))?,
+            None => 80,
+        };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    // The value of the Host header, which puts ipv6 addresses back in brackets
+    fn host_header(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+}
+
+// Uses HTTP/1.0 so the response body is never chunked and ends when the server closes the
+// connection
+fn request(url: &Url, method: &str, body: &str) -> Result<String, String> {
+    let io_error = |e: std::io::Error| e.to_string();
+    let mut stream = TcpStream::connect((url.host.as_str(), url.port)).map_err(io_error)?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(io_error)?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(io_error)?;
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        url.path,
+        url.host_header(),
+        body.len(),
+        body
+    )
+    .map_err(io_error)?;
+    let mut response = String::new();
+    stream
+        .take(MAX_RESPONSE_BYTES + 1)
+        .read_to_string(&mut response)
+        .map_err(io_error)?;
+    if response.len() as u64 > MAX_RESPONSE_BYTES {
+        return Err("response too large".to_string());
+    }
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "malformed response".to_string())?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| "malformed response".to_string())?;
+    if !status.starts_with('2') {
+        return Err(format!("server responded with status {}", status));
+    }
+    Ok(body.to_string())
+}
+
+/// Talks to a leaderboard server over plain http, making each request on its own thread
+pub struct HttpLeaderboard {
+    url: Url,
+    top_entries: Option<Receiver<Result<Vec<RunSummary>, String>>>,
+}
+
+impl HttpLeaderboard {
+    pub fn new(url: &str) -> Result<Self, String> {
+        Ok(Self {
+            url: Url::parse(url)?,
+            top_entries: None,
+        })
+    }
+}
+
+impl LeaderboardClient for HttpLeaderboard {
+    fn submit(&mut self, summary: &RunSummary) {
+        let url = self.url.clone();
+        let body = summary.to_json();
+        thread::spawn(move || {
+            if let Err(e) = request(&url, "POST", &body) {
+                log::error!("couldn't submit run to leaderboard: {}", e);
+            }
+        });
+    }
+
+    fn request_top_entries(&mut self) {
+        let url = self.url.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = request(&url, "GET", "").and_then(|body| entries_from_json(&body));
+            let _ = sender.send(result);
+        });
+        self.top_entries = Some(receiver);
+    }
+
+    fn poll_top_entries(&mut self) -> Option<Result<Vec<RunSummary>, String>> {
+        let result = self.top_entries.as_ref()?.try_recv().ok()?;
+        self.top_entries = None;
+        Some(result)
+    }
+}
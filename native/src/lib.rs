@@ -1,12 +1,81 @@
-use app::{AppStorage, InitialRngSeed};
+use app::{AppStorage, Balance, InitialRngSeed};
 use general_storage_file::{FileStorage, IfDirectoryMissing};
 use general_storage_static::StaticStorage;
 pub use meap;
 
+#[cfg(feature = "discord")]
+mod discord;
+#[cfg(feature = "leaderboard")]
+mod leaderboard;
+#[cfg(feature = "twitch")]
+mod twitch;
+
 const DEFAULT_SAVE_FILE: &str = "save";
 const DEFAULT_NEXT_TO_EXE_STORAGE_DIR: &str = "save";
 const DEFAULT_CONFIG_FILE: &str = "config.json";
 const DEFAULT_CONTROLS_FILE: &str = "controls.json";
+const DEFAULT_REPLAY_FILE: &str = "replay";
+const DEFAULT_BALANCE_FILE: &str = "balance.toml";
+
+// The balance file is optional, so the defaults are used without complaint if it's missing
+fn load_balance(path: &str) -> Balance {
+    match std::fs::read_to_string(path) {
+        Ok(s) => Balance::from_toml_str(&s).unwrap_or_else(|e| {
+            log::error!("couldn't load balance from {}: {}", path, e);
+            Balance::default()
+        }),
+        Err(_) => Balance::default(),
+    }
+}
+
+#[cfg(feature = "script")]
+fn load_script(path: &str) -> Option<Box<dyn app::Script>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| log::error!("couldn't read script {}: {}", path, e))
+        .ok()?;
+    app::RhaiScript::compile(&source)
+        .map(|script| Box::new(script) as Box<_>)
+        .map_err(|e| log::error!("couldn't compile script {}: {}", path, e))
+        .ok()
+}
+
+/// Fonts chosen on the command line to use in place of the ones built into the graphical
+/// frontends
+pub struct FontArgs {
+    pub normal: Option<String>,
+    pub bold: Option<String>,
+}
+impl FontArgs {
+    pub fn parser() -> impl meap::Parser<Item = Self> {
+        meap::let_map! {
+            let {
+                normal = opt_opt::<String, _>("PATH", "font").desc("ttf file to use for regular text");
+                bold = opt_opt::<String, _>("PATH", "bold-font").desc("ttf file to use for bold text");
+            } in {
+                Self { normal, bold }
+            }
+        }
+    }
+
+    /// Reads the regular and bold fonts, falling back to the given defaults for any which
+    /// weren't chosen or couldn't be read
+    pub fn load(&self, default_normal: &[u8], default_bold: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        (
+            load_font(self.normal.as_deref(), default_normal),
+            load_font(self.bold.as_deref(), default_bold),
+        )
+    }
+}
+
+fn load_font(path: Option<&str>, default: &[u8]) -> Vec<u8> {
+    match path {
+        None => default.to_vec(),
+        Some(path) => std::fs::read(path).unwrap_or_else(|e| {
+            log::error!("couldn't read font {}: {}", path, e);
+            default.to_vec()
+        }),
+    }
+}
 
 pub struct NativeCommon {
     pub storage: AppStorage,
@@ -14,6 +83,12 @@ pub struct NativeCommon {
     pub omniscient: bool,
     pub new_game: bool,
     pub mute: bool,
+    pub balance: Balance,
+    #[cfg(feature = "leaderboard")]
+    pub leaderboard: Option<Box<dyn app::leaderboard::LeaderboardClient>>,
+    pub presence: Option<Box<dyn app::presence::PresenceClient>>,
+    pub voting: Option<app::vote::Voting>,
+    pub script: Option<Box<dyn app::Script>>,
 }
 impl NativeCommon {
     pub fn parser() -> impl meap::Parser<Item = Self> {
@@ -26,17 +101,46 @@ impl NativeCommon {
                     .with_default(DEFAULT_CONFIG_FILE.to_string());
                 controls_file = opt_opt("PATH", "controls-file").desc("controls file")
                     .with_default(DEFAULT_CONTROLS_FILE.to_string());
+                replay_file = opt_opt("PATH", "replay-file").desc("replay of the last finished run")
+                    .with_default(DEFAULT_REPLAY_FILE.to_string());
                 storage_dir = opt_opt("PATH", 'd').name("storage-dir")
                     .desc("directory that will contain state")
                     .with_default(DEFAULT_NEXT_TO_EXE_STORAGE_DIR.to_string());
                 delete_save = flag("delete-save").desc("delete save game file");
                 delete_config = flag("delete-config").desc("delete config file");
                 delete_controls = flag("delete-controls").desc("delete controls file");
+                export_save = opt_opt::<String, _>("PATH", "export-save")
+                    .desc("export the save game to a file which can be imported by the web version");
+                import_save = opt_opt::<String, _>("PATH", "import-save")
+                    .desc("replace the save game with one exported from another version");
                 new_game = flag("new-game").desc("start a new game, skipping the menu");
                 omniscient = flag("omniscient").desc("enable omniscience");
                 mute = flag("mute").desc("mute all sound");
+                balance_file = opt_opt("PATH", "balance-file")
+                    .desc("toml file of tunable numbers to use in place of the game's defaults")
+                    .with_default(DEFAULT_BALANCE_FILE.to_string());
+                leaderboard_url = opt_opt::<String, _>("URL", "leaderboard-url")
+                    .desc("submit finished runs to the leaderboard server at this http url");
+                discord_app_id = opt_opt::<String, _>("ID", "discord-app-id")
+                    .desc("show what you're doing in the game on your discord profile, using this discord application id");
+                twitch_channel = opt_opt::<String, _>("NAME", "twitch-channel")
+                    .desc("let the chat of this twitch channel vote on the choices in menus");
+                twitch_user = opt_opt::<String, _>("NAME", "twitch-user")
+                    .desc("post vote announcements to twitch chat as this user, with the oauth token in the TWITCH_OAUTH_TOKEN environment variable");
+                vote_seconds = opt_opt::<u64, _>("INT", "vote-seconds")
+                    .desc("how long twitch chat has to vote each time a menu opens");
+                script_file = opt_opt::<String, _>("PATH", "script-file")
+                    .desc("rhai script of hooks for modding the game");
             } in {{
                 let initial_rng_seed = rng_seed.map(InitialRngSeed::U64).unwrap_or(InitialRngSeed::Random);
+                app::install_crash_report_hook({
+                    let storage_dir = storage_dir.clone();
+                    move || {
+                        FileStorage::next_to_exe(&storage_dir, IfDirectoryMissing::Create)
+                            .ok()
+                            .map(StaticStorage::new)
+                    }
+                });
                 let mut file_storage = StaticStorage::new(
                     FileStorage::next_to_exe(storage_dir, IfDirectoryMissing::Create)
                     .expect("failed to open directory"),
@@ -59,18 +163,100 @@ impl NativeCommon {
                         log::warn!("couldn't find controls file to delete");
                     }
                 }
-                let storage = AppStorage {
+                #[cfg(not(feature = "script"))]
+                if script_file.is_some() {
+                    log::warn!("this build doesn't support scripts");
+                }
+                let mut storage = AppStorage {
                     handle: file_storage,
                     save_game_key: save_file,
                     config_key: config_file,
                     controls_key: controls_file,
+                    replay_key: replay_file,
                 };
+                if let Some(path) = import_save {
+                    let result = std::fs::read(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|bytes| storage.import_save(&bytes));
+                    if let Err(e) = result {
+                        log::error!("couldn't import save from {}: {}", path, e);
+                    }
+                }
+                if let Some(path) = export_save {
+                    match storage.export_save() {
+                        Some(bytes) => {
+                            if let Err(e) = std::fs::write(&path, bytes) {
+                                log::error!("couldn't export save to {}: {}", path, e);
+                            }
+                        }
+                        None => log::warn!("couldn't find save file to export"),
+                    }
+                }
+                #[cfg(not(feature = "leaderboard"))]
+                if leaderboard_url.is_some() {
+                    log::warn!("this build doesn't support leaderboards");
+                }
+                #[cfg(not(feature = "discord"))]
+                if discord_app_id.is_some() {
+                    log::warn!("this build doesn't support discord");
+                }
+                #[cfg(not(feature = "twitch"))]
+                if twitch_channel.is_some() || twitch_user.is_some() || vote_seconds.is_some() {
+                    log::warn!("this build doesn't support twitch");
+                }
                 Self {
                     initial_rng_seed,
                     storage,
                     omniscient,
                     new_game,
                     mute,
+                    balance: load_balance(&balance_file),
+                    #[cfg(feature = "leaderboard")]
+                    leaderboard: leaderboard_url.and_then(|url| {
+                        match leaderboard::HttpLeaderboard::new(&url) {
+                            Ok(leaderboard) => Some(Box::new(leaderboard) as Box<_>),
+                            Err(e) => {
+                                log::error!("couldn't use leaderboard: {}", e);
+                                None
+                            }
+                        }
+                    }),
+                    #[cfg(feature = "discord")]
+                    presence: discord_app_id.map(|app_id| {
+                        Box::new(discord::DiscordPresence::new(&app_id)) as Box<_>
+                    }),
+                    #[cfg(not(feature = "discord"))]
+                    presence: None,
+                    #[cfg(feature = "twitch")]
+                    voting: twitch_channel.and_then(|channel| {
+                        let token = std::env::var("TWITCH_OAUTH_TOKEN").ok();
+                        let login = match (twitch_user.as_deref(), token.as_deref()) {
+                            (Some(user), Some(token)) => Some((user, token)),
+                            (Some(_), None) => {
+                                log::warn!("TWITCH_OAUTH_TOKEN isn't set so votes won't be announced");
+                                None
+                            }
+                            (None, _) => None,
+                        };
+                        match twitch::TwitchChat::connect(&channel, login) {
+                            Ok(chat) => Some(app::vote::Voting {
+                                chat_client: Box::new(chat),
+                                window: vote_seconds
+                                    .map(std::time::Duration::from_secs)
+                                    .unwrap_or(app::vote::DEFAULT_WINDOW),
+                            }),
+                            Err(e) => {
+                                log::error!("couldn't connect to twitch chat: {}", e);
+                                None
+                            }
+                        }
+                    }),
+                    #[cfg(not(feature = "twitch"))]
+                    voting: None,
+                    #[cfg(feature = "script")]
+                    script: script_file.and_then(|path| load_script(&path)),
+                    #[cfg(not(feature = "script"))]
+                    script: None,
                 }
             }}
         }
@@ -0,0 +1,159 @@
+use app::vote::{ChatClient, ChatMessage};
+use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+const HOST: &str = "irc.chat.twitch.tv";
+// Twitch's chat over TLS, so the oauth token isn't sent in the clear
+const PORT: u16 = 6697;
+
+// How long the chat thread waits for a message before checking for anything to post
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+// Twitch lets anyone read a channel's chat without logging in by using a nickname like this
+const ANONYMOUS_NICK: &str = "justinfan31415";
+
+// Twitch's chat is IRC. Messages look like ":nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :text".
+fn parse_privmsg(line: &str) -> Option<ChatMessage> {
+    let line = line.strip_prefix(':')?;
+    let (prefix, rest) = line.split_once(' ')?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (_channel, text) = rest.split_once(" :")?;
+    let user = prefix.split('!').next()?;
+    Some(ChatMessage {
+        user: user.to_string(),
+        text: text.to_string(),
+    })
+}
+
+fn connect_tls() -> io::Result<TlsStream> {
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    let server_name = ServerName::try_from(HOST).map_err(io::Error::other)?;
+    let connection =
+        ClientConnection::new(Arc::new(config), server_name).map_err(io::Error::other)?;
+    Ok(StreamOwned::new(
+        connection,
+        TcpStream::connect((HOST, PORT))?,
+    ))
+}
+
+// A TLS stream can't be split between threads, so the thread which reads the chat also posts
+// the lines sent to it by the game
+fn run_chat(
+    mut stream: TlsStream,
+    outgoing: Receiver<String>,
+    sender: Sender<ChatMessage>,
+) -> io::Result<()> {
+    stream.sock.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut pending = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        for line in outgoing.try_iter() {
+            stream.write_all(line.as_bytes())?;
+            stream.flush()?;
+        }
+        let n = match stream.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        pending.extend_from_slice(&buf[..n]);
+        while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+            let line = String::from_utf8_lossy(&pending[..end])
+                .trim_end_matches('\r')
+                .to_string();
+            pending.drain(..=end);
+            if let Some(server) = line.strip_prefix("PING ") {
+                write!(stream, "PONG {}\r\n", server)?;
+                stream.flush()?;
+            } else if let Some(message) = parse_privmsg(&line) {
+                if sender.send(message).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Reads (and if given a token, posts to) a Twitch channel's chat. The connection is handled on
+/// its own thread.
+pub struct TwitchChat {
+    channel: String,
+    // lines to post, which are only sent when logged in as anonymous users can't post
+    outgoing: Sender<String>,
+    logged_in: bool,
+    receiver: Receiver<ChatMessage>,
+}
+
+impl TwitchChat {
+    /// Join the chat of `channel`. Posting requires a login, given as a user name and oauth token.
+    pub fn connect(channel: &str, login: Option<(&str, &str)>) -> io::Result<Self> {
+        let channel = channel.trim_start_matches('#').to_lowercase();
+        let mut stream = connect_tls()?;
+        match login {
+            Some((user, token)) => {
+                let token = token.trim_start_matches("oauth:");
+                write!(
+                    stream,
+                    "PASS oauth:{}\r\nNICK {}\r\n",
+                    token,
+                    user.to_lowercase()
+                )?;
+            }
+            None => write!(stream, "NICK {}\r\n", ANONYMOUS_NICK)?,
+        }
+        write!(stream, "JOIN #{}\r\n", channel)?;
+        stream.flush()?;
+        let (sender, receiver) = mpsc::channel();
+        let (outgoing, outgoing_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(e) = run_chat(stream, outgoing_receiver, sender) {
+                log::error!("lost connection to twitch chat: {}", e);
+            }
+        });
+        Ok(Self {
+            channel,
+            outgoing,
+            logged_in: login.is_some(),
+            receiver,
+        })
+    }
+}
+
+impl ChatClient for TwitchChat {
+    fn poll_messages(&mut self) -> Vec<ChatMessage> {
+        self.receiver.try_iter().collect()
+    }
+
+    fn announce(&mut self, text: &str) {
+        if !self.logged_in {
+            return;
+        }
+        let line = format!("PRIVMSG #{} :{}\r\n", self.channel, text);
+        if self.outgoing.send(line).is_err() {
+            log::error!("couldn't post to twitch chat as the connection was lost");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use coord_2d::Size;
 use procgen::city::{Map, TentacleSpec};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
@@ -13,6 +14,6 @@ fn main() {
         distance_from_centre: 40.0,
         spread: 0.3,
     };
-    let map = Map::generate(&tentacle_spec, &mut rng);
+    let map = Map::generate(Size::new(50, 25), &tentacle_spec, &mut rng);
     map.print();
 }
@@ -2,7 +2,7 @@ use coord_2d::{Coord, Size};
 use direction::{CardinalDirection, Direction, OrdinalDirection};
 use grid_2d::Grid;
 use rand::{seq::SliceRandom, Rng};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Ground1 {
@@ -44,8 +44,8 @@ impl Rect {
 }
 
 impl Map1 {
-    fn new() -> Self {
-        let grid = Grid::new_clone(Size::new(50, 25), Tile1::Wall);
+    fn new(size: Size) -> Self {
+        let grid = Grid::new_clone(size, Tile1::Wall);
         Self {
             grid,
             street_builders: Vec::new(),
@@ -366,10 +366,10 @@ impl Map1 {
         true
     }
 
-    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+    pub fn generate<R: Rng>(size: Size, rng: &mut R) -> Self {
         let mut map = loop {
             let mut map = loop {
-                let mut map = Self::new();
+                let mut map = Self::new(size);
                 map.add_street_builders(rng);
                 map.build_streets(rng);
                 map.split_rectangles(rng);
@@ -472,8 +472,8 @@ impl Map2 {
         }
     }
 
-    pub fn generate<R: Rng>(rng: &mut R) -> Self {
-        let map1 = Map1::generate(rng);
+    pub fn generate<R: Rng>(size: Size, rng: &mut R) -> Self {
+        let map1 = Map1::generate(size, rng);
         let mut map2 = Self::from_map1(&map1);
         map2.add_footpath();
         map2
@@ -741,8 +741,8 @@ impl Map3 {
         }
     }
 
-    pub fn generate<R: Rng>(rng: &mut R) -> Self {
-        let map2 = Map2::generate(rng);
+    pub fn generate<R: Rng>(size: Size, rng: &mut R) -> Self {
+        let map2 = Map2::generate(size, rng);
         let mut map3 = Self::from_map2(&map2);
         let floor_blocks = map3.add_floor();
         for block in floor_blocks {
@@ -783,21 +783,21 @@ impl Tile4 {
 }
 
 struct Area {
-    boundary: HashSet<Coord>,
+    boundary: BTreeSet<Coord>,
 }
 
 struct Adjacencies {
     areas: Vec<Area>,
-    shared_boundaries: Grid<HashSet<Coord>>,
+    shared_boundaries: Grid<BTreeSet<Coord>>,
     neighbours: Vec<Vec<usize>>,
 }
 
 impl Adjacencies {
     fn shared_boundary(
-        shared_boundaries: &Grid<HashSet<Coord>>,
+        shared_boundaries: &Grid<BTreeSet<Coord>>,
         a: usize,
         b: usize,
-    ) -> &HashSet<Coord> {
+    ) -> &BTreeSet<Coord> {
         let coord = if a < b {
             Coord::new(a as i32, b as i32)
         } else {
@@ -812,7 +812,7 @@ impl Adjacencies {
 }
 
 struct DisconnectedRooms {
-    as_coords: HashSet<Coord>,
+    as_coords: BTreeSet<Coord>,
 }
 
 impl DisconnectedRooms {
@@ -927,7 +927,7 @@ impl Map4 {
         let mut ret = Vec::new();
         for (coord, &tile) in self.grid.enumerate() {
             if !tile.is_solid() && seen.insert(coord) {
-                let mut boundary = HashSet::new();
+                let mut boundary = BTreeSet::new();
                 let mut area_coords = HashSet::new();
                 let mut queue = VecDeque::new();
                 area_coords.insert(coord);
@@ -976,7 +976,7 @@ impl Map4 {
                     let b = &areas[b];
                     a.boundary.intersection(&b.boundary).cloned().collect()
                 } else {
-                    HashSet::new()
+                    BTreeSet::new()
                 }
             });
         let neighbours = (0..areas.len())
@@ -1063,8 +1063,8 @@ impl Map4 {
         }
     }
 
-    pub fn generate<R: Rng>(rng: &mut R) -> Self {
-        let map3 = Map3::generate(rng);
+    pub fn generate<R: Rng>(size: Size, rng: &mut R) -> Self {
+        let map3 = Map3::generate(size, rng);
         let mut map4 = Self::from_map3(&map3);
         map4.add_debris(rng);
         map4.add_doors(rng);
@@ -1084,12 +1084,14 @@ pub enum Tile5 {
     Tentacle,
     StairsDown,
     StairsUp,
+    Water,
+    Bridge,
 }
 
 impl Tile5 {
     fn is_solid(&self) -> bool {
         match self {
-            Self::Wall | Self::Debris | Self::Tentacle => true,
+            Self::Wall | Self::Debris | Self::Tentacle | Self::Water => true,
             _ => false,
         }
     }
@@ -1098,6 +1100,10 @@ impl Tile5 {
     }
 }
 
+/// Pieces cut off from the rest of the city by the river which are larger than this many cells
+/// cause the map to be regenerated rather than flooded
+const MAX_FLOODED_POCKET_SIZE: usize = 16;
+
 pub struct Map5 {
     pub grid: Grid<Tile5>,
     tentacle_coords: Vec<Coord>,
@@ -1264,6 +1270,89 @@ impl Map5 {
         }
     }
 
+    // Carve a river from the top of the map to the bottom, meandering from side to side. Where
+    // it crosses a street, footpath or alley a bridge is left in place of the road.
+    fn add_river<R: Rng>(&mut self, rng: &mut R) {
+        let half_width = 1;
+        let width = self.grid.width() as i32;
+        let mut centre = rng.gen_range((width / 4)..(3 * width / 4));
+        for y in 0..(self.grid.height() as i32) {
+            centre = (centre + rng.gen_range(-1..=1)).clamp(half_width + 1, width - half_width - 2);
+            for x in (centre - half_width)..=(centre + half_width) {
+                let tile = self.grid.get_checked_mut(Coord::new(x, y));
+                *tile = match *tile {
+                    Tile5::Street | Tile5::Footpath | Tile5::Alley => Tile5::Bridge,
+                    _ => Tile5::Water,
+                };
+            }
+        }
+    }
+
+    // Label each open cell with an identifier shared by every open cell reachable from it
+    fn label_open_regions(&self) -> Grid<Option<usize>> {
+        let mut labels = Grid::new_copy(self.grid.size(), None);
+        let mut next_label = 0;
+        for (start, tile) in self.grid.enumerate() {
+            if !tile.is_open() || labels.get_checked(start).is_some() {
+                continue;
+            }
+            *labels.get_checked_mut(start) = Some(next_label);
+            let mut queue = VecDeque::new();
+            queue.push_front(start);
+            while let Some(coord) = queue.pop_back() {
+                for d in CardinalDirection::all() {
+                    let coord = coord + d.coord();
+                    if let Some(tile) = self.grid.get(coord) {
+                        let label = labels.get_checked_mut(coord);
+                        if tile.is_open() && label.is_none() {
+                            *label = Some(next_label);
+                            queue.push_front(coord);
+                        }
+                    }
+                }
+            }
+            next_label += 1;
+        }
+        labels
+    }
+
+    // The river can cut pieces off the largest open region, which takes in the streets where the
+    // player and shops are placed. Small pockets, such as the sliver of a room between its wall
+    // and the river, are flooded. Returns false without changing the map if a larger area would
+    // be cut off, as the river would strand part of the city from the player.
+    fn flood_cut_off_pockets(&mut self, labels_before_river: &Grid<Option<usize>>) -> bool {
+        let mut region_sizes = HashMap::new();
+        for &label in labels_before_river.iter().flatten() {
+            *region_sizes.entry(label).or_insert(0) += 1;
+        }
+        let main_region = match region_sizes.into_iter().max_by_key(|&(_, size)| size) {
+            Some((label, _)) => label,
+            None => return true,
+        };
+        let labels_after_river = self.label_open_regions();
+        let mut pieces: HashMap<usize, Vec<Coord>> = HashMap::new();
+        for (coord, &after) in labels_after_river.enumerate() {
+            if let (Some(before), Some(after)) = (*labels_before_river.get_checked(coord), after) {
+                if before == main_region {
+                    pieces.entry(after).or_default().push(coord);
+                }
+            }
+        }
+        let mut pieces = pieces.into_values().collect::<Vec<_>>();
+        pieces.sort_by_key(|piece| std::cmp::Reverse(piece.len()));
+        let cut_off = pieces.split_off(1.min(pieces.len()));
+        if cut_off
+            .iter()
+            .any(|piece| piece.len() > MAX_FLOODED_POCKET_SIZE)
+        {
+            return false;
+        }
+        for coord in cut_off.into_iter().flatten() {
+            *self.grid.get_checked_mut(coord) = Tile5::Water;
+        }
+        true
+    }
+
     fn add_debris_around_edge(&mut self) {
         for (_coord, tile) in self.grid.edge_enumerate_mut() {
             if tile.is_open() {
@@ -1286,6 +1375,8 @@ impl Map5 {
                     Tile5::Tentacle => print!("~"),
                     Tile5::StairsDown => print!(">"),
                     Tile5::StairsUp => print!("<"),
+                    Tile5::Water => print!("≈"),
+                    Tile5::Bridge => print!("="),
                 }
             }
             println!("");
@@ -1318,14 +1409,36 @@ impl Map5 {
         false
     }
 
-    pub fn generate<R: Rng>(tentacle_spec: &TentacleSpec, rng: &mut R) -> Self {
+    pub fn generate<R: Rng>(size: Size, tentacle_spec: &TentacleSpec, rng: &mut R) -> Self {
+        Self::generate_with_river(size, tentacle_spec, false, rng)
+    }
+
+    /// Like `generate` but a river runs through the map, crossed by bridges where it meets the
+    /// streets
+    pub fn generate_surface<R: Rng>(size: Size, tentacle_spec: &TentacleSpec, rng: &mut R) -> Self {
+        Self::generate_with_river(size, tentacle_spec, true, rng)
+    }
+
+    fn generate_with_river<R: Rng>(
+        size: Size,
+        tentacle_spec: &TentacleSpec,
+        river: bool,
+        rng: &mut R,
+    ) -> Self {
         loop {
-            let map4 = Map4::generate(rng);
+            let map4 = Map4::generate(size, rng);
             let mut map5 = Self::from_map4(&map4);
             let mut corners = OrdinalDirection::all().collect::<Vec<_>>();
             corners.shuffle(rng);
             map5.add_tentacles(corners.pop().unwrap(), &tentacle_spec, rng);
             map5.clear_around_tentactle();
+            if river {
+                let labels_before_river = map5.label_open_regions();
+                map5.add_river(rng);
+                if !map5.flood_cut_off_pockets(&labels_before_river) {
+                    continue;
+                }
+            }
             let stairs_down =
                 if let Some(x) = map5.add_stairs(corners.pop().unwrap(), Tile5::StairsDown, rng) {
                     x
@@ -1,25 +1,52 @@
 #![windows_subsystem = "windows"]
 use app::{app, AppArgs, NAME};
 use chargrid_sdl2::*;
-use native::{meap, NativeCommon};
+use native::{meap, FontArgs, NativeCommon};
+
+struct Args {
+    native_common: NativeCommon,
+    font_args: FontArgs,
+}
+
+impl Args {
+    fn parser() -> impl meap::Parser<Item = Self> {
+        meap::let_map! {
+            let {
+                native_common = NativeCommon::parser();
+                font_args = FontArgs::parser();
+            } in {
+                Self { native_common, font_args }
+            }
+        }
+    }
+}
 
 fn main() {
     use meap::Parser;
     env_logger::init();
-    let NativeCommon {
-        storage,
-        initial_rng_seed,
-        omniscient,
-        new_game,
-        mute,
-    } = NativeCommon::parser()
-        .with_help_default()
-        .parse_env_or_exit();
+    let Args {
+        native_common:
+            NativeCommon {
+                storage,
+                initial_rng_seed,
+                omniscient,
+                new_game,
+                mute,
+                balance,
+                #[cfg(feature = "leaderboard")]
+                leaderboard,
+                presence,
+                voting,
+                script,
+            },
+        font_args,
+    } = Args::parser().with_help_default().parse_env_or_exit();
+    let (normal, bold) = font_args.load(
+        include_bytes!("./fonts/PxPlus_IBM_CGAthin-2y.ttf"),
+        include_bytes!("./fonts/PxPlus_IBM_CGA-2y.ttf"),
+    );
     let context = Context::new(Config {
-        font_bytes: FontBytes {
-            normal: include_bytes!("./fonts/PxPlus_IBM_CGAthin-2y.ttf").to_vec(),
-            bold: include_bytes!("./fonts/PxPlus_IBM_CGA-2y.ttf").to_vec(),
-        },
+        font_bytes: FontBytes { normal, bold },
         title: NAME.to_string(),
         window_dimensions_px: Dimensions {
             width: 960.,
@@ -36,7 +63,7 @@ fn main() {
         },
         underline_width_cell_ratio: 0.1,
         underline_top_offset_cell_ratio: 0.8,
-        resizable: false,
+        resizable: true,
     });
     context.run(app(AppArgs {
         storage,
@@ -44,5 +71,14 @@ fn main() {
         omniscient,
         new_game,
         mute,
+        balance,
+        audio: true,
+        share_seed: None,
+        export_file: None,
+        #[cfg(feature = "leaderboard")]
+        leaderboard,
+        presence,
+        voting,
+        script,
     }));
 }
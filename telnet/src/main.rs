@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::thread;
+
+mod protocol;
+mod session;
+
+use session::ActiveNames;
+
+// Anyone who can connect can play any saved game by entering its name, so only local
+// connections are accepted unless another address is chosen
+const DEFAULT_ADDRESS: &str = "127.0.0.1:2323";
+const DEFAULT_STORAGE_DIR: &str = "sessions";
+const DEFAULT_MAX_SESSIONS: usize = 32;
+
+struct Args {
+    address: String,
+    storage_dir: String,
+    max_sessions: usize,
+}
+
+// Frees up a session slot when a session's thread ends
+struct SessionSlot(Arc<AtomicUsize>);
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Args {
+    fn parser() -> impl meap::Parser<Item = Self> {
+        meap::let_map! {
+            let {
+                address = opt_opt("ADDRESS", 'a').name("address").desc("address to listen on")
+                    .with_default(DEFAULT_ADDRESS.to_string());
+                storage_dir = opt_opt("PATH", 'd').name("storage-dir")
+                    .desc("directory that will contain each player's saves and settings")
+                    .with_default(DEFAULT_STORAGE_DIR.to_string());
+                max_sessions = opt_opt("INT", "max-sessions")
+                    .desc("number of players who can be connected at once")
+                    .with_default(DEFAULT_MAX_SESSIONS);
+            } in {
+                Self { address, storage_dir, max_sessions }
+            }
+        }
+    }
+}
+
+fn main() {
+    use meap::Parser;
+    env_logger::init();
+    let Args {
+        address,
+        storage_dir,
+        max_sessions,
+    } = Args::parser().with_help_default().parse_env_or_exit();
+    let storage_dir = Arc::new(PathBuf::from(storage_dir));
+    let active_names = ActiveNames::default();
+    let num_sessions = Arc::new(AtomicUsize::new(0));
+    let listener = TcpListener::bind(&address).expect("failed to listen");
+    log::info!("listening on {}", address);
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        // only the accepting thread adds sessions, so the count can't go over the limit
+        if num_sessions.load(Ordering::SeqCst) >= max_sessions {
+            log::info!("turned away a connection as the server is full");
+            let _ = stream.write_all(b"The server is full. Try again later.\r\n");
+            continue;
+        }
+        num_sessions.fetch_add(1, Ordering::SeqCst);
+        let slot = SessionSlot(Arc::clone(&num_sessions));
+        let storage_dir = Arc::clone(&storage_dir);
+        let active_names = Arc::clone(&active_names);
+        thread::spawn(move || {
+            let _slot = slot;
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            log::info!("session started with {}", peer);
+            match session::run(stream, &storage_dir, &active_names) {
+                Ok(()) => log::info!("session with {} finished", peer),
+                Err(e) => log::info!("session with {} ended: {}", peer, e),
+            }
+        });
+    }
+}
@@ -0,0 +1,263 @@
+use chargrid_input::{keys, Input, KeyboardInput};
+use chargrid_runtime::{rgb_int::Rgb24, FrameBuffer, FrameBufferCell, Size};
+use std::fmt::Write;
+
+// Telnet commands and options (RFC 854, 857, 858 and 1073)
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+const ESC: u8 = 0x1b;
+
+/// Window sizes reported by clients are clamped to this many cells in each dimension, so a
+/// client can't make the server allocate a huge frame buffer
+const MAX_WINDOW_SIZE: u32 = 500;
+
+// No sequence the decoder understands is anywhere near this long, so a client which sends this
+// many bytes without completing one is sending garbage
+const MAX_PENDING: usize = 1024;
+
+/// Sent when a client connects. The server echoes input itself and asks the client to send
+/// characters as they're typed rather than a line at a time, and to report its window size.
+pub const NEGOTIATION: &[u8] = &[IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DO, NAWS];
+
+/// Sent before the game starts to switch to the alternate screen and hide the cursor
+pub const ENTER_GAME: &str = "\x1b[?1049h\x1b[?25l\x1b[2J";
+
+/// Sent when a session ends to undo `ENTER_GAME`
+pub const LEAVE_GAME: &str = "\x1b[0m\x1b[?25h\x1b[?1049l";
+
+/// Something sent by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientEvent {
+    Input(Input),
+    Resize(Size),
+}
+
+/// Turns the bytes sent by a telnet client into input events, stripping out telnet commands
+#[derive(Default)]
+pub struct Decoder {
+    // Bytes which might be the start of a sequence that hasn't been completely received
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn decode(&mut self, bytes: &[u8], events: &mut Vec<ClientEvent>) {
+        self.pending.extend_from_slice(bytes);
+        let mut i = 0;
+        while i < self.pending.len() {
+            match Self::decode_one(&self.pending[i..]) {
+                Some((consumed, event)) => {
+                    if let Some(event) = event {
+                        events.push(event);
+                    }
+                    i += consumed;
+                }
+                None => break,
+            }
+        }
+        self.pending.drain(..i);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.clear();
+        }
+    }
+
+    // Returns the number of bytes consumed and the event they represent, or `None` if more bytes
+    // are needed
+    fn decode_one(bytes: &[u8]) -> Option<(usize, Option<ClientEvent>)> {
+        let keyboard = |n, keyboard_input| {
+            Some((n, Some(ClientEvent::Input(Input::Keyboard(keyboard_input)))))
+        };
+        match bytes {
+            [IAC, WILL | WONT | DO | DONT, _, ..] => Some((3, None)),
+            [IAC, SB, rest @ ..] => {
+                let end = rest.windows(2).position(|w| w == [IAC, SE])?;
+                let data = unescape_iac(&rest[..end]);
+                let event = match data.as_slice() {
+                    [NAWS, w0, w1, h0, h1] => Some(ClientEvent::Resize(Size::new(
+                        (u16::from_be_bytes([*w0, *w1]) as u32).min(MAX_WINDOW_SIZE),
+                        (u16::from_be_bytes([*h0, *h1]) as u32).min(MAX_WINDOW_SIZE),
+                    ))),
+                    _ => None,
+                };
+                Some((end + 4, event))
+            }
+            [IAC, _, ..] => Some((2, None)),
+            [IAC] => None,
+            // Clients send a carriage return as CR LF or CR NUL
+            [b'\r', b'\n' | 0, ..] => keyboard(2, keys::RETURN),
+            [b'\r'] => None,
+            [b'\r', ..] => keyboard(1, keys::RETURN),
+            [ESC, b'[' | b'O', rest @ ..] => {
+                let end = rest.iter().position(|b| (0x40..=0x7e).contains(b))?;
+                let keyboard_input = match &rest[..=end] {
+                    b"A" => KeyboardInput::Up,
+                    b"B" => KeyboardInput::Down,
+                    b"C" => KeyboardInput::Right,
+                    b"D" => KeyboardInput::Left,
+                    b"H" | b"1~" => KeyboardInput::Home,
+                    b"F" | b"4~" => KeyboardInput::End,
+                    b"3~" => KeyboardInput::Delete,
+                    b"5~" => KeyboardInput::PageUp,
+                    b"6~" => KeyboardInput::PageDown,
+                    _ => return Some((end + 3, None)),
+                };
+                keyboard(end + 3, keyboard_input)
+            }
+            // An escape sequence always arrives in one packet, so an escape followed by anything
+            // else was the escape key
+            [ESC, ..] => keyboard(1, keys::ESCAPE),
+            [8 | 127, ..] => keyboard(1, keys::BACKSPACE),
+            [3, ..] => keyboard(1, keys::ETX),
+            [b'\t', ..] => keyboard(1, keys::TAB),
+            [byte @ 0x20..=0x7e, ..] => keyboard(1, KeyboardInput::Char(*byte as char)),
+            [_, ..] => Some((1, None)),
+            [] => None,
+        }
+    }
+}
+
+fn unescape_iac(bytes: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        if byte == IAC && iter.peek() == Some(&&IAC) {
+            iter.next();
+        }
+        unescaped.push(byte);
+    }
+    unescaped
+}
+
+// The cell most recently sent to the client at each position, so only cells which change need
+// to be sent
+#[derive(Clone, PartialEq)]
+struct OutputCell {
+    character: char,
+    bold: bool,
+    underline: bool,
+    foreground: Rgb24,
+    background: Rgb24,
+}
+
+impl OutputCell {
+    fn from_frame_buffer_cell(cell: &FrameBufferCell) -> Self {
+        Self {
+            character: cell.character,
+            bold: cell.bold,
+            underline: cell.underline,
+            foreground: cell.foreground.to_rgb24(),
+            background: cell.background.to_rgb24(),
+        }
+    }
+}
+
+/// Turns frames into the ansi escape sequences which draw them on the client's terminal
+pub struct Encoder {
+    output_cells: Vec<Option<OutputCell>>,
+}
+
+impl Encoder {
+    pub fn new(size: Size) -> Self {
+        Self {
+            output_cells: vec![None; size.count()],
+        }
+    }
+
+    /// Forget what's on the client's screen, so the next frame is drawn in full
+    pub fn reset(&mut self, size: Size, output: &mut String) {
+        self.output_cells = vec![None; size.count()];
+        output.push_str("\x1b[0m\x1b[2J");
+    }
+
+    pub fn encode(&mut self, frame_buffer: &FrameBuffer, output: &mut String) {
+        for ((coord, cell), output_cell) in
+            frame_buffer.enumerate().zip(self.output_cells.iter_mut())
+        {
+            let cell = OutputCell::from_frame_buffer_cell(cell);
+            if output_cell.as_ref() == Some(&cell) {
+                continue;
+            }
+            let _ = write!(output, "\x1b[{};{}H\x1b[0", coord.y + 1, coord.x + 1);
+            if cell.bold {
+                output.push_str(";1");
+            }
+            if cell.underline {
+                output.push_str(";4");
+            }
+            let Rgb24 { r, g, b } = cell.foreground;
+            let _ = write!(output, ";38;2;{};{};{}", r, g, b);
+            let Rgb24 { r, g, b } = cell.background;
+            let _ = write!(output, ";48;2;{};{};{}m", r, g, b);
+            output.push(cell.character);
+            *output_cell = Some(cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> Vec<ClientEvent> {
+        let mut events = Vec::new();
+        Decoder::default().decode(bytes, &mut events);
+        events
+    }
+
+    fn key(keyboard_input: KeyboardInput) -> ClientEvent {
+        ClientEvent::Input(Input::Keyboard(keyboard_input))
+    }
+
+    #[test]
+    fn keys_and_commands() {
+        assert_eq!(
+            decode(&[b'a', IAC, WILL, NAWS, b'\r', b'\n', ESC, b'[', b'A']),
+            vec![
+                key(KeyboardInput::Char('a')),
+                key(keys::RETURN),
+                key(KeyboardInput::Up)
+            ]
+        );
+    }
+
+    #[test]
+    fn sequence_split_across_reads() {
+        let mut decoder = Decoder::default();
+        let mut events = Vec::new();
+        decoder.decode(&[IAC, SB, NAWS, 0, 80], &mut events);
+        assert!(events.is_empty());
+        decoder.decode(&[0, 24, IAC, SE], &mut events);
+        assert_eq!(events, vec![ClientEvent::Resize(Size::new(80, 24))]);
+    }
+
+    #[test]
+    fn window_size_is_clamped() {
+        assert_eq!(
+            decode(&[IAC, SB, NAWS, IAC, IAC, 0, 0x01, 0xf5, IAC, SE]),
+            vec![ClientEvent::Resize(Size::new(
+                MAX_WINDOW_SIZE,
+                MAX_WINDOW_SIZE
+            ))]
+        );
+    }
+
+    #[test]
+    fn unterminated_sequence_is_dropped() {
+        let mut decoder = Decoder::default();
+        let mut events = Vec::new();
+        decoder.decode(&[IAC, SB, NAWS], &mut events);
+        decoder.decode(&[0; MAX_PENDING], &mut events);
+        assert!(events.is_empty());
+        assert!(decoder.pending.is_empty());
+        decoder.decode(b"x", &mut events);
+        assert_eq!(events, vec![key(KeyboardInput::Char('x'))]);
+    }
+}
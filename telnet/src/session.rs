@@ -0,0 +1,230 @@
+use crate::protocol::{self, ClientEvent, Decoder, Encoder};
+use app::{app, AppArgs, AppStorage, InitialRngSeed};
+use chargrid_input::{keys, Input, KeyboardInput};
+use chargrid_runtime::{app as chargrid_app, on_frame, on_input, FrameBuffer, Size};
+use general_storage_file::{FileStorage, IfDirectoryMissing};
+use general_storage_static::StaticStorage;
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{
+    mpsc::{self, Receiver, TryRecvError},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / 60);
+
+// Used until the client reports its window size, or if it never does
+const DEFAULT_SIZE: Size = Size::new_u16(80, 30);
+
+const MAX_NAME_LENGTH: usize = 16;
+
+/// The names of players who are currently connected, so two sessions can't share a save file
+pub type ActiveNames = Arc<Mutex<HashSet<String>>>;
+
+// Removes a player's name from the active names when their session ends
+struct ActiveName<'a> {
+    name: String,
+    active_names: &'a ActiveNames,
+}
+
+impl<'a> Drop for ActiveName<'a> {
+    fn drop(&mut self) {
+        self.active_names.lock().unwrap().remove(&self.name);
+    }
+}
+
+struct Connection {
+    stream: TcpStream,
+    // Bytes from the client are read on a separate thread so the game can keep running while
+    // the client is idle
+    receiver: Receiver<Vec<u8>>,
+    decoder: Decoder,
+    size: Size,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        let mut reader = stream.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0; 1024];
+            while let Ok(n @ 1..) = reader.read(&mut buf) {
+                if sender.send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            stream,
+            receiver,
+            decoder: Decoder::default(),
+            size: DEFAULT_SIZE,
+        })
+    }
+
+    fn send(&mut self, s: &str) -> io::Result<()> {
+        self.stream.write_all(s.as_bytes())
+    }
+
+    fn decode(&mut self, bytes: &[u8], events: &mut Vec<ClientEvent>) {
+        let start = events.len();
+        self.decoder.decode(bytes, events);
+        for event in &events[start..] {
+            if let &ClientEvent::Resize(size) = event {
+                if size.width() > 0 && size.height() > 0 {
+                    self.size = size;
+                }
+            }
+        }
+    }
+
+    // Everything the client has sent since the last call, without waiting
+    fn poll(&mut self) -> io::Result<Vec<ClientEvent>> {
+        let mut events = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(bytes) => self.decode(&bytes, &mut events),
+                Err(TryRecvError::Empty) => return Ok(events),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(io::ErrorKind::ConnectionAborted.into())
+                }
+            }
+        }
+    }
+
+    // Waits until the client sends something
+    fn wait(&mut self) -> io::Result<Vec<ClientEvent>> {
+        let bytes = self
+            .receiver
+            .recv()
+            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?;
+        let mut events = Vec::new();
+        self.decode(&bytes, &mut events);
+        Ok(events)
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'
+}
+
+// Asks the player for the name their saves and settings are kept under
+fn prompt_name<'a>(
+    connection: &mut Connection,
+    active_names: &'a ActiveNames,
+) -> io::Result<ActiveName<'a>> {
+    connection.send("Welcome to Electric Organ!\r\n\r\n")?;
+    connection.send(
+        "Names aren't protected by a password, so anyone who enters your name can play your\r\n\
+         saved game.\r\n\r\n",
+    )?;
+    loop {
+        connection.send("Name: ")?;
+        let mut name = String::new();
+        'line: loop {
+            for event in connection.wait()? {
+                match event {
+                    ClientEvent::Input(Input::Keyboard(keys::RETURN)) => break 'line,
+                    ClientEvent::Input(Input::Keyboard(keys::ETX | keys::ESCAPE)) => {
+                        return Err(io::ErrorKind::Interrupted.into())
+                    }
+                    ClientEvent::Input(Input::Keyboard(keys::BACKSPACE)) if !name.is_empty() => {
+                        name.pop();
+                        connection.send("\x08 \x08")?;
+                    }
+                    ClientEvent::Input(Input::Keyboard(KeyboardInput::Char(ch)))
+                        if is_name_char(ch) && name.len() < MAX_NAME_LENGTH =>
+                    {
+                        name.push(ch);
+                        connection.send(ch.encode_utf8(&mut [0; 4]))?;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        connection.send("\r\n")?;
+        if name.is_empty() {
+            connection.send("Names are made of letters, numbers, '-' and '_'.\r\n")?;
+        } else if active_names.lock().unwrap().insert(name.clone()) {
+            return Ok(ActiveName { name, active_names });
+        } else {
+            connection.send("Someone with that name is already playing.\r\n")?;
+        }
+    }
+}
+
+fn storage(storage_dir: &Path, name: &str) -> io::Result<AppStorage> {
+    let file_storage = FileStorage::new(storage_dir.join(name), IfDirectoryMissing::Create)
+        .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    Ok(AppStorage {
+        handle: StaticStorage::new(file_storage),
+        save_game_key: "save".to_string(),
+        config_key: "config.json".to_string(),
+        controls_key: "controls.json".to_string(),
+        replay_key: "replay".to_string(),
+    })
+}
+
+fn play(connection: &mut Connection, storage: AppStorage) -> io::Result<()> {
+    let mut component = app(AppArgs {
+        storage,
+        initial_rng_seed: InitialRngSeed::Random,
+        omniscient: false,
+        new_game: false,
+        mute: true,
+        audio: false,
+        share_seed: None,
+        export_file: None,
+        balance: Default::default(),
+        #[cfg(feature = "leaderboard")]
+        leaderboard: None,
+        presence: None,
+        voting: None,
+        script: None,
+    });
+    let mut frame_buffer = FrameBuffer::new(connection.size);
+    let mut encoder = Encoder::new(connection.size);
+    let mut output = protocol::ENTER_GAME.to_string();
+    loop {
+        let frame_start = Instant::now();
+        for event in connection.poll()? {
+            if let ClientEvent::Input(input) = event {
+                if let Some(chargrid_app::Exit) = on_input(&mut component, input, &frame_buffer) {
+                    return Ok(());
+                }
+            }
+        }
+        if connection.size != frame_buffer.size() {
+            frame_buffer.resize(connection.size);
+            encoder.reset(connection.size, &mut output);
+        }
+        if let Some(chargrid_app::Exit) =
+            on_frame(&mut component, FRAME_DURATION, &mut frame_buffer)
+        {
+            return Ok(());
+        }
+        encoder.encode(&frame_buffer, &mut output);
+        connection.send(&output)?;
+        output.clear();
+        if let Some(until_next_frame) = FRAME_DURATION.checked_sub(frame_start.elapsed()) {
+            thread::sleep(until_next_frame);
+        }
+    }
+}
+
+/// Plays the game with a single client until they quit or disconnect. Each player's saves and
+/// settings are kept in a directory named after them inside `storage_dir`. There are no
+/// passwords, so a name is all it takes to continue someone's saved game.
+pub fn run(stream: TcpStream, storage_dir: &Path, active_names: &ActiveNames) -> io::Result<()> {
+    let mut connection = Connection::new(stream)?;
+    connection.stream.write_all(protocol::NEGOTIATION)?;
+    let active_name = prompt_name(&mut connection, active_names)?;
+    let storage = storage(storage_dir, &active_name.name)?;
+    let result = play(&mut connection, storage);
+    let _ = connection.send(protocol::LEAVE_GAME);
+    result
+}
@@ -0,0 +1,79 @@
+use app::leaderboard::{entries_from_json, LeaderboardClient, RunSummary};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{Headers, RequestInit, Response};
+
+fn js_error(value: JsValue) -> String {
+    value.as_string().unwrap_or_else(|| format!("{:?}", value))
+}
+
+async fn fetch_text(url: &str, init: &RequestInit) -> Result<String, String> {
+    let window = web_sys::window().unwrap();
+    let response: Response = JsFuture::from(window.fetch_with_str_and_init(url, init))
+        .await
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(js_error)?;
+    if !response.ok() {
+        return Err(format!(
+            "server responded with status {}",
+            response.status()
+        ));
+    }
+    JsFuture::from(response.text().map_err(js_error)?)
+        .await
+        .map_err(js_error)?
+        .as_string()
+        .ok_or_else(|| "response wasn't text".to_string())
+}
+
+/// Talks to a leaderboard server with the browser's fetch api
+pub struct FetchLeaderboard {
+    url: String,
+    top_entries: Rc<RefCell<Option<Result<Vec<RunSummary>, String>>>>,
+}
+
+impl FetchLeaderboard {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            top_entries: Rc::new(RefCell::new(None)),
+        }
+    }
+}
+
+impl LeaderboardClient for FetchLeaderboard {
+    fn submit(&mut self, summary: &RunSummary) {
+        let url = self.url.clone();
+        let body = summary.to_json();
+        spawn_local(async move {
+            let headers = Headers::new().unwrap();
+            let _ = headers.set("Content-Type", "application/json");
+            let mut init = RequestInit::new();
+            init.method("POST")
+                .headers(&headers)
+                .body(Some(&JsValue::from_str(&body)));
+            if let Err(e) = fetch_text(&url, &init).await {
+                log::error!("couldn't submit run to leaderboard: {}", e);
+            }
+        });
+    }
+
+    fn request_top_entries(&mut self) {
+        let url = self.url.clone();
+        let top_entries = Rc::clone(&self.top_entries);
+        spawn_local(async move {
+            let mut init = RequestInit::new();
+            init.method("GET");
+            let result = fetch_text(&url, &init)
+                .await
+                .and_then(|body| entries_from_json(&body));
+            *top_entries.borrow_mut() = Some(result);
+        });
+    }
+
+    fn poll_top_entries(&mut self) -> Option<Result<Vec<RunSummary>, String>> {
+        self.top_entries.borrow_mut().take()
+    }
+}
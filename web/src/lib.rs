@@ -2,31 +2,189 @@ use app::{app, AppArgs, AppStorage, InitialRngSeed};
 use chargrid_web::{Context, LoopMethod, Size};
 use general_storage_static::StaticStorage;
 use general_storage_web::LocalStorage;
-use wasm_bindgen::prelude::*;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{Blob, FileReader, HtmlAnchorElement, HtmlInputElement, Url, UrlSearchParams};
+
+#[cfg(feature = "leaderboard")]
+mod leaderboard;
 
 const SAVE_KEY: &str = "save";
 const CONFIG_KEY: &str = "config";
 const CONTROLS_KEY: &str = "controls";
+const REPLAY_KEY: &str = "replay";
+const EXPORT_FILE_NAME: &str = "electric-organ.sav";
+const SEED_PARAM: &str = "seed";
+#[cfg(feature = "leaderboard")]
+const LEADERBOARD_URL_META: &str = "meta[name=leaderboard-url]";
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+    fn write_text_to_clipboard(text: &str) -> js_sys::Promise;
+}
+
+fn app_storage(handle: StaticStorage) -> AppStorage {
+    AppStorage {
+        handle,
+        save_game_key: SAVE_KEY.to_string(),
+        config_key: CONFIG_KEY.to_string(),
+        controls_key: CONTROLS_KEY.to_string(),
+        replay_key: REPLAY_KEY.to_string(),
+    }
+}
+
+fn alert(message: &str) {
+    let _ = web_sys::window().unwrap().alert_with_message(message);
+}
+
+// The seed given in the page's url (e.g. "?seed=1234"), if any
+fn seed_from_url() -> Option<u64> {
+    let search = web_sys::window().unwrap().location().search().ok()?;
+    UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(SEED_PARAM)?
+        .parse()
+        .ok()
+}
+
+// Copy a link to this page which will start a game with the given seed
+fn share_seed(seed: u64) {
+    let location = web_sys::window().unwrap().location();
+    let (origin, pathname) = match (location.origin(), location.pathname()) {
+        (Ok(origin), Ok(pathname)) => (origin, pathname),
+        _ => return,
+    };
+    let _ = write_text_to_clipboard(&format!("{}{}?{}={}", origin, pathname, SEED_PARAM, seed));
+}
+
+// The leaderboard server is configured by the page hosting the game with a meta tag, e.g.
+// `<meta name="leaderboard-url" content="https://example.com/scores">`
+#[cfg(feature = "leaderboard")]
+fn leaderboard_from_page() -> Option<Box<dyn app::leaderboard::LeaderboardClient>> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let url = document
+        .query_selector(LEADERBOARD_URL_META)
+        .ok()??
+        .get_attribute("content")
+        .filter(|url| !url.is_empty())?;
+    Some(Box::new(leaderboard::FetchLeaderboard::new(url)))
+}
+
+// Download the saved game as a file
+fn export_save() -> Result<(), JsValue> {
+    let bytes = match app_storage(StaticStorage::new(LocalStorage::new())).export_save() {
+        Some(bytes) => bytes,
+        None => {
+            alert("There is no saved game to export. Save from the pause menu first.");
+            return Ok(());
+        }
+    };
+    download(EXPORT_FILE_NAME, &bytes)
+}
+
+// Offer some bytes to the user as a file download
+fn download(name: &str, bytes: &[u8]) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = Blob::new_with_u8_array_sequence(&parts)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window().unwrap().document().unwrap();
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    Url::revoke_object_url(&url)
+}
+
+fn export_file(name: &str, bytes: &[u8]) {
+    if let Err(e) = download(name, bytes) {
+        log::error!("Failed to export {}: {:?}", name, e);
+    }
+}
+
+// Replace the saved game with the file chosen in `input`, then reload the page so it's loaded
+fn import_save(input: &HtmlInputElement) -> Result<(), JsValue> {
+    let file = match input.files().and_then(|files| files.get(0)) {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+    let reader = FileReader::new()?;
+    let onload = Closure::once({
+        let reader = reader.clone();
+        move || {
+            let bytes = match reader.result() {
+                Ok(buffer) => js_sys::Uint8Array::new(&buffer).to_vec(),
+                Err(_) => return alert("Failed to read the save file."),
+            };
+            match app_storage(StaticStorage::new(LocalStorage::new())).import_save(&bytes) {
+                Ok(()) => {
+                    let _ = web_sys::window().unwrap().location().reload();
+                }
+                Err(message) => alert(&message),
+            }
+        }
+    });
+    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+    onload.forget();
+    reader.read_as_array_buffer(&file)
+}
+
+// Attach the export and import flows to their controls on the page, if it has them
+fn setup_save_transfer() -> Result<(), JsValue> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    if let Some(button) = document.get_element_by_id("export-save") {
+        let onclick = Closure::<dyn FnMut()>::new(|| {
+            if let Err(e) = export_save() {
+                log::error!("Failed to export save: {:?}", e);
+            }
+        });
+        button.add_event_listener_with_callback("click", onclick.as_ref().unchecked_ref())?;
+        onclick.forget();
+    }
+    if let Some(input) = document.get_element_by_id("import-save") {
+        let input = input.dyn_into::<HtmlInputElement>()?;
+        let onchange = Closure::<dyn FnMut()>::new({
+            let input = input.clone();
+            move || {
+                if let Err(e) = import_save(&input) {
+                    log::error!("Failed to import save: {:?}", e);
+                }
+            }
+        });
+        input.add_event_listener_with_callback("change", onchange.as_ref().unchecked_ref())?;
+        onchange.forget();
+    }
+    Ok(())
+}
 
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
     wasm_logger::init(wasm_logger::Config::new(log::Level::Info));
     console_error_panic_hook::set_once();
+    app::install_crash_report_hook(|| Some(StaticStorage::new(LocalStorage::new())));
     let mut storage = StaticStorage::new(LocalStorage::new());
     let _ = storage.remove(CONFIG_KEY);
     let _ = storage.remove(CONTROLS_KEY);
+    setup_save_transfer()?;
     let context = Context::new(Size::new(80, 30), "content");
     let args = AppArgs {
-        storage: AppStorage {
-            handle: storage,
-            save_game_key: SAVE_KEY.to_string(),
-            config_key: CONFIG_KEY.to_string(),
-            controls_key: CONTROLS_KEY.to_string(),
-        },
-        initial_rng_seed: InitialRngSeed::Random,
+        storage: app_storage(storage),
+        initial_rng_seed: seed_from_url()
+            .map(InitialRngSeed::U64)
+            .unwrap_or(InitialRngSeed::Random),
         omniscient: false,
         new_game: false,
         mute: false,
+        audio: true,
+        share_seed: Some(Box::new(share_seed)),
+        export_file: Some(Box::new(export_file)),
+        balance: Default::default(),
+        #[cfg(feature = "leaderboard")]
+        leaderboard: leaderboard_from_page(),
+        presence: None,
+        voting: None,
+        script: None,
     };
     context.run_with_loop_method(app(args), LoopMethod::SetTimeoutMs(1000 / 60));
     Ok(())
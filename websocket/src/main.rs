@@ -0,0 +1,81 @@
+use game::{remote::RemoteGame, Config};
+use rand::Rng;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use tungstenite::Message;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:2324";
+
+struct Args {
+    address: String,
+}
+
+impl Args {
+    fn parser() -> impl meap::Parser<Item = Self> {
+        meap::let_map! {
+            let {
+                address = opt_opt("ADDRESS", 'a').name("address").desc("address to listen on")
+                    .with_default(DEFAULT_ADDRESS.to_string());
+            } in {
+                Self { address }
+            }
+        }
+    }
+}
+
+// tungstenite's error is large, so it's boxed to keep the result small
+type Result<T> = std::result::Result<T, Box<tungstenite::Error>>;
+
+// Plays a new game with a single client until they disconnect
+fn run(stream: TcpStream) -> Result<()> {
+    let mut websocket = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => unreachable!("the stream is blocking"),
+    })?;
+    let rng_seed = rand::thread_rng().gen();
+    log::info!("new game with seed {}", rng_seed);
+    let mut game = RemoteGame::new(Config::default(), rng_seed);
+    websocket.send(Message::Text(game.server_json()))?;
+    loop {
+        match websocket.read()? {
+            Message::Text(text) => {
+                if let Err(e) = game.handle_client_json(&text) {
+                    log::warn!("ignoring invalid message ({}): {}", e, text);
+                }
+                websocket.send(Message::Text(game.server_json()))?;
+            }
+            Message::Close(_) => return Ok(()),
+            _ => (),
+        }
+    }
+}
+
+fn main() {
+    use meap::Parser;
+    env_logger::init();
+    let Args { address } = Args::parser().with_help_default().parse_env_or_exit();
+    let listener = TcpListener::bind(&address).expect("failed to listen");
+    log::info!("listening on {}", address);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            log::info!("session started with {}", peer);
+            match run(stream).map_err(|e| *e) {
+                Ok(()) | Err(tungstenite::Error::ConnectionClosed) => {
+                    log::info!("session with {} finished", peer)
+                }
+                Err(e) => log::info!("session with {} ended: {}", peer, e),
+            }
+        });
+    }
+}
@@ -1,10 +1,11 @@
 #![windows_subsystem = "windows"]
 use app::{app, AppArgs, NAME};
 use chargrid_wgpu::*;
-use native::{meap, NativeCommon};
+use native::{meap, FontArgs, NativeCommon};
 
 struct Args {
     native_common: NativeCommon,
+    font_args: FontArgs,
     force_opengl: bool,
 }
 
@@ -13,9 +14,10 @@ impl Args {
         meap::let_map! {
             let {
                 native_common = NativeCommon::parser();
+                font_args = FontArgs::parser();
                 force_opengl = flag("force-opengl").desc("force opengl");
             } in {
-                Self { native_common, force_opengl }
+                Self { native_common, font_args, force_opengl }
             }
         }
     }
@@ -32,14 +34,22 @@ fn main() {
                 omniscient,
                 new_game,
                 mute,
+                balance,
+                #[cfg(feature = "leaderboard")]
+                leaderboard,
+                presence,
+                voting,
+                script,
             },
+        font_args,
         force_opengl,
     } = Args::parser().with_help_default().parse_env_or_exit();
+    let (normal, bold) = font_args.load(
+        include_bytes!("./fonts/PxPlus_IBM_CGAthin-2y.ttf"),
+        include_bytes!("./fonts/PxPlus_IBM_CGA-2y.ttf"),
+    );
     let context = Context::new(Config {
-        font_bytes: FontBytes {
-            normal: include_bytes!("./fonts/PxPlus_IBM_CGAthin-2y.ttf").to_vec(),
-            bold: include_bytes!("./fonts/PxPlus_IBM_CGA-2y.ttf").to_vec(),
-        },
+        font_bytes: FontBytes { normal, bold },
         title: NAME.to_string(),
         window_dimensions_px: Dimensions {
             width: 960.,
@@ -55,7 +65,7 @@ fn main() {
         },
         underline_width_cell_ratio: 0.1,
         underline_top_offset_cell_ratio: 0.8,
-        resizable: false,
+        resizable: true,
         force_secondary_adapter: force_opengl,
     });
     context.run(app(AppArgs {
@@ -64,5 +74,14 @@ fn main() {
         omniscient,
         new_game,
         mute,
+        balance,
+        audio: true,
+        share_seed: None,
+        export_file: None,
+        #[cfg(feature = "leaderboard")]
+        leaderboard,
+        presence,
+        voting,
+        script,
     }));
 }